@@ -0,0 +1,513 @@
+# English messages. Each line is `key = text`; `{ $name }` is replaced
+# with an argument. Keep ja.ftl in step with this file.
+
+language = Language
+
+# Dialogs
+delete-profile-title = Delete Profile
+delete-profile-confirm = Are you sure you want to delete "{ $name }"?
+discard-changes-title = Discard Changes
+discard-changes-confirm = "{ $name }" has unsaved changes. Switch to Automatic and discard them?
+discard-changes = Discard
+cancel = Cancel
+delete = Delete
+close = Close
+preview = Preview
+preview-intro = Apply will run the following steps:
+
+# Mode and interface
+mode-automatic-dhcp = Automatic (DHCP)
+mode-manual = Manual
+automatic = Automatic
+network-interface = Network Interface
+interface-details = Details
+hidden-interface = { $name } (hidden)
+disconnected-interface = { $name } (disconnected)
+show-all-interfaces = Show all adapters
+show-all-interfaces-title = Also list virtual, VPN, and loopback adapters and the ones you have hidden
+show-disconnected-interfaces = Show disconnected adapters
+show-disconnected-interfaces-title = Also list adapters without a link, such as an unplugged Ethernet port, so that DNS can be set on them ahead of time
+refresh-interfaces = Reload the adapter list
+hide-interface = Hide
+interface-alias = Display name
+interface-alias-title = A name to show for this adapter in place of its Windows name. Leave blank to use the Windows name.
+unhide-interface = Unhide
+vpn-active = A VPN is connected through { $name }. DNS servers set on this adapter may be ignored until it disconnects.
+use-vpn-adapter = Use VPN adapter
+adapter-description = Description
+ip-addresses = IP addresses
+default-gateway = Default gateway
+dhcp-server = DHCP server
+mac-address = MAC address
+link-speed = Link speed
+not-available = None
+elevation-warning = windns is not running as administrator, so it is read-only: current DNS status and profiles can be viewed and edited, but DNS settings cannot be applied.
+restart-as-admin = Restart as Administrator
+read-only-reason = Changing DNS settings needs administrator rights. Restart windns as administrator to apply.
+dns-policy-managed = DNS settings on this PC are managed by Group Policy ({ $policies }). Changes windns makes to them may be overridden.
+dns-policy-overrides = Group Policy overrides these settings, so they will not take effect: { $policies }.
+policy-dns-servers = "DNS Servers" set to { $servers }
+policy-doh-prohibited = "Configure DNS over HTTPS (DoH) name resolution" set to Prohibit
+policy-doh-required = "Configure DNS over HTTPS (DoH) name resolution" set to Require
+policy-suffix-search-list = "DNS Suffix Search List"
+policy-nrpt = Name Resolution Policy rules
+
+# DNS settings
+dns-settings = DNS Settings
+example = e.g., { $example }
+use-family-servers = Use { $family } DNS servers
+leave-family-unchanged = Unchanged
+leave-family-unchanged-title = Keep the { $family } servers the adapter has, e.g. ones pushed by a VPN, instead of resetting them to DHCP
+dns-server-primary = Primary DNS
+dns-server-secondary = Secondary DNS
+dns-server-numbered = DNS Server { $number }
+add-dns-server = + Add DNS Server
+remove = Remove
+remove-server-title = Remove this server
+dns-over-https = DNS over HTTPS
+doh-off = Off
+doh-on-manual = On (manual template)
+doh-template-url = DoH Template URL
+discover = Discover
+discover-doh-title = Ask the server for its DoH template (DDR)
+resolve = Resolve
+resolve-host-title = Look up the addresses of this host name with the current DNS servers
+resolved-addresses = { $host } resolves to { $addresses }
+use-resolved-addresses = Use these addresses
+allow-plaintext-fallback = Allow fallback to plaintext
+auto-upgrade = Upgrade to DoH on every adapter
+auto-upgrade-title = When off, the template is registered but only used on adapters where DoH is turned on
+suffixes-need-powershell = DNS suffixes require PowerShell, which is unavailable
+nrpt-needs-powershell = NRPT rules require PowerShell, which is unavailable
+doh-needs-windows-11 = DNS over HTTPS requires Windows 11 or Windows Server 2022 or later
+doh-needs-powershell = DNS over HTTPS requires PowerShell, which is unavailable
+
+# DNS suffixes
+dns-suffixes = DNS Suffixes
+set-connection-suffix = Set connection-specific suffix
+connection-suffix-placeholder = e.g., corp.example.com (empty to clear)
+replace-search-list = Replace suffix search list
+search-suffix-numbered = Search Suffix { $number }
+remove-suffix-title = Remove this suffix
+add-search-suffix = + Add Search Suffix
+check-host = Host to resolve after apply
+check-host-placeholder = e.g., intranet.corp.example (optional)
+check-host-title = If this host does not resolve once the profile is applied, the apply ends with a warning
+before-apply = Command before apply
+before-apply-title = PowerShell command run before the profile is applied, e.g. to start a local proxy. If it fails, the profile is not applied
+after-apply = Command after apply
+after-apply-title = PowerShell command run once the profile is applied, e.g. ipconfig /registerdns
+hook-command-placeholder = e.g., Restart-Service MyProxy (optional)
+allow-hooks = Allow these commands to run
+allow-hooks-title = The commands run as administrator each time you click Apply. Only allow commands you wrote or have checked; changing them needs allowing again
+local-resolver = Use a local resolver (Pi-hole, AdGuard Home, dnscrypt-proxy)
+local-resolver-title = Send DNS queries to a resolver running on this PC at 127.0.0.1 and ::1
+local-resolver-check = Check that the local resolver answers before apply
+local-resolver-check-title = Send a test query to 127.0.0.1 and ::1 and stop if nothing answers
+
+# NRPT
+nrpt-rules = NRPT Rules
+use-nrpt-rules = Apply the NRPT rules of this profile
+namespace = Namespace
+name-servers = Name Servers
+remove-rule-title = Remove this rule
+add-nrpt-rule = + Add NRPT Rule
+active-nrpt-rules = Active NRPT Rules
+refresh = Refresh
+no-nrpt-rules = No NRPT rules are configured.
+source = Source
+source-other = Other
+delete-rule-title = Delete this rule
+
+# Actions and settings
+flush-after-apply = Flush DNS cache after apply
+probe-doh = Check DoH servers before apply
+probe-doh-title = Send a test query to each DoH server and stop if one does not answer
+check-resolution = Test name resolution after apply
+check-resolution-title = Resolve a test host through the new DNS server and show the result in the status bar
+launch-at-startup = Launch at Windows startup
+launch-at-startup-title = Start windns when you sign in, so network rules, the schedule and the tray menu are ready right away
+start-minimized = Start minimized
+open-links = Open windns:// links
+open-links-title = Let links and shortcuts such as windns://apply/Cloudflare apply a profile
+logon-task = Apply at logon
+logon-task-title = Start windns as administrator at sign-in through a scheduled task and apply this profile, without a UAC prompt
+logon-task-off = Off
+logon-task-checking = Checking the scheduled task…
+logon-task-none = No scheduled task is registered
+logon-task-registered = A scheduled task applies { $profile } at logon
+logon-task-missing-profile = The scheduled task applies a profile that no longer exists
+theme = Theme
+theme-system = System
+theme-light = Light
+theme-dark = Dark
+save = Save
+cleanup-doh = Clean Up DoH
+cleanup-doh-title = Remove DoH servers registered by this app that no profile uses
+restore-original = Restore Original
+restore-original-title = Revert this interface to the DNS settings it had before windns changed it
+reset-all = Reset All to Automatic
+reset-all-title = Set every adapter back to the DNS servers DHCP provides and turn DoH off, for when name resolution is broken
+flush-dns-cache = Flush DNS Cache
+export-state = Export State
+export-state-title = Save the DNS servers, DoH templates and suffixes of every adapter to a JSON or Markdown report
+applying = Applying...
+apply = Apply
+apply-for = Apply for…
+apply-for-title = Apply the settings, then put the previous ones back after the chosen time
+apply-for-minutes = { $minutes } minutes
+
+# Status bar
+origin-static = static
+close-message = Close message
+active-profile = Active Profile:
+current-ipv4-dns = Current IPv4 DNS:
+current-ipv6-dns = Current IPv6 DNS:
+resolution-check = Resolution Check:
+temporary-apply = Temporary:
+temporary-apply-reverts-in = Previous settings return in { $time }
+keep-temporary = Keep
+keep-temporary-title = Keep these settings and do not put the previous ones back
+revert-temporary = Revert Now
+revert-temporary-title = Put the previous settings back now
+latency-ms = { $ms } ms
+latency-no-answer = no answer
+
+# Profiles
+all-tags = All
+search-profiles = Search profiles by name or tag
+no-profiles = (No profiles)
+profile = Profile
+profile-name = Profile Name
+profile-tags = Tags, comma-separated
+profile-color-title = Accent color of this profile
+remove-color-title = Remove the accent color
+new = New
+copy = Copy
+copy-profile-title = Copy this profile to the clipboard
+copy-servers-title = Copy these DNS servers to the clipboard
+paste = Paste
+paste-profile-title = Add a profile copied on another machine
+export = Export
+export-profiles-title = Save all profiles to a JSON file
+import = Import
+import-profiles-title = Add the profiles from an exported JSON file
+
+# Providers
+resolver-provider = Resolver Provider
+choose-provider = Choose provider…
+filtering-variant = Filtering variant
+add = Add
+add-provider-title = Add your own provider, e.g. a Pi-hole on your network
+import-doh = Import from Windows
+import-doh-title = Add a provider for each DoH server set up in Windows that is not in the list yet
+edit = Edit
+edit-provider = Edit Provider
+new-provider = New Provider
+name = Name
+ipv4-addresses = IPv4 Addresses
+ipv6-addresses = IPv6 Addresses
+doh-template-optional = DoH Template URL (optional)
+save-provider = Save Provider
+
+# DNSSEC
+profile-named = Profile "{ $name }"
+current-dns = Current DNS
+dnssec-validation = DNSSEC Validation
+dnssec-check-title = Resolve a correctly signed name and one with broken signatures
+checking = Checking...
+check = Check
+not-checked-yet = not checked yet
+no-dns-server-to-check = No DNS server to check.
+
+# Leak test
+leak-test = DNS Leak Test
+leak-test-title = Look up unique names under bash.ws and list the resolvers that asked for them
+testing = Testing...
+run-leak-test = Run Leak Test
+no-leak-resolvers = No resolver reached the leak test service.
+resolver = Resolver
+country = Country
+network = Network
+leak-test-intro = Check which resolvers actually answer for this PC, e.g. after connecting a VPN.
+
+# Lookup
+current-server = Current server ({ $server })
+server-address = Server address
+dns-lookup = DNS Lookup
+lookup-name = Name, e.g. example.com
+looking-up = Looking up...
+look-up = Look Up
+lookup-summary = { $status } from { $server } in { $elapsed } ms
+record-type = Type
+record-data = Data
+
+# Network rules
+network-rules = Network Rules
+save-rules = Save Rules
+network-rules-enabled = Switch profiles automatically when an interface joins a network below; on other networks use Automatic
+selected-network = Selected interface: SSID { $ssid }, gateway MAC { $mac }
+wifi-ssid = Wi-Fi SSID
+gateway-mac = Gateway MAC
+add-network-rule = + Add Network Rule
+
+# Schedule
+schedule = Schedule
+save-schedule = Save Schedule
+schedule-enabled = Apply profiles to the selected interface by time of day while windns is open
+time-range-to = to
+remove-entry-title = Remove this entry
+weekday-mon = Mon
+weekday-tue = Tue
+weekday-wed = Wed
+weekday-thu = Thu
+weekday-fri = Fri
+weekday-sat = Sat
+weekday-sun = Sun
+add-schedule-entry = + Add Schedule Entry
+at-other-times = At other times
+
+# Hotkeys
+hotkeys = Hotkeys
+hotkeys-intro = Apply a profile to the selected interface from anywhere while windns is open
+hotkey-none = (None)
+
+# Validation
+server-primary = Primary
+server-secondary = Secondary
+server-numbered = Server { $number }
+new-profile = New Profile
+new-profile-numbered = New Profile { $number }
+no-profile-selected = No profile selected
+clipboard-not-profile = The clipboard does not contain a windns profile
+pasted-profile-no-name = The pasted profile has no name
+pasted-profile-invalid-color = The pasted profile has an invalid color
+no-provider-edited = No provider is being edited
+duplicate-provider-name = A provider with this name already exists
+empty-profile-name = Profile name cannot be empty
+duplicate-profile-name = A profile with this name already exists
+family-mismatch = { $families } servers are enabled, but { $interface } has no { $families } connectivity, so they will not be used.
+suspicious-dns-address = { $address } is probably not meant: { $reason }.
+doh-template-conflict = DoH templates are set per server for the whole system, so applying replaces the template used by { $conflicts }.
+doh-template-conflict-entry = { $address } in "{ $profile }"
+no-interface-selected = No interface selected
+invalid-connection-suffix = Invalid connection-specific DNS suffix
+invalid-search-suffix = Invalid DNS suffix in search list: { $suffix }
+invalid-nrpt-namespace = Invalid NRPT namespace: { $namespace }
+nrpt-rule-needs-server = NRPT rule for { $namespace } needs a name server
+invalid-nrpt-server = Invalid NRPT name server address: { $address }
+invalid-check-host = Invalid host to check after apply: { $host }
+primary-dns-required = { $family } primary DNS is required when enabled
+invalid-dns-address = Invalid { $family } { $server } DNS address
+duplicate-dns-address = { $family } { $server } DNS address { $address } is already in the list
+doh-address-required = { $family } { $server } DNS address is required when DoH is enabled
+doh-template-required = { $family } { $server } DoH template URL is required when DoH is enabled
+invalid-doh-template = Invalid { $family } { $server } DoH template URL
+
+# Messages
+app-title = Windows DNS Switcher
+save-config-failed = Failed to save config: { $error }
+load-config-failed = Failed to load config: { $error }. Changes will not be saved until a backup is restored or windns is restarted with a readable config.
+profile-skipped = Skipped profile { $profile }: { $reason }
+profile-skipped-at-line = Skipped profile { $profile } at line { $line }: { $reason }
+profiles-skipped = { $count } profile(s) in the config could not be read and were left out; Activity lists why. They stay in the file, after the other profiles, until it is fixed.
+schedule-saved = Schedule saved
+apply-cancelled = Apply cancelled. DNS settings may be partially applied.
+powershell-unavailable = PowerShell is unavailable; using netsh. DoH settings will not be applied.
+no-interfaces = No network interfaces found
+get-interfaces-failed = Failed to get network interfaces: { $error }
+interface-unavailable = The selected network interface is no longer available
+interface-switched = The selected network interface is no longer available; switched to { $interface }
+source-network-rule = Network rule
+source-tray-menu = Tray menu
+tray-reverts-at = Previous settings return at { $time }
+source-schedule = Schedule
+source-link = Link
+source-control = Control pipe
+auto-applied = { $source } applied { $profile } to { $interface }
+rule-notification-title = Network rule applied
+rule-notification-matched = { $condition } { $value } matched, so { $profile } was applied to { $interface }
+rule-notification-other = No network rule matched, so { $profile } was applied to { $interface }
+undo-rule = Undo
+tray-undo-rule = Undo network rule on { $interface }
+nothing-to-undo = No network rule change to undo
+applied-save-failed = { $applied } but failed to save config: { $error }
+auto-apply-failed = Failed to apply { $profile } to { $interface }: { $error }
+hotkeys-unavailable = Could not register { $hotkeys }; another program may be using them
+invalid-link = Not a windns link: { $link }
+link-profile-not-found = No profile named { $name }
+control-not-elevated = windns is not running as administrator
+control-busy = windns is busy applying DNS settings
+control-no-interface = No network interface is selected
+control-already-applied = The profile is already applied
+dns-changed-externally = DNS servers of { $interface } were changed outside windns
+applied-dns-overridden = DNS on { $interface } changed outside windns: now { $servers }
+profile-copied = Profile copied to the clipboard
+copy-profile-failed = Failed to copy profile: { $error }
+servers-copied = { $family } servers copied to the clipboard
+copy-servers-failed = Failed to copy DNS servers: { $error }
+read-clipboard-failed = Failed to read the clipboard: { $error }
+paste-profile-failed = Failed to paste profile: { $error }
+profile-added = Added profile "{ $name }"
+profiles-exported = Exported { $count } profile(s) to { $path }
+export-profiles-failed = Failed to export profiles: { $error }
+state-exported = Exported the DNS state to { $path }
+export-state-failed = Failed to export the DNS state: { $error }
+import-profiles-failed = Failed to import profiles: { $error }
+profiles-imported = Imported { $count } profile(s)
+dnssec-validating = validates DNSSEC
+dnssec-not-validating = does not validate DNSSEC
+dnssec-inconclusive = could not be checked
+dnssec-check-failed = check failed: { $error }
+delete-nrpt-rule-failed = Failed to delete NRPT rule: { $error }
+nrpt-rule-deleted = NRPT rule deleted
+doh-discovered = Discovered DoH template for { $address }: { $template }
+doh-discovery-failed = Failed to discover DoH template for { $address }: { $error }
+host-resolved = Resolved { $host } to { $addresses }
+host-resolution-failed = Failed to resolve { $host }: { $error }
+provider-saved = Provider "{ $name }" saved
+provider-deleted = Provider deleted
+import-doh-done = Providers imported from Windows: { $names }
+import-doh-none = Every DoH server set up in Windows is already in the provider list
+import-doh-failed = Failed to read the DoH servers of Windows: { $error }
+network-rules-saved = Network rules saved
+lookup-failed = Lookup of { $name } at { $server } failed: { $error }
+leak-test-failed = DNS leak test failed: { $error }
+dns-cache-flushed = DNS cache flushed
+flush-dns-cache-failed = Failed to flush DNS cache: { $error }
+settings-saved-warning = Settings saved. { $warning }
+settings-saved = Settings saved
+applied-save-config-failed = Settings applied but failed to save config: { $error }
+dns-applied-warning = DNS settings applied. { $warning }
+dns-applied = DNS settings applied successfully
+dns-applied-temporarily = DNS settings applied for { $minutes } minutes
+temporary-apply-failed = Failed to read the current DNS settings to put back later: { $error }
+temporary-apply-kept = Settings kept; the previous ones will not be put back
+temporary-apply-reverted = Put the previous DNS settings of { $interface } back
+temporary-apply-reverted-warning = Put the previous DNS settings of { $interface } back. { $warning }
+temporary-apply-revert-failed = Failed to put the previous DNS settings of { $interface } back: { $error }
+check-host-failed = { $host } does not resolve: { $error }
+hook-ran = Ran { $command }
+hook-output = { $command }: { $output }
+hook-failed = { $command } failed: { $error }
+hooks-skipped = The profile's commands before and after apply only run when you click Apply, so they were not run
+hooks-not-approved = The profile's commands before and after apply were not run because they are not allowed in the editor
+apply-failed = Failed to apply DNS settings: { $error }
+doh-probe-failed = DNS settings not applied because a DoH server did not answer: { $failures }. Uncheck "{ $option }" to apply anyway.
+local-resolver-not-running = DNS settings not applied because no local resolver answers on { $servers }. Start it first, or uncheck "{ $option }" to apply anyway.
+resolution-ok = Resolution OK ({ $elapsed } ms, { $host } via { $server })
+resolution-failed = Resolving { $host } via { $server } failed: { $error }
+health-ok = Name resolution and HTTPS working ({ $host }, { $elapsed } ms)
+health-failed = Name resolution or HTTPS failing: { $error }
+snapshot-failed = Failed to save the original DNS settings: { $error }
+clear-cache-failed = DNS cache clear failed: { $error }
+restored-save-config-failed = Original DNS settings restored but failed to save config: { $error }
+restored-warning = Original DNS settings restored. { $warning }
+restored = Original DNS settings restored
+reset-all-done = Adapters reset to automatic DNS: { $interfaces }
+reset-all-warning = Adapters reset to automatic DNS, with warnings: { $warnings }
+reset-all-failed = Some adapters could not be reset: { $errors }
+reset-all-list-failed = Failed to list the network adapters: { $error }
+reset-all-interface-done = { $interface }: reset to automatic DNS
+reset-all-not-elevated = windns reset-all must be run as administrator
+cli-profile = Profile: { $profile }
+cli-step-ok = { $step }: OK
+cli-step-warning = { $step }: { $warning }
+cli-step-failed = { $step } failed: { $error }
+cli-not-elevated = windns apply must be run as administrator
+restore-failed = Failed to restore original DNS settings: { $error }
+no-unused-doh = No unused DoH servers to remove
+unused-doh-removed = Removed unused DoH servers: { $servers }
+remove-unused-doh-failed = Failed to remove unused DoH servers: { $error }
+
+# Settings
+settings = Settings
+settings-appearance = Appearance
+settings-startup = Startup
+settings-apply = When applying
+settings-prompts = Prompts and monitoring
+confirm-profile-delete = Ask before deleting a profile
+warn-external-change = Warn when DNS servers are changed outside windns
+notify-network-rules = Show a notification when a network rule applies a profile
+control-server = Allow other programs to control windns
+control-server-title = Accept list, status and apply requests as JSON on the named pipe \\.\pipe\windns
+trace-commands = Record commands in a trace log
+trace-commands-title = Writes every PowerShell, netsh and ipconfig command with its output and timing to { $path }, to find out why a change failed. Addresses in URLs that can identify an account are hidden.
+use-cim-backend = Set DNS servers through WMI
+use-cim-backend-title = Reads and sets DNS servers through the MSFT_DNSClientServerAddress WMI class instead of the IP Helper API, for machines where the default way does not work. DoH, suffixes and NRPT still use PowerShell.
+dns-refresh-interval = Check for DNS changes every
+seconds = seconds
+
+# Active profile
+custom-dns = Custom (unmanaged)
+
+# Field errors
+field-required = Required
+field-invalid-address = Not a valid { $family } address
+field-host-name = A host name: click Resolve to fill in its addresses
+field-loopback-address = Loopback address: only a DNS server on this PC answers here
+field-multicast-address = Multicast address, not a DNS server
+field-broadcast-address = Broadcast address, not a DNS server
+field-unspecified-address = Unspecified address, which no server has
+field-invalid-doh-template = Must be an https:// URL with a host name
+field-malformed-doh-template = Not a valid URL template: check for spaces and unbalanced braces
+field-doh-template-no-dns-variable = The template has variables but no dns variable for the query
+field-doh-template-single-label = The host name has no dot, so it only resolves on a local network
+
+# Activity log
+activity-count = Activity ({ $count })
+no-activity = Nothing has happened yet.
+clear-activity = Clear
+history-count = History ({ $count })
+no-history = windns has not changed any DNS settings yet.
+history-change = { $before } → { $after }
+history-unknown = unknown
+history-failed = Failed: { $error }
+revert = Revert
+revert-title = Put the adapter back to the DNS settings it had before this change
+history-reverted = DNS settings of { $interface } reverted
+history-reverted-warning = DNS settings of { $interface } reverted. { $warning }
+history-revert-failed = Failed to revert DNS settings: { $error }
+history-interface-missing = { $interface } is not connected, so its DNS settings cannot be reverted
+
+# Updates
+settings-updates = Updates
+current-version = windns { $version }
+checking-for-updates = Checking for updates…
+up-to-date = windns { $version } is the latest version
+update-available = windns { $version } is available
+downloading-update = Downloading windns { $version }…
+update-ready = windns { $version } is ready. Restart to finish updating.
+update-failed = Update failed: { $error }
+release-notes = Release notes
+check-for-updates = Check for Updates
+download-update = Download and Install
+restart-to-update = Restart to Update
+
+# Backups
+settings-config-file = Config File
+config-folder = Folder: { $path }
+config-folder-argument = Folder: { $path } (set with --config)
+move-config = Move…
+move-config-title = Keep the config in another folder, such as OneDrive or Dropbox, so profiles roam between computers
+default-config-folder = Use Default Folder
+config-moved = Moved the config to { $path }
+config-adopted = Now using the config already in { $path }; the previous one was backed up
+move-config-failed = Failed to move the config: { $error }
+config-format = Format
+config-format-title = JSONC is the default. TOML allows comments and is easier to edit by hand.
+config-format-changed = Saved the config to { $path }
+config-format-failed = Failed to change the config format: { $error }
+encrypt-profiles = Encrypt profiles for this Windows user
+encrypt-profiles-title = Store profiles encrypted with Windows DPAPI so that other users cannot read them. The config can then only be loaded by this user on this computer.
+backups-hint = windns keeps a copy of its settings and profiles each time it starts.
+open-backups = Restore from Backup…
+restore-backup-title = Restore from Backup
+restore-backup-intro = Restoring replaces all profiles and settings. The current ones are backed up first. The last { $count } backups are kept.
+no-backups = There are no backups yet.
+backup-profiles = { $count } profile(s)
+backup-unreadable = Cannot be read
+restore = Restore
+config-restored = Restored the backup from { $created }
+restore-backup-failed = Failed to restore the backup: { $error }
+list-backups-failed = Failed to list backups: { $error }
@@ -0,0 +1,512 @@
+# Japanese messages. Keys and placeables match en.ftl.
+
+language = 言語
+
+# Dialogs
+delete-profile-title = プロファイルの削除
+delete-profile-confirm = 「{ $name }」を削除しますか?
+discard-changes-title = 変更の破棄
+discard-changes-confirm = 「{ $name }」には保存されていない変更があります。変更を破棄して自動に切り替えますか?
+discard-changes = 破棄
+cancel = キャンセル
+delete = 削除
+close = 閉じる
+preview = プレビュー
+preview-intro = 適用すると次の手順が実行されます:
+
+# Mode and interface
+mode-automatic-dhcp = 自動 (DHCP)
+mode-manual = 手動
+automatic = 自動
+network-interface = ネットワーク インターフェイス
+interface-details = 詳細
+hidden-interface = { $name } (非表示)
+disconnected-interface = { $name } (未接続)
+show-all-interfaces = すべてのアダプターを表示
+show-all-interfaces-title = 仮想、VPN、ループバックのアダプターと非表示にしたアダプターも一覧に表示します
+show-disconnected-interfaces = 未接続のアダプターを表示
+show-disconnected-interfaces-title = ケーブルが外れたイーサネット ポートなど、リンクのないアダプターも一覧に表示し、事前に DNS を設定できるようにします
+refresh-interfaces = アダプターの一覧を再読み込み
+hide-interface = 非表示にする
+interface-alias = 表示名
+interface-alias-title = Windows の名前の代わりにこのアダプターに表示する名前。空欄にすると Windows の名前を使用します。
+unhide-interface = 再表示する
+vpn-active = VPN が { $name } で接続されています。切断されるまで、このアダプターに設定した DNS サーバーは使われないことがあります。
+use-vpn-adapter = VPN アダプターを使う
+adapter-description = 説明
+ip-addresses = IP アドレス
+default-gateway = デフォルト ゲートウェイ
+dhcp-server = DHCP サーバー
+mac-address = MAC アドレス
+link-speed = リンク速度
+not-available = なし
+elevation-warning = windns は管理者として実行されていないため、読み取り専用です。DNS の状態の確認とプロファイルの編集はできますが、DNS 設定は適用できません。
+restart-as-admin = 管理者として再起動
+read-only-reason = DNS 設定の変更には管理者権限が必要です。適用するには windns を管理者として再起動してください。
+dns-policy-managed = この PC の DNS 設定はグループ ポリシーで管理されています ({ $policies })。windns による変更は上書きされる可能性があります。
+dns-policy-overrides = グループ ポリシーがこの設定を上書きするため、設定は反映されません: { $policies }。
+policy-dns-servers = "DNS サーバー" が { $servers } に設定されています
+policy-doh-prohibited = "DNS over HTTPS (DoH) の名前解決を構成する" が禁止に設定されています
+policy-doh-required = "DNS over HTTPS (DoH) の名前解決を構成する" が必須に設定されています
+policy-suffix-search-list = "DNS サフィックス検索一覧"
+policy-nrpt = 名前解決ポリシーの規則
+
+# DNS settings
+dns-settings = DNS 設定
+example = 例: { $example }
+use-family-servers = { $family } の DNS サーバーを使用
+leave-family-unchanged = 変更しない
+leave-family-unchanged-title = アダプターの { $family } サーバー (VPN から設定されたものなど) を DHCP に戻さず、そのまま残します
+dns-server-primary = 優先 DNS
+dns-server-secondary = 代替 DNS
+dns-server-numbered = DNS サーバー { $number }
+add-dns-server = + DNS サーバーを追加
+remove = 削除
+remove-server-title = このサーバーを削除
+dns-over-https = DNS over HTTPS
+doh-off = オフ
+doh-on-manual = オン (手動テンプレート)
+doh-template-url = DoH テンプレート URL
+discover = 検出
+discover-doh-title = サーバーに DoH テンプレートを問い合わせる (DDR)
+resolve = 解決
+resolve-host-title = 現在の DNS サーバーでこのホスト名のアドレスを調べる
+resolved-addresses = { $host } のアドレス: { $addresses }
+use-resolved-addresses = これらのアドレスを使用
+allow-plaintext-fallback = 平文へのフォールバックを許可
+auto-upgrade = すべてのアダプターで DoH にアップグレード
+auto-upgrade-title = オフにすると、テンプレートは登録されますが DoH をオンにしたアダプターでのみ使われます
+suffixes-need-powershell = DNS サフィックスには PowerShell が必要ですが、利用できません
+nrpt-needs-powershell = NRPT 規則には PowerShell が必要ですが、利用できません
+doh-needs-windows-11 = DNS over HTTPS には Windows 11 または Windows Server 2022 以降が必要です
+doh-needs-powershell = DNS over HTTPS には PowerShell が必要ですが、利用できません
+
+# DNS suffixes
+dns-suffixes = DNS サフィックス
+set-connection-suffix = 接続固有のサフィックスを設定
+connection-suffix-placeholder = 例: corp.example.com (空欄でクリア)
+replace-search-list = サフィックス検索一覧を置き換える
+search-suffix-numbered = 検索サフィックス { $number }
+remove-suffix-title = このサフィックスを削除
+add-search-suffix = + 検索サフィックスを追加
+check-host = 適用後に名前解決するホスト
+check-host-placeholder = 例: intranet.corp.example (省略可)
+check-host-title = プロファイルの適用後にこのホストを名前解決できない場合、警告を表示します
+before-apply = 適用前のコマンド
+before-apply-title = プロファイルを適用する前に実行する PowerShell コマンドです (ローカルプロキシの起動など)。失敗するとプロファイルは適用されません
+after-apply = 適用後のコマンド
+after-apply-title = プロファイルを適用した後に実行する PowerShell コマンドです (ipconfig /registerdns など)
+hook-command-placeholder = 例: Restart-Service MyProxy (省略可)
+allow-hooks = これらのコマンドの実行を許可する
+allow-hooks-title = コマンドは 「適用」をクリックするたびに管理者として実行されます。自分で書いたか確認したコマンドだけを許可してください。変更すると再度の許可が必要です
+local-resolver = ローカルリゾルバーを使用 (Pi-hole、AdGuard Home、dnscrypt-proxy)
+local-resolver-title = この PC で動作するリゾルバー (127.0.0.1 と ::1) に DNS クエリを送る
+local-resolver-check = 適用前にローカルリゾルバーが応答することを確認
+local-resolver-check-title = 127.0.0.1 と ::1 にテストクエリを送り、応答がなければ中止する
+
+# NRPT
+use-nrpt-rules = このプロファイルの NRPT ルールを適用
+nrpt-rules = NRPT 規則
+namespace = 名前空間
+name-servers = ネーム サーバー
+remove-rule-title = この規則を削除
+add-nrpt-rule = + NRPT 規則を追加
+active-nrpt-rules = 有効な NRPT 規則
+refresh = 更新
+no-nrpt-rules = NRPT 規則は構成されていません。
+source = 設定元
+source-other = その他
+delete-rule-title = この規則を削除
+
+# Actions and settings
+flush-after-apply = 適用後に DNS キャッシュをフラッシュ
+probe-doh = 適用前に DoH サーバーを確認
+probe-doh-title = 各 DoH サーバーにテスト クエリを送り、応答しないサーバーがあれば中止します
+check-resolution = 適用後に名前解決をテスト
+check-resolution-title = 新しい DNS サーバーでテスト用ホストを解決し、結果をステータス バーに表示します
+launch-at-startup = Windows の起動時に起動
+launch-at-startup-title = サインイン時に windns を起動し、ネットワーク ルール、スケジュール、トレイ メニューをすぐに使えるようにします
+start-minimized = 最小化して起動
+open-links = windns:// リンクを開く
+open-links-title = windns://apply/Cloudflare のようなリンクやショートカットでプロファイルを適用できるようにします
+logon-task = ログオン時に適用
+logon-task-title = サインイン時にスケジュールされたタスクで windns を管理者として起動し、UAC の確認なしでこのプロファイルを適用します
+logon-task-off = オフ
+logon-task-checking = スケジュールされたタスクを確認しています…
+logon-task-none = スケジュールされたタスクは登録されていません
+logon-task-registered = スケジュールされたタスクがログオン時に { $profile } を適用します
+logon-task-missing-profile = スケジュールされたタスクが適用するプロファイルは存在しません
+theme = テーマ
+theme-system = システム
+theme-light = ライト
+theme-dark = ダーク
+save = 保存
+cleanup-doh = DoH を整理
+cleanup-doh-title = このアプリが登録した DoH サーバーのうち、どのプロファイルも使っていないものを削除します
+restore-original = 元に戻す
+restore-original-title = このインターフェイスを windns が変更する前の DNS 設定に戻します
+reset-all = すべて自動に戻す
+reset-all-title = 名前解決がおかしいときに、すべてのアダプターを DHCP が提供する DNS サーバーに戻し、DoH をオフにします
+flush-dns-cache = DNS キャッシュをフラッシュ
+export-state = 状態をエクスポート
+export-state-title = すべてのアダプターの DNS サーバー、DoH テンプレート、サフィックスを JSON または Markdown のレポートに保存します
+applying = 適用中...
+apply = 適用
+apply-for = 期間を指定して適用…
+apply-for-title = 設定を適用し、指定した時間が過ぎたら元の設定に戻す
+apply-for-minutes = { $minutes } 分間
+
+# Status bar
+origin-static = 静的
+close-message = メッセージを閉じる
+active-profile = 使用中のプロファイル:
+current-ipv4-dns = 現在の IPv4 DNS:
+current-ipv6-dns = 現在の IPv6 DNS:
+resolution-check = 名前解決の確認:
+temporary-apply = 一時適用:
+temporary-apply-reverts-in = { $time } 後に元の設定に戻ります
+keep-temporary = 維持
+keep-temporary-title = この設定を維持し、元の設定に戻さない
+revert-temporary = 今すぐ戻す
+revert-temporary-title = 今すぐ元の設定に戻す
+latency-ms = { $ms } ms
+latency-no-answer = 応答なし
+
+# Profiles
+all-tags = すべて
+search-profiles = 名前またはタグでプロファイルを検索
+no-profiles = (プロファイルなし)
+profile = プロファイル
+profile-name = プロファイル名
+profile-tags = タグ (コンマ区切り)
+profile-color-title = このプロファイルのアクセント カラー
+remove-color-title = アクセント カラーを削除
+new = 新規
+copy = コピー
+copy-profile-title = このプロファイルをクリップボードにコピー
+copy-servers-title = この DNS サーバーをクリップボードにコピー
+paste = 貼り付け
+paste-profile-title = 別のマシンでコピーしたプロファイルを追加
+export = エクスポート
+export-profiles-title = すべてのプロファイルを JSON ファイルに保存
+import = インポート
+import-profiles-title = エクスポートした JSON ファイルからプロファイルを追加
+
+# Providers
+resolver-provider = DNS プロバイダー
+choose-provider = プロバイダーを選択…
+filtering-variant = フィルタリングの種類
+add = 追加
+add-provider-title = 独自のプロバイダー (ネットワーク上の Pi-hole など) を追加
+import-doh = Windows から取り込む
+import-doh-title = Windows に設定されている DoH サーバーのうち、一覧にないものをプロバイダーとして追加します
+edit = 編集
+edit-provider = プロバイダーの編集
+new-provider = 新しいプロバイダー
+name = 名前
+ipv4-addresses = IPv4 アドレス
+ipv6-addresses = IPv6 アドレス
+doh-template-optional = DoH テンプレート URL (省略可)
+save-provider = プロバイダーを保存
+
+# DNSSEC
+profile-named = プロファイル「{ $name }」
+current-dns = 現在の DNS
+dnssec-validation = DNSSEC 検証
+dnssec-check-title = 正しく署名された名前と署名が壊れた名前を解決します
+checking = 確認中...
+check = 確認
+not-checked-yet = 未確認
+no-dns-server-to-check = 確認する DNS サーバーがありません。
+
+# Leak test
+leak-test = DNS リーク テスト
+leak-test-title = bash.ws 配下の一意な名前を解決し、問い合わせてきたリゾルバーを一覧表示します
+testing = テスト中...
+run-leak-test = リーク テストを実行
+no-leak-resolvers = リーク テスト サービスに到達したリゾルバーはありません。
+resolver = リゾルバー
+country = 国
+network = ネットワーク
+leak-test-intro = VPN 接続後などに、この PC の問い合わせに実際に応答しているリゾルバーを確認します。
+
+# Lookup
+current-server = 現在のサーバー ({ $server })
+server-address = サーバー アドレス
+dns-lookup = DNS 参照
+lookup-name = 名前 (例: example.com)
+looking-up = 参照中...
+look-up = 参照
+lookup-summary = { $server } から { $status } ({ $elapsed } ms)
+record-type = 種類
+record-data = データ
+
+# Network rules
+network-rules = ネットワーク ルール
+save-rules = ルールを保存
+network-rules-enabled = インターフェイスが下記のネットワークに接続したらプロファイルを自動で切り替え、それ以外のネットワークでは自動にします
+selected-network = 選択中のインターフェイス: SSID { $ssid }、ゲートウェイ MAC { $mac }
+wifi-ssid = Wi-Fi SSID
+gateway-mac = ゲートウェイ MAC
+add-network-rule = + ネットワーク ルールを追加
+
+# Schedule
+schedule = スケジュール
+save-schedule = スケジュールを保存
+schedule-enabled = windns の起動中、時間帯に応じて選択中のインターフェイスにプロファイルを適用します
+time-range-to = ～
+remove-entry-title = この項目を削除
+weekday-mon = 月
+weekday-tue = 火
+weekday-wed = 水
+weekday-thu = 木
+weekday-fri = 金
+weekday-sat = 土
+weekday-sun = 日
+add-schedule-entry = + スケジュール項目を追加
+at-other-times = その他の時間帯
+
+# Hotkeys
+hotkeys = ホットキー
+hotkeys-intro = windns の起動中、どこからでも選択中のインターフェイスにプロファイルを適用します
+hotkey-none = (なし)
+
+# Validation
+server-primary = 優先
+server-secondary = 代替
+server-numbered = サーバー { $number }
+new-profile = 新しいプロファイル
+new-profile-numbered = 新しいプロファイル { $number }
+no-profile-selected = プロファイルが選択されていません
+clipboard-not-profile = クリップボードに windns のプロファイルがありません
+pasted-profile-no-name = 貼り付けたプロファイルに名前がありません
+pasted-profile-invalid-color = 貼り付けたプロファイルの色が無効です
+no-provider-edited = 編集中のプロバイダーがありません
+duplicate-provider-name = 同じ名前のプロバイダーが既に存在します
+empty-profile-name = プロファイル名を入力してください
+duplicate-profile-name = 同じ名前のプロファイルが既に存在します
+family-mismatch = { $families } サーバーが有効ですが、{ $interface } には { $families } の接続がないため使用されません。
+suspicious-dns-address = { $address } は意図したアドレスではない可能性があります: { $reason }。
+doh-template-conflict = DoH テンプレートはサーバーごとにシステム全体で設定されるため、適用すると { $conflicts } が使用するテンプレートが置き換えられます。
+doh-template-conflict-entry = "{ $profile }" の { $address }
+no-interface-selected = インターフェイスが選択されていません
+invalid-connection-suffix = 接続固有の DNS サフィックスが無効です
+invalid-search-suffix = 検索一覧の DNS サフィックスが無効です: { $suffix }
+invalid-nrpt-namespace = NRPT の名前空間が無効です: { $namespace }
+nrpt-rule-needs-server = { $namespace } の NRPT 規則にはネーム サーバーが必要です
+invalid-nrpt-server = NRPT のネーム サーバー アドレスが無効です: { $address }
+invalid-check-host = 適用後に確認するホストが無効です: { $host }
+primary-dns-required = { $family } を有効にする場合は優先 DNS が必要です
+invalid-dns-address = { $family } の{ $server } DNS アドレスが無効です
+duplicate-dns-address = { $family } の{ $server } DNS アドレス { $address } は既に一覧にあります
+doh-address-required = DoH を有効にする場合は { $family } の{ $server } DNS アドレスが必要です
+doh-template-required = DoH を有効にする場合は { $family } の{ $server } DoH テンプレート URL が必要です
+invalid-doh-template = { $family } の{ $server } DoH テンプレート URL が無効です
+
+# Messages
+app-title = Windows DNS Switcher
+save-config-failed = 設定を保存できませんでした: { $error }
+load-config-failed = 設定を読み込めませんでした: { $error }。バックアップを復元するか、読み込める設定で windns を再起動するまで、変更は保存されません。
+profile-skipped = プロファイル { $profile } を読み込めませんでした: { $reason }
+profile-skipped-at-line = プロファイル { $profile } (行 { $line }) を読み込めませんでした: { $reason }
+profiles-skipped = 設定内の { $count } 個のプロファイルを読み込めなかったため除外しました。理由はアクティビティに表示されます。修正されるまで、ほかのプロファイルの後ろにファイル内でそのまま残ります。
+schedule-saved = スケジュールを保存しました
+apply-cancelled = 適用を取り消しました。DNS 設定が一部だけ適用されている可能性があります。
+powershell-unavailable = PowerShell を使用できないため netsh を使用します。DoH 設定は適用されません。
+no-interfaces = ネットワーク インターフェイスが見つかりません
+get-interfaces-failed = ネットワーク インターフェイスを取得できませんでした: { $error }
+interface-unavailable = 選択したネットワーク インターフェイスは使用できなくなりました
+interface-switched = 選択したネットワーク インターフェイスは使用できなくなったため、{ $interface } に切り替えました
+source-network-rule = ネットワーク ルール
+source-tray-menu = トレイ メニュー
+tray-reverts-at = { $time } に元の設定に戻ります
+source-schedule = スケジュール
+source-link = リンク
+source-control = 制御パイプ
+auto-applied = { $source } により { $profile } を { $interface } に適用しました
+rule-notification-title = ネットワーク ルールを適用しました
+rule-notification-matched = { $condition } { $value } に一致したため、{ $profile } を { $interface } に適用しました
+rule-notification-other = 一致するネットワーク ルールがないため、{ $profile } を { $interface } に適用しました
+undo-rule = 元に戻す
+tray-undo-rule = { $interface } のネットワーク ルールを元に戻す
+nothing-to-undo = 元に戻せるネットワーク ルールの変更はありません
+applied-save-failed = { $applied }。ただし設定を保存できませんでした: { $error }
+auto-apply-failed = { $profile } を { $interface } に適用できませんでした: { $error }
+hotkeys-unavailable = { $hotkeys } を登録できませんでした。別のプログラムが使用している可能性があります
+invalid-link = windns のリンクではありません: { $link }
+link-profile-not-found = { $name } という名前のプロファイルはありません
+control-not-elevated = windns が管理者として実行されていません
+control-busy = windns は DNS 設定を適用中です
+control-no-interface = ネットワーク インターフェイスが選択されていません
+control-already-applied = プロファイルは既に適用されています
+dns-changed-externally = { $interface } の DNS サーバーが windns の外部で変更されました
+applied-dns-overridden = { $interface } の DNS が windns 以外で変更されました。現在: { $servers }
+profile-copied = プロファイルをクリップボードにコピーしました
+copy-profile-failed = プロファイルをコピーできませんでした: { $error }
+servers-copied = { $family } サーバーをクリップボードにコピーしました
+copy-servers-failed = DNS サーバーをコピーできませんでした: { $error }
+read-clipboard-failed = クリップボードを読み取れませんでした: { $error }
+paste-profile-failed = プロファイルを貼り付けられませんでした: { $error }
+profile-added = プロファイル「{ $name }」を追加しました
+profiles-exported = { $count } 件のプロファイルを { $path } にエクスポートしました
+export-profiles-failed = プロファイルをエクスポートできませんでした: { $error }
+state-exported = DNS の状態を { $path } にエクスポートしました
+export-state-failed = DNS の状態をエクスポートできませんでした: { $error }
+import-profiles-failed = プロファイルをインポートできませんでした: { $error }
+profiles-imported = { $count } 件のプロファイルをインポートしました
+dnssec-validating = DNSSEC を検証します
+dnssec-not-validating = DNSSEC を検証しません
+dnssec-inconclusive = 確認できませんでした
+dnssec-check-failed = 確認に失敗しました: { $error }
+delete-nrpt-rule-failed = NRPT 規則を削除できませんでした: { $error }
+nrpt-rule-deleted = NRPT 規則を削除しました
+doh-discovered = { $address } の DoH テンプレートが見つかりました: { $template }
+doh-discovery-failed = { $address } の DoH テンプレートが見つかりませんでした: { $error }
+host-resolved = { $host } を { $addresses } に解決しました
+host-resolution-failed = { $host } を解決できませんでした: { $error }
+provider-saved = プロバイダー「{ $name }」を保存しました
+provider-deleted = プロバイダーを削除しました
+import-doh-done = Windows からプロバイダーを取り込みました: { $names }
+import-doh-none = Windows に設定されている DoH サーバーはすべてプロバイダー一覧にあります
+import-doh-failed = Windows の DoH サーバーを読み取れませんでした: { $error }
+network-rules-saved = ネットワーク ルールを保存しました
+lookup-failed = { $server } での { $name } の参照に失敗しました: { $error }
+leak-test-failed = DNS リーク テストに失敗しました: { $error }
+dns-cache-flushed = DNS キャッシュをフラッシュしました
+flush-dns-cache-failed = DNS キャッシュをフラッシュできませんでした: { $error }
+settings-saved-warning = 設定を保存しました。{ $warning }
+settings-saved = 設定を保存しました
+applied-save-config-failed = 設定を適用しましたが、保存できませんでした: { $error }
+dns-applied-warning = DNS 設定を適用しました。{ $warning }
+dns-applied = DNS 設定を適用しました
+dns-applied-temporarily = DNS 設定を { $minutes } 分間適用しました
+temporary-apply-failed = 後で戻すための現在の DNS 設定を読み取れませんでした: { $error }
+temporary-apply-kept = 設定を維持しました。元の設定には戻しません
+temporary-apply-reverted = { $interface } の DNS 設定を元に戻しました
+temporary-apply-reverted-warning = { $interface } の DNS 設定を元に戻しました。{ $warning }
+temporary-apply-revert-failed = { $interface } の DNS 設定を元に戻せませんでした: { $error }
+check-host-failed = { $host } を名前解決できません: { $error }
+hook-ran = { $command } を実行しました
+hook-output = { $command }: { $output }
+hook-failed = { $command } が失敗しました: { $error }
+hooks-skipped = 適用前後のコマンドは 「適用」をクリックしたときだけ実行されるため、実行しませんでした
+hooks-not-approved = 適用前後のコマンドはエディターで許可されていないため、実行しませんでした
+apply-failed = DNS 設定を適用できませんでした: { $error }
+doh-probe-failed = DoH サーバーが応答しなかったため、DNS 設定を適用しませんでした: { $failures }。それでも適用するには「{ $option }」をオフにしてください。
+local-resolver-not-running = { $servers } でローカルリゾルバーが応答しないため、DNS 設定を適用しませんでした。先にリゾルバーを起動するか、「{ $option }」をオフにしてください。
+resolution-ok = 名前解決 OK ({ $elapsed } ms、{ $server } 経由で { $host })
+resolution-failed = { $server } 経由での { $host } の名前解決に失敗しました: { $error }
+health-ok = 名前解決と HTTPS は正常です ({ $host }、{ $elapsed } ms)
+health-failed = 名前解決または HTTPS に失敗しています: { $error }
+snapshot-failed = 元の DNS 設定を保存できませんでした: { $error }
+clear-cache-failed = DNS キャッシュをクリアできませんでした: { $error }
+restored-save-config-failed = 元の DNS 設定に戻しましたが、設定を保存できませんでした: { $error }
+restored-warning = 元の DNS 設定に戻しました。{ $warning }
+restored = 元の DNS 設定に戻しました
+reset-all-done = アダプターを自動 DNS に戻しました: { $interfaces }
+reset-all-warning = アダプターを自動 DNS に戻しましたが、警告があります: { $warnings }
+reset-all-failed = 一部のアダプターを戻せませんでした: { $errors }
+reset-all-list-failed = ネットワークアダプターの一覧を取得できませんでした: { $error }
+reset-all-interface-done = { $interface }: 自動 DNS に戻しました
+reset-all-not-elevated = windns reset-all は管理者として実行する必要があります
+cli-profile = プロファイル: { $profile }
+cli-step-ok = { $step }: OK
+cli-step-warning = { $step }: { $warning }
+cli-step-failed = { $step } に失敗しました: { $error }
+cli-not-elevated = windns apply は管理者として実行する必要があります
+restore-failed = 元の DNS 設定に戻せませんでした: { $error }
+no-unused-doh = 削除する未使用の DoH サーバーはありません
+unused-doh-removed = 未使用の DoH サーバーを削除しました: { $servers }
+remove-unused-doh-failed = 未使用の DoH サーバーを削除できませんでした: { $error }
+
+# Settings
+settings = 設定
+settings-appearance = 外観
+settings-startup = 起動
+settings-apply = 適用時
+settings-prompts = 確認と監視
+confirm-profile-delete = プロファイルを削除する前に確認
+warn-external-change = windns の外部で DNS サーバーが変更されたら警告
+notify-network-rules = ネットワーク ルールがプロファイルを適用したら通知を表示
+control-server = 他のプログラムから windns を操作できるようにする
+control-server-title = 名前付きパイプ \\.\pipe\windns で JSON の list、status、apply 要求を受け付けます
+trace-commands = コマンドをトレースログに記録する
+trace-commands-title = PowerShell、netsh、ipconfig の各コマンドと出力、所要時間を { $path } に書き込み、変更に失敗した原因を調べられるようにします。アカウントを特定できる URL の部分は伏せられます。
+use-cim-backend = WMI で DNS サーバーを設定
+use-cim-backend-title = IP Helper API の代わりに WMI の MSFT_DNSClientServerAddress クラスで DNS サーバーを読み書きします。既定の方法がうまく動かない環境向けです。DoH、サフィックス、NRPT には引き続き PowerShell を使います。
+dns-refresh-interval = DNS の変更を確認する間隔
+seconds = 秒
+
+# Active profile
+custom-dns = カスタム (管理外)
+
+# Field errors
+field-required = 必須です
+field-invalid-address = 有効な { $family } アドレスではありません
+field-host-name = ホスト名です: 「解決」をクリックするとアドレスを入力します
+field-loopback-address = ループバック アドレスです。この PC 上の DNS サーバーのみが応答します
+field-multicast-address = マルチキャスト アドレスであり、DNS サーバーではありません
+field-broadcast-address = ブロードキャスト アドレスであり、DNS サーバーではありません
+field-unspecified-address = 未指定アドレスであり、どのサーバーも持ちません
+field-invalid-doh-template = ホスト名を含む https:// の URL を入力してください
+field-malformed-doh-template = URL テンプレートの形式が正しくありません。空白や括弧の対応を確認してください
+field-doh-template-no-dns-variable = テンプレートに変数がありますが、クエリを入れる dns 変数がありません
+field-doh-template-single-label = ホスト名にドットがないため、ローカルネットワークでのみ解決されます
+
+# Activity log
+activity-count = アクティビティ ({ $count })
+no-activity = まだ何も行われていません。
+clear-activity = クリア
+history-count = 履歴 ({ $count })
+no-history = windns はまだ DNS 設定を変更していません。
+history-change = { $before } → { $after }
+history-unknown = 不明
+history-failed = 失敗: { $error }
+revert = 元に戻す
+revert-title = この変更の前の DNS 設定にアダプターを戻します
+history-reverted = { $interface } の DNS 設定を元に戻しました
+history-reverted-warning = { $interface } の DNS 設定を元に戻しました。{ $warning }
+history-revert-failed = DNS 設定を元に戻せませんでした: { $error }
+history-interface-missing = { $interface } が接続されていないため、DNS 設定を元に戻せません
+
+# Updates
+settings-updates = アップデート
+current-version = windns { $version }
+checking-for-updates = アップデートを確認しています…
+up-to-date = windns { $version } は最新バージョンです
+update-available = windns { $version } を利用できます
+downloading-update = windns { $version } をダウンロードしています…
+update-ready = windns { $version } の準備ができました。再起動するとアップデートが完了します。
+update-failed = アップデートできませんでした: { $error }
+release-notes = リリースノート
+check-for-updates = アップデートを確認
+download-update = ダウンロードしてインストール
+restart-to-update = 再起動してアップデート
+
+# Backups
+settings-config-file = 設定ファイル
+config-folder = フォルダー: { $path }
+config-folder-argument = フォルダー: { $path } (--config で指定)
+move-config = 移動…
+move-config-title = OneDrive や Dropbox などの別のフォルダーに設定を保存して、プロファイルを複数のコンピューターで共有します
+default-config-folder = 既定のフォルダーに戻す
+config-moved = 設定を { $path } に移動しました
+config-adopted = { $path } にある設定を使用しています。以前の設定はバックアップしました
+move-config-failed = 設定を移動できませんでした: { $error }
+config-format = 形式
+config-format-title = 既定は JSONC です。TOML はコメントを書けて、手で編集しやすい形式です。
+config-format-changed = 設定を { $path } に保存しました
+config-format-failed = 設定ファイルの形式を変更できませんでした: { $error }
+encrypt-profiles = プロファイルをこの Windows ユーザー用に暗号化
+encrypt-profiles-title = Windows DPAPI で暗号化してプロファイルを保存し、他のユーザーが読めないようにします。設定ファイルはこのコンピューターのこのユーザーだけが読み込めるようになります。
+backups-hint = windns は起動するたびに設定とプロファイルのコピーを保存します。
+open-backups = バックアップから復元…
+restore-backup-title = バックアップから復元
+restore-backup-intro = 復元するとすべてのプロファイルと設定が置き換わります。現在の内容は先にバックアップされます。バックアップは最新の { $count } 件が保存されます。
+no-backups = バックアップはまだありません。
+backup-profiles = プロファイル { $count } 件
+backup-unreadable = 読み込めません
+restore = 復元
+config-restored = { $created } のバックアップを復元しました
+restore-backup-failed = バックアップを復元できませんでした: { $error }
+list-backups-failed = バックアップを一覧表示できませんでした: { $error }
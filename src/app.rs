@@ -1,47 +1,303 @@
 use crate::components::*;
 use crate::dns::{
-    DnsCommandError, DnsMode, DnsSettings, capture_window_state, clear_dns_cache, get_current_dns,
-    get_network_interfaces, load_config, save_config, set_dns_automatic, set_dns_with_settings,
+    AddressFamily, AppSettings, Capabilities, ConfigFormat, ControlRequest, ControlResponse,
+    ControlServer, DnsBackend, DnsCommandError, DnsMode, DnsSettings, DnssecStatus, HistoryEntry,
+    HotkeyListener, InterfaceWatcher, LinkListener, LookupQuery, NetworkInfo, NetworkInterface,
+    NetworkRule, NetworkRules, NotificationAction, ProviderDraft, RelaunchSelection, Schedule,
+    SkippedProfile, StartupSetting, SystemBackend, TemporaryApply, UNDO_RULE_LINK, WindowState,
+    append_history, backup_config, capture_window_state, check_dnssec, check_for_update,
+    check_health, check_host_resolves, check_resolution, clear_dns_cache, collect_state_report,
+    config_format, config_location, detect_dns_policies, detect_network, discover_doh_template,
+    download_update, export_profiles, get_clipboard_text, get_dns_server_addresses,
+    get_dns_suffixes, get_interface_details, get_network_interfaces, hooks_approved, hotkey_label,
+    import_profiles, install_update, interfaces_to_reset, is_elevated, is_undo_rule_link,
+    link_argument, link_handler_registered, list_config_backups, load_config_with_skipped,
+    load_history, local_time, logon_task_profile, lookup, measure_latency, move_config,
+    new_doh_servers, parse_link, relaunch_as_admin, remove_unused_doh_servers,
+    resolve_host_addresses, restore_config_backup, run_hook_command, run_leak_test, save_config,
+    save_state_report, server_answers, set_clipboard_text, set_command_timeout, set_command_trace,
+    set_config_format, set_dns_with_settings, set_link_handler_registered, set_logon_task,
+    set_startup_setting, show_notification, snapshot_dns_settings, startup_setting,
+    system_prefers_dark, unix_now, unreachable_doh_templates, update_config,
 };
-use crate::state::{AppState, Message};
-use dioxus::desktop::window;
+use crate::i18n::{set_language, t};
+use crate::state::{AppState, Message, MessageLevel, ResolvedHost, UpdateStatus};
+use crate::tray::{
+    TrayMenuState, is_reset_all_item, is_undo_rule_item, load_tray_icon, menu_target,
+};
+use dioxus::desktop::tao::event::{Event, WindowEvent};
+use dioxus::desktop::tao::window::Theme;
+use dioxus::desktop::trayicon::init_tray_icon;
+use dioxus::desktop::{use_tray_menu_event_handler, use_wry_event_handler, window};
 use dioxus::prelude::*;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// How often the schedule is checked for a profile change.
+const SCHEDULE_CHECK_INTERVAL: Duration = Duration::from_secs(20);
+/// How often temporary applies are checked for having run out.
+const TEMPORARY_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+/// How often the Windows dark mode setting is checked.
+const THEME_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+/// How often name resolution and HTTPS connectivity are checked.
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// How often the round-trip time of the DNS servers in use is measured.
+const LATENCY_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+/// How long the window has to stay put before its placement is saved.
+const WINDOW_SAVE_DELAY: Duration = Duration::from_secs(1);
 
 #[allow(non_snake_case)]
 pub fn App() -> Element {
     let mut state = use_signal(AppState::new);
+    use_hook(move || {
+        if let Some(worker) = state.peek().command_queue.worker() {
+            spawn(worker);
+        }
+    });
+    let mut apply_task = use_signal(|| None::<Task>);
+    let mut hotkey_task = use_signal(|| None::<Task>);
+
+    // Re-registers the hotkeys; dropping the old listener releases them.
+    let mut restart_hotkeys = move || {
+        if let Some(task) = hotkey_task.take() {
+            task.cancel();
+        }
+        let task = spawn(async move {
+            watch_hotkeys(state).await;
+        });
+        hotkey_task.set(Some(task));
+    };
 
     use_effect(move || {
         spawn(async move {
             initialize_app(state).await;
+            restart_hotkeys();
+            spawn(async move {
+                watch_network_rules(state).await;
+            });
+            spawn(async move {
+                watch_schedule(state).await;
+            });
+            spawn(async move {
+                watch_temporary_applies(state).await;
+            });
+            spawn(async move {
+                watch_links(state).await;
+            });
+            watch_interfaces(state).await;
+        });
+        spawn(async move {
+            watch_current_dns(state).await;
+        });
+        spawn(async move {
+            watch_system_theme(state).await;
+        });
+        spawn(async move {
+            watch_health(state).await;
+        });
+        spawn(async move {
+            watch_server_latency(state).await;
         });
     });
 
-    use_drop(move || {
-        let win = window();
+    // Runs the control server while it is turned on in the settings;
+    // cancelling the task closes the pipe.
+    let control_enabled = use_memo(move || state.read().config.settings.enables_control_server());
+    let mut control_task = use_signal(|| None::<Task>);
+    use_effect(move || {
+        let enabled = control_enabled();
+        if let Some(task) = control_task.take() {
+            task.cancel();
+        }
+        if enabled {
+            let task = spawn(async move {
+                serve_control_requests(state).await;
+            });
+            control_task.set(Some(task));
+        }
+    });
+
+    let is_dark_theme = use_memo(move || state.read().is_dark_theme());
+    use_effect(move || {
+        let theme = if is_dark_theme() {
+            Theme::Dark
+        } else {
+            Theme::Light
+        };
+        window().window.set_theme(Some(theme));
+    });
 
-        if let Some(window_state) = capture_window_state(&win.window) {
-            let mut config = load_config().unwrap_or_else(|_| state.peek().config.clone());
-            config.window = Some(window_state);
+    let tray_menu = use_memo(move || TrayMenuState::from_state(&state.read()));
+    let tray = use_hook(|| {
+        let tray = init_tray_icon(TrayMenuState::default().build_menu(), load_tray_icon());
+        let _ = tray.set_tooltip(Some(t!("app-title")));
+        tray
+    });
 
-            if let Err(e) = save_config(&config) {
-                eprintln!("Failed to save window state: {}", e);
+    use_effect({
+        let tray = tray.clone();
+        move || {
+            tray.set_menu(Some(Box::new(tray_menu.read().build_menu())));
+        }
+    });
+
+    use_tray_menu_event_handler(move |event| {
+        if is_reset_all_item(&event.id) {
+            spawn(async move {
+                reset_all_dns(state).await;
+            });
+            return;
+        }
+        if is_undo_rule_item(&event.id) {
+            spawn(async move {
+                undo_network_rule(state).await;
+            });
+            return;
+        }
+        let Some(target) = menu_target(&event.id) else {
+            return;
+        };
+        let tray = tray.clone();
+        spawn(async move {
+            apply_from_tray(state, target).await;
+            // Clicking a check item toggles it, so the menu is rebuilt
+            // even when nothing was applied.
+            tray.set_menu(Some(Box::new(tray_menu.peek().build_menu())));
+        });
+    });
+
+    // Bounds of the window while it is neither maximized nor minimized,
+    // saved in place of the maximized ones.
+    let mut restored_bounds = use_signal(|| None::<WindowState>);
+    let mut window_moves = use_signal(|| 0u64);
+    let desktop = window();
+    use_wry_event_handler(move |event, _| {
+        if let Event::WindowEvent {
+            event: WindowEvent::Moved(_) | WindowEvent::Resized(_),
+            ..
+        } = event
+        {
+            let win = &desktop.window;
+            if !win.is_maximized() && !win.is_minimized() {
+                restored_bounds.set(capture_window_state(win, None));
+            }
+            *window_moves.write() += 1;
+        }
+    });
+
+    // Saves the placement once the window stops moving, so that it is not
+    // lost if windns is killed before it can save on exit.
+    let mut window_save_task = use_signal(|| None::<Task>);
+    use_effect(move || {
+        if window_moves() == 0 {
+            return;
+        }
+        if let Some(task) = window_save_task.take() {
+            task.cancel();
+        }
+        let task = spawn(async move {
+            tokio::time::sleep(WINDOW_SAVE_DELAY).await;
+            save_window_placement(state, restored_bounds.peek().clone());
+        });
+        window_save_task.set(Some(task));
+    });
+
+    use_drop(move || {
+        let win = window();
+        // Nothing was selected if the interfaces never loaded.
+        let selection = state.peek().relaunch_selection();
+        let result = update_config(|config| {
+            let restored = restored_bounds.peek().clone().or(config.window.take());
+            config.window = capture_window_state(&win.window, restored.as_ref()).or(restored);
+            if selection.interface_guid.is_some() {
+                config.last_selection = Some(selection);
             }
+        });
+        if let Err(e) = result {
+            eprintln!("Failed to save window state: {}", e);
         }
     });
 
-    let on_interface_change = move |index: usize| {
+    let on_interface_change = move |guid: String| {
         spawn(async move {
-            change_interface(state, index).await;
+            change_interface(state, guid).await;
         });
     };
 
     let on_mode_change = move |mode: DnsMode| {
-        change_dns_mode(state, mode);
+        if mode == DnsMode::Automatic && state.read().has_unsaved_changes() {
+            state.write().show_discard_confirm = true;
+        } else {
+            change_dns_mode(state, mode);
+        }
     };
 
     let on_settings_change = move |settings: DnsSettings| {
-        state.write().current_settings = settings;
+        state.write().set_current_settings(settings);
+    };
+
+    let on_provider_change = move |(provider, variant): (String, Option<String>)| {
+        state
+            .write()
+            .fill_from_provider(&provider, variant.as_deref());
+    };
+
+    let on_provider_draft_change = move |draft: Option<ProviderDraft>| {
+        state.write().provider_draft = draft;
+    };
+
+    let on_save_provider = move |_| {
+        save_custom_provider(state);
+    };
+
+    let on_delete_provider = move |id: String| {
+        delete_custom_provider(state, id);
+    };
+
+    let on_import_doh_providers = move |_| {
+        spawn(async move {
+            import_doh_providers(state).await;
+        });
+    };
+
+    let on_revert_history = move |entry: HistoryEntry| {
+        spawn(async move {
+            revert_history_entry(state, entry).await;
+        });
+    };
+
+    let on_discover_doh = move |(family, index): (AddressFamily, usize)| {
+        spawn(async move {
+            discover_doh(state, family, index).await;
+        });
+    };
+
+    let on_resolve_server_host = move |(family, index): (AddressFamily, usize)| {
+        spawn(async move {
+            resolve_server_host(state, family, index).await;
+        });
+    };
+
+    let on_use_resolved_host = move |_| {
+        state.write().use_resolved_host();
+    };
+
+    let on_copy_profile_servers = move |family: AddressFamily| {
+        let text = match family {
+            AddressFamily::IPv4 => state.read().current_settings.ipv4.clipboard_text(),
+            AddressFamily::IPv6 => state.read().current_settings.ipv6.clipboard_text(),
+        };
+        spawn(async move {
+            copy_servers(state, family, text).await;
+        });
+    };
+
+    let on_copy_current_dns = move |family: AddressFamily| {
+        let text = state.read().current_dns_state.addresses(family).join("\n");
+        spawn(async move {
+            copy_servers(state, family, text).await;
+        });
     };
 
     let on_profile_change = move |id: String| {
@@ -56,8 +312,52 @@ pub fn App() -> Element {
         state.write().current_profile_name = name;
     };
 
+    let on_profile_tags_change = move |tags: String| {
+        state.write().current_profile_tags = tags;
+    };
+
+    let on_profile_tag_filter = move |tag: Option<String>| {
+        state.write().profile_tag_filter = tag;
+    };
+
+    let on_profile_search = move |text: String| {
+        state.write().profile_search = text;
+    };
+
+    let on_profile_color_change = move |color: Option<String>| {
+        state.write().current_profile_color = color;
+    };
+
     let on_delete_profile = move |_| {
-        state.write().show_delete_confirm = true;
+        if state.read().config.settings.confirms_profile_delete() {
+            state.write().show_delete_confirm = true;
+        } else {
+            state.write().delete_current_profile();
+        }
+    };
+
+    let on_copy_profile = move |_| {
+        spawn(async move {
+            copy_profile(state).await;
+        });
+    };
+
+    let on_paste_profile = move |_| {
+        spawn(async move {
+            paste_profile(state).await;
+        });
+    };
+
+    let on_export_profiles = move |_| {
+        spawn(async move {
+            export_profile_file(state).await;
+        });
+    };
+
+    let on_import_profiles = move |_| {
+        spawn(async move {
+            import_profile_file(state).await;
+        });
     };
 
     let on_confirm_delete = move |_| {
@@ -70,167 +370,1830 @@ pub fn App() -> Element {
         state.write().show_delete_confirm = false;
     };
 
+    let on_confirm_discard = move |_| {
+        {
+            let mut write_state = state.write();
+            write_state.discard_profile_changes();
+            write_state.show_discard_confirm = false;
+        }
+        change_dns_mode(state, DnsMode::Automatic);
+    };
+
+    let on_cancel_discard = move |_| {
+        state.write().show_discard_confirm = false;
+    };
+
     let on_save = move |_| {
         spawn(async move {
             save_settings_only(state).await;
         });
     };
 
-    let on_apply = move |_| {
+    let on_preview = move |_| {
+        let preview = state.read().preview_apply();
+        let mut write_state = state.write();
+        write_state.clear_message();
+        match preview {
+            Ok(steps) => write_state.preview_steps = Some(steps),
+            Err(e) => write_state.set_message(Message::error(e)),
+        }
+    };
+
+    let on_cleanup_doh = move |_| {
         spawn(async move {
-            apply_dns_settings(state).await;
+            cleanup_doh_servers(state).await;
         });
     };
 
-    let show_delete_confirm = state.read().show_delete_confirm;
-    let profile_name_for_dialog = state.read().current_profile_name.clone();
+    let on_restart_elevated = move |_| {
+        let selection = state.read().relaunch_selection();
+        match relaunch_as_admin(&selection) {
+            Ok(()) => window().close(),
+            Err(e) => state.write().set_message(Message::error(e.to_string())),
+        }
+    };
 
-    rsx! {
-        style { {include_str!("../assets/main.css")} }
+    let on_check_update = move |_| {
+        spawn(async move {
+            check_for_updates(state).await;
+        });
+    };
 
-        if show_delete_confirm {
-            DeleteConfirmDialog {
-                profile_name: profile_name_for_dialog,
-                on_confirm: on_confirm_delete,
-                on_cancel: on_cancel_delete,
-            }
-        }
+    let on_download_update = move |_| {
+        spawn(async move {
+            download_release(state).await;
+        });
+    };
 
-        div { class: "app-container",
-            div { class: "content",
-                NetworkSelector {
-                    state: state,
-                    on_change: on_interface_change
-                }
-                DnsInput {
-                    state: state,
-                    on_settings_change: on_settings_change,
-                    on_mode_change: on_mode_change,
-                    on_profile_change: on_profile_change,
-                    on_new_profile: on_new_profile,
-                    on_profile_name_change: on_profile_name_change,
-                    on_delete_profile: on_delete_profile,
-                }
-                ActionButtons {
-                    state: state,
-                    on_save: on_save,
-                    on_apply: on_apply,
-                }
-            }
-            StatusBar { state: state }
+    let on_install_update = move |_| {
+        let (package, selection) = {
+            let read_state = state.read();
+            let Some(UpdateStatus::Ready(_, package)) = &read_state.update_status else {
+                return;
+            };
+            (package.clone(), read_state.relaunch_selection())
+        };
+        match install_update(&package, &selection.to_args()) {
+            Ok(()) => window().close(),
+            Err(e) => state.write().set_message(Message::error(e.to_string())),
         }
-    }
-}
+    };
 
-async fn initialize_app(mut state: Signal<AppState>) {
-    state.write().clear_message();
+    let on_restore_original = move |_| {
+        spawn(async move {
+            restore_original_dns(state).await;
+        });
+    };
 
-    match load_config() {
-        Ok(config) => {
-            let first_id = config.sorted_profiles().first().map(|p| p.id.clone());
-            let mut st = state.write();
-            st.config = config;
-            if let Some(id) = first_id {
-                st.select_profile(&id);
-            }
+    let on_reset_all = move |_| {
+        spawn(async move {
+            reset_all_dns(state).await;
+        });
+    };
+
+    let on_flush_cache = move |_| {
+        spawn(async move {
+            flush_dns_cache(state).await;
+        });
+    };
+
+    let on_export_state = move |_| {
+        spawn(async move {
+            export_state_report(state).await;
+        });
+    };
+
+    let on_startup_change = move |setting: StartupSetting| match set_startup_setting(setting) {
+        Ok(()) => state.write().startup = setting,
+        Err(e) => state.write().set_message(Message::error(e.to_string())),
+    };
+
+    let on_link_handler_change =
+        move |registered: bool| match set_link_handler_registered(registered) {
+            Ok(()) => state.write().link_handler = registered,
+            Err(e) => state.write().set_message(Message::error(e.to_string())),
+        };
+
+    let on_logon_task_change = move |profile_id: Option<String>| {
+        spawn(async move {
+            change_logon_task(state, profile_id).await;
+        });
+    };
+
+    let on_app_settings_change = move |settings: AppSettings| {
+        if settings.language != state.read().config.settings.language {
+            set_language(settings.language());
         }
-        Err(e) => {
+        set_command_trace(settings.traces_commands());
+        let backend = state.read().backend.with_cim(settings.uses_cim_backend());
+        state.write().backend = backend;
+        let relist = settings.shows_disconnected_interfaces()
+            != state.read().config.settings.shows_disconnected_interfaces();
+        state.write().config.settings = settings;
+        if relist && !refresh_interfaces(state) {
+            spawn(async move {
+                refresh_current_dns(state).await;
+            });
+        }
+        let config = state.read().config.clone();
+        if let Err(e) = save_config(&config) {
             state
                 .write()
-                .set_message(Message::error(format!("Failed to load config: {}", e)));
+                .set_message(Message::error(t!("save-config-failed", error = e)));
         }
-    }
-
-    match get_network_interfaces() {
-        Ok(interfaces) => {
-            if interfaces.is_empty() {
-                state
-                    .write()
-                    .set_message(Message::error("No network interfaces found"));
-                return;
-            }
-            {
-                let mut write_state = state.write();
-                write_state.interfaces = interfaces;
-                write_state.selected_interface_index = 0;
-            }
+    };
 
-            refresh_current_dns(state).await;
-        }
-        Err(e) => {
-            state.write().set_message(Message::error(format!(
-                "Failed to get network interfaces: {}",
-                e
-            )));
+    let on_interface_hidden_change = move |(guid, hidden): (String, bool)| {
+        state.write().set_interface_hidden(&guid, hidden);
+        let config = state.read().config.clone();
+        if let Err(e) = save_config(&config) {
+            state
+                .write()
+                .set_message(Message::error(t!("save-config-failed", error = e)));
         }
-    }
-}
-
-async fn change_interface(mut state: Signal<AppState>, index: usize) {
-    {
-        let mut write_state = state.write();
-        write_state.selected_interface_index = index;
-        write_state.clear_message();
-    }
-
-    refresh_current_dns(state).await;
-}
-
-fn change_dns_mode(mut state: Signal<AppState>, mode: DnsMode) {
-    let old_mode = state.read().dns_mode;
+    };
 
-    if old_mode == mode {
-        return;
-    }
+    let on_interface_alias_change = move |(guid, alias): (String, String)| {
+        state.write().set_interface_alias(&guid, &alias);
+        let config = state.read().config.clone();
+        if let Err(e) = save_config(&config) {
+            state
+                .write()
+                .set_message(Message::error(t!("save-config-failed", error = e)));
+        }
+    };
 
-    let mut write_state = state.write();
-    write_state.dns_mode = mode;
-    write_state.clear_message();
+    let on_open_settings = move |_| {
+        state.write().show_settings = true;
+    };
 
-    if mode == DnsMode::Manual && write_state.config.profiles.is_empty() {
-        write_state.create_new_profile();
-    } else if mode == DnsMode::Manual
-        && write_state.selected_profile_id.is_none()
-        && let Some(first) = write_state.config.sorted_profiles().first()
-    {
-        let first_id = first.id.clone();
-        drop(write_state);
-        state.write().select_profile(&first_id);
-    }
-}
+    let on_close_settings = move |_| {
+        state.write().show_settings = false;
+    };
 
-async fn refresh_current_dns(mut state: Signal<AppState>) {
-    let interface_index = {
-        let read_state = state.read();
-        read_state.selected_interface().map(|i| i.interface_index)
+    let on_open_backups = move |_| match list_config_backups() {
+        Ok(backups) => {
+            let mut write_state = state.write();
+            write_state.show_settings = false;
+            write_state.config_backups = Some(backups);
+        }
+        Err(e) => state
+            .write()
+            .set_message(Message::error(t!("list-backups-failed", error = e))),
     };
 
-    if let Some(index) = interface_index {
-        match get_current_dns(index).await {
-            Ok(dns_state) => {
-                state.write().current_dns_state = dns_state;
+    let on_config_format_change = move |format: ConfigFormat| {
+        let result = set_config_format(&state.read().config, format);
+        let mut write_state = state.write();
+        match result {
+            Ok(path) => {
+                write_state.config_format = format;
+                write_state.set_message(Message::success(t!(
+                    "config-format-changed",
+                    path = path.display()
+                )));
             }
             Err(e) => {
-                eprintln!("Failed to refresh DNS state: {}", e);
+                write_state.set_message(Message::error(t!("config-format-failed", error = e)))
             }
         }
-    }
-}
+    };
 
-async fn save_settings_only(mut state: Signal<AppState>) {
-    let validation_result = {
-        let read_state = state.read();
-        if read_state.dns_mode == DnsMode::Manual {
-            read_state.validate_current_settings()
-        } else {
-            Ok(())
-        }
+    let on_move_config = move |_| {
+        spawn(async move {
+            let Some(folder) = rfd::AsyncFileDialog::new().pick_folder().await else {
+                return;
+            };
+            change_config_dir(state, Some(folder.path()));
+        });
     };
 
-    if let Err(e) = validation_result {
+    let on_reset_config_location = move |_| {
+        change_config_dir(state, None);
+    };
+
+    let on_restore_backup = move |path: PathBuf| {
+        restore_backup(state, &path);
+    };
+
+    let on_close_backups = move |_| {
+        state.write().config_backups = None;
+    };
+
+    let on_run_leak_test = move |_| {
+        spawn(async move {
+            run_dns_leak_test(state).await;
+        });
+    };
+
+    let on_check_dnssec = move |_| {
+        spawn(async move {
+            check_dnssec_validation(state).await;
+        });
+    };
+
+    let on_lookup_change = move |query: LookupQuery| {
+        state.write().lookup_query = query;
+    };
+
+    let on_run_lookup = move |_| {
+        spawn(async move {
+            run_lookup(state).await;
+        });
+    };
+
+    let on_network_rules_change = move |rules: NetworkRules| {
+        state.write().network_rules_draft = Some(rules);
+    };
+
+    let on_save_network_rules = move |_| {
+        spawn(async move {
+            save_network_rules(state).await;
+        });
+    };
+
+    let on_schedule_change = move |schedule: Schedule| {
+        state.write().schedule_draft = Some(schedule);
+    };
+
+    let on_save_schedule = move |_| {
+        state.write().clear_message();
+        let result = state.write().save_schedule();
+        let message = match result {
+            Ok(()) => {
+                let config = state.read().config.clone();
+                match save_config(&config) {
+                    Ok(()) => Message::success(t!("schedule-saved")),
+                    Err(e) => Message::error(t!("save-config-failed", error = e)),
+                }
+            }
+            Err(e) => Message::error(e),
+        };
+        state.write().set_message(message);
+    };
+
+    let on_hotkey_change = move |(slot, profile_id): (u8, Option<String>)| {
+        {
+            let mut write_state = state.write();
+            match profile_id {
+                Some(id) => write_state.config.hotkeys.insert(slot, id),
+                None => write_state.config.hotkeys.remove(&slot),
+            };
+        }
+        let config = state.read().config.clone();
+        if let Err(e) = save_config(&config) {
+            state
+                .write()
+                .set_message(Message::error(t!("save-config-failed", error = e)));
+        }
+        restart_hotkeys();
+    };
+
+    let on_refresh_interfaces = move |_| {
+        spawn(async move {
+            reload_interfaces(state).await;
+        });
+    };
+
+    let on_refresh_nrpt = move |_| {
+        spawn(async move {
+            refresh_nrpt_rules(state).await;
+        });
+    };
+
+    let on_delete_nrpt_rule = move |name: String| {
+        spawn(async move {
+            delete_nrpt_rule(state, name).await;
+        });
+    };
+
+    let on_close_preview = move |_| {
+        state.write().preview_steps = None;
+    };
+
+    let on_apply = move |_| {
+        let task = spawn(async move {
+            apply_dns_settings(state, None).await;
+            apply_task.set(None);
+        });
+        apply_task.set(Some(task));
+    };
+
+    let on_apply_for = move |minutes: u64| {
+        let task = spawn(async move {
+            apply_dns_settings(state, Some(minutes)).await;
+            apply_task.set(None);
+        });
+        apply_task.set(Some(task));
+    };
+
+    let on_keep_temporary = move |_| {
+        keep_temporary_apply(state);
+    };
+
+    let on_revert_temporary = move |_| {
+        let guid = state.read().selected_interface_guid.clone();
+        if let Some(guid) = guid {
+            spawn(async move {
+                revert_temporary_apply(state, guid).await;
+            });
+        }
+    };
+
+    let on_cancel_apply = move |_| {
+        let Some(task) = apply_task.take() else {
+            return;
+        };
+        // Dropping the apply future kills the running command.
+        task.cancel();
+        {
+            let mut write_state = state.write();
+            write_state.set_loading(false);
+            write_state.set_message(Message::warning(t!("apply-cancelled")));
+        }
+        spawn(async move {
+            refresh_current_dns(state).await;
+        });
+    };
+
+    let show_delete_confirm = state.read().show_delete_confirm;
+    let show_discard_confirm = state.read().show_discard_confirm;
+    let show_settings = state.read().show_settings;
+    let profile_name_for_dialog = state.read().current_profile_name.clone();
+    let preview_steps = state.read().preview_steps.clone();
+    let config_backups = state.read().config_backups.clone();
+    let is_elevated = state.read().is_elevated;
+    let supports_nrpt = state.read().backend.supports_nrpt();
+    let language = state.read().config.settings.language();
+    let theme_class = if is_dark_theme() {
+        "theme-dark"
+    } else {
+        "theme-light"
+    };
+
+    rsx! {
+        style { {include_str!("../assets/main.css")} }
+
+        // Keyed by language so that every component, including those that
+        // do not read the state, is rendered again in the new language.
+        for language in std::iter::once(language) {
+            div { key: "{language.code()}", class: "theme-root {theme_class}",
+                if show_delete_confirm {
+                    DeleteConfirmDialog {
+                        profile_name: profile_name_for_dialog.clone(),
+                        on_confirm: on_confirm_delete,
+                        on_cancel: on_cancel_delete,
+                    }
+                }
+
+                if show_discard_confirm {
+                    DiscardChangesDialog {
+                        profile_name: profile_name_for_dialog.clone(),
+                        on_confirm: on_confirm_discard,
+                        on_cancel: on_cancel_discard,
+                    }
+                }
+
+                if show_settings {
+                    SettingsDialog {
+                        state: state,
+                        on_change: on_app_settings_change,
+                        on_startup_change: on_startup_change,
+                        on_link_handler_change: on_link_handler_change,
+                        on_logon_task_change: on_logon_task_change,
+                        on_check_update: on_check_update,
+                        on_download_update: on_download_update,
+                        on_install_update: on_install_update,
+                        on_config_format_change: on_config_format_change,
+                        on_move_config: on_move_config,
+                        on_reset_config_location: on_reset_config_location,
+                        on_open_backups: on_open_backups,
+                        on_close: on_close_settings,
+                    }
+                }
+
+                if let Some(backups) = config_backups.clone() {
+                    RestoreBackupDialog {
+                        backups: backups,
+                        on_restore: on_restore_backup,
+                        on_close: on_close_backups,
+                    }
+                }
+
+                if let Some(steps) = preview_steps.clone() {
+                    PreviewDialog {
+                        steps: steps,
+                        on_close: on_close_preview,
+                    }
+                }
+
+                div { class: "app-container",
+                    if !is_elevated {
+                        ElevationBanner { on_restart: on_restart_elevated }
+                    }
+                    div { class: "content",
+                        NetworkSelector {
+                            state: state,
+                            on_change: on_interface_change,
+                            on_settings_change: on_app_settings_change,
+                            on_hidden_change: on_interface_hidden_change,
+                            on_alias_change: on_interface_alias_change,
+                            on_refresh: on_refresh_interfaces,
+                        }
+                        DnsInput {
+                            state: state,
+                            on_settings_change: on_settings_change,
+                            on_provider_change: on_provider_change,
+                            on_provider_draft_change: on_provider_draft_change,
+                            on_save_provider: on_save_provider,
+                            on_delete_provider: on_delete_provider,
+                            on_import_doh_providers: on_import_doh_providers,
+                            on_discover_doh: on_discover_doh,
+                            on_resolve_host: on_resolve_server_host,
+                            on_use_resolved_host: on_use_resolved_host,
+                            on_copy_servers: on_copy_profile_servers,
+                            on_mode_change: on_mode_change,
+                            on_profile_change: on_profile_change,
+                            on_new_profile: on_new_profile,
+                            on_profile_name_change: on_profile_name_change,
+                            on_profile_tags_change: on_profile_tags_change,
+                            on_profile_tag_filter: on_profile_tag_filter,
+                            on_profile_search: on_profile_search,
+                            on_profile_color_change: on_profile_color_change,
+                            on_delete_profile: on_delete_profile,
+                            on_copy_profile: on_copy_profile,
+                            on_paste_profile: on_paste_profile,
+                            on_export_profiles: on_export_profiles,
+                            on_import_profiles: on_import_profiles,
+                        }
+                        ActionButtons {
+                            state: state,
+                            on_save: on_save,
+                            on_cleanup_doh: on_cleanup_doh,
+                            on_restore_original: on_restore_original,
+                            on_reset_all: on_reset_all,
+                            on_flush_cache: on_flush_cache,
+                            on_export_state: on_export_state,
+                            on_open_settings: on_open_settings,
+                            on_preview: on_preview,
+                            on_apply: on_apply,
+                            on_apply_for: on_apply_for,
+                            on_cancel: on_cancel_apply,
+                        }
+                        if supports_nrpt {
+                            NrptRuleList {
+                                state: state,
+                                on_refresh: on_refresh_nrpt,
+                                on_delete: on_delete_nrpt_rule,
+                            }
+                        }
+                        LookupTool {
+                            state: state,
+                            on_change: on_lookup_change,
+                            on_run: on_run_lookup,
+                        }
+                        NetworkRulesPanel {
+                            state: state,
+                            on_change: on_network_rules_change,
+                            on_save: on_save_network_rules,
+                        }
+                        HotkeysPanel { state: state, on_change: on_hotkey_change }
+                        SchedulePanel {
+                            state: state,
+                            on_change: on_schedule_change,
+                            on_save: on_save_schedule,
+                        }
+                        DnssecPanel { state: state, on_check: on_check_dnssec }
+                        LeakTestPanel { state: state, on_run: on_run_leak_test }
+                        HistoryPanel { state: state, on_revert: on_revert_history }
+                        ActivityPanel { state: state }
+                    }
+                    StatusBar {
+                        state: state,
+                        on_copy: on_copy_current_dns,
+                        on_keep_temporary: on_keep_temporary,
+                        on_revert_temporary: on_revert_temporary,
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn initialize_app(mut state: Signal<AppState>) {
+    {
+        let mut write_state = state.write();
+        write_state.clear_message();
+        write_state.is_elevated = is_elevated();
+    }
+    let mut selection = RelaunchSelection::from_args(std::env::args().skip(1));
+
+    if let Err(e) = backup_config() {
+        eprintln!("Failed to back up the config: {}", e);
+    }
+    {
+        let mut write_state = state.write();
+        write_state.config_format = config_format();
+        write_state.config_location = config_location().ok();
+    }
+    match load_config_with_skipped() {
+        Ok(loaded) => {
+            let config = loaded.config;
+            if let Some(secs) = config.command_timeout_secs {
+                set_command_timeout(secs);
+            }
+            set_command_trace(config.settings.traces_commands());
+            let first_id = config.sorted_profiles().first().map(|p| p.id.clone());
+            let mut st = state.write();
+            st.config = config;
+            if let Some(id) = first_id {
+                st.select_profile(&id);
+            }
+            for skipped in &loaded.skipped {
+                let message = skipped_profile_message(skipped);
+                eprintln!("{}", message);
+                st.set_message(Message::warning(message));
+            }
+            if !loaded.skipped.is_empty() {
+                st.set_message(Message::warning(t!(
+                    "profiles-skipped",
+                    count = loaded.skipped.len()
+                )));
+            }
+        }
+        Err(e) => {
+            state
+                .write()
+                .set_message(Message::error(t!("load-config-failed", error = e)));
+        }
+    }
+
+    // A selection handed over on restart wins over the one saved on exit.
+    if selection == RelaunchSelection::default() {
+        selection = state
+            .read()
+            .config
+            .last_selection
+            .clone()
+            .unwrap_or_default();
+    }
+
+    {
+        let mut write_state = state.write();
+        write_state.startup = startup_setting();
+        write_state.link_handler = link_handler_registered();
+    }
+    let logon_task = match logon_task_profile().await {
+        Ok(profile_id) => profile_id,
+        Err(e) => {
+            eprintln!("Failed to read the logon task: {}", e);
+            None
+        }
+    };
+    state.write().logon_task = Some(logon_task);
+    match load_history() {
+        Ok(history) => state.write().history = history.into(),
+        Err(e) => eprintln!("Failed to read the history: {}", e),
+    }
+    state.write().dns_policies = detect_dns_policies();
+    let policy_warning = state.read().policy_warning();
+    if let Some(warning) = policy_warning {
+        state.write().set_message(Message::warning(warning));
+    }
+
+    let use_cim = state.read().config.settings.uses_cim_backend();
+    let backend = SystemBackend::detect().await.with_cim(use_cim);
+    state.write().backend = backend;
+    if backend.supports_nrpt() {
+        refresh_nrpt_rules(state).await;
+    }
+    if backend.supports_doh() {
+        state.write().capabilities = Capabilities::detect().await;
+        let known = if state.peek().capabilities.doh {
+            backend.get_doh_templates().await
+        } else {
+            Ok(Vec::new())
+        };
+        match known {
+            Ok(servers) => state.write().set_known_doh_templates(servers),
+            Err(e) => eprintln!("Failed to read the known DoH servers: {}", e),
+        }
+    } else {
+        state
+            .write()
+            .set_message(Message::warning(t!("powershell-unavailable")));
+    }
+
+    let include_disconnected = state.read().config.settings.shows_disconnected_interfaces();
+    match get_network_interfaces(include_disconnected) {
+        Ok(interfaces) => {
+            if interfaces.is_empty() {
+                state
+                    .write()
+                    .set_message(Message::error(t!("no-interfaces")));
+                return;
+            }
+            {
+                let mut write_state = state.write();
+                write_state.set_interfaces(interfaces);
+                write_state.restore_interface_profile();
+                write_state.restore_selection(&selection);
+            }
+
+            refresh_current_dns(state).await;
+        }
+        Err(e) => {
+            state
+                .write()
+                .set_message(Message::error(t!("get-interfaces-failed", error = e)));
+        }
+    }
+}
+
+/// Saves where the window is, unless that is already in the config.
+fn save_window_placement(mut state: Signal<AppState>, restored: Option<WindowState>) {
+    let restored = restored.or_else(|| state.peek().config.window.clone());
+    let Some(window_state) = capture_window_state(&window().window, restored.as_ref()) else {
+        return;
+    };
+    if state.peek().config.window.as_ref() == Some(&window_state) {
+        return;
+    }
+
+    state.write().config.window = Some(window_state.clone());
+    if let Err(e) = update_config(|config| config.window = Some(window_state)) {
+        eprintln!("Failed to save window state: {}", e);
+    }
+}
+
+fn skipped_profile_message(skipped: &SkippedProfile) -> String {
+    let profile = match &skipped.name {
+        Some(name) => format!("\"{}\"", name),
+        None => format!("#{}", skipped.position),
+    };
+    match skipped.line {
+        Some(line) => t!(
+            "profile-skipped-at-line",
+            profile = profile,
+            line = line,
+            reason = &skipped.reason
+        ),
+        None => t!(
+            "profile-skipped",
+            profile = profile,
+            reason = &skipped.reason
+        ),
+    }
+}
+
+async fn change_interface(mut state: Signal<AppState>, guid: String) {
+    state.write().clear_message();
+
+    if refresh_interfaces(state) {
+        if state.write().select_interface(&guid) {
+            state.write().restore_interface_profile();
+        } else {
+            state
+                .write()
+                .set_message(Message::warning(t!("interface-unavailable")));
+        }
+    }
+
+    refresh_current_dns(state).await;
+}
+
+/// Reloads the interface list so that adapters added or removed while the
+/// app is open are picked up. Returns false, after reporting it, when the
+/// selected adapter has disappeared.
+fn refresh_interfaces(state: Signal<AppState>) -> bool {
+    let include_disconnected = state.read().config.settings.shows_disconnected_interfaces();
+    match get_network_interfaces(include_disconnected) {
+        Ok(interfaces) => update_interfaces(state, interfaces),
+        Err(e) => {
+            eprintln!("Failed to refresh network interfaces: {}", e);
+            true
+        }
+    }
+}
+
+/// Reloads the interface list on request, keeping the selected adapter if
+/// it is still there.
+async fn reload_interfaces(mut state: Signal<AppState>) {
+    state.write().clear_message();
+    refresh_interfaces(state);
+    refresh_current_dns(state).await;
+}
+
+/// Refreshes the interface list whenever an adapter appears, goes away, or
+/// gains or loses its addresses.
+async fn watch_interfaces(state: Signal<AppState>) {
+    let watcher = InterfaceWatcher::new();
+    loop {
+        watcher.changed().await;
+
+        let include_disconnected = state.read().config.settings.shows_disconnected_interfaces();
+        let Ok(interfaces) = get_network_interfaces(include_disconnected) else {
+            continue;
+        };
+        if state.read().is_loading || interfaces == state.read().interfaces {
+            continue;
+        }
+
+        update_interfaces(state, interfaces);
+        refresh_current_dns(state).await;
+    }
+}
+
+/// Applies network rules whenever an interface joins a different network.
+async fn watch_network_rules(state: Signal<AppState>) {
+    let watcher = InterfaceWatcher::new();
+    loop {
+        run_network_rules(state, false).await;
+        watcher.changed().await;
+    }
+}
+
+/// Detects the network of every interface and applies the matching rule to
+/// those on a different network than last time, or to all of them with
+/// `all_interfaces`.
+async fn run_network_rules(mut state: Signal<AppState>, all_interfaces: bool) {
+    if !state.read().config.network_rules.enabled {
+        return;
+    }
+
+    // An adapter without a link is on no network to match.
+    let interfaces: Vec<NetworkInterface> = state
+        .read()
+        .interfaces
+        .iter()
+        .filter(|i| !i.is_disconnected)
+        .cloned()
+        .collect();
+    for interface in interfaces {
+        let network = detect_network(&interface).await;
+        let previous = state
+            .write()
+            .networks
+            .insert(interface.interface_guid.clone(), network.clone());
+        if all_interfaces || previous.as_ref() != Some(&network) {
+            apply_network_rule(state, &interface, &network).await;
+        }
+    }
+}
+
+/// Applies the profile the network rules pick for `network` to
+/// `interface`.
+async fn apply_network_rule(
+    state: Signal<AppState>,
+    interface: &NetworkInterface,
+    network: &NetworkInfo,
+) {
+    let rules = state.read().config.network_rules.clone();
+    if !rules.enabled || !network.is_connected() {
+        return;
+    }
+    let rule = rules.matching_rule(network).cloned();
+    let target = rule.as_ref().and_then(|rule| rule.profile_id.clone());
+    let message =
+        apply_profile_automatically(state, interface, target.clone(), &t!("source-network-rule"))
+            .await;
+    if message.is_none_or(|message| message.level == MessageLevel::Error) {
+        return;
+    }
+
+    let undo = state
+        .read()
+        .history
+        .back()
+        .filter(|entry| {
+            entry.interface_guid == interface.interface_guid
+                && entry.error.is_none()
+                && entry.before.is_some()
+        })
+        .cloned();
+    state.write().rule_undo = undo;
+    if state.read().config.settings.notifies_network_rules() {
+        notify_network_rule(state, interface, rule.as_ref(), target).await;
+    }
+}
+
+/// Shows a Windows notification saying which network rule switched
+/// `interface` to `target`, with a button to undo it when windns:// links
+/// open in windns.
+async fn notify_network_rule(
+    state: Signal<AppState>,
+    interface: &NetworkInterface,
+    rule: Option<&NetworkRule>,
+    target: Option<String>,
+) {
+    let (text, link_handler) = {
+        let read_state = state.read();
+        let interface = read_state.config.interface_name(interface);
+        let profile = target
+            .as_deref()
+            .and_then(|id| read_state.config.find_profile(id))
+            .map_or_else(|| t!("automatic"), |profile| profile.name.clone());
+        let text = match rule {
+            Some(rule) => t!(
+                "rule-notification-matched",
+                interface = interface,
+                condition = rule.condition.label(),
+                value = rule.condition.value(),
+                profile = profile
+            ),
+            None => t!(
+                "rule-notification-other",
+                interface = interface,
+                profile = profile
+            ),
+        };
+        (text, read_state.link_handler)
+    };
+    let action = link_handler.then(|| NotificationAction {
+        label: t!("undo-rule"),
+        link: UNDO_RULE_LINK.to_string(),
+    });
+    if let Err(e) = show_notification(&t!("rule-notification-title"), &text, action.as_ref()).await
+    {
+        eprintln!("Failed to show the notification: {}", e);
+    }
+}
+
+/// Puts back the settings the interface had before a network rule last
+/// switched it.
+async fn undo_network_rule(mut state: Signal<AppState>) {
+    let undo = state.write().rule_undo.take();
+    match undo {
+        Some(entry) => revert_history_entry(state, entry).await,
+        None => state
+            .write()
+            .set_message(Message::warning(t!("nothing-to-undo"))),
+    }
+}
+
+/// Applies the profile `target` (Automatic for `None`) to `interface` on
+/// behalf of `source`, unless it is already the one last applied there.
+async fn apply_profile_automatically(
+    mut state: Signal<AppState>,
+    interface: &NetworkInterface,
+    target: Option<String>,
+    source: &str,
+) -> Option<Message> {
+    let (settings, target_name) = {
+        let read_state = state.read();
+        if !read_state.is_elevated || read_state.is_loading {
+            return None;
+        }
+
+        let last_applied = read_state
+            .config
+            .interface_profiles
+            .get(&interface.interface_guid);
+        if last_applied == Some(&target) {
+            return None;
+        }
+
+        match &target {
+            Some(id) => match read_state.config.find_profile(id) {
+                Some(profile) => (profile.settings.clone(), profile.name.clone()),
+                None => return None,
+            },
+            None => (DnsSettings::new(), t!("automatic")),
+        }
+    };
+
+    state.write().set_loading(true);
+    let target_interface = interface.clone();
+    let result = queued(state, &interface.interface_guid, async move {
+        apply_settings_to_interface(state, &target_interface, &settings, false).await
+    })
+    .await;
+    state.write().set_loading(false);
+    let result = result?;
+
+    let message = match result {
+        Ok(warning) => {
+            state
+                .write()
+                .config
+                .interface_profiles
+                .insert(interface.interface_guid.clone(), target);
+            let config = state.read().config.clone();
+            let applied = t!(
+                "auto-applied",
+                source = source,
+                profile = target_name,
+                interface = config.interface_name(&interface)
+            );
+
+            if let Err(e) = save_config(&config) {
+                Message::error(t!("applied-save-failed", applied = applied, error = e))
+            } else if let Some(warn_msg) = warning {
+                Message::warning(format!("{}. {}", applied, warn_msg))
+            } else {
+                Message::success(applied)
+            }
+        }
+        Err(e) => Message::error(t!(
+            "auto-apply-failed",
+            profile = target_name,
+            interface = state.read().config.interface_name(&interface),
+            error = e
+        )),
+    };
+    state.write().set_message(message.clone());
+
+    let is_selected = state
+        .read()
+        .selected_interface()
+        .is_some_and(|i| i.interface_guid == interface.interface_guid);
+    if is_selected {
+        state.write().restore_interface_profile();
+        refresh_current_dns(state).await;
+    }
+    Some(message)
+}
+
+/// Applies a profile picked from the tray menu, or Automatic for `None`,
+/// to the selected interface.
+async fn apply_from_tray(state: Signal<AppState>, target: Option<String>) {
+    let interface = state.read().selected_interface().cloned();
+    if let Some(interface) = interface {
+        apply_profile_automatically(state, &interface, target, &t!("source-tray-menu")).await;
+    }
+}
+
+/// Answers requests from other programs through the control pipe.
+async fn serve_control_requests(mut state: Signal<AppState>) {
+    let mut server = match ControlServer::start() {
+        Ok(server) => server,
+        Err(e) => {
+            state.write().set_message(Message::error(e.to_string()));
+            return;
+        }
+    };
+
+    while let Some(call) = server.next_call().await {
+        let response = match &call.request {
+            ControlRequest::List => ControlResponse::Profiles(state.read().control_profiles()),
+            ControlRequest::Status => ControlResponse::Status(state.read().control_status()),
+            ControlRequest::Apply { profile } => {
+                apply_from_control(state, profile.as_deref()).await
+            }
+        };
+        call.respond(response);
+    }
+}
+
+/// Applies a profile named by a control request, or Automatic for `None`,
+/// to the selected interface.
+async fn apply_from_control(state: Signal<AppState>, profile: Option<&str>) -> ControlResponse {
+    let (interface, target) = {
+        let read_state = state.read();
+        if !read_state.is_elevated {
+            return ControlResponse::Error(t!("control-not-elevated"));
+        }
+        if read_state.is_loading {
+            return ControlResponse::Error(t!("control-busy"));
+        }
+        let Some(interface) = read_state.selected_interface().cloned() else {
+            return ControlResponse::Error(t!("control-no-interface"));
+        };
+        let target = match profile {
+            Some(name) => match read_state.config.find_profile_by_name_or_id(name) {
+                Some(profile) => Some(profile.id.clone()),
+                None => return ControlResponse::Error(t!("link-profile-not-found", name = name)),
+            },
+            None => None,
+        };
+        (interface, target)
+    };
+
+    match apply_profile_automatically(state, &interface, target, &t!("source-control")).await {
+        Some(message) if message.level == MessageLevel::Error => {
+            ControlResponse::Error(message.text)
+        }
+        Some(message) => ControlResponse::Applied(message.text),
+        None => ControlResponse::Applied(t!("control-already-applied")),
+    }
+}
+
+/// Applies the profiles `windns://` links ask for: the one windns was
+/// started with, then those handed over by instances started for later
+/// links.
+async fn watch_links(state: Signal<AppState>) {
+    if let Some(link) = link_argument(std::env::args().skip(1)) {
+        apply_link(state, &link).await;
+    }
+
+    let mut listener = LinkListener::start();
+    while let Some(link) = listener.opened().await {
+        apply_link(state, &link).await;
+    }
+}
+
+async fn apply_link(mut state: Signal<AppState>, link: &str) {
+    if is_undo_rule_link(link) {
+        undo_network_rule(state).await;
+        return;
+    }
+    let Some(target) = parse_link(link) else {
+        state
+            .write()
+            .set_message(Message::warning(t!("invalid-link", link = link)));
+        return;
+    };
+    let target = match target {
+        Some(name) => {
+            let profile_id = state
+                .read()
+                .config
+                .find_profile_by_name_or_id(&name)
+                .map(|p| p.id.clone());
+            if profile_id.is_none() {
+                state
+                    .write()
+                    .set_message(Message::warning(t!("link-profile-not-found", name = name)));
+                return;
+            }
+            profile_id
+        }
+        None => None,
+    };
+
+    let interface = state.read().selected_interface().cloned();
+    if let Some(interface) = interface {
+        apply_profile_automatically(state, &interface, target, &t!("source-link")).await;
+    }
+}
+
+/// Applies the profile assigned to a hotkey to the selected interface when
+/// the hotkey is pressed, even while the window is in the background.
+async fn watch_hotkeys(mut state: Signal<AppState>) {
+    let slots: Vec<u8> = state.read().config.hotkeys.keys().copied().collect();
+    if slots.is_empty() {
+        return;
+    }
+
+    let (mut listener, failed) = HotkeyListener::new(&slots);
+    if !failed.is_empty() {
+        let labels: Vec<String> = failed.into_iter().map(hotkey_label).collect();
+        state.write().set_message(Message::warning(t!(
+            "hotkeys-unavailable",
+            hotkeys = labels.join(", ")
+        )));
+    }
+
+    while let Some(slot) = listener.pressed().await {
+        let profile_id = state.read().config.hotkeys.get(&slot).cloned();
+        let interface = state.read().selected_interface().cloned();
+        if let (Some(profile_id), Some(interface)) = (profile_id, interface) {
+            let source = hotkey_label(slot);
+            apply_profile_automatically(state, &interface, Some(profile_id), &source).await;
+        }
+    }
+}
+
+/// Applies the scheduled profile to the selected interface whenever the
+/// schedule calls for a different one.
+async fn watch_schedule(state: Signal<AppState>) {
+    let mut last_target: Option<Option<String>> = None;
+    loop {
+        let schedule = state.read().config.schedule.clone();
+        let interface = state.read().selected_interface().cloned();
+        match interface {
+            Some(interface) if schedule.enabled => {
+                let (day, time) = local_time();
+                let target = schedule.target_profile(day, time);
+                if last_target.as_ref() != Some(&target) {
+                    last_target = Some(target.clone());
+                    apply_profile_automatically(state, &interface, target, &t!("source-schedule"))
+                        .await;
+                }
+            }
+            _ => last_target = None,
+        }
+
+        tokio::time::sleep(SCHEDULE_CHECK_INTERVAL).await;
+    }
+}
+
+/// Keeps the System theme in step with the Windows dark mode setting.
+async fn watch_system_theme(mut state: Signal<AppState>) {
+    loop {
+        let dark = system_prefers_dark();
+        if state.read().system_dark_mode != dark {
+            state.write().system_dark_mode = dark;
+        }
+        tokio::time::sleep(THEME_CHECK_INTERVAL).await;
+    }
+}
+
+/// Picks up DNS server changes made outside windns, e.g. by a DHCP renewal,
+/// a VPN client, or group policy. The adapter's servers are polled cheaply
+/// and the full state is only re-read when they change.
+async fn watch_current_dns(mut state: Signal<AppState>) {
+    let mut last_seen: Option<(String, Vec<String>)> = None;
+    loop {
+        let interval = state.read().config.settings.dns_refresh_interval();
+        tokio::time::sleep(interval).await;
+
+        let Some(interface) = state.read().selected_interface().cloned() else {
+            continue;
+        };
+        let generation = state.read().selection_generation();
+        let Ok(servers) = get_dns_server_addresses(&interface.interface_guid) else {
+            continue;
+        };
+
+        let seen = (interface.interface_guid.clone(), servers);
+        let is_baseline = last_seen
+            .as_ref()
+            .is_none_or(|(guid, _)| guid != &interface.interface_guid);
+        if last_seen.as_ref() == Some(&seen) {
+            continue;
+        }
+        last_seen = Some(seen);
+        if is_baseline || state.read().is_loading {
+            continue;
+        }
+
+        let backend = state.read().backend;
+        let queried = interface.clone();
+        let dns_state = queued(state, &interface.interface_guid, async move {
+            backend.get_current_dns(&queried).await
+        })
+        .await;
+        let Some(Ok(dns_state)) = dns_state else {
+            continue;
+        };
+        if !state.read().is_current_selection(generation) || state.read().is_loading {
+            continue;
+        }
+
+        let changed = state.write().observe_current_dns(dns_state);
+        if changed && state.read().config.settings.warns_on_external_dns_change() {
+            let message = external_change_message(&state.read(), &interface);
+            state.write().set_message(Message::warning(message));
+        }
+    }
+}
+
+/// Warning about DNS servers changed outside windns, listing the new ones
+/// when they undo what windns applied.
+fn external_change_message(state: &AppState, interface: &NetworkInterface) -> String {
+    if !state.applied_dns_overridden() {
+        return t!(
+            "dns-changed-externally",
+            interface = state.config.interface_name(interface)
+        );
+    }
+    let current = &state.current_dns_state;
+    let servers: Vec<&str> = current
+        .ipv4
+        .iter()
+        .chain(&current.ipv6)
+        .map(String::as_str)
+        .collect();
+    let servers = if servers.is_empty() {
+        t!("not-available")
+    } else {
+        servers.join(", ")
+    };
+    t!(
+        "applied-dns-overridden",
+        interface = state.config.interface_name(interface),
+        servers = servers
+    )
+}
+
+fn update_interfaces(mut state: Signal<AppState>, interfaces: Vec<NetworkInterface>) -> bool {
+    if state.write().set_interfaces(interfaces) {
+        return true;
+    }
+
+    let message = {
+        let state = state.read();
+        match state.selected_interface() {
+            Some(interface) => t!(
+                "interface-switched",
+                interface = state.config.interface_name(interface)
+            ),
+            None => t!("no-interfaces"),
+        }
+    };
+    state.write().set_message(Message::warning(message));
+    false
+}
+
+fn change_dns_mode(mut state: Signal<AppState>, mode: DnsMode) {
+    let old_mode = state.read().dns_mode;
+
+    if old_mode == mode {
+        return;
+    }
+
+    let mut write_state = state.write();
+    write_state.dns_mode = mode;
+    write_state.clear_message();
+
+    if mode == DnsMode::Manual && write_state.config.profiles.is_empty() {
+        write_state.create_new_profile();
+    } else if mode == DnsMode::Manual
+        && write_state.selected_profile_id.is_none()
+        && let Some(first) = write_state.config.sorted_profiles().first()
+    {
+        let first_id = first.id.clone();
+        drop(write_state);
+        state.write().select_profile(&first_id);
+    }
+}
+
+async fn copy_profile(mut state: Signal<AppState>) {
+    let text = match state.read().profile_share_text() {
+        Ok(text) => text,
+        Err(e) => {
+            state.write().set_message(Message::error(e));
+            return;
+        }
+    };
+
+    let message = match set_clipboard_text(&text).await {
+        Ok(()) => Message::success(t!("profile-copied")),
+        Err(e) => Message::error(t!("copy-profile-failed", error = e)),
+    };
+    state.write().set_message(message);
+}
+
+async fn copy_servers(mut state: Signal<AppState>, family: AddressFamily, text: String) {
+    let message = match set_clipboard_text(&text).await {
+        Ok(()) => Message::success(t!("servers-copied", family = family.as_str())),
+        Err(e) => Message::error(t!("copy-servers-failed", error = e)),
+    };
+    state.write().set_message(message);
+}
+
+async fn paste_profile(mut state: Signal<AppState>) {
+    let text = match get_clipboard_text().await {
+        Ok(text) => text,
+        Err(e) => {
+            state
+                .write()
+                .set_message(Message::error(t!("read-clipboard-failed", error = e)));
+            return;
+        }
+    };
+
+    let result = state.write().paste_profile(&text);
+    let name = match result {
+        Ok(name) => name,
+        Err(e) => {
+            state
+                .write()
+                .set_message(Message::error(t!("paste-profile-failed", error = e)));
+            return;
+        }
+    };
+
+    let config = state.read().config.clone();
+    let message = match save_config(&config) {
+        Ok(()) => Message::success(t!("profile-added", name = name)),
+        Err(e) => Message::error(t!("save-config-failed", error = e)),
+    };
+    state.write().set_message(message);
+}
+
+async fn export_profile_file(mut state: Signal<AppState>) {
+    let Some(file) = rfd::AsyncFileDialog::new()
+        .add_filter("JSON", &["json"])
+        .set_file_name("windns-profiles.json")
+        .save_file()
+        .await
+    else {
+        return;
+    };
+
+    let config = state.read().config.clone();
+    let message = match export_profiles(&config, file.path()) {
+        Ok(()) => Message::success(t!(
+            "profiles-exported",
+            count = config.profiles.len(),
+            path = file.path().display()
+        )),
+        Err(e) => Message::error(t!("export-profiles-failed", error = e)),
+    };
+    state.write().set_message(message);
+}
+
+async fn import_profile_file(mut state: Signal<AppState>) {
+    let Some(file) = rfd::AsyncFileDialog::new()
+        .add_filter("JSON", &["json", "jsonc"])
+        .pick_file()
+        .await
+    else {
+        return;
+    };
+
+    let mut config = state.read().config.clone();
+    let count = match import_profiles(&mut config, file.path()) {
+        Ok(count) => count,
+        Err(e) => {
+            state
+                .write()
+                .set_message(Message::error(t!("import-profiles-failed", error = e)));
+            return;
+        }
+    };
+
+    if let Err(e) = save_config(&config) {
+        state
+            .write()
+            .set_message(Message::error(t!("save-config-failed", error = e)));
+        return;
+    }
+
+    let mut write_state = state.write();
+    write_state.config = config;
+    write_state.set_message(Message::success(t!("profiles-imported", count = count)));
+}
+
+/// Moves the config into `dir`, or back to the default folder for `None`.
+fn change_config_dir(mut state: Signal<AppState>, dir: Option<&Path>) {
+    let result = move_config(&state.read().config, dir);
+    let mut write_state = state.write();
+    match result {
+        Ok(found) => {
+            write_state.config_format = config_format();
+            write_state.config_location = config_location().ok();
+            let path = write_state
+                .config_location
+                .as_ref()
+                .map(|location| location.dir().display().to_string())
+                .unwrap_or_default();
+            let message = match found {
+                Some(config) => {
+                    write_state.restore_config(config);
+                    t!("config-adopted", path = path)
+                }
+                None => t!("config-moved", path = path),
+            };
+            write_state.set_message(Message::success(message));
+        }
+        Err(e) => {
+            write_state.set_message(Message::error(t!("move-config-failed", error = e)));
+        }
+    }
+}
+
+fn restore_backup(mut state: Signal<AppState>, path: &Path) {
+    let created = state
+        .read()
+        .config_backups
+        .iter()
+        .flatten()
+        .find(|backup| backup.path == path)
+        .map(|backup| backup.created.clone())
+        .unwrap_or_default();
+    match restore_config_backup(path) {
+        Ok(config) => {
+            if let Some(secs) = config.command_timeout_secs {
+                set_command_timeout(secs);
+            }
+            let mut write_state = state.write();
+            write_state.restore_config(config);
+            write_state.config_backups = None;
+            write_state.set_message(Message::success(t!("config-restored", created = created)));
+        }
+        Err(e) => state
+            .write()
+            .set_message(Message::error(t!("restore-backup-failed", error = e))),
+    }
+}
+
+/// Runs `operation` in the command queue, after the operations already
+/// queued for the interface `interface_guid`. The operation must not queue
+/// another one for the same interface, which would wait forever.
+async fn queued<T: 'static>(
+    state: Signal<AppState>,
+    interface_guid: &str,
+    operation: impl Future<Output = T> + 'static,
+) -> Option<T> {
+    let queue = state.read().command_queue.clone();
+    queue.run(interface_guid, operation).await
+}
+
+async fn refresh_current_dns(mut state: Signal<AppState>) {
+    let Some(interface) = state.read().selected_interface().cloned() else {
+        return;
+    };
+    let backend = state.read().backend;
+    let generation = state.read().selection_generation();
+    let guid = interface.interface_guid.clone();
+
+    let Some((details, dns_state)) = queued(state, &guid, async move {
+        let details = get_interface_details(&interface.interface_guid);
+        (details, backend.get_current_dns(&interface).await)
+    })
+    .await
+    else {
+        return;
+    };
+    // Another interface was picked meanwhile; its own refresh fills these.
+    if !state.read().is_current_selection(generation) {
+        return;
+    }
+    let details = match details {
+        Ok(details) => Some(details),
+        Err(e) => {
+            eprintln!("Failed to read interface details: {}", e);
+            None
+        }
+    };
+    state.write().interface_details = details;
+    match dns_state {
+        Ok(dns_state) => {
+            state.write().current_dns_state = dns_state;
+        }
+        Err(e) => {
+            eprintln!("Failed to refresh DNS state: {}", e);
+        }
+    }
+}
+
+async fn refresh_nrpt_rules(mut state: Signal<AppState>) {
+    let backend = state.read().backend;
+    if !backend.supports_nrpt() {
+        return;
+    }
+
+    match backend.get_nrpt_rules().await {
+        Ok(rules) => {
+            state.write().nrpt_rules = rules;
+        }
+        Err(e) => {
+            eprintln!("Failed to refresh NRPT rules: {}", e);
+        }
+    }
+}
+
+async fn delete_nrpt_rule(mut state: Signal<AppState>, name: String) {
+    state.write().clear_message();
+    if let Some(reason) = state.read().read_only_reason() {
+        state.write().set_message(Message::warning(reason));
+        return;
+    }
+
+    let backend = state.read().backend;
+    if let Err(e) = backend.remove_nrpt_rule(&name).await {
+        state
+            .write()
+            .set_message(Message::error(t!("delete-nrpt-rule-failed", error = e)));
+    } else {
+        state
+            .write()
+            .set_message(Message::success(t!("nrpt-rule-deleted")));
+    }
+
+    refresh_nrpt_rules(state).await;
+}
+
+async fn discover_doh(mut state: Signal<AppState>, family: AddressFamily, index: usize) {
+    state.write().clear_message();
+
+    let address = {
+        let read_state = state.read();
+        let entry = match family {
+            AddressFamily::IPv4 => &read_state.current_settings.ipv4,
+            AddressFamily::IPv6 => &read_state.current_settings.ipv6,
+        };
+        match entry.servers.get(index) {
+            Some(server) => server.address.clone(),
+            None => return,
+        }
+    };
+
+    match discover_doh_template(&address).await {
+        Ok(template) => {
+            let mut write_state = state.write();
+            write_state.set_doh_template(family, index, template.clone());
+            write_state.set_message(Message::success(t!(
+                "doh-discovered",
+                address = address.trim(),
+                template = template
+            )));
+        }
+        Err(e) => {
+            state.write().set_message(Message::error(t!(
+                "doh-discovery-failed",
+                address = address.trim(),
+                error = e
+            )));
+        }
+    }
+}
+
+async fn resolve_server_host(mut state: Signal<AppState>, family: AddressFamily, index: usize) {
+    state.write().clear_message();
+
+    let host = {
+        let read_state = state.read();
+        let entry = match family {
+            AddressFamily::IPv4 => &read_state.current_settings.ipv4,
+            AddressFamily::IPv6 => &read_state.current_settings.ipv6,
+        };
+        match entry.servers.get(index) {
+            Some(server) => server.address.trim().to_string(),
+            None => return,
+        }
+    };
+
+    match resolve_host_addresses(&host).await {
+        Ok(addresses) => {
+            let list = addresses
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+            let mut write_state = state.write();
+            write_state.resolved_host = Some(ResolvedHost {
+                family,
+                index,
+                host: host.clone(),
+                addresses,
+            });
+            write_state.set_message(Message::success(t!(
+                "host-resolved",
+                host = host,
+                addresses = list
+            )));
+        }
+        Err(e) => {
+            state.write().set_message(Message::error(t!(
+                "host-resolution-failed",
+                host = host,
+                error = e
+            )));
+        }
+    }
+}
+
+fn save_custom_provider(mut state: Signal<AppState>) {
+    state.write().clear_message();
+
+    let name = match state.write().save_provider_draft() {
+        Ok(name) => name,
+        Err(e) => {
+            state.write().set_message(Message::error(e));
+            return;
+        }
+    };
+
+    let config = state.read().config.clone();
+    let message = match save_config(&config) {
+        Ok(()) => Message::success(t!("provider-saved", name = name)),
+        Err(e) => Message::error(t!("save-config-failed", error = e)),
+    };
+    state.write().set_message(message);
+}
+
+fn delete_custom_provider(mut state: Signal<AppState>, id: String) {
+    state.write().clear_message();
+
+    if !state.write().delete_custom_provider(&id) {
+        return;
+    }
+
+    let config = state.read().config.clone();
+    let message = match save_config(&config) {
+        Ok(()) => Message::success(t!("provider-deleted")),
+        Err(e) => Message::error(t!("save-config-failed", error = e)),
+    };
+    state.write().set_message(message);
+}
+
+/// Adds a provider for each DoH server mapping Windows has that no
+/// provider covers yet, so that manual DoH settings can be used in
+/// profiles.
+async fn import_doh_providers(mut state: Signal<AppState>) {
+    state.write().clear_message();
+    let backend = state.read().backend;
+    let servers = match backend.get_doh_templates().await {
+        Ok(servers) => servers,
+        Err(e) => {
+            state
+                .write()
+                .set_message(Message::error(t!("import-doh-failed", error = e)));
+            return;
+        }
+    };
+
+    let names = state.write().import_doh_providers(&servers);
+    state.write().set_known_doh_templates(servers);
+    if names.is_empty() {
+        state
+            .write()
+            .set_message(Message::success(t!("import-doh-none")));
+        return;
+    }
+
+    let config = state.read().config.clone();
+    let message = match save_config(&config) {
+        Ok(()) => Message::success(t!("import-doh-done", names = names.join(", "))),
+        Err(e) => Message::error(t!("save-config-failed", error = e)),
+    };
+    state.write().set_message(message);
+}
+
+async fn check_dnssec_validation(mut state: Signal<AppState>) {
+    let Some(server) = state.read().dnssec_server() else {
+        return;
+    };
+
+    {
+        let mut write_state = state.write();
+        write_state.clear_message();
+        write_state.is_checking_dnssec = true;
+    }
+
+    let result = match check_dnssec(&server).await {
+        Ok(DnssecStatus::Validating) => Message::success(DnssecStatus::Validating.description()),
+        Ok(status) => Message::warning(status.description()),
+        Err(e) => Message::error(t!("dnssec-check-failed", error = e)),
+    };
+
+    let mut write_state = state.write();
+    write_state.is_checking_dnssec = false;
+    write_state.dnssec_results.insert(server, result);
+}
+
+async fn save_network_rules(mut state: Signal<AppState>) {
+    state.write().clear_message();
+
+    let result = state.write().save_network_rules();
+    if let Err(e) = result {
         state.write().set_message(Message::error(e));
         return;
-    }
+    }
+
+    let config = state.read().config.clone();
+    let message = match save_config(&config) {
+        Ok(()) => Message::success(t!("network-rules-saved")),
+        Err(e) => Message::error(t!("save-config-failed", error = e)),
+    };
+    state.write().set_message(message);
+
+    run_network_rules(state, true).await;
+}
+
+async fn run_lookup(mut state: Signal<AppState>) {
+    let (server, query) = {
+        let read_state = state.read();
+        (read_state.lookup_server(), read_state.lookup_query.clone())
+    };
+    let Some(server) = server else {
+        return;
+    };
+
+    state.write().is_looking_up = true;
+
+    let result = lookup(&server, &query.name, query.record_type)
+        .await
+        .map_err(|e| {
+            t!(
+                "lookup-failed",
+                name = query.name.trim(),
+                server = server,
+                error = e
+            )
+        });
+
+    let mut write_state = state.write();
+    write_state.is_looking_up = false;
+    write_state.lookup_result = Some(result);
+}
+
+async fn run_dns_leak_test(mut state: Signal<AppState>) {
+    {
+        let mut write_state = state.write();
+        write_state.clear_message();
+        write_state.is_leak_testing = true;
+    }
+
+    let result = run_leak_test().await;
+
+    let mut write_state = state.write();
+    write_state.is_leak_testing = false;
+    match result {
+        Ok(result) => write_state.leak_test = Some(result),
+        Err(e) => {
+            write_state.set_message(Message::error(t!("leak-test-failed", error = e)));
+        }
+    }
+}
+
+async fn change_logon_task(mut state: Signal<AppState>, profile_id: Option<String>) {
+    let previous = state.write().logon_task.take();
+
+    match set_logon_task(profile_id.as_deref()).await {
+        Ok(()) => state.write().logon_task = Some(profile_id),
+        Err(e) => {
+            let mut write_state = state.write();
+            write_state.logon_task = previous;
+            write_state.set_message(Message::error(e.to_string()));
+        }
+    }
+}
+
+async fn check_for_updates(mut state: Signal<AppState>) {
+    state.write().update_status = Some(UpdateStatus::Checking);
+
+    let status = match check_for_update().await {
+        Ok(Some(release)) => UpdateStatus::Available(release),
+        Ok(None) => UpdateStatus::UpToDate,
+        Err(e) => UpdateStatus::Failed(e.to_string()),
+    };
+    state.write().update_status = Some(status);
+}
+
+async fn download_release(mut state: Signal<AppState>) {
+    let release = {
+        let mut write_state = state.write();
+        let Some(UpdateStatus::Available(release)) = write_state.update_status.clone() else {
+            return;
+        };
+        write_state.update_status = Some(UpdateStatus::Downloading(release.clone()));
+        release
+    };
+
+    let status = match download_update(&release).await {
+        Ok(package) => UpdateStatus::Ready(release, package),
+        Err(e) => UpdateStatus::Failed(e.to_string()),
+    };
+    state.write().update_status = Some(status);
+}
+
+async fn flush_dns_cache(mut state: Signal<AppState>) {
+    state.write().clear_message();
+
+    let message = match clear_dns_cache().await {
+        Ok(()) => Message::success(t!("dns-cache-flushed")),
+        Err(e) => Message::error(t!("flush-dns-cache-failed", error = e)),
+    };
+    state.write().set_message(message);
+}
+
+async fn export_state_report(mut state: Signal<AppState>) {
+    let Some(file) = rfd::AsyncFileDialog::new()
+        .add_filter("JSON", &["json"])
+        .add_filter("Markdown", &["md"])
+        .set_file_name("windns-state.json")
+        .save_file()
+        .await
+    else {
+        return;
+    };
+
+    let (backend, interfaces) = {
+        let read_state = state.read();
+        (read_state.backend, read_state.interfaces.clone())
+    };
+    let report = collect_state_report(
+        &backend,
+        &interfaces,
+        get_dns_suffixes().await,
+        |interface, current| {
+            state
+                .read()
+                .matching_profile(&interface.interface_guid, current)
+                .map(|p| p.name.clone())
+        },
+    )
+    .await;
+
+    let message = match save_state_report(&report, file.path()) {
+        Ok(()) => Message::success(t!("state-exported", path = file.path().display())),
+        Err(e) => Message::error(t!("export-state-failed", error = e)),
+    };
+    state.write().set_message(message);
+}
+
+async fn save_settings_only(mut state: Signal<AppState>) {
+    let validation_result = {
+        let read_state = state.read();
+        if read_state.dns_mode == DnsMode::Manual {
+            read_state.validate_current_settings()
+        } else {
+            Ok(None)
+        }
+    };
+
+    let validation_warning = match validation_result {
+        Ok(warning) => warning,
+        Err(e) => {
+            state.write().set_message(Message::error(e));
+            return;
+        }
+    };
 
     if state.read().dns_mode == DnsMode::Manual {
         state.write().update_current_profile();
@@ -241,59 +2204,176 @@ async fn save_settings_only(mut state: Signal<AppState>) {
     if let Err(e) = save_config(&config) {
         state
             .write()
-            .set_message(Message::error(format!("Failed to save config: {}", e)));
+            .set_message(Message::error(t!("save-config-failed", error = e)));
+    } else if let Some(warning) = validation_warning {
+        state.write().set_message(Message::warning(t!(
+            "settings-saved-warning",
+            warning = warning
+        )));
     } else {
         state
             .write()
-            .set_message(Message::success("Settings saved"));
+            .set_message(Message::success(t!("settings-saved")));
     }
 }
 
-async fn apply_dns_settings(mut state: Signal<AppState>) {
-    let validation_result = {
+/// Applies the settings in the form to the selected interface. With
+/// `minutes`, the interface's previous settings are put back once that
+/// many minutes have passed.
+async fn apply_dns_settings(mut state: Signal<AppState>, minutes: Option<u64>) {
+    {
         let mut write_state = state.write();
         write_state.clear_message();
-        write_state.validate_current_settings()
-    };
+        write_state.resolution_check = None;
+    }
+    if let Some(reason) = state.read().read_only_reason() {
+        state.write().set_message(Message::warning(reason));
+        return;
+    }
 
-    if let Err(e) = validation_result {
-        state.write().set_message(Message::error(e));
+    if !refresh_interfaces(state) {
+        refresh_current_dns(state).await;
         return;
     }
 
+    let validation_warning = match state.read().validate_current_settings() {
+        Ok(warning) => warning,
+        Err(e) => {
+            state.write().set_message(Message::error(e));
+            return;
+        }
+    };
+
     state.write().set_loading(true);
 
-    let result = apply_dns_settings_impl(&state).await;
+    if let Some(error) = probe_doh_servers(state).await {
+        state.write().set_loading(false);
+        state.write().set_message(Message::error(error));
+        return;
+    }
+
+    if let Some(warning) = check_local_resolver(state).await {
+        state.write().set_loading(false);
+        state.write().set_message(Message::warning(warning));
+        return;
+    }
+
+    let temporary = match minutes {
+        Some(minutes) => match temporary_apply_for(state, minutes).await {
+            Ok(temporary) => Some(temporary),
+            Err(e) => {
+                state.write().set_loading(false);
+                state
+                    .write()
+                    .set_message(Message::error(t!("temporary-apply-failed", error = e)));
+                return;
+            }
+        },
+        None => None,
+    };
+
+    let (guid, profile_id, check_host, generation) = {
+        let read_state = state.read();
+        let (profile_id, check_host) = match read_state.dns_mode {
+            DnsMode::Automatic => (None, None),
+            DnsMode::Manual => (
+                read_state.selected_profile_id.clone(),
+                read_state
+                    .current_settings
+                    .check_host
+                    .clone()
+                    .filter(|host| !host.trim().is_empty()),
+            ),
+        };
+        (
+            read_state
+                .selected_interface_guid
+                .clone()
+                .unwrap_or_default(),
+            profile_id,
+            check_host,
+            read_state.selection_generation(),
+        )
+    };
+    let result = queued(state, &guid, apply_dns_settings_impl(state)).await;
 
     state.write().set_loading(false);
+    let Some(result) = result else {
+        return;
+    };
 
     match result {
-        Ok(warning) => {
-            if state.read().dns_mode == DnsMode::Manual {
-                state.write().update_current_profile();
+        Ok(apply_warning) => {
+            let check_warning = match &check_host {
+                Some(host) => check_host_resolves(host.trim())
+                    .await
+                    .err()
+                    .map(|e| t!("check-host-failed", host = host.trim(), error = e)),
+                None => None,
+            };
+            let warnings: Vec<String> = [validation_warning, apply_warning, check_warning]
+                .into_iter()
+                .flatten()
+                .collect();
+            let warning = (!warnings.is_empty()).then(|| warnings.join(" "));
+            // A plain apply is a lasting choice, so it cancels a pending
+            // revert of the interface.
+            match temporary {
+                Some(temporary) => {
+                    state
+                        .write()
+                        .config
+                        .temporary_applies
+                        .insert(guid.clone(), temporary);
+                }
+                None => {
+                    state.write().config.temporary_applies.remove(&guid);
+                }
+            }
+            let is_current = state.read().is_current_selection(generation);
+            if is_current {
+                if state.read().dns_mode == DnsMode::Manual {
+                    state.write().update_current_profile();
+                }
+                state.write().remember_interface_profile();
+            } else {
+                // The form now shows another interface, so only record
+                // what was applied to this one.
+                state
+                    .write()
+                    .config
+                    .interface_profiles
+                    .insert(guid, profile_id);
             }
 
             let config = state.read().config.clone();
 
             let final_message = if let Err(e) = save_config(&config) {
-                Message::error(format!("Settings applied but failed to save config: {}", e))
+                Message::error(t!("applied-save-config-failed", error = e))
             } else if let Some(warn_msg) = warning {
-                Message::warning(format!("DNS settings applied. {}", warn_msg))
+                Message::warning(t!("dns-applied-warning", warning = warn_msg))
+            } else if let Some(minutes) = minutes {
+                Message::success(t!("dns-applied-temporarily", minutes = minutes))
             } else {
-                Message::success("DNS settings applied successfully")
+                Message::success(t!("dns-applied"))
             };
 
-            state.write().set_message(final_message);
+            let mut write_state = state.write();
+            write_state.set_message(final_message);
+            write_state.leak_test = None;
+            drop(write_state);
 
             refresh_current_dns(state).await;
+            refresh_nrpt_rules(state).await;
+            if is_current {
+                check_resolution_after_apply(state).await;
+            }
+            update_health(state).await;
         }
         Err(e) => {
             let (message, should_refresh) = match &e {
                 DnsCommandError::DnsAppliedButDohFailed(_) => (Message::error(e.to_string()), true),
-                _ => (
-                    Message::error(format!("Failed to apply DNS settings: {}", e)),
-                    false,
-                ),
+                _ => (Message::error(t!("apply-failed", error = e)), false),
             };
             state.write().set_message(message);
 
@@ -304,40 +2384,671 @@ async fn apply_dns_settings(mut state: Signal<AppState>) {
     }
 }
 
+/// What to put back once a temporary apply to the selected interface runs
+/// out in `minutes`: the settings the interface has now, or those an
+/// earlier temporary apply still running already kept, so that applying
+/// again only extends it.
+async fn temporary_apply_for(
+    state: Signal<AppState>,
+    minutes: u64,
+) -> Result<TemporaryApply, DnsCommandError> {
+    let (backend, interface, running, previous_profile) = {
+        let read_state = state.read();
+        let interface = read_state
+            .selected_interface()
+            .ok_or_else(|| DnsCommandError::CommandFailed(t!("no-interface-selected")))?
+            .clone();
+        let guid = &interface.interface_guid;
+        (
+            read_state.backend,
+            interface.clone(),
+            read_state.config.temporary_applies.get(guid).cloned(),
+            read_state
+                .config
+                .interface_profiles
+                .get(guid)
+                .cloned()
+                .flatten(),
+        )
+    };
+
+    let expires_at = unix_now() + minutes * 60;
+    if let Some(running) = running {
+        return Ok(TemporaryApply {
+            expires_at,
+            ..running
+        });
+    }
+    let before = snapshot_dns_settings(&backend, &interface).await?;
+    Ok(TemporaryApply {
+        before,
+        previous_profile,
+        expires_at,
+    })
+}
+
+/// Keeps the settings of the selected interface, dropping its pending
+/// revert.
+fn keep_temporary_apply(mut state: Signal<AppState>) {
+    let Some(guid) = state.read().selected_interface_guid.clone() else {
+        return;
+    };
+    state.write().config.temporary_applies.remove(&guid);
+    let config = state.read().config.clone();
+    let message = match save_config(&config) {
+        Ok(()) => Message::success(t!("temporary-apply-kept")),
+        Err(e) => Message::error(t!("save-config-failed", error = e)),
+    };
+    state.write().set_message(message);
+}
+
+/// Puts back the settings of the temporary applies that have run out.
+async fn watch_temporary_applies(state: Signal<AppState>) {
+    loop {
+        let now = unix_now();
+        let expired: Vec<String> = state
+            .read()
+            .config
+            .temporary_applies
+            .iter()
+            .filter(|(_, temporary)| temporary.has_expired(now))
+            .map(|(guid, _)| guid.clone())
+            .collect();
+        for guid in expired {
+            revert_temporary_apply(state, guid).await;
+        }
+
+        tokio::time::sleep(TEMPORARY_CHECK_INTERVAL).await;
+    }
+}
+
+/// Puts back the settings an interface had before it was applied to
+/// temporarily.
+async fn revert_temporary_apply(mut state: Signal<AppState>, guid: String) {
+    let (interface, before) = {
+        let read_state = state.read();
+        if read_state.is_loading || !read_state.is_elevated {
+            return;
+        }
+        let Some(temporary) = read_state.config.temporary_applies.get(&guid) else {
+            return;
+        };
+        // An adapter that is not listed, e.g. unplugged, is reverted once
+        // it is back.
+        let Some(interface) = read_state
+            .interfaces
+            .iter()
+            .find(|i| i.interface_guid == guid)
+        else {
+            return;
+        };
+        (interface.clone(), temporary.before.clone())
+    };
+
+    state.write().set_loading(true);
+    let target_interface = interface.clone();
+    let result = queued(state, &guid, async move {
+        apply_settings_to_interface(state, &target_interface, &before, false).await
+    })
+    .await;
+    state.write().set_loading(false);
+    let Some(result) = result else {
+        return;
+    };
+
+    let name = state.read().config.interface_name(&interface);
+    let message = match result {
+        Ok(warning) => {
+            state.write().finish_temporary_apply(&guid);
+            match warning {
+                Some(warning) => Message::warning(t!(
+                    "temporary-apply-reverted-warning",
+                    interface = name,
+                    warning = warning
+                )),
+                None => Message::success(t!("temporary-apply-reverted", interface = name)),
+            }
+        }
+        Err(e) => {
+            // Trying again would most likely fail the same way, every
+            // second.
+            state.write().config.temporary_applies.remove(&guid);
+            Message::error(t!(
+                "temporary-apply-revert-failed",
+                interface = name,
+                error = e
+            ))
+        }
+    };
+    let config = state.read().config.clone();
+    let message = match save_config(&config) {
+        Ok(()) => message,
+        Err(e) => Message::error(t!("save-config-failed", error = e)),
+    };
+    state.write().set_message(message);
+
+    let is_selected = state.read().selected_interface_guid.as_deref() == Some(guid.as_str());
+    if is_selected {
+        state.write().restore_interface_profile();
+        refresh_current_dns(state).await;
+    }
+}
+
+/// Sends a test query to every DoH template about to be applied, so that a
+/// dead endpoint cannot leave the machine without name resolution. Returns
+/// the error to show when one does not answer.
+async fn probe_doh_servers(state: Signal<AppState>) -> Option<String> {
+    let settings = {
+        let read_state = state.read();
+        if !read_state.config.settings.probes_doh_before_apply()
+            || read_state.dns_mode == DnsMode::Automatic
+            || read_state.doh_unavailable_reason().is_some()
+        {
+            return None;
+        }
+        read_state.current_settings.clone()
+    };
+
+    let failures = unreachable_doh_templates(&settings).await;
+    if failures.is_empty() {
+        return None;
+    }
+
+    Some(t!(
+        "doh-probe-failed",
+        failures = failures.join("; "),
+        option = t!("probe-doh")
+    ))
+}
+
+/// Makes sure the local resolver the settings hand DNS to is running, so
+/// that applying does not leave the machine without name resolution.
+/// Returns the warning to show when nothing answers on its addresses.
+async fn check_local_resolver(state: Signal<AppState>) -> Option<String> {
+    let servers = state.read().local_resolver_servers();
+    if servers.is_empty() {
+        return None;
+    }
+    for server in &servers {
+        if server_answers(server).await {
+            return None;
+        }
+    }
+    Some(t!(
+        "local-resolver-not-running",
+        servers = servers.join(", "),
+        option = t!("local-resolver-check")
+    ))
+}
+
+/// Resolves the configured test host through the first DNS server now in
+/// use, so a mistyped server shows up right after the apply.
+async fn check_resolution_after_apply(mut state: Signal<AppState>) {
+    let generation = state.read().selection_generation();
+    let (server, host) = {
+        let read_state = state.read();
+        if !read_state.config.settings.checks_resolution_after_apply() {
+            return;
+        }
+        let current = &read_state.current_dns_state;
+        let Some(server) = current.ipv4.iter().chain(&current.ipv6).next() else {
+            return;
+        };
+        (
+            server.clone(),
+            read_state
+                .config
+                .settings
+                .resolution_check_host()
+                .to_string(),
+        )
+    };
+
+    let result = match check_resolution(&server, &host).await {
+        Ok(elapsed) => Message::success(t!(
+            "resolution-ok",
+            elapsed = elapsed.as_millis(),
+            host = host,
+            server = server
+        )),
+        Err(e) => Message::error(t!(
+            "resolution-failed",
+            host = host,
+            server = server,
+            error = e
+        )),
+    };
+    if state.read().is_current_selection(generation) {
+        state.write().resolution_check = Some(result);
+    }
+}
+
+/// Keeps the health indicator up to date, so that name resolution failing
+/// after a switch does not go unnoticed.
+async fn watch_health(state: Signal<AppState>) {
+    loop {
+        if !state.read().is_loading {
+            update_health(state).await;
+        }
+        tokio::time::sleep(HEALTH_CHECK_INTERVAL).await;
+    }
+}
+
+/// Measures how fast the DNS servers of the selected interface answer, so
+/// that a slow or unresponsive one shows in the status bar.
+async fn watch_server_latency(mut state: Signal<AppState>) {
+    loop {
+        tokio::time::sleep(LATENCY_CHECK_INTERVAL).await;
+        if state.read().is_loading {
+            continue;
+        }
+
+        let (servers, host, generation) = {
+            let read_state = state.read();
+            let current = &read_state.current_dns_state;
+            let servers: Vec<String> = current.ipv4.iter().chain(&current.ipv6).cloned().collect();
+            (
+                servers,
+                read_state
+                    .config
+                    .settings
+                    .resolution_check_host()
+                    .to_string(),
+                read_state.selection_generation(),
+            )
+        };
+        let mut latency = BTreeMap::new();
+        for server in servers {
+            let elapsed = measure_latency(&server, &host).await.ok();
+            latency.insert(server, elapsed);
+        }
+        if state.read().is_current_selection(generation) {
+            state.write().server_latency = latency;
+        }
+    }
+}
+
+async fn update_health(mut state: Signal<AppState>) {
+    let host = state
+        .read()
+        .config
+        .settings
+        .resolution_check_host()
+        .to_string();
+    let health = match check_health(&host).await {
+        Ok(elapsed) => {
+            Message::success(t!("health-ok", host = host, elapsed = elapsed.as_millis()))
+        }
+        Err(e) => Message::error(t!("health-failed", error = e)),
+    };
+    state.write().health = Some(health);
+}
+
 async fn apply_dns_settings_impl(
-    state: &Signal<AppState>,
+    mut state: Signal<AppState>,
 ) -> Result<Option<String>, DnsCommandError> {
     let interface = state
         .read()
         .selected_interface()
-        .ok_or_else(|| DnsCommandError::CommandFailed("No interface selected".to_string()))?
+        .ok_or_else(|| DnsCommandError::CommandFailed(t!("no-interface-selected")))?
         .clone();
 
-    let interface_index = interface.interface_index;
-    let interface_guid = &interface.interface_guid;
-    let dns_mode = state.read().dns_mode;
-    let settings = state.read().current_settings.clone();
+    let settings = match state.read().dns_mode {
+        DnsMode::Automatic => DnsSettings::new(),
+        DnsMode::Manual => state.read().current_settings.clone(),
+    };
+
+    apply_settings_to_interface(state, &interface, &settings, true).await
+}
+
+/// Applies `settings` to `interface`, saving the interface's original DNS
+/// settings first and clearing the DNS cache afterwards if configured.
+/// Hook commands run with administrator rights, so they only run when
+/// `clicked` says the user started the apply in the window, and only once
+/// they are approved in the editor.
+async fn apply_settings_to_interface(
+    mut state: Signal<AppState>,
+    interface: &NetworkInterface,
+    settings: &DnsSettings,
+    clicked: bool,
+) -> Result<Option<String>, DnsCommandError> {
+    let backend = state.read().backend;
+    let has_snapshot = state
+        .read()
+        .config
+        .original_dns
+        .contains_key(&interface.interface_guid);
 
-    let dns_warning = match dns_mode {
-        DnsMode::Automatic => {
-            set_dns_automatic(interface_index).await?;
+    // Read every time for the history, and kept as the original settings
+    // the first time.
+    let before = snapshot_dns_settings(&backend, interface).await;
+    let snapshot_warning = match &before {
+        Ok(snapshot) => {
+            if !has_snapshot {
+                state
+                    .write()
+                    .config
+                    .original_dns
+                    .insert(interface.interface_guid.clone(), snapshot.clone());
+            }
             None
         }
-        DnsMode::Manual => {
-            set_dns_with_settings(interface_index, interface_guid, &settings).await?
+        Err(e) => (!has_snapshot).then(|| t!("snapshot-failed", error = e)),
+    };
+
+    // Policies can be pushed at any time, so look again before each change.
+    state.write().dns_policies = detect_dns_policies();
+    let policy_warning = state.read().policy_override_warning(settings);
+
+    let run_hooks = clicked && hooks_approved(settings);
+    let skipped_hooks_warning = match (settings.has_hooks(), run_hooks) {
+        (true, false) if clicked => Some(t!("hooks-not-approved")),
+        (true, false) => Some(t!("hooks-skipped")),
+        _ => None,
+    };
+    if run_hooks && let Some(command) = hook_command(&settings.before_apply) {
+        run_apply_hook(state, command).await?;
+    }
+    let created_doh_servers = new_doh_servers(&backend, settings).await;
+    let result = set_dns_with_settings(&backend, interface, settings).await;
+    state
+        .write()
+        .config
+        .record_doh_servers(&created_doh_servers);
+    record_change(state, interface, before.ok(), settings, &result);
+    let dns_warning = result?;
+
+    let flush_cache = state.read().config.settings.flushes_cache_after_apply();
+    let cache_warning = if flush_cache {
+        clear_dns_cache()
+            .await
+            .err()
+            .map(|e| t!("clear-cache-failed", error = e))
+    } else {
+        None
+    };
+
+    let hook_warning = match hook_command(&settings.after_apply).filter(|_| run_hooks) {
+        Some(command) => run_apply_hook(state, command)
+            .await
+            .err()
+            .map(|e| t!("hook-failed", command = command, error = e)),
+        None => None,
+    };
+
+    let warnings: Vec<String> = [
+        snapshot_warning,
+        policy_warning,
+        dns_warning,
+        cache_warning,
+        skipped_hooks_warning,
+        hook_warning,
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+
+    Ok((!warnings.is_empty()).then(|| warnings.join("; ")))
+}
+
+/// A hook command of a profile, unless it is blank.
+fn hook_command(command: &Option<String>) -> Option<&str> {
+    command
+        .as_deref()
+        .map(str::trim)
+        .filter(|command| !command.is_empty())
+}
+
+/// Runs a hook command of the settings being applied and adds what it
+/// printed, or why it failed, to the activity log.
+async fn run_apply_hook(mut state: Signal<AppState>, command: &str) -> Result<(), DnsCommandError> {
+    let result = run_hook_command(command).await;
+    let message = match &result {
+        Ok(output) if output.is_empty() => Message::success(t!("hook-ran", command = command)),
+        Ok(output) => Message::success(t!("hook-output", command = command, output = output)),
+        Err(e) => Message::error(t!("hook-failed", command = command, error = e)),
+    };
+    state.write().set_message(message);
+    result.map(|_| ())
+}
+
+/// Adds a change to the history file and the History view.
+fn record_change(
+    mut state: Signal<AppState>,
+    interface: &NetworkInterface,
+    before: Option<DnsSettings>,
+    after: &DnsSettings,
+    result: &Result<Option<String>, DnsCommandError>,
+) {
+    let name = state.read().config.interface_name(interface);
+    let mut entry = HistoryEntry::now(&interface.interface_guid, &name, before, after.clone());
+    match result {
+        Ok(warning) => entry.warning = warning.clone(),
+        Err(e) => entry.error = Some(e.to_string()),
+    }
+    if let Err(e) = append_history(&entry) {
+        eprintln!("Failed to write the history: {}", e);
+    }
+    state.write().add_history(entry);
+}
+
+/// Puts an interface back to the settings it had before a change in the
+/// history. The interface then no longer has a profile windns applied.
+async fn revert_history_entry(mut state: Signal<AppState>, entry: HistoryEntry) {
+    state.write().clear_message();
+    if let Some(reason) = state.read().read_only_reason() {
+        state.write().set_message(Message::warning(reason));
+        return;
+    }
+    let Some(before) = entry.before else {
+        return;
+    };
+    let interface = state
+        .read()
+        .interfaces
+        .iter()
+        .find(|i| i.interface_guid == entry.interface_guid)
+        .cloned();
+    let Some(interface) = interface else {
+        state.write().set_message(Message::error(t!(
+            "history-interface-missing",
+            interface = entry.interface_name
+        )));
+        return;
+    };
+
+    state.write().set_loading(true);
+    let target_interface = interface.clone();
+    let result = queued(state, &interface.interface_guid, async move {
+        apply_settings_to_interface(state, &target_interface, &before, false).await
+    })
+    .await;
+    state.write().set_loading(false);
+    let Some(result) = result else {
+        return;
+    };
+
+    let name = state.read().config.interface_name(&interface);
+    let message = match result {
+        Ok(warning) => {
+            state
+                .write()
+                .config
+                .interface_profiles
+                .remove(&interface.interface_guid);
+            let config = state.read().config.clone();
+            if let Err(e) = save_config(&config) {
+                Message::error(t!("applied-save-config-failed", error = e))
+            } else if let Some(warning) = warning {
+                Message::warning(t!(
+                    "history-reverted-warning",
+                    interface = name,
+                    warning = warning
+                ))
+            } else {
+                Message::success(t!("history-reverted", interface = name))
+            }
+        }
+        Err(e) => Message::error(t!("history-revert-failed", error = e)),
+    };
+    state.write().set_message(message);
+    refresh_current_dns(state).await;
+}
+
+/// Puts the selected interface back to the DNS settings it had before
+/// windns first changed it, and forgets the snapshot once that succeeds.
+async fn restore_original_dns(mut state: Signal<AppState>) {
+    state.write().clear_message();
+    if let Some(reason) = state.read().read_only_reason() {
+        state.write().set_message(Message::warning(reason));
+        return;
+    }
+
+    let (interface, original) = {
+        let read_state = state.read();
+        match (read_state.selected_interface(), read_state.original_dns()) {
+            (Some(interface), Some(original)) => (interface.clone(), original.clone()),
+            _ => return,
+        }
+    };
+
+    state.write().set_loading(true);
+    let backend = state.read().backend;
+    let target_interface = interface.clone();
+    let settings = original.clone();
+    let result = queued(state, &interface.interface_guid, async move {
+        let before = snapshot_dns_settings(&backend, &target_interface)
+            .await
+            .ok();
+        let result = set_dns_with_settings(&backend, &target_interface, &settings).await;
+        (before, result)
+    })
+    .await;
+    state.write().set_loading(false);
+    let Some((before, result)) = result else {
+        return;
+    };
+    record_change(state, &interface, before, &original, &result);
+
+    let message = match result {
+        Ok(warning) => {
+            state
+                .write()
+                .config
+                .original_dns
+                .remove(&interface.interface_guid);
+            let config = state.read().config.clone();
+
+            if let Err(e) = save_config(&config) {
+                Message::error(t!("restored-save-config-failed", error = e))
+            } else if let Some(warn_msg) = warning {
+                Message::warning(t!("restored-warning", warning = warn_msg))
+            } else {
+                Message::success(t!("restored"))
+            }
         }
+        Err(e) => Message::error(t!("restore-failed", error = e)),
     };
+    state.write().set_message(message);
+
+    refresh_current_dns(state).await;
+}
 
-    let cache_warning = match clear_dns_cache().await {
-        Ok(()) => None,
-        Err(e) => Some(format!("DNS cache clear failed: {}", e)),
+/// Resets every adapter to the DNS servers DHCP provides and turns off the
+/// DoH settings windns made for it, carrying on past failures.
+async fn reset_all_dns(mut state: Signal<AppState>) {
+    state.write().clear_message();
+    if let Some(reason) = state.read().read_only_reason() {
+        state.write().set_message(Message::warning(reason));
+        return;
+    }
+    let interfaces = match get_network_interfaces(false) {
+        Ok(interfaces) => interfaces_to_reset(interfaces, &state.read().config),
+        Err(e) => {
+            state
+                .write()
+                .set_message(Message::error(t!("reset-all-list-failed", error = e)));
+            return;
+        }
     };
 
-    let combined_warning = match (dns_warning, cache_warning) {
-        (None, None) => None,
-        (Some(w), None) | (None, Some(w)) => Some(w),
-        (Some(w1), Some(w2)) => Some(format!("{}; {}", w1, w2)),
+    state.write().set_loading(true);
+    let selected_guid = state.read().selected_interface_guid.clone();
+    let mut selected_reset = false;
+    let mut reset = Vec::new();
+    let mut warnings = Vec::new();
+    let mut errors = Vec::new();
+    for interface in interfaces {
+        let target_interface = interface.clone();
+        let result = queued(state, &interface.interface_guid, async move {
+            apply_settings_to_interface(state, &target_interface, &DnsSettings::new(), false).await
+        })
+        .await;
+        let Some(result) = result else {
+            continue;
+        };
+        let name = state.read().config.interface_name(&interface);
+        match result {
+            Ok(warning) => {
+                state
+                    .write()
+                    .config
+                    .mark_automatic(&interface.interface_guid);
+                if let Some(warning) = warning {
+                    warnings.push(format!("{}: {}", name, warning));
+                }
+                selected_reset |= selected_guid.as_ref() == Some(&interface.interface_guid);
+                reset.push(name);
+            }
+            Err(e) => errors.push(format!("{}: {}", name, e)),
+        }
+    }
+    state.write().set_loading(false);
+
+    let config = state.read().config.clone();
+    let message = if let Err(e) = save_config(&config) {
+        Message::error(t!("save-config-failed", error = e))
+    } else if !errors.is_empty() {
+        Message::error(t!("reset-all-failed", errors = errors.join("; ")))
+    } else if !warnings.is_empty() {
+        Message::warning(t!("reset-all-warning", warnings = warnings.join("; ")))
+    } else {
+        Message::success(t!("reset-all-done", interfaces = reset.join(", ")))
     };
+    state.write().set_message(message);
+
+    if selected_reset {
+        state.write().restore_interface_profile();
+    }
+    refresh_current_dns(state).await;
+    refresh_nrpt_rules(state).await;
+    update_health(state).await;
+}
+
+async fn cleanup_doh_servers(mut state: Signal<AppState>) {
+    state.write().clear_message();
+    if let Some(reason) = state.read().read_only_reason() {
+        state.write().set_message(Message::warning(reason));
+        return;
+    }
+
+    let backend = state.read().backend;
+    let mut config = state.read().config.clone();
+    let result = remove_unused_doh_servers(&backend, &mut config).await;
 
-    Ok(combined_warning)
+    state.write().config.managed_doh_servers = config.managed_doh_servers;
+    let config = state.read().config.clone();
+    if let Err(e) = save_config(&config) {
+        state
+            .write()
+            .set_message(Message::error(t!("save-config-failed", error = e)));
+        return;
+    }
+
+    let message = match result {
+        Ok(removed) if removed.is_empty() => Message::success(t!("no-unused-doh")),
+        Ok(removed) => Message::success(t!("unused-doh-removed", servers = removed.join(", "))),
+        Err(e) => Message::error(t!("remove-unused-doh-failed", error = e)),
+    };
+    state.write().set_message(message);
 }
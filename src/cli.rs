@@ -0,0 +1,150 @@
+use crate::dns::{
+    AppConfig, ApplyStep, CliApplyResult, CliCommand, CliError, ControlRequest, DnsBackend,
+    DnsSettings, HistoryEntry, SystemBackend, append_history, apply_dns_settings, apply_text,
+    clear_dns_cache, get_network_interfaces, is_elevated, new_doh_servers, profiles_text,
+    save_config, snapshot_dns_settings, status_text,
+};
+use crate::i18n::t;
+use crate::state::AppState;
+use serde::Serialize;
+
+/// Runs `windns status`, `windns list` or `windns apply` on the interface
+/// the window had selected last, printing the result. Returns the exit
+/// code, 1 when the command or any step of it failed.
+pub fn run_cli(command: CliCommand, config: AppConfig) -> i32 {
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => return fail(command.json, e.to_string()),
+    };
+    runtime.block_on(run(command, config))
+}
+
+async fn run(command: CliCommand, config: AppConfig) -> i32 {
+    let json = command.json;
+    let mut state = AppState::new();
+    state.is_elevated = is_elevated();
+    state.backend = SystemBackend::detect()
+        .await
+        .with_cim(config.settings.uses_cim_backend());
+    let include_disconnected = config.settings.shows_disconnected_interfaces();
+    state.config = config;
+    match get_network_interfaces(include_disconnected) {
+        Ok(interfaces) => {
+            state.set_interfaces(interfaces);
+        }
+        Err(e) => return fail(json, t!("get-interfaces-failed", error = e)),
+    }
+    if let Some(selection) = state.config.last_selection.clone() {
+        state.restore_selection(&selection);
+    }
+    if let Some(interface) = state.selected_interface().cloned() {
+        match state.backend.get_current_dns(&interface).await {
+            Ok(current) => state.current_dns_state = current,
+            Err(e) => return fail(json, e.to_string()),
+        }
+    }
+
+    match command.request {
+        ControlRequest::Status => {
+            let status = state.control_status();
+            print(json, &status, status_text(&status));
+            i32::from(status.interface.is_none())
+        }
+        ControlRequest::List => {
+            let profiles = state.control_profiles();
+            print(json, &profiles, profiles_text(&profiles));
+            0
+        }
+        ControlRequest::Apply { profile } => apply(state, profile.as_deref(), json).await,
+    }
+}
+
+/// Applies the profile named `profile`, or Automatic for `None`, the way
+/// the tray menu does. Hook commands of the profile are not run.
+async fn apply(mut state: AppState, profile: Option<&str>, json: bool) -> i32 {
+    if !state.is_elevated {
+        return fail(json, t!("cli-not-elevated"));
+    }
+    let Some(interface) = state.selected_interface().cloned() else {
+        return fail(json, t!("control-no-interface"));
+    };
+    let (settings, target, name) = match profile {
+        Some(name) => match state.config.find_profile_by_name_or_id(name) {
+            Some(profile) => (
+                profile.settings.clone(),
+                Some(profile.id.clone()),
+                Some(profile.name.clone()),
+            ),
+            None => return fail(json, t!("link-profile-not-found", name = name)),
+        },
+        None => (DnsSettings::new(), None, None),
+    };
+
+    let backend = state.backend;
+    let before = snapshot_dns_settings(&backend, &interface).await.ok();
+    if let Some(before) = &before {
+        state
+            .config
+            .original_dns
+            .entry(interface.interface_guid.clone())
+            .or_insert_with(|| before.clone());
+    }
+    let created_doh_servers = new_doh_servers(&backend, &settings).await;
+    let report = apply_dns_settings(&backend, &interface, &settings).await;
+    let mut steps = report.steps();
+    let result = report.into_result();
+    state.config.record_doh_servers(&created_doh_servers);
+
+    let interface_name = state.config.interface_name(&interface);
+    let mut entry = HistoryEntry::now(
+        &interface.interface_guid,
+        &interface_name,
+        before,
+        settings.clone(),
+    );
+    match &result {
+        Ok(warning) => entry.warning = warning.clone(),
+        Err(e) => entry.error = Some(e.to_string()),
+    }
+    if let Err(e) = append_history(&entry) {
+        eprintln!("Failed to write the history: {}", e);
+    }
+
+    if result.is_ok() {
+        state
+            .config
+            .interface_profiles
+            .insert(interface.interface_guid.clone(), target);
+        if state.config.settings.flushes_cache_after_apply() {
+            let error = clear_dns_cache().await.err().map(|e| e.to_string());
+            steps.push(ApplyStep::new("cache", error));
+        }
+    }
+    if let Err(e) = save_config(&state.config) {
+        steps.push(ApplyStep::new("config", Some(e.to_string())));
+    }
+
+    let result = CliApplyResult::new(interface_name, name, steps);
+    print(json, &result, apply_text(&result));
+    i32::from(!result.ok)
+}
+
+fn print<T: Serialize>(json: bool, value: &T, text: String) {
+    if json {
+        println!("{}", serde_json::to_string(value).unwrap_or_default());
+    } else {
+        println!("{}", text);
+    }
+}
+
+fn fail(json: bool, error: String) -> i32 {
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string(&CliError { error }).unwrap_or_default()
+        );
+    } else {
+        eprintln!("{}", error);
+    }
+    1
+}
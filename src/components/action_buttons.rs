@@ -1,3 +1,5 @@
+use crate::dns::TEMPORARY_APPLY_MINUTES;
+use crate::i18n::t;
 use crate::state::AppState;
 use dioxus::prelude::*;
 
@@ -5,9 +7,24 @@ use dioxus::prelude::*;
 pub fn ActionButtons(
     state: Signal<AppState>,
     on_save: EventHandler<()>,
+    on_cleanup_doh: EventHandler<()>,
+    on_restore_original: EventHandler<()>,
+    on_reset_all: EventHandler<()>,
+    on_flush_cache: EventHandler<()>,
+    on_export_state: EventHandler<()>,
+    on_open_settings: EventHandler<()>,
+    on_preview: EventHandler<()>,
     on_apply: EventHandler<()>,
+    on_apply_for: EventHandler<u64>,
+    on_cancel: EventHandler<()>,
 ) -> Element {
     let is_loading = state.read().is_loading;
+    let doh_available = state.read().doh_unavailable_reason().is_none();
+    let has_original = state.read().original_dns().is_some();
+    // Without administrator rights only the buttons that change the system
+    // are off; profiles can still be edited and saved.
+    let read_only_reason = state.read().read_only_reason();
+    let read_only = read_only_reason.is_some();
 
     rsx! {
         div { class: "button-group",
@@ -15,13 +32,82 @@ pub fn ActionButtons(
                 class: "secondary",
                 disabled: is_loading,
                 onclick: move |_| on_save.call(()),
-                "Save"
+                {t!("save")}
             }
             button {
-                class: "primary",
+                class: "secondary",
+                onclick: move |_| on_open_settings.call(()),
+                {t!("settings")}
+            }
+            button {
+                class: "secondary",
+                disabled: is_loading || !doh_available || read_only,
+                title: read_only_reason.clone().unwrap_or_else(|| t!("cleanup-doh-title")),
+                onclick: move |_| on_cleanup_doh.call(()),
+                {t!("cleanup-doh")}
+            }
+            button {
+                class: "secondary",
+                disabled: is_loading || !has_original || read_only,
+                title: read_only_reason.clone().unwrap_or_else(|| t!("restore-original-title")),
+                onclick: move |_| on_restore_original.call(()),
+                {t!("restore-original")}
+            }
+            button {
+                class: "secondary danger",
+                disabled: is_loading || read_only,
+                title: read_only_reason.clone().unwrap_or_else(|| t!("reset-all-title")),
+                onclick: move |_| on_reset_all.call(()),
+                {t!("reset-all")}
+            }
+            button {
+                class: "secondary",
                 disabled: is_loading,
+                onclick: move |_| on_flush_cache.call(()),
+                {t!("flush-dns-cache")}
+            }
+            button {
+                class: "secondary",
+                title: t!("export-state-title"),
+                onclick: move |_| on_export_state.call(()),
+                {t!("export-state")}
+            }
+            button {
+                class: "secondary",
+                disabled: is_loading,
+                onclick: move |_| on_preview.call(()),
+                {t!("preview")}
+            }
+            button {
+                class: "primary",
+                disabled: is_loading || read_only,
+                title: read_only_reason.unwrap_or_default(),
                 onclick: move |_| on_apply.call(()),
-                if is_loading { "Applying..." } else { "Apply" }
+                if is_loading { {t!("applying")} } else { {t!("apply")} }
+            }
+            select {
+                class: "apply-for",
+                disabled: is_loading || read_only,
+                title: t!("apply-for-title"),
+                // Always shows the prompt, so that picking the same
+                // duration again applies again.
+                value: "",
+                onchange: move |evt: Event<FormData>| {
+                    if let Ok(minutes) = evt.value().parse() {
+                        on_apply_for.call(minutes);
+                    }
+                },
+                option { value: "", disabled: true, {t!("apply-for")} }
+                for minutes in TEMPORARY_APPLY_MINUTES {
+                    option { value: "{minutes}", {t!("apply-for-minutes", minutes = minutes)} }
+                }
+            }
+            if is_loading {
+                button {
+                    class: "secondary",
+                    onclick: move |_| on_cancel.call(()),
+                    {t!("cancel")}
+                }
             }
         }
     }
@@ -0,0 +1,43 @@
+use crate::i18n::t;
+use crate::state::{AppState, MessageLevel};
+use dioxus::prelude::*;
+
+#[component]
+pub fn ActivityPanel(state: Signal<AppState>) -> Element {
+    let entries = state
+        .read()
+        .message_log
+        .iter()
+        .rev()
+        .cloned()
+        .collect::<Vec<_>>();
+
+    rsx! {
+        details { class: "section activity-panel",
+            summary { class: "section-title", {t!("activity-count", count = entries.len())} }
+            if entries.is_empty() {
+                p { class: "nrpt-empty", {t!("no-activity")} }
+            } else {
+                ul { class: "activity-log",
+                    for (index, entry) in entries.into_iter().enumerate() {
+                        li {
+                            key: "{index}",
+                            class: match entry.message.level {
+                                MessageLevel::Success => "activity-entry success",
+                                MessageLevel::Warning => "activity-entry warning",
+                                MessageLevel::Error => "activity-entry error",
+                            },
+                            span { class: "activity-time", "{entry.time}" }
+                            span { "{entry.message.text}" }
+                        }
+                    }
+                }
+                button {
+                    class: "secondary",
+                    onclick: move |_| state.write().clear_message_log(),
+                    {t!("clear-activity")}
+                }
+            }
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use crate::components::use_focus_trap;
+use crate::i18n::t;
 use dioxus::prelude::*;
 
 #[component]
@@ -6,21 +8,33 @@ pub fn DeleteConfirmDialog(
     on_confirm: EventHandler<()>,
     on_cancel: EventHandler<()>,
 ) -> Element {
+    let trap = use_focus_trap(2);
+
     rsx! {
         div { class: "dialog-overlay",
-            div { class: "delete-confirm-dialog",
-                h3 { "Delete Profile" }
-                p { "Are you sure you want to delete \"{profile_name}\"?" }
+            div {
+                class: "delete-confirm-dialog",
+                role: "alertdialog",
+                aria_modal: "true",
+                aria_labelledby: "delete-confirm-title",
+                aria_describedby: "delete-confirm-text",
+                onkeydown: move |evt| trap.keydown(evt, on_cancel),
+                h3 { id: "delete-confirm-title", {t!("delete-profile-title")} }
+                p { id: "delete-confirm-text", {t!("delete-profile-confirm", name = profile_name)} }
                 div { class: "dialog-buttons",
                     button {
                         class: "secondary",
+                        onmounted: move |evt| trap.mounted(0, evt.data()),
+                        onfocus: move |_| trap.focused(0),
                         onclick: move |_| on_cancel.call(()),
-                        "Cancel"
+                        {t!("cancel")}
                     }
                     button {
                         class: "primary danger",
+                        onmounted: move |evt| trap.mounted(1, evt.data()),
+                        onfocus: move |_| trap.focused(1),
                         onclick: move |_| on_confirm.call(()),
-                        "Delete"
+                        {t!("delete")}
                     }
                 }
             }
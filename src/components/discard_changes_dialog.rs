@@ -0,0 +1,43 @@
+use crate::components::use_focus_trap;
+use crate::i18n::t;
+use dioxus::prelude::*;
+
+#[component]
+pub fn DiscardChangesDialog(
+    profile_name: String,
+    on_confirm: EventHandler<()>,
+    on_cancel: EventHandler<()>,
+) -> Element {
+    let trap = use_focus_trap(2);
+
+    rsx! {
+        div { class: "dialog-overlay",
+            div {
+                class: "delete-confirm-dialog",
+                role: "alertdialog",
+                aria_modal: "true",
+                aria_labelledby: "discard-changes-title",
+                aria_describedby: "discard-changes-text",
+                onkeydown: move |evt| trap.keydown(evt, on_cancel),
+                h3 { id: "discard-changes-title", {t!("discard-changes-title")} }
+                p { id: "discard-changes-text", {t!("discard-changes-confirm", name = profile_name)} }
+                div { class: "dialog-buttons",
+                    button {
+                        class: "secondary",
+                        onmounted: move |evt| trap.mounted(0, evt.data()),
+                        onfocus: move |_| trap.focused(0),
+                        onclick: move |_| on_cancel.call(()),
+                        {t!("cancel")}
+                    }
+                    button {
+                        class: "primary danger",
+                        onmounted: move |evt| trap.mounted(1, evt.data()),
+                        onfocus: move |_| trap.focused(1),
+                        onclick: move |_| on_confirm.call(()),
+                        {t!("discard-changes")}
+                    }
+                }
+            }
+        }
+    }
+}
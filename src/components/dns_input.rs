@@ -1,23 +1,53 @@
-use crate::components::{DnsModeSelector, ProfileSelector};
-use crate::dns::{AddressFamily, DnsMode, DnsSettings, DohMode};
-use crate::state::AppState;
+use crate::components::{
+    DnsModeSelector, DnsSuffixInput, NrptInput, ProfileSelector, ProviderSelector,
+};
+use crate::dns::{
+    AddressFamily, DnsEntry, DnsMode, DnsServerEntry, DnsSettings, DohMode, LocalResolver,
+    ProviderDraft, approve_hooks, hooks_approved, looks_like_host_name,
+};
+use crate::i18n::t;
+use crate::state::{AppState, DnsServerErrors, ResolvedHost, dns_server_errors};
 use dioxus::prelude::*;
 
 #[component]
 pub fn DnsInput(
     state: Signal<AppState>,
     on_settings_change: EventHandler<DnsSettings>,
+    on_provider_change: EventHandler<(String, Option<String>)>,
+    on_provider_draft_change: EventHandler<Option<ProviderDraft>>,
+    on_save_provider: EventHandler<()>,
+    on_delete_provider: EventHandler<String>,
+    on_import_doh_providers: EventHandler<()>,
+    on_discover_doh: EventHandler<(AddressFamily, usize)>,
+    on_resolve_host: EventHandler<(AddressFamily, usize)>,
+    on_use_resolved_host: EventHandler<()>,
+    on_copy_servers: EventHandler<AddressFamily>,
     on_mode_change: EventHandler<DnsMode>,
     on_profile_change: EventHandler<String>,
     on_new_profile: EventHandler<()>,
     on_profile_name_change: EventHandler<String>,
+    on_profile_tags_change: EventHandler<String>,
+    on_profile_tag_filter: EventHandler<Option<String>>,
+    on_profile_search: EventHandler<String>,
+    on_profile_color_change: EventHandler<Option<String>>,
     on_delete_profile: EventHandler<()>,
+    on_copy_profile: EventHandler<()>,
+    on_paste_profile: EventHandler<()>,
+    on_export_profiles: EventHandler<()>,
+    on_import_profiles: EventHandler<()>,
 ) -> Element {
     let dns_mode = state.read().dns_mode;
     let settings = state.read().current_settings.clone();
     let interface = state.read().selected_interface().cloned();
+    let doh_unavailable_reason = state.read().doh_unavailable_reason();
+    let resolved_host = state.read().resolved_host.clone();
+    let suffix_unavailable_reason =
+        (!state.read().backend.supports_suffixes()).then(|| t!("suffixes-need-powershell"));
+    let nrpt_unavailable_reason =
+        (!state.read().backend.supports_nrpt()).then(|| t!("nrpt-needs-powershell"));
 
     let is_automatic = dns_mode == DnsMode::Automatic;
+    let is_local_resolver = settings.local_resolver.is_some();
 
     let (has_ipv4, has_ipv6) = interface
         .map(|i| (i.has_ipv4, i.has_ipv6))
@@ -25,7 +55,7 @@ pub fn DnsInput(
 
     rsx! {
         div { class: "section",
-            h2 { class: "section-title", "DNS Settings" }
+            h2 { class: "section-title", {t!("dns-settings")} }
             DnsModeSelector { current_mode: dns_mode, on_change: on_mode_change }
 
             ProfileSelector {
@@ -34,7 +64,66 @@ pub fn DnsInput(
                 on_profile_change: on_profile_change,
                 on_new_profile: on_new_profile,
                 on_name_change: on_profile_name_change,
+                on_tags_change: on_profile_tags_change,
+                on_tag_filter: on_profile_tag_filter,
+                on_search: on_profile_search,
+                on_color_change: on_profile_color_change,
                 on_delete: on_delete_profile,
+                on_copy: on_copy_profile,
+                on_paste: on_paste_profile,
+                on_export: on_export_profiles,
+                on_import: on_import_profiles,
+            }
+
+            ProviderSelector {
+                state: state,
+                disabled: is_automatic,
+                on_change: on_provider_change,
+                on_draft_change: on_provider_draft_change,
+                on_save: on_save_provider,
+                on_delete: on_delete_provider,
+                on_import_doh: on_import_doh_providers,
+            }
+
+            div { class: "checkbox-group",
+                input {
+                    r#type: "checkbox",
+                    id: "local-resolver",
+                    checked: is_local_resolver,
+                    disabled: is_automatic,
+                    onchange: move |evt: Event<FormData>| {
+                        let mut new_settings = state.read().current_settings.clone();
+                        if evt.checked() {
+                            new_settings.use_local_resolver();
+                        } else {
+                            new_settings.local_resolver = None;
+                        }
+                        on_settings_change.call(new_settings);
+                    },
+                }
+                label { r#for: "local-resolver", title: t!("local-resolver-title"), {t!("local-resolver")} }
+            }
+            if let Some(local_resolver) = settings.local_resolver.clone() {
+                div { class: "checkbox-group",
+                    input {
+                        r#type: "checkbox",
+                        id: "local-resolver-check",
+                        checked: local_resolver.check_listening,
+                        disabled: is_automatic,
+                        onchange: move |evt: Event<FormData>| {
+                            let mut new_settings = state.read().current_settings.clone();
+                            new_settings.local_resolver = Some(LocalResolver {
+                                check_listening: evt.checked(),
+                            });
+                            on_settings_change.call(new_settings);
+                        },
+                    }
+                    label {
+                        r#for: "local-resolver-check",
+                        title: t!("local-resolver-check-title"),
+                        {t!("local-resolver-check")}
+                    }
+                }
             }
 
             div { class: "dns-settings-grid",
@@ -42,7 +131,13 @@ pub fn DnsInput(
                     DnsFamilyPanel {
                         family: AddressFamily::IPv4,
                         entry: settings.ipv4.clone(),
-                        disabled: is_automatic,
+                        disabled: is_automatic || is_local_resolver,
+                        doh_unavailable_reason: doh_unavailable_reason.clone(),
+                        resolved_host: resolved_host.clone().filter(|r| r.family == AddressFamily::IPv4),
+                        on_discover: move |index| on_discover_doh.call((AddressFamily::IPv4, index)),
+                        on_resolve: move |index| on_resolve_host.call((AddressFamily::IPv4, index)),
+                        on_use_resolved: on_use_resolved_host,
+                        on_copy: move |_| on_copy_servers.call(AddressFamily::IPv4),
                         on_change: move |entry| {
                             let mut new_settings = state.read().current_settings.clone();
                             new_settings.ipv4 = entry;
@@ -54,7 +149,13 @@ pub fn DnsInput(
                     DnsFamilyPanel {
                         family: AddressFamily::IPv6,
                         entry: settings.ipv6.clone(),
-                        disabled: is_automatic,
+                        disabled: is_automatic || is_local_resolver,
+                        doh_unavailable_reason: doh_unavailable_reason.clone(),
+                        resolved_host: resolved_host.clone().filter(|r| r.family == AddressFamily::IPv6),
+                        on_discover: move |index| on_discover_doh.call((AddressFamily::IPv6, index)),
+                        on_resolve: move |index| on_resolve_host.call((AddressFamily::IPv6, index)),
+                        on_use_resolved: on_use_resolved_host,
+                        on_copy: move |_| on_copy_servers.call(AddressFamily::IPv6),
                         on_change: move |entry| {
                             let mut new_settings = state.read().current_settings.clone();
                             new_settings.ipv6 = entry;
@@ -63,6 +164,108 @@ pub fn DnsInput(
                     }
                 }
             }
+
+            DnsSuffixInput {
+                connection_suffix: settings.connection_suffix.clone(),
+                suffixes: settings.suffixes.clone(),
+                disabled: is_automatic,
+                unavailable_reason: suffix_unavailable_reason,
+                on_connection_suffix_change: move |suffix| {
+                    let mut new_settings = state.read().current_settings.clone();
+                    new_settings.connection_suffix = suffix;
+                    on_settings_change.call(new_settings);
+                },
+                on_change: move |suffixes| {
+                    let mut new_settings = state.read().current_settings.clone();
+                    new_settings.suffixes = suffixes;
+                    on_settings_change.call(new_settings);
+                },
+            }
+
+            NrptInput {
+                nrpt: settings.nrpt.clone(),
+                disabled: is_automatic,
+                unavailable_reason: nrpt_unavailable_reason,
+                on_change: move |nrpt| {
+                    let mut new_settings = state.read().current_settings.clone();
+                    new_settings.nrpt = nrpt;
+                    on_settings_change.call(new_settings);
+                },
+            }
+
+            div { class: "form-group check-host",
+                label { r#for: "check-host", title: t!("check-host-title"), {t!("check-host")} }
+                input {
+                    r#type: "text",
+                    id: "check-host",
+                    placeholder: t!("check-host-placeholder"),
+                    value: settings.check_host.clone().unwrap_or_default(),
+                    disabled: is_automatic,
+                    oninput: move |evt: Event<FormData>| {
+                        let host = evt.value();
+                        let mut new_settings = state.read().current_settings.clone();
+                        new_settings.check_host = (!host.is_empty()).then_some(host);
+                        on_settings_change.call(new_settings);
+                    },
+                }
+            }
+
+            div { class: "form-group apply-hook",
+                label { r#for: "before-apply", title: t!("before-apply-title"), {t!("before-apply")} }
+                input {
+                    r#type: "text",
+                    id: "before-apply",
+                    placeholder: t!("hook-command-placeholder"),
+                    value: settings.before_apply.clone().unwrap_or_default(),
+                    disabled: is_automatic,
+                    oninput: move |evt: Event<FormData>| {
+                        let command = evt.value();
+                        let mut new_settings = state.read().current_settings.clone();
+                        new_settings.before_apply = (!command.is_empty()).then_some(command);
+                        new_settings.hooks_approval = None;
+                        on_settings_change.call(new_settings);
+                    },
+                }
+            }
+
+            div { class: "form-group apply-hook",
+                label { r#for: "after-apply", title: t!("after-apply-title"), {t!("after-apply")} }
+                input {
+                    r#type: "text",
+                    id: "after-apply",
+                    placeholder: t!("hook-command-placeholder"),
+                    value: settings.after_apply.clone().unwrap_or_default(),
+                    disabled: is_automatic,
+                    oninput: move |evt: Event<FormData>| {
+                        let command = evt.value();
+                        let mut new_settings = state.read().current_settings.clone();
+                        new_settings.after_apply = (!command.is_empty()).then_some(command);
+                        new_settings.hooks_approval = None;
+                        on_settings_change.call(new_settings);
+                    },
+                }
+            }
+            if settings.has_hooks() {
+                div { class: "checkbox-group",
+                    input {
+                        r#type: "checkbox",
+                        id: "allow-hooks",
+                        checked: hooks_approved(&settings),
+                        disabled: is_automatic,
+                        onchange: move |evt: Event<FormData>| {
+                            let mut new_settings = state.read().current_settings.clone();
+                            new_settings.hooks_approval = None;
+                            if evt.checked()
+                                && let Err(e) = approve_hooks(&mut new_settings)
+                            {
+                                eprintln!("Failed to allow the commands: {}", e);
+                            }
+                            on_settings_change.call(new_settings);
+                        },
+                    }
+                    label { r#for: "allow-hooks", title: t!("allow-hooks-title"), {t!("allow-hooks")} }
+                }
+            }
         }
     }
 }
@@ -70,16 +273,23 @@ pub fn DnsInput(
 #[component]
 fn DnsFamilyPanel(
     family: AddressFamily,
-    entry: crate::dns::DnsEntry,
+    entry: DnsEntry,
     disabled: bool,
-    on_change: EventHandler<crate::dns::DnsEntry>,
+    doh_unavailable_reason: Option<String>,
+    /// Addresses resolved for a host name typed in one of the servers.
+    resolved_host: Option<ResolvedHost>,
+    on_discover: EventHandler<usize>,
+    on_resolve: EventHandler<usize>,
+    on_use_resolved: EventHandler<()>,
+    on_copy: EventHandler<()>,
+    on_change: EventHandler<DnsEntry>,
 ) -> Element {
     let family_label = family.as_str();
     let is_disabled = disabled || !entry.enabled;
 
-    let (placeholder_primary, placeholder_secondary) = match family {
-        AddressFamily::IPv4 => ("e.g., 8.8.8.8", "e.g., 8.8.4.4"),
-        AddressFamily::IPv6 => ("e.g., 2001:4860:4860::8888", "e.g., 2001:4860:4860::8844"),
+    let placeholders = match family {
+        AddressFamily::IPv4 => ["8.8.8.8", "8.8.4.4"],
+        AddressFamily::IPv6 => ["2001:4860:4860::8888", "2001:4860:4860::8844"],
     };
 
     let id_prefix = match family {
@@ -87,13 +297,47 @@ fn DnsFamilyPanel(
         AddressFamily::IPv6 => "ipv6",
     };
 
+    let removable = entry.servers.len() > 1;
+    let has_addresses = !entry.get_addresses().is_empty();
+
     rsx! {
         div { class: "dns-family-panel",
             div { class: "dns-family-header",
-                span { class: "dns-family-title", "{family_label}" }
+                span { class: "dns-family-title",
+                    "{family_label}"
+                    button {
+                        class: "copy-btn",
+                        disabled: !has_addresses,
+                        title: t!("copy-servers-title"),
+                        onclick: move |_| on_copy.call(()),
+                        {t!("copy")}
+                    }
+                }
+                if !entry.enabled {
+                    label {
+                        class: "family-unchanged",
+                        title: t!("leave-family-unchanged-title", family = family_label),
+                        input {
+                            r#type: "checkbox",
+                            checked: entry.unchanged,
+                            disabled: disabled,
+                            onchange: {
+                                let entry = entry.clone();
+                                move |evt: Event<FormData>| {
+                                    let mut new_entry = entry.clone();
+                                    new_entry.unchanged = evt.checked();
+                                    on_change.call(new_entry);
+                                }
+                            },
+                        }
+                        {t!("leave-family-unchanged")}
+                    }
+                }
                 label { class: "toggle-switch",
                     input {
                         r#type: "checkbox",
+                        role: "switch",
+                        aria_label: t!("use-family-servers", family = family_label),
                         checked: entry.enabled,
                         disabled: disabled,
                         onchange: {
@@ -109,36 +353,72 @@ fn DnsFamilyPanel(
                 }
             }
 
-            DnsServerInput {
-                id_prefix: format!("{}-primary", id_prefix),
-                label: "Primary DNS",
-                placeholder: placeholder_primary.to_string(),
-                server: entry.primary.clone(),
-                disabled: is_disabled,
-                on_change: {
-                    let entry = entry.clone();
-                    move |server| {
-                        let mut new_entry = entry.clone();
-                        new_entry.primary = server;
-                        on_change.call(new_entry);
-                    }
-                },
+            for (index, server) in entry.servers.iter().enumerate() {
+                DnsServerInput {
+                    key: "{id_prefix}-{index}",
+                    id_prefix: format!("{}-{}", id_prefix, index),
+                    label: match index {
+                        0 => t!("dns-server-primary"),
+                        1 => t!("dns-server-secondary"),
+                        _ => t!("dns-server-numbered", number = index + 1),
+                    },
+                    placeholder: placeholders
+                        .get(index)
+                        .map(|example| t!("example", example = example))
+                        .unwrap_or_default(),
+                    server: server.clone(),
+                    errors: if is_disabled {
+                        DnsServerErrors::default()
+                    } else {
+                        dns_server_errors(server, family, index == 0)
+                    },
+                    disabled: is_disabled,
+                    doh_unavailable_reason: doh_unavailable_reason.clone(),
+                    removable: removable,
+                    resolved_addresses: resolved_host
+                        .as_ref()
+                        .filter(|r| r.index == index && r.host == server.address.trim())
+                        .map(|r| {
+                            r.addresses
+                                .iter()
+                                .map(ToString::to_string)
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        }),
+                    on_discover: move |_| on_discover.call(index),
+                    on_resolve: move |_| on_resolve.call(index),
+                    on_use_resolved: on_use_resolved,
+                    on_change: {
+                        let entry = entry.clone();
+                        move |server| {
+                            let mut new_entry = entry.clone();
+                            new_entry.servers[index] = server;
+                            on_change.call(new_entry);
+                        }
+                    },
+                    on_remove: {
+                        let entry = entry.clone();
+                        move |_| {
+                            let mut new_entry = entry.clone();
+                            new_entry.servers.remove(index);
+                            on_change.call(new_entry);
+                        }
+                    },
+                }
             }
 
-            DnsServerInput {
-                id_prefix: format!("{}-secondary", id_prefix),
-                label: "Secondary DNS",
-                placeholder: placeholder_secondary.to_string(),
-                server: entry.secondary.clone(),
+            button {
+                class: "secondary add-server-btn",
                 disabled: is_disabled,
-                on_change: {
+                onclick: {
                     let entry = entry.clone();
-                    move |server| {
+                    move |_| {
                         let mut new_entry = entry.clone();
-                        new_entry.secondary = server;
+                        new_entry.servers.push(DnsServerEntry::default());
                         on_change.call(new_entry);
                     }
                 },
+                {t!("add-dns-server")}
             }
         }
     }
@@ -149,19 +429,51 @@ fn DnsServerInput(
     id_prefix: String,
     label: String,
     placeholder: String,
-    server: crate::dns::DnsServerEntry,
+    server: DnsServerEntry,
+    errors: DnsServerErrors,
     disabled: bool,
-    on_change: EventHandler<crate::dns::DnsServerEntry>,
+    doh_unavailable_reason: Option<String>,
+    removable: bool,
+    /// Addresses the host name in the address field resolved to, if it was
+    /// resolved.
+    resolved_addresses: Option<String>,
+    on_discover: EventHandler<()>,
+    on_resolve: EventHandler<()>,
+    on_use_resolved: EventHandler<()>,
+    on_change: EventHandler<DnsServerEntry>,
+    on_remove: EventHandler<()>,
 ) -> Element {
     let doh_enabled = server.doh_mode == DohMode::On;
+    let is_host_name = looks_like_host_name(&server.address);
 
     rsx! {
         div { class: "dns-server-section",
             div { class: "form-group",
-                label { r#for: "{id_prefix}-address", "{label}" }
+                div { class: "dns-server-label",
+                    label { r#for: "{id_prefix}-address", "{label}" }
+                    if is_host_name {
+                        button {
+                            class: "remove-server-btn",
+                            disabled: disabled,
+                            title: t!("resolve-host-title"),
+                            onclick: move |_| on_resolve.call(()),
+                            {t!("resolve")}
+                        }
+                    }
+                    if removable {
+                        button {
+                            class: "remove-server-btn",
+                            disabled: disabled,
+                            title: t!("remove-server-title"),
+                            onclick: move |_| on_remove.call(()),
+                            {t!("remove")}
+                        }
+                    }
+                }
                 input {
                     r#type: "text",
                     id: "{id_prefix}-address",
+                    class: if errors.address.is_some() { "invalid" },
                     placeholder: "{placeholder}",
                     value: "{server.address}",
                     disabled: disabled,
@@ -174,13 +486,32 @@ fn DnsServerInput(
                         }
                     },
                 }
+                if let Some(error) = &errors.address {
+                    p { class: "field-error", "{error}" }
+                } else if let Some(warning) = &errors.address_warning {
+                    p { class: "field-warning", "{warning}" }
+                }
+                if let Some(addresses) = resolved_addresses {
+                    div { class: "resolved-addresses",
+                        span {
+                            {t!("resolved-addresses", host = server.address.trim(), addresses = addresses)}
+                        }
+                        button {
+                            class: "secondary",
+                            disabled: disabled,
+                            onclick: move |_| on_use_resolved.call(()),
+                            {t!("use-resolved-addresses")}
+                        }
+                    }
+                }
             }
 
             div { class: "form-group",
-                label { r#for: "{id_prefix}-doh", "DNS over HTTPS" }
+                label { r#for: "{id_prefix}-doh", {t!("dns-over-https")} }
                 select {
                     id: "{id_prefix}-doh",
-                    disabled: disabled,
+                    disabled: disabled || doh_unavailable_reason.is_some(),
+                    title: doh_unavailable_reason.clone().unwrap_or_default(),
                     value: if doh_enabled { "on" } else { "off" },
                     onchange: {
                         let server = server.clone();
@@ -194,18 +525,28 @@ fn DnsServerInput(
                             on_change.call(new_server);
                         }
                     },
-                    option { value: "off", "Off" }
-                    option { value: "on", "On (manual template)" }
+                    option { value: "off", {t!("doh-off")} }
+                    option { value: "on", {t!("doh-on-manual")} }
                 }
             }
 
             if doh_enabled {
                 div { class: "doh-options",
                     div { class: "form-group",
-                        label { r#for: "{id_prefix}-template", "DoH Template URL" }
+                        div { class: "dns-server-label",
+                            label { r#for: "{id_prefix}-template", {t!("doh-template-url")} }
+                            button {
+                                class: "remove-server-btn",
+                                disabled: disabled || server.address.trim().is_empty(),
+                                title: t!("discover-doh-title"),
+                                onclick: move |_| on_discover.call(()),
+                                {t!("discover")}
+                            }
+                        }
                         input {
                             r#type: "text",
                             id: "{id_prefix}-template",
+                            class: if errors.doh_template.is_some() { "invalid" },
                             placeholder: "https://dns.example.com/dns-query",
                             value: "{server.doh_template}",
                             disabled: disabled,
@@ -218,6 +559,11 @@ fn DnsServerInput(
                                 }
                             },
                         }
+                        if let Some(error) = &errors.doh_template {
+                            p { class: "field-error", "{error}" }
+                        } else if let Some(warning) = &errors.doh_template_warning {
+                            p { class: "field-warning", "{warning}" }
+                        }
                     }
 
                     div { class: "checkbox-group",
@@ -235,7 +581,29 @@ fn DnsServerInput(
                                 }
                             },
                         }
-                        label { r#for: "{id_prefix}-fallback", "Allow fallback to plaintext" }
+                        label { r#for: "{id_prefix}-fallback", {t!("allow-plaintext-fallback")} }
+                    }
+
+                    div { class: "checkbox-group",
+                        input {
+                            r#type: "checkbox",
+                            id: "{id_prefix}-auto-upgrade",
+                            checked: server.auto_upgrade,
+                            disabled: disabled,
+                            onchange: {
+                                let server = server.clone();
+                                move |evt: Event<FormData>| {
+                                    let mut new_server = server.clone();
+                                    new_server.auto_upgrade = evt.checked();
+                                    on_change.call(new_server);
+                                }
+                            },
+                        }
+                        label {
+                            r#for: "{id_prefix}-auto-upgrade",
+                            title: t!("auto-upgrade-title"),
+                            {t!("auto-upgrade")}
+                        }
                     }
                 }
             }
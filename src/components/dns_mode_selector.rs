@@ -1,4 +1,5 @@
 use crate::dns::DnsMode;
+use crate::i18n::t;
 use dioxus::prelude::*;
 
 #[component]
@@ -14,7 +15,7 @@ pub fn DnsModeSelector(current_mode: DnsMode, on_change: EventHandler<DnsMode>)
                         checked: current_mode == DnsMode::Automatic,
                         onchange: move |_| on_change.call(DnsMode::Automatic)
                     }
-                    label { r#for: "mode-automatic", "Automatic (DHCP)" }
+                    label { r#for: "mode-automatic", {t!("mode-automatic-dhcp")} }
                 }
                 div { class: "radio-option",
                     input {
@@ -24,7 +25,7 @@ pub fn DnsModeSelector(current_mode: DnsMode, on_change: EventHandler<DnsMode>)
                         checked: current_mode == DnsMode::Manual,
                         onchange: move |_| on_change.call(DnsMode::Manual)
                     }
-                    label { r#for: "mode-manual", "Manual" }
+                    label { r#for: "mode-manual", {t!("mode-manual")} }
                 }
             }
         }
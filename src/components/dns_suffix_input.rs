@@ -0,0 +1,124 @@
+use crate::dns::DnsSuffixSettings;
+use crate::i18n::t;
+use dioxus::prelude::*;
+
+#[component]
+pub fn DnsSuffixInput(
+    connection_suffix: Option<String>,
+    suffixes: DnsSuffixSettings,
+    disabled: bool,
+    unavailable_reason: Option<String>,
+    on_connection_suffix_change: EventHandler<Option<String>>,
+    on_change: EventHandler<DnsSuffixSettings>,
+) -> Element {
+    let disabled = disabled || unavailable_reason.is_some();
+    let title = unavailable_reason.unwrap_or_default();
+    let search_list_disabled = disabled || !suffixes.enabled;
+
+    rsx! {
+        div { class: "dns-family-panel dns-suffix-panel",
+            div { class: "dns-family-header",
+                span { class: "dns-family-title", {t!("dns-suffixes")} }
+            }
+
+            div { class: "checkbox-group", title: "{title}",
+                input {
+                    r#type: "checkbox",
+                    id: "connection-suffix-enabled",
+                    checked: connection_suffix.is_some(),
+                    disabled: disabled,
+                    onchange: move |evt: Event<FormData>| {
+                        on_connection_suffix_change.call(evt.checked().then(String::new));
+                    },
+                }
+                label { r#for: "connection-suffix-enabled", {t!("set-connection-suffix")} }
+            }
+
+            if let Some(suffix) = connection_suffix.clone() {
+                div { class: "form-group",
+                    input {
+                        r#type: "text",
+                        id: "connection-suffix",
+                        placeholder: t!("connection-suffix-placeholder"),
+                        value: "{suffix}",
+                        disabled: disabled,
+                        oninput: move |evt: Event<FormData>| {
+                            on_connection_suffix_change.call(Some(evt.value()));
+                        },
+                    }
+                }
+            }
+
+            div { class: "checkbox-group", title: "{title}",
+                input {
+                    r#type: "checkbox",
+                    id: "search-list-enabled",
+                    checked: suffixes.enabled,
+                    disabled: disabled,
+                    onchange: {
+                        let suffixes = suffixes.clone();
+                        move |evt: Event<FormData>| {
+                            let mut new_suffixes = suffixes.clone();
+                            new_suffixes.enabled = evt.checked();
+                            on_change.call(new_suffixes);
+                        }
+                    },
+                }
+                label { r#for: "search-list-enabled", {t!("replace-search-list")} }
+            }
+
+            for (index, suffix) in suffixes.search_list.iter().enumerate() {
+                div { key: "search-suffix-{index}", class: "form-group",
+                    div { class: "dns-server-label",
+                        label { r#for: "search-suffix-{index}",
+                            {t!("search-suffix-numbered", number = index + 1)}
+                        }
+                        button {
+                            class: "remove-server-btn",
+                            disabled: search_list_disabled,
+                            title: t!("remove-suffix-title"),
+                            onclick: {
+                                let suffixes = suffixes.clone();
+                                move |_| {
+                                    let mut new_suffixes = suffixes.clone();
+                                    new_suffixes.search_list.remove(index);
+                                    on_change.call(new_suffixes);
+                                }
+                            },
+                            {t!("remove")}
+                        }
+                    }
+                    input {
+                        r#type: "text",
+                        id: "search-suffix-{index}",
+                        placeholder: t!("example", example = "example.com"),
+                        value: "{suffix}",
+                        disabled: search_list_disabled,
+                        oninput: {
+                            let suffixes = suffixes.clone();
+                            move |evt: Event<FormData>| {
+                                let mut new_suffixes = suffixes.clone();
+                                new_suffixes.search_list[index] = evt.value();
+                                on_change.call(new_suffixes);
+                            }
+                        },
+                    }
+                }
+            }
+
+            button {
+                class: "secondary add-server-btn",
+                disabled: search_list_disabled,
+                onclick: {
+                    let suffixes = suffixes.clone();
+                    move |_| {
+                        let mut new_suffixes = suffixes.clone();
+                        new_suffixes.search_list.push(String::new());
+                        on_change.call(new_suffixes);
+                    }
+                },
+                {t!("add-search-suffix")}
+            }
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use crate::dns::DnsMode;
+use crate::i18n::t;
+use crate::state::{AppState, MessageLevel};
+use dioxus::prelude::*;
+
+#[component]
+pub fn DnssecPanel(state: Signal<AppState>, on_check: EventHandler<()>) -> Element {
+    let (server, result, subject, is_checking) = {
+        let state = state.read();
+        let server = state.dnssec_server();
+        let result = server
+            .as_ref()
+            .and_then(|s| state.dnssec_results.get(s))
+            .cloned();
+        let subject = if state.dns_mode == DnsMode::Manual {
+            t!("profile-named", name = state.current_profile_name)
+        } else {
+            t!("current-dns")
+        };
+        (server, result, subject, state.is_checking_dnssec)
+    };
+
+    rsx! {
+        div { class: "section",
+            div { class: "nrpt-list-header",
+                h2 { class: "section-title", {t!("dnssec-validation")} }
+                button {
+                    class: "secondary",
+                    disabled: server.is_none() || is_checking,
+                    title: t!("dnssec-check-title"),
+                    onclick: move |_| on_check.call(()),
+                    if is_checking { {t!("checking")} } else { {t!("check")} }
+                }
+            }
+
+            if let Some(server) = server {
+                p { class: "nrpt-empty",
+                    "{subject} ({server}): "
+                    if let Some(result) = result {
+                        span {
+                            class: if result.level == MessageLevel::Success { "dnssec-result resolution-ok" } else { "dnssec-result resolution-failed" },
+                            "{result.text}"
+                        }
+                    } else {
+                        {t!("not-checked-yet")}
+                    }
+                }
+            } else {
+                p { class: "nrpt-empty", {t!("no-dns-server-to-check")} }
+            }
+        }
+    }
+}
@@ -0,0 +1,16 @@
+use crate::i18n::t;
+use dioxus::prelude::*;
+
+#[component]
+pub fn ElevationBanner(on_restart: EventHandler<()>) -> Element {
+    rsx! {
+        div { class: "elevation-banner",
+            span { {t!("elevation-warning")} }
+            button {
+                class: "secondary",
+                onclick: move |_| on_restart.call(()),
+                {t!("restart-as-admin")}
+            }
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use dioxus::prelude::*;
+use std::rc::Rc;
+
+/// Keeps keyboard focus inside a dialog with a few buttons: the first one
+/// is focused when the dialog opens, Tab and Shift+Tab cycle through them,
+/// and Escape cancels.
+#[derive(Clone, Copy)]
+pub struct FocusTrap {
+    buttons: Signal<Vec<Option<Rc<MountedData>>>>,
+    focused: Signal<usize>,
+}
+
+pub fn use_focus_trap(button_count: usize) -> FocusTrap {
+    FocusTrap {
+        buttons: use_signal(|| vec![None; button_count]),
+        focused: use_signal(|| 0),
+    }
+}
+
+impl FocusTrap {
+    /// Remembers the button at `index`, focusing it if it is the first.
+    pub fn mounted(mut self, index: usize, element: Rc<MountedData>) {
+        if index == 0 {
+            focus(element.clone());
+        }
+        self.buttons.write()[index] = Some(element);
+    }
+
+    pub fn focused(mut self, index: usize) {
+        self.focused.set(index);
+    }
+
+    /// Handles a key pressed anywhere in the dialog.
+    pub fn keydown(self, evt: KeyboardEvent, on_cancel: EventHandler<()>) {
+        match evt.key() {
+            Key::Escape => on_cancel.call(()),
+            Key::Tab => {
+                evt.prevent_default();
+                let count = self.buttons.read().len();
+                let current = *self.focused.read();
+                let next = if evt.modifiers().contains(Modifiers::SHIFT) {
+                    (current + count - 1) % count
+                } else {
+                    (current + 1) % count
+                };
+                if let Some(element) = self.buttons.read()[next].clone() {
+                    focus(element);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn focus(element: Rc<MountedData>) {
+    spawn(async move {
+        let _ = element.set_focus(true).await;
+    });
+}
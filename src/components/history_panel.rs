@@ -0,0 +1,93 @@
+use crate::dns::{DnsSettings, DohMode, HistoryEntry};
+use crate::i18n::t;
+use crate::state::AppState;
+use dioxus::prelude::*;
+
+/// The servers `settings` set, or Automatic when they leave both families
+/// to DHCP.
+fn describe_settings(settings: &DnsSettings) -> String {
+    let servers: Vec<String> = [&settings.ipv4, &settings.ipv6]
+        .into_iter()
+        .filter(|entry| entry.enabled)
+        .flat_map(|entry| &entry.servers)
+        .filter(|server| !server.address.trim().is_empty())
+        .map(|server| match server.doh_mode {
+            DohMode::On => format!("{} (DoH)", server.address.trim()),
+            DohMode::Off => server.address.trim().to_string(),
+        })
+        .collect();
+    if servers.is_empty() {
+        t!("automatic")
+    } else {
+        servers.join(", ")
+    }
+}
+
+#[component]
+pub fn HistoryPanel(state: Signal<AppState>, on_revert: EventHandler<HistoryEntry>) -> Element {
+    let entries = state
+        .read()
+        .history
+        .iter()
+        .rev()
+        .cloned()
+        .collect::<Vec<_>>();
+    let is_loading = state.read().is_loading;
+    let read_only_reason = state.read().read_only_reason();
+
+    rsx! {
+        details { class: "section activity-panel",
+            summary { class: "section-title", {t!("history-count", count = entries.len())} }
+            if entries.is_empty() {
+                p { class: "nrpt-empty", {t!("no-history")} }
+            } else {
+                ul { class: "activity-log history-log",
+                    for (index, entry) in entries.into_iter().enumerate() {
+                        li {
+                            key: "{index}",
+                            class: if entry.error.is_some() {
+                                "activity-entry history-entry error"
+                            } else if entry.warning.is_some() {
+                                "activity-entry history-entry warning"
+                            } else {
+                                "activity-entry history-entry"
+                            },
+                            span { class: "activity-time", "{entry.time}" }
+                            div { class: "history-info",
+                                span { class: "history-interface", "{entry.interface_name}" }
+                                span {
+                                    {
+                                        t!(
+                                            "history-change",
+                                            before = entry
+                                                .before
+                                                .as_ref()
+                                                .map(describe_settings)
+                                                .unwrap_or_else(|| t!("history-unknown")),
+                                            after = describe_settings(&entry.after)
+                                        )
+                                    }
+                                }
+                                if let Some(error) = entry.error.clone() {
+                                    span { {t!("history-failed", error = error)} }
+                                } else if let Some(warning) = entry.warning.clone() {
+                                    span { "{warning}" }
+                                }
+                            }
+                            button {
+                                class: "secondary",
+                                disabled: is_loading || read_only_reason.is_some() || !entry.can_revert(),
+                                title: read_only_reason.clone().unwrap_or_else(|| t!("revert-title")),
+                                onclick: {
+                                    let entry = entry.clone();
+                                    move |_| on_revert.call(entry.clone())
+                                },
+                                {t!("revert")}
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use crate::dns::{HOTKEY_SLOTS, hotkey_label};
+use crate::i18n::t;
+use crate::state::AppState;
+use dioxus::prelude::*;
+
+#[component]
+pub fn HotkeysPanel(
+    state: Signal<AppState>,
+    on_change: EventHandler<(u8, Option<String>)>,
+) -> Element {
+    let (profiles, assignments) = {
+        let state = state.read();
+        let profiles = state
+            .sorted_profiles()
+            .into_iter()
+            .map(|p| (p.id.clone(), p.name.clone()))
+            .collect::<Vec<_>>();
+        let assignments = HOTKEY_SLOTS
+            .map(|slot| {
+                (
+                    slot,
+                    hotkey_label(slot),
+                    state.config.hotkeys.get(&slot).cloned(),
+                )
+            })
+            .collect::<Vec<_>>();
+        (profiles, assignments)
+    };
+
+    rsx! {
+        div { class: "section",
+            h2 { class: "section-title", {t!("hotkeys")} }
+            p { class: "nrpt-empty",
+                {t!("hotkeys-intro")}
+            }
+
+            for (slot, label, assigned) in assignments {
+                div { key: "{slot}", class: "network-rule",
+                    span { class: "hotkey-label", "{label}" }
+                    select {
+                        value: "{assigned.clone().unwrap_or_default()}",
+                        onchange: move |evt: Event<FormData>| {
+                            let id = evt.value();
+                            on_change.call((slot, (!id.is_empty()).then_some(id)));
+                        },
+                        option {
+                            value: "",
+                            selected: assigned.is_none(),
+                            {t!("hotkey-none")}
+                        }
+                        for (id, name) in profiles.iter() {
+                            option {
+                                value: "{id}",
+                                selected: assigned.as_ref() == Some(id),
+                                "{name}"
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
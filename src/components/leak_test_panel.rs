@@ -0,0 +1,57 @@
+use crate::i18n::t;
+use crate::state::AppState;
+use dioxus::prelude::*;
+
+#[component]
+pub fn LeakTestPanel(state: Signal<AppState>, on_run: EventHandler<()>) -> Element {
+    let result = state.read().leak_test.clone();
+    let is_running = state.read().is_leak_testing;
+    let is_loading = state.read().is_loading;
+
+    rsx! {
+        div { class: "section",
+            div { class: "nrpt-list-header",
+                h2 { class: "section-title", {t!("leak-test")} }
+                button {
+                    class: "secondary",
+                    disabled: is_loading || is_running,
+                    title: t!("leak-test-title"),
+                    onclick: move |_| on_run.call(()),
+                    if is_running { {t!("testing")} } else { {t!("run-leak-test")} }
+                }
+            }
+
+            if let Some(result) = result {
+                if let Some(conclusion) = result.conclusion.clone() {
+                    p { class: "leak-test-conclusion", "{conclusion}" }
+                }
+                if result.resolvers.is_empty() {
+                    p { class: "nrpt-empty", {t!("no-leak-resolvers")} }
+                } else {
+                    table { class: "nrpt-table",
+                        thead {
+                            tr {
+                                th { {t!("resolver")} }
+                                th { {t!("country")} }
+                                th { {t!("network")} }
+                            }
+                        }
+                        tbody {
+                            for resolver in result.resolvers {
+                                tr { key: "{resolver.ip}",
+                                    td { "{resolver.ip}" }
+                                    td { "{resolver.country_name}" }
+                                    td { "{resolver.asn}" }
+                                }
+                            }
+                        }
+                    }
+                }
+            } else {
+                p { class: "nrpt-empty",
+                    {t!("leak-test-intro")}
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use crate::dns::{LookupQuery, RecordType};
+use crate::i18n::t;
+use crate::state::AppState;
+use dioxus::prelude::*;
+
+#[component]
+pub fn LookupTool(
+    state: Signal<AppState>,
+    on_change: EventHandler<LookupQuery>,
+    on_run: EventHandler<()>,
+) -> Element {
+    let (query, result, is_running, current_server) = {
+        let state = state.read();
+        let current = &state.current_dns_state;
+        let current_server = current.ipv4.iter().chain(&current.ipv6).next().cloned();
+        (
+            state.lookup_query.clone(),
+            state.lookup_result.clone(),
+            state.is_looking_up,
+            current_server,
+        )
+    };
+    let can_run = !is_running
+        && !query.name.trim().is_empty()
+        && (!query.server.trim().is_empty() || current_server.is_some());
+    let server_placeholder = current_server
+        .map(|server| t!("current-server", server = server))
+        .unwrap_or_else(|| t!("server-address"));
+
+    let name_query = query.clone();
+    let type_query = query.clone();
+    let server_query = query.clone();
+
+    rsx! {
+        div { class: "section",
+            h2 { class: "section-title", {t!("dns-lookup")} }
+            div { class: "lookup-form",
+                input {
+                    r#type: "text",
+                    class: "lookup-name",
+                    placeholder: t!("lookup-name"),
+                    value: "{query.name}",
+                    oninput: move |evt| {
+                        on_change.call(LookupQuery {
+                            name: evt.value(),
+                            ..name_query.clone()
+                        })
+                    },
+                }
+                select {
+                    value: "{query.record_type.as_str()}",
+                    onchange: move |evt| {
+                        if let Some(record_type) = RecordType::parse(&evt.value()) {
+                            on_change.call(LookupQuery {
+                                record_type,
+                                ..type_query.clone()
+                            });
+                        }
+                    },
+                    for record_type in RecordType::ALL {
+                        option {
+                            value: "{record_type.as_str()}",
+                            selected: record_type == query.record_type,
+                            "{record_type.as_str()}"
+                        }
+                    }
+                }
+                input {
+                    r#type: "text",
+                    class: "lookup-server",
+                    placeholder: "{server_placeholder}",
+                    value: "{query.server}",
+                    oninput: move |evt| {
+                        on_change.call(LookupQuery {
+                            server: evt.value(),
+                            ..server_query.clone()
+                        })
+                    },
+                }
+                button {
+                    class: "secondary",
+                    disabled: !can_run,
+                    onclick: move |_| on_run.call(()),
+                    if is_running { {t!("looking-up")} } else { {t!("look-up")} }
+                }
+            }
+
+            if let Some(Ok(response)) = result {
+                p { class: "nrpt-empty",
+                    {t!(
+                        "lookup-summary",
+                        status = response.status,
+                        server = response.server,
+                        elapsed = response.elapsed.as_millis()
+                    )}
+                }
+                if !response.records.is_empty() {
+                    table { class: "nrpt-table",
+                        thead {
+                            tr {
+                                th { {t!("name")} }
+                                th { {t!("record-type")} }
+                                th { "TTL" }
+                                th { {t!("record-data")} }
+                            }
+                        }
+                        tbody {
+                            for record in response.records {
+                                tr {
+                                    td { "{record.name}" }
+                                    td { "{record.record_type}" }
+                                    td { "{record.ttl}" }
+                                    td { class: "lookup-data", "{record.data}" }
+                                }
+                            }
+                        }
+                    }
+                }
+            } else if let Some(Err(error)) = result {
+                p { class: "lookup-error", "{error}" }
+            }
+        }
+    }
+}
@@ -1,15 +1,51 @@
 mod action_buttons;
+mod activity_panel;
 mod delete_confirm_dialog;
+mod discard_changes_dialog;
 mod dns_input;
 mod dns_mode_selector;
+mod dns_suffix_input;
+mod dnssec_panel;
+mod elevation_banner;
+mod focus_trap;
+mod history_panel;
+mod hotkeys_panel;
+mod leak_test_panel;
+mod lookup_tool;
+mod network_rules_panel;
 mod network_selector;
+mod nrpt_input;
+mod nrpt_rule_list;
+mod preview_dialog;
 mod profile_selector;
+mod provider_selector;
+mod restore_backup_dialog;
+mod schedule_panel;
+mod settings_dialog;
 mod status_bar;
 
 pub use action_buttons::ActionButtons;
+pub use activity_panel::ActivityPanel;
 pub use delete_confirm_dialog::DeleteConfirmDialog;
+pub use discard_changes_dialog::DiscardChangesDialog;
 pub use dns_input::DnsInput;
 pub use dns_mode_selector::DnsModeSelector;
+pub use dns_suffix_input::DnsSuffixInput;
+pub use dnssec_panel::DnssecPanel;
+pub use elevation_banner::ElevationBanner;
+pub use focus_trap::use_focus_trap;
+pub use history_panel::HistoryPanel;
+pub use hotkeys_panel::HotkeysPanel;
+pub use leak_test_panel::LeakTestPanel;
+pub use lookup_tool::LookupTool;
+pub use network_rules_panel::NetworkRulesPanel;
 pub use network_selector::NetworkSelector;
+pub use nrpt_input::NrptInput;
+pub use nrpt_rule_list::NrptRuleList;
+pub use preview_dialog::PreviewDialog;
 pub use profile_selector::ProfileSelector;
+pub use provider_selector::ProviderSelector;
+pub use restore_backup_dialog::RestoreBackupDialog;
+pub use schedule_panel::SchedulePanel;
+pub use settings_dialog::SettingsDialog;
 pub use status_bar::StatusBar;
@@ -0,0 +1,179 @@
+use crate::dns::{NetworkCondition, NetworkRule, NetworkRules};
+use crate::i18n::t;
+use crate::state::AppState;
+use dioxus::prelude::*;
+
+#[component]
+pub fn NetworkRulesPanel(
+    state: Signal<AppState>,
+    on_change: EventHandler<NetworkRules>,
+    on_save: EventHandler<()>,
+) -> Element {
+    let (rules, has_changes, profiles, network) = {
+        let state = state.read();
+        let profiles = state
+            .sorted_profiles()
+            .into_iter()
+            .map(|p| (p.id.clone(), p.name.clone()))
+            .collect::<Vec<_>>();
+        let network = state
+            .selected_interface()
+            .and_then(|i| state.networks.get(&i.interface_guid))
+            .map(|network| {
+                let ssid = network.ssid.clone().unwrap_or_else(|| "-".to_string());
+                let mac = network
+                    .gateway_mac
+                    .clone()
+                    .unwrap_or_else(|| "-".to_string());
+                (ssid, mac)
+            });
+        (
+            state.network_rules().clone(),
+            state.network_rules_draft.is_some(),
+            profiles,
+            network,
+        )
+    };
+
+    rsx! {
+        div { class: "section",
+            div { class: "nrpt-list-header",
+                h2 { class: "section-title", {t!("network-rules")} }
+                button {
+                    class: "secondary",
+                    disabled: !has_changes,
+                    onclick: move |_| on_save.call(()),
+                    {t!("save-rules")}
+                }
+            }
+
+            div { class: "checkbox-group",
+                input {
+                    r#type: "checkbox",
+                    id: "network-rules-enabled",
+                    checked: rules.enabled,
+                    onchange: {
+                        let rules = rules.clone();
+                        move |evt: Event<FormData>| {
+                            let mut new_rules = rules.clone();
+                            new_rules.enabled = evt.checked();
+                            on_change.call(new_rules);
+                        }
+                    },
+                }
+                label { r#for: "network-rules-enabled",
+                    {t!("network-rules-enabled")}
+                }
+            }
+
+            if let Some((ssid, mac)) = network {
+                p { class: "nrpt-empty", {t!("selected-network", ssid = ssid, mac = mac)} }
+            }
+
+            for (index, rule) in rules.rules.iter().enumerate() {
+                div { key: "{rule.id}", class: "network-rule",
+                    select {
+                        value: if matches!(rule.condition, NetworkCondition::Ssid(_)) { "ssid" } else { "gateway" },
+                        onchange: {
+                            let rules = rules.clone();
+                            move |evt: Event<FormData>| {
+                                let mut new_rules = rules.clone();
+                                let value = new_rules.rules[index].condition.value().to_string();
+                                new_rules.rules[index].condition = if evt.value() == "ssid" {
+                                    NetworkCondition::Ssid(value)
+                                } else {
+                                    NetworkCondition::GatewayMac(value)
+                                };
+                                on_change.call(new_rules);
+                            }
+                        },
+                        option {
+                            value: "ssid",
+                            selected: matches!(rule.condition, NetworkCondition::Ssid(_)),
+                            {t!("wifi-ssid")}
+                        }
+                        option {
+                            value: "gateway",
+                            selected: matches!(rule.condition, NetworkCondition::GatewayMac(_)),
+                            {t!("gateway-mac")}
+                        }
+                    }
+                    input {
+                        r#type: "text",
+                        placeholder: condition_placeholder(&rule.condition),
+                        value: "{rule.condition.value()}",
+                        oninput: {
+                            let rules = rules.clone();
+                            move |evt: Event<FormData>| {
+                                let mut new_rules = rules.clone();
+                                new_rules.rules[index].condition = match &new_rules.rules[index].condition {
+                                    NetworkCondition::Ssid(_) => NetworkCondition::Ssid(evt.value()),
+                                    NetworkCondition::GatewayMac(_) => NetworkCondition::GatewayMac(evt.value()),
+                                };
+                                on_change.call(new_rules);
+                            }
+                        },
+                    }
+                    select {
+                        value: "{rule.profile_id.clone().unwrap_or_default()}",
+                        onchange: {
+                            let rules = rules.clone();
+                            move |evt: Event<FormData>| {
+                                let mut new_rules = rules.clone();
+                                let id = evt.value();
+                                new_rules.rules[index].profile_id = (!id.is_empty()).then_some(id);
+                                on_change.call(new_rules);
+                            }
+                        },
+                        option {
+                            value: "",
+                            selected: rule.profile_id.is_none(),
+                            {t!("automatic")}
+                        }
+                        for (id, name) in profiles.iter() {
+                            option {
+                                value: "{id}",
+                                selected: rule.profile_id.as_ref() == Some(id),
+                                "{name}"
+                            }
+                        }
+                    }
+                    button {
+                        class: "remove-server-btn",
+                        title: t!("remove-rule-title"),
+                        onclick: {
+                            let rules = rules.clone();
+                            move |_| {
+                                let mut new_rules = rules.clone();
+                                new_rules.rules.remove(index);
+                                on_change.call(new_rules);
+                            }
+                        },
+                        {t!("remove")}
+                    }
+                }
+            }
+
+            button {
+                class: "secondary add-server-btn",
+                onclick: {
+                    let rules = rules.clone();
+                    move |_| {
+                        let mut new_rules = rules.clone();
+                        new_rules.rules.push(NetworkRule::new());
+                        on_change.call(new_rules);
+                    }
+                },
+                {t!("add-network-rule")}
+            }
+        }
+    }
+}
+
+fn condition_placeholder(condition: &NetworkCondition) -> String {
+    let example = match condition {
+        NetworkCondition::Ssid(_) => "HomeNet",
+        NetworkCondition::GatewayMac(_) => "AA-BB-CC-DD-EE-FF",
+    };
+    t!("example", example = example)
+}
@@ -1,31 +1,211 @@
+use crate::dns::{AppSettings, InterfaceDetails, format_link_speed};
+use crate::i18n::t;
 use crate::state::AppState;
 use dioxus::prelude::*;
+use dioxus_free_icons::Icon;
+use dioxus_free_icons::icons::md_navigation_icons::MdRefresh;
 
 #[component]
-pub fn NetworkSelector(state: Signal<AppState>, on_change: EventHandler<usize>) -> Element {
-    let interfaces = state.read().interfaces.clone();
-    let selected_index = state.read().selected_interface_index;
+pub fn NetworkSelector(
+    state: Signal<AppState>,
+    on_change: EventHandler<String>,
+    on_settings_change: EventHandler<AppSettings>,
+    on_hidden_change: EventHandler<(String, bool)>,
+    on_alias_change: EventHandler<(String, String)>,
+    on_refresh: EventHandler<()>,
+) -> Element {
+    let (interfaces, selected_guid, details, settings, selected_hidden, selected_virtual, vpn) = {
+        let state = state.read();
+        let interfaces: Vec<_> = state
+            .visible_interfaces()
+            .into_iter()
+            .map(|i| {
+                let mut label = state.config.interface_name(i);
+                if i.is_disconnected {
+                    label = t!("disconnected-interface", name = label);
+                }
+                if state.is_interface_hidden(i) {
+                    label = t!("hidden-interface", name = label);
+                }
+                (i.interface_guid.clone(), label, i.is_disconnected)
+            })
+            .collect();
+        let selected = state.selected_interface();
+        (
+            interfaces,
+            state.selected_interface_guid.clone().unwrap_or_default(),
+            state.interface_details.clone(),
+            state.config.settings.clone(),
+            selected.is_some_and(|i| state.config.hidden_interfaces.contains(&i.interface_guid)),
+            selected.is_none_or(|i| i.is_virtual),
+            state
+                .active_vpn()
+                .map(|i| (i.interface_guid.clone(), state.config.interface_name(i))),
+        )
+    };
+    let (alias, raw_name) = {
+        let state = state.read();
+        let selected = state.selected_interface();
+        (
+            selected
+                .and_then(|i| state.config.interface_aliases.get(&i.interface_guid))
+                .cloned()
+                .unwrap_or_default(),
+            selected.map(|i| i.display_name()).unwrap_or_default(),
+        )
+    };
+    let show_all = settings.shows_all_interfaces();
+    let show_disconnected = settings.shows_disconnected_interfaces();
+    let is_loading = state.read().is_loading;
 
     rsx! {
         div { class: "section",
-            h2 { class: "section-title", "Network Interface" }
-            div { class: "form-group",
+            h2 { class: "section-title", {t!("network-interface")} }
+            div { class: "form-group interface-select-row",
                 select {
                     id: "interface-select",
-                    value: "{selected_index}",
-                    onchange: move |evt| {
-                        if let Ok(index) = evt.value().parse::<usize>() {
-                            on_change.call(index);
-                        }
-                    },
-                    for (index, interface) in interfaces.iter().enumerate() {
+                    aria_label: t!("network-interface"),
+                    value: "{selected_guid}",
+                    onchange: move |evt| on_change.call(evt.value()),
+                    for (guid, label, disconnected) in interfaces.iter() {
                         option {
-                            value: "{index}",
-                            selected: index == selected_index,
-                            "{interface.display_name()}"
+                            key: "{guid}",
+                            value: "{guid}",
+                            class: if *disconnected { "disconnected" },
+                            selected: *guid == selected_guid,
+                            "{label}"
                         }
                     }
                 }
+                button {
+                    class: "secondary icon-button",
+                    disabled: is_loading,
+                    aria_label: t!("refresh-interfaces"),
+                    title: t!("refresh-interfaces"),
+                    onclick: move |_| on_refresh.call(()),
+                    Icon { width: 16, height: 16, fill: "currentColor", icon: MdRefresh }
+                }
+            }
+            div { class: "interface-options",
+                input {
+                    r#type: "text",
+                    class: "interface-alias",
+                    aria_label: t!("interface-alias"),
+                    title: t!("interface-alias-title"),
+                    placeholder: "{raw_name}",
+                    value: "{alias}",
+                    disabled: selected_guid.is_empty(),
+                    onchange: {
+                        let guid = selected_guid.clone();
+                        move |evt: Event<FormData>| on_alias_change.call((guid.clone(), evt.value()))
+                    },
+                }
+                div { class: "checkbox-group",
+                    input {
+                        r#type: "checkbox",
+                        id: "show-all-interfaces",
+                        checked: show_all,
+                        onchange: {
+                            let settings = settings.clone();
+                            move |evt: Event<FormData>| {
+                                on_settings_change.call(AppSettings {
+                                    show_all_interfaces: Some(evt.checked()),
+                                    ..settings.clone()
+                                })
+                            }
+                        },
+                    }
+                    label {
+                        r#for: "show-all-interfaces",
+                        title: t!("show-all-interfaces-title"),
+                        {t!("show-all-interfaces")}
+                    }
+                }
+                div { class: "checkbox-group",
+                    input {
+                        r#type: "checkbox",
+                        id: "show-disconnected-interfaces",
+                        checked: show_disconnected,
+                        onchange: move |evt: Event<FormData>| {
+                            on_settings_change.call(AppSettings {
+                                show_disconnected_interfaces: Some(evt.checked()),
+                                ..settings.clone()
+                            })
+                        },
+                    }
+                    label {
+                        r#for: "show-disconnected-interfaces",
+                        title: t!("show-disconnected-interfaces-title"),
+                        {t!("show-disconnected-interfaces")}
+                    }
+                }
+                button {
+                    class: "secondary",
+                    disabled: selected_virtual,
+                    onclick: {
+                        let guid = selected_guid.clone();
+                        move |_| on_hidden_change.call((guid.clone(), !selected_hidden))
+                    },
+                    if selected_hidden {
+                        {t!("unhide-interface")}
+                    } else {
+                        {t!("hide-interface")}
+                    }
+                }
+            }
+            if let Some((vpn_guid, vpn_name)) = vpn {
+                div { class: "vpn-warning", role: "alert",
+                    span { {t!("vpn-active", name = &vpn_name)} }
+                    button {
+                        class: "secondary",
+                        onclick: move |_| on_change.call(vpn_guid.clone()),
+                        {t!("use-vpn-adapter")}
+                    }
+                }
+            }
+            if let Some(details) = details {
+                InterfaceDetailsPanel { details: details }
+            }
+        }
+    }
+}
+
+#[component]
+fn InterfaceDetailsPanel(details: InterfaceDetails) -> Element {
+    let none = t!("not-available");
+    let list = |values: &[String]| {
+        if values.is_empty() {
+            none.clone()
+        } else {
+            values.join(", ")
+        }
+    };
+    let rows = [
+        (t!("adapter-description"), details.description.clone()),
+        (t!("ip-addresses"), list(&details.addresses)),
+        (t!("default-gateway"), list(&details.gateways)),
+        (t!("dhcp-server"), list(&details.dhcp_servers)),
+        (
+            t!("mac-address"),
+            details.mac.clone().unwrap_or_else(|| none.clone()),
+        ),
+        (
+            t!("link-speed"),
+            details
+                .link_speed
+                .map(format_link_speed)
+                .unwrap_or_else(|| none.clone()),
+        ),
+    ];
+
+    rsx! {
+        details { class: "interface-details",
+            summary { {t!("interface-details")} }
+            div { class: "interface-details-grid",
+                for (label, value) in rows {
+                    div { class: "interface-details-label", "{label}" }
+                    div { class: "interface-details-value", "{value}" }
+                }
             }
         }
     }
@@ -0,0 +1,113 @@
+use crate::dns::{NrptRule, NrptSettings};
+use crate::i18n::t;
+use dioxus::prelude::*;
+
+#[component]
+pub fn NrptInput(
+    nrpt: NrptSettings,
+    disabled: bool,
+    unavailable_reason: Option<String>,
+    on_change: EventHandler<NrptSettings>,
+) -> Element {
+    let toggle_disabled = disabled || unavailable_reason.is_some();
+    let is_disabled = toggle_disabled || !nrpt.enabled;
+
+    rsx! {
+        div { class: "dns-family-panel nrpt-panel",
+            div { class: "dns-family-header",
+                span { class: "dns-family-title", {t!("nrpt-rules")} }
+                label {
+                    class: "toggle-switch",
+                    title: unavailable_reason.clone().unwrap_or_default(),
+                    input {
+                        r#type: "checkbox",
+                        role: "switch",
+                        aria_label: t!("use-nrpt-rules"),
+                        checked: nrpt.enabled,
+                        disabled: toggle_disabled,
+                        onchange: {
+                            let nrpt = nrpt.clone();
+                            move |evt: Event<FormData>| {
+                                let mut new_nrpt = nrpt.clone();
+                                new_nrpt.enabled = evt.checked();
+                                on_change.call(new_nrpt);
+                            }
+                        },
+                    }
+                    span { class: "toggle-slider" }
+                }
+            }
+
+            for (index, rule) in nrpt.rules.iter().enumerate() {
+                div { key: "nrpt-rule-{index}", class: "nrpt-rule",
+                    div { class: "form-group",
+                        div { class: "dns-server-label",
+                            label { r#for: "nrpt-namespace-{index}", {t!("namespace")} }
+                            button {
+                                class: "remove-server-btn",
+                                disabled: is_disabled,
+                                title: t!("remove-rule-title"),
+                                onclick: {
+                                    let nrpt = nrpt.clone();
+                                    move |_| {
+                                        let mut new_nrpt = nrpt.clone();
+                                        new_nrpt.rules.remove(index);
+                                        on_change.call(new_nrpt);
+                                    }
+                                },
+                                {t!("remove")}
+                            }
+                        }
+                        input {
+                            r#type: "text",
+                            id: "nrpt-namespace-{index}",
+                            placeholder: t!("example", example = "*.corp.example"),
+                            value: "{rule.namespace}",
+                            disabled: is_disabled,
+                            oninput: {
+                                let nrpt = nrpt.clone();
+                                move |evt: Event<FormData>| {
+                                    let mut new_nrpt = nrpt.clone();
+                                    new_nrpt.rules[index].namespace = evt.value();
+                                    on_change.call(new_nrpt);
+                                }
+                            },
+                        }
+                    }
+                    div { class: "form-group",
+                        label { r#for: "nrpt-servers-{index}", {t!("name-servers")} }
+                        input {
+                            r#type: "text",
+                            id: "nrpt-servers-{index}",
+                            placeholder: t!("example", example = "10.0.0.53, 10.0.1.53"),
+                            value: "{rule.name_servers}",
+                            disabled: is_disabled,
+                            oninput: {
+                                let nrpt = nrpt.clone();
+                                move |evt: Event<FormData>| {
+                                    let mut new_nrpt = nrpt.clone();
+                                    new_nrpt.rules[index].name_servers = evt.value();
+                                    on_change.call(new_nrpt);
+                                }
+                            },
+                        }
+                    }
+                }
+            }
+
+            button {
+                class: "secondary add-server-btn",
+                disabled: is_disabled,
+                onclick: {
+                    let nrpt = nrpt.clone();
+                    move |_| {
+                        let mut new_nrpt = nrpt.clone();
+                        new_nrpt.rules.push(NrptRule::default());
+                        on_change.call(new_nrpt);
+                    }
+                },
+                {t!("add-nrpt-rule")}
+            }
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use crate::i18n::t;
+use crate::state::AppState;
+use dioxus::prelude::*;
+
+#[component]
+pub fn NrptRuleList(
+    state: Signal<AppState>,
+    on_refresh: EventHandler<()>,
+    on_delete: EventHandler<String>,
+) -> Element {
+    let rules = state.read().nrpt_rules.clone();
+    let is_loading = state.read().is_loading;
+    let read_only_reason = state.read().read_only_reason();
+
+    rsx! {
+        div { class: "section",
+            div { class: "nrpt-list-header",
+                h2 { class: "section-title", {t!("active-nrpt-rules")} }
+                button {
+                    class: "secondary",
+                    disabled: is_loading,
+                    onclick: move |_| on_refresh.call(()),
+                    {t!("refresh")}
+                }
+            }
+
+            if rules.is_empty() {
+                p { class: "nrpt-empty", {t!("no-nrpt-rules")} }
+            } else {
+                table { class: "nrpt-table",
+                    thead {
+                        tr {
+                            th { {t!("namespace")} }
+                            th { {t!("name-servers")} }
+                            th { {t!("source")} }
+                            th {}
+                        }
+                    }
+                    tbody {
+                        for rule in rules {
+                            tr { key: "{rule.name}",
+                                td { {rule.namespaces.join(", ")} }
+                                td { {rule.name_servers.join(", ")} }
+                                td { if rule.managed { "windns" } else { {t!("source-other")} } }
+                                td {
+                                    button {
+                                        class: "remove-server-btn",
+                                        disabled: is_loading || read_only_reason.is_some(),
+                                        title: read_only_reason.clone().unwrap_or_else(|| t!("delete-rule-title")),
+                                        onclick: {
+                                            let name = rule.name.clone();
+                                            move |_| on_delete.call(name.clone())
+                                        },
+                                        {t!("delete")}
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
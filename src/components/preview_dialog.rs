@@ -0,0 +1,43 @@
+use crate::dns::PreviewStep;
+use crate::i18n::t;
+use dioxus::prelude::*;
+
+#[component]
+pub fn PreviewDialog(steps: Vec<PreviewStep>, on_close: EventHandler<()>) -> Element {
+    rsx! {
+        div { class: "dialog-overlay",
+            div {
+                class: "preview-dialog",
+                role: "dialog",
+                aria_modal: "true",
+                aria_labelledby: "preview-title",
+                tabindex: "-1",
+                onmounted: move |evt| async move {
+                    let _ = evt.set_focus(true).await;
+                },
+                onkeydown: move |evt| {
+                    if evt.key() == Key::Escape {
+                        on_close.call(());
+                    }
+                },
+                h3 { id: "preview-title", {t!("preview")} }
+                p { {t!("preview-intro")} }
+                div { class: "preview-steps",
+                    for (number, step) in steps.iter().enumerate().map(|(i, s)| (i + 1, s)) {
+                        div { key: "{number}", class: "preview-step",
+                            div { class: "preview-step-title", "{number}. {step.title}" }
+                            pre { class: "preview-command", "{step.command}" }
+                        }
+                    }
+                }
+                div { class: "dialog-buttons",
+                    button {
+                        class: "secondary",
+                        onclick: move |_| on_close.call(()),
+                        {t!("close")}
+                    }
+                }
+            }
+        }
+    }
+}
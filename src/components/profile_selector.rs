@@ -1,3 +1,4 @@
+use crate::i18n::t;
 use crate::state::AppState;
 use dioxus::prelude::*;
 
@@ -8,34 +9,109 @@ pub fn ProfileSelector(
     on_profile_change: EventHandler<String>,
     on_new_profile: EventHandler<()>,
     on_name_change: EventHandler<String>,
+    on_tags_change: EventHandler<String>,
+    on_tag_filter: EventHandler<Option<String>>,
+    on_search: EventHandler<String>,
+    on_color_change: EventHandler<Option<String>>,
     on_delete: EventHandler<()>,
+    on_copy: EventHandler<()>,
+    on_paste: EventHandler<()>,
+    on_export: EventHandler<()>,
+    on_import: EventHandler<()>,
 ) -> Element {
-    let (profiles, selected_id, current_name, has_profile) = {
+    let (profiles, selected_id, current_name, current_tags, current_color, has_profile) = {
         let state = state.read();
         let profiles = state
-            .sorted_profiles()
+            .filtered_profiles()
             .into_iter()
             .map(|p| (p.id.clone(), p.name.clone()))
             .collect::<Vec<_>>();
         let selected_id = state.selected_profile_id.clone().unwrap_or_default();
         let current_name = state.current_profile_name.clone();
+        let current_tags = state.current_profile_tags.clone();
+        let current_color = state.current_profile_color.clone();
         let has_profile = state.selected_profile_id.is_some();
-        (profiles, selected_id, current_name, has_profile)
+        (
+            profiles,
+            selected_id,
+            current_name,
+            current_tags,
+            current_color,
+            has_profile,
+        )
+    };
+    let search = state.read().profile_search.clone();
+    let (tags, no_filter) = {
+        let state = state.read();
+        let filter = state.profile_tag_filter.as_deref();
+        let tags = state
+            .config
+            .profile_tags()
+            .into_iter()
+            .map(|tag| {
+                let active = filter.is_some_and(|f| f.eq_ignore_ascii_case(&tag));
+                (tag, active)
+            })
+            .collect::<Vec<_>>();
+        (tags, filter.is_none())
     };
 
     let has_profiles = !profiles.is_empty();
+    let has_color = current_color.is_some();
+    let swatch_style = current_color
+        .as_ref()
+        .map(|color| format!("background-color: {};", color))
+        .unwrap_or_default();
+    let picker_value = current_color.unwrap_or_else(|| "#4fc3f7".to_string());
 
     rsx! {
+        input {
+            r#type: "text",
+            class: "profile-search-input",
+            placeholder: t!("search-profiles"),
+            aria_label: t!("search-profiles"),
+            disabled: disabled,
+            value: "{search}",
+            oninput: move |evt: Event<FormData>| {
+                on_search.call(evt.value());
+            },
+        }
+        if !tags.is_empty() {
+            div { class: "tag-filter",
+                button {
+                    class: if no_filter { "tag-chip active" } else { "tag-chip" },
+                    aria_pressed: "{no_filter}",
+                    onclick: move |_| on_tag_filter.call(None),
+                    {t!("all-tags")}
+                }
+                for (tag, active) in tags {
+                    button {
+                        key: "{tag}",
+                        class: if active { "tag-chip active" } else { "tag-chip" },
+                        aria_pressed: "{active}",
+                        onclick: {
+                            let tag = tag.clone();
+                            move |_| on_tag_filter.call(Some(tag.clone()))
+                        },
+                        "{tag}"
+                    }
+                }
+            }
+        }
         div { class: "profile-selector",
+            if has_profile && has_color {
+                span { class: "profile-swatch", style: "{swatch_style}" }
+            }
             select {
                 class: "profile-dropdown",
+                aria_label: t!("profile"),
                 disabled: disabled,
                 value: "{selected_id}",
                 onchange: move |evt: Event<FormData>| {
                     on_profile_change.call(evt.value());
                 },
                 if !has_profiles {
-                    option { value: "", disabled: true, selected: true, "(No profiles)" }
+                    option { value: "", disabled: true, selected: true, {t!("no-profiles")} }
                 }
                 for (id, name) in profiles {
                     option { value: "{id}", "{name}" }
@@ -44,24 +120,84 @@ pub fn ProfileSelector(
             input {
                 r#type: "text",
                 class: "profile-name-input",
-                placeholder: "Profile Name",
+                placeholder: t!("profile-name"),
+                aria_label: t!("profile-name"),
                 disabled: disabled || !has_profile,
                 value: "{current_name}",
                 oninput: move |evt: Event<FormData>| {
                     on_name_change.call(evt.value());
                 },
             }
+            input {
+                r#type: "text",
+                class: "profile-tags-input",
+                placeholder: t!("profile-tags"),
+                aria_label: t!("profile-tags"),
+                disabled: disabled || !has_profile,
+                value: "{current_tags}",
+                oninput: move |evt: Event<FormData>| {
+                    on_tags_change.call(evt.value());
+                },
+            }
+            input {
+                r#type: "color",
+                class: "profile-color-input",
+                title: t!("profile-color-title"),
+                aria_label: t!("profile-color-title"),
+                disabled: disabled || !has_profile,
+                value: "{picker_value}",
+                oninput: move |evt: Event<FormData>| {
+                    on_color_change.call(Some(evt.value()));
+                },
+            }
+            if has_color {
+                button {
+                    class: "remove-server-btn",
+                    title: t!("remove-color-title"),
+                    aria_label: t!("remove-color-title"),
+                    disabled: disabled || !has_profile,
+                    onclick: move |_| on_color_change.call(None),
+                    "×"
+                }
+            }
             button {
                 class: "secondary new-profile-btn",
                 disabled: disabled,
                 onclick: move |_| on_new_profile.call(()),
-                "New"
+                {t!("new")}
             }
             button {
                 class: "secondary danger delete-btn",
                 disabled: disabled || !has_profile,
                 onclick: move |_| on_delete.call(()),
-                "Delete"
+                {t!("delete")}
+            }
+            button {
+                class: "secondary",
+                disabled: disabled || !has_profile,
+                title: t!("copy-profile-title"),
+                onclick: move |_| on_copy.call(()),
+                {t!("copy")}
+            }
+            button {
+                class: "secondary",
+                disabled: disabled,
+                title: t!("paste-profile-title"),
+                onclick: move |_| on_paste.call(()),
+                {t!("paste")}
+            }
+            button {
+                class: "secondary",
+                disabled: !has_profiles,
+                title: t!("export-profiles-title"),
+                onclick: move |_| on_export.call(()),
+                {t!("export")}
+            }
+            button {
+                class: "secondary",
+                title: t!("import-profiles-title"),
+                onclick: move |_| on_import.call(()),
+                {t!("import")}
             }
         }
     }
@@ -0,0 +1,222 @@
+use crate::dns::ProviderDraft;
+use crate::i18n::t;
+use crate::state::AppState;
+use dioxus::prelude::*;
+
+#[component]
+pub fn ProviderSelector(
+    state: Signal<AppState>,
+    disabled: bool,
+    on_change: EventHandler<(String, Option<String>)>,
+    on_draft_change: EventHandler<Option<ProviderDraft>>,
+    on_save: EventHandler<()>,
+    on_delete: EventHandler<String>,
+    on_import_doh: EventHandler<()>,
+) -> Element {
+    let (catalog, provider_id, variant_id, custom_provider, draft) = {
+        let state = state.read();
+        let (provider_id, variant_id) = state.selected_provider.clone().unwrap_or_default();
+        let custom_provider = state.config.find_custom_provider(&provider_id).cloned();
+        (
+            state.provider_catalog(),
+            provider_id,
+            variant_id,
+            custom_provider,
+            state.provider_draft.clone(),
+        )
+    };
+    let doh_unavailable_reason = state.read().doh_unavailable_reason();
+
+    let variants = catalog
+        .iter()
+        .find(|p| p.id == provider_id)
+        .map(|p| p.variants.clone())
+        .unwrap_or_default();
+
+    rsx! {
+        div { class: "form-group provider-selector",
+            label { r#for: "provider-select", {t!("resolver-provider")} }
+            div { class: "provider-selects",
+                select {
+                    id: "provider-select",
+                    disabled: disabled,
+                    value: "{provider_id}",
+                    onchange: move |evt: Event<FormData>| {
+                        let id = evt.value();
+                        if !id.is_empty() {
+                            on_change.call((id, None));
+                        }
+                    },
+                    option { value: "", selected: provider_id.is_empty(), {t!("choose-provider")} }
+                    for provider in catalog.iter() {
+                        option {
+                            key: "{provider.id}",
+                            value: "{provider.id}",
+                            selected: provider.id == provider_id,
+                            "{provider.name}"
+                        }
+                    }
+                }
+                if variants.len() > 1 {
+                    select {
+                        class: "provider-variant",
+                        disabled: disabled,
+                        title: t!("filtering-variant"),
+                        value: "{variant_id}",
+                        onchange: {
+                            let provider_id = provider_id.clone();
+                            move |evt: Event<FormData>| {
+                                on_change.call((provider_id.clone(), Some(evt.value())))
+                            }
+                        },
+                        for variant in variants.iter() {
+                            option {
+                                key: "{variant.id}",
+                                value: "{variant.id}",
+                                selected: variant.id == variant_id,
+                                "{variant.name}"
+                            }
+                        }
+                    }
+                }
+                button {
+                    class: "secondary",
+                    disabled: disabled,
+                    title: t!("add-provider-title"),
+                    onclick: move |_| on_draft_change.call(Some(ProviderDraft::default())),
+                    {t!("add")}
+                }
+                button {
+                    class: "secondary",
+                    disabled: disabled || custom_provider.is_none(),
+                    onclick: {
+                        let custom_provider = custom_provider.clone();
+                        move |_| {
+                            if let Some(provider) = &custom_provider {
+                                on_draft_change.call(Some(ProviderDraft::from_provider(provider)));
+                            }
+                        }
+                    },
+                    {t!("edit")}
+                }
+                button {
+                    class: "secondary danger",
+                    disabled: disabled || custom_provider.is_none(),
+                    onclick: {
+                        let provider_id = provider_id.clone();
+                        move |_| on_delete.call(provider_id.clone())
+                    },
+                    {t!("delete")}
+                }
+                button {
+                    class: "secondary",
+                    disabled: disabled || doh_unavailable_reason.is_some(),
+                    title: doh_unavailable_reason.unwrap_or_else(|| t!("import-doh-title")),
+                    onclick: move |_| on_import_doh.call(()),
+                    {t!("import-doh")}
+                }
+            }
+            if let Some(draft) = draft {
+                ProviderDraftForm {
+                    draft: draft,
+                    on_change: on_draft_change,
+                    on_save: on_save,
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn ProviderDraftForm(
+    draft: ProviderDraft,
+    on_change: EventHandler<Option<ProviderDraft>>,
+    on_save: EventHandler<()>,
+) -> Element {
+    let title = if draft.id.is_some() {
+        t!("edit-provider")
+    } else {
+        t!("new-provider")
+    };
+
+    rsx! {
+        div { class: "provider-form",
+            span { class: "dns-family-title", "{title}" }
+            div { class: "form-group",
+                label { r#for: "provider-name", {t!("name")} }
+                input {
+                    r#type: "text",
+                    id: "provider-name",
+                    placeholder: t!("example", example = "Home Pi-hole"),
+                    value: "{draft.name}",
+                    oninput: {
+                        let draft = draft.clone();
+                        move |evt: Event<FormData>| {
+                            on_change.call(Some(ProviderDraft { name: evt.value(), ..draft.clone() }))
+                        }
+                    },
+                }
+            }
+            div { class: "form-group",
+                label { r#for: "provider-ipv4", {t!("ipv4-addresses")} }
+                input {
+                    r#type: "text",
+                    id: "provider-ipv4",
+                    placeholder: t!("example", example = "192.168.1.53, 192.168.1.54"),
+                    value: "{draft.ipv4}",
+                    oninput: {
+                        let draft = draft.clone();
+                        move |evt: Event<FormData>| {
+                            on_change.call(Some(ProviderDraft { ipv4: evt.value(), ..draft.clone() }))
+                        }
+                    },
+                }
+            }
+            div { class: "form-group",
+                label { r#for: "provider-ipv6", {t!("ipv6-addresses")} }
+                input {
+                    r#type: "text",
+                    id: "provider-ipv6",
+                    placeholder: t!("example", example = "fd00::53"),
+                    value: "{draft.ipv6}",
+                    oninput: {
+                        let draft = draft.clone();
+                        move |evt: Event<FormData>| {
+                            on_change.call(Some(ProviderDraft { ipv6: evt.value(), ..draft.clone() }))
+                        }
+                    },
+                }
+            }
+            div { class: "form-group",
+                label { r#for: "provider-template", {t!("doh-template-optional")} }
+                input {
+                    r#type: "text",
+                    id: "provider-template",
+                    placeholder: t!("example", example = "https://dns.home.example/dns-query"),
+                    value: "{draft.doh_template}",
+                    oninput: {
+                        let draft = draft.clone();
+                        move |evt: Event<FormData>| {
+                            on_change.call(Some(ProviderDraft {
+                                doh_template: evt.value(),
+                                ..draft.clone()
+                            }))
+                        }
+                    },
+                }
+            }
+            div { class: "provider-form-buttons",
+                button {
+                    class: "secondary",
+                    onclick: move |_| on_change.call(None),
+                    {t!("cancel")}
+                }
+                button {
+                    class: "primary",
+                    onclick: move |_| on_save.call(()),
+                    {t!("save-provider")}
+                }
+            }
+        }
+    }
+}
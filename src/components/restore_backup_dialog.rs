@@ -0,0 +1,77 @@
+use crate::dns::{ConfigBackup, MAX_CONFIG_BACKUPS};
+use crate::i18n::t;
+use dioxus::prelude::*;
+use std::path::PathBuf;
+
+#[component]
+pub fn RestoreBackupDialog(
+    backups: Vec<ConfigBackup>,
+    on_restore: EventHandler<PathBuf>,
+    on_close: EventHandler<()>,
+) -> Element {
+    let rows: Vec<(String, String, String, PathBuf, bool)> = backups
+        .into_iter()
+        .map(|backup| {
+            let profiles = match backup.profile_count {
+                Some(count) => t!("backup-profiles", count = count),
+                None => t!("backup-unreadable"),
+            };
+            (
+                backup.path.display().to_string(),
+                backup.created,
+                profiles,
+                backup.path,
+                backup.profile_count.is_some(),
+            )
+        })
+        .collect();
+
+    rsx! {
+        div { class: "dialog-overlay",
+            div {
+                class: "backup-dialog",
+                role: "dialog",
+                aria_modal: "true",
+                aria_labelledby: "backup-title",
+                tabindex: "-1",
+                onmounted: move |evt| async move {
+                    let _ = evt.set_focus(true).await;
+                },
+                onkeydown: move |evt| {
+                    if evt.key() == Key::Escape {
+                        on_close.call(());
+                    }
+                },
+                h3 { id: "backup-title", {t!("restore-backup-title")} }
+                p { {t!("restore-backup-intro", count = MAX_CONFIG_BACKUPS)} }
+                if rows.is_empty() {
+                    p { class: "backup-empty", {t!("no-backups")} }
+                } else {
+                    div { class: "backup-list",
+                        for (key, created, profiles, path, readable) in rows {
+                            div { key: "{key}", class: "backup-item",
+                                div { class: "backup-info",
+                                    span { class: "backup-created", "{created}" }
+                                    span { class: "backup-profiles", "{profiles}" }
+                                }
+                                button {
+                                    class: "secondary",
+                                    disabled: !readable,
+                                    onclick: move |_| on_restore.call(path.clone()),
+                                    {t!("restore")}
+                                }
+                            }
+                        }
+                    }
+                }
+                div { class: "dialog-buttons",
+                    button {
+                        class: "secondary",
+                        onclick: move |_| on_close.call(()),
+                        {t!("close")}
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use crate::dns::{Schedule, ScheduleEntry, TimeOfDay, Weekday};
+use crate::i18n::t;
+use crate::state::AppState;
+use dioxus::prelude::*;
+
+#[component]
+pub fn SchedulePanel(
+    state: Signal<AppState>,
+    on_change: EventHandler<Schedule>,
+    on_save: EventHandler<()>,
+) -> Element {
+    let (schedule, has_changes, profiles) = {
+        let state = state.read();
+        let profiles = state
+            .sorted_profiles()
+            .into_iter()
+            .map(|p| (p.id.clone(), p.name.clone()))
+            .collect::<Vec<_>>();
+        (
+            state.schedule().clone(),
+            state.schedule_draft.is_some(),
+            profiles,
+        )
+    };
+
+    rsx! {
+        div { class: "section",
+            div { class: "nrpt-list-header",
+                h2 { class: "section-title", {t!("schedule")} }
+                button {
+                    class: "secondary",
+                    disabled: !has_changes,
+                    onclick: move |_| on_save.call(()),
+                    {t!("save-schedule")}
+                }
+            }
+
+            div { class: "checkbox-group",
+                input {
+                    r#type: "checkbox",
+                    id: "schedule-enabled",
+                    checked: schedule.enabled,
+                    onchange: {
+                        let schedule = schedule.clone();
+                        move |evt: Event<FormData>| {
+                            let mut new_schedule = schedule.clone();
+                            new_schedule.enabled = evt.checked();
+                            on_change.call(new_schedule);
+                        }
+                    },
+                }
+                label { r#for: "schedule-enabled",
+                    {t!("schedule-enabled")}
+                }
+            }
+
+            for (index, entry) in schedule.entries.iter().enumerate() {
+                div { key: "{entry.id}", class: "schedule-entry",
+                    div { class: "network-rule",
+                        input {
+                            r#type: "time",
+                            value: "{entry.start}",
+                            onchange: {
+                                let schedule = schedule.clone();
+                                move |evt: Event<FormData>| {
+                                    if let Some(time) = TimeOfDay::parse(&evt.value()) {
+                                        let mut new_schedule = schedule.clone();
+                                        new_schedule.entries[index].start = time;
+                                        on_change.call(new_schedule);
+                                    }
+                                }
+                            },
+                        }
+                        span { {t!("time-range-to")} }
+                        input {
+                            r#type: "time",
+                            value: "{entry.end}",
+                            onchange: {
+                                let schedule = schedule.clone();
+                                move |evt: Event<FormData>| {
+                                    if let Some(time) = TimeOfDay::parse(&evt.value()) {
+                                        let mut new_schedule = schedule.clone();
+                                        new_schedule.entries[index].end = time;
+                                        on_change.call(new_schedule);
+                                    }
+                                }
+                            },
+                        }
+                        select {
+                            value: "{entry.profile_id.clone().unwrap_or_default()}",
+                            onchange: {
+                                let schedule = schedule.clone();
+                                move |evt: Event<FormData>| {
+                                    let mut new_schedule = schedule.clone();
+                                    let id = evt.value();
+                                    new_schedule.entries[index].profile_id = (!id.is_empty()).then_some(id);
+                                    on_change.call(new_schedule);
+                                }
+                            },
+                            option {
+                                value: "",
+                                selected: entry.profile_id.is_none(),
+                                {t!("automatic")}
+                            }
+                            for (id, name) in profiles.iter() {
+                                option {
+                                    value: "{id}",
+                                    selected: entry.profile_id.as_ref() == Some(id),
+                                    "{name}"
+                                }
+                            }
+                        }
+                        button {
+                            class: "remove-server-btn",
+                            title: t!("remove-entry-title"),
+                            onclick: {
+                                let schedule = schedule.clone();
+                                move |_| {
+                                    let mut new_schedule = schedule.clone();
+                                    new_schedule.entries.remove(index);
+                                    on_change.call(new_schedule);
+                                }
+                            },
+                            {t!("remove")}
+                        }
+                    }
+                    div { class: "schedule-days",
+                        for day in Weekday::ALL {
+                            label { key: "{day.as_str()}", class: "checkbox-group",
+                                input {
+                                    r#type: "checkbox",
+                                    checked: entry.days.contains(&day),
+                                    onchange: {
+                                        let schedule = schedule.clone();
+                                        move |evt: Event<FormData>| {
+                                            let mut new_schedule = schedule.clone();
+                                            let days = &mut new_schedule.entries[index].days;
+                                            days.retain(|d| *d != day);
+                                            if evt.checked() {
+                                                days.push(day);
+                                                days.sort_by_key(|d| Weekday::ALL.iter().position(|w| w == d));
+                                            }
+                                            on_change.call(new_schedule);
+                                        }
+                                    },
+                                }
+                                {t!(&format!("weekday-{}", day.as_str().to_lowercase()))}
+                            }
+                        }
+                    }
+                }
+            }
+
+            button {
+                class: "secondary add-server-btn",
+                onclick: {
+                    let schedule = schedule.clone();
+                    move |_| {
+                        let mut new_schedule = schedule.clone();
+                        new_schedule.entries.push(ScheduleEntry::new());
+                        on_change.call(new_schedule);
+                    }
+                },
+                {t!("add-schedule-entry")}
+            }
+
+            div { class: "form-group",
+                label { r#for: "schedule-default", {t!("at-other-times")} }
+                select {
+                    id: "schedule-default",
+                    value: "{schedule.default_profile_id.clone().unwrap_or_default()}",
+                    onchange: {
+                        let schedule = schedule.clone();
+                        move |evt: Event<FormData>| {
+                            let mut new_schedule = schedule.clone();
+                            let id = evt.value();
+                            new_schedule.default_profile_id = (!id.is_empty()).then_some(id);
+                            on_change.call(new_schedule);
+                        }
+                    },
+                    option {
+                        value: "",
+                        selected: schedule.default_profile_id.is_none(),
+                        {t!("automatic")}
+                    }
+                    for (id, name) in profiles.iter() {
+                        option {
+                            value: "{id}",
+                            selected: schedule.default_profile_id.as_ref() == Some(id),
+                            "{name}"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
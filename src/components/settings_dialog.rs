@@ -0,0 +1,514 @@
+use crate::dns::{
+    AppSettings, ConfigFormat, ConfigLocation, StartupSetting, ThemePreference, current_version,
+    trace_log_path,
+};
+use crate::i18n::{Language, t};
+use crate::state::{AppState, UpdateStatus};
+use dioxus::prelude::*;
+
+#[component]
+pub fn SettingsDialog(
+    state: Signal<AppState>,
+    on_change: EventHandler<AppSettings>,
+    on_startup_change: EventHandler<StartupSetting>,
+    on_link_handler_change: EventHandler<bool>,
+    on_logon_task_change: EventHandler<Option<String>>,
+    on_check_update: EventHandler<()>,
+    on_download_update: EventHandler<()>,
+    on_install_update: EventHandler<()>,
+    on_config_format_change: EventHandler<ConfigFormat>,
+    on_move_config: EventHandler<()>,
+    on_reset_config_location: EventHandler<()>,
+    on_open_backups: EventHandler<()>,
+    on_close: EventHandler<()>,
+) -> Element {
+    let (settings, startup, link_handler, doh_available, update_status) = {
+        let state = state.read();
+        (
+            state.config.settings.clone(),
+            state.startup,
+            state.link_handler,
+            state.doh_unavailable_reason().is_none(),
+            state.update_status.clone(),
+        )
+    };
+    let trace_log = trace_log_path()
+        .map(|path| path.display().to_string())
+        .unwrap_or_default();
+    let (config_format, config_location) = {
+        let state = state.read();
+        (state.config_format, state.config_location.clone())
+    };
+    let config_folder = match &config_location {
+        Some(ConfigLocation::Argument(dir)) => {
+            t!("config-folder-argument", path = dir.display())
+        }
+        Some(location) => t!("config-folder", path = location.dir().display()),
+        None => String::new(),
+    };
+    let (logon_task, profiles) = {
+        let state = state.read();
+        let profiles: Vec<(String, String)> = state
+            .sorted_profiles()
+            .into_iter()
+            .map(|p| (p.id.clone(), p.name.clone()))
+            .collect();
+        (state.logon_task.clone(), profiles)
+    };
+    let logon_status = match &logon_task {
+        None => t!("logon-task-checking"),
+        Some(None) => t!("logon-task-none"),
+        Some(Some(id)) => match profiles.iter().find(|(profile_id, _)| profile_id == id) {
+            Some((_, name)) => t!("logon-task-registered", profile = name),
+            None => t!("logon-task-missing-profile"),
+        },
+    };
+    let release = match &update_status {
+        Some(
+            UpdateStatus::Available(release)
+            | UpdateStatus::Downloading(release)
+            | UpdateStatus::Ready(release, _),
+        ) => Some(release.clone()),
+        _ => None,
+    };
+    let theme = settings.theme();
+    let language = settings.language();
+    let refresh_secs = settings.dns_refresh_secs();
+    let refresh_range = AppSettings::DNS_REFRESH_SECS_RANGE;
+    let backend = state.read().backend;
+
+    // Each control hands back a copy of the settings with its field changed.
+    let update = {
+        let settings = settings.clone();
+        move |change: fn(&mut AppSettings, bool)| {
+            let settings = settings.clone();
+            move |evt: Event<FormData>| {
+                let mut new_settings = settings.clone();
+                change(&mut new_settings, evt.checked());
+                on_change.call(new_settings);
+            }
+        }
+    };
+
+    rsx! {
+        div { class: "dialog-overlay",
+            div {
+                class: "settings-dialog",
+                role: "dialog",
+                aria_modal: "true",
+                aria_labelledby: "settings-title",
+                tabindex: "-1",
+                onmounted: move |evt| async move {
+                    let _ = evt.set_focus(true).await;
+                },
+                onkeydown: move |evt| {
+                    if evt.key() == Key::Escape {
+                        on_close.call(());
+                    }
+                },
+                h3 { id: "settings-title", {t!("settings")} }
+
+                div { class: "settings-group",
+                    h4 { {t!("settings-appearance")} }
+                    div { class: "theme-option",
+                        label { r#for: "theme-select", {t!("theme")} }
+                        select {
+                            id: "theme-select",
+                            value: "{theme.as_str()}",
+                            onchange: {
+                                let settings = settings.clone();
+                                move |evt: Event<FormData>| {
+                                    if let Some(theme) = ThemePreference::parse(&evt.value()) {
+                                        on_change.call(AppSettings {
+                                            theme: Some(theme),
+                                            ..settings.clone()
+                                        });
+                                    }
+                                }
+                            },
+                            for choice in ThemePreference::ALL {
+                                option {
+                                    value: "{choice.as_str()}",
+                                    selected: choice == theme,
+                                    {t!(theme_message(choice))}
+                                }
+                            }
+                        }
+                    }
+                    div { class: "theme-option",
+                        label { r#for: "language-select", {t!("language")} }
+                        select {
+                            id: "language-select",
+                            value: "{language.code()}",
+                            onchange: {
+                                let settings = settings.clone();
+                                move |evt: Event<FormData>| {
+                                    if let Some(language) = Language::from_code(&evt.value()) {
+                                        on_change.call(AppSettings {
+                                            language: Some(language),
+                                            ..settings.clone()
+                                        });
+                                    }
+                                }
+                            },
+                            for choice in Language::ALL {
+                                option {
+                                    value: "{choice.code()}",
+                                    selected: choice == language,
+                                    "{choice.native_name()}"
+                                }
+                            }
+                        }
+                    }
+                }
+
+                div { class: "settings-group",
+                    h4 { {t!("settings-startup")} }
+                    div { class: "checkbox-group",
+                        input {
+                            r#type: "checkbox",
+                            id: "launch-at-startup",
+                            checked: startup.enabled,
+                            onchange: move |evt: Event<FormData>| {
+                                on_startup_change.call(StartupSetting {
+                                    enabled: evt.checked(),
+                                    ..startup
+                                })
+                            },
+                        }
+                        label {
+                            r#for: "launch-at-startup",
+                            title: t!("launch-at-startup-title"),
+                            {t!("launch-at-startup")}
+                        }
+                    }
+                    div { class: "checkbox-group",
+                        input {
+                            r#type: "checkbox",
+                            id: "start-minimized",
+                            checked: startup.minimized,
+                            disabled: !startup.enabled,
+                            onchange: move |evt: Event<FormData>| {
+                                on_startup_change.call(StartupSetting {
+                                    minimized: evt.checked(),
+                                    ..startup
+                                })
+                            },
+                        }
+                        label { r#for: "start-minimized", {t!("start-minimized")} }
+                    }
+                    div { class: "checkbox-group",
+                        input {
+                            r#type: "checkbox",
+                            id: "open-links",
+                            checked: link_handler,
+                            onchange: move |evt: Event<FormData>| on_link_handler_change.call(evt.checked()),
+                        }
+                        label {
+                            r#for: "open-links",
+                            title: t!("open-links-title"),
+                            {t!("open-links")}
+                        }
+                    }
+                    div { class: "theme-option",
+                        label {
+                            r#for: "logon-task-profile",
+                            title: t!("logon-task-title"),
+                            {t!("logon-task")}
+                        }
+                        select {
+                            id: "logon-task-profile",
+                            disabled: logon_task.is_none(),
+                            onchange: move |evt: Event<FormData>| {
+                                let value = evt.value();
+                                on_logon_task_change.call((!value.is_empty()).then_some(value));
+                            },
+                            option {
+                                value: "",
+                                selected: logon_task == Some(None),
+                                {t!("logon-task-off")}
+                            }
+                            for (id, name) in profiles {
+                                option {
+                                    value: "{id}",
+                                    selected: logon_task.as_ref().is_some_and(|task| task.as_ref() == Some(&id)),
+                                    "{name}"
+                                }
+                            }
+                        }
+                    }
+                    p { class: "input-hint", {logon_status} }
+                }
+
+                div { class: "settings-group",
+                    h4 { {t!("settings-apply")} }
+                    div { class: "checkbox-group",
+                        input {
+                            r#type: "checkbox",
+                            id: "flush-cache-after-apply",
+                            checked: settings.flushes_cache_after_apply(),
+                            onchange: update(|s, checked| s.flush_cache_after_apply = Some(checked)),
+                        }
+                        label { r#for: "flush-cache-after-apply", {t!("flush-after-apply")} }
+                    }
+                    div { class: "checkbox-group",
+                        input {
+                            r#type: "checkbox",
+                            id: "probe-doh-before-apply",
+                            checked: settings.probes_doh_before_apply(),
+                            disabled: !doh_available,
+                            onchange: update(|s, checked| s.probe_doh_before_apply = Some(checked)),
+                        }
+                        label {
+                            r#for: "probe-doh-before-apply",
+                            title: t!("probe-doh-title"),
+                            {t!("probe-doh")}
+                        }
+                    }
+                    div { class: "checkbox-group",
+                        input {
+                            r#type: "checkbox",
+                            id: "check-resolution-after-apply",
+                            checked: settings.checks_resolution_after_apply(),
+                            onchange: update(|s, checked| s.check_resolution_after_apply = Some(checked)),
+                        }
+                        label {
+                            r#for: "check-resolution-after-apply",
+                            title: t!("check-resolution-title"),
+                            {t!("check-resolution")}
+                        }
+                    }
+                }
+
+                div { class: "settings-group",
+                    h4 { {t!("settings-prompts")} }
+                    div { class: "checkbox-group",
+                        input {
+                            r#type: "checkbox",
+                            id: "confirm-profile-delete",
+                            checked: settings.confirms_profile_delete(),
+                            onchange: update(|s, checked| s.confirm_profile_delete = Some(checked)),
+                        }
+                        label { r#for: "confirm-profile-delete", {t!("confirm-profile-delete")} }
+                    }
+                    div { class: "checkbox-group",
+                        input {
+                            r#type: "checkbox",
+                            id: "warn-external-change",
+                            checked: settings.warns_on_external_dns_change(),
+                            onchange: update(|s, checked| s.warn_on_external_dns_change = Some(checked)),
+                        }
+                        label { r#for: "warn-external-change", {t!("warn-external-change")} }
+                    }
+                    div { class: "checkbox-group",
+                        input {
+                            r#type: "checkbox",
+                            id: "notify-network-rules",
+                            checked: settings.notifies_network_rules(),
+                            onchange: update(|s, checked| s.notify_network_rules = Some(checked)),
+                        }
+                        label { r#for: "notify-network-rules", {t!("notify-network-rules")} }
+                    }
+                    div { class: "checkbox-group",
+                        input {
+                            r#type: "checkbox",
+                            id: "enable-control-server",
+                            checked: settings.enables_control_server(),
+                            onchange: update(|s, checked| s.enable_control_server = Some(checked)),
+                        }
+                        label {
+                            r#for: "enable-control-server",
+                            title: t!("control-server-title"),
+                            {t!("control-server")}
+                        }
+                    }
+                    div { class: "checkbox-group",
+                        input {
+                            r#type: "checkbox",
+                            id: "trace-commands",
+                            checked: settings.traces_commands(),
+                            onchange: update(|s, checked| s.trace_commands = Some(checked)),
+                        }
+                        label {
+                            r#for: "trace-commands",
+                            title: t!("trace-commands-title", path = trace_log),
+                            {t!("trace-commands")}
+                        }
+                    }
+                    div { class: "checkbox-group",
+                        input {
+                            r#type: "checkbox",
+                            id: "use-cim-backend",
+                            checked: settings.uses_cim_backend(),
+                            disabled: !backend.supports_doh(),
+                            onchange: update(|s, checked| s.use_cim_backend = Some(checked)),
+                        }
+                        label {
+                            r#for: "use-cim-backend",
+                            title: t!("use-cim-backend-title"),
+                            {t!("use-cim-backend")}
+                        }
+                    }
+                    div { class: "theme-option",
+                        label { r#for: "dns-refresh-secs", {t!("dns-refresh-interval")} }
+                        input {
+                            r#type: "number",
+                            id: "dns-refresh-secs",
+                            class: "settings-number",
+                            min: "{refresh_range.start()}",
+                            max: "{refresh_range.end()}",
+                            value: "{refresh_secs}",
+                            onchange: {
+                                let settings = settings.clone();
+                                move |evt: Event<FormData>| {
+                                    if let Ok(secs) = evt.value().trim().parse::<u64>() {
+                                        on_change.call(AppSettings {
+                                            dns_refresh_secs: Some(secs),
+                                            ..settings.clone()
+                                        });
+                                    }
+                                }
+                            },
+                        }
+                        span { {t!("seconds")} }
+                    }
+                }
+
+                div { class: "settings-group",
+                    h4 { {t!("settings-updates")} }
+                    p { class: "update-status", {update_message(update_status.as_ref())} }
+                    if let Some(release) = &release {
+                        a {
+                            class: "release-notes-link",
+                            href: "{release.page_url}",
+                            target: "_blank",
+                            {t!("release-notes")}
+                        }
+                    }
+                    div { class: "update-actions",
+                        if matches!(update_status, Some(UpdateStatus::Ready(..))) {
+                            button { onclick: move |_| on_install_update.call(()), {t!("restart-to-update")} }
+                        } else if release.is_some() {
+                            button {
+                                disabled: matches!(update_status, Some(UpdateStatus::Downloading(_))),
+                                onclick: move |_| on_download_update.call(()),
+                                {t!("download-update")}
+                            }
+                        } else {
+                            button {
+                                class: "secondary",
+                                disabled: update_status == Some(UpdateStatus::Checking),
+                                onclick: move |_| on_check_update.call(()),
+                                {t!("check-for-updates")}
+                            }
+                        }
+                    }
+                }
+
+                div { class: "settings-group",
+                    h4 { {t!("settings-config-file")} }
+                    p { class: "input-hint config-folder", {config_folder} }
+                    div { class: "update-actions",
+                        button {
+                            class: "secondary",
+                            title: t!("move-config-title"),
+                            disabled: matches!(config_location, None | Some(ConfigLocation::Argument(_))),
+                            onclick: move |_| on_move_config.call(()),
+                            {t!("move-config")}
+                        }
+                        if matches!(config_location, Some(ConfigLocation::Redirected(_))) {
+                            button {
+                                class: "secondary",
+                                onclick: move |_| on_reset_config_location.call(()),
+                                {t!("default-config-folder")}
+                            }
+                        }
+                    }
+                    div { class: "theme-option",
+                        label {
+                            r#for: "config-format",
+                            title: t!("config-format-title"),
+                            {t!("config-format")}
+                        }
+                        select {
+                            id: "config-format",
+                            value: "{config_format.extension()}",
+                            onchange: move |evt: Event<FormData>| {
+                                if let Some(format) = ConfigFormat::parse(&evt.value()) {
+                                    on_config_format_change.call(format);
+                                }
+                            },
+                            for choice in ConfigFormat::ALL {
+                                option {
+                                    value: "{choice.extension()}",
+                                    selected: choice == config_format,
+                                    {format_label(choice)}
+                                }
+                            }
+                        }
+                    }
+                    div { class: "checkbox-group",
+                        input {
+                            r#type: "checkbox",
+                            id: "encrypt-profiles",
+                            checked: settings.encrypts_profiles(),
+                            onchange: update(|s, checked| s.encrypt_profiles = Some(checked)),
+                        }
+                        label {
+                            r#for: "encrypt-profiles",
+                            title: t!("encrypt-profiles-title"),
+                            {t!("encrypt-profiles")}
+                        }
+                    }
+                    p { class: "input-hint", {t!("backups-hint")} }
+                    div { class: "update-actions",
+                        button {
+                            class: "secondary",
+                            onclick: move |_| on_open_backups.call(()),
+                            {t!("open-backups")}
+                        }
+                    }
+                }
+
+                div { class: "dialog-buttons",
+                    button {
+                        class: "secondary",
+                        onclick: move |_| on_close.call(()),
+                        {t!("close")}
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn update_message(status: Option<&UpdateStatus>) -> String {
+    match status {
+        None => t!("current-version", version = current_version()),
+        Some(UpdateStatus::Checking) => t!("checking-for-updates"),
+        Some(UpdateStatus::UpToDate) => t!("up-to-date", version = current_version()),
+        Some(UpdateStatus::Available(release)) => {
+            t!("update-available", version = release.version)
+        }
+        Some(UpdateStatus::Downloading(release)) => {
+            t!("downloading-update", version = release.version)
+        }
+        Some(UpdateStatus::Ready(release, _)) => t!("update-ready", version = release.version),
+        Some(UpdateStatus::Failed(error)) => t!("update-failed", error = error),
+    }
+}
+
+fn format_label(format: ConfigFormat) -> &'static str {
+    match format {
+        ConfigFormat::Jsonc => "JSONC (config.jsonc)",
+        ConfigFormat::Toml => "TOML (config.toml)",
+    }
+}
+
+fn theme_message(theme: ThemePreference) -> &'static str {
+    match theme {
+        ThemePreference::System => "theme-system",
+        ThemePreference::Light => "theme-light",
+        ThemePreference::Dark => "theme-dark",
+    }
+}
@@ -1,17 +1,52 @@
-use crate::dns::AddressFamily;
-use crate::state::{AppState, MessageLevel};
+use crate::dns::{AddressFamily, CurrentDnsState, format_countdown, unix_now};
+use crate::i18n::t;
+use crate::state::{AppState, DnsMatch, MessageLevel};
 use dioxus::prelude::*;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Round-trip times up to which a server counts as fast, and as usable.
+const FAST_LATENCY: Duration = Duration::from_millis(50);
+const SLOW_LATENCY: Duration = Duration::from_millis(200);
+/// How often the countdown of a temporary apply is redrawn.
+const COUNTDOWN_TICK: Duration = Duration::from_secs(1);
 
 #[component]
-pub fn StatusBar(state: Signal<AppState>) -> Element {
-    let (current_state, message) = {
+pub fn StatusBar(
+    state: Signal<AppState>,
+    on_copy: EventHandler<AddressFamily>,
+    on_keep_temporary: EventHandler<()>,
+    on_revert_temporary: EventHandler<()>,
+) -> Element {
+    let reverts_at = state
+        .read()
+        .selected_temporary_apply()
+        .map(|temporary| temporary.expires_at);
+    let (current_state, message, resolution_check, health, latency, active_profile) = {
         let read_state = state.read();
+        let active_profile = match read_state.dns_match() {
+            DnsMatch::Profile(profile) => {
+                let swatch_style = profile
+                    .color
+                    .as_ref()
+                    .map(|color| format!("background-color: {};", color));
+                (profile.name.clone(), swatch_style)
+            }
+            DnsMatch::Automatic => (t!("automatic"), None),
+            DnsMatch::Custom => (t!("custom-dns"), None),
+        };
         (
             read_state.current_dns_state.clone(),
-            read_state.message.clone(),
+            read_state.message().cloned(),
+            read_state.resolution_check.clone(),
+            read_state.health.clone(),
+            read_state.server_latency.clone(),
+            active_profile,
         )
     };
 
+    let (name, swatch_style) = active_profile;
+
     rsx! {
         div { class: "status-bar",
             if let Some(msg) = message {
@@ -28,8 +63,8 @@ pub fn StatusBar(state: Signal<AppState>) -> Element {
                             button {
                                 r#type: "button",
                                 class: "message-close-btn",
-                                aria_label: "Close message",
-                                title: "Close",
+                                aria_label: t!("close-message"),
+                                title: t!("close"),
                                 onclick: move |_| state.write().clear_message(),
                                 "×"
                             }
@@ -39,13 +74,173 @@ pub fn StatusBar(state: Signal<AppState>) -> Element {
             }
 
             div { class: "status-section",
-                div { class: "status-label", "Current IPv4 DNS:" }
-                div { class: "status-value", "{current_state.get_display(AddressFamily::IPv4)}" }
+                div { class: "status-label", {t!("active-profile")} }
+                div { class: "status-value active-profile",
+                    if let Some(health) = health {
+                        span {
+                            class: if health.level == MessageLevel::Success { "health-dot healthy" } else { "health-dot failing" },
+                            role: "img",
+                            aria_label: "{health.text}",
+                            title: "{health.text}",
+                        }
+                    }
+                    if let Some(style) = swatch_style {
+                        span { class: "profile-swatch", style: "{style}" }
+                    }
+                    "{name}"
+                }
+            }
+
+            if let Some(expires_at) = reverts_at {
+                TemporaryCountdown {
+                    expires_at: expires_at,
+                    on_keep: on_keep_temporary,
+                    on_revert: on_revert_temporary,
+                }
+            }
+
+            div { class: "status-section",
+                div { class: "status-label",
+                    {t!("current-ipv4-dns")}
+                    if !current_state.addresses(AddressFamily::IPv4).is_empty() {
+                        button {
+                            class: "copy-btn",
+                            title: t!("copy-servers-title"),
+                            onclick: move |_| on_copy.call(AddressFamily::IPv4),
+                            {t!("copy")}
+                        }
+                    }
+                }
+                ServerList {
+                    current: current_state.clone(),
+                    family: AddressFamily::IPv4,
+                    latency: latency.clone(),
+                }
             }
 
             div { class: "status-section",
-                div { class: "status-label", "Current IPv6 DNS:" }
-                div { class: "status-value", "{current_state.get_display(AddressFamily::IPv6)}" }
+                div { class: "status-label",
+                    {t!("current-ipv6-dns")}
+                    if !current_state.addresses(AddressFamily::IPv6).is_empty() {
+                        button {
+                            class: "copy-btn",
+                            title: t!("copy-servers-title"),
+                            onclick: move |_| on_copy.call(AddressFamily::IPv6),
+                            {t!("copy")}
+                        }
+                    }
+                }
+                ServerList {
+                    current: current_state.clone(),
+                    family: AddressFamily::IPv6,
+                    latency: latency,
+                }
+            }
+
+            if let Some(check) = resolution_check {
+                div { class: "status-section",
+                    div { class: "status-label", {t!("resolution-check")} }
+                    div {
+                        class: if check.level == MessageLevel::Success { "status-value resolution-ok" } else { "status-value resolution-failed" },
+                        "{check.text}"
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Time left until the settings of a temporary apply are put back, redrawn
+/// every second on its own so the rest of the status bar is left alone.
+#[component]
+fn TemporaryCountdown(
+    expires_at: u64,
+    on_keep: EventHandler<()>,
+    on_revert: EventHandler<()>,
+) -> Element {
+    let mut now = use_signal(unix_now);
+    use_future(move || async move {
+        loop {
+            tokio::time::sleep(COUNTDOWN_TICK).await;
+            now.set(unix_now());
+        }
+    });
+    let remaining = format_countdown(Duration::from_secs(expires_at.saturating_sub(now())));
+
+    rsx! {
+        div { class: "status-section",
+            div { class: "status-label", {t!("temporary-apply")} }
+            div { class: "status-value temporary-apply",
+                span { {t!("temporary-apply-reverts-in", time = remaining)} }
+                button {
+                    class: "copy-btn",
+                    title: t!("keep-temporary-title"),
+                    onclick: move |_| on_keep.call(()),
+                    {t!("keep-temporary")}
+                }
+                button {
+                    class: "copy-btn",
+                    title: t!("revert-temporary-title"),
+                    onclick: move |_| on_revert.call(()),
+                    {t!("revert-temporary")}
+                }
+            }
+        }
+    }
+}
+
+/// The current servers of `family`, each followed by its last measured
+/// round-trip time once there is one.
+#[component]
+fn ServerList(
+    current: CurrentDnsState,
+    family: AddressFamily,
+    latency: BTreeMap<String, Option<Duration>>,
+) -> Element {
+    let addresses = current.addresses(family);
+    if !addresses
+        .iter()
+        .any(|address| latency.contains_key(address))
+    {
+        return rsx! {
+            div { class: "status-value", "{current.get_display(family)}" }
+        };
+    }
+
+    let servers: Vec<(String, Option<(&str, String)>)> = addresses
+        .iter()
+        .map(|address| {
+            let readout = latency.get(address).map(|elapsed| match elapsed {
+                Some(elapsed) if *elapsed <= FAST_LATENCY => {
+                    ("latency-fast", t!("latency-ms", ms = elapsed.as_millis()))
+                }
+                Some(elapsed) if *elapsed <= SLOW_LATENCY => {
+                    ("latency-slow", t!("latency-ms", ms = elapsed.as_millis()))
+                }
+                Some(elapsed) => ("latency-bad", t!("latency-ms", ms = elapsed.as_millis())),
+                None => ("latency-bad", t!("latency-no-answer")),
+            });
+            (current.server_label(address), readout)
+        })
+        .collect();
+    let last = servers.len() - 1;
+    let origin = current.origin(family).label();
+
+    rsx! {
+        div { class: "status-value",
+            for (index, (label, readout)) in servers.into_iter().enumerate() {
+                span { key: "{label}",
+                    "{label}"
+                    if let Some((class, text)) = readout {
+                        span { class: "latency {class}", " — {text}" }
+                    }
+                    if index < last {
+                        ", "
+                    }
+                }
+            }
+            if let Some(origin) = origin {
+                " ({origin})"
             }
         }
     }
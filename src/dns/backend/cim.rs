@@ -0,0 +1,315 @@
+use crate::dns::backend::native::{current_dns_from_addresses, split_addresses_by_family};
+use crate::dns::backend::powershell::{
+    doh_preview_steps, nrpt_preview_steps, suffix_preview_steps,
+};
+use crate::dns::backend::{DnsBackend, PowerShellBackend, PreviewStep, set_servers};
+use crate::dns::commands::{DnsCommandError, Result, collect_addresses};
+use crate::dns::types::{
+    ActiveNrptRule, AddressFamily, CurrentDnsState, DnsServerEntry, DnsSettings, NetworkInterface,
+    NrptRule,
+};
+
+/// Namespace of the CIM classes behind the DnsClient PowerShell module.
+const CIM_NAMESPACE: &str = r"ROOT\StandardCimv2";
+const SERVER_ADDRESS_CLASS: &str = "MSFT_DNSClientServerAddress";
+
+/// Backend that reads and sets server addresses through WMI, on the same
+/// `MSFT_DNSClientServerAddress` instances `Set-DnsClientServerAddress`
+/// changes, without starting a process. For machines where the IP Helper
+/// API of the native backend misbehaves. Operations without a CIM
+/// equivalent are delegated to PowerShell.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CimBackend;
+
+impl DnsBackend for CimBackend {
+    async fn get_current_dns(&self, interface: &NetworkInterface) -> Result<CurrentDnsState> {
+        let index = interface.interface_index;
+        let mut addresses =
+            run_blocking(move || query_server_addresses(index, AddressFamily::IPv4)).await?;
+        addresses.extend(
+            run_blocking(move || query_server_addresses(index, AddressFamily::IPv6)).await?,
+        );
+        current_dns_from_addresses(interface, &addresses)
+    }
+
+    async fn set_manual(&self, interface: &NetworkInterface, addresses: &[String]) -> Result<()> {
+        let (ipv4, ipv6) = split_addresses_by_family(addresses)?;
+        let index = interface.interface_index;
+        run_blocking(move || set_server_addresses(index, AddressFamily::IPv4, &ipv4)).await?;
+        run_blocking(move || set_server_addresses(index, AddressFamily::IPv6, &ipv6)).await
+    }
+
+    async fn set_automatic(&self, interface: &NetworkInterface) -> Result<()> {
+        self.set_manual(interface, &[]).await
+    }
+
+    async fn set_family(
+        &self,
+        interface: &NetworkInterface,
+        family: AddressFamily,
+        addresses: &[String],
+    ) -> Result<()> {
+        let (ipv4, ipv6) = split_addresses_by_family(addresses)?;
+        let servers = match family {
+            AddressFamily::IPv4 => ipv4,
+            AddressFamily::IPv6 => ipv6,
+        };
+        let index = interface.interface_index;
+        run_blocking(move || set_server_addresses(index, family, &servers)).await
+    }
+
+    async fn configure_doh(&self, server: &DnsServerEntry) -> Result<()> {
+        PowerShellBackend.configure_doh(server).await
+    }
+
+    async fn enable_doh_registry(
+        &self,
+        interface: &NetworkInterface,
+        servers: &[&DnsServerEntry],
+    ) -> Result<()> {
+        PowerShellBackend
+            .enable_doh_registry(interface, servers)
+            .await
+    }
+
+    async fn disable_doh_registry(&self, interface: &NetworkInterface) -> Result<()> {
+        PowerShellBackend.disable_doh_registry(interface).await
+    }
+
+    async fn get_doh_servers(&self) -> Result<Vec<String>> {
+        PowerShellBackend.get_doh_servers().await
+    }
+
+    async fn get_doh_templates(&self) -> Result<Vec<DnsServerEntry>> {
+        PowerShellBackend.get_doh_templates().await
+    }
+
+    async fn remove_doh_server(&self, address: &str) -> Result<()> {
+        PowerShellBackend.remove_doh_server(address).await
+    }
+
+    async fn set_connection_suffix(
+        &self,
+        interface: &NetworkInterface,
+        suffix: &str,
+    ) -> Result<()> {
+        PowerShellBackend
+            .set_connection_suffix(interface, suffix)
+            .await
+    }
+
+    async fn set_suffix_search_list(&self, suffixes: &[String]) -> Result<()> {
+        PowerShellBackend.set_suffix_search_list(suffixes).await
+    }
+
+    async fn get_nrpt_rules(&self) -> Result<Vec<ActiveNrptRule>> {
+        PowerShellBackend.get_nrpt_rules().await
+    }
+
+    async fn replace_nrpt_rules(&self, rules: &[NrptRule]) -> Result<()> {
+        PowerShellBackend.replace_nrpt_rules(rules).await
+    }
+
+    async fn remove_nrpt_rule(&self, name: &str) -> Result<()> {
+        PowerShellBackend.remove_nrpt_rule(name).await
+    }
+
+    fn preview_settings(
+        &self,
+        interface: &NetworkInterface,
+        settings: &DnsSettings,
+    ) -> Vec<PreviewStep> {
+        let addresses = collect_addresses(settings);
+        let (ipv4, ipv6) = split_addresses_by_family(&addresses).unwrap_or_default();
+        let describe = |family: AddressFamily, servers: &[String]| {
+            format!(
+                "{}(InterfaceIndex = {}, AddressFamily = {}).ServerAddresses = \"{}\"",
+                SERVER_ADDRESS_CLASS,
+                interface.interface_index,
+                family.as_str(),
+                servers.join(",")
+            )
+        };
+
+        let commands: Vec<String> = [
+            (AddressFamily::IPv4, &ipv4, &settings.ipv4),
+            (AddressFamily::IPv6, &ipv6, &settings.ipv6),
+        ]
+        .into_iter()
+        .filter(|(_, _, entry)| !entry.is_left_alone())
+        .map(|(family, servers, _)| describe(family, servers))
+        .collect();
+
+        let mut steps = Vec::new();
+        if !commands.is_empty() {
+            steps.push(PreviewStep::new(
+                "Set DNS servers (WMI)",
+                commands.join("\n"),
+            ));
+        }
+        steps.extend(doh_preview_steps(interface, settings));
+        steps.extend(suffix_preview_steps(interface, settings));
+        steps.extend(nrpt_preview_steps(settings));
+        steps
+    }
+
+    async fn apply_settings(
+        &self,
+        interface: &NetworkInterface,
+        settings: &DnsSettings,
+    ) -> Result<Option<String>> {
+        set_servers(self, interface, settings).await?;
+        PowerShellBackend.apply_doh_batch(interface, settings).await
+    }
+}
+
+/// `AddressFamily` values of the CIM class, which are the Winsock ones.
+fn cim_address_family(family: AddressFamily) -> u16 {
+    match family {
+        AddressFamily::IPv4 => 2,
+        AddressFamily::IPv6 => 23,
+    }
+}
+
+/// WQL query for the server address instance of one family of an
+/// interface.
+fn server_address_query(interface_index: u32, family: AddressFamily) -> String {
+    format!(
+        "SELECT * FROM {} WHERE InterfaceIndex = {} AND AddressFamily = {}",
+        SERVER_ADDRESS_CLASS,
+        interface_index,
+        cim_address_family(family)
+    )
+}
+
+/// Runs a WMI call on a blocking thread, which initializes COM for itself.
+async fn run_blocking<T: Send + 'static>(
+    call: impl FnOnce() -> Result<T> + Send + 'static,
+) -> Result<T> {
+    tokio::task::spawn_blocking(call)
+        .await
+        .map_err(|e| DnsCommandError::WindowsApi(e.to_string()))?
+}
+
+#[cfg(target_os = "windows")]
+fn wmi_error(error: impl std::fmt::Display) -> DnsCommandError {
+    DnsCommandError::WindowsApi(format!("WMI: {}", error))
+}
+
+#[cfg(target_os = "windows")]
+fn connect() -> Result<wmi::WMIConnection> {
+    let com = wmi::COMLibrary::new().map_err(wmi_error)?;
+    wmi::WMIConnection::with_namespace_path(CIM_NAMESPACE, com).map_err(wmi_error)
+}
+
+#[cfg(target_os = "windows")]
+fn query_server_addresses(interface_index: u32, family: AddressFamily) -> Result<Vec<String>> {
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct ServerAddress {
+        server_addresses: Option<Vec<String>>,
+    }
+
+    let rows: Vec<ServerAddress> = connect()?
+        .raw_query(server_address_query(interface_index, family))
+        .map_err(wmi_error)?;
+    Ok(rows
+        .into_iter()
+        .flat_map(|row| row.server_addresses.unwrap_or_default())
+        .collect())
+}
+
+#[cfg(not(target_os = "windows"))]
+fn query_server_addresses(_interface_index: u32, _family: AddressFamily) -> Result<Vec<String>> {
+    Err(DnsCommandError::WindowsApi(
+        "Not supported on this platform".to_string(),
+    ))
+}
+
+/// Writes the servers of one family to its CIM instance, as
+/// `Set-DnsClientServerAddress` does. An empty list resets the family to
+/// the DHCP-provided servers.
+#[cfg(target_os = "windows")]
+fn set_server_addresses(
+    interface_index: u32,
+    family: AddressFamily,
+    addresses: &[String],
+) -> Result<()> {
+    use wmi::Variant;
+
+    let connection = connect()?;
+    let instance = connection
+        .exec_query_native_wrapper(server_address_query(interface_index, family))
+        .map_err(wmi_error)?
+        .next()
+        .ok_or_else(|| {
+            DnsCommandError::WindowsApi(format!(
+                "No {} instance for interface {}",
+                SERVER_ADDRESS_CLASS, interface_index
+            ))
+        })?
+        .map_err(wmi_error)?;
+
+    let value = if addresses.is_empty() {
+        Variant::Null
+    } else {
+        Variant::Array(addresses.iter().cloned().map(Variant::String).collect())
+    };
+    instance
+        .put_property("ServerAddresses", value)
+        .map_err(wmi_error)?;
+    // The default flags update the existing instance, like the ModifyInstance
+    // call the cmdlet makes.
+    unsafe {
+        connection
+            .svc
+            .PutInstance(&instance.inner, Default::default(), None, None)
+    }
+    .map_err(wmi_error)
+}
+
+#[cfg(not(target_os = "windows"))]
+fn set_server_addresses(
+    _interface_index: u32,
+    _family: AddressFamily,
+    _addresses: &[String],
+) -> Result<()> {
+    Err(DnsCommandError::WindowsApi(
+        "Not supported on this platform".to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dns::types::test_interface;
+
+    #[test]
+    fn test_preview_settings_lists_both_families() {
+        let interface = test_interface("Ethernet", 3);
+        let mut settings = DnsSettings::new();
+        settings.ipv4.enabled = true;
+        settings.ipv4.servers[0].address = "9.9.9.9".to_string();
+        settings.ipv4.servers[1].address = "149.112.112.112".to_string();
+
+        let steps = CimBackend.preview_settings(&interface, &settings);
+        assert_eq!(steps[0].title, "Set DNS servers (WMI)");
+        assert_eq!(
+            steps[0].command,
+            "MSFT_DNSClientServerAddress(InterfaceIndex = 3, AddressFamily = IPv4)\
+             .ServerAddresses = \"9.9.9.9,149.112.112.112\"\n\
+             MSFT_DNSClientServerAddress(InterfaceIndex = 3, AddressFamily = IPv6)\
+             .ServerAddresses = \"\""
+        );
+    }
+
+    #[test]
+    fn test_server_address_query() {
+        assert_eq!(
+            server_address_query(12, AddressFamily::IPv6),
+            "SELECT * FROM MSFT_DNSClientServerAddress WHERE InterfaceIndex = 12 AND AddressFamily = 23"
+        );
+    }
+}
@@ -0,0 +1,262 @@
+use crate::dns::backend::{DnsBackend, PreviewStep};
+use crate::dns::commands::{DnsCommandError, Result, collect_addresses};
+use crate::dns::types::{
+    ActiveNrptRule, AddressFamily, CurrentDnsState, DnsServerEntry, DnsSettings, DohMode,
+    NetworkInterface, NrptRule,
+};
+use std::cell::RefCell;
+
+/// In-memory backend for tests. Records every call and can be told to fail
+/// specific operations.
+#[derive(Debug, Default)]
+pub struct MockBackend {
+    pub current: RefCell<CurrentDnsState>,
+    pub calls: RefCell<Vec<String>>,
+    pub fail_set: bool,
+    pub fail_registry: bool,
+    pub fail_disable_registry: bool,
+    pub fail_suffixes: bool,
+    pub fail_nrpt: bool,
+    pub nrpt_rules: RefCell<Vec<ActiveNrptRule>>,
+    pub failing_doh_addresses: Vec<String>,
+    pub doh_servers: RefCell<Vec<String>>,
+}
+
+impl MockBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn calls(&self) -> Vec<String> {
+        self.calls.borrow().clone()
+    }
+
+    fn record(&self, call: String) {
+        self.calls.borrow_mut().push(call);
+    }
+}
+
+impl DnsBackend for MockBackend {
+    async fn get_current_dns(&self, interface: &NetworkInterface) -> Result<CurrentDnsState> {
+        self.record(format!("get_current_dns {}", interface.interface_index));
+        Ok(self.current.borrow().clone())
+    }
+
+    async fn set_manual(&self, interface: &NetworkInterface, addresses: &[String]) -> Result<()> {
+        self.record(format!(
+            "set_manual {} {}",
+            interface.interface_index,
+            addresses.join(",")
+        ));
+        if self.fail_set {
+            return Err(DnsCommandError::CommandFailed("mock failure".to_string()));
+        }
+
+        let mut current = self.current.borrow_mut();
+        current.ipv4 = addresses
+            .iter()
+            .filter(|a| !a.contains(':'))
+            .cloned()
+            .collect();
+        current.ipv6 = addresses
+            .iter()
+            .filter(|a| a.contains(':'))
+            .cloned()
+            .collect();
+        Ok(())
+    }
+
+    async fn set_family(
+        &self,
+        interface: &NetworkInterface,
+        family: AddressFamily,
+        addresses: &[String],
+    ) -> Result<()> {
+        self.record(format!(
+            "set_family {} {} {}",
+            interface.interface_index,
+            family.as_str(),
+            addresses.join(",")
+        ));
+        if self.fail_set {
+            return Err(DnsCommandError::CommandFailed("mock failure".to_string()));
+        }
+
+        let mut current = self.current.borrow_mut();
+        match family {
+            AddressFamily::IPv4 => current.ipv4 = addresses.to_vec(),
+            AddressFamily::IPv6 => current.ipv6 = addresses.to_vec(),
+        }
+        Ok(())
+    }
+
+    async fn set_automatic(&self, interface: &NetworkInterface) -> Result<()> {
+        self.record(format!("set_automatic {}", interface.interface_index));
+        if self.fail_set {
+            return Err(DnsCommandError::CommandFailed("mock failure".to_string()));
+        }
+
+        *self.current.borrow_mut() = CurrentDnsState::new();
+        Ok(())
+    }
+
+    async fn configure_doh(&self, server: &DnsServerEntry) -> Result<()> {
+        self.record(format!(
+            "configure_doh {} {}",
+            server.address, server.doh_template
+        ));
+        if self.failing_doh_addresses.contains(&server.address) {
+            return Err(DnsCommandError::CommandFailed(format!(
+                "mock DoH failure for {}",
+                server.address
+            )));
+        }
+
+        let mut doh_servers = self.doh_servers.borrow_mut();
+        if !doh_servers.contains(&server.address) {
+            doh_servers.push(server.address.clone());
+        }
+        Ok(())
+    }
+
+    async fn enable_doh_registry(
+        &self,
+        interface: &NetworkInterface,
+        servers: &[&DnsServerEntry],
+    ) -> Result<()> {
+        self.record(format!(
+            "enable_doh_registry {} {}",
+            interface.interface_guid,
+            servers
+                .iter()
+                .map(|s| s.address.as_str())
+                .collect::<Vec<_>>()
+                .join(",")
+        ));
+        if self.fail_registry {
+            return Err(DnsCommandError::RegistryFailed(
+                "mock registry failure".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    async fn disable_doh_registry(&self, interface: &NetworkInterface) -> Result<()> {
+        self.record(format!("disable_doh_registry {}", interface.interface_guid));
+        if self.fail_disable_registry {
+            return Err(DnsCommandError::RegistryFailed(
+                "mock registry failure".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    async fn get_doh_servers(&self) -> Result<Vec<String>> {
+        self.record("get_doh_servers".to_string());
+        Ok(self.doh_servers.borrow().clone())
+    }
+
+    async fn get_doh_templates(&self) -> Result<Vec<DnsServerEntry>> {
+        self.record("get_doh_templates".to_string());
+        Ok(self
+            .doh_servers
+            .borrow()
+            .iter()
+            .map(|address| DnsServerEntry {
+                address: address.clone(),
+                doh_mode: DohMode::On,
+                doh_template: format!("https://{}/dns-query", address),
+                allow_fallback: false,
+                auto_upgrade: true,
+            })
+            .collect())
+    }
+
+    async fn remove_doh_server(&self, address: &str) -> Result<()> {
+        self.record(format!("remove_doh_server {}", address));
+        if self.failing_doh_addresses.iter().any(|a| a == address) {
+            return Err(DnsCommandError::CommandFailed(format!(
+                "mock DoH failure for {}",
+                address
+            )));
+        }
+
+        self.doh_servers.borrow_mut().retain(|a| a != address);
+        Ok(())
+    }
+
+    async fn set_connection_suffix(
+        &self,
+        interface: &NetworkInterface,
+        suffix: &str,
+    ) -> Result<()> {
+        self.record(format!(
+            "set_connection_suffix {} {}",
+            interface.interface_index, suffix
+        ));
+        if self.fail_suffixes {
+            return Err(DnsCommandError::CommandFailed(
+                "mock suffix failure".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    async fn set_suffix_search_list(&self, suffixes: &[String]) -> Result<()> {
+        self.record(format!("set_suffix_search_list {}", suffixes.join(",")));
+        if self.fail_suffixes {
+            return Err(DnsCommandError::CommandFailed(
+                "mock suffix failure".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    async fn get_nrpt_rules(&self) -> Result<Vec<ActiveNrptRule>> {
+        self.record("get_nrpt_rules".to_string());
+        Ok(self.nrpt_rules.borrow().clone())
+    }
+
+    async fn replace_nrpt_rules(&self, rules: &[NrptRule]) -> Result<()> {
+        self.record(format!(
+            "replace_nrpt_rules {}",
+            rules
+                .iter()
+                .map(|r| format!("{}={}", r.normalized_namespace(), r.name_servers))
+                .collect::<Vec<_>>()
+                .join(";")
+        ));
+        if self.fail_nrpt {
+            return Err(DnsCommandError::CommandFailed(
+                "mock NRPT failure".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    async fn remove_nrpt_rule(&self, name: &str) -> Result<()> {
+        self.record(format!("remove_nrpt_rule {}", name));
+        if self.fail_nrpt {
+            return Err(DnsCommandError::CommandFailed(
+                "mock NRPT failure".to_string(),
+            ));
+        }
+        self.nrpt_rules.borrow_mut().retain(|r| r.name != name);
+        Ok(())
+    }
+
+    fn preview_settings(
+        &self,
+        interface: &NetworkInterface,
+        settings: &DnsSettings,
+    ) -> Vec<PreviewStep> {
+        vec![PreviewStep::new(
+            "Set DNS servers",
+            format!(
+                "set_manual {} {}",
+                interface.interface_index,
+                collect_addresses(settings).join(",")
+            ),
+        )]
+    }
+}
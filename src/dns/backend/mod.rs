@@ -0,0 +1,383 @@
+pub mod cim;
+#[cfg(test)]
+pub mod mock;
+pub mod native;
+pub mod netsh;
+pub mod powershell;
+
+use crate::dns::commands::{
+    DnsCommandError, DohOutcome, Result, collect_addresses, doh_targets, is_powershell_available,
+};
+use crate::dns::types::{
+    ActiveNrptRule, AddressFamily, CurrentDnsState, DnsServerEntry, DnsSettings, NetworkInterface,
+    NrptRule,
+};
+
+pub use cim::CimBackend;
+pub use native::NativeBackend;
+pub use netsh::NetshBackend;
+pub use powershell::PowerShellBackend;
+
+/// A single operation an apply would perform, for display in the preview.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PreviewStep {
+    pub title: String,
+    pub command: String,
+}
+
+impl PreviewStep {
+    pub fn new(title: impl Into<String>, command: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            command: command.into(),
+        }
+    }
+}
+
+/// Operations needed to read and change the DNS configuration of an interface.
+pub trait DnsBackend {
+    async fn get_current_dns(&self, interface: &NetworkInterface) -> Result<CurrentDnsState>;
+
+    async fn set_manual(&self, interface: &NetworkInterface, addresses: &[String]) -> Result<()>;
+
+    async fn set_automatic(&self, interface: &NetworkInterface) -> Result<()>;
+
+    /// Sets the servers of one address family, leaving the other as it is.
+    /// An empty list resets the family to DHCP.
+    async fn set_family(
+        &self,
+        interface: &NetworkInterface,
+        family: AddressFamily,
+        addresses: &[String],
+    ) -> Result<()>;
+
+    /// Registers the DoH template for a server address system-wide.
+    async fn configure_doh(&self, server: &DnsServerEntry) -> Result<()>;
+
+    /// Writes the DoH settings of `servers` to the Dnscache registry key of
+    /// the interface, replacing those of servers that are no longer used.
+    async fn enable_doh_registry(
+        &self,
+        interface: &NetworkInterface,
+        servers: &[&DnsServerEntry],
+    ) -> Result<()>;
+
+    /// Removes the DoH marker set by `enable_doh_registry`, if present.
+    async fn disable_doh_registry(&self, interface: &NetworkInterface) -> Result<()>;
+
+    /// Server addresses that have a system-wide DoH template registered.
+    async fn get_doh_servers(&self) -> Result<Vec<String>>;
+
+    /// System-wide DoH templates, as server entries with DoH turned on.
+    async fn get_doh_templates(&self) -> Result<Vec<DnsServerEntry>>;
+
+    /// Removes the system-wide DoH template of a server address, if present.
+    async fn remove_doh_server(&self, address: &str) -> Result<()>;
+
+    /// Sets the connection-specific suffix of the interface. An empty suffix
+    /// clears it.
+    async fn set_connection_suffix(&self, interface: &NetworkInterface, suffix: &str)
+    -> Result<()>;
+
+    /// Replaces the system-wide DNS suffix search list.
+    async fn set_suffix_search_list(&self, suffixes: &[String]) -> Result<()>;
+
+    /// Every NRPT rule on the system, including those of other tools.
+    async fn get_nrpt_rules(&self) -> Result<Vec<ActiveNrptRule>>;
+
+    /// Replaces the NRPT rules created by windns with `rules`.
+    async fn replace_nrpt_rules(&self, rules: &[NrptRule]) -> Result<()>;
+
+    /// Deletes an NRPT rule by the name Windows assigned to it.
+    async fn remove_nrpt_rule(&self, name: &str) -> Result<()>;
+
+    /// Describes what `apply_settings` would execute, without executing it.
+    fn preview_settings(
+        &self,
+        interface: &NetworkInterface,
+        settings: &DnsSettings,
+    ) -> Vec<PreviewStep>;
+
+    /// Applies a complete set of DNS settings. The default implementation
+    /// runs each operation separately; backends may override it to batch them.
+    async fn apply_settings(
+        &self,
+        interface: &NetworkInterface,
+        settings: &DnsSettings,
+    ) -> Result<Option<String>> {
+        set_servers(self, interface, settings).await?;
+
+        let mut outcome = DohOutcome::default();
+        let targets = doh_targets(settings);
+        if targets.is_empty() {
+            // The marker covers both families, so it stays while one is left
+            // alone.
+            if !settings.leaves_family_unchanged()
+                && let Err(e) = self.disable_doh_registry(interface).await
+            {
+                outcome.disable_error = Some(registry_error_message(e));
+            }
+            return outcome.into_result();
+        }
+
+        let mut configured = Vec::new();
+        for (label, server) in targets {
+            let error = self.configure_doh(server).await.err();
+            if error.is_none() {
+                configured.push(server);
+            }
+            outcome.record(&label, error.map(|e| e.to_string()));
+        }
+
+        if !configured.is_empty()
+            && let Err(e) = self.enable_doh_registry(interface, &configured).await
+        {
+            outcome.registry_error = Some(registry_error_message(e));
+        }
+
+        outcome.into_result()
+    }
+}
+
+/// Sets the servers of `settings`. Both families are set at once, unless
+/// one is left alone, which then keeps the servers it has.
+pub(crate) async fn set_servers<B: DnsBackend + ?Sized>(
+    backend: &B,
+    interface: &NetworkInterface,
+    settings: &DnsSettings,
+) -> Result<()> {
+    if !settings.leaves_family_unchanged() {
+        let addresses = collect_addresses(settings);
+        return if addresses.is_empty() {
+            backend.set_automatic(interface).await
+        } else {
+            backend.set_manual(interface, &addresses).await
+        };
+    }
+
+    for (family, entry) in [
+        (AddressFamily::IPv4, &settings.ipv4),
+        (AddressFamily::IPv6, &settings.ipv6),
+    ] {
+        if entry.is_left_alone() {
+            continue;
+        }
+        let addresses = if entry.enabled {
+            entry.get_addresses()
+        } else {
+            Vec::new()
+        };
+        backend.set_family(interface, family, &addresses).await?;
+    }
+    Ok(())
+}
+
+pub(crate) fn registry_error_message(error: DnsCommandError) -> String {
+    match error {
+        DnsCommandError::RegistryFailed(msg) => msg,
+        other => other.to_string(),
+    }
+}
+
+/// Backend chosen at runtime for the current machine.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum SystemBackend {
+    #[default]
+    Native,
+    /// Sets server addresses through WMI instead of the IP Helper API.
+    Cim,
+    Netsh,
+}
+
+impl SystemBackend {
+    /// Uses the native backend when PowerShell is usable and falls back to
+    /// netsh on machines where PowerShell is blocked.
+    pub async fn detect() -> Self {
+        if is_powershell_available().await {
+            Self::Native
+        } else {
+            Self::Netsh
+        }
+    }
+
+    /// The backend to use when the CIM backend is chosen in the settings,
+    /// or not. Netsh stays, since the others need PowerShell for DoH.
+    pub fn with_cim(self, use_cim: bool) -> Self {
+        match self {
+            Self::Native | Self::Cim if use_cim => Self::Cim,
+            Self::Native | Self::Cim => Self::Native,
+            Self::Netsh => Self::Netsh,
+        }
+    }
+
+    pub fn supports_doh(&self) -> bool {
+        matches!(self, Self::Native | Self::Cim)
+    }
+
+    pub fn supports_suffixes(&self) -> bool {
+        matches!(self, Self::Native | Self::Cim)
+    }
+
+    pub fn supports_nrpt(&self) -> bool {
+        matches!(self, Self::Native | Self::Cim)
+    }
+}
+
+impl DnsBackend for SystemBackend {
+    async fn get_current_dns(&self, interface: &NetworkInterface) -> Result<CurrentDnsState> {
+        match self {
+            Self::Native => NativeBackend.get_current_dns(interface).await,
+            Self::Cim => CimBackend.get_current_dns(interface).await,
+            Self::Netsh => NetshBackend.get_current_dns(interface).await,
+        }
+    }
+
+    async fn set_manual(&self, interface: &NetworkInterface, addresses: &[String]) -> Result<()> {
+        match self {
+            Self::Native => NativeBackend.set_manual(interface, addresses).await,
+            Self::Cim => CimBackend.set_manual(interface, addresses).await,
+            Self::Netsh => NetshBackend.set_manual(interface, addresses).await,
+        }
+    }
+
+    async fn set_automatic(&self, interface: &NetworkInterface) -> Result<()> {
+        match self {
+            Self::Native => NativeBackend.set_automatic(interface).await,
+            Self::Cim => CimBackend.set_automatic(interface).await,
+            Self::Netsh => NetshBackend.set_automatic(interface).await,
+        }
+    }
+
+    async fn set_family(
+        &self,
+        interface: &NetworkInterface,
+        family: AddressFamily,
+        addresses: &[String],
+    ) -> Result<()> {
+        match self {
+            Self::Native => NativeBackend.set_family(interface, family, addresses).await,
+            Self::Cim => CimBackend.set_family(interface, family, addresses).await,
+            Self::Netsh => NetshBackend.set_family(interface, family, addresses).await,
+        }
+    }
+
+    async fn configure_doh(&self, server: &DnsServerEntry) -> Result<()> {
+        match self {
+            Self::Native => NativeBackend.configure_doh(server).await,
+            Self::Cim => CimBackend.configure_doh(server).await,
+            Self::Netsh => NetshBackend.configure_doh(server).await,
+        }
+    }
+
+    async fn enable_doh_registry(
+        &self,
+        interface: &NetworkInterface,
+        servers: &[&DnsServerEntry],
+    ) -> Result<()> {
+        match self {
+            Self::Native => NativeBackend.enable_doh_registry(interface, servers).await,
+            Self::Cim => CimBackend.enable_doh_registry(interface, servers).await,
+            Self::Netsh => NetshBackend.enable_doh_registry(interface, servers).await,
+        }
+    }
+
+    async fn disable_doh_registry(&self, interface: &NetworkInterface) -> Result<()> {
+        match self {
+            Self::Native => NativeBackend.disable_doh_registry(interface).await,
+            Self::Cim => CimBackend.disable_doh_registry(interface).await,
+            Self::Netsh => NetshBackend.disable_doh_registry(interface).await,
+        }
+    }
+
+    async fn get_doh_servers(&self) -> Result<Vec<String>> {
+        match self {
+            Self::Native => NativeBackend.get_doh_servers().await,
+            Self::Cim => CimBackend.get_doh_servers().await,
+            Self::Netsh => NetshBackend.get_doh_servers().await,
+        }
+    }
+
+    async fn get_doh_templates(&self) -> Result<Vec<DnsServerEntry>> {
+        match self {
+            Self::Native => NativeBackend.get_doh_templates().await,
+            Self::Cim => CimBackend.get_doh_templates().await,
+            Self::Netsh => NetshBackend.get_doh_templates().await,
+        }
+    }
+
+    async fn remove_doh_server(&self, address: &str) -> Result<()> {
+        match self {
+            Self::Native => NativeBackend.remove_doh_server(address).await,
+            Self::Cim => CimBackend.remove_doh_server(address).await,
+            Self::Netsh => NetshBackend.remove_doh_server(address).await,
+        }
+    }
+
+    async fn set_connection_suffix(
+        &self,
+        interface: &NetworkInterface,
+        suffix: &str,
+    ) -> Result<()> {
+        match self {
+            Self::Native => NativeBackend.set_connection_suffix(interface, suffix).await,
+            Self::Cim => CimBackend.set_connection_suffix(interface, suffix).await,
+            Self::Netsh => NetshBackend.set_connection_suffix(interface, suffix).await,
+        }
+    }
+
+    async fn set_suffix_search_list(&self, suffixes: &[String]) -> Result<()> {
+        match self {
+            Self::Native => NativeBackend.set_suffix_search_list(suffixes).await,
+            Self::Cim => CimBackend.set_suffix_search_list(suffixes).await,
+            Self::Netsh => NetshBackend.set_suffix_search_list(suffixes).await,
+        }
+    }
+
+    async fn get_nrpt_rules(&self) -> Result<Vec<ActiveNrptRule>> {
+        match self {
+            Self::Native => NativeBackend.get_nrpt_rules().await,
+            Self::Cim => CimBackend.get_nrpt_rules().await,
+            Self::Netsh => NetshBackend.get_nrpt_rules().await,
+        }
+    }
+
+    async fn replace_nrpt_rules(&self, rules: &[NrptRule]) -> Result<()> {
+        match self {
+            Self::Native => NativeBackend.replace_nrpt_rules(rules).await,
+            Self::Cim => CimBackend.replace_nrpt_rules(rules).await,
+            Self::Netsh => NetshBackend.replace_nrpt_rules(rules).await,
+        }
+    }
+
+    async fn remove_nrpt_rule(&self, name: &str) -> Result<()> {
+        match self {
+            Self::Native => NativeBackend.remove_nrpt_rule(name).await,
+            Self::Cim => CimBackend.remove_nrpt_rule(name).await,
+            Self::Netsh => NetshBackend.remove_nrpt_rule(name).await,
+        }
+    }
+
+    fn preview_settings(
+        &self,
+        interface: &NetworkInterface,
+        settings: &DnsSettings,
+    ) -> Vec<PreviewStep> {
+        match self {
+            Self::Native => NativeBackend.preview_settings(interface, settings),
+            Self::Cim => CimBackend.preview_settings(interface, settings),
+            Self::Netsh => NetshBackend.preview_settings(interface, settings),
+        }
+    }
+
+    async fn apply_settings(
+        &self,
+        interface: &NetworkInterface,
+        settings: &DnsSettings,
+    ) -> Result<Option<String>> {
+        match self {
+            Self::Native => NativeBackend.apply_settings(interface, settings).await,
+            Self::Cim => CimBackend.apply_settings(interface, settings).await,
+            Self::Netsh => NetshBackend.apply_settings(interface, settings).await,
+        }
+    }
+}
@@ -0,0 +1,450 @@
+use crate::dns::backend::powershell::{
+    doh_preview_steps, nrpt_preview_steps, suffix_preview_steps,
+};
+use crate::dns::backend::{DnsBackend, PowerShellBackend, PreviewStep, set_servers};
+use crate::dns::commands::{DnsCommandError, Result, collect_addresses, normalize_guid};
+use crate::dns::network::get_dns_server_addresses;
+use crate::dns::types::{
+    ActiveNrptRule, AddressFamily, CurrentDnsState, DnsOrigin, DnsServerEntry, DnsSettings,
+    NetworkInterface, NrptRule,
+};
+use crate::dns::validation::parse_ipv6_with_zone;
+use std::net::IpAddr;
+
+/// Backend that sets server addresses through the IP Helper API.
+/// Operations without a native equivalent are delegated to PowerShell.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NativeBackend;
+
+impl DnsBackend for NativeBackend {
+    async fn get_current_dns(&self, interface: &NetworkInterface) -> Result<CurrentDnsState> {
+        match read_current_dns(interface) {
+            Ok(state) => Ok(state),
+            Err(_) => PowerShellBackend.get_current_dns(interface).await,
+        }
+    }
+
+    async fn set_manual(&self, interface: &NetworkInterface, addresses: &[String]) -> Result<()> {
+        if addresses.is_empty() {
+            return self.set_automatic(interface).await;
+        }
+        set_dns_servers(&interface.interface_guid, addresses)
+    }
+
+    async fn set_automatic(&self, interface: &NetworkInterface) -> Result<()> {
+        reset_dns_servers(&interface.interface_guid)
+    }
+
+    async fn set_family(
+        &self,
+        interface: &NetworkInterface,
+        family: AddressFamily,
+        addresses: &[String],
+    ) -> Result<()> {
+        let (ipv4, ipv6) = split_addresses_by_family(addresses)?;
+        let (servers, ipv6_family) = match family {
+            AddressFamily::IPv4 => (ipv4, false),
+            AddressFamily::IPv6 => (ipv6, true),
+        };
+        set_name_servers(&interface.interface_guid, &servers.join(","), ipv6_family)
+    }
+
+    async fn configure_doh(&self, server: &DnsServerEntry) -> Result<()> {
+        PowerShellBackend.configure_doh(server).await
+    }
+
+    async fn enable_doh_registry(
+        &self,
+        interface: &NetworkInterface,
+        servers: &[&DnsServerEntry],
+    ) -> Result<()> {
+        PowerShellBackend
+            .enable_doh_registry(interface, servers)
+            .await
+    }
+
+    async fn disable_doh_registry(&self, interface: &NetworkInterface) -> Result<()> {
+        PowerShellBackend.disable_doh_registry(interface).await
+    }
+
+    async fn get_doh_servers(&self) -> Result<Vec<String>> {
+        PowerShellBackend.get_doh_servers().await
+    }
+
+    async fn get_doh_templates(&self) -> Result<Vec<DnsServerEntry>> {
+        PowerShellBackend.get_doh_templates().await
+    }
+
+    async fn remove_doh_server(&self, address: &str) -> Result<()> {
+        PowerShellBackend.remove_doh_server(address).await
+    }
+
+    async fn set_connection_suffix(
+        &self,
+        interface: &NetworkInterface,
+        suffix: &str,
+    ) -> Result<()> {
+        PowerShellBackend
+            .set_connection_suffix(interface, suffix)
+            .await
+    }
+
+    async fn set_suffix_search_list(&self, suffixes: &[String]) -> Result<()> {
+        PowerShellBackend.set_suffix_search_list(suffixes).await
+    }
+
+    async fn get_nrpt_rules(&self) -> Result<Vec<ActiveNrptRule>> {
+        PowerShellBackend.get_nrpt_rules().await
+    }
+
+    async fn replace_nrpt_rules(&self, rules: &[NrptRule]) -> Result<()> {
+        PowerShellBackend.replace_nrpt_rules(rules).await
+    }
+
+    async fn remove_nrpt_rule(&self, name: &str) -> Result<()> {
+        PowerShellBackend.remove_nrpt_rule(name).await
+    }
+
+    fn preview_settings(
+        &self,
+        interface: &NetworkInterface,
+        settings: &DnsSettings,
+    ) -> Vec<PreviewStep> {
+        let addresses = collect_addresses(settings);
+        let (ipv4, ipv6) = split_addresses_by_family(&addresses).unwrap_or_default();
+        let commands: Vec<String> = [
+            ("IPv4", &ipv4, &settings.ipv4),
+            ("IPv6", &ipv6, &settings.ipv6),
+        ]
+        .into_iter()
+        .filter(|(_, _, entry)| !entry.is_left_alone())
+        .map(|(family, servers, _)| {
+            format!(
+                "SetInterfaceDnsSettings({}, {} NameServer = \"{}\")",
+                interface.interface_guid,
+                family,
+                servers.join(",")
+            )
+        })
+        .collect();
+
+        let mut steps = Vec::new();
+        if !commands.is_empty() {
+            steps.push(PreviewStep::new(
+                "Set DNS servers (IP Helper API)",
+                commands.join("\n"),
+            ));
+        }
+        steps.extend(doh_preview_steps(interface, settings));
+        steps.extend(suffix_preview_steps(interface, settings));
+        steps.extend(nrpt_preview_steps(settings));
+        steps
+    }
+
+    async fn apply_settings(
+        &self,
+        interface: &NetworkInterface,
+        settings: &DnsSettings,
+    ) -> Result<Option<String>> {
+        set_servers(self, interface, settings).await?;
+        PowerShellBackend.apply_doh_batch(interface, settings).await
+    }
+}
+
+/// Splits a mixed address list into IPv4 and IPv6 server lists,
+/// preserving order and the zone ID of link-local IPv6 servers.
+/// Unparseable entries are returned as errors.
+pub fn split_addresses_by_family(addresses: &[String]) -> Result<(Vec<String>, Vec<String>)> {
+    let mut ipv4 = Vec::new();
+    let mut ipv6 = Vec::new();
+
+    for address in addresses {
+        match address.trim().parse::<IpAddr>() {
+            Ok(IpAddr::V4(addr)) => ipv4.push(addr.to_string()),
+            Ok(IpAddr::V6(addr)) => ipv6.push(addr.to_string()),
+            Err(_) if let Some((addr, Some(zone))) = parse_ipv6_with_zone(address.trim()) => {
+                ipv6.push(format!("{}%{}", addr, zone))
+            }
+            Err(_) => {
+                return Err(DnsCommandError::WindowsApi(format!(
+                    "Invalid DNS server address: {}",
+                    address
+                )));
+            }
+        }
+    }
+
+    Ok((ipv4, ipv6))
+}
+
+/// Reads the current DNS state without starting PowerShell: the servers
+/// from `GetAdaptersAddresses`, their origin from the static configuration,
+/// and DoH use from the DNS client's registry settings.
+pub fn read_current_dns(interface: &NetworkInterface) -> Result<CurrentDnsState> {
+    let addresses = get_dns_server_addresses(&interface.interface_guid)
+        .map_err(|e| DnsCommandError::WindowsApi(e.to_string()))?;
+    current_dns_from_addresses(interface, &addresses)
+}
+
+/// The current DNS state for servers read one way or another, with their
+/// origin and DoH use looked up as `read_current_dns` does.
+pub(crate) fn current_dns_from_addresses(
+    interface: &NetworkInterface,
+    addresses: &[String],
+) -> Result<CurrentDnsState> {
+    let (ipv4, ipv6) = split_addresses_by_family(addresses)?;
+
+    let origin = |ipv6: bool| match get_name_servers(&interface.interface_guid, ipv6) {
+        Ok(servers) if servers.trim().is_empty() => DnsOrigin::Dhcp,
+        Ok(_) => DnsOrigin::Static,
+        Err(_) => DnsOrigin::Unknown,
+    };
+    let doh_servers = ipv4
+        .iter()
+        .chain(&ipv6)
+        .filter(|address| uses_doh(interface, address, registry_value_exists))
+        .cloned()
+        .collect();
+
+    Ok(CurrentDnsState {
+        ipv4_origin: origin(false),
+        ipv6_origin: origin(true),
+        ipv4,
+        ipv6,
+        doh_servers,
+    })
+}
+
+/// Same check as `current_dns_script`: the server needs a registered DoH
+/// template, and DoH must be on for the whole interface or for the server.
+fn uses_doh(
+    interface: &NetworkInterface,
+    address: &str,
+    value_exists: impl Fn(&str, &str) -> bool,
+) -> bool {
+    let interface_key = format!(
+        r"SYSTEM\CurrentControlSet\Services\Dnscache\InterfaceSpecificParameters\{{{}}}",
+        normalize_guid(&interface.interface_guid)
+    );
+    let family_key = if address.contains(':') { "Doh6" } else { "Doh" };
+    let template_key = format!(
+        r"SYSTEM\CurrentControlSet\Services\Dnscache\Parameters\DohWellKnownServers\{}",
+        address
+    );
+    let server_key = format!(
+        r"{}\DohInterfaceSettings\{}\{}",
+        interface_key, family_key, address
+    );
+
+    value_exists(&template_key, "Template")
+        && (value_exists(&interface_key, "DohFlags") || value_exists(&server_key, "DohFlags"))
+}
+
+#[cfg(target_os = "windows")]
+fn registry_value_exists(subkey: &str, name: &str) -> bool {
+    use windows::Win32::Foundation::NO_ERROR;
+    use windows::Win32::System::Registry::{HKEY_LOCAL_MACHINE, RRF_RT_ANY, RegGetValueW};
+    use windows::core::HSTRING;
+
+    let mut size = 0u32;
+    let result = unsafe {
+        RegGetValueW(
+            HKEY_LOCAL_MACHINE,
+            &HSTRING::from(subkey),
+            &HSTRING::from(name),
+            RRF_RT_ANY,
+            None,
+            None,
+            Some(&mut size),
+        )
+    };
+    result == NO_ERROR
+}
+
+#[cfg(not(target_os = "windows"))]
+fn registry_value_exists(_subkey: &str, _name: &str) -> bool {
+    false
+}
+
+/// Sets the DNS servers of an interface. A family with an empty list is
+/// reset to the DHCP-provided servers.
+pub fn set_dns_servers(interface_guid: &str, addresses: &[String]) -> Result<()> {
+    let (ipv4, ipv6) = split_addresses_by_family(addresses)?;
+    set_name_servers(interface_guid, &ipv4.join(","), false)?;
+    set_name_servers(interface_guid, &ipv6.join(","), true)?;
+    Ok(())
+}
+
+/// Resets both address families of an interface to DHCP-provided servers.
+pub fn reset_dns_servers(interface_guid: &str) -> Result<()> {
+    set_name_servers(interface_guid, "", false)?;
+    set_name_servers(interface_guid, "", true)?;
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn parse_interface_guid(interface_guid: &str) -> Result<windows::core::GUID> {
+    windows::core::GUID::try_from(normalize_guid(interface_guid).as_str()).map_err(|_| {
+        DnsCommandError::WindowsApi(format!("Invalid interface GUID: {}", interface_guid))
+    })
+}
+
+/// Returns the statically configured servers of one address family, which
+/// are empty when the interface uses the DHCP-provided ones.
+#[cfg(target_os = "windows")]
+fn get_name_servers(interface_guid: &str, ipv6: bool) -> Result<String> {
+    use windows::Win32::Foundation::NO_ERROR;
+    use windows::Win32::NetworkManagement::IpHelper::{
+        DNS_INTERFACE_SETTINGS, DNS_INTERFACE_SETTINGS_VERSION1, DNS_SETTING_IPV6,
+        FreeInterfaceDnsSettings, GetInterfaceDnsSettings,
+    };
+
+    let guid = parse_interface_guid(interface_guid)?;
+    let mut settings = DNS_INTERFACE_SETTINGS {
+        Version: DNS_INTERFACE_SETTINGS_VERSION1,
+        Flags: if ipv6 { DNS_SETTING_IPV6 as u64 } else { 0 },
+        ..Default::default()
+    };
+
+    let result = unsafe { GetInterfaceDnsSettings(guid, &mut settings) };
+    if result != NO_ERROR {
+        return Err(DnsCommandError::WindowsApi(format!(
+            "GetInterfaceDnsSettings failed with code {}",
+            result.0
+        )));
+    }
+
+    let name_servers = if settings.NameServer.is_null() {
+        String::new()
+    } else {
+        unsafe { settings.NameServer.to_string() }.unwrap_or_default()
+    };
+    unsafe { FreeInterfaceDnsSettings(&mut settings) };
+    Ok(name_servers)
+}
+
+#[cfg(not(target_os = "windows"))]
+fn get_name_servers(_interface_guid: &str, _ipv6: bool) -> Result<String> {
+    Err(DnsCommandError::WindowsApi(
+        "Not supported on this platform".to_string(),
+    ))
+}
+
+#[cfg(target_os = "windows")]
+fn set_name_servers(interface_guid: &str, name_servers: &str, ipv6: bool) -> Result<()> {
+    use windows::Win32::Foundation::NO_ERROR;
+    use windows::Win32::NetworkManagement::IpHelper::{
+        DNS_INTERFACE_SETTINGS, DNS_INTERFACE_SETTINGS_VERSION1, DNS_SETTING_IPV6,
+        DNS_SETTING_NAMESERVER, SetInterfaceDnsSettings,
+    };
+    use windows::core::PWSTR;
+
+    let guid = parse_interface_guid(interface_guid)?;
+
+    let mut wide_servers: Vec<u16> = name_servers
+        .encode_utf16()
+        .chain(std::iter::once(0))
+        .collect();
+
+    let mut flags = DNS_SETTING_NAMESERVER as u64;
+    if ipv6 {
+        flags |= DNS_SETTING_IPV6 as u64;
+    }
+
+    let settings = DNS_INTERFACE_SETTINGS {
+        Version: DNS_INTERFACE_SETTINGS_VERSION1,
+        Flags: flags,
+        NameServer: PWSTR(wide_servers.as_mut_ptr()),
+        ..Default::default()
+    };
+
+    let result = unsafe { SetInterfaceDnsSettings(guid, &settings) };
+
+    if result != NO_ERROR {
+        return Err(DnsCommandError::WindowsApi(format!(
+            "SetInterfaceDnsSettings failed with code {}",
+            result.0
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(not(target_os = "windows"))]
+fn set_name_servers(_interface_guid: &str, _name_servers: &str, _ipv6: bool) -> Result<()> {
+    Err(DnsCommandError::WindowsApi(
+        "Not supported on this platform".to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dns::types::test_interface;
+
+    #[test]
+    fn test_split_addresses_by_family() {
+        let addresses = vec![
+            "8.8.8.8".to_string(),
+            "2001:4860:4860::8888".to_string(),
+            "8.8.4.4".to_string(),
+            "fe80::1%12".to_string(),
+        ];
+        let (ipv4, ipv6) = split_addresses_by_family(&addresses).unwrap();
+        assert_eq!(ipv4, vec!["8.8.8.8", "8.8.4.4"]);
+        assert_eq!(ipv6, vec!["2001:4860:4860::8888", "fe80::1%12"]);
+    }
+
+    #[test]
+    fn test_split_addresses_by_family_empty() {
+        let (ipv4, ipv6) = split_addresses_by_family(&[]).unwrap();
+        assert!(ipv4.is_empty());
+        assert!(ipv6.is_empty());
+    }
+
+    #[test]
+    fn test_preview_settings_lists_both_families() {
+        let interface = test_interface("Ethernet", 3);
+        let mut settings = DnsSettings::new();
+        settings.ipv4.enabled = true;
+        settings.ipv4.servers[0].address = "9.9.9.9".to_string();
+
+        let steps = NativeBackend.preview_settings(&interface, &settings);
+        assert_eq!(steps.len(), 2);
+        assert_eq!(
+            steps[0].command,
+            "SetInterfaceDnsSettings({GUID-3}, IPv4 NameServer = \"9.9.9.9\")\n\
+             SetInterfaceDnsSettings({GUID-3}, IPv6 NameServer = \"\")"
+        );
+        assert_eq!(steps[1].title, "Turn off DoH on the interface");
+    }
+
+    #[test]
+    fn test_uses_doh_requires_registered_template() {
+        let interface = test_interface("Ethernet", 3);
+        let interface_key =
+            r"SYSTEM\CurrentControlSet\Services\Dnscache\InterfaceSpecificParameters\{GUID-3}";
+        let per_server = |subkey: &str, name: &str| {
+            name == "Template" && subkey.ends_with(r"DohWellKnownServers\1.1.1.1")
+                || name == "DohFlags"
+                    && subkey == format!(r"{}\DohInterfaceSettings\Doh\1.1.1.1", interface_key)
+        };
+        assert!(uses_doh(&interface, "1.1.1.1", per_server));
+        assert!(!uses_doh(&interface, "8.8.8.8", per_server));
+
+        let interface_wide = |subkey: &str, name: &str| {
+            name == "Template" || (name == "DohFlags" && subkey == interface_key)
+        };
+        assert!(uses_doh(&interface, "2606:4700:4700::1111", interface_wide));
+
+        let no_template = |_: &str, name: &str| name == "DohFlags";
+        assert!(!uses_doh(&interface, "1.1.1.1", no_template));
+    }
+
+    #[test]
+    fn test_split_addresses_by_family_invalid() {
+        let addresses = vec!["not-an-ip".to_string()];
+        let result = split_addresses_by_family(&addresses);
+        assert!(matches!(result, Err(DnsCommandError::WindowsApi(_))));
+    }
+}
@@ -0,0 +1,401 @@
+use crate::dns::backend::native::read_current_dns;
+use crate::dns::backend::{DnsBackend, PreviewStep};
+use crate::dns::commands::{DnsCommandError, Result, collect_addresses, doh_targets, run_netsh};
+use crate::dns::types::{
+    ActiveNrptRule, AddressFamily, CurrentDnsState, DnsOrigin, DnsServerEntry, DnsSettings,
+    NetworkInterface, NrptRule,
+};
+use std::net::IpAddr;
+
+/// Backend built on `netsh interface ipv4/ipv6`, used when PowerShell is
+/// blocked. It only supports plain DNS; DoH requires PowerShell.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NetshBackend;
+
+impl DnsBackend for NetshBackend {
+    async fn get_current_dns(&self, interface: &NetworkInterface) -> Result<CurrentDnsState> {
+        if let Ok(state) = read_current_dns(interface) {
+            return Ok(state);
+        }
+
+        let name = format!("name={}", interface.interface_index);
+        let ipv4_output = run_netsh(&["interface", "ipv4", "show", "dnsservers", &name]).await?;
+        let ipv6_output = run_netsh(&["interface", "ipv6", "show", "dnsservers", &name]).await?;
+
+        Ok(CurrentDnsState {
+            ipv4: parse_dns_servers(&ipv4_output, AddressFamily::IPv4),
+            ipv6: parse_dns_servers(&ipv6_output, AddressFamily::IPv6),
+            doh_servers: Vec::new(),
+            ipv4_origin: parse_dns_origin(&ipv4_output),
+            ipv6_origin: parse_dns_origin(&ipv6_output),
+        })
+    }
+
+    async fn set_manual(&self, interface: &NetworkInterface, addresses: &[String]) -> Result<()> {
+        let (ipv4, ipv6) = partition_by_family(addresses);
+
+        set_family_servers(interface, AddressFamily::IPv4, &ipv4).await?;
+        set_family_servers(interface, AddressFamily::IPv6, &ipv6).await?;
+        Ok(())
+    }
+
+    async fn set_automatic(&self, interface: &NetworkInterface) -> Result<()> {
+        set_family_servers(interface, AddressFamily::IPv4, &[]).await?;
+        set_family_servers(interface, AddressFamily::IPv6, &[]).await?;
+        Ok(())
+    }
+
+    async fn set_family(
+        &self,
+        interface: &NetworkInterface,
+        family: AddressFamily,
+        addresses: &[String],
+    ) -> Result<()> {
+        set_family_servers(interface, family, addresses).await
+    }
+
+    async fn configure_doh(&self, _server: &DnsServerEntry) -> Result<()> {
+        Err(DnsCommandError::Unsupported(
+            "DoH configuration requires PowerShell".to_string(),
+        ))
+    }
+
+    async fn enable_doh_registry(
+        &self,
+        _interface: &NetworkInterface,
+        _servers: &[&DnsServerEntry],
+    ) -> Result<()> {
+        Err(DnsCommandError::Unsupported(
+            "DoH configuration requires PowerShell".to_string(),
+        ))
+    }
+
+    async fn disable_doh_registry(&self, _interface: &NetworkInterface) -> Result<()> {
+        // DoH is never enabled in this mode, so there is nothing to undo.
+        Ok(())
+    }
+
+    async fn get_doh_servers(&self) -> Result<Vec<String>> {
+        Err(DnsCommandError::Unsupported(
+            "DoH configuration requires PowerShell".to_string(),
+        ))
+    }
+
+    async fn get_doh_templates(&self) -> Result<Vec<DnsServerEntry>> {
+        Err(DnsCommandError::Unsupported(
+            "DoH configuration requires PowerShell".to_string(),
+        ))
+    }
+
+    async fn remove_doh_server(&self, _address: &str) -> Result<()> {
+        Err(DnsCommandError::Unsupported(
+            "DoH configuration requires PowerShell".to_string(),
+        ))
+    }
+
+    async fn set_connection_suffix(
+        &self,
+        _interface: &NetworkInterface,
+        _suffix: &str,
+    ) -> Result<()> {
+        Err(DnsCommandError::Unsupported(
+            "DNS suffix configuration requires PowerShell".to_string(),
+        ))
+    }
+
+    async fn set_suffix_search_list(&self, _suffixes: &[String]) -> Result<()> {
+        Err(DnsCommandError::Unsupported(
+            "DNS suffix configuration requires PowerShell".to_string(),
+        ))
+    }
+
+    async fn get_nrpt_rules(&self) -> Result<Vec<ActiveNrptRule>> {
+        Err(DnsCommandError::Unsupported(
+            "NRPT configuration requires PowerShell".to_string(),
+        ))
+    }
+
+    async fn replace_nrpt_rules(&self, _rules: &[NrptRule]) -> Result<()> {
+        Err(DnsCommandError::Unsupported(
+            "NRPT configuration requires PowerShell".to_string(),
+        ))
+    }
+
+    async fn remove_nrpt_rule(&self, _name: &str) -> Result<()> {
+        Err(DnsCommandError::Unsupported(
+            "NRPT configuration requires PowerShell".to_string(),
+        ))
+    }
+
+    fn preview_settings(
+        &self,
+        interface: &NetworkInterface,
+        settings: &DnsSettings,
+    ) -> Vec<PreviewStep> {
+        let (ipv4, ipv6) = partition_by_family(&collect_addresses(settings));
+        let commands = [
+            (AddressFamily::IPv4, &ipv4, &settings.ipv4),
+            (AddressFamily::IPv6, &ipv6, &settings.ipv6),
+        ]
+        .into_iter()
+        .filter(|(_, _, entry)| !entry.is_left_alone())
+        .flat_map(|(family, servers, _)| family_commands(interface, family, servers))
+        .map(|args| format!("netsh {}", args.join(" ")))
+        .collect::<Vec<_>>();
+
+        let mut steps = Vec::new();
+        if !commands.is_empty() {
+            steps.push(PreviewStep::new(
+                "Set DNS servers (netsh)",
+                commands.join("\n"),
+            ));
+        }
+        if !doh_targets(settings).is_empty() {
+            steps.push(PreviewStep::new(
+                "Configure DoH",
+                "Skipped: DoH configuration requires PowerShell",
+            ));
+        }
+        if settings.connection_suffix.is_some() || settings.suffixes.enabled {
+            steps.push(PreviewStep::new(
+                "Set DNS suffixes",
+                "Skipped: DNS suffix configuration requires PowerShell",
+            ));
+        }
+        if settings.nrpt.enabled {
+            steps.push(PreviewStep::new(
+                "Replace NRPT rules",
+                "Skipped: NRPT configuration requires PowerShell",
+            ));
+        }
+        steps
+    }
+}
+
+fn partition_by_family(addresses: &[String]) -> (Vec<String>, Vec<String>) {
+    addresses.iter().cloned().partition(|a| !a.contains(':'))
+}
+
+/// Builds the netsh argument lists that replace the servers of one family.
+/// An empty list switches the family back to DHCP.
+fn family_commands(
+    interface: &NetworkInterface,
+    family: AddressFamily,
+    addresses: &[String],
+) -> Vec<Vec<String>> {
+    let context = match family {
+        AddressFamily::IPv4 => "ipv4",
+        AddressFamily::IPv6 => "ipv6",
+    };
+    let name = format!("name={}", interface.interface_index);
+    let base = |verb: &str| {
+        vec![
+            "interface".to_string(),
+            context.to_string(),
+            verb.to_string(),
+            "dnsservers".to_string(),
+            name.clone(),
+        ]
+    };
+
+    if addresses.is_empty() {
+        let mut command = base("set");
+        command.push("source=dhcp".to_string());
+        return vec![command];
+    }
+
+    addresses
+        .iter()
+        .enumerate()
+        .map(|(i, address)| {
+            if i == 0 {
+                let mut command = base("set");
+                command.extend([
+                    "source=static".to_string(),
+                    format!("address={}", address),
+                    "register=primary".to_string(),
+                    "validate=no".to_string(),
+                ]);
+                command
+            } else {
+                let mut command = base("add");
+                command.extend([
+                    format!("address={}", address),
+                    format!("index={}", i + 1),
+                    "validate=no".to_string(),
+                ]);
+                command
+            }
+        })
+        .collect()
+}
+
+async fn set_family_servers(
+    interface: &NetworkInterface,
+    family: AddressFamily,
+    addresses: &[String],
+) -> Result<()> {
+    for command in family_commands(interface, family, addresses) {
+        let args: Vec<&str> = command.iter().map(|a| a.as_str()).collect();
+        run_netsh(&args).await?;
+    }
+    Ok(())
+}
+
+/// Extracts server addresses of the given family from
+/// `netsh interface ipvX show dnsservers` output. The labels are localized,
+/// so every whitespace-separated token that parses as an address is taken.
+fn parse_dns_servers(output: &str, family: AddressFamily) -> Vec<String> {
+    output
+        .split_whitespace()
+        .filter_map(|token| {
+            let address = token.split('%').next().unwrap_or(token);
+            match (address.parse::<IpAddr>().ok()?, family) {
+                (IpAddr::V4(addr), AddressFamily::IPv4) => Some(addr.to_string()),
+                (IpAddr::V6(addr), AddressFamily::IPv6) => Some(addr.to_string()),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Tells DHCP-provided from static servers in `show dnsservers` output.
+/// The labels are localized, but the DHCP one contains "DHCP" in every
+/// language, so servers without it are static.
+fn parse_dns_origin(output: &str) -> DnsOrigin {
+    if output.contains("DHCP") {
+        DnsOrigin::Dhcp
+    } else if output
+        .split_whitespace()
+        .any(|t| t.parse::<IpAddr>().is_ok())
+    {
+        DnsOrigin::Static
+    } else {
+        DnsOrigin::Unknown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dns::types::test_interface;
+
+    #[test]
+    fn test_parse_dns_servers_static_ipv4() {
+        let output = r#"
+Configuration for interface "Ethernet"
+    Statically Configured DNS Servers:    8.8.8.8
+                                          8.8.4.4
+    Register with which suffix:           Primary only
+"#;
+        assert_eq!(
+            parse_dns_servers(output, AddressFamily::IPv4),
+            vec!["8.8.8.8", "8.8.4.4"]
+        );
+        assert_eq!(parse_dns_origin(output), DnsOrigin::Static);
+    }
+
+    #[test]
+    fn test_parse_dns_servers_dhcp_none() {
+        let output = r#"
+Configuration for interface "Wi-Fi 2"
+    DNS servers configured through DHCP:  None
+    Register with which suffix:           Primary only
+"#;
+        assert!(parse_dns_servers(output, AddressFamily::IPv4).is_empty());
+        assert_eq!(parse_dns_origin(output), DnsOrigin::Dhcp);
+    }
+
+    #[test]
+    fn test_parse_dns_servers_ipv6_with_zone() {
+        let output = r#"
+Configuration for interface "Ethernet"
+    DNS servers configured through DHCP:  fec0:0:0:ffff::1%1
+                                          2001:4860:4860::8888
+"#;
+        assert_eq!(
+            parse_dns_servers(output, AddressFamily::IPv6),
+            vec!["fec0:0:0:ffff::1", "2001:4860:4860::8888"]
+        );
+    }
+
+    #[test]
+    fn test_family_commands_dhcp() {
+        let commands = family_commands(&test_interface("Ethernet", 12), AddressFamily::IPv4, &[]);
+        assert_eq!(
+            commands,
+            vec![vec![
+                "interface",
+                "ipv4",
+                "set",
+                "dnsservers",
+                "name=12",
+                "source=dhcp"
+            ]]
+        );
+    }
+
+    #[test]
+    fn test_family_commands_static() {
+        let addresses = vec!["1.1.1.1".to_string(), "1.0.0.1".to_string()];
+        let commands = family_commands(
+            &test_interface("Ethernet", 12),
+            AddressFamily::IPv4,
+            &addresses,
+        );
+        assert_eq!(commands.len(), 2);
+        assert_eq!(
+            commands[0],
+            vec![
+                "interface",
+                "ipv4",
+                "set",
+                "dnsservers",
+                "name=12",
+                "source=static",
+                "address=1.1.1.1",
+                "register=primary",
+                "validate=no"
+            ]
+        );
+        assert_eq!(
+            commands[1],
+            vec![
+                "interface",
+                "ipv4",
+                "add",
+                "dnsservers",
+                "name=12",
+                "address=1.0.0.1",
+                "index=2",
+                "validate=no"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_preview_settings() {
+        let mut settings = DnsSettings::new();
+        settings.ipv4.enabled = true;
+        settings.ipv4.servers[0].address = "1.1.1.1".to_string();
+
+        let steps = NetshBackend.preview_settings(&test_interface("Ethernet", 12), &settings);
+        assert_eq!(steps.len(), 1);
+        assert_eq!(
+            steps[0].command,
+            "netsh interface ipv4 set dnsservers name=12 source=static address=1.1.1.1 register=primary validate=no\n\
+             netsh interface ipv6 set dnsservers name=12 source=dhcp"
+        );
+    }
+
+    #[test]
+    fn test_family_commands_ipv6_context() {
+        let addresses = vec!["2606:4700:4700::1111".to_string()];
+        let commands = family_commands(
+            &test_interface("Ethernet", 12),
+            AddressFamily::IPv6,
+            &addresses,
+        );
+        assert_eq!(commands[0][1], "ipv6");
+    }
+}
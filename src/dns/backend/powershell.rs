@@ -0,0 +1,1294 @@
+use crate::dns::backend::{DnsBackend, PreviewStep, registry_error_message};
+use crate::dns::commands::{
+    DnsCommandError, DohOutcome, Result, collect_addresses, doh_targets, escape_powershell_string,
+    normalize_error_message, normalize_guid, run_powershell,
+};
+use crate::dns::nrpt;
+use crate::dns::types::{
+    ActiveNrptRule, AddressFamily, CurrentDnsState, DnsOrigin, DnsServerEntry, DnsSettings,
+    DohMode, NetworkInterface, NrptRule,
+};
+use serde::Deserialize;
+use std::future::poll_fn;
+use std::task::Poll;
+
+const AF_INET: u64 = 2;
+const AF_INET6: u64 = 23;
+
+/// Backend that performs every operation through PowerShell cmdlets.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PowerShellBackend;
+
+impl PowerShellBackend {
+    /// Runs the DoH steps (and the registry update) for `settings`, without
+    /// touching the server addresses. The servers are configured side by
+    /// side, each in its own PowerShell, and the registry is written once
+    /// they are all done. Without any DoH server, this turns DoH off on the
+    /// interface instead.
+    pub async fn apply_doh_batch(
+        &self,
+        interface: &NetworkInterface,
+        settings: &DnsSettings,
+    ) -> Result<Option<String>> {
+        let targets = doh_targets(settings);
+        if targets.is_empty() {
+            let script = build_apply_script(None, interface, settings);
+            return run_apply_script(&script).await;
+        }
+
+        let mut outcome = DohOutcome::default();
+        let configured =
+            configure_side_by_side(&targets, |server| self.configure_doh(server), &mut outcome)
+                .await;
+        if !configured.is_empty() {
+            let script =
+                enable_doh_registry_script(interface, &configured, &doh_family_keys(settings));
+            if let Err(e) = run_registry_script(&script).await {
+                outcome.registry_error = Some(registry_error_message(e));
+            }
+        }
+        outcome.into_result()
+    }
+}
+
+impl DnsBackend for PowerShellBackend {
+    async fn get_current_dns(&self, interface: &NetworkInterface) -> Result<CurrentDnsState> {
+        let output = run_powershell(&current_dns_script(interface)).await?;
+        parse_current_dns(&output)
+    }
+
+    async fn set_manual(&self, interface: &NetworkInterface, addresses: &[String]) -> Result<()> {
+        if addresses.is_empty() {
+            return self.set_automatic(interface).await;
+        }
+
+        run_powershell(&set_manual_script(interface, addresses)).await?;
+        Ok(())
+    }
+
+    async fn set_automatic(&self, interface: &NetworkInterface) -> Result<()> {
+        run_powershell(&set_automatic_script(interface)).await?;
+        Ok(())
+    }
+
+    async fn set_family(
+        &self,
+        interface: &NetworkInterface,
+        family: AddressFamily,
+        addresses: &[String],
+    ) -> Result<()> {
+        run_powershell(&set_family_script(interface, family, addresses)).await?;
+        Ok(())
+    }
+
+    async fn configure_doh(&self, server: &DnsServerEntry) -> Result<()> {
+        run_powershell(&configure_doh_script(server)).await?;
+        Ok(())
+    }
+
+    async fn enable_doh_registry(
+        &self,
+        interface: &NetworkInterface,
+        servers: &[&DnsServerEntry],
+    ) -> Result<()> {
+        run_registry_script(&enable_doh_registry_script(
+            interface,
+            servers,
+            &DOH_FAMILY_KEYS,
+        ))
+        .await
+    }
+
+    async fn disable_doh_registry(&self, interface: &NetworkInterface) -> Result<()> {
+        run_registry_script(&disable_doh_registry_script(interface, &DOH_FAMILY_KEYS)).await
+    }
+
+    async fn get_doh_servers(&self) -> Result<Vec<String>> {
+        let script = "ConvertTo-Json -Compress -InputObject @(Get-DnsClientDohServerAddress | ForEach-Object { $_.ServerAddress })";
+        let output = run_powershell(script).await?;
+        parse_doh_servers(&output)
+    }
+
+    async fn get_doh_templates(&self) -> Result<Vec<DnsServerEntry>> {
+        let script = "ConvertTo-Json -Compress -InputObject @(Get-DnsClientDohServerAddress | Select-Object ServerAddress, DohTemplate, AllowFallbackToUdp, AutoUpgrade)";
+        let output = run_powershell(script).await?;
+        parse_doh_templates(&output)
+    }
+
+    async fn remove_doh_server(&self, address: &str) -> Result<()> {
+        run_powershell(&remove_doh_server_script(address)).await?;
+        Ok(())
+    }
+
+    async fn set_connection_suffix(
+        &self,
+        interface: &NetworkInterface,
+        suffix: &str,
+    ) -> Result<()> {
+        run_powershell(&set_connection_suffix_script(interface, suffix)).await?;
+        Ok(())
+    }
+
+    async fn set_suffix_search_list(&self, suffixes: &[String]) -> Result<()> {
+        run_powershell(&set_suffix_search_list_script(suffixes)).await?;
+        Ok(())
+    }
+
+    async fn get_nrpt_rules(&self) -> Result<Vec<ActiveNrptRule>> {
+        let output = run_powershell(nrpt::LIST_RULES_SCRIPT).await?;
+        nrpt::parse_rules(&output)
+    }
+
+    async fn replace_nrpt_rules(&self, rules: &[NrptRule]) -> Result<()> {
+        run_powershell(&nrpt::replace_rules_script(rules)).await?;
+        Ok(())
+    }
+
+    async fn remove_nrpt_rule(&self, name: &str) -> Result<()> {
+        run_powershell(&nrpt::remove_rule_script(name)).await?;
+        Ok(())
+    }
+
+    fn preview_settings(
+        &self,
+        interface: &NetworkInterface,
+        settings: &DnsSettings,
+    ) -> Vec<PreviewStep> {
+        let mut steps: Vec<PreviewStep> = set_servers_script(interface, settings)
+            .map(|(title, script)| PreviewStep::new(title, script))
+            .into_iter()
+            .collect();
+        steps.extend(doh_preview_steps(interface, settings));
+        steps.extend(suffix_preview_steps(interface, settings));
+        steps.extend(nrpt_preview_steps(settings));
+        steps
+    }
+
+    async fn apply_settings(
+        &self,
+        interface: &NetworkInterface,
+        settings: &DnsSettings,
+    ) -> Result<Option<String>> {
+        let set_script = set_servers_script(interface, settings).map(|(_, script)| script);
+        let script = build_apply_script(set_script.as_deref(), interface, settings);
+        run_apply_script(&script).await
+    }
+}
+
+/// Configures the servers of `targets` with `configure` side by side and
+/// records each result in `outcome`. Returns the servers that succeeded, in
+/// order.
+async fn configure_side_by_side<'a, F, Fut>(
+    targets: &[(String, &'a DnsServerEntry)],
+    configure: F,
+    outcome: &mut DohOutcome,
+) -> Vec<&'a DnsServerEntry>
+where
+    F: Fn(&'a DnsServerEntry) -> Fut,
+    Fut: Future<Output = Result<()>>,
+{
+    let mut pending: Vec<_> = targets
+        .iter()
+        .map(|(_, server)| (Box::pin(configure(server)), None))
+        .collect();
+    poll_fn(|cx| {
+        let mut done = true;
+        for (future, result) in pending.iter_mut().filter(|(_, result)| result.is_none()) {
+            match future.as_mut().poll(cx) {
+                Poll::Ready(value) => *result = Some(value),
+                Poll::Pending => done = false,
+            }
+        }
+        if done { Poll::Ready(()) } else { Poll::Pending }
+    })
+    .await;
+
+    let mut configured = Vec::new();
+    for ((label, server), (_, result)) in targets.iter().zip(pending) {
+        let error = result.and_then(Result::err);
+        if error.is_none() {
+            configured.push(*server);
+        }
+        outcome.record(label, error.map(|e| e.to_string()));
+    }
+    configured
+}
+
+async fn run_registry_script(script: &str) -> Result<()> {
+    run_powershell(script).await.map_err(|e| {
+        DnsCommandError::RegistryFailed(match e {
+            DnsCommandError::CommandFailed(msg) => msg,
+            other => other.to_string(),
+        })
+    })?;
+    Ok(())
+}
+
+/// Preview of the suffix steps, for the suffixes the settings manage.
+pub(crate) fn suffix_preview_steps(
+    interface: &NetworkInterface,
+    settings: &DnsSettings,
+) -> Vec<PreviewStep> {
+    let mut steps = Vec::new();
+    if let Some(suffix) = &settings.connection_suffix {
+        steps.push(PreviewStep::new(
+            "Set connection-specific DNS suffix",
+            set_connection_suffix_script(interface, suffix),
+        ));
+    }
+    if settings.suffixes.enabled {
+        steps.push(PreviewStep::new(
+            "Set DNS suffix search list",
+            set_suffix_search_list_script(&settings.suffixes.search_suffixes()),
+        ));
+    }
+    steps
+}
+
+/// Preview of the NRPT step, if the settings manage NRPT rules.
+pub(crate) fn nrpt_preview_steps(settings: &DnsSettings) -> Option<PreviewStep> {
+    settings.nrpt.enabled.then(|| {
+        PreviewStep::new(
+            "Replace NRPT rules",
+            nrpt::replace_rules_script(&settings.nrpt.rules),
+        )
+    })
+}
+
+/// Preview of the DoH and registry steps, shared by the backends that
+/// delegate DoH configuration to PowerShell.
+pub(crate) fn doh_preview_steps(
+    interface: &NetworkInterface,
+    settings: &DnsSettings,
+) -> Vec<PreviewStep> {
+    let targets = doh_targets(settings);
+    let family_keys = doh_family_keys(settings);
+    if family_keys.is_empty() {
+        return Vec::new();
+    }
+    if targets.is_empty() {
+        return vec![PreviewStep::new(
+            "Turn off DoH on the interface",
+            dedent(&disable_doh_registry_script(interface, &family_keys)),
+        )];
+    }
+
+    let servers: Vec<&DnsServerEntry> = targets.iter().map(|(_, server)| *server).collect();
+    let mut steps: Vec<PreviewStep> = targets
+        .iter()
+        .map(|(label, server)| {
+            PreviewStep::new(
+                format!("Configure DoH ({})", label),
+                dedent(&configure_doh_script(server)),
+            )
+        })
+        .collect();
+    steps.push(PreviewStep::new(
+        "Enable DoH on the interface",
+        enable_doh_registry_script(interface, &servers, &family_keys),
+    ));
+    steps
+}
+
+/// Removes surrounding blank lines and the common indentation of a script.
+fn dedent(script: &str) -> String {
+    let lines: Vec<&str> = script
+        .trim_end()
+        .trim_start_matches('\n')
+        .lines()
+        .map(|line| line.trim_end())
+        .collect();
+    let indent = lines
+        .iter()
+        .filter(|line| !line.is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+
+    lines
+        .iter()
+        .map(|line| line.get(indent..).unwrap_or(""))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn address_list(addresses: &[String]) -> String {
+    addresses
+        .iter()
+        .map(|a| format!("'{}'", escape_powershell_string(a)))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn set_manual_script(interface: &NetworkInterface, addresses: &[String]) -> String {
+    format!(
+        "Set-DnsClientServerAddress -InterfaceIndex {} -ServerAddresses @({})",
+        interface.interface_index,
+        address_list(addresses)
+    )
+}
+
+/// Sets the servers of one family through its own server address
+/// instance, which leaves the other family as it is. An empty list resets
+/// the family to DHCP.
+fn set_family_script(
+    interface: &NetworkInterface,
+    family: AddressFamily,
+    addresses: &[String],
+) -> String {
+    let servers = if addresses.is_empty() {
+        "-ResetServerAddresses".to_string()
+    } else {
+        format!("-ServerAddresses @({})", address_list(addresses))
+    };
+    format!(
+        "Get-DnsClientServerAddress -InterfaceIndex {} -AddressFamily {} | Set-DnsClientServerAddress {}",
+        interface.interface_index,
+        family.as_str(),
+        servers
+    )
+}
+
+/// Script that sets the servers of `settings`, with its title in the
+/// preview. A family left alone is not set, so that it keeps its servers;
+/// `None` when both are left alone.
+fn set_servers_script(
+    interface: &NetworkInterface,
+    settings: &DnsSettings,
+) -> Option<(&'static str, String)> {
+    if !settings.leaves_family_unchanged() {
+        let addresses = collect_addresses(settings);
+        return Some(if addresses.is_empty() {
+            ("Reset DNS servers to DHCP", set_automatic_script(interface))
+        } else {
+            ("Set DNS servers", set_manual_script(interface, &addresses))
+        });
+    }
+
+    let scripts: Vec<String> = [
+        (AddressFamily::IPv4, &settings.ipv4),
+        (AddressFamily::IPv6, &settings.ipv6),
+    ]
+    .into_iter()
+    .filter(|(_, entry)| !entry.is_left_alone())
+    .map(|(family, entry)| {
+        let addresses = if entry.enabled {
+            entry.get_addresses()
+        } else {
+            Vec::new()
+        };
+        set_family_script(interface, family, &addresses)
+    })
+    .collect();
+    (!scripts.is_empty()).then(|| ("Set DNS servers", scripts.join("; ")))
+}
+
+fn set_connection_suffix_script(interface: &NetworkInterface, suffix: &str) -> String {
+    format!(
+        "Set-DnsClient -InterfaceIndex {} -ConnectionSpecificSuffix '{}'",
+        interface.interface_index,
+        escape_powershell_string(suffix.trim())
+    )
+}
+
+fn set_suffix_search_list_script(suffixes: &[String]) -> String {
+    let search_list = suffixes
+        .iter()
+        .map(|s| format!("'{}'", escape_powershell_string(s)))
+        .collect::<Vec<_>>()
+        .join(",");
+
+    format!(
+        "Set-DnsClientGlobalSetting -SuffixSearchList @({})",
+        search_list
+    )
+}
+
+fn set_automatic_script(interface: &NetworkInterface) -> String {
+    format!(
+        "Set-DnsClientServerAddress -InterfaceIndex {} -ResetServerAddresses",
+        interface.interface_index
+    )
+}
+
+fn configure_doh_script(server: &DnsServerEntry) -> String {
+    let fallback_str = if server.allow_fallback {
+        "$true"
+    } else {
+        "$false"
+    };
+    let auto_upgrade_str = if server.auto_upgrade {
+        "$true"
+    } else {
+        "$false"
+    };
+    let escaped_address = escape_powershell_string(&server.address);
+    let escaped_template = escape_powershell_string(&server.doh_template);
+
+    format!(
+        r#"
+        $addr = '{}'
+        $existing = Get-DnsClientDohServerAddress -ServerAddress $addr -ErrorAction SilentlyContinue
+        if ($existing) {{
+            Set-DnsClientDohServerAddress -ServerAddress $addr -DohTemplate '{}' -AllowFallbackToUdp {} -AutoUpgrade {}
+        }} else {{
+            Add-DnsClientDohServerAddress -ServerAddress $addr -DohTemplate '{}' -AllowFallbackToUdp {} -AutoUpgrade {}
+        }}
+        "#,
+        escaped_address,
+        escaped_template,
+        fallback_str,
+        auto_upgrade_str,
+        escaped_template,
+        fallback_str,
+        auto_upgrade_str
+    )
+}
+
+fn remove_doh_server_script(address: &str) -> String {
+    format!(
+        r#"
+        $addr = '{}'
+        if (Get-DnsClientDohServerAddress -ServerAddress $addr -ErrorAction SilentlyContinue) {{
+            Remove-DnsClientDohServerAddress -ServerAddress $addr
+        }}
+        "#,
+        escape_powershell_string(address)
+    )
+}
+
+/// `DohFlags` bits of a per-server key: the template is given explicitly,
+/// and falling back to plaintext DNS is allowed.
+const DOH_FLAG_MANUAL_TEMPLATE: u64 = 0x2;
+const DOH_FLAG_ALLOW_FALLBACK: u64 = 0x10;
+
+/// First Windows build (11 22H2) that reads the per-server DoH keys.
+const PER_SERVER_DOH_MIN_BUILD: u32 = 22621;
+
+fn interface_registry_path(interface: &NetworkInterface) -> String {
+    let normalized_guid = normalize_guid(&interface.interface_guid);
+    format!(
+        r"HKLM:\SYSTEM\CurrentControlSet\Services\Dnscache\InterfaceSpecificParameters\{{{}}}",
+        escape_powershell_string(&normalized_guid)
+    )
+}
+
+/// Registry key holding the static configuration of the interface, where a
+/// non-empty `NameServer` value means the DNS servers are set manually.
+fn tcpip_registry_path(interface: &NetworkInterface, service: &str) -> String {
+    let normalized_guid = normalize_guid(&interface.interface_guid);
+    format!(
+        r"HKLM:\SYSTEM\CurrentControlSet\Services\{}\Parameters\Interfaces\{{{}}}",
+        service,
+        escape_powershell_string(&normalized_guid)
+    )
+}
+
+/// PowerShell hashtable describing the registry settings of one server.
+fn doh_registry_entry(server: &DnsServerEntry) -> String {
+    let family_key = if server.address.contains(':') {
+        "Doh6"
+    } else {
+        "Doh"
+    };
+    let mut flags = DOH_FLAG_MANUAL_TEMPLATE;
+    if server.allow_fallback {
+        flags |= DOH_FLAG_ALLOW_FALLBACK;
+    }
+
+    format!(
+        "@{{ Address = '{}'; Key = '{}'; Template = '{}'; Flags = {} }}",
+        escape_powershell_string(&server.address),
+        family_key,
+        escape_powershell_string(&server.doh_template),
+        flags
+    )
+}
+
+/// `DohInterfaceSettings` subkeys holding the per-server DoH settings of
+/// IPv4 and IPv6.
+const DOH_FAMILY_KEYS: [&str; 2] = ["Doh", "Doh6"];
+
+/// The DoH subkeys of the families `settings` sets. Those of a family left
+/// alone are not touched.
+fn doh_family_keys(settings: &DnsSettings) -> Vec<&'static str> {
+    DOH_FAMILY_KEYS
+        .into_iter()
+        .zip([&settings.ipv4, &settings.ipv6])
+        .filter(|(_, entry)| !entry.is_left_alone())
+        .map(|(key, _)| key)
+        .collect()
+}
+
+/// The keys as a PowerShell array.
+fn key_list(family_keys: &[&str]) -> String {
+    let keys = family_keys
+        .iter()
+        .map(|key| format!("'{}'", key))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("@({})", keys)
+}
+
+fn enable_doh_registry_script(
+    interface: &NetworkInterface,
+    servers: &[&DnsServerEntry],
+    family_keys: &[&str],
+) -> String {
+    let entries = servers
+        .iter()
+        .map(|server| doh_registry_entry(server))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(
+        "$dohServers = @({})\n{}",
+        entries,
+        dedent(&write_doh_registry_script(interface, family_keys))
+    )
+}
+
+/// Writes the servers in `$dohServers` to the registry. Windows 11 22H2+
+/// keeps DoH settings per server under a key for each address family, so
+/// only the families that use DoH are affected, and servers no longer used
+/// are only removed from `family_keys`; older builds only know the
+/// interface-wide flag.
+fn write_doh_registry_script(interface: &NetworkInterface, family_keys: &[&str]) -> String {
+    format!(
+        r#"
+        $regPath = '{}'
+        $settingsPath = "$regPath\DohInterfaceSettings"
+        $build = [int](Get-ItemProperty -Path 'HKLM:\SOFTWARE\Microsoft\Windows NT\CurrentVersion').CurrentBuildNumber
+        if (-not (Test-Path $regPath)) {{
+            New-Item -Path $regPath -Force | Out-Null
+        }}
+        if ($build -lt {}) {{
+            New-ItemProperty -Path $regPath -Name 'DohFlags' -Value 1 -PropertyType DWord -Force | Out-Null
+        }} else {{
+            if (Get-ItemProperty -Path $regPath -Name 'DohFlags' -ErrorAction SilentlyContinue) {{
+                Remove-ItemProperty -Path $regPath -Name 'DohFlags' -Force
+            }}
+            $addresses = @($dohServers | ForEach-Object {{ $_.Address }})
+            foreach ($familyKey in {}) {{
+                $familyPath = "$settingsPath\$familyKey"
+                if (Test-Path $familyPath) {{
+                    Get-ChildItem -Path $familyPath |
+                        Where-Object {{ $addresses -notcontains $_.PSChildName }} |
+                        Remove-Item -Recurse -Force
+                }}
+            }}
+            foreach ($server in $dohServers) {{
+                $serverPath = "$settingsPath\$($server.Key)\$($server.Address)"
+                New-Item -Path $serverPath -Force | Out-Null
+                New-ItemProperty -Path $serverPath -Name 'DohFlags' -Value $server.Flags -PropertyType QWord -Force | Out-Null
+                New-ItemProperty -Path $serverPath -Name 'DohTemplate' -Value $server.Template -PropertyType String -Force | Out-Null
+            }}
+        }}
+        "#,
+        interface_registry_path(interface),
+        PER_SERVER_DOH_MIN_BUILD,
+        key_list(family_keys)
+    )
+}
+
+/// Turns DoH off for the families in `family_keys`. The interface-wide flag
+/// of older builds also covers a family left alone, so it is only removed
+/// with both.
+fn disable_doh_registry_script(interface: &NetworkInterface, family_keys: &[&str]) -> String {
+    let remove_flag = if family_keys.len() == DOH_FAMILY_KEYS.len() {
+        r#"
+        if (Get-ItemProperty -Path $regPath -Name 'DohFlags' -ErrorAction SilentlyContinue) {
+            Remove-ItemProperty -Path $regPath -Name 'DohFlags' -Force
+        }"#
+    } else {
+        ""
+    };
+    format!(
+        r#"
+        $regPath = '{}'{}
+        foreach ($familyKey in {}) {{
+            $familyPath = "$regPath\DohInterfaceSettings\$familyKey"
+            if (Test-Path $familyPath) {{
+                Remove-Item -Path $familyPath -Recurse -Force
+            }}
+        }}
+        "#,
+        interface_registry_path(interface),
+        remove_flag,
+        key_list(family_keys)
+    )
+}
+
+const SET_STEP: &str = "set";
+const REGISTRY_STEP: &str = "registry";
+const DISABLE_REGISTRY_STEP: &str = "registry-off";
+
+/// Builds one script that runs every apply step and prints the per-step
+/// results as a JSON array. DoH steps only run if the address change (when
+/// present) succeeded, and the registry step only if a DoH step succeeded.
+/// Without any DoH server, the registry marker is removed instead.
+fn build_apply_script(
+    set_script: Option<&str>,
+    interface: &NetworkInterface,
+    settings: &DnsSettings,
+) -> String {
+    let targets = doh_targets(settings);
+    let family_keys = doh_family_keys(settings);
+    let doh_steps = if family_keys.is_empty() {
+        String::new()
+    } else if targets.is_empty() {
+        format!(
+            "Invoke-Step '{}' {{ {} }} | Out-Null\n",
+            DISABLE_REGISTRY_STEP,
+            disable_doh_registry_script(interface, &family_keys)
+        )
+    } else {
+        let mut steps = String::from("$dohServers = @()\n");
+        for (label, server) in targets {
+            steps.push_str(&format!(
+                "if (Invoke-Step '{}' {{ {} }}) {{ $dohServers += {} }}\n",
+                label,
+                configure_doh_script(server),
+                doh_registry_entry(server)
+            ));
+        }
+        steps.push_str(&format!(
+            "if ($dohServers.Count -gt 0) {{ Invoke-Step '{}' {{ {} }} | Out-Null }}\n",
+            REGISTRY_STEP,
+            write_doh_registry_script(interface, &family_keys)
+        ));
+        steps
+    };
+
+    let body = match set_script {
+        Some(set_script) => format!(
+            "if (Invoke-Step '{}' {{ {} }}) {{\n{}}}\n",
+            SET_STEP, set_script, doh_steps
+        ),
+        None => doh_steps,
+    };
+
+    format!(
+        r#"
+        $results = @()
+        function Invoke-Step([string]$Name, [scriptblock]$Action) {{
+            try {{
+                & $Action | Out-Null
+                $script:results += [pscustomobject]@{{ step = $Name; ok = $true; error = $null }}
+                return $true
+            }} catch {{
+                $script:results += [pscustomobject]@{{ step = $Name; ok = $false; error = $_.Exception.Message }}
+                return $false
+            }}
+        }}
+        {}
+        ConvertTo-Json -Compress -InputObject @($results)
+        "#,
+        body
+    )
+}
+
+#[derive(Debug, Deserialize)]
+struct StepResult {
+    step: String,
+    ok: bool,
+    error: Option<String>,
+}
+
+async fn run_apply_script(script: &str) -> Result<Option<String>> {
+    let output = run_powershell(script).await?;
+    let results: Vec<StepResult> =
+        serde_json::from_str(output.trim()).map_err(|_| DnsCommandError::InvalidOutput)?;
+    interpret_step_results(&results)
+}
+
+/// Maps per-step results of a batched apply onto the same outcome the
+/// stepwise apply produces.
+fn interpret_step_results(results: &[StepResult]) -> Result<Option<String>> {
+    let mut outcome = DohOutcome::default();
+
+    for result in results {
+        let error = if result.ok {
+            None
+        } else {
+            Some(result.error.clone().unwrap_or_default())
+        };
+
+        match result.step.as_str() {
+            SET_STEP => {
+                if let Some(e) = error {
+                    return Err(DnsCommandError::CommandFailed(normalize_error_message(&e)));
+                }
+            }
+            REGISTRY_STEP => outcome.registry_error = error,
+            DISABLE_REGISTRY_STEP => outcome.disable_error = error,
+            label => outcome.record(label, error),
+        }
+    }
+
+    outcome.into_result()
+}
+
+/// Parses the JSON array of addresses printed by `get_doh_servers`.
+fn parse_doh_servers(output: &str) -> Result<Vec<String>> {
+    if output.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    serde_json::from_str(output.trim()).map_err(|_| DnsCommandError::InvalidOutput)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DohServerAddress {
+    server_address: String,
+    doh_template: String,
+    allow_fallback_to_udp: bool,
+    auto_upgrade: bool,
+}
+
+/// Parses the JSON array of registrations printed by `get_doh_templates`.
+fn parse_doh_templates(output: &str) -> Result<Vec<DnsServerEntry>> {
+    if output.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let registrations: Vec<DohServerAddress> =
+        serde_json::from_str(output.trim()).map_err(|_| DnsCommandError::InvalidOutput)?;
+    Ok(registrations
+        .into_iter()
+        .map(|r| DnsServerEntry {
+            address: r.server_address,
+            doh_mode: DohMode::On,
+            doh_template: r.doh_template,
+            allow_fallback: r.allow_fallback_to_udp,
+            auto_upgrade: r.auto_upgrade,
+        })
+        .collect())
+}
+
+/// Prints the server addresses of the interface and the addresses among
+/// them that use DoH. A server uses DoH when it has a system-wide template
+/// and DoH is enabled for the interface, either through the interface-wide
+/// flag or a per-server key.
+fn current_dns_script(interface: &NetworkInterface) -> String {
+    format!(
+        r#"
+        $servers = @(Get-DnsClientServerAddress -InterfaceIndex {} | Select-Object AddressFamily, ServerAddresses)
+        $regPath = '{}'
+        $interfaceDoh = [bool](Get-ItemProperty -Path $regPath -Name 'DohFlags' -ErrorAction SilentlyContinue)
+        $perServerDoh = @(foreach ($familyKey in 'Doh', 'Doh6') {{
+            $familyPath = "$regPath\DohInterfaceSettings\$familyKey"
+            if (Test-Path $familyPath) {{
+                Get-ChildItem -Path $familyPath | ForEach-Object {{ $_.PSChildName }}
+            }}
+        }})
+        $dohServers = @(Get-DnsClientDohServerAddress -ErrorAction SilentlyContinue |
+            ForEach-Object {{ $_.ServerAddress }} |
+            Where-Object {{ $interfaceDoh -or $perServerDoh -contains $_ }})
+        $staticServers = @{{
+            IPv4 = [bool](Get-ItemProperty -Path '{}' -Name 'NameServer' -ErrorAction SilentlyContinue).NameServer
+            IPv6 = [bool](Get-ItemProperty -Path '{}' -Name 'NameServer' -ErrorAction SilentlyContinue).NameServer
+        }}
+        ConvertTo-Json -Compress -Depth 3 -InputObject @{{ Servers = $servers; DohServers = $dohServers; StaticServers = $staticServers }}
+        "#,
+        interface.interface_index,
+        interface_registry_path(interface),
+        tcpip_registry_path(interface, "Tcpip"),
+        tcpip_registry_path(interface, "Tcpip6")
+    )
+}
+
+/// Parses the JSON object printed by `current_dns_script`.
+fn parse_current_dns(output: &str) -> Result<CurrentDnsState> {
+    let mut state = CurrentDnsState::new();
+
+    if output.trim().is_empty() || output.trim() == "null" {
+        return Ok(state);
+    }
+
+    let json_value: serde_json::Value =
+        serde_json::from_str(output).map_err(|_| DnsCommandError::InvalidOutput)?;
+
+    let entries = match json_value.get("Servers") {
+        Some(serde_json::Value::Array(entries)) => entries.clone(),
+        Some(serde_json::Value::Null) | None => Vec::new(),
+        Some(entry) => vec![entry.clone()],
+    };
+
+    for entry in entries {
+        if let Some(family) = entry.get("AddressFamily").and_then(|v| v.as_u64())
+            && let Some(addresses) = entry.get("ServerAddresses").and_then(|v| v.as_array())
+        {
+            let addr_list: Vec<String> = addresses
+                .iter()
+                .filter_map(|a| a.as_str().map(|s| s.to_string()))
+                .collect();
+
+            match family {
+                AF_INET => state.ipv4 = addr_list,
+                AF_INET6 => state.ipv6 = addr_list,
+                _ => {}
+            }
+        }
+    }
+
+    if let Some(static_servers) = json_value.get("StaticServers") {
+        let origin = |key: &str| match static_servers.get(key).and_then(|v| v.as_bool()) {
+            Some(true) => DnsOrigin::Static,
+            Some(false) => DnsOrigin::Dhcp,
+            None => DnsOrigin::Unknown,
+        };
+        state.ipv4_origin = origin("IPv4");
+        state.ipv6_origin = origin("IPv6");
+    }
+
+    if let Some(doh_servers) = json_value.get("DohServers").and_then(|v| v.as_array()) {
+        state.doh_servers = doh_servers
+            .iter()
+            .filter_map(|a| a.as_str())
+            .filter(|a| state.ipv4.iter().chain(&state.ipv6).any(|s| s == a))
+            .map(|a| a.to_string())
+            .collect();
+    }
+
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dns::types::{DnsEntry, DohMode, test_interface};
+
+    fn doh_settings() -> DnsSettings {
+        DnsSettings {
+            ipv4: DnsEntry {
+                enabled: true,
+                unchanged: false,
+                servers: vec![
+                    DnsServerEntry {
+                        address: "1.1.1.1".to_string(),
+                        doh_mode: DohMode::On,
+                        doh_template: "https://cloudflare-dns.com/dns-query".to_string(),
+                        allow_fallback: false,
+                        auto_upgrade: true,
+                    },
+                    DnsServerEntry {
+                        address: "1.0.0.1".to_string(),
+                        ..Default::default()
+                    },
+                ],
+            },
+            ipv6: DnsEntry::default(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_configure_side_by_side_overlaps_calls() {
+        let servers = ["1.1.1.1", "bad", "9.9.9.9"].map(|address| DnsServerEntry {
+            address: address.to_string(),
+            ..Default::default()
+        });
+        let targets: Vec<_> = servers
+            .iter()
+            .enumerate()
+            .map(|(i, server)| (format!("Server {}", i + 1), server))
+            .collect();
+        let in_flight = &std::cell::Cell::new(0);
+        let peak = &std::cell::Cell::new(0);
+
+        let mut outcome = DohOutcome::default();
+        let configured = configure_side_by_side(
+            &targets,
+            |server| async move {
+                in_flight.set(in_flight.get() + 1);
+                peak.set(peak.get().max(in_flight.get()));
+                tokio::task::yield_now().await;
+                in_flight.set(in_flight.get() - 1);
+                if server.address == "bad" {
+                    return Err(DnsCommandError::CommandFailed("failed".to_string()));
+                }
+                Ok(())
+            },
+            &mut outcome,
+        )
+        .await;
+
+        assert_eq!(peak.get(), 3);
+        let addresses: Vec<&str> = configured.iter().map(|s| s.address.as_str()).collect();
+        assert_eq!(addresses, ["1.1.1.1", "9.9.9.9"]);
+        assert_eq!(outcome.attempted, 3);
+        assert_eq!(outcome.errors.len(), 1);
+        assert!(outcome.errors[0].starts_with("Server 2: "));
+    }
+
+    fn step(name: &str, error: Option<&str>) -> StepResult {
+        StepResult {
+            step: name.to_string(),
+            ok: error.is_none(),
+            error: error.map(|e| e.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_build_apply_script_contains_all_steps() {
+        let interface = test_interface("Ethernet", 7);
+        let settings = doh_settings();
+        let set_script = set_manual_script(&interface, &collect_addresses(&settings));
+        let script = build_apply_script(Some(&set_script), &interface, &settings);
+
+        assert!(script.contains("Invoke-Step 'set'"));
+        assert!(script.contains("-ServerAddresses @('1.1.1.1','1.0.0.1')"));
+        assert!(script.contains("Invoke-Step 'IPv4 Primary'"));
+        assert!(!script.contains("Invoke-Step 'IPv4 Secondary'"));
+        assert!(script.contains("-AllowFallbackToUdp $false"));
+        assert!(script.contains("Invoke-Step 'registry'"));
+        assert!(script.contains("$dohServers += @{ Address = '1.1.1.1'; Key = 'Doh';"));
+        assert!(script.contains("InterfaceSpecificParameters\\{GUID-7}"));
+        assert!(script.contains("ConvertTo-Json -Compress -InputObject @($results)"));
+    }
+
+    #[test]
+    fn test_set_manual_script_keeps_zone_id() {
+        let addresses = ["192.168.1.1".to_string(), "fe80::1%12".to_string()];
+        assert_eq!(
+            set_manual_script(&test_interface("Ethernet", 7), &addresses),
+            format!(
+                "Set-DnsClientServerAddress -InterfaceIndex {} -ServerAddresses @('192.168.1.1','fe80::1%12')",
+                test_interface("Ethernet", 7).interface_index
+            )
+        );
+    }
+
+    #[test]
+    fn test_build_apply_script_without_doh_removes_registry_flag() {
+        let mut settings = doh_settings();
+        settings.ipv4.servers[0].doh_mode = DohMode::Off;
+        let script = build_apply_script(None, &test_interface("Ethernet", 7), &settings);
+
+        assert!(script.contains("Invoke-Step 'registry-off'"));
+        assert!(script.contains("Remove-ItemProperty -Path $regPath -Name 'DohFlags'"));
+        assert!(script.contains("Remove-Item -Path $familyPath -Recurse"));
+        assert!(!script.contains("Invoke-Step 'registry' "));
+        assert!(!script.contains("Add-DnsClientDohServerAddress"));
+    }
+
+    #[test]
+    fn test_doh_registry_entry_uses_family_key_and_flags() {
+        let mut server = DnsServerEntry {
+            address: "2606:4700:4700::1111".to_string(),
+            doh_mode: DohMode::On,
+            doh_template: "https://cloudflare-dns.com/dns-query".to_string(),
+            allow_fallback: false,
+            auto_upgrade: true,
+        };
+        assert_eq!(
+            doh_registry_entry(&server),
+            "@{ Address = '2606:4700:4700::1111'; Key = 'Doh6'; \
+             Template = 'https://cloudflare-dns.com/dns-query'; Flags = 2 }"
+        );
+
+        server.address = "1.1.1.1".to_string();
+        server.allow_fallback = true;
+        let entry = doh_registry_entry(&server);
+        assert!(entry.contains("Key = 'Doh';"));
+        assert!(entry.contains("Flags = 18"));
+    }
+
+    #[test]
+    fn test_enable_doh_registry_script_lists_only_given_servers() {
+        let settings = doh_settings();
+        let script = enable_doh_registry_script(
+            &test_interface("Ethernet", 7),
+            &[&settings.ipv4.servers[0]],
+            &DOH_FAMILY_KEYS,
+        );
+
+        assert!(script.starts_with("$dohServers = @(@{ Address = '1.1.1.1'; Key = 'Doh';"));
+        assert!(!script.contains("'1.0.0.1'"));
+        assert!(script.contains("if ($build -lt 22621)"));
+        assert!(script.contains("$settingsPath\\$($server.Key)\\$($server.Address)"));
+    }
+
+    #[test]
+    fn test_build_apply_script_without_set_step() {
+        let script = build_apply_script(None, &test_interface("Ethernet", 7), &doh_settings());
+        assert!(!script.contains("Invoke-Step 'set'"));
+        assert!(script.contains("Invoke-Step 'IPv4 Primary'"));
+    }
+
+    #[test]
+    fn test_dedent() {
+        let script = "\n        if ($a) {\n            Do-It\n        }\n        ";
+        assert_eq!(dedent(script), "if ($a) {\n    Do-It\n}");
+    }
+
+    #[test]
+    fn test_preview_settings_with_doh() {
+        let steps =
+            PowerShellBackend.preview_settings(&test_interface("Ethernet", 7), &doh_settings());
+        let titles: Vec<&str> = steps.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(
+            titles,
+            vec![
+                "Set DNS servers",
+                "Configure DoH (IPv4 Primary)",
+                "Enable DoH on the interface"
+            ]
+        );
+        assert!(steps[1].command.starts_with("$addr = '1.1.1.1'"));
+    }
+
+    #[test]
+    fn test_set_connection_suffix_script() {
+        assert_eq!(
+            set_connection_suffix_script(&test_interface("Ethernet", 7), " corp.example.com "),
+            "Set-DnsClient -InterfaceIndex 7 -ConnectionSpecificSuffix 'corp.example.com'"
+        );
+    }
+
+    #[test]
+    fn test_set_suffix_search_list_script() {
+        let suffixes = vec![
+            "corp.example.com".to_string(),
+            "o'brien.example".to_string(),
+        ];
+        assert_eq!(
+            set_suffix_search_list_script(&suffixes),
+            "Set-DnsClientGlobalSetting -SuffixSearchList @('corp.example.com','o''brien.example')"
+        );
+    }
+
+    #[test]
+    fn test_preview_settings_includes_managed_suffixes() {
+        let mut settings = doh_settings();
+        let titles = |settings: &DnsSettings| {
+            PowerShellBackend
+                .preview_settings(&test_interface("Ethernet", 7), settings)
+                .into_iter()
+                .map(|s| s.title)
+                .collect::<Vec<_>>()
+        };
+        assert!(!titles(&settings).iter().any(|t| t.contains("suffix")));
+
+        settings.connection_suffix = Some("corp.example.com".to_string());
+        assert_eq!(
+            titles(&settings).last().unwrap(),
+            "Set connection-specific DNS suffix"
+        );
+
+        settings.suffixes.enabled = true;
+        assert_eq!(
+            titles(&settings).last().unwrap(),
+            "Set DNS suffix search list"
+        );
+
+        settings.nrpt.enabled = true;
+        assert_eq!(titles(&settings).last().unwrap(), "Replace NRPT rules");
+    }
+
+    #[test]
+    fn test_preview_settings_automatic() {
+        let steps =
+            PowerShellBackend.preview_settings(&test_interface("Ethernet", 7), &DnsSettings::new());
+        assert_eq!(steps.len(), 2);
+        assert!(steps[0].command.contains("-ResetServerAddresses"));
+        assert!(steps[1].command.contains("Remove-ItemProperty"));
+    }
+
+    #[test]
+    fn test_interpret_step_results_success() {
+        let results = vec![
+            step("set", None),
+            step("IPv4 Primary", None),
+            step("registry", None),
+        ];
+        assert!(matches!(interpret_step_results(&results), Ok(None)));
+    }
+
+    #[test]
+    fn test_interpret_step_results_set_failed() {
+        let results = vec![step("set", Some("Access is denied.\n"))];
+        match interpret_step_results(&results) {
+            Err(DnsCommandError::CommandFailed(msg)) => assert_eq!(msg, "Access is denied."),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_interpret_step_results_partial_doh_failure() {
+        let results = vec![
+            step("set", None),
+            step("IPv4 Primary", None),
+            step("IPv4 Secondary", Some("bad template")),
+            step("registry", None),
+        ];
+        let warning = interpret_step_results(&results)
+            .unwrap()
+            .expect("should warn");
+        assert_eq!(
+            warning,
+            "Some DoH configurations failed: IPv4 Secondary: bad template"
+        );
+    }
+
+    #[test]
+    fn test_interpret_step_results_all_doh_failed() {
+        let results = vec![
+            step("set", None),
+            step("IPv4 Primary", Some("bad template")),
+        ];
+        assert!(matches!(
+            interpret_step_results(&results),
+            Err(DnsCommandError::DnsAppliedButDohFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_interpret_step_results_registry_failed() {
+        let results = vec![
+            step("set", None),
+            step("IPv4 Primary", None),
+            step("registry", Some("denied")),
+        ];
+        match interpret_step_results(&results) {
+            Err(DnsCommandError::DnsAppliedButDohFailed(msg)) => {
+                assert_eq!(msg, "Registry configuration failed: denied")
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_interpret_step_results_registry_off_failed_warns() {
+        let results = vec![step("set", None), step("registry-off", Some("denied"))];
+        let warning = interpret_step_results(&results).unwrap();
+        assert_eq!(
+            warning.as_deref(),
+            Some("Failed to turn off DoH on the interface: denied")
+        );
+    }
+
+    #[test]
+    fn test_parse_doh_servers() {
+        assert_eq!(
+            parse_doh_servers(r#"["1.1.1.1","2606:4700:4700::1111"]"#).unwrap(),
+            vec!["1.1.1.1", "2606:4700:4700::1111"]
+        );
+        assert!(parse_doh_servers("[]").unwrap().is_empty());
+        assert!(parse_doh_servers("").unwrap().is_empty());
+        assert!(parse_doh_servers("not json").is_err());
+    }
+
+    #[test]
+    fn test_parse_doh_templates() {
+        let output = r#"[{"ServerAddress":"1.1.1.1","DohTemplate":"https://cloudflare-dns.com/dns-query","AllowFallbackToUdp":false,"AutoUpgrade":true}]"#;
+        assert_eq!(
+            parse_doh_templates(output).unwrap(),
+            vec![DnsServerEntry {
+                address: "1.1.1.1".to_string(),
+                doh_mode: DohMode::On,
+                doh_template: "https://cloudflare-dns.com/dns-query".to_string(),
+                allow_fallback: false,
+                auto_upgrade: true,
+            }]
+        );
+        assert!(parse_doh_templates("").unwrap().is_empty());
+        assert!(parse_doh_templates(r#"[{"ServerAddress":"1.1.1.1"}]"#).is_err());
+    }
+
+    #[test]
+    fn test_configure_doh_script_passes_flags() {
+        let mut server = DnsServerEntry {
+            address: "1.1.1.1".to_string(),
+            doh_mode: DohMode::On,
+            doh_template: "https://cloudflare-dns.com/dns-query".to_string(),
+            allow_fallback: false,
+            auto_upgrade: true,
+        };
+        let script = configure_doh_script(&server);
+        assert!(script.contains("-AllowFallbackToUdp $false -AutoUpgrade $true"));
+
+        server.auto_upgrade = false;
+        let script = configure_doh_script(&server);
+        assert!(script.contains("-AllowFallbackToUdp $false -AutoUpgrade $false"));
+        assert!(!script.contains("$true"));
+    }
+
+    #[test]
+    fn test_remove_doh_server_script_escapes_address() {
+        let script = remove_doh_server_script("a'b");
+        assert!(script.contains("$addr = 'a''b'"));
+        assert!(script.contains("Remove-DnsClientDohServerAddress -ServerAddress $addr"));
+    }
+
+    #[test]
+    fn test_step_result_deserialize() {
+        let json = r#"[{"step":"set","ok":true,"error":null},{"step":"IPv4 Primary","ok":false,"error":"x"}]"#;
+        let results: Vec<StepResult> = serde_json::from_str(json).unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results[0].ok);
+        assert_eq!(results[1].error.as_deref(), Some("x"));
+    }
+
+    #[test]
+    fn test_parse_current_dns_empty() {
+        let state = parse_current_dns("").unwrap();
+        assert!(state.ipv4.is_empty());
+        assert!(state.ipv6.is_empty());
+
+        let state = parse_current_dns("null").unwrap();
+        assert!(state.ipv4.is_empty());
+    }
+
+    #[test]
+    fn test_parse_current_dns_both_families() {
+        let output = r#"{"Servers":[{"AddressFamily":2,"ServerAddresses":["8.8.8.8","8.8.4.4"]},{"AddressFamily":23,"ServerAddresses":["2001:4860:4860::8888"]}],"DohServers":[]}"#;
+        let state = parse_current_dns(output).unwrap();
+        assert_eq!(state.ipv4, vec!["8.8.8.8", "8.8.4.4"]);
+        assert_eq!(state.ipv6, vec!["2001:4860:4860::8888"]);
+        assert!(state.doh_servers.is_empty());
+    }
+
+    #[test]
+    fn test_parse_current_dns_single_object() {
+        let output =
+            r#"{"Servers":{"AddressFamily":2,"ServerAddresses":["1.1.1.1"]},"DohServers":null}"#;
+        let state = parse_current_dns(output).unwrap();
+        assert_eq!(state.ipv4, vec!["1.1.1.1"]);
+        assert!(state.ipv6.is_empty());
+    }
+
+    #[test]
+    fn test_parse_current_dns_origin() {
+        let output = r#"{"Servers":[{"AddressFamily":2,"ServerAddresses":["1.1.1.1"]},{"AddressFamily":23,"ServerAddresses":["fe80::1"]}],"DohServers":[],"StaticServers":{"IPv4":true,"IPv6":false}}"#;
+        let state = parse_current_dns(output).unwrap();
+        assert_eq!(state.ipv4_origin, DnsOrigin::Static);
+        assert_eq!(state.ipv6_origin, DnsOrigin::Dhcp);
+
+        let state = parse_current_dns(r#"{"Servers":[],"DohServers":[]}"#).unwrap();
+        assert_eq!(state.ipv4_origin, DnsOrigin::Unknown);
+    }
+
+    #[test]
+    fn test_parse_current_dns_doh_servers_limited_to_current() {
+        let output = r#"{"Servers":[{"AddressFamily":2,"ServerAddresses":["1.1.1.1","1.0.0.1"]}],"DohServers":["1.1.1.1","9.9.9.9"]}"#;
+        let state = parse_current_dns(output).unwrap();
+        assert_eq!(state.doh_servers, vec!["1.1.1.1"]);
+    }
+
+    #[test]
+    fn test_current_dns_script_checks_both_registry_layouts() {
+        let script = current_dns_script(&test_interface("Ethernet", 7));
+        assert!(script.contains("Get-DnsClientServerAddress -InterfaceIndex 7"));
+        assert!(script.contains("InterfaceSpecificParameters\\{GUID-7}"));
+        assert!(script.contains("-Name 'DohFlags'"));
+        assert!(script.contains("$regPath\\DohInterfaceSettings\\$familyKey"));
+        assert!(script.contains("Services\\Tcpip\\Parameters\\Interfaces\\{GUID-7}"));
+        assert!(script.contains("Services\\Tcpip6\\Parameters\\Interfaces\\{GUID-7}"));
+    }
+
+    #[test]
+    fn test_parse_current_dns_invalid() {
+        let result = parse_current_dns("not json");
+        assert!(matches!(result, Err(DnsCommandError::InvalidOutput)));
+    }
+}
@@ -0,0 +1,85 @@
+use crate::dns::commands::run_powershell;
+use serde::Deserialize;
+
+/// First Windows build with the DoH client cmdlets (Windows Server 2022;
+/// Windows 11 starts at 22000).
+pub const MIN_DOH_BUILD: u32 = 20348;
+
+const PROBE_SCRIPT: &str = "ConvertTo-Json -Compress -InputObject @{ \
+    Build = [int](Get-ItemProperty -Path 'HKLM:\\SOFTWARE\\Microsoft\\Windows NT\\CurrentVersion').CurrentBuildNumber; \
+    DohCmdlets = [bool](Get-Command Get-DnsClientDohServerAddress -ErrorAction SilentlyContinue) }";
+
+/// Features of the running Windows installation that windns depends on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Capabilities {
+    pub doh: bool,
+}
+
+impl Default for Capabilities {
+    /// Assumes everything is supported until detection has run.
+    fn default() -> Self {
+        Self { doh: true }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ProbeResult {
+    build: u32,
+    doh_cmdlets: bool,
+}
+
+impl Capabilities {
+    /// Probes the OS build and the DoH cmdlets. Anything that cannot be
+    /// probed is reported as unsupported.
+    pub async fn detect() -> Self {
+        match run_powershell(PROBE_SCRIPT).await {
+            Ok(output) => parse_probe(&output),
+            Err(_) => Self { doh: false },
+        }
+    }
+}
+
+fn parse_probe(output: &str) -> Capabilities {
+    match serde_json::from_str::<ProbeResult>(output.trim()) {
+        Ok(probe) => Capabilities {
+            doh: probe.build >= MIN_DOH_BUILD && probe.doh_cmdlets,
+        },
+        Err(_) => Capabilities { doh: false },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_probe_windows_11() {
+        let capabilities = parse_probe(r#"{"Build":22631,"DohCmdlets":true}"#);
+        assert!(capabilities.doh);
+    }
+
+    #[test]
+    fn test_parse_probe_windows_10() {
+        let capabilities = parse_probe(r#"{"Build":19045,"DohCmdlets":false}"#);
+        assert!(!capabilities.doh);
+    }
+
+    #[test]
+    fn test_parse_probe_missing_cmdlets() {
+        let capabilities = parse_probe(r#"{"Build":22631,"DohCmdlets":false}"#);
+        assert!(!capabilities.doh);
+    }
+
+    #[test]
+    fn test_parse_probe_invalid_output() {
+        assert!(!parse_probe("not json").doh);
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_detect() {
+        let capabilities = Capabilities::detect().await;
+        println!("{:?}", capabilities);
+    }
+}
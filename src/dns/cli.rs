@@ -0,0 +1,212 @@
+use crate::dns::commands::ApplyStep;
+use crate::dns::config::CONFIG_ARG;
+use crate::dns::control::{ControlProfile, ControlRequest, ControlStatus};
+use crate::i18n::t;
+use serde::Serialize;
+
+/// Argument that prints the result of a command as one line of JSON, for
+/// scripts.
+const JSON_ARG: &str = "--json";
+
+/// A command run from a command prompt without opening the window, such as
+/// `windns apply Cloudflare --json`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CliCommand {
+    pub request: ControlRequest,
+    pub json: bool,
+}
+
+/// Reads `windns status`, `windns list` or `windns apply [<profile>]` from
+/// the command-line arguments. `apply` without a profile switches to
+/// Automatic, as in the control pipe.
+pub fn cli_command(args: impl IntoIterator<Item = String>) -> Option<CliCommand> {
+    let mut json = false;
+    let mut words = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == JSON_ARG {
+            json = true;
+        } else if arg == CONFIG_ARG {
+            args.next();
+        } else if !arg.starts_with("--") {
+            words.push(arg);
+        }
+    }
+
+    let mut words = words.into_iter();
+    let request = match words.next()?.as_str() {
+        "status" => ControlRequest::Status,
+        "list" => ControlRequest::List,
+        "apply" => ControlRequest::Apply {
+            profile: words.next(),
+        },
+        _ => return None,
+    };
+    Some(CliCommand { request, json })
+}
+
+/// Result of `windns apply`, as printed with `--json`.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct CliApplyResult {
+    pub interface: String,
+    /// Name of the profile applied; `None` for Automatic.
+    pub profile: Option<String>,
+    /// Whether every step succeeded.
+    pub ok: bool,
+    pub steps: Vec<ApplyStep>,
+    /// Warnings of steps that went through in part, such as DoH failing
+    /// for some of the servers.
+    pub warnings: Vec<String>,
+}
+
+impl CliApplyResult {
+    pub fn new(interface: String, profile: Option<String>, steps: Vec<ApplyStep>) -> Self {
+        Self {
+            interface,
+            profile,
+            ok: steps.iter().all(|step| step.ok),
+            warnings: steps.iter().filter_map(|s| s.warning.clone()).collect(),
+            steps,
+        }
+    }
+}
+
+/// Reply to a command that failed before doing anything, as printed with
+/// `--json`.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct CliError {
+    pub error: String,
+}
+
+pub fn status_text(status: &ControlStatus) -> String {
+    let Some(interface) = &status.interface else {
+        return t!("control-no-interface");
+    };
+    let profile = match &status.profile {
+        Some(name) => name.clone(),
+        None if status.automatic => t!("automatic"),
+        None => t!("custom-dns"),
+    };
+    let mut lines = vec![interface.clone(), t!("cli-profile", profile = profile)];
+    if !status.ipv4.is_empty() {
+        lines.push(format!("IPv4: {}", status.ipv4.join(", ")));
+    }
+    if !status.ipv6.is_empty() {
+        lines.push(format!("IPv6: {}", status.ipv6.join(", ")));
+    }
+    lines.join("\n")
+}
+
+/// One profile per line, the one in use marked with `*`.
+pub fn profiles_text(profiles: &[ControlProfile]) -> String {
+    profiles
+        .iter()
+        .map(|p| format!("{} {}", if p.active { "*" } else { " " }, p.name))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn apply_text(result: &CliApplyResult) -> String {
+    let profile = result.profile.clone().unwrap_or_else(|| t!("automatic"));
+    let mut lines = vec![format!("{} → {}", result.interface, profile)];
+    lines.extend(
+        result
+            .steps
+            .iter()
+            .map(|step| match (&step.error, &step.warning) {
+                (Some(error), _) => t!("cli-step-failed", step = step.step, error = error),
+                (None, Some(warning)) => {
+                    t!("cli-step-warning", step = step.step, warning = warning)
+                }
+                (None, None) => t!("cli-step-ok", step = step.step),
+            }),
+    );
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn test_cli_command() {
+        assert_eq!(
+            cli_command(args("status")),
+            Some(CliCommand {
+                request: ControlRequest::Status,
+                json: false
+            })
+        );
+        assert_eq!(
+            cli_command(args("--config D:\\windns list --json")),
+            Some(CliCommand {
+                request: ControlRequest::List,
+                json: true
+            })
+        );
+        assert_eq!(
+            cli_command(args("apply --json Cloudflare")).map(|c| c.request),
+            Some(ControlRequest::Apply {
+                profile: Some("Cloudflare".to_string())
+            })
+        );
+        assert_eq!(
+            cli_command(args("apply")).map(|c| c.request),
+            Some(ControlRequest::Apply { profile: None })
+        );
+        // The value of --config is not a command.
+        assert_eq!(cli_command(args("--config status")), None);
+        assert_eq!(cli_command(args("reset-all")), None);
+        assert_eq!(cli_command(args("windns://apply/Cloudflare")), None);
+        assert_eq!(cli_command(args("--minimized")), None);
+    }
+
+    #[test]
+    fn test_cli_apply_result_json() {
+        let steps = vec![
+            ApplyStep {
+                step: "servers",
+                ok: true,
+                warning: None,
+                error: None,
+            },
+            ApplyStep {
+                step: "doh",
+                ok: true,
+                warning: Some("Some DoH configurations failed".to_string()),
+                error: None,
+            },
+            ApplyStep {
+                step: "nrpt",
+                ok: false,
+                warning: None,
+                error: Some("Access denied".to_string()),
+            },
+        ];
+        let result = CliApplyResult::new("Ethernet".to_string(), None, steps);
+        assert!(!result.ok);
+        assert_eq!(result.warnings, vec!["Some DoH configurations failed"]);
+
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["profile"], serde_json::Value::Null);
+        assert_eq!(json["steps"][2]["error"], "Access denied");
+        assert!(json["steps"][0].get("error").is_none());
+    }
+
+    #[test]
+    fn test_profiles_text_marks_active_profile() {
+        let profile = |name: &str, active| ControlProfile {
+            id: name.to_lowercase(),
+            name: name.to_string(),
+            active,
+        };
+        assert_eq!(
+            profiles_text(&[profile("Cloudflare", true), profile("Google", false)]),
+            "* Cloudflare\n  Google"
+        );
+    }
+}
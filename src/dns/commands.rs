@@ -1,4 +1,15 @@
-use crate::dns::types::CurrentDnsState;
+use crate::dns::backend::DnsBackend;
+use crate::dns::dpapi::encode_base64;
+use crate::dns::trace::{TraceDetail, redact, trace_command};
+use crate::dns::types::{
+    AddressFamily, AppConfig, CurrentDnsState, DnsEntry, DnsOrigin, DnsServerEntry, DnsSettings,
+    DohMode, NetworkInterface,
+};
+use crate::dns::validation::same_address;
+use serde::Serialize;
+use std::process::Output;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
 use thiserror::Error;
 use tokio::process::Command;
 
@@ -6,10 +17,18 @@ use tokio::process::Command;
 pub enum DnsCommandError {
     #[error("PowerShell command failed: {0}")]
     CommandFailed(String),
+    #[error("netsh command failed: {0}")]
+    NetshFailed(String),
+    #[error("Windows API error: {0}")]
+    WindowsApi(String),
+    #[error("Not supported: {0}")]
+    Unsupported(String),
     #[error("Registry configuration failed: {0}")]
     RegistryFailed(String),
     #[error("DNS settings applied, but DoH configuration failed: {0}")]
     DnsAppliedButDohFailed(String),
+    #[error("Command timed out after {0} seconds")]
+    Timeout(u64),
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
     #[error("Invalid output format")]
@@ -18,23 +37,51 @@ pub enum DnsCommandError {
 
 pub type Result<T> = std::result::Result<T, DnsCommandError>;
 
-const AF_INET: u64 = 2;
-const AF_INET6: u64 = 23;
-
 #[cfg(windows)]
 const CREATE_NO_WINDOW: u32 = 0x08000000;
 
-fn escape_powershell_string(s: &str) -> String {
+pub const DEFAULT_COMMAND_TIMEOUT_SECS: u64 = 30;
+
+static COMMAND_TIMEOUT_SECS: AtomicU64 = AtomicU64::new(DEFAULT_COMMAND_TIMEOUT_SECS);
+
+/// Sets how long external commands may run before they are killed.
+pub fn set_command_timeout(secs: u64) {
+    COMMAND_TIMEOUT_SECS.store(secs.max(1), Ordering::Relaxed);
+}
+
+fn command_timeout() -> Duration {
+    Duration::from_secs(COMMAND_TIMEOUT_SECS.load(Ordering::Relaxed))
+}
+
+/// Runs the command to completion, killing it if it exceeds `timeout`.
+/// The child is also killed when the returned future is dropped, which is
+/// how a cancelled apply stops a running command.
+async fn output_with_timeout(
+    command: &mut Command,
+    timeout: Duration,
+    detail: TraceDetail,
+) -> Result<Output> {
+    command.kill_on_drop(true);
+    let started = Instant::now();
+    let result = match tokio::time::timeout(timeout, command.output()).await {
+        Ok(output) => output.map_err(DnsCommandError::from),
+        Err(_) => Err(DnsCommandError::Timeout(timeout.as_secs())),
+    };
+    trace_command(command.as_std(), detail, started.elapsed(), result.as_ref());
+    result
+}
+
+pub(crate) fn escape_powershell_string(s: &str) -> String {
     s.replace('`', "``")
         .replace("'", "''")
         .replace(['\n', '\r'], "")
 }
 
-fn normalize_guid(guid: &str) -> String {
+pub(crate) fn normalize_guid(guid: &str) -> String {
     guid.trim_matches(['{', '}'].as_ref()).to_string()
 }
 
-fn normalize_error_message(msg: &str) -> String {
+pub(crate) fn normalize_error_message(msg: &str) -> String {
     msg.lines()
         .map(|line| line.trim())
         .filter(|line| !line.is_empty())
@@ -42,7 +89,21 @@ fn normalize_error_message(msg: &str) -> String {
         .join(" ")
 }
 
-async fn run_powershell(script: &str) -> Result<String> {
+pub(crate) async fn run_powershell(script: &str) -> Result<String> {
+    run_powershell_with_timeout(script, command_timeout()).await
+}
+
+/// Runs a script that may take longer than other commands, such as a
+/// download.
+pub(crate) async fn run_powershell_with_timeout(script: &str, timeout: Duration) -> Result<String> {
+    run_powershell_traced(script, timeout, TraceDetail::Full).await
+}
+
+async fn run_powershell_traced(
+    script: &str,
+    timeout: Duration,
+    detail: TraceDetail,
+) -> Result<String> {
     let script_with_setup = format!(
         "[Console]::OutputEncoding = [System.Text.Encoding]::UTF8; $ErrorActionPreference = 'Stop'; {}",
         script
@@ -58,7 +119,7 @@ async fn run_powershell(script: &str) -> Result<String> {
     #[cfg(windows)]
     command.creation_flags(CREATE_NO_WINDOW);
 
-    let output = command.output().await?;
+    let output = output_with_timeout(&mut command, timeout, detail).await?;
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
@@ -70,164 +131,156 @@ async fn run_powershell(script: &str) -> Result<String> {
     Ok(String::from_utf8_lossy(&output.stdout).to_string())
 }
 
-pub async fn get_current_dns(interface_index: u32) -> Result<CurrentDnsState> {
-    let script = format!(
-        "Get-DnsClientServerAddress -InterfaceIndex {} | ConvertTo-Json -Compress",
-        interface_index
-    );
+pub(crate) async fn run_netsh(args: &[&str]) -> Result<String> {
+    let mut command = Command::new("netsh.exe");
+    command.args(args);
 
-    let output = run_powershell(&script).await?;
+    #[cfg(windows)]
+    command.creation_flags(CREATE_NO_WINDOW);
 
-    let mut state = CurrentDnsState::new();
+    let output = output_with_timeout(&mut command, command_timeout(), TraceDetail::Full).await?;
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
 
-    if output.trim().is_empty() || output.trim() == "null" {
-        return Ok(state);
+    if !output.status.success() {
+        // netsh reports most errors on stdout
+        let message = if stdout.trim().is_empty() {
+            String::from_utf8_lossy(&output.stderr).to_string()
+        } else {
+            stdout
+        };
+        return Err(DnsCommandError::NetshFailed(normalize_error_message(
+            &message,
+        )));
     }
 
-    let json_value: serde_json::Value =
-        serde_json::from_str(&output).map_err(|_| DnsCommandError::InvalidOutput)?;
-
-    let entries = if json_value.is_array() {
-        json_value.as_array().expect("checked is_array").clone()
-    } else {
-        vec![json_value]
-    };
+    Ok(stdout)
+}
 
-    for entry in entries {
-        if let Some(family) = entry.get("AddressFamily").and_then(|v| v.as_u64())
-            && let Some(addresses) = entry.get("ServerAddresses").and_then(|v| v.as_array())
-        {
-            let addr_list: Vec<String> = addresses
-                .iter()
-                .filter_map(|a| a.as_str().map(|s| s.to_string()))
-                .collect();
+/// Longest output of a hook command kept for the activity log, in
+/// characters.
+const MAX_HOOK_OUTPUT_CHARS: usize = 300;
 
-            match family {
-                AF_INET => state.ipv4 = addr_list,
-                AF_INET6 => state.ipv6 = addr_list,
-                _ => {}
-            }
+/// Runs a command a profile has run before or after it is applied, as
+/// PowerShell would, and returns what it printed. What it printed, or its
+/// error, is redacted as in the trace log and cut short for the activity log.
+pub async fn run_hook_command(command: &str) -> Result<String> {
+    match run_powershell(command.trim()).await {
+        Ok(output) => Ok(hook_output(output.trim())),
+        Err(DnsCommandError::CommandFailed(message)) => {
+            Err(DnsCommandError::CommandFailed(hook_output(&message)))
         }
+        Err(e) => Err(e),
     }
-
-    Ok(state)
 }
 
-pub async fn set_dns_automatic(interface_index: u32) -> Result<()> {
-    let script = format!(
-        "Set-DnsClientServerAddress -InterfaceIndex {} -ResetServerAddresses",
-        interface_index
-    );
-
-    run_powershell(&script).await?;
+fn hook_output(text: &str) -> String {
+    let text = redact(text);
+    match text.char_indices().nth(MAX_HOOK_OUTPUT_CHARS) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text,
+    }
+}
 
-    Ok(())
+/// Returns true if PowerShell can be started and runs scripts.
+pub async fn is_powershell_available() -> bool {
+    run_powershell("$PSVersionTable.PSVersion.Major")
+        .await
+        .is_ok_and(|output| !output.trim().is_empty())
 }
 
-pub async fn set_dns_manual(interface_index: u32, addresses: Vec<String>) -> Result<()> {
-    if addresses.is_empty() {
-        return set_dns_automatic(interface_index).await;
+/// Addresses of all enabled families in apply order, without duplicates.
+pub(crate) fn collect_addresses(settings: &DnsSettings) -> Vec<String> {
+    let mut all_addresses: Vec<String> = Vec::new();
+
+    if settings.ipv4.enabled {
+        all_addresses.extend(settings.ipv4.get_addresses());
+    }
+    if settings.ipv6.enabled {
+        all_addresses.extend(settings.ipv6.get_addresses());
     }
 
-    let addr_list = addresses
-        .iter()
-        .map(|a| format!("'{}'", escape_powershell_string(a)))
-        .collect::<Vec<_>>()
-        .join(",");
+    let mut seen = std::collections::HashSet::new();
+    all_addresses.retain(|addr| seen.insert(addr.clone()));
+    all_addresses
+}
 
-    let script = format!(
-        "Set-DnsClientServerAddress -InterfaceIndex {} -ServerAddresses @({})",
-        interface_index, addr_list
-    );
+/// Servers of enabled families that have a usable DoH configuration,
+/// paired with the label used in error messages.
+pub(crate) fn doh_targets(settings: &DnsSettings) -> Vec<(String, &DnsServerEntry)> {
+    let mut candidates = Vec::new();
+    for (family, entry) in [("IPv4", &settings.ipv4), ("IPv6", &settings.ipv6)] {
+        if entry.enabled {
+            candidates.extend(entry.servers.iter().enumerate().map(|(index, server)| {
+                (
+                    format!("{} {}", family, DnsEntry::server_label(index)),
+                    server,
+                )
+            }));
+        }
+    }
 
-    run_powershell(&script).await?;
+    candidates
+        .into_iter()
+        .filter(|(_, server)| {
+            server.doh_mode == DohMode::On
+                && !server.address.is_empty()
+                && !server.doh_template.is_empty()
+        })
+        .collect()
+}
 
-    Ok(())
+/// Collected results of the DoH steps of an apply.
+#[derive(Debug, Default)]
+pub(crate) struct DohOutcome {
+    pub attempted: usize,
+    pub errors: Vec<String>,
+    pub registry_error: Option<String>,
+    pub disable_error: Option<String>,
 }
 
-async fn configure_doh_for_server(
-    address: &str,
-    template: &str,
-    allow_fallback: bool,
-) -> Result<()> {
-    let fallback_str = if allow_fallback { "$true" } else { "$false" };
-    let escaped_address = escape_powershell_string(address);
-    let escaped_template = escape_powershell_string(template);
-
-    let script = format!(
-        r#"
-        $addr = '{}'
-        $existing = Get-DnsClientDohServerAddress -ServerAddress $addr -ErrorAction SilentlyContinue
-        if ($existing) {{
-            Set-DnsClientDohServerAddress -ServerAddress $addr -DohTemplate '{}' -AllowFallbackToUdp {} -AutoUpgrade $true
-        }} else {{
-            Add-DnsClientDohServerAddress -ServerAddress $addr -DohTemplate '{}' -AllowFallbackToUdp {} -AutoUpgrade $true
-        }}
-        "#,
-        escaped_address, escaped_template, fallback_str, escaped_template, fallback_str
-    );
+impl DohOutcome {
+    pub fn record(&mut self, label: &str, error: Option<String>) {
+        self.attempted += 1;
+        if let Some(e) = error {
+            self.errors
+                .push(format!("{}: {}", label, normalize_error_message(&e)));
+        }
+    }
 
-    run_powershell(&script).await?;
-    Ok(())
-}
+    pub fn any_succeeded(&self) -> bool {
+        self.errors.len() < self.attempted
+    }
 
-async fn enable_doh_registry(interface_guid: &str) -> Result<()> {
-    let normalized_guid = normalize_guid(interface_guid);
-    let escaped_guid = escape_powershell_string(&normalized_guid);
-    let script = format!(
-        r#"
-        $regPath = 'HKLM:\SYSTEM\CurrentControlSet\Services\Dnscache\InterfaceSpecificParameters\{{{}}}'
-        if (-not (Test-Path $regPath)) {{
-            New-Item -Path $regPath -Force | Out-Null
-        }}
-        $propName = 'DohFlags'
-        $existingProp = Get-ItemProperty -Path $regPath -Name $propName -ErrorAction SilentlyContinue
-        if ($existingProp) {{
-            Set-ItemProperty -Path $regPath -Name $propName -Value 1 -Force
-        }} else {{
-            New-ItemProperty -Path $regPath -Name $propName -Value 1 -PropertyType DWord -Force | Out-Null
-        }}
-        "#,
-        escaped_guid
-    );
+    /// Converts the outcome into the result of `set_dns_with_settings`.
+    pub fn into_result(self) -> Result<Option<String>> {
+        if self.attempted > 0 && !self.any_succeeded() {
+            return Err(DnsCommandError::DnsAppliedButDohFailed(
+                self.errors.join("; "),
+            ));
+        }
 
-    run_powershell(&script).await.map_err(|e| {
-        DnsCommandError::RegistryFailed(match e {
-            DnsCommandError::CommandFailed(msg) => msg,
-            other => other.to_string(),
-        })
-    })?;
-    Ok(())
-}
+        if let Some(e) = self.registry_error {
+            return Err(DnsCommandError::DnsAppliedButDohFailed(format!(
+                "Registry configuration failed: {}",
+                normalize_error_message(&e)
+            )));
+        }
 
-/// Attempts to configure DoH for a server.
-/// Returns (was_attempted: bool, error: Option<String>)
-/// - (false, None): DoH not applicable (not enabled or empty config)
-/// - (true, None): DoH configured successfully
-/// - (true, Some(err)): DoH configuration failed
-async fn try_configure_doh(
-    server: &crate::dns::DnsServerEntry,
-    label: &str,
-) -> (bool, Option<String>) {
-    if server.doh_mode != crate::dns::DohMode::On
-        || server.address.is_empty()
-        || server.doh_template.is_empty()
-    {
-        return (false, None);
-    }
+        if let Some(e) = self.disable_error {
+            return Ok(Some(format!(
+                "Failed to turn off DoH on the interface: {}",
+                normalize_error_message(&e)
+            )));
+        }
 
-    match configure_doh_for_server(&server.address, &server.doh_template, server.allow_fallback)
-        .await
-    {
-        Ok(()) => (true, None),
-        Err(e) => (
-            true,
-            Some(format!(
-                "{}: {}",
-                label,
-                normalize_error_message(&e.to_string())
-            )),
-        ),
+        if !self.errors.is_empty() {
+            return Ok(Some(format!(
+                "Some DoH configurations failed: {}",
+                self.errors.join("; ")
+            )));
+        }
+
+        Ok(None)
     }
 }
 
@@ -236,119 +289,366 @@ async fn try_configure_doh(
 /// - Ok(Some(warning)): DNS applied, some DoH configs failed but at least one succeeded
 /// - Err(DnsAppliedButDohFailed): DNS applied, but all DoH configs failed or registry failed
 /// - Err(other): DNS application itself failed
-pub async fn set_dns_with_settings(
-    interface_index: u32,
-    interface_guid: &str,
-    settings: &crate::dns::DnsSettings,
+///
+/// Suffixes and NRPT rules are set once the servers are in place; failures
+/// there only add to the warning. Families left unchanged keep the servers
+/// the interface has right before the apply.
+pub async fn set_dns_with_settings<B: DnsBackend>(
+    backend: &B,
+    interface: &NetworkInterface,
+    settings: &DnsSettings,
 ) -> Result<Option<String>> {
-    let mut all_addresses: Vec<String> = Vec::new();
+    apply_dns_settings(backend, interface, settings)
+        .await
+        .into_result()
+}
 
-    if settings.ipv4.enabled {
-        all_addresses.extend(settings.ipv4.get_addresses());
-    }
-    if settings.ipv6.enabled {
-        all_addresses.extend(settings.ipv6.get_addresses());
-    }
+/// What an apply did, part by part.
+#[derive(Debug)]
+pub struct ApplyReport {
+    /// Result of setting the servers and their DoH settings, as
+    /// `set_dns_with_settings` describes it.
+    pub servers: Result<Option<String>>,
+    /// Errors of setting the suffixes, if the settings manage any.
+    pub suffix_errors: Option<Vec<String>>,
+    /// Error of replacing the NRPT rules, if the settings manage them.
+    pub nrpt_error: Option<Option<String>>,
+}
 
-    let mut seen = std::collections::HashSet::new();
-    all_addresses.retain(|addr| seen.insert(addr.clone()));
+/// One part of an apply, as printed by `windns apply --json`.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ApplyStep {
+    pub step: &'static str,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
 
-    if all_addresses.is_empty() {
-        set_dns_automatic(interface_index).await?;
-        return Ok(None);
+impl ApplyStep {
+    pub fn new(step: &'static str, error: Option<String>) -> Self {
+        Self {
+            step,
+            ok: error.is_none(),
+            warning: None,
+            error,
+        }
     }
+}
 
-    set_dns_manual(interface_index, all_addresses).await?;
-
-    let mut doh_errors: Vec<String> = Vec::new();
-    let mut any_doh_succeeded = false;
-    let mut any_doh_attempted = false;
-
-    if settings.ipv4.enabled {
-        let (was_attempted, error) =
-            try_configure_doh(&settings.ipv4.primary, "IPv4 Primary").await;
-        if was_attempted {
-            any_doh_attempted = true;
-            if let Some(e) = error {
-                doh_errors.push(e);
-            } else {
-                any_doh_succeeded = true;
-            }
+impl ApplyReport {
+    fn servers_failed(error: DnsCommandError) -> Self {
+        Self {
+            servers: Err(error),
+            suffix_errors: None,
+            nrpt_error: None,
         }
+    }
 
-        let (was_attempted, error) =
-            try_configure_doh(&settings.ipv4.secondary, "IPv4 Secondary").await;
-        if was_attempted {
-            any_doh_attempted = true;
-            if let Some(e) = error {
-                doh_errors.push(e);
-            } else {
-                any_doh_succeeded = true;
+    /// The parts that ran. Setting the servers comes first; nothing else
+    /// runs if it fails.
+    pub fn steps(&self) -> Vec<ApplyStep> {
+        let doh = match &self.servers {
+            Ok(warning) => ApplyStep {
+                warning: warning.clone(),
+                ..ApplyStep::new("doh", None)
+            },
+            Err(DnsCommandError::DnsAppliedButDohFailed(msg)) => {
+                ApplyStep::new("doh", Some(msg.clone()))
             }
+            Err(e) => return vec![ApplyStep::new("servers", Some(e.to_string()))],
+        };
+        let mut steps = vec![ApplyStep::new("servers", None), doh];
+        if let Some(errors) = &self.suffix_errors {
+            steps.push(ApplyStep::new(
+                "suffixes",
+                (!errors.is_empty()).then(|| errors.join("; ")),
+            ));
         }
+        if let Some(error) = &self.nrpt_error {
+            steps.push(ApplyStep::new("nrpt", error.clone()));
+        }
+        steps
     }
 
-    if settings.ipv6.enabled {
-        let (was_attempted, error) =
-            try_configure_doh(&settings.ipv6.primary, "IPv6 Primary").await;
-        if was_attempted {
-            any_doh_attempted = true;
-            if let Some(e) = error {
-                doh_errors.push(e);
-            } else {
-                any_doh_succeeded = true;
-            }
+    /// Folds the parts into the result of `set_dns_with_settings`.
+    pub fn into_result(self) -> Result<Option<String>> {
+        let mut warnings = Vec::new();
+        if let Some(errors) = self.suffix_errors.filter(|errors| !errors.is_empty()) {
+            warnings.push(format!(
+                "DNS suffix configuration failed: {}",
+                errors.join("; ")
+            ));
         }
+        if let Some(Some(error)) = self.nrpt_error {
+            warnings.push(format!("NRPT configuration failed: {}", error));
+        }
+        if warnings.is_empty() {
+            return self.servers;
+        }
+        let extra_warning = warnings.join("; ");
 
-        let (was_attempted, error) =
-            try_configure_doh(&settings.ipv6.secondary, "IPv6 Secondary").await;
-        if was_attempted {
-            any_doh_attempted = true;
-            if let Some(e) = error {
-                doh_errors.push(e);
-            } else {
-                any_doh_succeeded = true;
+        match self.servers {
+            Ok(None) => Ok(Some(extra_warning)),
+            Ok(Some(warning)) => Ok(Some(format!("{}; {}", warning, extra_warning))),
+            Err(DnsCommandError::DnsAppliedButDohFailed(msg)) => Err(
+                DnsCommandError::DnsAppliedButDohFailed(format!("{}; {}", msg, extra_warning)),
+            ),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Applies `settings` like `set_dns_with_settings`, reporting each part.
+pub async fn apply_dns_settings<B: DnsBackend>(
+    backend: &B,
+    interface: &NetworkInterface,
+    settings: &DnsSettings,
+) -> ApplyReport {
+    let kept;
+    let settings = if settings.leaves_family_unchanged() {
+        match keeping_current(backend, interface, settings).await {
+            Ok(settings) => {
+                kept = settings;
+                &kept
             }
+            Err(e) => return ApplyReport::servers_failed(e),
+        }
+    } else {
+        settings
+    };
+    let servers = match backend.apply_settings(interface, settings).await {
+        Err(e) if !matches!(e, DnsCommandError::DnsAppliedButDohFailed(_)) => {
+            return ApplyReport::servers_failed(e);
         }
+        servers => servers,
+    };
+
+    let manages_suffixes = settings.connection_suffix.is_some() || settings.suffixes.enabled;
+    let suffix_errors = apply_suffixes(backend, interface, settings).await;
+    let nrpt_error = if settings.nrpt.enabled {
+        Some(
+            backend
+                .replace_nrpt_rules(&settings.nrpt.rules)
+                .await
+                .err()
+                .map(command_error_message),
+        )
+    } else {
+        None
+    };
+    ApplyReport {
+        servers,
+        suffix_errors: manages_suffixes.then_some(suffix_errors),
+        nrpt_error,
     }
+}
 
-    if any_doh_attempted && !any_doh_succeeded {
-        return Err(DnsCommandError::DnsAppliedButDohFailed(
-            doh_errors.join("; "),
-        ));
+/// `settings` with the families left unchanged filled in from what the
+/// interface has right now.
+async fn keeping_current<B: DnsBackend>(
+    backend: &B,
+    interface: &NetworkInterface,
+    settings: &DnsSettings,
+) -> Result<DnsSettings> {
+    let current = backend.get_current_dns(interface).await?;
+    let templates = templates_in_use(backend, &current).await?;
+    Ok(settings.keeping_unchanged(&current, &templates))
+}
+
+/// Sets the suffixes managed by `settings` and returns the errors.
+async fn apply_suffixes<B: DnsBackend>(
+    backend: &B,
+    interface: &NetworkInterface,
+    settings: &DnsSettings,
+) -> Vec<String> {
+    let mut errors = Vec::new();
+
+    if let Some(suffix) = &settings.connection_suffix
+        && let Err(e) = backend
+            .set_connection_suffix(interface, suffix.trim())
+            .await
+    {
+        errors.push(command_error_message(e));
     }
 
-    if any_doh_succeeded {
-        enable_doh_registry(interface_guid).await.map_err(|e| {
-            DnsCommandError::DnsAppliedButDohFailed(format!(
-                "Registry configuration failed: {}",
-                normalize_error_message(&match e {
-                    DnsCommandError::RegistryFailed(msg) => msg,
-                    other => other.to_string(),
-                })
-            ))
-        })?;
-    }
-
-    if !doh_errors.is_empty() {
-        return Ok(Some(format!(
-            "Some DoH configurations failed: {}",
-            doh_errors.join("; ")
-        )));
+    if settings.suffixes.enabled
+        && let Err(e) = backend
+            .set_suffix_search_list(&settings.suffixes.search_suffixes())
+            .await
+    {
+        errors.push(command_error_message(e));
+    }
+
+    errors
+}
+
+pub(crate) fn command_error_message(error: DnsCommandError) -> String {
+    match error {
+        DnsCommandError::CommandFailed(msg) => normalize_error_message(&msg),
+        other => other.to_string(),
+    }
+}
+
+/// DoH servers in `settings` that the system has no template for yet, i.e.
+/// the mappings an apply would create. Returns nothing if the existing
+/// mappings cannot be listed, so unknown mappings are never claimed.
+pub async fn new_doh_servers<B: DnsBackend>(backend: &B, settings: &DnsSettings) -> Vec<String> {
+    let targets = doh_targets(settings);
+    if targets.is_empty() {
+        return Vec::new();
+    }
+
+    let Ok(existing) = backend.get_doh_servers().await else {
+        return Vec::new();
+    };
+
+    targets
+        .into_iter()
+        .map(|(_, server)| server.address.clone())
+        .filter(|address| !existing.iter().any(|e| same_address(e, address)))
+        .collect()
+}
+
+/// Reads the DNS configuration of `interface` as settings that reproduce
+/// it. Families served by DHCP stay disabled, and servers queried over DoH
+/// keep their system-wide template.
+pub async fn snapshot_dns_settings<B: DnsBackend>(
+    backend: &B,
+    interface: &NetworkInterface,
+) -> Result<DnsSettings> {
+    let current = backend.get_current_dns(interface).await?;
+    let templates = templates_in_use(backend, &current).await?;
+
+    let entry = |family: AddressFamily| {
+        if current.addresses(family).is_empty() || current.origin(family) == DnsOrigin::Dhcp {
+            return DnsEntry::default();
+        }
+        DnsEntry {
+            enabled: true,
+            unchanged: false,
+            servers: current.server_entries(family, &templates),
+        }
+    };
+
+    Ok(DnsSettings {
+        ipv4: entry(AddressFamily::IPv4),
+        ipv6: entry(AddressFamily::IPv6),
+        ..DnsSettings::new()
+    })
+}
+
+/// System-wide DoH templates, if any current server of the interface is
+/// queried over DoH and so needs its template.
+async fn templates_in_use<B: DnsBackend>(
+    backend: &B,
+    current: &CurrentDnsState,
+) -> Result<Vec<DnsServerEntry>> {
+    if current.doh_servers.is_empty() {
+        Ok(Vec::new())
+    } else {
+        backend.get_doh_templates().await
     }
+}
+
+/// Removes the managed DoH mappings that no profile uses anymore and
+/// returns their addresses. Stops at the first failure; mappings removed
+/// before it are already dropped from `config`.
+pub async fn remove_unused_doh_servers<B: DnsBackend>(
+    backend: &B,
+    config: &mut AppConfig,
+) -> Result<Vec<String>> {
+    let mut removed = Vec::new();
+    for address in config.unused_doh_servers() {
+        backend.remove_doh_server(&address).await?;
+        config.managed_doh_servers.retain(|a| a != &address);
+        removed.push(address);
+    }
+    Ok(removed)
+}
 
-    Ok(None)
+/// Script that puts `text` on the clipboard with Windows line breaks. The
+/// text is passed as base64, as a quoted string loses its line breaks.
+fn clipboard_script(text: &str) -> String {
+    let text = text.lines().collect::<Vec<_>>().join("\r\n");
+    format!(
+        "Set-Clipboard -Value ([System.Text.Encoding]::UTF8.GetString([Convert]::FromBase64String('{}')))",
+        encode_base64(text.as_bytes())
+    )
 }
 
+/// Puts `text` on the Windows clipboard.
+pub async fn set_clipboard_text(text: &str) -> Result<()> {
+    let script = clipboard_script(text);
+    run_powershell_traced(&script, command_timeout(), TraceDetail::TimingOnly).await?;
+    Ok(())
+}
+
+/// Reads the text on the Windows clipboard.
+pub async fn get_clipboard_text() -> Result<String> {
+    let output = run_powershell_traced(
+        "Get-Clipboard -Raw",
+        command_timeout(),
+        TraceDetail::TimingOnly,
+    )
+    .await?;
+    Ok(output.trim().to_string())
+}
+
+/// Clears the resolver cache, falling back to `ipconfig /flushdns` when
+/// PowerShell is unavailable.
 pub async fn clear_dns_cache() -> Result<()> {
     let script = "Clear-DnsClientCache";
-    run_powershell(script).await?;
-    Ok(())
+    let Err(powershell_error) = run_powershell(script).await else {
+        return Ok(());
+    };
+
+    let mut command = Command::new("ipconfig.exe");
+    command.arg("/flushdns");
+
+    #[cfg(windows)]
+    command.creation_flags(CREATE_NO_WINDOW);
+
+    match output_with_timeout(&mut command, command_timeout(), TraceDetail::Full).await {
+        Ok(output) if output.status.success() => Ok(()),
+        _ => Err(powershell_error),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::dns::backend::mock::MockBackend;
+    use crate::dns::types::{
+        CurrentDnsState, DnsSuffixSettings, NrptRule, NrptSettings, test_interface,
+    };
+
+    fn server(address: &str, doh_template: Option<&str>) -> DnsServerEntry {
+        DnsServerEntry {
+            address: address.to_string(),
+            doh_mode: if doh_template.is_some() {
+                DohMode::On
+            } else {
+                DohMode::Off
+            },
+            doh_template: doh_template.unwrap_or_default().to_string(),
+            allow_fallback: true,
+            auto_upgrade: true,
+        }
+    }
+
+    fn ipv4_settings(primary: DnsServerEntry, secondary: DnsServerEntry) -> DnsSettings {
+        DnsSettings {
+            ipv4: DnsEntry {
+                enabled: true,
+                unchanged: false,
+                servers: vec![primary, secondary],
+            },
+            ipv6: DnsEntry::default(),
+            ..Default::default()
+        }
+    }
 
     #[tokio::test]
     #[ignore]
@@ -364,6 +664,559 @@ mod tests {
         assert!(result.expect("should succeed").contains("test"));
     }
 
+    #[tokio::test]
+    #[ignore]
+    async fn test_output_with_timeout_kills_stalled_command() {
+        let mut command = Command::new("powershell.exe");
+        command.args(["-NoProfile", "-Command", "Start-Sleep -Seconds 10"]);
+
+        let started = std::time::Instant::now();
+        let result =
+            output_with_timeout(&mut command, Duration::from_secs(1), TraceDetail::Full).await;
+        assert!(matches!(result, Err(DnsCommandError::Timeout(1))));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_clipboard_script_keeps_line_breaks() {
+        let script = clipboard_script("1.1.1.1\n1.0.0.1\r\n");
+        let encoded = script
+            .split('\'')
+            .nth(1)
+            .expect("the script should quote the text");
+        let decoded = crate::dns::dpapi::decode_base64(encoded).unwrap();
+        assert_eq!(String::from_utf8(decoded).unwrap(), "1.1.1.1\r\n1.0.0.1");
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_clipboard_round_trip() {
+        set_clipboard_text("1.1.1.1\n1.0.0.1").await.unwrap();
+        assert_eq!(
+            get_clipboard_text().await.unwrap().replace("\r\n", "\n"),
+            "1.1.1.1\n1.0.0.1"
+        );
+    }
+
+    #[test]
+    fn test_hook_output() {
+        assert_eq!(hook_output("done"), "done");
+        assert_eq!(
+            hook_output("Connected to https://dns.nextdns.io/abc123"),
+            "Connected to https://dns.nextdns.io/***"
+        );
+        let long = "あ".repeat(MAX_HOOK_OUTPUT_CHARS + 10);
+        let shown = hook_output(&long);
+        assert_eq!(shown.chars().count(), MAX_HOOK_OUTPUT_CHARS + 1);
+        assert!(shown.ends_with('…'));
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_run_hook_command() {
+        assert_eq!(
+            run_hook_command(" Write-Output 'done' ").await.unwrap(),
+            "done"
+        );
+        assert!(run_hook_command("exit 3").await.is_err());
+    }
+
+    #[test]
+    fn test_timeout_error_message() {
+        let error = DnsCommandError::Timeout(30);
+        assert_eq!(error.to_string(), "Command timed out after 30 seconds");
+    }
+
+    #[tokio::test]
+    async fn test_set_dns_with_settings_no_addresses_resets() {
+        let backend = MockBackend::new();
+        let result = set_dns_with_settings(
+            &backend,
+            &test_interface("Ethernet", 7),
+            &DnsSettings::new(),
+        )
+        .await;
+        assert!(matches!(result, Ok(None)));
+        assert_eq!(
+            backend.calls(),
+            vec!["set_automatic 7", "disable_doh_registry {GUID-7}"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_set_dns_with_settings_plain_dns() {
+        let backend = MockBackend::new();
+        let settings = ipv4_settings(server("8.8.8.8", None), server("8.8.4.4", None));
+
+        let result =
+            set_dns_with_settings(&backend, &test_interface("Ethernet", 7), &settings).await;
+        assert!(matches!(result, Ok(None)));
+        assert_eq!(
+            backend.calls(),
+            vec![
+                "set_manual 7 8.8.8.8,8.8.4.4",
+                "disable_doh_registry {GUID-7}"
+            ]
+        );
+        assert_eq!(backend.current.borrow().ipv4, vec!["8.8.8.8", "8.8.4.4"]);
+    }
+
+    #[tokio::test]
+    async fn test_set_dns_with_settings_leaves_unknown_family_alone() {
+        let backend = MockBackend::new();
+        backend.current.borrow_mut().ipv6 = vec!["fd00::53".to_string()];
+        let mut settings = ipv4_settings(server("8.8.8.8", None), DnsServerEntry::default());
+        settings.ipv6.unchanged = true;
+
+        let result =
+            set_dns_with_settings(&backend, &test_interface("Ethernet", 7), &settings).await;
+        assert!(matches!(result, Ok(None)));
+        assert_eq!(
+            backend.calls(),
+            vec!["get_current_dns 7", "set_family 7 IPv4 8.8.8.8"]
+        );
+        assert_eq!(backend.current.borrow().ipv6, vec!["fd00::53"]);
+    }
+
+    #[tokio::test]
+    async fn test_set_dns_with_settings_deduplicates_addresses() {
+        let backend = MockBackend::new();
+        let settings = ipv4_settings(server("8.8.8.8", None), server("8.8.8.8", None));
+
+        set_dns_with_settings(&backend, &test_interface("Ethernet", 7), &settings)
+            .await
+            .unwrap();
+        assert_eq!(
+            backend.calls(),
+            vec!["set_manual 7 8.8.8.8", "disable_doh_registry {GUID-7}"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_set_dns_with_settings_disable_registry_failure_warns() {
+        let backend = MockBackend {
+            fail_disable_registry: true,
+            ..Default::default()
+        };
+        let settings = ipv4_settings(server("8.8.8.8", None), DnsServerEntry::default());
+
+        let result =
+            set_dns_with_settings(&backend, &test_interface("Ethernet", 7), &settings).await;
+        let warning = result.unwrap().expect("should warn");
+        assert_eq!(
+            warning,
+            "Failed to turn off DoH on the interface: mock registry failure"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_set_dns_with_settings_doh_success() {
+        let backend = MockBackend::new();
+        let settings = ipv4_settings(
+            server("1.1.1.1", Some("https://cloudflare-dns.com/dns-query")),
+            server("1.0.0.1", None),
+        );
+
+        let result =
+            set_dns_with_settings(&backend, &test_interface("Ethernet", 7), &settings).await;
+        assert!(matches!(result, Ok(None)));
+        assert_eq!(
+            backend.calls(),
+            vec![
+                "set_manual 7 1.1.1.1,1.0.0.1",
+                "configure_doh 1.1.1.1 https://cloudflare-dns.com/dns-query",
+                "enable_doh_registry {GUID-7} 1.1.1.1",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_set_dns_with_settings_partial_doh_failure_warns() {
+        let backend = MockBackend {
+            failing_doh_addresses: vec!["1.0.0.1".to_string()],
+            ..Default::default()
+        };
+        let settings = ipv4_settings(
+            server("1.1.1.1", Some("https://cloudflare-dns.com/dns-query")),
+            server("1.0.0.1", Some("https://cloudflare-dns.com/dns-query")),
+        );
+
+        let result =
+            set_dns_with_settings(&backend, &test_interface("Ethernet", 7), &settings).await;
+        let warning = result.unwrap().expect("should warn");
+        assert!(warning.contains("IPv4 Secondary"));
+    }
+
+    #[tokio::test]
+    async fn test_set_dns_with_settings_all_doh_failed() {
+        let backend = MockBackend {
+            failing_doh_addresses: vec!["1.1.1.1".to_string()],
+            ..Default::default()
+        };
+        let settings = ipv4_settings(
+            server("1.1.1.1", Some("https://cloudflare-dns.com/dns-query")),
+            DnsServerEntry::default(),
+        );
+
+        let result =
+            set_dns_with_settings(&backend, &test_interface("Ethernet", 7), &settings).await;
+        assert!(matches!(
+            result,
+            Err(DnsCommandError::DnsAppliedButDohFailed(_))
+        ));
+        assert!(
+            !backend
+                .calls()
+                .iter()
+                .any(|c| c.starts_with("enable_doh_registry"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_set_dns_with_settings_registry_failure() {
+        let backend = MockBackend {
+            fail_registry: true,
+            ..Default::default()
+        };
+        let settings = ipv4_settings(
+            server("1.1.1.1", Some("https://cloudflare-dns.com/dns-query")),
+            DnsServerEntry::default(),
+        );
+
+        let result =
+            set_dns_with_settings(&backend, &test_interface("Ethernet", 7), &settings).await;
+        match result {
+            Err(DnsCommandError::DnsAppliedButDohFailed(msg)) => {
+                assert!(msg.contains("Registry configuration failed"));
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_set_dns_with_settings_set_failure() {
+        let backend = MockBackend {
+            fail_set: true,
+            ..Default::default()
+        };
+        let settings = ipv4_settings(server("8.8.8.8", None), DnsServerEntry::default());
+
+        let result =
+            set_dns_with_settings(&backend, &test_interface("Ethernet", 7), &settings).await;
+        assert!(matches!(result, Err(DnsCommandError::CommandFailed(_))));
+    }
+
+    fn with_suffixes(mut settings: DnsSettings) -> DnsSettings {
+        settings.connection_suffix = Some("corp.example.com".to_string());
+        settings.suffixes = DnsSuffixSettings {
+            enabled: true,
+            search_list: vec!["corp.example.com".to_string(), "example.com".to_string()],
+        };
+        settings
+    }
+
+    #[tokio::test]
+    async fn test_set_dns_with_settings_sets_suffixes() {
+        let backend = MockBackend::new();
+        let settings = with_suffixes(ipv4_settings(
+            server("8.8.8.8", None),
+            DnsServerEntry::default(),
+        ));
+
+        let result =
+            set_dns_with_settings(&backend, &test_interface("Ethernet", 7), &settings).await;
+        assert!(matches!(result, Ok(None)));
+        assert_eq!(
+            backend.calls()[2..],
+            [
+                "set_connection_suffix 7 corp.example.com",
+                "set_suffix_search_list corp.example.com,example.com",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_set_dns_with_settings_connection_suffix_only() {
+        let backend = MockBackend::new();
+        let mut settings = ipv4_settings(server("8.8.8.8", None), DnsServerEntry::default());
+        settings.connection_suffix = Some(String::new());
+
+        set_dns_with_settings(&backend, &test_interface("Ethernet", 7), &settings)
+            .await
+            .unwrap();
+        assert_eq!(backend.calls().last().unwrap(), "set_connection_suffix 7 ");
+        assert!(
+            !backend
+                .calls()
+                .iter()
+                .any(|c| c.starts_with("set_suffix_search_list"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_set_dns_with_settings_suffix_failure_warns() {
+        let backend = MockBackend {
+            fail_suffixes: true,
+            ..Default::default()
+        };
+        let settings = with_suffixes(ipv4_settings(
+            server("8.8.8.8", None),
+            DnsServerEntry::default(),
+        ));
+
+        let result =
+            set_dns_with_settings(&backend, &test_interface("Ethernet", 7), &settings).await;
+        assert_eq!(
+            result.unwrap().as_deref(),
+            Some("DNS suffix configuration failed: mock suffix failure; mock suffix failure")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_set_dns_with_settings_set_failure_skips_suffixes() {
+        let backend = MockBackend {
+            fail_set: true,
+            ..Default::default()
+        };
+        let settings = with_suffixes(ipv4_settings(
+            server("8.8.8.8", None),
+            DnsServerEntry::default(),
+        ));
+
+        let result =
+            set_dns_with_settings(&backend, &test_interface("Ethernet", 7), &settings).await;
+        assert!(matches!(result, Err(DnsCommandError::CommandFailed(_))));
+        assert!(
+            !backend
+                .calls()
+                .iter()
+                .any(|c| c.starts_with("set_connection_suffix"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_set_dns_with_settings_replaces_nrpt_rules() {
+        let backend = MockBackend::new();
+        let mut settings = ipv4_settings(server("8.8.8.8", None), DnsServerEntry::default());
+        settings.nrpt = NrptSettings {
+            enabled: true,
+            rules: vec![NrptRule {
+                namespace: "*.corp.example".to_string(),
+                name_servers: "10.0.0.53".to_string(),
+            }],
+        };
+
+        let result =
+            set_dns_with_settings(&backend, &test_interface("Ethernet", 7), &settings).await;
+        assert!(matches!(result, Ok(None)));
+        assert_eq!(
+            backend.calls().last().unwrap(),
+            "replace_nrpt_rules .corp.example=10.0.0.53"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_set_dns_with_settings_nrpt_failure_warns() {
+        let backend = MockBackend {
+            fail_nrpt: true,
+            ..Default::default()
+        };
+        let mut settings = ipv4_settings(server("8.8.8.8", None), DnsServerEntry::default());
+        settings.nrpt.enabled = true;
+
+        let result =
+            set_dns_with_settings(&backend, &test_interface("Ethernet", 7), &settings).await;
+        assert_eq!(
+            result.unwrap().as_deref(),
+            Some("NRPT configuration failed: mock NRPT failure")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_set_dns_with_settings_nrpt_disabled_leaves_rules() {
+        let backend = MockBackend::new();
+        let settings = ipv4_settings(server("8.8.8.8", None), DnsServerEntry::default());
+
+        set_dns_with_settings(&backend, &test_interface("Ethernet", 7), &settings)
+            .await
+            .unwrap();
+        assert!(!backend.calls().iter().any(|c| c.contains("nrpt")));
+    }
+
+    #[tokio::test]
+    async fn test_apply_dns_settings_reports_each_step() {
+        let backend = MockBackend {
+            fail_suffixes: true,
+            failing_doh_addresses: vec!["1.0.0.1".to_string()],
+            ..Default::default()
+        };
+        let mut settings = with_suffixes(ipv4_settings(
+            server("1.1.1.1", Some("https://cloudflare-dns.com/dns-query")),
+            server("1.0.0.1", Some("https://cloudflare-dns.com/dns-query")),
+        ));
+        settings.nrpt.enabled = true;
+
+        let report = apply_dns_settings(&backend, &test_interface("Ethernet", 7), &settings).await;
+        let steps = report.steps();
+        let names: Vec<&str> = steps.iter().map(|s| s.step).collect();
+        assert_eq!(names, ["servers", "doh", "suffixes", "nrpt"]);
+        assert!(steps[1].ok);
+        assert!(
+            steps[1]
+                .warning
+                .as_deref()
+                .unwrap()
+                .starts_with("Some DoH configurations failed: IPv4 Secondary")
+        );
+        assert!(!steps[2].ok);
+        assert_eq!(
+            steps[2].error.as_deref(),
+            Some("mock suffix failure; mock suffix failure")
+        );
+        assert!(steps[3].ok);
+
+        let json = serde_json::to_string(&steps[0]).unwrap();
+        assert_eq!(json, r#"{"step":"servers","ok":true}"#);
+    }
+
+    #[tokio::test]
+    async fn test_apply_dns_settings_stops_when_servers_fail() {
+        let backend = MockBackend {
+            fail_set: true,
+            ..Default::default()
+        };
+        let settings = with_suffixes(ipv4_settings(
+            server("8.8.8.8", None),
+            DnsServerEntry::default(),
+        ));
+
+        let report = apply_dns_settings(&backend, &test_interface("Ethernet", 7), &settings).await;
+        let steps = report.steps();
+        assert_eq!(steps.len(), 1);
+        assert_eq!(steps[0].step, "servers");
+        assert!(!steps[0].ok);
+        assert!(matches!(
+            report.into_result(),
+            Err(DnsCommandError::CommandFailed(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_new_doh_servers_skips_existing_mappings() {
+        let backend = MockBackend::new();
+        backend.doh_servers.borrow_mut().push("1.1.1.1".to_string());
+        let settings = ipv4_settings(
+            server("1.1.1.1", Some("https://cloudflare-dns.com/dns-query")),
+            server("1.0.0.1", Some("https://cloudflare-dns.com/dns-query")),
+        );
+
+        let created = new_doh_servers(&backend, &settings).await;
+        assert_eq!(created, vec!["1.0.0.1"]);
+    }
+
+    #[tokio::test]
+    async fn test_new_doh_servers_without_doh_does_not_query() {
+        let backend = MockBackend::new();
+        let settings = ipv4_settings(server("8.8.8.8", None), DnsServerEntry::default());
+
+        assert!(new_doh_servers(&backend, &settings).await.is_empty());
+        assert!(backend.calls().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_remove_unused_doh_servers() {
+        let backend = MockBackend::new();
+        backend
+            .doh_servers
+            .borrow_mut()
+            .extend(["1.1.1.1".to_string(), "9.9.9.9".to_string()]);
+
+        let mut config = AppConfig::new();
+        let mut profile = crate::dns::types::DnsProfile::new("Cloudflare".to_string());
+        profile.settings = ipv4_settings(
+            server("1.1.1.1", Some("https://cloudflare-dns.com/dns-query")),
+            DnsServerEntry::default(),
+        );
+        config.add_profile(profile);
+        config.record_doh_servers(&["1.1.1.1".to_string(), "9.9.9.9".to_string()]);
+
+        let removed = remove_unused_doh_servers(&backend, &mut config)
+            .await
+            .unwrap();
+        assert_eq!(removed, vec!["9.9.9.9"]);
+        assert_eq!(config.managed_doh_servers, vec!["1.1.1.1"]);
+        assert_eq!(*backend.doh_servers.borrow(), vec!["1.1.1.1"]);
+    }
+
+    #[tokio::test]
+    async fn test_remove_unused_doh_servers_keeps_failed_entry() {
+        let backend = MockBackend {
+            failing_doh_addresses: vec!["9.9.9.9".to_string()],
+            ..Default::default()
+        };
+        let mut config = AppConfig::new();
+        config.record_doh_servers(&["9.9.9.9".to_string()]);
+
+        let result = remove_unused_doh_servers(&backend, &mut config).await;
+        assert!(result.is_err());
+        assert_eq!(config.managed_doh_servers, vec!["9.9.9.9"]);
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_dns_settings_keeps_static_servers_and_doh() {
+        let backend = MockBackend::new();
+        *backend.current.borrow_mut() = CurrentDnsState {
+            ipv4: vec!["1.1.1.1".to_string(), "192.168.1.1".to_string()],
+            ipv6: vec!["fe80::1".to_string()],
+            doh_servers: vec!["1.1.1.1".to_string()],
+            ipv4_origin: DnsOrigin::Static,
+            ipv6_origin: DnsOrigin::Dhcp,
+        };
+        *backend.doh_servers.borrow_mut() = vec!["1.1.1.1".to_string(), "9.9.9.9".to_string()];
+
+        let snapshot = snapshot_dns_settings(&backend, &test_interface("Ethernet", 7))
+            .await
+            .unwrap();
+
+        assert!(snapshot.ipv4.enabled);
+        assert_eq!(
+            snapshot.ipv4.servers,
+            vec![
+                DnsServerEntry {
+                    address: "1.1.1.1".to_string(),
+                    doh_mode: DohMode::On,
+                    doh_template: "https://1.1.1.1/dns-query".to_string(),
+                    allow_fallback: false,
+                    auto_upgrade: true,
+                },
+                server("192.168.1.1", None),
+            ]
+        );
+        assert_eq!(snapshot.ipv6, DnsEntry::default());
+        assert_eq!(snapshot.connection_suffix, None);
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_dns_settings_of_dhcp_interface_resets() {
+        let backend = MockBackend::new();
+        *backend.current.borrow_mut() = CurrentDnsState {
+            ipv4: vec!["192.168.1.1".to_string()],
+            ipv4_origin: DnsOrigin::Dhcp,
+            ..Default::default()
+        };
+
+        let snapshot = snapshot_dns_settings(&backend, &test_interface("Ethernet", 7))
+            .await
+            .unwrap();
+        assert_eq!(snapshot, DnsSettings::new());
+        assert!(!backend.calls().contains(&"get_doh_templates".to_string()));
+
+        set_dns_with_settings(&backend, &test_interface("Ethernet", 7), &snapshot)
+            .await
+            .unwrap();
+        assert!(backend.calls().contains(&"set_automatic 7".to_string()));
+    }
+
     #[test]
     fn test_escape_powershell_string() {
         assert_eq!(escape_powershell_string("test"), "test");
@@ -1,6 +1,13 @@
-use crate::dns::types::AppConfig;
+use crate::dns::dpapi::{self, DpapiError, decode_base64, encode_base64};
+use crate::dns::types::{AppConfig, DnsProfile, DnsSettings};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::fs;
+use std::io::Read;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -9,46 +16,812 @@ pub enum ConfigError {
     Io(#[from] std::io::Error),
     #[error("JSON error: {0}")]
     Json(#[from] serde_json::Error),
+    #[error("TOML error: {0}")]
+    TomlParse(#[from] toml::de::Error),
+    #[error("TOML error: {0}")]
+    TomlWrite(#[from] toml::ser::Error),
     #[error("Config directory not found")]
     ConfigDirNotFound,
+    #[error("Encrypted profiles: {0}")]
+    Encryption(#[from] DpapiError),
+    #[error("Encrypted profiles are damaged")]
+    DamagedProfiles,
+    #[error("{0} profiles could not be read")]
+    SkippedProfiles(usize),
+    #[error("The config file could not be loaded, so it is not overwritten")]
+    NotLoaded,
 }
 
 pub type Result<T> = std::result::Result<T, ConfigError>;
 
-pub fn get_config_path() -> Result<PathBuf> {
+/// File format of the config, told apart by the file's extension.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ConfigFormat {
+    #[default]
+    Jsonc,
+    Toml,
+}
+
+impl ConfigFormat {
+    pub const ALL: [ConfigFormat; 2] = [ConfigFormat::Jsonc, ConfigFormat::Toml];
+
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension() {
+            Some(ext) if ext.eq_ignore_ascii_case("toml") => ConfigFormat::Toml,
+            _ => ConfigFormat::Jsonc,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ConfigFormat::Jsonc => "jsonc",
+            ConfigFormat::Toml => "toml",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|f| f.extension() == value)
+    }
+}
+
+/// Config file in `dir`: `config.toml` if there is one, else `config.jsonc`.
+fn config_path_in(dir: &Path) -> PathBuf {
+    let toml = dir.join("config.toml");
+    if toml.exists() {
+        toml
+    } else {
+        dir.join("config.jsonc")
+    }
+}
+
+pub(crate) const CONFIG_ARG: &str = "--config";
+
+/// File in the default config directory that names the directory the
+/// config has been moved to, such as a OneDrive folder.
+const REDIRECT_FILE: &str = "redirect.txt";
+
+static CONFIG_DIR_ARGUMENT: OnceLock<PathBuf> = OnceLock::new();
+
+/// Directory given with `--config <dir>`, if any.
+pub fn config_dir_argument(args: impl IntoIterator<Item = String>) -> Option<PathBuf> {
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == CONFIG_ARG {
+            return args.next().map(PathBuf::from);
+        }
+    }
+    None
+}
+
+/// Uses the config in `dir` for the rest of the run, whatever the redirect
+/// stub says.
+pub fn set_config_dir_argument(dir: PathBuf) {
+    let _ = CONFIG_DIR_ARGUMENT.set(dir);
+}
+
+/// Arguments that make another windns process use the same config.
+pub fn config_dir_args() -> Vec<String> {
+    CONFIG_DIR_ARGUMENT
+        .get()
+        .map(|dir| vec![CONFIG_ARG.to_string(), dir.display().to_string()])
+        .unwrap_or_default()
+}
+
+pub fn default_config_dir() -> Result<PathBuf> {
     let config_dir = dirs::config_dir()
         .or_else(dirs::data_local_dir)
         .ok_or(ConfigError::ConfigDirNotFound)?;
 
-    let app_config_dir = config_dir.join("windns");
-    Ok(app_config_dir.join("config.jsonc"))
+    Ok(config_dir.join("windns"))
+}
+
+/// Where the config directory is, and what chose it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConfigLocation {
+    Default(PathBuf),
+    /// Named by the redirect stub in the default directory.
+    Redirected(PathBuf),
+    /// Given with `--config`.
+    Argument(PathBuf),
+}
+
+impl ConfigLocation {
+    pub fn dir(&self) -> &Path {
+        match self {
+            ConfigLocation::Default(dir)
+            | ConfigLocation::Redirected(dir)
+            | ConfigLocation::Argument(dir) => dir,
+        }
+    }
+}
+
+/// Directory named by the redirect stub in `default_dir`, if there is one.
+fn redirected_dir(default_dir: &Path) -> Option<PathBuf> {
+    let target = fs::read_to_string(default_dir.join(REDIRECT_FILE)).ok()?;
+    let target = target.trim();
+    (!target.is_empty()).then(|| PathBuf::from(target))
+}
+
+pub fn config_location() -> Result<ConfigLocation> {
+    if let Some(dir) = CONFIG_DIR_ARGUMENT.get() {
+        return Ok(ConfigLocation::Argument(dir.clone()));
+    }
+    let default_dir = default_config_dir()?;
+    Ok(match redirected_dir(&default_dir) {
+        Some(dir) => ConfigLocation::Redirected(dir),
+        None => ConfigLocation::Default(default_dir),
+    })
+}
+
+pub fn get_config_path() -> Result<PathBuf> {
+    Ok(config_path_in(config_location()?.dir()))
+}
+
+/// Moves the config at `config_path` into `new_dir` and points the redirect
+/// stub in `default_dir` there, or removes the stub when `new_dir` is the
+/// default. The config left behind is backed up first. If `new_dir`
+/// already has a config, as when a second computer joins a synced folder,
+/// that one is kept and returned for use instead.
+pub fn move_config_at(
+    default_dir: &Path,
+    config_path: &Path,
+    config: &AppConfig,
+    new_dir: &Path,
+    now_secs: u64,
+) -> Result<Option<AppConfig>> {
+    // The file would be removed after writing defaults to `new_dir`.
+    ensure_loaded(config_path)?;
+    let same_dir = config_path.parent() == Some(new_dir);
+    let existing = config_path_in(new_dir);
+    let found = if same_dir {
+        None
+    } else if existing.exists() {
+        Some(load_config_from_path(&existing)?)
+    } else {
+        let file_name = config_path.file_name().unwrap_or("config.jsonc".as_ref());
+        save_config_to_path(config, &new_dir.join(file_name))?;
+        None
+    };
+
+    let redirect = default_dir.join(REDIRECT_FILE);
+    if new_dir == default_dir {
+        if redirect.exists() {
+            fs::remove_file(&redirect)?;
+        }
+    } else {
+        fs::create_dir_all(default_dir)?;
+        fs::write(&redirect, new_dir.display().to_string())?;
+    }
+
+    if !same_dir && config_path.exists() {
+        backup_config_at(config_path, now_secs, MAX_CONFIG_BACKUPS)?;
+        fs::remove_file(config_path)?;
+    }
+    Ok(found)
+}
+
+/// Key that holds the profiles, encrypted and base64-encoded, in place of
+/// `profiles` when `encrypt_profiles` is on.
+const ENCRYPTED_PROFILES_KEY: &str = "encrypted_profiles";
+
+type Cipher = fn(&[u8]) -> dpapi::Result<Vec<u8>>;
+
+/// Replaces `profiles` in a serialized config with their encrypted form.
+fn encrypt_profiles(value: &mut serde_json::Value, protect: Cipher) -> Result<()> {
+    let Some(map) = value.as_object_mut() else {
+        return Ok(());
+    };
+    let profiles = map.remove("profiles").unwrap_or_default();
+    let encrypted = protect(&serde_json::to_vec(&profiles)?)?;
+    map.insert(
+        ENCRYPTED_PROFILES_KEY.to_string(),
+        encode_base64(&encrypted).into(),
+    );
+    Ok(())
+}
+
+/// Puts the profiles of a config saved by `encrypt_profiles` back in place.
+fn decrypt_profiles(value: &mut serde_json::Value, unprotect: Cipher) -> Result<()> {
+    let Some(encrypted) = value
+        .as_object_mut()
+        .and_then(|map| map.remove(ENCRYPTED_PROFILES_KEY))
+    else {
+        return Ok(());
+    };
+    let data = encrypted
+        .as_str()
+        .and_then(decode_base64)
+        .ok_or(ConfigError::DamagedProfiles)?;
+    let profiles: serde_json::Value = serde_json::from_slice(&unprotect(&data)?)?;
+    value["profiles"] = profiles;
+    Ok(())
+}
+
+/// Allows the hook commands of `settings` to run. The approval is the
+/// commands encrypted with DPAPI, which only this user on this computer
+/// can decrypt, so one that comes with a shared config does not verify.
+pub fn approve_hooks(settings: &mut DnsSettings) -> Result<()> {
+    approve_hooks_with(settings, dpapi::protect)
+}
+
+fn approve_hooks_with(settings: &mut DnsSettings, protect: Cipher) -> Result<()> {
+    settings.hooks_approval = Some(encode_base64(&protect(&settings.hook_text())?));
+    Ok(())
+}
+
+/// Whether the hook commands of `settings` are the ones the user allowed
+/// with `approve_hooks`.
+pub fn hooks_approved(settings: &DnsSettings) -> bool {
+    hooks_approved_with(settings, dpapi::unprotect)
+}
+
+fn hooks_approved_with(settings: &DnsSettings, unprotect: Cipher) -> bool {
+    settings
+        .hooks_approval
+        .as_deref()
+        .and_then(decode_base64)
+        .and_then(|data| unprotect(&data).ok())
+        .is_some_and(|commands| commands == settings.hook_text())
+}
+
+/// A profile in the config file that could not be read and was left out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkippedProfile {
+    /// Position of the profile in the file, starting at 1.
+    pub position: usize,
+    pub name: Option<String>,
+    /// Line of the problem, or of the profile's `[[profiles]]` header in
+    /// TOML. `None` for encrypted profiles.
+    pub line: Option<usize>,
+    pub reason: String,
+}
+
+/// A loaded config and the profiles of its file that were left out.
+#[derive(Debug)]
+pub struct LoadedConfig {
+    pub config: AppConfig,
+    pub skipped: Vec<SkippedProfile>,
+}
+
+fn line_at(text: &str, offset: usize) -> usize {
+    text.bytes().take(offset).filter(|&b| b == b'\n').count() + 1
+}
+
+/// Byte ranges of the elements of the top-level `profiles` array in JSON
+/// text that is known to parse.
+fn json_profile_spans(text: &str) -> Vec<Range<usize>> {
+    let mut spans = Vec::new();
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut string_start = 0;
+    let mut last_string = "";
+    let mut in_profiles = false;
+    let mut element_start = None;
+    for (i, b) in text.bytes().enumerate() {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if b == b'\\' {
+                escaped = true;
+            } else if b == b'"' {
+                in_string = false;
+                last_string = &text[string_start + 1..i];
+            }
+            continue;
+        }
+        if in_profiles
+            && depth == 2
+            && element_start.is_none()
+            && !matches!(b, b',' | b']')
+            && !b.is_ascii_whitespace()
+        {
+            element_start = Some(i);
+        }
+        match b {
+            b'"' => {
+                in_string = true;
+                string_start = i;
+            }
+            b'{' | b'[' => {
+                if b == b'[' && depth == 1 && last_string == "profiles" {
+                    in_profiles = true;
+                }
+                depth += 1;
+            }
+            b'}' | b']' => {
+                depth -= 1;
+                if in_profiles && depth == 1 {
+                    spans.extend(element_start.take().map(|start| start..i));
+                    in_profiles = false;
+                }
+            }
+            b',' if in_profiles && depth == 2 => {
+                spans.extend(element_start.take().map(|start| start..i));
+            }
+            _ => {}
+        }
+    }
+    spans
+}
+
+/// Line of each `[[profiles]]` header in TOML text.
+fn toml_profile_lines(text: &str) -> Vec<usize> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| line.trim_start().starts_with("[[profiles]]"))
+        .map(|(i, _)| i + 1)
+        .collect()
+}
+
+/// Line in `content` and message of the error in the profile at `span` of
+/// `stripped`, which is `content` with its comments blanked out.
+fn json_profile_error(
+    content: &str,
+    stripped: &str,
+    span: Range<usize>,
+) -> Option<(usize, String)> {
+    let slice = &stripped[span.clone()];
+    let e = serde_json::from_str::<DnsProfile>(slice).err()?;
+    let offset: usize = slice
+        .split_inclusive('\n')
+        .take(e.line().saturating_sub(1))
+        .map(str::len)
+        .sum::<usize>()
+        + e.column().saturating_sub(1);
+    let message = e.to_string();
+    let position = format!(" at line {} column {}", e.line(), e.column());
+    let reason = message.strip_suffix(&position).unwrap_or(&message);
+    Some((line_at(content, span.start + offset), reason.to_string()))
+}
+
+/// Config files in use that failed to load. The config in memory then only
+/// has defaults, so saving it would replace everything in the file; saves
+/// to these files are refused until a backup is restored over them.
+static UNLOADED_CONFIGS: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+fn unloaded_configs() -> std::sync::MutexGuard<'static, Vec<PathBuf>> {
+    UNLOADED_CONFIGS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn ensure_loaded(path: &Path) -> Result<()> {
+    if unloaded_configs().iter().any(|p| p == path) {
+        return Err(ConfigError::NotLoaded);
+    }
+    Ok(())
+}
+
+/// Loads the config in use at `path`, refusing later saves to it if it
+/// cannot be loaded.
+fn load_config_in_use(path: &Path, unprotect: Cipher) -> Result<LoadedConfig> {
+    let loaded = load_config_with(path, unprotect);
+    if loaded.is_err() {
+        unloaded_configs().push(path.to_path_buf());
+    }
+    loaded
 }
 
 pub fn load_config_from_path(path: &Path) -> Result<AppConfig> {
+    Ok(load_config_with(path, dpapi::unprotect)?.config)
+}
+
+/// Loads the config at `path`. Profiles are read one by one, so one that
+/// cannot be read is left out and reported instead of failing the load.
+fn load_config_with(path: &Path, unprotect: Cipher) -> Result<LoadedConfig> {
     if !path.exists() {
-        return Ok(AppConfig::new());
+        return Ok(LoadedConfig {
+            config: AppConfig::new(),
+            skipped: Vec::new(),
+        });
     }
 
     let content = fs::read_to_string(path)?;
-    let stripped = json_comments::StripComments::new(content.as_bytes());
-    let config: AppConfig = serde_json::from_reader(stripped)?;
+    let format = ConfigFormat::from_path(path);
+    let mut stripped = String::new();
+    let mut value: serde_json::Value = match format {
+        ConfigFormat::Jsonc => {
+            json_comments::StripComments::new(content.as_bytes()).read_to_string(&mut stripped)?;
+            serde_json::from_str(&stripped)?
+        }
+        // Going through JSON lets maps with number keys, such as the
+        // hotkeys, read their TOML string keys.
+        ConfigFormat::Toml => toml::from_str(&content)?,
+    };
+    decrypt_profiles(&mut value, unprotect)?;
 
-    Ok(config)
+    let profiles = value.as_object_mut().and_then(|map| map.remove("profiles"));
+    let profiles = match profiles {
+        Some(serde_json::Value::Array(profiles)) => profiles,
+        // Not a list, so let reading the whole config report it.
+        Some(profiles) => {
+            value["profiles"] = profiles;
+            Vec::new()
+        }
+        None => Vec::new(),
+    };
+    let mut config: AppConfig = serde_json::from_value(value)?;
+
+    let spans = match format {
+        ConfigFormat::Jsonc => json_profile_spans(&stripped),
+        ConfigFormat::Toml => Vec::new(),
+    };
+    let toml_lines = match format {
+        ConfigFormat::Jsonc => Vec::new(),
+        ConfigFormat::Toml => toml_profile_lines(&content),
+    };
+    let count = profiles.len();
+    let mut skipped = Vec::new();
+    for (index, profile) in profiles.into_iter().enumerate() {
+        let name = profile
+            .get("name")
+            .and_then(serde_json::Value::as_str)
+            .map(str::to_string);
+        let error = match DnsProfile::deserialize(&profile) {
+            Ok(parsed) => {
+                config.profiles.push(parsed);
+                continue;
+            }
+            Err(e) => e,
+        };
+        config.unreadable_profiles.push(profile);
+        // Encrypted profiles are not in the text, so the counts differ.
+        let located = if spans.len() == count {
+            json_profile_error(&content, &stripped, spans[index].clone())
+        } else {
+            None
+        };
+        let (line, reason) = match located {
+            Some((line, reason)) => (Some(line), reason),
+            None if toml_lines.len() == count => (Some(toml_lines[index]), error.to_string()),
+            None => (None, error.to_string()),
+        };
+        skipped.push(SkippedProfile {
+            position: index + 1,
+            name,
+            line,
+            reason,
+        });
+    }
+
+    Ok(LoadedConfig { config, skipped })
+}
+
+/// Drops nulls, which TOML cannot store. Only `interface_profiles` has
+/// them, so an interface last set to Automatic is not remembered in TOML.
+fn without_nulls(value: serde_json::Value) -> serde_json::Value {
+    use serde_json::Value;
+
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .filter(|(_, v)| !v.is_null())
+                .map(|(k, v)| (k, without_nulls(v)))
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(
+            items
+                .into_iter()
+                .filter(|v| !v.is_null())
+                .map(without_nulls)
+                .collect(),
+        ),
+        value => value,
+    }
 }
 
 pub fn save_config_to_path(config: &AppConfig, path: &Path) -> Result<()> {
+    save_config_with(config, path, dpapi::protect)
+}
+
+/// Reads the config at `path`, lets `update` change it and writes it back,
+/// so that saving a few fields keeps whatever else is in the file. Nothing
+/// is written if the file cannot be read or some of its profiles cannot,
+/// since they would be lost.
+fn update_config_with(
+    path: &Path,
+    unprotect: Cipher,
+    protect: Cipher,
+    update: impl FnOnce(&mut AppConfig),
+) -> Result<()> {
+    let mut loaded = load_config_with(path, unprotect)?;
+    if !loaded.skipped.is_empty() {
+        return Err(ConfigError::SkippedProfiles(loaded.skipped.len()));
+    }
+    update(&mut loaded.config);
+    save_config_with(&loaded.config, path, protect)
+}
+
+fn save_config_with(config: &AppConfig, path: &Path, protect: Cipher) -> Result<()> {
+    ensure_loaded(path)?;
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent)?;
     }
 
-    let json = serde_json::to_string_pretty(config)?;
+    let format = ConfigFormat::from_path(path);
+    let encrypt = config.settings.encrypts_profiles();
+    let content =
+        if format == ConfigFormat::Jsonc && !encrypt && config.unreadable_profiles.is_empty() {
+            serde_json::to_string_pretty(config)?
+        } else {
+            let mut value = serde_json::to_value(config)?;
+            if let Some(profiles) = value
+                .get_mut("profiles")
+                .and_then(serde_json::Value::as_array_mut)
+            {
+                profiles.extend(config.unreadable_profiles.iter().cloned());
+            }
+            if encrypt {
+                encrypt_profiles(&mut value, protect)?;
+            }
+            match format {
+                ConfigFormat::Jsonc => serde_json::to_string_pretty(&value)?,
+                ConfigFormat::Toml => toml::to_string_pretty(&without_nulls(value))?,
+            }
+        };
+    fs::write(path, content)?;
+    Ok(())
+}
+
+/// Saves `config` in `format` next to the config file at `config_path` and
+/// removes that file if it had the other format. Returns the new path.
+pub fn set_config_format_at(
+    config: &AppConfig,
+    config_path: &Path,
+    format: ConfigFormat,
+) -> Result<PathBuf> {
+    ensure_loaded(config_path)?;
+    let new_path = config_path.with_file_name(format!("config.{}", format.extension()));
+    save_config_to_path(config, &new_path)?;
+    if new_path != config_path && config_path.exists() {
+        fs::remove_file(config_path)?;
+    }
+    Ok(new_path)
+}
+
+/// Format of the config file in use.
+pub fn config_format() -> ConfigFormat {
+    get_config_path().map_or(ConfigFormat::default(), |path| {
+        ConfigFormat::from_path(&path)
+    })
+}
+
+pub fn set_config_format(config: &AppConfig, format: ConfigFormat) -> Result<PathBuf> {
+    set_config_format_at(config, &get_config_path()?, format)
+}
+
+/// Layout of an exported profile file. It matches the `profiles` key of the
+/// config file, so a config file can be imported as well.
+#[derive(Serialize, Deserialize)]
+struct ProfileExport {
+    #[serde(default)]
+    profiles: Vec<DnsProfile>,
+}
+
+/// Writes every profile of `config` to `path` as JSON.
+pub fn export_profiles(config: &AppConfig, path: &Path) -> Result<()> {
+    let export = ProfileExport {
+        profiles: config.profiles.clone(),
+    };
+    let json = serde_json::to_string_pretty(&export)?;
     fs::write(path, json)?;
     Ok(())
 }
 
+/// Adds the profiles exported to `path` to `config`. Profiles whose ID is
+/// already in use get a new one, and hook commands are dropped. Returns how
+/// many profiles were added.
+pub fn import_profiles(config: &mut AppConfig, path: &Path) -> Result<usize> {
+    let content = fs::read_to_string(path)?;
+    let stripped = json_comments::StripComments::new(content.as_bytes());
+    let export: ProfileExport = serde_json::from_reader(stripped)?;
+
+    let mut ids: HashSet<String> = config.profiles.iter().map(|p| p.id.clone()).collect();
+    let count = export.profiles.len();
+    for mut profile in export.profiles {
+        profile.settings.remove_hooks();
+        if !ids.insert(profile.id.clone()) {
+            profile.id = DnsProfile::new(String::new()).id;
+            ids.insert(profile.id.clone());
+        }
+        config.add_profile(profile);
+    }
+    Ok(count)
+}
+
+/// Number of config backups kept; the oldest ones are deleted.
+pub const MAX_CONFIG_BACKUPS: usize = 10;
+
+/// A copy of the config file in the `backups` directory next to it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigBackup {
+    pub path: PathBuf,
+    /// When the backup was made, in UTC.
+    pub created: String,
+    /// Number of profiles in the backup, or `None` if it cannot be read.
+    pub profile_count: Option<usize>,
+}
+
+fn backup_dir(config_path: &Path) -> PathBuf {
+    config_path
+        .parent()
+        .map_or_else(|| PathBuf::from("backups"), |dir| dir.join("backups"))
+}
+
+/// Splits seconds since the Unix epoch into a UTC date and time.
+pub(crate) fn utc_date_time(secs: u64) -> (i64, u32, u32, u32, u32, u32) {
+    let days = (secs / 86_400) as i64;
+    let time = secs % 86_400;
+    // Howard Hinnant's days-to-civil algorithm.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (
+        year,
+        month,
+        day,
+        (time / 3_600) as u32,
+        (time % 3_600 / 60) as u32,
+        (time % 60) as u32,
+    )
+}
+
+/// File name of a backup made at `secs` since the Unix epoch. Names sort
+/// in the order the backups were made.
+fn backup_file_name(secs: u64, format: ConfigFormat) -> String {
+    let (year, month, day, hour, minute, second) = utc_date_time(secs);
+    format!(
+        "config-{:04}{:02}{:02}-{:02}{:02}{:02}.{}",
+        year,
+        month,
+        day,
+        hour,
+        minute,
+        second,
+        format.extension()
+    )
+}
+
+/// Creation time shown for a backup named by `backup_file_name`.
+fn backup_created(file_name: &str) -> Option<String> {
+    let (stamp, extension) = file_name.strip_prefix("config-")?.rsplit_once('.')?;
+    ConfigFormat::parse(extension)?;
+    let (date, time) = stamp.split_once('-')?;
+    let digits = |s: &str, len: usize| s.len() == len && s.bytes().all(|b| b.is_ascii_digit());
+    if !digits(date, 8) || !digits(time, 6) {
+        return None;
+    }
+    Some(format!(
+        "{}-{}-{} {}:{}:{} UTC",
+        &date[..4],
+        &date[4..6],
+        &date[6..],
+        &time[..2],
+        &time[2..4],
+        &time[4..]
+    ))
+}
+
+/// Backups of the config at `config_path`, newest first.
+pub fn list_config_backups_at(config_path: &Path) -> Result<Vec<ConfigBackup>> {
+    let dir = backup_dir(config_path);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups = Vec::new();
+    for entry in fs::read_dir(&dir)? {
+        let path = entry?.path();
+        let Some(created) = path
+            .file_name()
+            .and_then(|name| backup_created(&name.to_string_lossy()))
+        else {
+            continue;
+        };
+        let profile_count = load_config_from_path(&path)
+            .ok()
+            .map(|config| config.profiles.len());
+        backups.push(ConfigBackup {
+            path,
+            created,
+            profile_count,
+        });
+    }
+    backups.sort_by(|a, b| b.path.cmp(&a.path));
+    Ok(backups)
+}
+
+/// Copies the config at `config_path` into the backups directory, unless
+/// there is no config yet or the newest backup is the same, and deletes
+/// backups beyond the newest `keep`. Returns the new backup, if any.
+pub fn backup_config_at(config_path: &Path, now_secs: u64, keep: usize) -> Result<Option<PathBuf>> {
+    if !config_path.exists() {
+        return Ok(None);
+    }
+
+    let content = fs::read(config_path)?;
+    let backups = list_config_backups_at(config_path)?;
+    if backups
+        .first()
+        .is_some_and(|newest| fs::read(&newest.path).is_ok_and(|c| c == content))
+    {
+        return Ok(None);
+    }
+
+    let dir = backup_dir(config_path);
+    fs::create_dir_all(&dir)?;
+    let path = dir.join(backup_file_name(
+        now_secs,
+        ConfigFormat::from_path(config_path),
+    ));
+    fs::write(&path, content)?;
+
+    for old in list_config_backups_at(config_path)?.into_iter().skip(keep) {
+        fs::remove_file(old.path)?;
+    }
+    Ok(Some(path))
+}
+
+/// Replaces the config at `config_path` with `backup`, backing up the
+/// current config first so the restore can be undone.
+pub fn restore_config_backup_at(
+    config_path: &Path,
+    backup: &Path,
+    now_secs: u64,
+) -> Result<AppConfig> {
+    let config = load_config_from_path(backup)?;
+    backup_config_at(config_path, now_secs, MAX_CONFIG_BACKUPS)?;
+    unloaded_configs().retain(|p| p != config_path);
+    save_config_to_path(&config, config_path)?;
+    Ok(config)
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+pub fn backup_config() -> Result<Option<PathBuf>> {
+    backup_config_at(&get_config_path()?, now_secs(), MAX_CONFIG_BACKUPS)
+}
+
+pub fn list_config_backups() -> Result<Vec<ConfigBackup>> {
+    list_config_backups_at(&get_config_path()?)
+}
+
+pub fn restore_config_backup(backup: &Path) -> Result<AppConfig> {
+    restore_config_backup_at(&get_config_path()?, backup, now_secs())
+}
+
+/// Moves the config into `new_dir`, or back to the default directory for
+/// `None`. Returns the config found there, if any, which replaces this one.
+pub fn move_config(config: &AppConfig, new_dir: Option<&Path>) -> Result<Option<AppConfig>> {
+    let default_dir = default_config_dir()?;
+    move_config_at(
+        &default_dir,
+        &get_config_path()?,
+        config,
+        new_dir.unwrap_or(&default_dir),
+        now_secs(),
+    )
+}
+
 pub fn load_config() -> Result<AppConfig> {
-    let config_path = get_config_path()?;
-    load_config_from_path(&config_path)
+    Ok(load_config_with_skipped()?.config)
+}
+
+/// Loads the config, also returning the profiles that could not be read.
+pub fn load_config_with_skipped() -> Result<LoadedConfig> {
+    load_config_in_use(&get_config_path()?, dpapi::unprotect)
 }
 
 pub fn save_config(config: &AppConfig) -> Result<()> {
@@ -56,10 +829,22 @@ pub fn save_config(config: &AppConfig) -> Result<()> {
     save_config_to_path(config, &config_path)
 }
 
+/// Changes the saved config with `update`, leaving the rest of the file as
+/// it is; see `update_config_with`.
+pub fn update_config(update: impl FnOnce(&mut AppConfig)) -> Result<()> {
+    update_config_with(
+        &get_config_path()?,
+        dpapi::unprotect,
+        dpapi::protect,
+        update,
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::dns::types::{DnsProfile, DnsServerEntry};
+    use crate::dns::elevation::RelaunchSelection;
+    use crate::dns::types::{DnsMode, DnsProfile, DnsServerEntry};
     use tempfile::TempDir;
 
     #[test]
@@ -158,7 +943,10 @@ mod tests {
         assert_eq!(loaded.profiles[0].name, "Test Profile");
         assert_eq!(loaded.profiles[0].id, "test-id");
         assert!(loaded.profiles[0].settings.ipv4.enabled);
-        assert_eq!(loaded.profiles[0].settings.ipv4.primary.address, "8.8.8.8");
+        assert_eq!(
+            loaded.profiles[0].settings.ipv4.servers[0].address,
+            "8.8.8.8"
+        );
     }
 
     #[test]
@@ -174,6 +962,111 @@ mod tests {
         assert!(matches!(result.unwrap_err(), ConfigError::Json(_)));
     }
 
+    #[test]
+    fn test_load_config_skips_malformed_profiles() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.jsonc");
+        let valid = |name: &str| serde_json::to_string(&DnsProfile::new(name.to_string())).unwrap();
+        let content = format!(
+            r#"{{
+  /* Profiles, "with" [brackets]
+     in a comment */
+  "profiles": [
+    {},
+    {{
+      "id": "broken",
+      "name": "Broken",
+      "settings": 42
+    }},
+    {{ "id": "nameless", "tags": ["a]b"] }},
+    {}
+  ],
+  "theme": "Dark"
+}}"#,
+            valid("First"),
+            valid("Last")
+        );
+        fs::write(&config_path, content).unwrap();
+
+        let loaded = load_config_with(&config_path, flip_bits).unwrap();
+        let names: Vec<&str> = loaded
+            .config
+            .profiles
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        assert_eq!(names, ["First", "Last"]);
+        assert_eq!(loaded.skipped.len(), 2);
+        assert_eq!(loaded.skipped[0].position, 2);
+        assert_eq!(loaded.skipped[0].name.as_deref(), Some("Broken"));
+        assert_eq!(loaded.skipped[0].line, Some(9));
+        assert!(
+            loaded.skipped[0]
+                .reason
+                .starts_with("invalid type: integer `42`")
+        );
+        assert_eq!(loaded.skipped[1].position, 3);
+        assert_eq!(loaded.skipped[1].name, None);
+        assert_eq!(loaded.skipped[1].line, Some(11));
+        assert_eq!(loaded.skipped[1].reason, "missing field `name`");
+    }
+
+    #[test]
+    fn test_load_config_skips_malformed_encrypted_profiles() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.jsonc");
+        let profiles = r#"[{ "id": "broken", "name": "Broken" }]"#;
+        let encrypted = encode_base64(&flip_bits(profiles.as_bytes()).unwrap());
+        fs::write(
+            &config_path,
+            format!(r#"{{ "encrypted_profiles": "{}" }}"#, encrypted),
+        )
+        .unwrap();
+
+        let loaded = load_config_with(&config_path, flip_bits).unwrap();
+        assert!(loaded.config.profiles.is_empty());
+        assert_eq!(loaded.skipped[0].line, None);
+        assert_eq!(loaded.skipped[0].reason, "missing field `settings`");
+    }
+
+    #[test]
+    fn test_save_config_keeps_skipped_profiles() {
+        let temp_dir = TempDir::new().unwrap();
+        let broken = serde_json::json!({ "id": "broken", "name": "Broken", "settings": 42 });
+        for (name, encrypt) in [
+            ("config.jsonc", false),
+            ("config.toml", false),
+            ("config.jsonc", true),
+        ] {
+            let path = temp_dir.path().join(name);
+            let mut config = AppConfig::new();
+            config.add_profile(DnsProfile::new("Valid".to_string()));
+            config.settings.encrypt_profiles = Some(encrypt);
+            config.unreadable_profiles.push(broken.clone());
+            save_config_with(&config, &path, flip_bits).unwrap();
+
+            let mut loaded = load_config_with(&path, flip_bits).unwrap();
+            assert_eq!(loaded.skipped.len(), 1);
+            assert_eq!(loaded.config.unreadable_profiles, vec![broken.clone()]);
+
+            // An ordinary save of the loaded config keeps it in the file.
+            loaded
+                .config
+                .add_profile(DnsProfile::new("Added".to_string()));
+            save_config_with(&loaded.config, &path, flip_bits).unwrap();
+            let reloaded = load_config_with(&path, flip_bits).unwrap();
+            assert_eq!(reloaded.config.profiles.len(), 2);
+            assert_eq!(reloaded.skipped[0].name.as_deref(), Some("Broken"));
+            assert_eq!(reloaded.skipped[0].position, 3);
+        }
+    }
+
+    #[test]
+    fn test_toml_profile_lines() {
+        let toml = "theme = \"dark\"\n\n[[profiles]]\nid = \"a\"\n\n[profiles.settings]\n  [[profiles]] # second\n";
+        assert_eq!(toml_profile_lines(toml), [3, 7]);
+    }
+
     #[test]
     fn test_save_config_to_path_roundtrip() {
         let temp_dir = TempDir::new().unwrap();
@@ -182,18 +1075,25 @@ mod tests {
         let mut config = AppConfig::new();
         let mut profile = DnsProfile::new("Roundtrip Test".to_string());
         profile.settings.ipv4.enabled = true;
-        profile.settings.ipv4.primary = DnsServerEntry {
+        profile.settings.ipv4.servers[0] = DnsServerEntry {
             address: "1.1.1.1".to_string(),
             doh_mode: crate::dns::types::DohMode::Off,
             doh_template: String::new(),
             allow_fallback: true,
+            auto_upgrade: true,
         };
-        profile.settings.ipv4.secondary = DnsServerEntry {
+        profile.settings.ipv4.servers[1] = DnsServerEntry {
             address: "1.0.0.1".to_string(),
             doh_mode: crate::dns::types::DohMode::Off,
             doh_template: String::new(),
             allow_fallback: false,
+            auto_upgrade: true,
         };
+        config.last_selection = Some(RelaunchSelection {
+            interface_guid: Some("{GUID-7}".to_string()),
+            profile_id: Some(profile.id.clone()),
+            dns_mode: Some(DnsMode::Manual),
+        });
         config.add_profile(profile);
 
         save_config_to_path(&config, &config_path).unwrap();
@@ -203,12 +1103,519 @@ mod tests {
         assert_eq!(loaded.profiles.len(), 1);
         assert_eq!(loaded.profiles[0].name, "Roundtrip Test");
         assert!(loaded.profiles[0].settings.ipv4.enabled);
-        assert_eq!(loaded.profiles[0].settings.ipv4.primary.address, "1.1.1.1");
         assert_eq!(
-            loaded.profiles[0].settings.ipv4.secondary.address,
+            loaded.profiles[0].settings.ipv4.servers[0].address,
+            "1.1.1.1"
+        );
+        assert_eq!(
+            loaded.profiles[0].settings.ipv4.servers[1].address,
             "1.0.0.1"
         );
-        assert!(!loaded.profiles[0].settings.ipv4.secondary.allow_fallback);
+        assert!(!loaded.profiles[0].settings.ipv4.servers[1].allow_fallback);
+    }
+
+    #[test]
+    fn test_export_import_profiles_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("profiles.json");
+
+        let mut config = AppConfig::new();
+        let mut profile = DnsProfile::new("Exported".to_string());
+        profile.settings.ipv4.enabled = true;
+        profile.settings.ipv4.servers[0].address = "9.9.9.9".to_string();
+        config.add_profile(profile);
+        config.settings.flush_cache_after_apply = Some(false);
+        export_profiles(&config, &path).unwrap();
+
+        let exported = fs::read_to_string(&path).unwrap();
+        assert!(!exported.contains("flush_cache_after_apply"));
+
+        let mut other = AppConfig::new();
+        other.add_profile(DnsProfile::new("Existing".to_string()));
+        assert_eq!(import_profiles(&mut other, &path).unwrap(), 1);
+
+        assert_eq!(other.profiles.len(), 2);
+        assert_eq!(other.profiles[1], config.profiles[0]);
+    }
+
+    #[test]
+    fn test_import_profiles_drops_hooks() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("profiles.json");
+        let mut config = AppConfig::new();
+        let mut profile = DnsProfile::new("Shared".to_string());
+        profile.settings.before_apply = Some("Start-Process calc".to_string());
+        profile.settings.after_apply = Some("ipconfig /registerdns".to_string());
+        approve_hooks_with(&mut profile.settings, flip_bits).unwrap();
+        config.add_profile(profile);
+        export_profiles(&config, &path).unwrap();
+
+        let mut other = AppConfig::new();
+        import_profiles(&mut other, &path).unwrap();
+        let settings = &other.profiles[0].settings;
+        assert!(!settings.has_hooks());
+        assert_eq!(settings.hooks_approval, None);
+    }
+
+    #[test]
+    fn test_hooks_approved() {
+        let mut settings = DnsSettings {
+            before_apply: Some("Start-Service dnscrypt-proxy".to_string()),
+            ..Default::default()
+        };
+        assert!(!hooks_approved_with(&settings, flip_bits));
+
+        approve_hooks_with(&mut settings, flip_bits).unwrap();
+        assert!(hooks_approved_with(&settings, flip_bits));
+
+        // Changing a command needs a new approval.
+        let mut changed = settings.clone();
+        changed.after_apply = Some("Start-Process calc".to_string());
+        assert!(!hooks_approved_with(&changed, flip_bits));
+
+        // An approval made by another user or computer does not decrypt.
+        let fail = |_: &[u8]| Err(DpapiError::Unprotect("Access denied".to_string()));
+        assert!(!hooks_approved_with(&settings, fail));
+        settings.hooks_approval = Some("not base64!".to_string());
+        assert!(!hooks_approved_with(&settings, flip_bits));
+    }
+
+    #[test]
+    fn test_import_profiles_regenerates_conflicting_ids() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("profiles.json");
+        let mut exported = AppConfig::new();
+        for (id, name) in [("taken", "A"), ("fresh", "B"), ("fresh", "C")] {
+            let mut profile = DnsProfile::new(name.to_string());
+            profile.id = id.to_string();
+            exported.add_profile(profile);
+        }
+        export_profiles(&exported, &path).unwrap();
+
+        let mut config = AppConfig::new();
+        let mut existing = DnsProfile::new("Existing".to_string());
+        existing.id = "taken".to_string();
+        config.add_profile(existing);
+
+        assert_eq!(import_profiles(&mut config, &path).unwrap(), 3);
+
+        let ids: HashSet<_> = config.profiles.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids.len(), 4);
+        assert_eq!(config.find_profile("taken").unwrap().name, "Existing");
+        assert_eq!(config.find_profile("fresh").unwrap().name, "B");
+        assert!(config.profiles.iter().any(|p| p.name == "A"));
+        assert!(config.profiles.iter().any(|p| p.name == "C"));
+    }
+
+    #[test]
+    fn test_import_profiles_invalid_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("profiles.json");
+        fs::write(&path, "not json").unwrap();
+
+        let mut config = AppConfig::new();
+        let result = import_profiles(&mut config, &path);
+        assert!(matches!(result, Err(ConfigError::Json(_))));
+        assert!(config.profiles.is_empty());
+
+        let missing = temp_dir.path().join("missing.json");
+        assert!(matches!(
+            import_profiles(&mut config, &missing),
+            Err(ConfigError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_backup_file_name() {
+        assert_eq!(
+            backup_file_name(0, ConfigFormat::Jsonc),
+            "config-19700101-000000.jsonc"
+        );
+        assert_eq!(
+            backup_file_name(1_709_210_096, ConfigFormat::Toml),
+            "config-20240229-123456.toml"
+        );
+        assert_eq!(
+            backup_created("config-20240229-123456.jsonc").as_deref(),
+            Some("2024-02-29 12:34:56 UTC")
+        );
+        assert_eq!(
+            backup_created("config-20240229-123456.toml").as_deref(),
+            Some("2024-02-29 12:34:56 UTC")
+        );
+        assert_eq!(backup_created("config.jsonc"), None);
+        assert_eq!(backup_created("config-20240229-123456.json"), None);
+        assert_eq!(backup_created("config-2024-123456.jsonc"), None);
+    }
+
+    #[test]
+    fn test_backup_config_rotates() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.jsonc");
+        assert_eq!(backup_config_at(&config_path, 0, 2).unwrap(), None);
+
+        let mut config = AppConfig::new();
+        for (secs, name) in [(10, "A"), (20, "B"), (30, "C")] {
+            config.add_profile(DnsProfile::new(name.to_string()));
+            save_config_to_path(&config, &config_path).unwrap();
+            assert!(backup_config_at(&config_path, secs, 2).unwrap().is_some());
+        }
+        // Nothing changed since the last backup.
+        assert_eq!(backup_config_at(&config_path, 40, 2).unwrap(), None);
+
+        let backups = list_config_backups_at(&config_path).unwrap();
+        assert_eq!(backups.len(), 2);
+        assert_eq!(backups[0].created, "1970-01-01 00:00:30 UTC");
+        assert_eq!(backups[0].profile_count, Some(3));
+        assert_eq!(backups[1].profile_count, Some(2));
+    }
+
+    #[test]
+    fn test_restore_config_backup() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.jsonc");
+        let mut config = AppConfig::new();
+        config.add_profile(DnsProfile::new("Kept".to_string()));
+        save_config_to_path(&config, &config_path).unwrap();
+        let backup = backup_config_at(&config_path, 10, MAX_CONFIG_BACKUPS)
+            .unwrap()
+            .unwrap();
+
+        config.profiles.clear();
+        save_config_to_path(&config, &config_path).unwrap();
+
+        let restored = restore_config_backup_at(&config_path, &backup, 20).unwrap();
+        assert_eq!(restored.profiles[0].name, "Kept");
+        assert_eq!(load_config_from_path(&config_path).unwrap(), restored);
+
+        // The config replaced by the restore is kept as the newest backup.
+        let backups = list_config_backups_at(&config_path).unwrap();
+        assert_eq!(backups.len(), 2);
+        assert_eq!(backups[0].profile_count, Some(0));
+    }
+
+    #[test]
+    fn test_config_format_from_path() {
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("config.jsonc")),
+            ConfigFormat::Jsonc
+        );
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("config.TOML")),
+            ConfigFormat::Toml
+        );
+        assert_eq!(ConfigFormat::parse("toml"), Some(ConfigFormat::Toml));
+        assert_eq!(ConfigFormat::parse("json"), None);
+
+        let temp_dir = TempDir::new().unwrap();
+        assert_eq!(
+            config_path_in(temp_dir.path()),
+            temp_dir.path().join("config.jsonc")
+        );
+        fs::write(temp_dir.path().join("config.toml"), "").unwrap();
+        assert_eq!(
+            config_path_in(temp_dir.path()),
+            temp_dir.path().join("config.toml")
+        );
+    }
+
+    #[test]
+    fn test_toml_config_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+
+        let mut config = AppConfig::new();
+        let mut profile = DnsProfile::new("Cloudflare".to_string());
+        profile.settings.ipv4.enabled = true;
+        profile.settings.ipv4.servers[0].address = "1.1.1.1".to_string();
+        let id = profile.id.clone();
+        config.add_profile(profile);
+        config.hotkeys.insert(1, id.clone());
+        config
+            .interface_profiles
+            .insert("{GUID-1}".to_string(), Some(id));
+        config.settings.flush_cache_after_apply = Some(false);
+
+        save_config_to_path(&config, &config_path).unwrap();
+        assert_eq!(load_config_from_path(&config_path).unwrap(), config);
+
+        // TOML has no null, so an interface last set to Automatic is lost.
+        config
+            .interface_profiles
+            .insert("{GUID-2}".to_string(), None);
+        save_config_to_path(&config, &config_path).unwrap();
+        let loaded = load_config_from_path(&config_path).unwrap();
+        assert_eq!(loaded.interface_profiles.len(), 1);
+    }
+
+    #[test]
+    fn test_load_config_from_path_hand_written_toml() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        let toml_content = r#"
+# Written by hand
+flush_cache_after_apply = false
+
+[hotkeys]
+1 = "test-id"
+
+[[profiles]]
+id = "test-id"
+name = "Test Profile" # inline comment
+
+[profiles.settings.ipv4]
+enabled = true
+
+[[profiles.settings.ipv4.servers]]
+address = "8.8.8.8"
+doh_mode = "Off"
+doh_template = ""
+allow_fallback = true
+
+[profiles.settings.ipv6]
+enabled = false
+servers = []
+"#;
+        fs::write(&config_path, toml_content).unwrap();
+
+        let loaded = load_config_from_path(&config_path).unwrap();
+        assert_eq!(loaded.profiles.len(), 1);
+        assert_eq!(loaded.profiles[0].name, "Test Profile");
+        assert_eq!(
+            loaded.profiles[0].settings.ipv4.servers[0].address,
+            "8.8.8.8"
+        );
+        assert_eq!(loaded.hotkeys.get(&1).map(String::as_str), Some("test-id"));
+        assert!(!loaded.settings.flushes_cache_after_apply());
+    }
+
+    /// Stands in for DPAPI, which is only available on Windows.
+    fn flip_bits(data: &[u8]) -> dpapi::Result<Vec<u8>> {
+        Ok(data.iter().map(|b| !b).collect())
+    }
+
+    #[test]
+    fn test_encrypted_profiles_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = AppConfig::new();
+        let mut profile = DnsProfile::new("Corporate".to_string());
+        profile.settings.ipv4.servers[0].address = "10.0.0.53".to_string();
+        config.add_profile(profile);
+        config.settings.encrypt_profiles = Some(true);
+
+        for name in ["config.jsonc", "config.toml"] {
+            let path = temp_dir.path().join(name);
+            save_config_with(&config, &path, flip_bits).unwrap();
+
+            let content = fs::read_to_string(&path).unwrap();
+            assert!(content.contains(ENCRYPTED_PROFILES_KEY));
+            assert!(!content.contains("Corporate"));
+            assert!(!content.contains("10.0.0.53"));
+
+            assert_eq!(load_config_with(&path, flip_bits).unwrap().config, config);
+        }
+    }
+
+    #[test]
+    fn test_encrypted_profiles_load_errors() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.jsonc");
+
+        fs::write(&path, r#"{ "encrypted_profiles": "not base64!" }"#).unwrap();
+        assert!(matches!(
+            load_config_with(&path, flip_bits),
+            Err(ConfigError::DamagedProfiles)
+        ));
+
+        // Another user's profiles cannot be decrypted.
+        fs::write(&path, r#"{ "encrypted_profiles": "AAAA" }"#).unwrap();
+        let fail = |_: &[u8]| Err(DpapiError::Unprotect("Access denied".to_string()));
+        assert!(matches!(
+            load_config_with(&path, fail),
+            Err(ConfigError::Encryption(_))
+        ));
+    }
+
+    #[test]
+    fn test_update_config_keeps_rest_of_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.jsonc");
+        let mut config = AppConfig::new();
+        config.profiles.push(DnsProfile {
+            id: "saved".to_string(),
+            name: "Saved".to_string(),
+            settings: Default::default(),
+            tags: Vec::new(),
+            color: None,
+        });
+        save_config_with(&config, &path, flip_bits).unwrap();
+
+        let selection = RelaunchSelection {
+            interface_guid: Some("{GUID}".to_string()),
+            ..Default::default()
+        };
+        update_config_with(&path, flip_bits, flip_bits, |config| {
+            config.last_selection = Some(selection.clone());
+        })
+        .unwrap();
+
+        let loaded = load_config_with(&path, flip_bits).unwrap().config;
+        assert_eq!(loaded.profiles, config.profiles);
+        assert_eq!(loaded.last_selection, Some(selection));
+    }
+
+    #[test]
+    fn test_update_config_leaves_unreadable_file_alone() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.jsonc");
+        let update = |config: &mut AppConfig| config.window = None;
+
+        let skipped = r#"{ "profiles": [{ "id": "broken", "name": "Broken" }] }"#;
+        fs::write(&path, skipped).unwrap();
+        assert!(matches!(
+            update_config_with(&path, flip_bits, flip_bits, update),
+            Err(ConfigError::SkippedProfiles(1))
+        ));
+        assert_eq!(fs::read_to_string(&path).unwrap(), skipped);
+
+        let encrypted = r#"{ "encrypted_profiles": "AAAA" }"#;
+        fs::write(&path, encrypted).unwrap();
+        let fail = |_: &[u8]| Err(DpapiError::Unprotect("Access denied".to_string()));
+        assert!(update_config_with(&path, fail, flip_bits, update).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), encrypted);
+    }
+
+    #[test]
+    fn test_save_after_failed_load_leaves_file_unchanged() {
+        let temp_dir = TempDir::new().unwrap();
+        let default_dir = temp_dir.path().join("default");
+        let shared_dir = temp_dir.path().join("shared");
+        fs::create_dir_all(&shared_dir).unwrap();
+        let path = shared_dir.join("config.jsonc");
+        let encrypted = r#"{ "encrypted_profiles": "AAAA" }"#;
+        fs::write(&path, encrypted).unwrap();
+
+        // Another machine cannot decrypt the profiles of a synced config.
+        let fail = |_: &[u8]| Err(DpapiError::Unprotect("Access denied".to_string()));
+        assert!(load_config_in_use(&path, fail).is_err());
+
+        let defaults = AppConfig::new();
+        assert!(matches!(
+            save_config_with(&defaults, &path, flip_bits),
+            Err(ConfigError::NotLoaded)
+        ));
+        assert!(set_config_format_at(&defaults, &path, ConfigFormat::Toml).is_err());
+        assert!(move_config_at(&default_dir, &path, &defaults, &default_dir, 10).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), encrypted);
+        assert!(!default_dir.join("config.jsonc").exists());
+        assert!(!shared_dir.join("config.toml").exists());
+    }
+
+    #[test]
+    fn test_restore_config_backup_over_unloaded_config() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("config.jsonc");
+        let mut config = AppConfig::new();
+        config.add_profile(DnsProfile::new("Kept".to_string()));
+        save_config_to_path(&config, &path).unwrap();
+        let backup = backup_config_at(&path, 10, MAX_CONFIG_BACKUPS)
+            .unwrap()
+            .unwrap();
+
+        fs::write(&path, r#"{ "encrypted_profiles": "AAAA" }"#).unwrap();
+        let fail = |_: &[u8]| Err(DpapiError::Unprotect("Access denied".to_string()));
+        assert!(load_config_in_use(&path, fail).is_err());
+        assert!(matches!(
+            save_config_to_path(&AppConfig::new(), &path),
+            Err(ConfigError::NotLoaded)
+        ));
+
+        // Restoring a backup replaces the file on purpose, and the config
+        // restored is the one in use from then on.
+        let restored = restore_config_backup_at(&path, &backup, 20).unwrap();
+        assert_eq!(restored.profiles[0].name, "Kept");
+        save_config_to_path(&restored, &path).unwrap();
+    }
+
+    #[test]
+    fn test_set_config_format_at() {
+        let temp_dir = TempDir::new().unwrap();
+        let jsonc_path = temp_dir.path().join("config.jsonc");
+        let mut config = AppConfig::new();
+        config.add_profile(DnsProfile::new("Kept".to_string()));
+        save_config_to_path(&config, &jsonc_path).unwrap();
+
+        let toml_path = set_config_format_at(&config, &jsonc_path, ConfigFormat::Toml).unwrap();
+        assert_eq!(toml_path, temp_dir.path().join("config.toml"));
+        assert!(!jsonc_path.exists());
+        assert_eq!(load_config_from_path(&toml_path).unwrap(), config);
+
+        let path = set_config_format_at(&config, &toml_path, ConfigFormat::Jsonc).unwrap();
+        assert_eq!(path, jsonc_path);
+        assert!(!toml_path.exists());
+        assert_eq!(load_config_from_path(&jsonc_path).unwrap(), config);
+    }
+
+    #[test]
+    fn test_config_dir_argument() {
+        let args = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            config_dir_argument(args(&["--minimized", "--config", r"D:\Sync\windns"])),
+            Some(PathBuf::from(r"D:\Sync\windns"))
+        );
+        assert_eq!(config_dir_argument(args(&["--config"])), None);
+        assert_eq!(config_dir_argument(args(&["--minimized"])), None);
+    }
+
+    #[test]
+    fn test_move_config_at() {
+        let temp_dir = TempDir::new().unwrap();
+        let default_dir = temp_dir.path().join("default");
+        let sync_dir = temp_dir.path().join("sync");
+        let default_path = default_dir.join("config.toml");
+        let mut config = AppConfig::new();
+        config.add_profile(DnsProfile::new("Roaming".to_string()));
+        save_config_to_path(&config, &default_path).unwrap();
+
+        let found = move_config_at(&default_dir, &default_path, &config, &sync_dir, 10).unwrap();
+        assert_eq!(found, None);
+        assert!(!default_path.exists());
+        assert_eq!(redirected_dir(&default_dir), Some(sync_dir.clone()));
+        let sync_path = sync_dir.join("config.toml");
+        assert_eq!(load_config_from_path(&sync_path).unwrap(), config);
+        assert_eq!(list_config_backups_at(&default_path).unwrap().len(), 1);
+
+        let found = move_config_at(&default_dir, &sync_path, &config, &default_dir, 20).unwrap();
+        assert_eq!(found, None);
+        assert!(!sync_path.exists());
+        assert_eq!(redirected_dir(&default_dir), None);
+        assert_eq!(load_config_from_path(&default_path).unwrap(), config);
+    }
+
+    #[test]
+    fn test_move_config_at_uses_existing_config() {
+        let temp_dir = TempDir::new().unwrap();
+        let default_dir = temp_dir.path().join("default");
+        let sync_dir = temp_dir.path().join("sync");
+        let mut synced = AppConfig::new();
+        synced.add_profile(DnsProfile::new("From the other computer".to_string()));
+        save_config_to_path(&synced, &sync_dir.join("config.jsonc")).unwrap();
+
+        let default_path = default_dir.join("config.jsonc");
+        let mut local = AppConfig::new();
+        local.add_profile(DnsProfile::new("Local".to_string()));
+        save_config_to_path(&local, &default_path).unwrap();
+
+        let found = move_config_at(&default_dir, &default_path, &local, &sync_dir, 10).unwrap();
+        assert_eq!(found, Some(synced.clone()));
+        assert_eq!(
+            load_config_from_path(&sync_dir.join("config.jsonc")).unwrap(),
+            synced
+        );
+        // The local config is only kept as a backup.
+        assert!(!default_path.exists());
+        let backups = list_config_backups_at(&default_path).unwrap();
+        assert_eq!(backups[0].profile_count, Some(1));
     }
 
     #[test]
@@ -0,0 +1,354 @@
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::sync::{mpsc, oneshot};
+
+#[derive(Error, Debug)]
+pub enum ControlError {
+    #[error("Failed to start the control server: {0}")]
+    Start(String),
+}
+
+pub type Result<T> = std::result::Result<T, ControlError>;
+
+/// Pipe that other programs connect to, e.g. with
+/// `[System.IO.Pipes.NamedPipeClientStream]::new('windns')` in PowerShell.
+#[cfg(target_os = "windows")]
+pub const CONTROL_PIPE_NAME: &str = r"\\.\pipe\windns";
+
+/// Lets programs running as the user windns runs as connect to it, even
+/// when it runs as administrator; other users are denied. The medium label
+/// keeps Windows from denying them write access to a pipe created by an
+/// elevated process.
+#[cfg(any(target_os = "windows", test))]
+fn control_pipe_sddl(user_sid: &str) -> String {
+    format!(
+        "D:P(A;;GA;;;SY)(A;;GA;;;BA)(A;;GRGW;;;{})S:(ML;;NW;;;ME)",
+        user_sid
+    )
+}
+
+/// One request, sent by the client as a line of JSON such as
+/// `{"command": "apply", "profile": "Cloudflare"}`.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "command", rename_all = "lowercase")]
+pub enum ControlRequest {
+    /// Lists the profiles.
+    List,
+    /// Reports the selected interface and the DNS servers it uses.
+    Status,
+    /// Applies the profile with this name or ID to the selected interface,
+    /// or switches it to Automatic when there is none.
+    Apply {
+        #[serde(default)]
+        profile: Option<String>,
+    },
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ControlProfile {
+    pub id: String,
+    pub name: String,
+    /// Whether the DNS servers in use are this profile's.
+    pub active: bool,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct ControlStatus {
+    pub interface: Option<String>,
+    pub interface_guid: Option<String>,
+    /// Name of the profile whose servers are in use, if any.
+    pub profile: Option<String>,
+    /// Whether the interface gets its DNS servers automatically.
+    pub automatic: bool,
+    pub ipv4: Vec<String>,
+    pub ipv6: Vec<String>,
+    /// Whether windns can apply profiles, which needs administrator rights.
+    pub elevated: bool,
+}
+
+/// Reply to a request, sent back as one line of JSON such as
+/// `{"applied": "..."}` or `{"error": "..."}`.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ControlResponse {
+    Profiles(Vec<ControlProfile>),
+    Status(ControlStatus),
+    Applied(String),
+    Error(String),
+}
+
+/// A request waiting for the app to answer it.
+pub struct ControlCall {
+    pub request: ControlRequest,
+    responder: oneshot::Sender<ControlResponse>,
+}
+
+impl ControlCall {
+    pub fn respond(self, response: ControlResponse) {
+        // The client may have disconnected in the meantime.
+        let _ = self.responder.send(response);
+    }
+}
+
+/// Answers one line from a client, asking the app through `calls`.
+async fn handle_line(line: &str, calls: &mpsc::UnboundedSender<ControlCall>) -> String {
+    let response = match serde_json::from_str::<ControlRequest>(line.trim()) {
+        Ok(request) => {
+            let (responder, response) = oneshot::channel();
+            if calls.send(ControlCall { request, responder }).is_ok() {
+                response
+                    .await
+                    .unwrap_or_else(|_| ControlResponse::Error("windns is closing".to_string()))
+            } else {
+                ControlResponse::Error("windns is closing".to_string())
+            }
+        }
+        Err(e) => ControlResponse::Error(format!("Invalid request: {}", e)),
+    };
+    serde_json::to_string(&response).unwrap_or_default()
+}
+
+/// Named-pipe server for the JSON control API. Requests reach the app
+/// through `next_call`; the pipe is closed when the server is dropped.
+pub struct ControlServer {
+    calls: mpsc::UnboundedReceiver<ControlCall>,
+    #[cfg(target_os = "windows")]
+    task: tokio::task::JoinHandle<()>,
+    #[cfg(not(target_os = "windows"))]
+    _sender: mpsc::UnboundedSender<ControlCall>,
+}
+
+impl ControlServer {
+    #[cfg(target_os = "windows")]
+    pub fn start() -> Result<Self> {
+        let (sender, calls) = mpsc::unbounded_channel();
+        let first = create_pipe(true).map_err(|e| ControlError::Start(e.to_string()))?;
+        let task = tokio::spawn(accept_clients(first, sender));
+        Ok(Self { calls, task })
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    pub fn start() -> Result<Self> {
+        Err(ControlError::Start(
+            "Not supported on this platform".to_string(),
+        ))
+    }
+
+    /// Waits for the next request from a client.
+    pub async fn next_call(&mut self) -> Option<ControlCall> {
+        self.calls.recv().await
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl Drop for ControlServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn create_pipe(first: bool) -> std::io::Result<tokio::net::windows::named_pipe::NamedPipeServer> {
+    use tokio::net::windows::named_pipe::ServerOptions;
+    use windows::Win32::Security::Authorization::{
+        ConvertStringSecurityDescriptorToSecurityDescriptorW, SDDL_REVISION_1,
+    };
+    use windows::Win32::Security::{PSECURITY_DESCRIPTOR, SECURITY_ATTRIBUTES};
+    use windows::core::HSTRING;
+
+    let sddl = control_pipe_sddl(&current_user_sid()?);
+    let mut descriptor = PSECURITY_DESCRIPTOR::default();
+    unsafe {
+        ConvertStringSecurityDescriptorToSecurityDescriptorW(
+            &HSTRING::from(sddl),
+            SDDL_REVISION_1,
+            &mut descriptor,
+            None,
+        )
+    }
+    .map_err(std::io::Error::other)?;
+    let mut attributes = SECURITY_ATTRIBUTES {
+        nLength: std::mem::size_of::<SECURITY_ATTRIBUTES>() as u32,
+        lpSecurityDescriptor: descriptor.0,
+        bInheritHandle: false.into(),
+    };
+
+    let mut options = ServerOptions::new();
+    options
+        .first_pipe_instance(first)
+        .reject_remote_clients(true);
+    let server = unsafe {
+        options.create_with_security_attributes_raw(
+            CONTROL_PIPE_NAME,
+            (&mut attributes as *mut SECURITY_ATTRIBUTES).cast(),
+        )
+    };
+    free_descriptor(descriptor);
+    server
+}
+
+/// SID of the user in the token of this process, as a string such as
+/// `S-1-5-21-...`.
+#[cfg(target_os = "windows")]
+fn current_user_sid() -> std::io::Result<String> {
+    use windows::Win32::Foundation::{CloseHandle, HANDLE, HLOCAL, LocalFree};
+    use windows::Win32::Security::Authorization::ConvertSidToStringSidW;
+    use windows::Win32::Security::{GetTokenInformation, TOKEN_QUERY, TOKEN_USER, TokenUser};
+    use windows::Win32::System::Threading::{GetCurrentProcess, OpenProcessToken};
+    use windows::core::PWSTR;
+
+    unsafe {
+        let mut token = HANDLE::default();
+        OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token)
+            .map_err(std::io::Error::other)?;
+
+        let mut size = 0u32;
+        let _ = GetTokenInformation(token, TokenUser, None, 0, &mut size);
+        // u64 elements keep the TOKEN_USER at the start suitably aligned.
+        let mut buffer = vec![0u64; (size as usize).div_ceil(8)];
+        let result = GetTokenInformation(
+            token,
+            TokenUser,
+            Some(buffer.as_mut_ptr().cast()),
+            size,
+            &mut size,
+        );
+        let _ = CloseHandle(token);
+        result.map_err(std::io::Error::other)?;
+
+        let user = &*buffer.as_ptr().cast::<TOKEN_USER>();
+        let mut sid = PWSTR::null();
+        ConvertSidToStringSidW(user.User.Sid, &mut sid).map_err(std::io::Error::other)?;
+        let text = sid.to_string().map_err(std::io::Error::other);
+        let _ = LocalFree(Some(HLOCAL(sid.0.cast())));
+        text
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn free_descriptor(descriptor: windows::Win32::Security::PSECURITY_DESCRIPTOR) {
+    use windows::Win32::Foundation::{HLOCAL, LocalFree};
+
+    let _ = unsafe { LocalFree(Some(HLOCAL(descriptor.0))) };
+}
+
+/// Accepts clients one after another, keeping a pipe instance ready for
+/// the next one while the previous ones are served.
+#[cfg(target_os = "windows")]
+async fn accept_clients(
+    mut server: tokio::net::windows::named_pipe::NamedPipeServer,
+    calls: mpsc::UnboundedSender<ControlCall>,
+) {
+    loop {
+        if server.connect().await.is_err() {
+            return;
+        }
+        let next = match create_pipe(false) {
+            Ok(next) => next,
+            Err(_) => return,
+        };
+        let client = std::mem::replace(&mut server, next);
+        tokio::spawn(serve_client(client, calls.clone()));
+    }
+}
+
+#[cfg(target_os = "windows")]
+async fn serve_client(
+    client: tokio::net::windows::named_pipe::NamedPipeServer,
+    calls: mpsc::UnboundedSender<ControlCall>,
+) {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let (reader, mut writer) = tokio::io::split(client);
+    let mut lines = BufReader::new(reader).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut reply = handle_line(&line, &calls).await;
+        reply.push('\n');
+        if writer.write_all(reply.as_bytes()).await.is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_control_pipe_sddl_grants_only_given_user() {
+        let sddl = control_pipe_sddl("S-1-5-21-1-2-3-1001");
+        assert!(sddl.contains("(A;;GRGW;;;S-1-5-21-1-2-3-1001)"));
+        assert!(!sddl.contains(";IU)"));
+        assert!(sddl.starts_with("D:P(A;;GA;;;SY)(A;;GA;;;BA)"));
+        assert!(sddl.ends_with("S:(ML;;NW;;;ME)"));
+    }
+
+    #[test]
+    fn test_control_request_parse() {
+        let parse = |json: &str| serde_json::from_str::<ControlRequest>(json).ok();
+        assert_eq!(parse(r#"{"command": "list"}"#), Some(ControlRequest::List));
+        assert_eq!(
+            parse(r#"{"command": "status"}"#),
+            Some(ControlRequest::Status)
+        );
+        assert_eq!(
+            parse(r#"{"command": "apply", "profile": "Cloudflare"}"#),
+            Some(ControlRequest::Apply {
+                profile: Some("Cloudflare".to_string())
+            })
+        );
+        assert_eq!(
+            parse(r#"{"command": "apply"}"#),
+            Some(ControlRequest::Apply { profile: None })
+        );
+        assert_eq!(parse(r#"{"command": "delete"}"#), None);
+        assert_eq!(parse("list"), None);
+    }
+
+    #[test]
+    fn test_control_response_json() {
+        let json = |response: &ControlResponse| serde_json::to_string(response).unwrap();
+        assert_eq!(
+            json(&ControlResponse::Applied("Applied Home".to_string())),
+            r#"{"applied":"Applied Home"}"#
+        );
+        assert_eq!(
+            json(&ControlResponse::Profiles(vec![ControlProfile {
+                id: "1".to_string(),
+                name: "Home".to_string(),
+                active: true,
+            }])),
+            r#"{"profiles":[{"id":"1","name":"Home","active":true}]}"#
+        );
+        assert_eq!(
+            json(&ControlResponse::Error("Busy".to_string())),
+            r#"{"error":"Busy"}"#
+        );
+    }
+
+    #[tokio::test]
+    async fn test_handle_line() {
+        let (sender, mut calls) = mpsc::unbounded_channel::<ControlCall>();
+        let app = async move {
+            while let Some(call) = calls.recv().await {
+                let response = match call.request {
+                    ControlRequest::Status => ControlResponse::Status(ControlStatus::default()),
+                    _ => ControlResponse::Error("Unexpected".to_string()),
+                };
+                call.respond(response);
+            }
+        };
+        let app = tokio::spawn(app);
+
+        let reply = handle_line(r#"{"command": "status"}"#, &sender).await;
+        assert!(reply.starts_with(r#"{"status":{"interface":null"#));
+        let reply = handle_line("not json", &sender).await;
+        assert!(reply.starts_with(r#"{"error":"Invalid request"#));
+
+        drop(sender);
+        app.await.unwrap();
+    }
+}
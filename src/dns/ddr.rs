@@ -0,0 +1,437 @@
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+use thiserror::Error;
+use tokio::net::UdpSocket;
+
+#[derive(Error, Debug)]
+pub enum DdrError {
+    #[error("Invalid DNS server address: {0}")]
+    InvalidAddress(String),
+
+    #[error("Discovery query failed: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("The DNS server did not respond in time")]
+    Timeout,
+
+    #[error("The DNS server sent an invalid response")]
+    InvalidResponse,
+
+    #[error("The DNS server does not advertise a DoH endpoint")]
+    NotAdvertised,
+}
+
+pub type Result<T> = std::result::Result<T, DdrError>;
+
+const DDR_NAME: &str = "_dns.resolver.arpa";
+const QUERY_TIMEOUT: Duration = Duration::from_secs(5);
+const TYPE_SVCB: u16 = 64;
+const CLASS_IN: u16 = 1;
+
+const KEY_ALPN: u16 = 1;
+const KEY_PORT: u16 = 3;
+const KEY_DOHPATH: u16 = 7;
+
+/// An encrypted endpoint advertised by a resolver.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DesignatedResolver {
+    pub priority: u16,
+    pub target: String,
+    pub alpn: Vec<String>,
+    pub port: Option<u16>,
+    pub doh_path: Option<String>,
+}
+
+impl DesignatedResolver {
+    /// Returns the DoH template in the form Windows expects, or `None` when
+    /// the endpoint is not DoH (e.g. DoT only).
+    pub fn doh_template(&self) -> Option<String> {
+        let path = self.doh_path.as_deref()?;
+        if !self.alpn.iter().any(|a| a == "h2" || a == "h3") || self.target.is_empty() {
+            return None;
+        }
+
+        // Windows does not take URI template variables such as `{?dns}`.
+        let path = path.split('{').next().unwrap_or_default();
+        let port = match self.port {
+            Some(port) if port != 443 => format!(":{}", port),
+            _ => String::new(),
+        };
+        Some(format!("https://{}{}{}", self.target, port, path))
+    }
+}
+
+/// Discovers the DoH template of the DNS server at `address` via DDR
+/// (RFC 9462): the server answers an SVCB query for `_dns.resolver.arpa`
+/// with the encrypted endpoints it offers.
+pub async fn discover_doh_template(address: &str) -> Result<String> {
+    let ip: IpAddr = address
+        .trim()
+        .parse()
+        .map_err(|_| DdrError::InvalidAddress(address.to_string()))?;
+
+    let resolvers = query_designated_resolvers(ip).await?;
+    resolvers
+        .iter()
+        .find_map(DesignatedResolver::doh_template)
+        .ok_or(DdrError::NotAdvertised)
+}
+
+async fn query_designated_resolvers(ip: IpAddr) -> Result<Vec<DesignatedResolver>> {
+    let bind_address: SocketAddr = match ip {
+        IpAddr::V4(_) => "0.0.0.0:0".parse().unwrap(),
+        IpAddr::V6(_) => "[::]:0".parse().unwrap(),
+    };
+    let socket = UdpSocket::bind(bind_address).await?;
+    socket.connect(SocketAddr::new(ip, 53)).await?;
+
+    let id = query_id();
+    socket.send(&build_query(id)).await?;
+
+    let mut buffer = [0u8; 4096];
+    let len = tokio::time::timeout(QUERY_TIMEOUT, socket.recv(&mut buffer))
+        .await
+        .map_err(|_| DdrError::Timeout)??;
+
+    let mut resolvers = parse_response(id, &buffer[..len])?;
+    resolvers.sort_by_key(|r| r.priority);
+    Ok(resolvers)
+}
+
+pub(crate) fn query_id() -> u16 {
+    let bytes = uuid::Uuid::new_v4().into_bytes();
+    u16::from_be_bytes([bytes[0], bytes[1]])
+}
+
+/// Builds an SVCB query for `_dns.resolver.arpa` with recursion desired.
+pub(crate) fn build_query(id: u16) -> Vec<u8> {
+    encode_query(id, DDR_NAME, TYPE_SVCB)
+}
+
+/// Encodes a single-question query with recursion desired.
+pub(crate) fn encode_query(id: u16, name: &str, record_type: u16) -> Vec<u8> {
+    let mut query = Vec::with_capacity(12 + name.len() + 6);
+    query.extend_from_slice(&id.to_be_bytes());
+    query.extend_from_slice(&0x0100u16.to_be_bytes());
+    query.extend_from_slice(&1u16.to_be_bytes());
+    query.extend_from_slice(&[0; 6]);
+    for label in name.split('.') {
+        query.push(label.len() as u8);
+        query.extend_from_slice(label.as_bytes());
+    }
+    query.push(0);
+    query.extend_from_slice(&record_type.to_be_bytes());
+    query.extend_from_slice(&CLASS_IN.to_be_bytes());
+    query
+}
+
+/// Extracts the SVCB records of a response to `build_query(id)`.
+/// Alias-mode records (priority 0) are skipped.
+pub(crate) fn parse_response(id: u16, message: &[u8]) -> Result<Vec<DesignatedResolver>> {
+    let mut reader = Reader::new(message);
+    if reader.u16()? != id {
+        return Err(DdrError::InvalidResponse);
+    }
+    let flags = reader.u16()?;
+    if flags & 0x8000 == 0 {
+        return Err(DdrError::InvalidResponse);
+    }
+    if flags & 0x000f != 0 {
+        return Err(DdrError::NotAdvertised);
+    }
+    let question_count = reader.u16()?;
+    let answer_count = reader.u16()?;
+    reader.skip(4)?;
+
+    for _ in 0..question_count {
+        reader.name()?;
+        reader.skip(4)?;
+    }
+
+    let mut resolvers = Vec::new();
+    for _ in 0..answer_count {
+        reader.name()?;
+        let record_type = reader.u16()?;
+        reader.skip(6)?;
+        let length = reader.u16()? as usize;
+        let end = reader.pos + length;
+        if end > message.len() {
+            return Err(DdrError::InvalidResponse);
+        }
+
+        if record_type == TYPE_SVCB {
+            let resolver = parse_svcb(&mut reader, end)?;
+            if resolver.priority != 0 {
+                resolvers.push(resolver);
+            }
+        }
+        reader.pos = end;
+    }
+    Ok(resolvers)
+}
+
+fn parse_svcb(reader: &mut Reader, end: usize) -> Result<DesignatedResolver> {
+    let mut resolver = DesignatedResolver {
+        priority: reader.u16()?,
+        target: reader.name()?,
+        ..Default::default()
+    };
+
+    while reader.pos < end {
+        let key = reader.u16()?;
+        let len = reader.u16()? as usize;
+        let value = reader.bytes(len)?;
+        match key {
+            KEY_ALPN => {
+                let mut value = value;
+                while let Some((&len, rest)) = value.split_first() {
+                    let id = rest.get(..len as usize).ok_or(DdrError::InvalidResponse)?;
+                    resolver.alpn.push(String::from_utf8_lossy(id).into_owned());
+                    value = &rest[len as usize..];
+                }
+            }
+            KEY_PORT => {
+                let port: [u8; 2] = value.try_into().map_err(|_| DdrError::InvalidResponse)?;
+                resolver.port = Some(u16::from_be_bytes(port));
+            }
+            KEY_DOHPATH => {
+                resolver.doh_path = Some(String::from_utf8_lossy(value).into_owned());
+            }
+            _ => {}
+        }
+    }
+    Ok(resolver)
+}
+
+/// Cursor over a DNS message.
+pub(crate) struct Reader<'a> {
+    message: &'a [u8],
+    pub(crate) pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(message: &'a [u8]) -> Self {
+        Self { message, pos: 0 }
+    }
+
+    pub(crate) fn bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        let bytes = self
+            .message
+            .get(self.pos..self.pos + len)
+            .ok_or(DdrError::InvalidResponse)?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    pub(crate) fn skip(&mut self, len: usize) -> Result<()> {
+        self.bytes(len).map(|_| ())
+    }
+
+    pub(crate) fn u16(&mut self) -> Result<u16> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// Reads a domain name, following compression pointers.
+    pub(crate) fn name(&mut self) -> Result<String> {
+        let mut labels = Vec::new();
+        let mut pos = self.pos;
+        let mut resume = None;
+
+        for _ in 0..self.message.len() {
+            let len = *self.message.get(pos).ok_or(DdrError::InvalidResponse)? as usize;
+            if len == 0 {
+                self.pos = resume.unwrap_or(pos + 1);
+                return Ok(labels.join("."));
+            }
+            if len & 0xc0 == 0xc0 {
+                let low = *self.message.get(pos + 1).ok_or(DdrError::InvalidResponse)? as usize;
+                resume.get_or_insert(pos + 2);
+                pos = ((len & 0x3f) << 8) | low;
+                continue;
+            }
+            let label = self
+                .message
+                .get(pos + 1..pos + 1 + len)
+                .ok_or(DdrError::InvalidResponse)?;
+            labels.push(String::from_utf8_lossy(label).into_owned());
+            pos += 1 + len;
+        }
+        Err(DdrError::InvalidResponse)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode_name(name: &str) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for label in name.split('.').filter(|l| !l.is_empty()) {
+            bytes.push(label.len() as u8);
+            bytes.extend_from_slice(label.as_bytes());
+        }
+        bytes.push(0);
+        bytes
+    }
+
+    fn svcb_rdata(priority: u16, target: &str, params: &[(u16, Vec<u8>)]) -> Vec<u8> {
+        let mut rdata = priority.to_be_bytes().to_vec();
+        rdata.extend(encode_name(target));
+        for (key, value) in params {
+            rdata.extend_from_slice(&key.to_be_bytes());
+            rdata.extend_from_slice(&(value.len() as u16).to_be_bytes());
+            rdata.extend_from_slice(value);
+        }
+        rdata
+    }
+
+    fn response(id: u16, rcode: u16, answers: &[Vec<u8>]) -> Vec<u8> {
+        let mut message = build_query(id);
+        message[2..4].copy_from_slice(&(0x8180u16 | rcode).to_be_bytes());
+        message[6..8].copy_from_slice(&(answers.len() as u16).to_be_bytes());
+        for rdata in answers {
+            // Pointer to the question name at offset 12.
+            message.extend_from_slice(&[0xc0, 0x0c]);
+            message.extend_from_slice(&TYPE_SVCB.to_be_bytes());
+            message.extend_from_slice(&CLASS_IN.to_be_bytes());
+            message.extend_from_slice(&300u32.to_be_bytes());
+            message.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+            message.extend_from_slice(rdata);
+        }
+        message
+    }
+
+    fn alpn(ids: &[&str]) -> Vec<u8> {
+        ids.iter()
+            .flat_map(|id| std::iter::once(id.len() as u8).chain(id.bytes()))
+            .collect()
+    }
+
+    #[test]
+    fn test_build_query() {
+        let query = build_query(0x1234);
+        assert_eq!(&query[..4], &[0x12, 0x34, 0x01, 0x00]);
+        assert_eq!(&query[4..6], &[0, 1]);
+        assert_eq!(
+            &query[12..],
+            &[
+                4, b'_', b'd', b'n', b's', 8, b'r', b'e', b's', b'o', b'l', b'v', b'e', b'r', 4,
+                b'a', b'r', b'p', b'a', 0, 0, 64, 0, 1
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_response() {
+        let message = response(
+            7,
+            0,
+            &[
+                svcb_rdata(2, "dns.example.net", &[(KEY_ALPN, alpn(&["dot"]))]),
+                svcb_rdata(
+                    1,
+                    "dns.example.net",
+                    &[
+                        (KEY_ALPN, alpn(&["h2", "h3"])),
+                        (KEY_PORT, 8443u16.to_be_bytes().to_vec()),
+                        (KEY_DOHPATH, b"/dns-query{?dns}".to_vec()),
+                    ],
+                ),
+                svcb_rdata(0, "alias.example.net", &[]),
+            ],
+        );
+
+        let resolvers = parse_response(7, &message).unwrap();
+        assert_eq!(resolvers.len(), 2);
+        assert_eq!(resolvers[0].alpn, vec!["dot"]);
+        assert_eq!(resolvers[0].doh_template(), None);
+        assert_eq!(
+            resolvers[1],
+            DesignatedResolver {
+                priority: 1,
+                target: "dns.example.net".to_string(),
+                alpn: vec!["h2".to_string(), "h3".to_string()],
+                port: Some(8443),
+                doh_path: Some("/dns-query{?dns}".to_string()),
+            }
+        );
+        assert_eq!(
+            resolvers[1].doh_template().as_deref(),
+            Some("https://dns.example.net:8443/dns-query")
+        );
+    }
+
+    #[test]
+    fn test_parse_response_rejects_other_id() {
+        let message = response(7, 0, &[]);
+        assert!(matches!(
+            parse_response(8, &message),
+            Err(DdrError::InvalidResponse)
+        ));
+    }
+
+    #[test]
+    fn test_parse_response_error_rcode() {
+        let message = response(7, 3, &[]);
+        assert!(matches!(
+            parse_response(7, &message),
+            Err(DdrError::NotAdvertised)
+        ));
+    }
+
+    #[test]
+    fn test_parse_response_truncated() {
+        let message = response(7, 0, &[svcb_rdata(1, "dns.example.net", &[])]);
+        assert!(matches!(
+            parse_response(7, &message[..message.len() - 3]),
+            Err(DdrError::InvalidResponse)
+        ));
+    }
+
+    #[test]
+    fn test_doh_template_default_port() {
+        let resolver = DesignatedResolver {
+            priority: 1,
+            target: "dns.google".to_string(),
+            alpn: vec!["h2".to_string()],
+            port: Some(443),
+            doh_path: Some("/dns-query{?dns}".to_string()),
+        };
+        assert_eq!(
+            resolver.doh_template().as_deref(),
+            Some("https://dns.google/dns-query")
+        );
+    }
+
+    #[test]
+    fn test_doh_template_requires_path() {
+        let resolver = DesignatedResolver {
+            priority: 1,
+            target: "dns.google".to_string(),
+            alpn: vec!["h2".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(resolver.doh_template(), None);
+    }
+
+    #[tokio::test]
+    async fn test_discover_doh_template_invalid_address() {
+        assert!(matches!(
+            discover_doh_template("not an address").await,
+            Err(DdrError::InvalidAddress(_))
+        ));
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_discover_doh_template() {
+        let template = discover_doh_template("1.1.1.1").await.unwrap();
+        assert!(template.starts_with("https://"));
+    }
+}
@@ -0,0 +1,64 @@
+use crate::dns::resolution_check::{RCODE_NOERROR, RCODE_SERVFAIL, Result, query_a};
+use crate::i18n::t;
+
+/// Signed name that every resolver should resolve.
+const SIGNED_HOST: &str = "example.com";
+/// Name with deliberately broken signatures, which a validating resolver
+/// refuses with SERVFAIL.
+const BROKEN_HOST: &str = "dnssec-failed.org";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DnssecStatus {
+    Validating,
+    NotValidating,
+    /// The resolver failed on the signed name too, so nothing can be said.
+    Inconclusive,
+}
+
+impl DnssecStatus {
+    pub fn description(&self) -> String {
+        match self {
+            DnssecStatus::Validating => t!("dnssec-validating"),
+            DnssecStatus::NotValidating => t!("dnssec-not-validating"),
+            DnssecStatus::Inconclusive => t!("dnssec-inconclusive"),
+        }
+    }
+}
+
+/// Tells whether the DNS server at `server` validates DNSSEC by resolving
+/// a correctly signed name and one with broken signatures.
+pub async fn check_dnssec(server: &str) -> Result<DnssecStatus> {
+    let signed = query_a(server, SIGNED_HOST).await?;
+    let broken = query_a(server, BROKEN_HOST).await?;
+    Ok(classify(signed.rcode, broken.rcode))
+}
+
+fn classify(signed_rcode: u16, broken_rcode: u16) -> DnssecStatus {
+    match (signed_rcode, broken_rcode) {
+        (RCODE_NOERROR, RCODE_SERVFAIL) => DnssecStatus::Validating,
+        (RCODE_NOERROR, RCODE_NOERROR) => DnssecStatus::NotValidating,
+        _ => DnssecStatus::Inconclusive,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(classify(0, 2), DnssecStatus::Validating);
+        assert_eq!(classify(0, 0), DnssecStatus::NotValidating);
+        assert_eq!(classify(2, 2), DnssecStatus::Inconclusive);
+        assert_eq!(classify(0, 5), DnssecStatus::Inconclusive);
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_check_dnssec() {
+        assert_eq!(
+            check_dnssec("1.1.1.1").await.unwrap(),
+            DnssecStatus::Validating
+        );
+    }
+}
@@ -0,0 +1,203 @@
+use crate::dns::commands::{
+    command_error_message, doh_targets, escape_powershell_string, run_powershell,
+};
+use crate::dns::ddr::encode_query;
+use crate::dns::types::DnsSettings;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum DohProbeError {
+    #[error("Invalid DoH template URL")]
+    InvalidTemplate,
+
+    #[error("{0}")]
+    RequestFailed(String),
+
+    #[error("The server did not answer with a DNS message")]
+    InvalidResponse,
+}
+
+pub type Result<T> = std::result::Result<T, DohProbeError>;
+
+/// Name looked up by the probe. Any answer, even NXDOMAIN, shows that the
+/// endpoint works.
+const PROBE_NAME: &str = "example.com";
+const TYPE_A: u16 = 1;
+const PROBE_TIMEOUT_SECS: u32 = 5;
+
+/// Sends an RFC 8484 GET query to `template` and checks that a DNS
+/// response comes back.
+pub async fn probe_doh_template(template: &str) -> Result<()> {
+    let url = probe_url(template)?;
+    let output = run_powershell(&probe_script(&url))
+        .await
+        .map_err(|e| DohProbeError::RequestFailed(command_error_message(e)))?;
+    let message = decode_hex(output.trim()).ok_or(DohProbeError::InvalidResponse)?;
+    check_response(&message)
+}
+
+/// Probes every DoH template used by `settings` once and describes the ones
+/// that do not answer.
+pub async fn unreachable_doh_templates(settings: &DnsSettings) -> Vec<String> {
+    let mut templates: Vec<&str> = Vec::new();
+    for (_, server) in doh_targets(settings) {
+        if !templates.contains(&server.doh_template.as_str()) {
+            templates.push(&server.doh_template);
+        }
+    }
+
+    let mut failures = Vec::new();
+    for template in templates {
+        if let Err(e) = probe_doh_template(template).await {
+            failures.push(format!("{} ({})", template, e));
+        }
+    }
+    failures
+}
+
+/// Turns a template such as `https://dns.example/dns-query{?dns}` into a
+/// GET URL carrying the probe query.
+fn probe_url(template: &str) -> Result<String> {
+    let base = template.trim().replace("{?dns}", "");
+    if !base.starts_with("https://") || base.contains(['{', '}']) {
+        return Err(DohProbeError::InvalidTemplate);
+    }
+
+    // RFC 8484 recommends ID 0 so that responses can be cached.
+    let query = base64url(&encode_query(0, PROBE_NAME, TYPE_A));
+    let separator = if base.contains('?') { '&' } else { '?' };
+    Ok(format!("{}{}dns={}", base, separator, query))
+}
+
+/// Prints the response body as hex, which survives the console encoding.
+fn probe_script(url: &str) -> String {
+    format!(
+        r#"
+        $response = Invoke-WebRequest -UseBasicParsing -Uri '{}' -Headers @{{ Accept = 'application/dns-message' }} -TimeoutSec {}
+        if ($response.Content -isnot [byte[]]) {{ exit 0 }}
+        ($response.Content | ForEach-Object {{ $_.ToString('x2') }}) -join ''
+        "#,
+        escape_powershell_string(url),
+        PROBE_TIMEOUT_SECS
+    )
+}
+
+/// Accepts any response to the probe query; the response code does not
+/// matter as long as the server answered.
+fn check_response(message: &[u8]) -> Result<()> {
+    match message {
+        [0, 0, flags, ..] if message.len() >= 12 && flags & 0x80 != 0 => Ok(()),
+        _ => Err(DohProbeError::InvalidResponse),
+    }
+}
+
+fn base64url(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bits = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |acc, (i, &b)| acc | (b as u32) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            encoded.push(ALPHABET[(bits >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+    }
+    encoded
+}
+
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dns::types::{DnsEntry, DnsServerEntry, DohMode};
+
+    #[test]
+    fn test_base64url() {
+        assert_eq!(base64url(b""), "");
+        assert_eq!(base64url(b"f"), "Zg");
+        assert_eq!(base64url(b"fo"), "Zm8");
+        assert_eq!(base64url(b"foo"), "Zm9v");
+        assert_eq!(base64url(&[0xfb, 0xff]), "-_8");
+    }
+
+    #[test]
+    fn test_probe_url() {
+        // The query of RFC 8484 section 4.1.1 with the name changed.
+        let query = base64url(&encode_query(0, PROBE_NAME, TYPE_A));
+        assert!(query.starts_with("AAABAAABAAAAAAAAB2V4YW1wbGUDY29tAAAB"));
+
+        assert_eq!(
+            probe_url("https://dns.example/dns-query{?dns}").unwrap(),
+            format!("https://dns.example/dns-query?dns={}", query)
+        );
+        assert_eq!(
+            probe_url(" https://dns.example/q?ct=1 ").unwrap(),
+            format!("https://dns.example/q?ct=1&dns={}", query)
+        );
+        assert!(probe_url("http://dns.example/dns-query").is_err());
+        assert!(probe_url("https://dns.example/{path}").is_err());
+    }
+
+    #[test]
+    fn test_probe_script_escapes_url() {
+        let script = probe_script("https://dns.example/it's");
+        assert!(script.contains("-Uri 'https://dns.example/it''s'"));
+        assert!(script.contains("Accept = 'application/dns-message'"));
+    }
+
+    #[test]
+    fn test_check_response() {
+        let mut response = encode_query(0, PROBE_NAME, TYPE_A);
+        assert!(check_response(&response).is_err());
+
+        response[2] |= 0x80;
+        assert!(check_response(&response).is_ok());
+
+        // NXDOMAIN still proves that the endpoint answers.
+        response[3] |= 0x03;
+        assert!(check_response(&response).is_ok());
+
+        assert!(check_response(&response[..8]).is_err());
+        response[1] = 1;
+        assert!(check_response(&response).is_err());
+        assert!(check_response(b"<html>").is_err());
+    }
+
+    #[test]
+    fn test_decode_hex() {
+        assert_eq!(decode_hex("00ff8a"), Some(vec![0x00, 0xff, 0x8a]));
+        assert_eq!(decode_hex(""), Some(vec![]));
+        assert_eq!(decode_hex("abc"), None);
+        assert_eq!(decode_hex("zz"), None);
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_doh_templates_skips_plain_servers() {
+        let settings = DnsSettings {
+            ipv4: DnsEntry {
+                enabled: true,
+                unchanged: false,
+                servers: vec![DnsServerEntry {
+                    address: "1.1.1.1".to_string(),
+                    doh_mode: DohMode::Off,
+                    doh_template: "https://cloudflare-dns.com/dns-query".to_string(),
+                    allow_fallback: true,
+                    auto_upgrade: true,
+                }],
+            },
+            ..Default::default()
+        };
+        assert!(unreachable_doh_templates(&settings).await.is_empty());
+    }
+}
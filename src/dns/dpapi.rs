@@ -0,0 +1,156 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum DpapiError {
+    #[error("Failed to encrypt: {0}")]
+    Protect(String),
+    #[error("Failed to decrypt: {0}")]
+    Unprotect(String),
+}
+
+pub type Result<T> = std::result::Result<T, DpapiError>;
+
+/// Encrypts `data` with DPAPI so that only the current Windows user can
+/// decrypt it.
+#[cfg(target_os = "windows")]
+pub fn protect(data: &[u8]) -> Result<Vec<u8>> {
+    use windows::Win32::Security::Cryptography::{
+        CRYPT_INTEGER_BLOB, CRYPTPROTECT_UI_FORBIDDEN, CryptProtectData,
+    };
+    use windows::core::w;
+
+    let input = CRYPT_INTEGER_BLOB {
+        cbData: data.len() as u32,
+        pbData: data.as_ptr() as *mut u8,
+    };
+    let mut output = CRYPT_INTEGER_BLOB::default();
+    unsafe {
+        CryptProtectData(
+            &input,
+            w!("windns profiles"),
+            None,
+            None,
+            None,
+            CRYPTPROTECT_UI_FORBIDDEN,
+            &mut output,
+        )
+    }
+    .map_err(|e| DpapiError::Protect(e.to_string()))?;
+    Ok(take_blob(output))
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn protect(_data: &[u8]) -> Result<Vec<u8>> {
+    Err(DpapiError::Protect(
+        "Not supported on this platform".to_string(),
+    ))
+}
+
+/// Decrypts data encrypted by `protect` for the current Windows user.
+#[cfg(target_os = "windows")]
+pub fn unprotect(data: &[u8]) -> Result<Vec<u8>> {
+    use windows::Win32::Security::Cryptography::{
+        CRYPT_INTEGER_BLOB, CRYPTPROTECT_UI_FORBIDDEN, CryptUnprotectData,
+    };
+
+    let input = CRYPT_INTEGER_BLOB {
+        cbData: data.len() as u32,
+        pbData: data.as_ptr() as *mut u8,
+    };
+    let mut output = CRYPT_INTEGER_BLOB::default();
+    unsafe {
+        CryptUnprotectData(
+            &input,
+            None,
+            None,
+            None,
+            None,
+            CRYPTPROTECT_UI_FORBIDDEN,
+            &mut output,
+        )
+    }
+    .map_err(|e| DpapiError::Unprotect(e.to_string()))?;
+    Ok(take_blob(output))
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn unprotect(_data: &[u8]) -> Result<Vec<u8>> {
+    Err(DpapiError::Unprotect(
+        "Not supported on this platform".to_string(),
+    ))
+}
+
+/// Copies a blob allocated by DPAPI and frees it.
+#[cfg(target_os = "windows")]
+fn take_blob(blob: windows::Win32::Security::Cryptography::CRYPT_INTEGER_BLOB) -> Vec<u8> {
+    use windows::Win32::Foundation::{HLOCAL, LocalFree};
+
+    if blob.pbData.is_null() {
+        return Vec::new();
+    }
+    let data = unsafe { std::slice::from_raw_parts(blob.pbData, blob.cbData as usize) }.to_vec();
+    let _ = unsafe { LocalFree(Some(HLOCAL(blob.pbData.cast()))) };
+    data
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Standard base64 with padding, for storing encrypted data as text.
+pub fn encode_base64(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let group = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64_ALPHABET[(group >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+pub fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let text = text.trim_end_matches('=');
+    let mut out = Vec::with_capacity(text.len() * 3 / 4);
+    let mut group = 0u32;
+    let mut bits = 0;
+    for c in text.bytes() {
+        let value = BASE64_ALPHABET.iter().position(|&a| a == c)? as u32;
+        group = group << 6 | value;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((group >> bits) as u8);
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64_round_trip() {
+        for (data, text) in [
+            (&b""[..], ""),
+            (b"f", "Zg=="),
+            (b"fo", "Zm8="),
+            (b"foo", "Zm9v"),
+            (b"foobar", "Zm9vYmFy"),
+            (&[0xfb, 0xff], "+/8="),
+        ] {
+            assert_eq!(encode_base64(data), text);
+            assert_eq!(decode_base64(text).as_deref(), Some(data));
+        }
+        assert_eq!(decode_base64("Zm9v!"), None);
+    }
+}
@@ -0,0 +1,209 @@
+use crate::dns::types::DnsMode;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ElevationError {
+    #[error("Failed to restart as administrator: {0}")]
+    Relaunch(String),
+}
+
+pub type Result<T> = std::result::Result<T, ElevationError>;
+
+const INTERFACE_ARG: &str = "--interface";
+const PROFILE_ARG: &str = "--profile";
+const MODE_ARG: &str = "--mode";
+
+/// UI selection handed over to the elevated instance on restart, and kept
+/// in the config on exit to reopen where the user left off.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RelaunchSelection {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interface_guid: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dns_mode: Option<DnsMode>,
+}
+
+impl RelaunchSelection {
+    pub fn to_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(guid) = &self.interface_guid {
+            args.extend([INTERFACE_ARG.to_string(), guid.clone()]);
+        }
+        if let Some(id) = &self.profile_id {
+            args.extend([PROFILE_ARG.to_string(), id.clone()]);
+        }
+        if let Some(mode) = self.dns_mode {
+            args.extend([MODE_ARG.to_string(), mode.as_str().to_string()]);
+        }
+        args
+    }
+
+    /// Reads the selection from command-line arguments, ignoring anything
+    /// it does not recognize.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Self {
+        let mut selection = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                INTERFACE_ARG => selection.interface_guid = args.next(),
+                PROFILE_ARG => selection.profile_id = args.next(),
+                MODE_ARG => {
+                    selection.dns_mode = match args.next().as_deref() {
+                        Some("Automatic") => Some(DnsMode::Automatic),
+                        Some("Manual") => Some(DnsMode::Manual),
+                        _ => None,
+                    }
+                }
+                _ => {}
+            }
+        }
+        selection
+    }
+}
+
+/// Quotes each argument for the Windows command line. The values passed
+/// here are GUIDs, ids and mode names, which never contain quotes.
+fn command_line(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| format!("\"{}\"", arg))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(target_os = "windows")]
+pub fn is_elevated() -> bool {
+    use windows::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows::Win32::Security::{
+        GetTokenInformation, TOKEN_ELEVATION, TOKEN_QUERY, TokenElevation,
+    };
+    use windows::Win32::System::Threading::{GetCurrentProcess, OpenProcessToken};
+
+    unsafe {
+        let mut token = HANDLE::default();
+        if OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token).is_err() {
+            return false;
+        }
+
+        let mut elevation = TOKEN_ELEVATION::default();
+        let mut size = 0u32;
+        let result = GetTokenInformation(
+            token,
+            TokenElevation,
+            Some(&mut elevation as *mut TOKEN_ELEVATION as *mut std::ffi::c_void),
+            std::mem::size_of::<TOKEN_ELEVATION>() as u32,
+            &mut size,
+        );
+        let _ = CloseHandle(token);
+
+        result.is_ok() && elevation.TokenIsElevated != 0
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn is_elevated() -> bool {
+    true
+}
+
+/// Starts a new elevated instance through the UAC prompt. The caller is
+/// expected to close the current window once this succeeds.
+#[cfg(target_os = "windows")]
+pub fn relaunch_as_admin(selection: &RelaunchSelection) -> Result<()> {
+    use crate::dns::config::config_dir_args;
+    use crate::dns::trace::verbose_args;
+    use windows::Win32::UI::Shell::ShellExecuteW;
+    use windows::Win32::UI::WindowsAndMessaging::SW_SHOWNORMAL;
+    use windows::core::{HSTRING, PCWSTR, w};
+
+    let exe = std::env::current_exe().map_err(|e| ElevationError::Relaunch(e.to_string()))?;
+    let file = HSTRING::from(exe.as_os_str());
+    let mut args = selection.to_args();
+    args.extend(config_dir_args());
+    args.extend(verbose_args());
+    let parameters = HSTRING::from(command_line(&args));
+
+    let result = unsafe {
+        ShellExecuteW(
+            None,
+            w!("runas"),
+            &file,
+            &parameters,
+            PCWSTR::null(),
+            SW_SHOWNORMAL,
+        )
+    };
+
+    // ShellExecuteW reports success with a value greater than 32.
+    let code = result.0 as usize;
+    if code <= 32 {
+        return Err(ElevationError::Relaunch(format!(
+            "ShellExecuteW failed with code {}",
+            code
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn relaunch_as_admin(_selection: &RelaunchSelection) -> Result<()> {
+    Err(ElevationError::Relaunch(
+        "Not supported on this platform".to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_relaunch_selection_round_trip() {
+        let selection = RelaunchSelection {
+            interface_guid: Some("{GUID-7}".to_string()),
+            profile_id: Some("profile-1".to_string()),
+            dns_mode: Some(DnsMode::Manual),
+        };
+        assert_eq!(RelaunchSelection::from_args(selection.to_args()), selection);
+    }
+
+    #[test]
+    fn test_relaunch_selection_empty() {
+        let selection = RelaunchSelection::default();
+        assert!(selection.to_args().is_empty());
+        assert_eq!(RelaunchSelection::from_args(Vec::new()), selection);
+    }
+
+    #[test]
+    fn test_relaunch_selection_ignores_unknown_args() {
+        let selection = RelaunchSelection::from_args(args(&[
+            "--verbose",
+            "--mode",
+            "Hybrid",
+            "--interface",
+            "{GUID-7}",
+        ]));
+        assert_eq!(selection.interface_guid.as_deref(), Some("{GUID-7}"));
+        assert_eq!(selection.dns_mode, None);
+        assert_eq!(selection.profile_id, None);
+    }
+
+    #[test]
+    fn test_relaunch_selection_missing_value() {
+        let selection = RelaunchSelection::from_args(args(&["--profile"]));
+        assert_eq!(selection.profile_id, None);
+    }
+
+    #[test]
+    fn test_command_line_quotes_args() {
+        assert_eq!(
+            command_line(&args(&["--interface", "{GUID-7}"])),
+            "\"--interface\" \"{GUID-7}\""
+        );
+    }
+}
@@ -0,0 +1,95 @@
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio::net::{TcpStream, lookup_host};
+use tokio::time::timeout;
+
+#[derive(Error, Debug)]
+pub enum HealthError {
+    #[error("Could not resolve {0}: {1}")]
+    Resolution(String, std::io::Error),
+
+    #[error("{0} has no addresses")]
+    NoAddress(String),
+
+    #[error("Could not connect to {0} over HTTPS: {1}")]
+    Connect(String, std::io::Error),
+
+    #[error("{0} did not respond in time")]
+    Timeout(String),
+}
+
+pub type Result<T> = std::result::Result<T, HealthError>;
+
+const HTTPS_PORT: u16 = 443;
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Resolves `host` through the system resolver, and so through the DNS
+/// settings in effect including DoH and NRPT rules. Answers cached by
+/// Windows count as resolved.
+async fn resolve(host: &str) -> Result<Vec<SocketAddr>> {
+    let addresses: Vec<SocketAddr> = timeout(PROBE_TIMEOUT, lookup_host((host, HTTPS_PORT)))
+        .await
+        .map_err(|_| HealthError::Timeout(host.to_string()))?
+        .map_err(|e| HealthError::Resolution(host.to_string(), e))?
+        .collect();
+    if addresses.is_empty() {
+        return Err(HealthError::NoAddress(host.to_string()));
+    }
+    Ok(addresses)
+}
+
+/// Checks that `host` resolves with the DNS settings in effect.
+pub async fn check_host_resolves(host: &str) -> Result<()> {
+    resolve(host).await.map(|_| ())
+}
+
+/// The addresses `host` resolves to with the DNS settings in effect, in
+/// the order the resolver returned them.
+pub async fn resolve_host_addresses(host: &str) -> Result<Vec<IpAddr>> {
+    let mut addresses: Vec<IpAddr> = Vec::new();
+    for address in resolve(host).await? {
+        if !addresses.contains(&address.ip()) {
+            addresses.push(address.ip());
+        }
+    }
+    Ok(addresses)
+}
+
+/// Resolves `host` with the DNS settings in effect, then connects to its
+/// HTTPS port. Returns how long both took.
+pub async fn check_health(host: &str) -> Result<Duration> {
+    let start = Instant::now();
+    let addresses = resolve(host).await?;
+
+    let remaining = PROBE_TIMEOUT.saturating_sub(start.elapsed());
+    timeout(remaining, TcpStream::connect(&addresses[..]))
+        .await
+        .map_err(|_| HealthError::Timeout(host.to_string()))?
+        .map_err(|e| HealthError::Connect(host.to_string(), e))?;
+    Ok(start.elapsed())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dns::resolution_check::DEFAULT_CHECK_HOST;
+
+    #[tokio::test]
+    async fn test_check_health_unresolvable_host() {
+        assert!(check_health("windns-health.invalid").await.is_err());
+        assert!(check_host_resolves("windns-health.invalid").await.is_err());
+        assert!(
+            resolve_host_addresses("windns-health.invalid")
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_check_health() {
+        let elapsed = check_health(DEFAULT_CHECK_HOST).await.unwrap();
+        assert!(elapsed < PROBE_TIMEOUT);
+    }
+}
@@ -0,0 +1,167 @@
+use crate::dns::config::{self, default_config_dir};
+use crate::dns::trace::timestamp;
+use crate::dns::types::DnsSettings;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+const HISTORY_FILE: &str = "history.jsonl";
+/// Entries kept in memory for the History view. The file keeps them all.
+pub const MAX_HISTORY_ENTRIES: usize = 200;
+
+#[derive(Error, Debug)]
+pub enum HistoryError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    Config(#[from] config::ConfigError),
+}
+
+pub type Result<T> = std::result::Result<T, HistoryError>;
+
+/// One DNS change windns made, with the settings before and after.
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct HistoryEntry {
+    pub time: String,
+    pub interface_guid: String,
+    pub interface_name: String,
+    /// Settings the interface had before, or `None` if they could not be
+    /// read.
+    pub before: Option<DnsSettings>,
+    pub after: DnsSettings,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
+    /// Why the change failed, if it did.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl HistoryEntry {
+    /// An entry for a change made just now.
+    pub fn now(
+        interface_guid: &str,
+        interface_name: &str,
+        before: Option<DnsSettings>,
+        after: DnsSettings,
+    ) -> Self {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        Self {
+            time: timestamp(secs),
+            interface_guid: interface_guid.to_string(),
+            interface_name: interface_name.to_string(),
+            before,
+            after,
+            warning: None,
+            error: None,
+        }
+    }
+
+    /// Whether the settings before the change are known and can be put
+    /// back.
+    pub fn can_revert(&self) -> bool {
+        self.before.is_some()
+    }
+}
+
+/// Like the trace log, the history stays in the default config directory,
+/// since it describes this machine's adapters.
+pub fn history_path() -> config::Result<PathBuf> {
+    Ok(default_config_dir()?.join(HISTORY_FILE))
+}
+
+/// Adds `entry` at the end of the history file.
+pub fn append_history(entry: &HistoryEntry) -> Result<()> {
+    append_entry(&history_path()?, entry)
+}
+
+/// The latest entries of the history file, oldest first. Lines that cannot
+/// be read are skipped.
+pub fn load_history() -> Result<Vec<HistoryEntry>> {
+    read_entries(&history_path()?)
+}
+
+fn append_entry(path: &Path, entry: &HistoryEntry) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(line.as_bytes())?;
+    Ok(())
+}
+
+fn read_entries(path: &Path) -> Result<Vec<HistoryEntry>> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let entries: Vec<HistoryEntry> = text
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    let skip = entries.len().saturating_sub(MAX_HISTORY_ENTRIES);
+    Ok(entries.into_iter().skip(skip).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn entry(name: &str) -> HistoryEntry {
+        let mut after = DnsSettings::new();
+        after.ipv4.servers[0].address = "1.1.1.1".to_string();
+        HistoryEntry {
+            time: "2026-01-01 00:00:00 UTC".to_string(),
+            interface_guid: "{GUID}".to_string(),
+            interface_name: name.to_string(),
+            before: Some(DnsSettings::new()),
+            after,
+            warning: None,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_history_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("nested").join(HISTORY_FILE);
+        assert!(read_entries(&path).unwrap().is_empty());
+
+        let first = entry("Ethernet");
+        let mut second = entry("Wi-Fi");
+        second.before = None;
+        second.error = Some("Access denied".to_string());
+        append_entry(&path, &first).unwrap();
+        append_entry(&path, &second).unwrap();
+
+        assert_eq!(read_entries(&path).unwrap(), vec![first, second.clone()]);
+        assert!(!second.can_revert());
+    }
+
+    #[test]
+    fn test_read_entries_skips_bad_lines_and_keeps_latest() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(HISTORY_FILE);
+        fs::write(&path, "not json\n").unwrap();
+        for i in 0..MAX_HISTORY_ENTRIES + 2 {
+            append_entry(&path, &entry(&i.to_string())).unwrap();
+        }
+
+        let entries = read_entries(&path).unwrap();
+        assert_eq!(entries.len(), MAX_HISTORY_ENTRIES);
+        assert_eq!(entries[0].interface_name, "2");
+    }
+}
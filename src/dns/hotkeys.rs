@@ -0,0 +1,134 @@
+use tokio::sync::mpsc;
+
+/// Hotkeys are Ctrl+Alt+1 to Ctrl+Alt+9, identified by their digit.
+pub const HOTKEY_SLOTS: std::ops::RangeInclusive<u8> = 1..=9;
+
+pub fn hotkey_label(slot: u8) -> String {
+    format!("Ctrl+Alt+{}", slot)
+}
+
+/// System-wide Ctrl+Alt+<digit> hotkeys. Windows delivers hotkeys to the
+/// message queue of the thread that registered them, so they live on a
+/// thread of their own until the listener is dropped.
+pub struct HotkeyListener {
+    receiver: mpsc::UnboundedReceiver<u8>,
+    #[cfg(target_os = "windows")]
+    thread_id: u32,
+    #[cfg(not(target_os = "windows"))]
+    _sender: mpsc::UnboundedSender<u8>,
+}
+
+impl HotkeyListener {
+    /// Registers the hotkeys for `slots`. Also returns the slots that could
+    /// not be registered, usually because another program owns them.
+    #[cfg(target_os = "windows")]
+    pub fn new(slots: &[u8]) -> (Self, Vec<u8>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let (ready_sender, ready_receiver) = std::sync::mpsc::channel();
+        let slots = slots.to_vec();
+        let all_slots = slots.clone();
+
+        std::thread::spawn(move || run_hotkey_thread(&slots, sender, ready_sender));
+
+        let (thread_id, failed) = ready_receiver.recv().unwrap_or((0, all_slots));
+        (
+            Self {
+                receiver,
+                thread_id,
+            },
+            failed,
+        )
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    pub fn new(slots: &[u8]) -> (Self, Vec<u8>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        (
+            Self {
+                receiver,
+                _sender: sender,
+            },
+            slots.to_vec(),
+        )
+    }
+
+    /// Waits for the next hotkey press and returns its slot.
+    pub async fn pressed(&mut self) -> Option<u8> {
+        self.receiver.recv().await
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl Drop for HotkeyListener {
+    fn drop(&mut self) {
+        use windows::Win32::Foundation::{LPARAM, WPARAM};
+        use windows::Win32::UI::WindowsAndMessaging::{PostThreadMessageW, WM_QUIT};
+
+        if self.thread_id != 0 {
+            // The thread unregisters the hotkeys when its message loop ends.
+            let _ = unsafe { PostThreadMessageW(self.thread_id, WM_QUIT, WPARAM(0), LPARAM(0)) };
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn run_hotkey_thread(
+    slots: &[u8],
+    sender: mpsc::UnboundedSender<u8>,
+    ready: std::sync::mpsc::Sender<(u32, Vec<u8>)>,
+) {
+    use windows::Win32::System::Threading::GetCurrentThreadId;
+    use windows::Win32::UI::Input::KeyboardAndMouse::{
+        MOD_ALT, MOD_CONTROL, MOD_NOREPEAT, RegisterHotKey, UnregisterHotKey,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::{
+        GetMessageW, MSG, PM_NOREMOVE, PeekMessageW, WM_HOTKEY, WM_USER,
+    };
+
+    let mut msg = MSG::default();
+    // Creates the message queue before the thread ID is handed out, so
+    // that a quit message posted right away is not lost.
+    let _ = unsafe { PeekMessageW(&mut msg, None, WM_USER, WM_USER, PM_NOREMOVE) };
+    let thread_id = unsafe { GetCurrentThreadId() };
+
+    let mut registered = Vec::new();
+    let mut failed = Vec::new();
+    for &slot in slots {
+        let key = u32::from(b'0' + slot);
+        let result = unsafe {
+            RegisterHotKey(
+                None,
+                i32::from(slot),
+                MOD_CONTROL | MOD_ALT | MOD_NOREPEAT,
+                key,
+            )
+        };
+        if result.is_ok() {
+            registered.push(slot);
+        } else {
+            failed.push(slot);
+        }
+    }
+    if ready.send((thread_id, failed)).is_ok() {
+        while unsafe { GetMessageW(&mut msg, None, 0, 0) }.0 > 0 {
+            if msg.message == WM_HOTKEY && sender.send(msg.wParam.0 as u8).is_err() {
+                break;
+            }
+        }
+    }
+
+    for slot in registered {
+        let _ = unsafe { UnregisterHotKey(None, i32::from(slot)) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hotkey_label() {
+        assert_eq!(hotkey_label(1), "Ctrl+Alt+1");
+        assert_eq!(hotkey_label(*HOTKEY_SLOTS.end()), "Ctrl+Alt+9");
+    }
+}
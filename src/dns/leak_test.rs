@@ -0,0 +1,136 @@
+use crate::dns::commands::{command_error_message, run_powershell};
+use serde::Deserialize;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum LeakTestError {
+    #[error("{0}")]
+    RequestFailed(String),
+
+    #[error("The leak test service sent an invalid response")]
+    InvalidResponse,
+}
+
+pub type Result<T> = std::result::Result<T, LeakTestError>;
+
+/// Leak test service. Names under `<id>.bash.ws` are answered by it, and it
+/// reports the resolvers that asked for them.
+const LEAK_TEST_DOMAIN: &str = "bash.ws";
+const QUERY_COUNT: u32 = 10;
+
+/// A resolver seen by the leak test service.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LeakResolver {
+    pub ip: String,
+    pub country_name: String,
+    pub asn: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LeakTestResult {
+    /// Resolvers that forwarded the test queries to the service.
+    pub resolvers: Vec<LeakResolver>,
+    /// Summary written by the service, e.g. whether DNS is leaking.
+    pub conclusion: Option<String>,
+}
+
+/// One line of the service's report. Fields the service has no value for
+/// are null.
+#[derive(Deserialize)]
+struct LeakEntry {
+    #[serde(rename = "type")]
+    entry_type: String,
+    ip: Option<String>,
+    country_name: Option<String>,
+    asn: Option<String>,
+}
+
+/// Resolves unique names through the system resolver, as any application
+/// would, and asks the service which resolvers looked them up.
+pub async fn run_leak_test() -> Result<LeakTestResult> {
+    let output = run_powershell(&leak_test_script())
+        .await
+        .map_err(|e| LeakTestError::RequestFailed(command_error_message(e)))?;
+    parse_leak_test(&output)
+}
+
+fn leak_test_script() -> String {
+    format!(
+        r#"
+        $id = (Invoke-WebRequest -UseBasicParsing -Uri 'https://{domain}/id' -TimeoutSec 10).Content.Trim()
+        if ($id -notmatch '^[0-9A-Za-z]+$') {{ throw 'The leak test service sent an invalid test id' }}
+        1..{count} | ForEach-Object {{
+            Resolve-DnsName -Name "$_.$id.{domain}" -DnsOnly -NoHostsFile -QuickTimeout -ErrorAction SilentlyContinue | Out-Null
+        }}
+        (Invoke-WebRequest -UseBasicParsing -Uri "https://{domain}/dnsleak/test/$($id)?json" -TimeoutSec 10).Content
+        "#,
+        domain = LEAK_TEST_DOMAIN,
+        count = QUERY_COUNT
+    )
+}
+
+fn parse_leak_test(output: &str) -> Result<LeakTestResult> {
+    let entries: Vec<LeakEntry> =
+        serde_json::from_str(output.trim()).map_err(|_| LeakTestError::InvalidResponse)?;
+
+    let mut result = LeakTestResult::default();
+    for entry in entries {
+        match entry.entry_type.as_str() {
+            "dns" => result.resolvers.push(LeakResolver {
+                ip: entry.ip.unwrap_or_default(),
+                country_name: entry.country_name.unwrap_or_default(),
+                asn: entry.asn.unwrap_or_default(),
+            }),
+            // The service puts the conclusion text in the `ip` field.
+            "conclusion" => result.conclusion = entry.ip.filter(|text| !text.is_empty()),
+            _ => {}
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_leak_test() {
+        let output = r#"[
+            {"ip":"203.0.113.7","country":"JP","country_name":"Japan","asn":"AS64500 Example ISP","type":"ip"},
+            {"ip":"172.68.0.1","country":"JP","country_name":"Japan","asn":"AS13335 Cloudflare, Inc.","type":"dns"},
+            {"ip":"172.68.0.2","country":"JP","country_name":"Japan","asn":"AS13335 Cloudflare, Inc.","type":"dns"},
+            {"ip":"DNS is not leaking.","country":null,"country_name":null,"asn":null,"type":"conclusion"}
+        ]"#;
+
+        let result = parse_leak_test(output).unwrap();
+        assert_eq!(result.resolvers.len(), 2);
+        assert_eq!(
+            result.resolvers[0],
+            LeakResolver {
+                ip: "172.68.0.1".to_string(),
+                country_name: "Japan".to_string(),
+                asn: "AS13335 Cloudflare, Inc.".to_string(),
+            }
+        );
+        assert_eq!(result.conclusion.as_deref(), Some("DNS is not leaking."));
+    }
+
+    #[test]
+    fn test_parse_leak_test_without_resolvers() {
+        let result = parse_leak_test("[]").unwrap();
+        assert!(result.resolvers.is_empty());
+        assert_eq!(result.conclusion, None);
+
+        assert!(parse_leak_test("").is_err());
+        assert!(parse_leak_test("<html>").is_err());
+    }
+
+    #[test]
+    fn test_leak_test_script() {
+        let script = leak_test_script();
+        assert!(script.contains("https://bash.ws/id"));
+        assert!(script.contains("1..10 |"));
+        assert!(script.contains(r#"Resolve-DnsName -Name "$_.$id.bash.ws" -DnsOnly"#));
+        assert!(script.contains("https://bash.ws/dnsleak/test/$($id)?json"));
+    }
+}
@@ -0,0 +1,398 @@
+use thiserror::Error;
+use tokio::sync::mpsc;
+
+#[derive(Error, Debug)]
+pub enum LinkError {
+    #[error("Failed to register windns:// links: {0}")]
+    Registry(String),
+}
+
+pub type Result<T> = std::result::Result<T, LinkError>;
+
+const LINK_PREFIX: &str = "windns://";
+#[cfg(target_os = "windows")]
+const CLASSES_KEY: &str = r"Software\Classes\windns";
+#[cfg(target_os = "windows")]
+const COMMAND_KEY: &str = r"Software\Classes\windns\shell\open\command";
+
+/// Returns the first `windns://` link among command-line arguments, as
+/// Windows passes it when a link is opened.
+pub fn link_argument(args: impl IntoIterator<Item = String>) -> Option<String> {
+    args.into_iter()
+        .find(|arg| arg.to_ascii_lowercase().starts_with(LINK_PREFIX))
+}
+
+/// Link that undoes the profile change a network rule made last, opened
+/// from the notification about it.
+pub const UNDO_RULE_LINK: &str = "windns://undo";
+
+pub fn is_undo_rule_link(link: &str) -> bool {
+    link.trim_end_matches('/')
+        .eq_ignore_ascii_case(UNDO_RULE_LINK)
+}
+
+/// Reads what a link asks for: `windns://apply/<profile>` applies the
+/// profile with that name or ID and `windns://automatic` switches to
+/// Automatic, as `Some(None)`.
+pub fn parse_link(link: &str) -> Option<Option<String>> {
+    let prefix = link.get(..LINK_PREFIX.len())?;
+    if !prefix.eq_ignore_ascii_case(LINK_PREFIX) {
+        return None;
+    }
+    // Browsers and shortcuts may add a trailing slash.
+    let path = link[LINK_PREFIX.len()..].trim_end_matches('/');
+    let (action, target) = path.split_once('/').unwrap_or((path, ""));
+    match action.to_ascii_lowercase().as_str() {
+        "automatic" if target.is_empty() => Some(None),
+        "apply" => {
+            let profile = percent_decode(target)?;
+            (!profile.trim().is_empty()).then(|| Some(profile.trim().to_string()))
+        }
+        _ => None,
+    }
+}
+
+/// Decodes `%XX` escapes, such as the `%20` browsers put for spaces.
+fn percent_decode(text: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut rest = text.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = std::str::from_utf8(tail.get(..2)?).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+#[cfg(target_os = "windows")]
+fn link_command(exe: &std::path::Path) -> String {
+    format!("\"{}\" \"%1\"", exe.display())
+}
+
+/// Whether `windns://` links are registered to open windns.
+#[cfg(target_os = "windows")]
+pub fn link_handler_registered() -> bool {
+    use windows::Win32::Foundation::NO_ERROR;
+    use windows::Win32::System::Registry::{HKEY_CURRENT_USER, RRF_RT_REG_SZ, RegGetValueW};
+    use windows::core::{HSTRING, PCWSTR};
+
+    let subkey = HSTRING::from(COMMAND_KEY);
+    let mut size = 0u32;
+    let result = unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            &subkey,
+            PCWSTR::null(),
+            RRF_RT_REG_SZ,
+            None,
+            None,
+            Some(&mut size),
+        )
+    };
+    result == NO_ERROR
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn link_handler_registered() -> bool {
+    false
+}
+
+/// Registers windns as the handler of `windns://` links for the current
+/// user, or removes the registration.
+#[cfg(target_os = "windows")]
+pub fn set_link_handler_registered(registered: bool) -> Result<()> {
+    use windows::Win32::Foundation::{ERROR_FILE_NOT_FOUND, NO_ERROR, WIN32_ERROR};
+    use windows::Win32::System::Registry::{
+        HKEY_CURRENT_USER, REG_SZ, RegDeleteTreeW, RegSetKeyValueW,
+    };
+    use windows::core::{HSTRING, PCWSTR};
+
+    let set_value = |subkey: &str, name: Option<&str>, data: &str| -> WIN32_ERROR {
+        let subkey = HSTRING::from(subkey);
+        let name = name.map(HSTRING::from);
+        let data: Vec<u16> = data.encode_utf16().chain(std::iter::once(0)).collect();
+        unsafe {
+            RegSetKeyValueW(
+                HKEY_CURRENT_USER,
+                &subkey,
+                name.as_ref()
+                    .map_or(PCWSTR::null(), |name| PCWSTR(name.as_ptr())),
+                REG_SZ.0,
+                Some(data.as_ptr().cast()),
+                (data.len() * 2) as u32,
+            )
+        }
+    };
+
+    let result = if registered {
+        let exe = std::env::current_exe().map_err(|e| LinkError::Registry(e.to_string()))?;
+        [
+            set_value(CLASSES_KEY, None, "URL:windns"),
+            set_value(CLASSES_KEY, Some("URL Protocol"), ""),
+            set_value(COMMAND_KEY, None, &link_command(&exe)),
+        ]
+        .into_iter()
+        .find(|&result| result != NO_ERROR)
+        .unwrap_or(NO_ERROR)
+    } else {
+        match unsafe { RegDeleteTreeW(HKEY_CURRENT_USER, &HSTRING::from(CLASSES_KEY)) } {
+            ERROR_FILE_NOT_FOUND => NO_ERROR,
+            result => result,
+        }
+    };
+
+    if result != NO_ERROR {
+        return Err(LinkError::Registry(format!("registry error {}", result.0)));
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn set_link_handler_registered(_registered: bool) -> Result<()> {
+    Err(LinkError::Registry(
+        "Not supported on this platform".to_string(),
+    ))
+}
+
+/// Class of the hidden window a running windns receives links through.
+#[cfg(target_os = "windows")]
+const LINK_WINDOW_CLASS: windows::core::PCWSTR = windows::core::w!("windns-links");
+
+/// Hands a link to the windns that is already running. Returns false if
+/// there is none, or it did not take the link.
+#[cfg(target_os = "windows")]
+pub fn forward_link(link: &str) -> bool {
+    use windows::Win32::Foundation::{LPARAM, WPARAM};
+    use windows::Win32::System::DataExchange::COPYDATASTRUCT;
+    use windows::Win32::UI::WindowsAndMessaging::{
+        FindWindowExW, HWND_MESSAGE, SendMessageW, WM_COPYDATA,
+    };
+    use windows::core::PCWSTR;
+
+    let Ok(window) =
+        (unsafe { FindWindowExW(Some(HWND_MESSAGE), None, LINK_WINDOW_CLASS, PCWSTR::null()) })
+    else {
+        return false;
+    };
+    let data = COPYDATASTRUCT {
+        dwData: 0,
+        cbData: link.len() as u32,
+        lpData: link.as_ptr() as *mut _,
+    };
+    let result = unsafe {
+        SendMessageW(
+            window,
+            WM_COPYDATA,
+            Some(WPARAM(0)),
+            Some(LPARAM(&data as *const COPYDATASTRUCT as isize)),
+        )
+    };
+    result.0 != 0
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn forward_link(_link: &str) -> bool {
+    false
+}
+
+/// Receives the links opened while windns is running, which later
+/// instances hand over through `forward_link`. Like hotkeys, they arrive
+/// on a thread of their own, through a hidden window, until the listener
+/// is dropped.
+pub struct LinkListener {
+    receiver: mpsc::UnboundedReceiver<String>,
+    #[cfg(target_os = "windows")]
+    thread_id: u32,
+    #[cfg(not(target_os = "windows"))]
+    _sender: mpsc::UnboundedSender<String>,
+}
+
+impl LinkListener {
+    #[cfg(target_os = "windows")]
+    pub fn start() -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let (ready_sender, ready_receiver) = std::sync::mpsc::channel();
+
+        std::thread::spawn(move || run_link_thread(sender, ready_sender));
+
+        Self {
+            receiver,
+            thread_id: ready_receiver.recv().unwrap_or(0),
+        }
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    pub fn start() -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        Self {
+            receiver,
+            _sender: sender,
+        }
+    }
+
+    /// Waits for the next link.
+    pub async fn opened(&mut self) -> Option<String> {
+        self.receiver.recv().await
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl Drop for LinkListener {
+    fn drop(&mut self) {
+        use windows::Win32::Foundation::{LPARAM, WPARAM};
+        use windows::Win32::UI::WindowsAndMessaging::{PostThreadMessageW, WM_QUIT};
+
+        if self.thread_id != 0 {
+            let _ = unsafe { PostThreadMessageW(self.thread_id, WM_QUIT, WPARAM(0), LPARAM(0)) };
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+thread_local! {
+    static LINK_SENDER: std::cell::RefCell<Option<mpsc::UnboundedSender<String>>> =
+        const { std::cell::RefCell::new(None) };
+}
+
+#[cfg(target_os = "windows")]
+unsafe extern "system" fn link_window_proc(
+    window: windows::Win32::Foundation::HWND,
+    message: u32,
+    wparam: windows::Win32::Foundation::WPARAM,
+    lparam: windows::Win32::Foundation::LPARAM,
+) -> windows::Win32::Foundation::LRESULT {
+    use windows::Win32::Foundation::LRESULT;
+    use windows::Win32::System::DataExchange::COPYDATASTRUCT;
+    use windows::Win32::UI::WindowsAndMessaging::{DefWindowProcW, WM_COPYDATA};
+
+    if message != WM_COPYDATA {
+        return unsafe { DefWindowProcW(window, message, wparam, lparam) };
+    }
+    let data = unsafe { &*(lparam.0 as *const COPYDATASTRUCT) };
+    let bytes = if data.lpData.is_null() {
+        &[][..]
+    } else {
+        unsafe { std::slice::from_raw_parts(data.lpData as *const u8, data.cbData as usize) }
+    };
+    let link = String::from_utf8_lossy(bytes).into_owned();
+    let sent = LINK_SENDER.with(|sender| {
+        sender
+            .borrow()
+            .as_ref()
+            .is_some_and(|sender| sender.send(link).is_ok())
+    });
+    LRESULT(sent as isize)
+}
+
+#[cfg(target_os = "windows")]
+fn run_link_thread(sender: mpsc::UnboundedSender<String>, ready: std::sync::mpsc::Sender<u32>) {
+    use windows::Win32::System::Threading::GetCurrentThreadId;
+    use windows::Win32::UI::WindowsAndMessaging::{
+        ChangeWindowMessageFilterEx, CreateWindowExW, DestroyWindow, DispatchMessageW, GetMessageW,
+        HWND_MESSAGE, MSG, MSGFLT_ALLOW, PM_NOREMOVE, PeekMessageW, RegisterClassW,
+        WINDOW_EX_STYLE, WINDOW_STYLE, WM_COPYDATA, WM_USER, WNDCLASSW,
+    };
+    use windows::core::PCWSTR;
+
+    let mut msg = MSG::default();
+    // Creates the message queue before the thread ID is handed out, as the
+    // hotkey thread does.
+    let _ = unsafe { PeekMessageW(&mut msg, None, WM_USER, WM_USER, PM_NOREMOVE) };
+    let thread_id = unsafe { GetCurrentThreadId() };
+    LINK_SENDER.with(|cell| *cell.borrow_mut() = Some(sender));
+
+    let class = WNDCLASSW {
+        lpfnWndProc: Some(link_window_proc),
+        lpszClassName: LINK_WINDOW_CLASS,
+        ..Default::default()
+    };
+    let _ = unsafe { RegisterClassW(&class) };
+    let window = unsafe {
+        CreateWindowExW(
+            WINDOW_EX_STYLE::default(),
+            LINK_WINDOW_CLASS,
+            PCWSTR::null(),
+            WINDOW_STYLE::default(),
+            0,
+            0,
+            0,
+            0,
+            Some(HWND_MESSAGE),
+            None,
+            None,
+            None,
+        )
+    };
+    let Ok(window) = window else {
+        let _ = ready.send(0);
+        return;
+    };
+    // Links are opened by a non-elevated windns, which Windows would
+    // otherwise keep from sending messages to an elevated one.
+    let _ = unsafe { ChangeWindowMessageFilterEx(window, WM_COPYDATA, MSGFLT_ALLOW, None) };
+
+    if ready.send(thread_id).is_ok() {
+        while unsafe { GetMessageW(&mut msg, None, 0, 0) }.0 > 0 {
+            unsafe { DispatchMessageW(&msg) };
+        }
+    }
+
+    let _ = unsafe { DestroyWindow(window) };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_link_argument() {
+        let args = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            link_argument(args(&["--minimized", "WinDNS://apply/Home"])),
+            Some("WinDNS://apply/Home".to_string())
+        );
+        assert_eq!(link_argument(args(&["--interface", "{GUID-1}"])), None);
+    }
+
+    #[test]
+    fn test_parse_link() {
+        assert_eq!(
+            parse_link("windns://apply/Cloudflare"),
+            Some(Some("Cloudflare".to_string()))
+        );
+        assert_eq!(
+            parse_link("WINDNS://Apply/Home%20Network/"),
+            Some(Some("Home Network".to_string()))
+        );
+        assert_eq!(
+            parse_link("windns://apply/%E8%87%AA%E5%AE%85"),
+            Some(Some("自宅".to_string()))
+        );
+        assert_eq!(parse_link("windns://automatic"), Some(None));
+        assert_eq!(parse_link("windns://automatic/"), Some(None));
+    }
+
+    #[test]
+    fn test_is_undo_rule_link() {
+        assert!(is_undo_rule_link("windns://undo"));
+        assert!(is_undo_rule_link("WINDNS://Undo/"));
+        assert!(!is_undo_rule_link("windns://undo/Home"));
+        assert_eq!(parse_link("windns://undo"), None);
+    }
+
+    #[test]
+    fn test_parse_link_rejects_invalid_links() {
+        assert_eq!(parse_link("windns://apply/"), None);
+        assert_eq!(parse_link("windns://apply/%2"), None);
+        assert_eq!(parse_link("windns://apply/%FF"), None);
+        assert_eq!(parse_link("windns://remove/Home"), None);
+        assert_eq!(parse_link("windns://automatic/Home"), None);
+        assert_eq!(parse_link("https://apply/Home"), None);
+        assert_eq!(parse_link("win"), None);
+    }
+}
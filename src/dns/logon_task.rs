@@ -0,0 +1,114 @@
+use crate::dns::commands::{command_error_message, escape_powershell_string, run_powershell};
+use crate::dns::links::{link_argument, parse_link};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum LogonTaskError {
+    #[error("Failed to update the logon task: {0}")]
+    Command(String),
+}
+
+pub type Result<T> = std::result::Result<T, LogonTaskError>;
+
+/// Name of the task, per user so that each account can have its own.
+const TASK_NAME_SCRIPT: &str = "$name = 'windns-logon-' + $env:USERNAME";
+
+/// Arguments the task starts windns with: minimized, with a link that
+/// applies the profile once windns has loaded.
+fn task_arguments(profile_id: &str) -> String {
+    format!("--minimized \"windns://apply/{}\"", profile_id)
+}
+
+/// Profile ID in the arguments of a task created by `task_arguments`.
+fn profile_from_arguments(arguments: &str) -> Option<String> {
+    let args = arguments
+        .split_whitespace()
+        .map(|arg| arg.trim_matches('"').to_string());
+    parse_link(&link_argument(args)?)?
+}
+
+/// Profile that the scheduled task applies at logon, or `None` when there
+/// is no task.
+pub async fn logon_task_profile() -> Result<Option<String>> {
+    let script = format!(
+        r#"
+        {TASK_NAME_SCRIPT}
+        $task = Get-ScheduledTask -TaskName $name -ErrorAction SilentlyContinue
+        if ($task) {{ $task.Actions[0].Arguments }}
+        "#
+    );
+    let output = run_powershell(&script)
+        .await
+        .map_err(|e| LogonTaskError::Command(command_error_message(e)))?;
+    Ok(profile_from_arguments(output.trim()))
+}
+
+fn register_script(exe: &str, profile_id: &str) -> String {
+    format!(
+        r#"
+        {TASK_NAME_SCRIPT}
+        $user = "$env:USERDOMAIN\$env:USERNAME"
+        $action = New-ScheduledTaskAction -Execute '{exe}' -Argument '{arguments}'
+        $trigger = New-ScheduledTaskTrigger -AtLogOn -User $user
+        $principal = New-ScheduledTaskPrincipal -UserId $user -LogonType Interactive -RunLevel Highest
+        $settings = New-ScheduledTaskSettingsSet -AllowStartIfOnBatteries -DontStopIfGoingOnBatteries -ExecutionTimeLimit ([TimeSpan]::Zero)
+        Register-ScheduledTask -TaskName $name -Action $action -Trigger $trigger -Principal $principal -Settings $settings -Description 'Starts windns and applies a DNS profile at logon' -Force | Out-Null
+        "#,
+        exe = escape_powershell_string(exe),
+        arguments = escape_powershell_string(&task_arguments(profile_id)),
+    )
+}
+
+fn unregister_script() -> String {
+    format!(
+        r#"
+        {TASK_NAME_SCRIPT}
+        if (Get-ScheduledTask -TaskName $name -ErrorAction SilentlyContinue) {{
+            Unregister-ScheduledTask -TaskName $name -Confirm:$false
+        }}
+        "#
+    )
+}
+
+/// Creates a scheduled task that starts windns with highest privileges at
+/// logon and applies `profile_id`, so no UAC prompt is shown, or removes
+/// the task for `None`. Changing tasks needs administrator rights.
+pub async fn set_logon_task(profile_id: Option<&str>) -> Result<()> {
+    let script = match profile_id {
+        Some(id) => {
+            let exe =
+                std::env::current_exe().map_err(|e| LogonTaskError::Command(e.to_string()))?;
+            register_script(&exe.to_string_lossy(), id)
+        }
+        None => unregister_script(),
+    };
+    run_powershell(&script)
+        .await
+        .map_err(|e| LogonTaskError::Command(command_error_message(e)))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_task_arguments_round_trip() {
+        let id = "0b7c6c1e-5f0e-4a53-9d57-0a3f1f6c2b11";
+        assert_eq!(
+            profile_from_arguments(&task_arguments(id)).as_deref(),
+            Some(id)
+        );
+        assert_eq!(profile_from_arguments(""), None);
+        assert_eq!(profile_from_arguments("--minimized"), None);
+        assert_eq!(profile_from_arguments("\"windns://automatic\""), None);
+    }
+
+    #[test]
+    fn test_register_script_escapes_values() {
+        let script = register_script(r"C:\Users\O'Brien\windns.exe", "id-1");
+        assert!(script.contains(r"-Execute 'C:\Users\O''Brien\windns.exe'"));
+        assert!(script.contains(r#"-Argument '--minimized "windns://apply/id-1"'"#));
+        assert!(script.contains("-RunLevel Highest"));
+    }
+}
@@ -0,0 +1,392 @@
+use crate::dns::ddr::Reader;
+use crate::dns::resolution_check::{ResolutionCheckError, Result, exchange, parse_response};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::time::Duration;
+
+/// Record types offered by the lookup tool.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RecordType {
+    #[default]
+    A,
+    Aaaa,
+    Cname,
+    Mx,
+    Txt,
+    Ns,
+    Soa,
+    Srv,
+    Ptr,
+}
+
+impl RecordType {
+    pub const ALL: [RecordType; 9] = [
+        RecordType::A,
+        RecordType::Aaaa,
+        RecordType::Cname,
+        RecordType::Mx,
+        RecordType::Txt,
+        RecordType::Ns,
+        RecordType::Soa,
+        RecordType::Srv,
+        RecordType::Ptr,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RecordType::A => "A",
+            RecordType::Aaaa => "AAAA",
+            RecordType::Cname => "CNAME",
+            RecordType::Mx => "MX",
+            RecordType::Txt => "TXT",
+            RecordType::Ns => "NS",
+            RecordType::Soa => "SOA",
+            RecordType::Srv => "SRV",
+            RecordType::Ptr => "PTR",
+        }
+    }
+
+    pub fn parse(text: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|t| t.as_str().eq_ignore_ascii_case(text))
+    }
+
+    fn code(&self) -> u16 {
+        match self {
+            RecordType::A => 1,
+            RecordType::Ns => 2,
+            RecordType::Cname => 5,
+            RecordType::Soa => 6,
+            RecordType::Ptr => 12,
+            RecordType::Mx => 15,
+            RecordType::Txt => 16,
+            RecordType::Aaaa => 28,
+            RecordType::Srv => 33,
+        }
+    }
+}
+
+/// Contents of the lookup form.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LookupQuery {
+    pub name: String,
+    pub record_type: RecordType,
+    /// Server to ask; empty means the one currently in use.
+    pub server: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LookupRecord {
+    pub name: String,
+    pub record_type: String,
+    pub ttl: u32,
+    pub data: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LookupResponse {
+    pub server: String,
+    /// Response code, e.g. `NOERROR` or `NXDOMAIN`.
+    pub status: String,
+    pub records: Vec<LookupRecord>,
+    pub elapsed: Duration,
+}
+
+/// Looks up `name` at the DNS server at `server`, the way `dig @server`
+/// would. For PTR lookups an IP address is turned into its reverse name.
+pub async fn lookup(server: &str, name: &str, record_type: RecordType) -> Result<LookupResponse> {
+    let name = query_name(name, record_type)?;
+    let (id, message, elapsed) = exchange(server, &name, record_type.code()).await?;
+    let (rcode, _) = parse_response(id, &message)?;
+    let records = parse_answers(&message).ok_or(ResolutionCheckError::InvalidResponse)?;
+
+    Ok(LookupResponse {
+        server: server.trim().to_string(),
+        status: rcode_name(rcode),
+        records,
+        elapsed,
+    })
+}
+
+/// Checks `name` and, for PTR lookups of an address, builds the reverse
+/// name. Underscores are allowed, as in SRV names.
+fn query_name(name: &str, record_type: RecordType) -> Result<String> {
+    let name = name.trim().trim_end_matches('.');
+    if record_type == RecordType::Ptr
+        && let Ok(ip) = name.parse::<IpAddr>()
+    {
+        return Ok(reverse_name(ip));
+    }
+
+    let valid = !name.is_empty()
+        && name.len() <= 253
+        && name.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && label
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        });
+    if valid {
+        Ok(name.to_string())
+    } else {
+        Err(ResolutionCheckError::InvalidHost(name.to_string()))
+    }
+}
+
+fn reverse_name(ip: IpAddr) -> String {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, c, d] = ip.octets();
+            format!("{}.{}.{}.{}.in-addr.arpa", d, c, b, a)
+        }
+        IpAddr::V6(ip) => {
+            let nibbles: Vec<String> = ip
+                .octets()
+                .iter()
+                .rev()
+                .flat_map(|b| [b & 0x0f, b >> 4])
+                .map(|n| format!("{:x}", n))
+                .collect();
+            format!("{}.ip6.arpa", nibbles.join("."))
+        }
+    }
+}
+
+fn rcode_name(rcode: u16) -> String {
+    match rcode {
+        0 => "NOERROR".to_string(),
+        1 => "FORMERR".to_string(),
+        2 => "SERVFAIL".to_string(),
+        3 => "NXDOMAIN".to_string(),
+        4 => "NOTIMP".to_string(),
+        5 => "REFUSED".to_string(),
+        other => format!("RCODE {}", other),
+    }
+}
+
+/// Reads the answer section of a response. Returns `None` if the message
+/// is malformed.
+fn parse_answers(message: &[u8]) -> Option<Vec<LookupRecord>> {
+    let mut reader = Reader::new(message);
+    reader.skip(4).ok()?;
+    let question_count = reader.u16().ok()?;
+    let answer_count = reader.u16().ok()?;
+    reader.skip(4).ok()?;
+
+    for _ in 0..question_count {
+        reader.name().ok()?;
+        reader.skip(4).ok()?;
+    }
+
+    let mut records = Vec::new();
+    for _ in 0..answer_count {
+        let name = reader.name().ok()?;
+        let code = reader.u16().ok()?;
+        reader.skip(2).ok()?;
+        let ttl = reader.u32().ok()?;
+        let length = reader.u16().ok()? as usize;
+        let end = reader.pos + length;
+        if end > message.len() {
+            return None;
+        }
+
+        let record_type = RecordType::ALL.into_iter().find(|t| t.code() == code);
+        let data = match record_type {
+            Some(record_type) => record_data(&mut reader, record_type, length)?,
+            None => String::new(),
+        };
+        records.push(LookupRecord {
+            name,
+            record_type: record_type
+                .map(|t| t.as_str().to_string())
+                .unwrap_or_else(|| format!("TYPE{}", code)),
+            ttl,
+            data,
+        });
+        reader.pos = end;
+    }
+    Some(records)
+}
+
+fn record_data(reader: &mut Reader, record_type: RecordType, length: usize) -> Option<String> {
+    let data = match record_type {
+        RecordType::A => {
+            let b: [u8; 4] = reader.bytes(4).ok()?.try_into().ok()?;
+            Ipv4Addr::from(b).to_string()
+        }
+        RecordType::Aaaa => {
+            let b: [u8; 16] = reader.bytes(16).ok()?.try_into().ok()?;
+            Ipv6Addr::from(b).to_string()
+        }
+        RecordType::Cname | RecordType::Ns | RecordType::Ptr => reader.name().ok()?,
+        RecordType::Mx => {
+            let preference = reader.u16().ok()?;
+            format!("{} {}", preference, reader.name().ok()?)
+        }
+        RecordType::Txt => {
+            let end = reader.pos + length;
+            let mut strings = Vec::new();
+            while reader.pos < end {
+                let len = reader.bytes(1).ok()?[0] as usize;
+                let text = reader.bytes(len).ok()?;
+                strings.push(format!("\"{}\"", String::from_utf8_lossy(text)));
+            }
+            strings.join(" ")
+        }
+        RecordType::Soa => {
+            let mname = reader.name().ok()?;
+            let rname = reader.name().ok()?;
+            let numbers = (0..5)
+                .map(|_| reader.u32().map(|n| n.to_string()))
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .ok()?;
+            format!("{} {} {}", mname, rname, numbers.join(" "))
+        }
+        RecordType::Srv => {
+            let priority = reader.u16().ok()?;
+            let weight = reader.u16().ok()?;
+            let port = reader.u16().ok()?;
+            format!("{} {} {} {}", priority, weight, port, reader.name().ok()?)
+        }
+    };
+    Some(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dns::ddr::encode_query;
+
+    fn encode_name(name: &str) -> Vec<u8> {
+        let mut encoded = Vec::new();
+        for label in name.split('.') {
+            encoded.push(label.len() as u8);
+            encoded.extend_from_slice(label.as_bytes());
+        }
+        encoded.push(0);
+        encoded
+    }
+
+    fn answer(code: u16, ttl: u32, rdata: &[u8]) -> Vec<u8> {
+        // Name compressed to the question at offset 12.
+        let mut record = vec![0xc0, 12];
+        record.extend_from_slice(&code.to_be_bytes());
+        record.extend_from_slice(&1u16.to_be_bytes());
+        record.extend_from_slice(&ttl.to_be_bytes());
+        record.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+        record.extend_from_slice(rdata);
+        record
+    }
+
+    fn response(answers: &[Vec<u8>]) -> Vec<u8> {
+        let mut message = encode_query(1, "example.com", 1);
+        message[2..4].copy_from_slice(&0x8180u16.to_be_bytes());
+        message[6..8].copy_from_slice(&(answers.len() as u16).to_be_bytes());
+        for record in answers {
+            message.extend_from_slice(record);
+        }
+        message
+    }
+
+    #[test]
+    fn test_record_type_parse() {
+        assert_eq!(RecordType::parse("aaaa"), Some(RecordType::Aaaa));
+        assert_eq!(RecordType::parse("MX"), Some(RecordType::Mx));
+        assert_eq!(RecordType::parse("ANY"), None);
+        assert!(
+            RecordType::ALL
+                .iter()
+                .all(|t| RecordType::parse(t.as_str()) == Some(*t))
+        );
+    }
+
+    #[test]
+    fn test_parse_answers() {
+        let mut mx = 10u16.to_be_bytes().to_vec();
+        mx.extend(encode_name("mail.example.com"));
+        let message = response(&[
+            answer(1, 300, &[93, 184, 215, 14]),
+            answer(
+                28,
+                60,
+                &[0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1],
+            ),
+            answer(15, 3600, &mx),
+            answer(16, 30, b"\x05hello\x05world"),
+            answer(99, 30, &[1, 2]),
+        ]);
+
+        let records = parse_answers(&message).unwrap();
+        let data: Vec<_> = records.iter().map(|r| r.data.as_str()).collect();
+        assert_eq!(
+            data,
+            vec![
+                "93.184.215.14",
+                "2001:db8::1",
+                "10 mail.example.com",
+                "\"hello\" \"world\"",
+                ""
+            ]
+        );
+        assert_eq!(records[0].name, "example.com");
+        assert_eq!(records[0].record_type, "A");
+        assert_eq!(records[0].ttl, 300);
+        assert_eq!(records[4].record_type, "TYPE99");
+    }
+
+    #[test]
+    fn test_parse_answers_srv_and_soa() {
+        let mut srv = [0, 10, 0, 5, 0x13, 0xc4].to_vec();
+        srv.extend(encode_name("sip.example.com"));
+        let mut soa = encode_name("ns.example.com");
+        soa.extend(encode_name("admin.example.com"));
+        for n in [2024u32, 7200, 3600, 1209600, 300] {
+            soa.extend(n.to_be_bytes());
+        }
+
+        let records = parse_answers(&response(&[answer(33, 1, &srv), answer(6, 1, &soa)])).unwrap();
+        assert_eq!(records[0].data, "10 5 5060 sip.example.com");
+        assert_eq!(
+            records[1].data,
+            "ns.example.com admin.example.com 2024 7200 3600 1209600 300"
+        );
+    }
+
+    #[test]
+    fn test_parse_answers_truncated() {
+        let mut message = response(&[answer(1, 300, &[93, 184, 215, 14])]);
+        message.truncate(message.len() - 2);
+        assert!(parse_answers(&message).is_none());
+    }
+
+    #[test]
+    fn test_query_name() {
+        assert_eq!(
+            query_name(" Example.com. ", RecordType::A).unwrap(),
+            "Example.com"
+        );
+        assert_eq!(
+            query_name("_sip._tcp.example.com", RecordType::Srv).unwrap(),
+            "_sip._tcp.example.com"
+        );
+        assert_eq!(
+            query_name("192.0.2.1", RecordType::Ptr).unwrap(),
+            "1.2.0.192.in-addr.arpa"
+        );
+        assert!(
+            query_name("2001:db8::1", RecordType::Ptr)
+                .unwrap()
+                .starts_with("1.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.8.b.d.0.1.0.0.2")
+        );
+        assert!(query_name("", RecordType::A).is_err());
+        assert!(query_name("bad host", RecordType::A).is_err());
+        assert!(query_name("a..b", RecordType::A).is_err());
+    }
+
+    #[test]
+    fn test_rcode_name() {
+        assert_eq!(rcode_name(3), "NXDOMAIN");
+        assert_eq!(rcode_name(9), "RCODE 9");
+    }
+}
@@ -1,18 +1,108 @@
+pub mod backend;
+pub mod capabilities;
+pub mod cli;
 pub mod commands;
 pub mod config;
+pub mod control;
+pub mod ddr;
+pub mod dnssec;
+pub mod doh_probe;
+pub mod dpapi;
+pub mod elevation;
+pub mod health;
+pub mod history;
+pub mod hotkeys;
+pub mod leak_test;
+pub mod links;
+pub mod logon_task;
+pub mod lookup;
 pub mod network;
+pub mod network_rules;
+pub mod notification;
+pub mod nrpt;
+pub mod policy;
+pub mod providers;
+pub mod queue;
+pub mod report;
+pub mod reset;
+pub mod resolution_check;
+pub mod scheduler;
+pub mod startup;
+pub mod temporary;
+pub mod theme;
+pub mod trace;
 pub mod types;
+pub mod updater;
 pub mod validation;
 pub mod window;
 
+pub use backend::{DnsBackend, PreviewStep, SystemBackend};
+pub use capabilities::Capabilities;
+pub use cli::{
+    CliApplyResult, CliCommand, CliError, apply_text, cli_command, profiles_text, status_text,
+};
 pub use commands::{
-    DnsCommandError, clear_dns_cache, get_current_dns, set_dns_automatic, set_dns_with_settings,
+    ApplyStep, DnsCommandError, apply_dns_settings, clear_dns_cache, get_clipboard_text,
+    new_doh_servers, remove_unused_doh_servers, run_hook_command, set_clipboard_text,
+    set_command_timeout, set_dns_with_settings, snapshot_dns_settings,
+};
+pub use config::{
+    ConfigBackup, ConfigFormat, ConfigLocation, MAX_CONFIG_BACKUPS, SkippedProfile, approve_hooks,
+    backup_config, config_dir_args, config_dir_argument, config_format, config_location,
+    export_profiles, hooks_approved, import_profiles, list_config_backups, load_config,
+    load_config_with_skipped, move_config, restore_config_backup, save_config,
+    set_config_dir_argument, set_config_format, update_config,
+};
+pub use control::{
+    ControlCall, ControlProfile, ControlRequest, ControlResponse, ControlServer, ControlStatus,
+};
+pub use ddr::discover_doh_template;
+pub use dnssec::{DnssecStatus, check_dnssec};
+pub use doh_probe::unreachable_doh_templates;
+pub use elevation::{RelaunchSelection, is_elevated, relaunch_as_admin};
+pub use health::{check_health, check_host_resolves, resolve_host_addresses};
+pub use history::{HistoryEntry, MAX_HISTORY_ENTRIES, append_history, load_history};
+pub use hotkeys::{HOTKEY_SLOTS, HotkeyListener, hotkey_label};
+pub use leak_test::{LeakTestResult, run_leak_test};
+pub use links::{
+    LinkListener, UNDO_RULE_LINK, forward_link, is_undo_rule_link, link_argument,
+    link_handler_registered, parse_link, set_link_handler_registered,
+};
+pub use logon_task::{logon_task_profile, set_logon_task};
+pub use lookup::{LookupQuery, LookupResponse, RecordType, lookup};
+pub use network::{
+    InterfaceDetails, InterfaceWatcher, format_link_speed, get_dns_server_addresses,
+    get_interface_details, get_network_interfaces,
+};
+pub use network_rules::{NetworkCondition, NetworkInfo, NetworkRule, NetworkRules, detect_network};
+pub use notification::{NotificationAction, show_notification};
+pub use policy::{DnsPolicy, detect_dns_policies, overriding_policies};
+pub use providers::{
+    DnsProvider, ProviderDraft, find_provider, providers, providers_from_doh_servers,
+};
+pub use queue::CommandQueue;
+pub use report::{collect_state_report, get_dns_suffixes, save_state_report};
+pub use reset::{interfaces_to_reset, reset_all_argument, run_reset_all};
+pub use resolution_check::{check_resolution, measure_latency, server_answers};
+pub use scheduler::{Schedule, ScheduleEntry, TimeOfDay, Weekday, local_time};
+pub use startup::{StartupSetting, launched_minimized, set_startup_setting, startup_setting};
+pub use temporary::{TEMPORARY_APPLY_MINUTES, TemporaryApply, format_countdown, unix_now};
+pub use theme::{ThemePreference, system_prefers_dark};
+pub use trace::{
+    enable_verbose_trace, set_command_trace, trace_log_path, verbose_args, verbose_argument,
 };
-pub use config::{load_config, save_config};
-pub use network::get_network_interfaces;
 pub use types::{
-    AddressFamily, AppConfig, CurrentDnsState, DnsEntry, DnsMode, DnsProfile, DnsServerEntry,
-    DnsSettings, DohMode, NetworkInterface, WindowState,
+    ActiveNrptRule, AddressFamily, AppConfig, AppSettings, CurrentDnsState, DnsEntry, DnsMode,
+    DnsOrigin, DnsProfile, DnsServerEntry, DnsSettings, DnsSuffixSettings, DohMode, LocalResolver,
+    NetworkInterface, NrptRule, NrptSettings, WindowState, parse_tags,
+};
+pub use updater::{
+    Release, UpdatePackage, check_for_update, current_version, download_update, install_update,
+    remove_old_executable,
+};
+pub use validation::{
+    DohTemplateIssue, SuspiciousAddress, check_doh_template, looks_like_host_name,
+    parse_ipv6_with_zone, server_ip, suspicious_address, validate_color, validate_dns_suffix,
+    validate_doh_template, validate_ipv4, validate_ipv6,
 };
-pub use validation::{validate_doh_template, validate_ipv4, validate_ipv6};
 pub use window::{capture_window_state, validate_window_state};
@@ -1,5 +1,10 @@
 use crate::dns::types::NetworkInterface;
+#[cfg(target_os = "windows")]
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error;
+use tokio::sync::Notify;
 
 #[derive(Error, Debug)]
 pub enum NetworkError {
@@ -14,8 +19,218 @@ pub type Result<T> = std::result::Result<T, NetworkError>;
 const AF_INET: u16 = 2;
 const AF_INET6: u16 = 23;
 
+/// Addresses and hardware of one adapter, shown so that the right one can
+/// be picked.
+#[derive(Clone, PartialEq, Default, Debug)]
+pub struct InterfaceDetails {
+    pub description: String,
+    /// Unicast addresses with their prefix length, e.g. `192.168.1.10/24`.
+    pub addresses: Vec<String>,
+    pub gateways: Vec<String>,
+    pub dhcp_servers: Vec<String>,
+    /// MAC address as `AA-BB-CC-DD-EE-FF`, if the adapter has one.
+    pub mac: Option<String>,
+    /// Transmit speed in bits per second, if known.
+    pub link_speed: Option<u64>,
+}
+
+/// Interface types from ipifcons.h that are never a physical network.
+const IF_TYPE_PPP: u32 = 23;
+const IF_TYPE_SOFTWARE_LOOPBACK: u32 = 24;
+const IF_TYPE_PROP_VIRTUAL: u32 = 53;
+const IF_TYPE_TUNNEL: u32 = 131;
+const IF_TYPE_IEEE80211: u32 = 71;
+
+/// `IfOperStatusUp` from ifdef.h.
+const IF_OPER_STATUS_UP: i32 = 1;
+/// Adapter flags from iptypes.h telling whether a protocol is bound, for
+/// adapters without addresses to judge by.
+const IP_ADAPTER_IPV4_ENABLED: u32 = 0x80;
+const IP_ADAPTER_IPV6_ENABLED: u32 = 0x100;
+
+/// Words in the description of adapters created by hypervisors and WSL,
+/// which mostly report themselves as Ethernet.
+const VIRTUAL_ADAPTER_KEYWORDS: &[&str] = &[
+    "hyper-v",
+    "virtual",
+    "vmware",
+    "virtualbox",
+    "wsl",
+    "loopback",
+];
+
+/// Words in the description of adapters created by VPN clients.
+const VPN_ADAPTER_KEYWORDS: &[&str] = &[
+    "vpn",
+    "tap-",
+    "wintun",
+    "wireguard",
+    "anyconnect",
+    "pangp",
+    "fortinet",
+];
+
+/// Whether an adapter is a loopback, tunnel, VPN, or virtual machine one,
+/// judged by its interface type and description.
+fn is_virtual_adapter(if_type: u32, description: &str) -> bool {
+    let description_lower = description.to_lowercase();
+    matches!(
+        if_type,
+        IF_TYPE_SOFTWARE_LOOPBACK | IF_TYPE_PROP_VIRTUAL | IF_TYPE_TUNNEL
+    ) || VIRTUAL_ADAPTER_KEYWORDS
+        .iter()
+        .any(|keyword| description_lower.contains(keyword))
+        || is_vpn_adapter(if_type, description)
+}
+
+/// Whether an adapter belongs to a VPN client or a dial-up VPN connection.
+fn is_vpn_adapter(if_type: u32, description: &str) -> bool {
+    let description = description.to_lowercase();
+    if_type == IF_TYPE_PPP
+        || VPN_ADAPTER_KEYWORDS
+            .iter()
+            .any(|keyword| description.contains(keyword))
+}
+
+/// Formats a hardware address, or returns `None` for an empty one.
+fn format_mac(bytes: &[u8]) -> Option<String> {
+    if bytes.iter().all(|&b| b == 0) {
+        return None;
+    }
+    Some(
+        bytes
+            .iter()
+            .map(|b| format!("{:02X}", b))
+            .collect::<Vec<_>>()
+            .join("-"),
+    )
+}
+
+/// Formats a link speed the way Windows does, e.g. "1 Gbps" or
+/// "866.7 Mbps".
+pub fn format_link_speed(bits_per_second: u64) -> String {
+    let (value, unit) = match bits_per_second {
+        s if s >= 1_000_000_000 => (s as f64 / 1e9, "Gbps"),
+        s if s >= 1_000_000 => (s as f64 / 1e6, "Mbps"),
+        s if s >= 1_000 => (s as f64 / 1e3, "Kbps"),
+        s => (s as f64, "bps"),
+    };
+    let text = format!("{:.1}", value);
+    format!("{} {}", text.trim_end_matches(".0"), unit)
+}
+
+/// How often to re-read the interfaces when change notifications are
+/// unavailable.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Delay after a change notification, so that a burst of notifications
+/// for one adapter results in a single refresh.
+const SETTLE_DELAY: Duration = Duration::from_millis(500);
+
+/// Signals when network interfaces may have changed, e.g. when a USB NIC,
+/// VPN, or Wi-Fi adapter appears or goes away. Uses
+/// `NotifyIpInterfaceChange` where available and polling otherwise.
+pub struct InterfaceWatcher {
+    notify: Arc<Notify>,
+    #[cfg(target_os = "windows")]
+    handle: Option<windows::Win32::Foundation::HANDLE>,
+}
+
+impl InterfaceWatcher {
+    pub fn new() -> Self {
+        let notify = Arc::new(Notify::new());
+        Self {
+            #[cfg(target_os = "windows")]
+            handle: register_change_notification(&notify),
+            notify,
+        }
+    }
+
+    /// Waits until the next notification or poll interval.
+    pub async fn changed(&self) {
+        if self.is_registered() {
+            self.notify.notified().await;
+            tokio::time::sleep(SETTLE_DELAY).await;
+        } else {
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    fn is_registered(&self) -> bool {
+        self.handle.is_some()
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    fn is_registered(&self) -> bool {
+        false
+    }
+}
+
+impl Default for InterfaceWatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[cfg(target_os = "windows")]
-pub fn get_network_interfaces() -> Result<Vec<NetworkInterface>> {
+fn register_change_notification(
+    notify: &Arc<Notify>,
+) -> Option<windows::Win32::Foundation::HANDLE> {
+    use windows::Win32::Foundation::{HANDLE, NO_ERROR};
+    use windows::Win32::NetworkManagement::IpHelper::{
+        MIB_IPINTERFACE_ROW, MIB_NOTIFICATION_TYPE, NotifyIpInterfaceChange,
+    };
+    use windows::Win32::Networking::WinSock::AF_UNSPEC;
+
+    unsafe extern "system" fn on_change(
+        context: *const std::ffi::c_void,
+        _row: *const MIB_IPINTERFACE_ROW,
+        _notification_type: MIB_NOTIFICATION_TYPE,
+    ) {
+        let notify = unsafe { &*(context as *const Notify) };
+        notify.notify_one();
+    }
+
+    // The watcher owns this reference until the notification is cancelled.
+    let context = Arc::into_raw(notify.clone());
+    let mut handle = HANDLE::default();
+    let result = unsafe {
+        NotifyIpInterfaceChange(
+            AF_UNSPEC,
+            Some(on_change),
+            Some(context as *const std::ffi::c_void),
+            false,
+            &mut handle,
+        )
+    };
+
+    if result == NO_ERROR {
+        Some(handle)
+    } else {
+        unsafe { drop(Arc::from_raw(context)) };
+        None
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl Drop for InterfaceWatcher {
+    fn drop(&mut self) {
+        use windows::Win32::NetworkManagement::IpHelper::CancelMibChangeNotify2;
+
+        if let Some(handle) = self.handle.take() {
+            // No callbacks run after cancelling, so the context can be freed.
+            unsafe {
+                let _ = CancelMibChangeNotify2(handle);
+                drop(Arc::from_raw(Arc::as_ptr(&self.notify)));
+            }
+        }
+    }
+}
+
+/// Lists the adapters that are up, and with `include_disconnected` those
+/// without a link as well.
+#[cfg(target_os = "windows")]
+pub fn get_network_interfaces(include_disconnected: bool) -> Result<Vec<NetworkInterface>> {
     use windows::Win32::NetworkManagement::IpHelper::{
         GAA_FLAG_SKIP_ANYCAST, GAA_FLAG_SKIP_DNS_SERVER, GAA_FLAG_SKIP_MULTICAST,
         GetAdaptersAddresses, IP_ADAPTER_ADDRESSES_LH,
@@ -49,7 +264,8 @@ pub fn get_network_interfaces() -> Result<Vec<NetworkInterface>> {
         while !current.is_null() {
             let adapter = &*current;
 
-            if adapter.OperStatus.0 == 1 {
+            let is_disconnected = adapter.OperStatus.0 != IF_OPER_STATUS_UP;
+            if !is_disconnected || include_disconnected {
                 let name = if !adapter.FriendlyName.is_null() {
                     let len = (0..)
                         .take_while(|&i| *adapter.FriendlyName.0.offset(i) != 0)
@@ -95,9 +311,28 @@ pub fn get_network_interfaces() -> Result<Vec<NetworkInterface>> {
                     }
                     unicast = addr.Next;
                 }
+                if is_disconnected {
+                    let flags = adapter.Anonymous2.Flags;
+                    has_ipv4 |= flags & IP_ADAPTER_IPV4_ENABLED != 0;
+                    has_ipv6 |= flags & IP_ADAPTER_IPV6_ENABLED != 0;
+                }
 
                 if has_ipv4 || has_ipv6 {
+                    let description = if adapter.Description.is_null() {
+                        String::new()
+                    } else {
+                        adapter.Description.to_string().unwrap_or_default()
+                    };
+                    let ssid = if adapter.IfType == IF_TYPE_IEEE80211 {
+                        connected_ssid(&guid)
+                    } else {
+                        None
+                    };
                     interfaces.push(NetworkInterface {
+                        is_virtual: is_virtual_adapter(adapter.IfType, &description),
+                        is_vpn: is_vpn_adapter(adapter.IfType, &description),
+                        ssid,
+                        is_disconnected,
                         name,
                         interface_index: adapter.Anonymous1.Anonymous.IfIndex,
                         interface_guid: guid,
@@ -119,7 +354,243 @@ pub fn get_network_interfaces() -> Result<Vec<NetworkInterface>> {
 }
 
 #[cfg(not(target_os = "windows"))]
-pub fn get_network_interfaces() -> Result<Vec<NetworkInterface>> {
+pub fn get_network_interfaces(_include_disconnected: bool) -> Result<Vec<NetworkInterface>> {
+    Err(NetworkError::WindowsApi(
+        "Not supported on this platform".to_string(),
+    ))
+}
+
+/// Name of the network the Wi-Fi adapter with `interface_guid` is
+/// connected to, if any.
+#[cfg(target_os = "windows")]
+fn connected_ssid(interface_guid: &str) -> Option<String> {
+    use std::ffi::c_void;
+    use windows::Win32::Foundation::HANDLE;
+    use windows::Win32::NetworkManagement::WiFi::{
+        WLAN_CONNECTION_ATTRIBUTES, WlanCloseHandle, WlanFreeMemory, WlanOpenHandle,
+        WlanQueryInterface, wlan_intf_opcode_current_connection,
+    };
+    use windows::core::GUID;
+
+    let guid = GUID::try_from(interface_guid.trim_matches(['{', '}'])).ok()?;
+    let mut version = 0;
+    let mut handle = HANDLE::default();
+    unsafe {
+        if WlanOpenHandle(2, None, &mut version, &mut handle) != 0 {
+            return None;
+        }
+        let mut size = 0;
+        let mut data: *mut c_void = std::ptr::null_mut();
+        let result = WlanQueryInterface(
+            handle,
+            &guid,
+            wlan_intf_opcode_current_connection,
+            None,
+            &mut size,
+            &mut data,
+            None,
+        );
+        let ssid = if result == 0 && !data.is_null() {
+            let attributes = &*(data as *const WLAN_CONNECTION_ATTRIBUTES);
+            let ssid = &attributes.wlanAssociationAttributes.dot11Ssid;
+            let len = (ssid.uSSIDLength as usize).min(ssid.ucSSID.len());
+            Some(String::from_utf8_lossy(&ssid.ucSSID[..len]).into_owned())
+                .filter(|name| !name.is_empty())
+        } else {
+            None
+        };
+        if !data.is_null() {
+            WlanFreeMemory(data);
+        }
+        WlanCloseHandle(handle, None);
+        ssid
+    }
+}
+
+/// Reads the address in `address`, if it is an IPv4 or IPv6 one.
+#[cfg(target_os = "windows")]
+unsafe fn socket_address_ip(
+    address: &windows::Win32::Networking::WinSock::SOCKET_ADDRESS,
+) -> Option<IpAddr> {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+    use windows::Win32::Networking::WinSock::{SOCKADDR_IN, SOCKADDR_IN6};
+
+    if address.lpSockaddr.is_null() {
+        return None;
+    }
+    unsafe {
+        match (*address.lpSockaddr).sa_family.0 {
+            AF_INET => {
+                let addr = &*(address.lpSockaddr as *const SOCKADDR_IN);
+                let octets = addr.sin_addr.S_un.S_addr.to_ne_bytes();
+                Some(Ipv4Addr::from(octets).into())
+            }
+            AF_INET6 => {
+                let addr = &*(address.lpSockaddr as *const SOCKADDR_IN6);
+                Some(Ipv6Addr::from(addr.sin6_addr.u.Byte).into())
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Reads the addresses, gateways, and hardware of the adapter with
+/// `interface_guid`.
+#[cfg(target_os = "windows")]
+pub fn get_interface_details(interface_guid: &str) -> Result<InterfaceDetails> {
+    use windows::Win32::NetworkManagement::IpHelper::{
+        GAA_FLAG_INCLUDE_GATEWAYS, GAA_FLAG_SKIP_ANYCAST, GAA_FLAG_SKIP_DNS_SERVER,
+        GAA_FLAG_SKIP_MULTICAST, GetAdaptersAddresses, IP_ADAPTER_ADDRESSES_LH,
+    };
+    use windows::Win32::Networking::WinSock::AF_UNSPEC;
+
+    let flags = GAA_FLAG_SKIP_ANYCAST
+        | GAA_FLAG_SKIP_MULTICAST
+        | GAA_FLAG_SKIP_DNS_SERVER
+        | GAA_FLAG_INCLUDE_GATEWAYS;
+
+    let mut buffer_size: u32 = 15000;
+    let mut buffer: Vec<u8> = vec![0; buffer_size as usize];
+
+    unsafe {
+        let result = GetAdaptersAddresses(
+            AF_UNSPEC.0 as u32,
+            flags,
+            None,
+            Some(buffer.as_mut_ptr() as *mut IP_ADAPTER_ADDRESSES_LH),
+            &mut buffer_size,
+        );
+
+        if result != 0 {
+            return Err(NetworkError::WindowsApi(format!(
+                "GetAdaptersAddresses failed with code {}",
+                result
+            )));
+        }
+
+        let mut current = buffer.as_ptr() as *const IP_ADAPTER_ADDRESSES_LH;
+
+        while !current.is_null() {
+            let adapter = &*current;
+
+            if !adapter.AdapterName.is_null()
+                && std::ffi::CStr::from_ptr(adapter.AdapterName.0 as *const i8).to_string_lossy()
+                    == interface_guid
+            {
+                let mut details = InterfaceDetails::default();
+                if !adapter.Description.is_null() {
+                    details.description = adapter.Description.to_string().unwrap_or_default();
+                }
+
+                let mut unicast = adapter.FirstUnicastAddress;
+                while !unicast.is_null() {
+                    let entry = &*unicast;
+                    if let Some(ip) = socket_address_ip(&entry.Address) {
+                        details
+                            .addresses
+                            .push(format!("{}/{}", ip, entry.OnLinkPrefixLength));
+                    }
+                    unicast = entry.Next;
+                }
+
+                let mut gateway = adapter.FirstGatewayAddress;
+                while !gateway.is_null() {
+                    let entry = &*gateway;
+                    if let Some(ip) = socket_address_ip(&entry.Address) {
+                        details.gateways.push(ip.to_string());
+                    }
+                    gateway = entry.Next;
+                }
+
+                for server in [&adapter.Dhcpv4Server, &adapter.Dhcpv6Server] {
+                    if let Some(ip) = socket_address_ip(server).filter(|ip| !ip.is_unspecified()) {
+                        details.dhcp_servers.push(ip.to_string());
+                    }
+                }
+
+                let mac_len =
+                    (adapter.PhysicalAddressLength as usize).min(adapter.PhysicalAddress.len());
+                details.mac = format_mac(&adapter.PhysicalAddress[..mac_len]);
+                details.link_speed = Some(adapter.TransmitLinkSpeed)
+                    .filter(|&speed| speed != 0 && speed != u64::MAX);
+                return Ok(details);
+            }
+
+            current = adapter.Next;
+        }
+    }
+
+    Err(NetworkError::NoInterfaces)
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn get_interface_details(_interface_guid: &str) -> Result<InterfaceDetails> {
+    Err(NetworkError::WindowsApi(
+        "Not supported on this platform".to_string(),
+    ))
+}
+
+/// Reads the DNS server addresses the adapter with `interface_guid` uses.
+/// Much cheaper than a PowerShell query, so it is suitable for polling.
+#[cfg(target_os = "windows")]
+pub fn get_dns_server_addresses(interface_guid: &str) -> Result<Vec<String>> {
+    use windows::Win32::NetworkManagement::IpHelper::{
+        GAA_FLAG_SKIP_ANYCAST, GAA_FLAG_SKIP_MULTICAST, GAA_FLAG_SKIP_UNICAST,
+        GetAdaptersAddresses, IP_ADAPTER_ADDRESSES_LH,
+    };
+    use windows::Win32::Networking::WinSock::AF_UNSPEC;
+
+    let flags = GAA_FLAG_SKIP_ANYCAST | GAA_FLAG_SKIP_MULTICAST | GAA_FLAG_SKIP_UNICAST;
+
+    let mut buffer_size: u32 = 15000;
+    let mut buffer: Vec<u8> = vec![0; buffer_size as usize];
+
+    unsafe {
+        let result = GetAdaptersAddresses(
+            AF_UNSPEC.0 as u32,
+            flags,
+            None,
+            Some(buffer.as_mut_ptr() as *mut IP_ADAPTER_ADDRESSES_LH),
+            &mut buffer_size,
+        );
+
+        if result != 0 {
+            return Err(NetworkError::WindowsApi(format!(
+                "GetAdaptersAddresses failed with code {}",
+                result
+            )));
+        }
+
+        let mut current = buffer.as_ptr() as *const IP_ADAPTER_ADDRESSES_LH;
+
+        while !current.is_null() {
+            let adapter = &*current;
+
+            if !adapter.AdapterName.is_null()
+                && std::ffi::CStr::from_ptr(adapter.AdapterName.0 as *const i8).to_string_lossy()
+                    == interface_guid
+            {
+                let mut addresses = Vec::new();
+                let mut server = adapter.FirstDnsServerAddress;
+                while !server.is_null() {
+                    let entry = &*server;
+                    if let Some(ip) = socket_address_ip(&entry.Address) {
+                        addresses.push(ip.to_string());
+                    }
+                    server = entry.Next;
+                }
+                return Ok(addresses);
+            }
+
+            current = adapter.Next;
+        }
+    }
+
+    Err(NetworkError::NoInterfaces)
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn get_dns_server_addresses(_interface_guid: &str) -> Result<Vec<String>> {
     Err(NetworkError::WindowsApi(
         "Not supported on this platform".to_string(),
     ))
@@ -129,10 +600,81 @@ pub fn get_network_interfaces() -> Result<Vec<NetworkInterface>> {
 mod tests {
     use super::*;
 
+    #[test]
+    #[cfg(not(target_os = "windows"))]
+    fn test_interface_watcher_falls_back_to_polling() {
+        assert!(!InterfaceWatcher::new().is_registered());
+    }
+
+    #[test]
+    fn test_is_virtual_adapter() {
+        const IF_TYPE_ETHERNET_CSMACD: u32 = 6;
+
+        assert!(!is_virtual_adapter(
+            IF_TYPE_ETHERNET_CSMACD,
+            "Intel(R) Ethernet Connection I219-V"
+        ));
+        assert!(!is_virtual_adapter(
+            IF_TYPE_IEEE80211,
+            "Intel(R) Wi-Fi 6 AX201 160MHz"
+        ));
+        assert!(is_virtual_adapter(
+            IF_TYPE_ETHERNET_CSMACD,
+            "Hyper-V Virtual Ethernet Adapter"
+        ));
+        assert!(is_virtual_adapter(
+            IF_TYPE_ETHERNET_CSMACD,
+            "TAP-Windows Adapter V9"
+        ));
+        assert!(is_virtual_adapter(IF_TYPE_PROP_VIRTUAL, "WireGuard Tunnel"));
+        assert!(is_virtual_adapter(
+            IF_TYPE_SOFTWARE_LOOPBACK,
+            "Software Loopback Interface 1"
+        ));
+        assert!(is_virtual_adapter(IF_TYPE_TUNNEL, "Teredo Tunneling"));
+    }
+
+    #[test]
+    fn test_is_vpn_adapter() {
+        const IF_TYPE_ETHERNET_CSMACD: u32 = 6;
+
+        assert!(is_vpn_adapter(IF_TYPE_PROP_VIRTUAL, "WireGuard Tunnel"));
+        assert!(is_vpn_adapter(
+            IF_TYPE_ETHERNET_CSMACD,
+            "PANGP Virtual Ethernet Adapter"
+        ));
+        assert!(is_vpn_adapter(IF_TYPE_PPP, "WAN Miniport (IKEv2)"));
+        assert!(!is_vpn_adapter(
+            IF_TYPE_ETHERNET_CSMACD,
+            "Hyper-V Virtual Ethernet Adapter"
+        ));
+        assert!(!is_vpn_adapter(IF_TYPE_TUNNEL, "Teredo Tunneling"));
+    }
+
+    #[test]
+    fn test_format_mac() {
+        assert_eq!(
+            format_mac(&[0x00, 0x1a, 0x2b, 0x3c, 0x4d, 0x5e]).as_deref(),
+            Some("00-1A-2B-3C-4D-5E")
+        );
+        assert_eq!(format_mac(&[]), None);
+        assert_eq!(format_mac(&[0; 6]), None);
+    }
+
+    #[test]
+    fn test_format_link_speed() {
+        assert_eq!(format_link_speed(1_000_000_000), "1 Gbps");
+        assert_eq!(format_link_speed(2_500_000_000), "2.5 Gbps");
+        assert_eq!(format_link_speed(866_700_000), "866.7 Mbps");
+        assert_eq!(format_link_speed(100_000_000), "100 Mbps");
+        assert_eq!(format_link_speed(56_000), "56 Kbps");
+        assert_eq!(format_link_speed(300), "300 bps");
+    }
+
     #[test]
     #[cfg(target_os = "windows")]
     fn test_get_network_interfaces() {
-        let result = get_network_interfaces();
+        let result = get_network_interfaces(true);
         match result {
             Ok(interfaces) => {
                 assert!(!interfaces.is_empty());
@@ -0,0 +1,334 @@
+use crate::dns::commands::{run_netsh, run_powershell};
+use crate::dns::types::{AppConfig, NetworkInterface};
+use crate::i18n::t;
+use serde::{Deserialize, Serialize};
+
+/// What a network rule looks for on an interface.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+#[serde(tag = "kind", content = "value")]
+pub enum NetworkCondition {
+    /// Connected to the Wi-Fi network with this SSID.
+    Ssid(String),
+    /// The default gateway has this MAC address, e.g. a home router.
+    GatewayMac(String),
+}
+
+impl NetworkCondition {
+    /// What the condition looks at, as labeled in the rule list.
+    pub fn label(&self) -> String {
+        match self {
+            NetworkCondition::Ssid(_) => t!("wifi-ssid"),
+            NetworkCondition::GatewayMac(_) => t!("gateway-mac"),
+        }
+    }
+
+    pub fn value(&self) -> &str {
+        match self {
+            NetworkCondition::Ssid(value) | NetworkCondition::GatewayMac(value) => value,
+        }
+    }
+
+    fn matches(&self, network: &NetworkInfo) -> bool {
+        match self {
+            NetworkCondition::Ssid(ssid) => network.ssid.as_deref() == Some(ssid.trim()),
+            NetworkCondition::GatewayMac(mac) => {
+                network.gateway_mac.is_some() && network.gateway_mac == normalize_mac(mac)
+            }
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct NetworkRule {
+    pub id: String,
+    pub condition: NetworkCondition,
+    /// Profile to apply; `None` switches the interface to Automatic.
+    pub profile_id: Option<String>,
+}
+
+impl NetworkRule {
+    pub fn new() -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            condition: NetworkCondition::Ssid(String::new()),
+            profile_id: None,
+        }
+    }
+}
+
+impl Default for NetworkRule {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Rules that pick a profile from the network an interface is connected
+/// to. The first matching rule wins; with none matching, the interface is
+/// set to Automatic.
+#[derive(Clone, PartialEq, Serialize, Deserialize, Default, Debug)]
+pub struct NetworkRules {
+    pub enabled: bool,
+    #[serde(default)]
+    pub rules: Vec<NetworkRule>,
+}
+
+impl NetworkRules {
+    pub fn is_empty(&self) -> bool {
+        !self.enabled && self.rules.is_empty()
+    }
+
+    /// Profile to apply on `network`, or `None` for Automatic.
+    pub fn target_profile(&self, network: &NetworkInfo) -> Option<String> {
+        self.matching_rule(network)
+            .and_then(|rule| rule.profile_id.clone())
+    }
+
+    /// The rule that picks the profile on `network`, if any.
+    pub fn matching_rule(&self, network: &NetworkInfo) -> Option<&NetworkRule> {
+        self.rules
+            .iter()
+            .find(|rule| rule.condition.matches(network))
+    }
+
+    pub fn validate(&self, config: &AppConfig) -> Result<(), String> {
+        for rule in &self.rules {
+            match &rule.condition {
+                NetworkCondition::Ssid(ssid) if ssid.trim().is_empty() => {
+                    return Err("A network rule needs an SSID".to_string());
+                }
+                NetworkCondition::GatewayMac(mac) if normalize_mac(mac).is_none() => {
+                    return Err(format!("Invalid gateway MAC address: {}", mac.trim()));
+                }
+                _ => {}
+            }
+            if let Some(id) = &rule.profile_id
+                && config.find_profile(id).is_none()
+            {
+                return Err(format!(
+                    "The profile of the rule for {} no longer exists",
+                    rule.condition.value().trim()
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The network an interface is connected to.
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+pub struct NetworkInfo {
+    pub ssid: Option<String>,
+    /// MAC address of the default gateway, as `AA-BB-CC-DD-EE-FF`.
+    pub gateway_mac: Option<String>,
+}
+
+impl NetworkInfo {
+    pub fn is_connected(&self) -> bool {
+        self.ssid.is_some() || self.gateway_mac.is_some()
+    }
+}
+
+/// Looks up the Wi-Fi network and default gateway of `interface`. Parts
+/// that cannot be determined are left empty.
+pub async fn detect_network(interface: &NetworkInterface) -> NetworkInfo {
+    let ssid = match run_netsh(&["wlan", "show", "interfaces"]).await {
+        Ok(output) => parse_wlan_ssid(&output, &interface.interface_guid),
+        Err(_) => None,
+    };
+    let gateway_mac = match run_powershell(&gateway_mac_script(interface)).await {
+        Ok(output) => normalize_mac(output.trim()),
+        Err(_) => None,
+    };
+    NetworkInfo { ssid, gateway_mac }
+}
+
+fn gateway_mac_script(interface: &NetworkInterface) -> String {
+    format!(
+        r#"
+        $gateway = Get-NetRoute -InterfaceIndex {0} -DestinationPrefix '0.0.0.0/0' -ErrorAction SilentlyContinue |
+            Sort-Object RouteMetric | Select-Object -First 1 -ExpandProperty NextHop
+        if ($gateway) {{
+            Get-NetNeighbor -InterfaceIndex {0} -IPAddress $gateway -ErrorAction SilentlyContinue |
+                Select-Object -First 1 -ExpandProperty LinkLayerAddress
+        }}
+        "#,
+        interface.interface_index
+    )
+}
+
+/// Finds the SSID of the interface with `guid` in `netsh wlan show
+/// interfaces` output. The field labels are localized except for `GUID`
+/// and `SSID`.
+fn parse_wlan_ssid(output: &str, guid: &str) -> Option<String> {
+    let guid = guid.trim_matches(['{', '}']);
+    let mut in_interface = false;
+    for line in output.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim() {
+            "GUID" => in_interface = value.trim_matches(['{', '}']).eq_ignore_ascii_case(guid),
+            "SSID" if in_interface && !value.is_empty() => return Some(value.to_string()),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Normalizes a MAC address to `AA-BB-CC-DD-EE-FF`. Accepts `-` or `:` as
+/// separators.
+pub fn normalize_mac(mac: &str) -> Option<String> {
+    let parts: Vec<&str> = mac.trim().split(['-', ':']).collect();
+    let valid = parts.len() == 6
+        && parts
+            .iter()
+            .all(|p| p.len() == 2 && p.chars().all(|c| c.is_ascii_hexdigit()));
+    let normalized = parts.join("-").to_ascii_uppercase();
+    (valid && normalized != "00-00-00-00-00-00").then_some(normalized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dns::types::{DnsProfile, test_interface};
+
+    fn rule(condition: NetworkCondition, profile_id: Option<&str>) -> NetworkRule {
+        NetworkRule {
+            condition,
+            profile_id: profile_id.map(str::to_string),
+            ..NetworkRule::new()
+        }
+    }
+
+    #[test]
+    fn test_normalize_mac() {
+        assert_eq!(
+            normalize_mac("aa:bb:cc:dd:ee:0f").as_deref(),
+            Some("AA-BB-CC-DD-EE-0F")
+        );
+        assert_eq!(
+            normalize_mac(" AA-BB-CC-DD-EE-FF\r\n").as_deref(),
+            Some("AA-BB-CC-DD-EE-FF")
+        );
+        assert_eq!(normalize_mac("00-00-00-00-00-00"), None);
+        assert_eq!(normalize_mac("AA-BB-CC-DD-EE"), None);
+        assert_eq!(normalize_mac("GG-BB-CC-DD-EE-FF"), None);
+        assert_eq!(normalize_mac(""), None);
+    }
+
+    #[test]
+    fn test_parse_wlan_ssid() {
+        let output = "
+There are 2 interfaces on the system:
+
+    Name                   : Wi-Fi
+    Description            : Intel(R) Wi-Fi 6 AX201 160MHz
+    GUID                   : 11111111-2222-3333-4444-555555555555
+    State                  : connected
+    SSID                   : HomeNet
+    BSSID                  : aa:bb:cc:dd:ee:ff
+
+    Name                   : Wi-Fi 2
+    GUID                   : 66666666-7777-8888-9999-000000000000
+    State                  : disconnected
+";
+        assert_eq!(
+            parse_wlan_ssid(output, "{11111111-2222-3333-4444-555555555555}").as_deref(),
+            Some("HomeNet")
+        );
+        assert_eq!(
+            parse_wlan_ssid(output, "{66666666-7777-8888-9999-000000000000}"),
+            None
+        );
+        assert_eq!(parse_wlan_ssid(output, "{GUID-1}"), None);
+    }
+
+    #[test]
+    fn test_gateway_mac_script() {
+        let interface = test_interface("Ethernet", 7);
+        let script = gateway_mac_script(&interface);
+        assert!(script.contains("Get-NetRoute -InterfaceIndex 7"));
+        assert!(script.contains("Get-NetNeighbor -InterfaceIndex 7 -IPAddress $gateway"));
+    }
+
+    #[test]
+    fn test_target_profile_first_match_wins() {
+        let rules = NetworkRules {
+            enabled: true,
+            rules: vec![
+                rule(NetworkCondition::Ssid("HomeNet".to_string()), Some("home")),
+                rule(
+                    NetworkCondition::GatewayMac("aa:bb:cc:dd:ee:ff".to_string()),
+                    Some("office"),
+                ),
+                rule(NetworkCondition::Ssid("Cafe".to_string()), None),
+            ],
+        };
+
+        let home = NetworkInfo {
+            ssid: Some("HomeNet".to_string()),
+            gateway_mac: Some("AA-BB-CC-DD-EE-FF".to_string()),
+        };
+        assert_eq!(rules.target_profile(&home).as_deref(), Some("home"));
+
+        let office = NetworkInfo {
+            ssid: None,
+            gateway_mac: Some("AA-BB-CC-DD-EE-FF".to_string()),
+        };
+        assert_eq!(rules.target_profile(&office).as_deref(), Some("office"));
+
+        let cafe = NetworkInfo {
+            ssid: Some("Cafe".to_string()),
+            gateway_mac: None,
+        };
+        assert_eq!(rules.target_profile(&cafe), None);
+        assert_eq!(
+            rules
+                .matching_rule(&cafe)
+                .map(|rule| rule.condition.value()),
+            Some("Cafe")
+        );
+        assert_eq!(rules.target_profile(&NetworkInfo::default()), None);
+        assert!(rules.matching_rule(&NetworkInfo::default()).is_none());
+    }
+
+    #[test]
+    fn test_network_rules_validate() {
+        let mut config = AppConfig::new();
+        let profile = DnsProfile::new("Home".to_string());
+        let id = profile.id.clone();
+        config.add_profile(profile);
+
+        let mut rules = NetworkRules {
+            enabled: true,
+            rules: vec![rule(
+                NetworkCondition::Ssid("HomeNet".to_string()),
+                Some(&id),
+            )],
+        };
+        assert!(rules.validate(&config).is_ok());
+
+        rules.rules[0].condition = NetworkCondition::Ssid(" ".to_string());
+        assert!(rules.validate(&config).is_err());
+
+        rules.rules[0].condition = NetworkCondition::GatewayMac("router".to_string());
+        assert!(rules.validate(&config).is_err());
+
+        rules.rules[0].condition = NetworkCondition::GatewayMac("aa-bb-cc-dd-ee-ff".to_string());
+        rules.rules[0].profile_id = Some("deleted".to_string());
+        assert!(rules.validate(&config).is_err());
+    }
+
+    #[test]
+    fn test_network_rules_serialization() {
+        let rules = NetworkRules {
+            enabled: true,
+            rules: vec![rule(NetworkCondition::Ssid("HomeNet".to_string()), None)],
+        };
+        let json = serde_json::to_string(&rules).unwrap();
+        assert!(json.contains(r#""condition":{"kind":"Ssid","value":"HomeNet"}"#));
+        assert_eq!(serde_json::from_str::<NetworkRules>(&json).unwrap(), rules);
+        assert!(NetworkRules::default().is_empty());
+    }
+}
@@ -0,0 +1,89 @@
+use crate::dns::commands::{Result, escape_powershell_string, run_powershell};
+
+/// App ID the notifications are shown under. windns has no Start menu
+/// shortcut to register one of its own, so it borrows the one of Windows
+/// PowerShell, which every Windows has.
+const NOTIFICATION_APP_ID: &str =
+    r"{1AC14E77-02E7-4E5D-B744-2EB1AE5198B7}\WindowsPowerShell\v1.0\powershell.exe";
+
+/// A button on a notification that opens a `windns://` link, which the
+/// running windns receives like any other link.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NotificationAction {
+    pub label: String,
+    pub link: String,
+}
+
+/// Shows a Windows notification with a title, a line of text and
+/// optionally a button.
+pub async fn show_notification(
+    title: &str,
+    text: &str,
+    action: Option<&NotificationAction>,
+) -> Result<()> {
+    run_powershell(&notification_script(title, text, action)).await?;
+    Ok(())
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn notification_script(title: &str, text: &str, action: Option<&NotificationAction>) -> String {
+    let actions = action
+        .map(|action| {
+            format!(
+                r#"<actions><action content="{}" activationType="protocol" arguments="{}"/></actions>"#,
+                xml_escape(&action.label),
+                xml_escape(&action.link)
+            )
+        })
+        .unwrap_or_default();
+    let xml = format!(
+        r#"<toast><visual><binding template="ToastGeneric"><text>{}</text><text>{}</text></binding></visual>{}</toast>"#,
+        xml_escape(title),
+        xml_escape(text),
+        actions
+    );
+    format!(
+        r#"
+        [Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] | Out-Null
+        [Windows.Data.Xml.Dom.XmlDocument, Windows.Data.Xml.Dom.XmlDocument, ContentType = WindowsRuntime] | Out-Null
+        $xml = New-Object Windows.Data.Xml.Dom.XmlDocument
+        $xml.LoadXml('{}')
+        $toast = New-Object Windows.UI.Notifications.ToastNotification $xml
+        [Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier('{}').Show($toast)
+        "#,
+        escape_powershell_string(&xml),
+        NOTIFICATION_APP_ID
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notification_script_escapes_text() {
+        let script = notification_script("Network rule", "Bob's <Wi-Fi> & \"Home\"", None);
+        assert!(script.contains(
+            "<text>Network rule</text><text>Bob''s &lt;Wi-Fi&gt; &amp; &quot;Home&quot;</text>"
+        ));
+        assert!(!script.contains("<actions>"));
+    }
+
+    #[test]
+    fn test_notification_script_with_action() {
+        let action = NotificationAction {
+            label: "Undo".to_string(),
+            link: "windns://undo".to_string(),
+        };
+        let script = notification_script("Title", "Text", Some(&action));
+        assert!(script.contains(
+            r#"<actions><action content="Undo" activationType="protocol" arguments="windns://undo"/></actions>"#
+        ));
+    }
+}
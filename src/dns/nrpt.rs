@@ -0,0 +1,151 @@
+use crate::dns::commands::{DnsCommandError, Result, escape_powershell_string};
+use crate::dns::types::{ActiveNrptRule, NrptRule};
+
+/// Comment attached to the rules windns creates, so that applying a profile
+/// replaces only those and leaves rules from other tools alone.
+pub const MANAGED_COMMENT: &str = "Managed by windns";
+
+pub(crate) const LIST_RULES_SCRIPT: &str = "ConvertTo-Json -Compress -Depth 3 -InputObject @(Get-DnsClientNrptRule | Select-Object Name, Namespace, NameServers, Comment)";
+
+/// Removes the rules created by windns and adds `rules` in their place.
+pub(crate) fn replace_rules_script(rules: &[NrptRule]) -> String {
+    let mut script = format!(
+        "Get-DnsClientNrptRule | Where-Object {{ $_.Comment -eq '{}' }} | ForEach-Object {{ Remove-DnsClientNrptRule -Name $_.Name -Force }}",
+        MANAGED_COMMENT
+    );
+    for rule in rules {
+        let name_servers = rule
+            .name_server_addresses()
+            .iter()
+            .map(|a| format!("'{}'", escape_powershell_string(a)))
+            .collect::<Vec<_>>()
+            .join(",");
+        script.push_str(&format!(
+            "\nAdd-DnsClientNrptRule -Namespace '{}' -NameServers @({}) -Comment '{}'",
+            escape_powershell_string(&rule.normalized_namespace()),
+            name_servers,
+            MANAGED_COMMENT
+        ));
+    }
+    script
+}
+
+pub(crate) fn remove_rule_script(name: &str) -> String {
+    format!(
+        "Remove-DnsClientNrptRule -Name '{}' -Force",
+        escape_powershell_string(name)
+    )
+}
+
+/// Parses the JSON array printed by `LIST_RULES_SCRIPT`. Single-element
+/// lists may come out as plain strings.
+pub(crate) fn parse_rules(output: &str) -> Result<Vec<ActiveNrptRule>> {
+    if output.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let entries: Vec<serde_json::Value> =
+        serde_json::from_str(output.trim()).map_err(|_| DnsCommandError::InvalidOutput)?;
+
+    let strings = |value: Option<&serde_json::Value>| -> Vec<String> {
+        match value {
+            Some(serde_json::Value::String(s)) => vec![s.clone()],
+            Some(serde_json::Value::Array(items)) => items
+                .iter()
+                .filter_map(|v| v.as_str())
+                .map(|s| s.to_string())
+                .collect(),
+            _ => Vec::new(),
+        }
+    };
+
+    Ok(entries
+        .iter()
+        .filter_map(|entry| {
+            let name = entry.get("Name")?.as_str()?.to_string();
+            Some(ActiveNrptRule {
+                name,
+                namespaces: strings(entry.get("Namespace")),
+                name_servers: strings(entry.get("NameServers")),
+                managed: entry.get("Comment").and_then(|c| c.as_str()) == Some(MANAGED_COMMENT),
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(namespace: &str, name_servers: &str) -> NrptRule {
+        NrptRule {
+            namespace: namespace.to_string(),
+            name_servers: name_servers.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_replace_rules_script() {
+        let script = replace_rules_script(&[rule("*.corp.example", "10.0.0.53, 10.0.1.53")]);
+        let lines: Vec<&str> = script.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].contains("$_.Comment -eq 'Managed by windns'"));
+        assert!(lines[0].contains("Remove-DnsClientNrptRule -Name $_.Name -Force"));
+        assert_eq!(
+            lines[1],
+            "Add-DnsClientNrptRule -Namespace '.corp.example' -NameServers @('10.0.0.53','10.0.1.53') -Comment 'Managed by windns'"
+        );
+    }
+
+    #[test]
+    fn test_replace_rules_script_without_rules_only_removes() {
+        let script = replace_rules_script(&[]);
+        assert_eq!(script.lines().count(), 1);
+        assert!(!script.contains("Add-DnsClientNrptRule"));
+    }
+
+    #[test]
+    fn test_remove_rule_script() {
+        assert_eq!(
+            remove_rule_script("{ABC}"),
+            "Remove-DnsClientNrptRule -Name '{ABC}' -Force"
+        );
+    }
+
+    #[test]
+    fn test_parse_rules() {
+        let output = r#"[{"Name":"{A}","Namespace":[".corp.example"],"NameServers":["10.0.0.53","10.0.1.53"],"Comment":"Managed by windns"},{"Name":"{B}","Namespace":"vpn.example","NameServers":"10.8.0.1","Comment":null}]"#;
+        let rules = parse_rules(output).unwrap();
+        assert_eq!(
+            rules,
+            vec![
+                ActiveNrptRule {
+                    name: "{A}".to_string(),
+                    namespaces: vec![".corp.example".to_string()],
+                    name_servers: vec!["10.0.0.53".to_string(), "10.0.1.53".to_string()],
+                    managed: true,
+                },
+                ActiveNrptRule {
+                    name: "{B}".to_string(),
+                    namespaces: vec!["vpn.example".to_string()],
+                    name_servers: vec!["10.8.0.1".to_string()],
+                    managed: false,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_rules_empty() {
+        assert!(parse_rules("").unwrap().is_empty());
+        assert!(parse_rules("[]").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_rules_invalid() {
+        assert!(matches!(
+            parse_rules("not json"),
+            Err(DnsCommandError::InvalidOutput)
+        ));
+    }
+}
@@ -0,0 +1,286 @@
+use crate::dns::types::{DnsSettings, DohMode};
+use crate::i18n::t;
+
+/// Key the DNS Client policies of Group Policy are written to.
+#[cfg(target_os = "windows")]
+const POLICY_KEY: &str = r"SOFTWARE\Policies\Microsoft\Windows NT\DNSClient";
+/// Name Resolution Policy rules pushed by Group Policy, one subkey each.
+#[cfg(target_os = "windows")]
+const NRPT_POLICY_KEY: &str = r"SOFTWARE\Policies\Microsoft\Windows NT\DNSClient\DnsPolicyConfig";
+
+/// `DoHPolicy` values of the "Configure DNS over HTTPS (DoH) name
+/// resolution" policy. 2 allows DoH, which leaves the per-server settings
+/// in charge.
+const DOH_POLICY_PROHIBIT: u32 = 1;
+const DOH_POLICY_REQUIRE: u32 = 3;
+
+/// A Group Policy setting that takes precedence over DNS settings made on
+/// the machine, so that changes windns makes to them do not take effect.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DnsPolicy {
+    /// "DNS Servers": servers used on every adapter.
+    Servers(Vec<String>),
+    /// "Configure DNS over HTTPS (DoH) name resolution" set to Prohibit.
+    DohProhibited,
+    /// "Configure DNS over HTTPS (DoH) name resolution" set to Require.
+    DohRequired,
+    /// "DNS Suffix Search List".
+    SuffixSearchList,
+    /// Name Resolution Policy rules, which replace the local ones.
+    NrptRules,
+}
+
+impl DnsPolicy {
+    /// The policy as named in the Group Policy editor.
+    pub fn description(&self) -> String {
+        match self {
+            DnsPolicy::Servers(servers) => t!("policy-dns-servers", servers = servers.join(", ")),
+            DnsPolicy::DohProhibited => t!("policy-doh-prohibited"),
+            DnsPolicy::DohRequired => t!("policy-doh-required"),
+            DnsPolicy::SuffixSearchList => t!("policy-suffix-search-list"),
+            DnsPolicy::NrptRules => t!("policy-nrpt"),
+        }
+    }
+
+    /// Whether the policy keeps `settings` from taking effect once
+    /// applied.
+    pub fn overrides(&self, settings: &DnsSettings) -> bool {
+        let servers = || {
+            [&settings.ipv4, &settings.ipv6]
+                .into_iter()
+                .filter(|entry| entry.enabled)
+                .flat_map(|entry| &entry.servers)
+                .filter(|server| !server.address.trim().is_empty())
+        };
+        match self {
+            DnsPolicy::Servers(_) => true,
+            DnsPolicy::DohProhibited => servers().any(|server| server.doh_mode == DohMode::On),
+            DnsPolicy::DohRequired => servers().any(|server| server.doh_mode == DohMode::Off),
+            DnsPolicy::SuffixSearchList => settings.suffixes.enabled,
+            DnsPolicy::NrptRules => settings.nrpt.enabled && !settings.nrpt.rules.is_empty(),
+        }
+    }
+}
+
+/// The policies set by the registry values of the DNS Client policy key
+/// and the number of Name Resolution Policy rules.
+fn policies_from_values(
+    name_server: Option<&str>,
+    doh_policy: Option<u32>,
+    search_list: Option<&str>,
+    nrpt_rules: u32,
+) -> Vec<DnsPolicy> {
+    let mut policies = Vec::new();
+    if let Some(name_server) = name_server {
+        let servers: Vec<String> = name_server
+            .split([' ', ',', ';'])
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+            .collect();
+        if !servers.is_empty() {
+            policies.push(DnsPolicy::Servers(servers));
+        }
+    }
+    match doh_policy {
+        Some(DOH_POLICY_PROHIBIT) => policies.push(DnsPolicy::DohProhibited),
+        Some(DOH_POLICY_REQUIRE) => policies.push(DnsPolicy::DohRequired),
+        _ => {}
+    }
+    if search_list.is_some_and(|list| !list.trim().is_empty()) {
+        policies.push(DnsPolicy::SuffixSearchList);
+    }
+    if nrpt_rules > 0 {
+        policies.push(DnsPolicy::NrptRules);
+    }
+    policies
+}
+
+/// The Group Policy settings that manage DNS on this machine.
+#[cfg(target_os = "windows")]
+pub fn detect_dns_policies() -> Vec<DnsPolicy> {
+    policies_from_values(
+        read_policy_string("NameServer").as_deref(),
+        read_policy_dword("DoHPolicy"),
+        read_policy_string("SearchList").as_deref(),
+        count_subkeys(NRPT_POLICY_KEY),
+    )
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn detect_dns_policies() -> Vec<DnsPolicy> {
+    Vec::new()
+}
+
+/// The policies among `policies` that override `settings`.
+pub fn overriding_policies<'a>(
+    policies: &'a [DnsPolicy],
+    settings: &DnsSettings,
+) -> Vec<&'a DnsPolicy> {
+    policies
+        .iter()
+        .filter(|policy| policy.overrides(settings))
+        .collect()
+}
+
+#[cfg(target_os = "windows")]
+fn read_policy_dword(value: &str) -> Option<u32> {
+    use windows::Win32::Foundation::NO_ERROR;
+    use windows::Win32::System::Registry::{HKEY_LOCAL_MACHINE, RRF_RT_REG_DWORD, RegGetValueW};
+    use windows::core::HSTRING;
+
+    let mut data = 0u32;
+    let mut size = std::mem::size_of::<u32>() as u32;
+    let result = unsafe {
+        RegGetValueW(
+            HKEY_LOCAL_MACHINE,
+            &HSTRING::from(POLICY_KEY),
+            &HSTRING::from(value),
+            RRF_RT_REG_DWORD,
+            None,
+            Some((&mut data as *mut u32).cast()),
+            Some(&mut size),
+        )
+    };
+    (result == NO_ERROR).then_some(data)
+}
+
+#[cfg(target_os = "windows")]
+fn read_policy_string(value: &str) -> Option<String> {
+    use windows::Win32::Foundation::NO_ERROR;
+    use windows::Win32::System::Registry::{HKEY_LOCAL_MACHINE, RRF_RT_REG_SZ, RegGetValueW};
+    use windows::core::HSTRING;
+
+    let subkey = HSTRING::from(POLICY_KEY);
+    let value = HSTRING::from(value);
+    let mut size = 0u32;
+    let result = unsafe {
+        RegGetValueW(
+            HKEY_LOCAL_MACHINE,
+            &subkey,
+            &value,
+            RRF_RT_REG_SZ,
+            None,
+            None,
+            Some(&mut size),
+        )
+    };
+    if result != NO_ERROR {
+        return None;
+    }
+
+    let mut buffer = vec![0u16; (size as usize).div_ceil(2)];
+    let result = unsafe {
+        RegGetValueW(
+            HKEY_LOCAL_MACHINE,
+            &subkey,
+            &value,
+            RRF_RT_REG_SZ,
+            None,
+            Some(buffer.as_mut_ptr().cast()),
+            Some(&mut size),
+        )
+    };
+    if result != NO_ERROR {
+        return None;
+    }
+    let len = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+    Some(String::from_utf16_lossy(&buffer[..len]))
+}
+
+/// Number of subkeys of `subkey` under HKLM, 0 when it does not exist.
+#[cfg(target_os = "windows")]
+fn count_subkeys(subkey: &str) -> u32 {
+    use windows::Win32::Foundation::NO_ERROR;
+    use windows::Win32::System::Registry::{
+        HKEY, HKEY_LOCAL_MACHINE, KEY_READ, RegCloseKey, RegOpenKeyExW, RegQueryInfoKeyW,
+    };
+    use windows::core::HSTRING;
+
+    let mut key = HKEY::default();
+    let result = unsafe {
+        RegOpenKeyExW(
+            HKEY_LOCAL_MACHINE,
+            &HSTRING::from(subkey),
+            None,
+            KEY_READ,
+            &mut key,
+        )
+    };
+    if result != NO_ERROR {
+        return 0;
+    }
+
+    let mut count = 0u32;
+    let result = unsafe {
+        RegQueryInfoKeyW(
+            key,
+            None,
+            None,
+            None,
+            Some(&mut count),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+    };
+    unsafe {
+        let _ = RegCloseKey(key);
+    }
+    if result == NO_ERROR { count } else { 0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policies_from_values() {
+        assert!(policies_from_values(None, None, None, 0).is_empty());
+        assert!(policies_from_values(Some(" "), Some(2), Some(""), 0).is_empty());
+
+        assert_eq!(
+            policies_from_values(Some("10.0.0.1 10.0.0.2"), Some(1), Some("corp.example"), 2),
+            vec![
+                DnsPolicy::Servers(vec!["10.0.0.1".to_string(), "10.0.0.2".to_string()]),
+                DnsPolicy::DohProhibited,
+                DnsPolicy::SuffixSearchList,
+                DnsPolicy::NrptRules,
+            ]
+        );
+        assert_eq!(
+            policies_from_values(None, Some(3), None, 0),
+            vec![DnsPolicy::DohRequired]
+        );
+    }
+
+    #[test]
+    fn test_policy_overrides() {
+        let automatic = DnsSettings::new();
+        let mut doh = DnsSettings::new();
+        doh.ipv4.enabled = true;
+        doh.ipv4.servers[0].address = "1.1.1.1".to_string();
+        doh.ipv4.servers[0].doh_mode = DohMode::On;
+        let mut plain = doh.clone();
+        plain.ipv4.servers[0].doh_mode = DohMode::Off;
+
+        let servers = DnsPolicy::Servers(vec!["10.0.0.1".to_string()]);
+        assert!(servers.overrides(&automatic));
+        assert!(!DnsPolicy::DohProhibited.overrides(&automatic));
+        assert!(DnsPolicy::DohProhibited.overrides(&doh));
+        assert!(!DnsPolicy::DohProhibited.overrides(&plain));
+        assert!(DnsPolicy::DohRequired.overrides(&plain));
+        assert!(!DnsPolicy::DohRequired.overrides(&doh));
+        assert!(!DnsPolicy::SuffixSearchList.overrides(&doh));
+        assert!(!DnsPolicy::NrptRules.overrides(&doh));
+
+        let policies = [DnsPolicy::DohProhibited, DnsPolicy::SuffixSearchList];
+        assert_eq!(
+            overriding_policies(&policies, &doh),
+            vec![&DnsPolicy::DohProhibited]
+        );
+    }
+}
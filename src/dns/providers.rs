@@ -0,0 +1,484 @@
+use crate::dns::types::{DnsEntry, DnsServerEntry, DnsSettings, DohMode};
+use crate::dns::validation::{
+    doh_template_host, server_ip, validate_doh_template, validate_ipv4, validate_ipv6,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+const CATALOG: &str = include_str!("providers.json");
+
+/// A resolver that can be filled into a profile in one click, either from
+/// the built-in catalog or added by the user.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct DnsProvider {
+    pub id: String,
+    pub name: String,
+    /// Server sets of the provider, e.g. unfiltered or family filtering.
+    /// The first one is used when no variant is chosen.
+    pub variants: Vec<ProviderVariant>,
+}
+
+impl DnsProvider {
+    pub fn variant(&self, id: &str) -> Option<&ProviderVariant> {
+        self.variants.iter().find(|v| v.id == id)
+    }
+
+    pub fn default_variant(&self) -> Option<&ProviderVariant> {
+        self.variants.first()
+    }
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ProviderVariant {
+    pub id: String,
+    pub name: String,
+    pub ipv4: Vec<String>,
+    pub ipv6: Vec<String>,
+    /// DoH template served on every address of the variant; empty if the
+    /// resolver has no DoH endpoint.
+    #[serde(default)]
+    pub doh_template: String,
+}
+
+impl ProviderVariant {
+    /// Returns `settings` with the servers of this variant. The DoH
+    /// template is always filled in, but DoH is only turned on when
+    /// `use_doh` is set and there is a template. Suffixes and NRPT rules
+    /// are kept.
+    pub fn fill(&self, settings: &DnsSettings, use_doh: bool) -> DnsSettings {
+        let doh_mode = if use_doh && !self.doh_template.is_empty() {
+            DohMode::On
+        } else {
+            DohMode::Off
+        };
+        let entry = |addresses: &[String]| {
+            if addresses.is_empty() {
+                return DnsEntry::default();
+            }
+
+            DnsEntry {
+                enabled: true,
+                unchanged: false,
+                servers: addresses
+                    .iter()
+                    .map(|address| DnsServerEntry {
+                        address: address.clone(),
+                        doh_mode,
+                        doh_template: self.doh_template.clone(),
+                        allow_fallback: false,
+                        auto_upgrade: true,
+                    })
+                    .collect(),
+            }
+        };
+
+        DnsSettings {
+            ipv4: entry(&self.ipv4),
+            ipv6: entry(&self.ipv6),
+            local_resolver: None,
+            ..settings.clone()
+        }
+    }
+}
+
+/// The built-in provider catalog, in display order.
+pub fn providers() -> &'static [DnsProvider] {
+    static PROVIDERS: OnceLock<Vec<DnsProvider>> = OnceLock::new();
+    PROVIDERS.get_or_init(|| {
+        serde_json::from_str(CATALOG).expect("built-in provider catalog must be valid")
+    })
+}
+
+pub fn find_provider(id: &str) -> Option<&'static DnsProvider> {
+    providers().iter().find(|p| p.id == id)
+}
+
+/// Providers for the DoH servers Windows has templates for, leaving out
+/// the servers a provider in `known` already has with the same template.
+/// Servers sharing a template become one provider, named after the host of
+/// the template.
+pub fn providers_from_doh_servers(
+    servers: &[DnsServerEntry],
+    known: &[DnsProvider],
+) -> Vec<DnsProvider> {
+    let has_server = |provider: &DnsProvider, server: &DnsServerEntry| {
+        provider.variants.iter().any(|variant| {
+            variant.doh_template.trim() == server.doh_template.trim()
+                && variant
+                    .ipv4
+                    .iter()
+                    .chain(&variant.ipv6)
+                    .any(|address| server_ip(address) == server_ip(&server.address))
+        })
+    };
+
+    let mut imported: Vec<DnsProvider> = Vec::new();
+    for server in servers {
+        let template = server.doh_template.trim();
+        if template.is_empty()
+            || server_ip(&server.address).is_none()
+            || known.iter().chain(&imported).any(|p| has_server(p, server))
+        {
+            continue;
+        }
+        let index = match imported
+            .iter()
+            .position(|p| p.variants[0].doh_template == template)
+        {
+            Some(index) => index,
+            None => {
+                let name = unique_name(
+                    doh_template_host(template).unwrap_or(template),
+                    known.iter().chain(&imported),
+                );
+                imported.push(DnsProvider {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    name,
+                    variants: vec![ProviderVariant {
+                        id: "default".to_string(),
+                        name: "Default".to_string(),
+                        ipv4: Vec::new(),
+                        ipv6: Vec::new(),
+                        doh_template: template.to_string(),
+                    }],
+                });
+                imported.len() - 1
+            }
+        };
+        let variant = &mut imported[index].variants[0];
+        if validate_ipv4(&server.address) {
+            variant.ipv4.push(server.address.clone());
+        } else {
+            variant.ipv6.push(server.address.clone());
+        }
+    }
+    imported
+}
+
+/// `name`, or `name (2)`, `name (3)` and so on if a provider in `taken`
+/// already has it, ignoring case.
+fn unique_name<'a>(name: &str, taken: impl Iterator<Item = &'a DnsProvider> + Clone) -> String {
+    let is_taken = |candidate: &str| {
+        taken
+            .clone()
+            .any(|p| p.name.eq_ignore_ascii_case(candidate))
+    };
+    if !is_taken(name) {
+        return name.to_string();
+    }
+    (2..)
+        .map(|n| format!("{} ({})", name, n))
+        .find(|candidate| !is_taken(candidate))
+        .expect("some numbered name is free")
+}
+
+/// Form contents of a user-defined provider being added or edited.
+/// Addresses are kept as typed, separated by commas or spaces.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct ProviderDraft {
+    /// Id of the provider being edited; `None` while adding a new one.
+    pub id: Option<String>,
+    pub name: String,
+    pub ipv4: String,
+    pub ipv6: String,
+    pub doh_template: String,
+}
+
+impl ProviderDraft {
+    pub fn from_provider(provider: &DnsProvider) -> Self {
+        let variant = provider.default_variant();
+        Self {
+            id: Some(provider.id.clone()),
+            name: provider.name.clone(),
+            ipv4: variant.map(|v| v.ipv4.join(", ")).unwrap_or_default(),
+            ipv6: variant.map(|v| v.ipv6.join(", ")).unwrap_or_default(),
+            doh_template: variant.map(|v| v.doh_template.clone()).unwrap_or_default(),
+        }
+    }
+
+    /// Validates the form and builds a single-variant provider from it. A
+    /// new provider gets a fresh id.
+    pub fn to_provider(&self) -> Result<DnsProvider, String> {
+        let name = self.name.trim();
+        if name.is_empty() {
+            return Err("Provider name cannot be empty".to_string());
+        }
+
+        let ipv4 = split_addresses(&self.ipv4);
+        if let Some(address) = ipv4.iter().find(|a| !validate_ipv4(a)) {
+            return Err(format!("Invalid IPv4 address: {}", address));
+        }
+        let ipv6 = split_addresses(&self.ipv6);
+        if let Some(address) = ipv6.iter().find(|a| !validate_ipv6(a)) {
+            return Err(format!("Invalid IPv6 address: {}", address));
+        }
+        if ipv4.is_empty() && ipv6.is_empty() {
+            return Err("Provider needs at least one IPv4 or IPv6 address".to_string());
+        }
+
+        let doh_template = self.doh_template.trim();
+        if !validate_doh_template(doh_template) {
+            return Err("Invalid DoH template URL".to_string());
+        }
+
+        Ok(DnsProvider {
+            id: self
+                .id
+                .clone()
+                .unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+            name: name.to_string(),
+            variants: vec![ProviderVariant {
+                id: "default".to_string(),
+                name: "Default".to_string(),
+                ipv4,
+                ipv6,
+                doh_template: doh_template.to_string(),
+            }],
+        })
+    }
+}
+
+fn split_addresses(text: &str) -> Vec<String> {
+    text.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|a| !a.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dns::types::NrptSettings;
+    use crate::dns::validation::check_doh_template;
+
+    #[test]
+    fn test_providers_catalog_is_valid() {
+        let providers = providers();
+        assert!(providers.len() >= 6);
+
+        for provider in providers {
+            assert!(!provider.variants.is_empty(), "{}", provider.id);
+            for variant in &provider.variants {
+                assert!(!variant.ipv4.is_empty(), "{}/{}", provider.id, variant.id);
+                assert!(variant.ipv4.iter().all(|a| validate_ipv4(a)));
+                assert!(variant.ipv6.iter().all(|a| validate_ipv6(a)));
+                assert!(variant.doh_template.starts_with("https://"));
+                assert_eq!(check_doh_template(&variant.doh_template), vec![]);
+            }
+            assert!(unique(provider.variants.iter().map(|v| &v.id)));
+        }
+        assert!(unique(providers.iter().map(|p| &p.id)));
+    }
+
+    fn unique<'a>(ids: impl Iterator<Item = &'a String>) -> bool {
+        let mut ids: Vec<_> = ids.collect();
+        let len = ids.len();
+        ids.sort();
+        ids.dedup();
+        ids.len() == len
+    }
+
+    #[test]
+    fn test_provider_variants() {
+        let cloudflare = find_provider("cloudflare").unwrap();
+        assert_eq!(cloudflare.default_variant().unwrap().ipv4[0], "1.1.1.1");
+        assert_eq!(cloudflare.variant("malware").unwrap().ipv4[0], "1.1.1.2");
+        assert_eq!(cloudflare.variant("family").unwrap().ipv4[0], "1.1.1.3");
+        assert!(cloudflare.variant("unknown").is_none());
+
+        assert_eq!(
+            find_provider("quad9").unwrap().variant("ecs").unwrap().ipv4[0],
+            "9.9.9.11"
+        );
+        assert!(
+            find_provider("adguard")
+                .unwrap()
+                .variant("family")
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn test_provider_fill_without_template_leaves_doh_off() {
+        let variant = ProviderVariant {
+            id: "default".to_string(),
+            name: "Default".to_string(),
+            ipv4: vec!["192.168.1.53".to_string()],
+            ipv6: Vec::new(),
+            doh_template: String::new(),
+        };
+
+        let filled = variant.fill(&DnsSettings::new(), true);
+        assert_eq!(filled.ipv4.servers[0].doh_mode, DohMode::Off);
+        assert!(!filled.ipv6.enabled);
+    }
+
+    fn doh_server(address: &str, template: &str) -> DnsServerEntry {
+        DnsServerEntry {
+            address: address.to_string(),
+            doh_mode: DohMode::On,
+            doh_template: template.to_string(),
+            allow_fallback: false,
+            auto_upgrade: false,
+        }
+    }
+
+    #[test]
+    fn test_providers_from_doh_servers() {
+        let nextdns = "https://dns.nextdns.io/abc123";
+        let servers = [
+            // Shipped with Windows and already in the catalog.
+            doh_server("1.1.1.1", "https://cloudflare-dns.com/dns-query"),
+            doh_server("45.90.28.0", nextdns),
+            doh_server("2a07:a8c0::", nextdns),
+            doh_server("45.90.28.0", nextdns),
+            doh_server("10.0.0.53", "https://doh.corp.example/dns-query{?dns}"),
+        ];
+
+        let imported = providers_from_doh_servers(&servers, providers());
+        assert_eq!(imported.len(), 2);
+        assert_eq!(imported[0].name, "dns.nextdns.io");
+        let variant = imported[0].default_variant().unwrap();
+        assert_eq!(variant.ipv4, vec!["45.90.28.0"]);
+        assert_eq!(variant.ipv6, vec!["2a07:a8c0::"]);
+        assert_eq!(variant.doh_template, nextdns);
+        assert_eq!(imported[1].name, "doh.corp.example");
+        assert_ne!(imported[0].id, imported[1].id);
+
+        // Importing again adds nothing.
+        let known: Vec<DnsProvider> = providers().iter().chain(&imported).cloned().collect();
+        assert!(providers_from_doh_servers(&servers, &known).is_empty());
+    }
+
+    #[test]
+    fn test_providers_from_doh_servers_keeps_names_unique() {
+        let mut known = providers().to_vec();
+        known.push(DnsProvider {
+            id: "custom".to_string(),
+            name: "DNS.NextDNS.io".to_string(),
+            variants: Vec::new(),
+        });
+        let servers = [
+            doh_server("45.90.28.0", "https://dns.nextdns.io/abc123"),
+            doh_server("45.90.30.0", "https://dns.nextdns.io/def456"),
+        ];
+
+        let names: Vec<String> = providers_from_doh_servers(&servers, &known)
+            .into_iter()
+            .map(|p| p.name)
+            .collect();
+        assert_eq!(names, vec!["dns.nextdns.io (2)", "dns.nextdns.io (3)"]);
+    }
+
+    #[test]
+    fn test_provider_draft_round_trip() {
+        let draft = ProviderDraft {
+            id: None,
+            name: " Pi-hole ".to_string(),
+            ipv4: "192.168.1.53,  192.168.1.54".to_string(),
+            ipv6: "fd00::53".to_string(),
+            doh_template: String::new(),
+        };
+
+        let provider = draft.to_provider().unwrap();
+        assert_eq!(provider.name, "Pi-hole");
+        assert!(!provider.id.is_empty());
+        let variant = provider.default_variant().unwrap();
+        assert_eq!(variant.ipv4, vec!["192.168.1.53", "192.168.1.54"]);
+        assert_eq!(variant.ipv6, vec!["fd00::53"]);
+
+        let edited = ProviderDraft::from_provider(&provider);
+        assert_eq!(edited.id.as_deref(), Some(provider.id.as_str()));
+        assert_eq!(edited.ipv4, "192.168.1.53, 192.168.1.54");
+        assert_eq!(edited.to_provider().unwrap(), provider);
+    }
+
+    #[test]
+    fn test_provider_draft_validation() {
+        let valid = ProviderDraft {
+            name: "Home".to_string(),
+            ipv4: "192.168.1.53".to_string(),
+            ..Default::default()
+        };
+        assert!(valid.to_provider().is_ok());
+
+        let cases = [
+            ProviderDraft {
+                name: "  ".to_string(),
+                ..valid.clone()
+            },
+            ProviderDraft {
+                ipv4: "192.168.1".to_string(),
+                ..valid.clone()
+            },
+            ProviderDraft {
+                ipv6: "192.168.1.53".to_string(),
+                ..valid.clone()
+            },
+            ProviderDraft {
+                ipv4: " , ".to_string(),
+                ..valid.clone()
+            },
+            ProviderDraft {
+                doh_template: "http://dns.example/dns-query".to_string(),
+                ..valid.clone()
+            },
+        ];
+        for draft in cases {
+            assert!(draft.to_provider().is_err(), "{:?}", draft);
+        }
+    }
+
+    #[test]
+    fn test_find_provider() {
+        assert_eq!(find_provider("quad9").unwrap().name, "Quad9");
+        assert!(find_provider("unknown").is_none());
+    }
+
+    #[test]
+    fn test_provider_fill_replaces_servers_only() {
+        let mut settings = DnsSettings::new();
+        settings.connection_suffix = Some("corp.example.com".to_string());
+        settings.nrpt = NrptSettings {
+            enabled: true,
+            rules: Vec::new(),
+        };
+
+        let filled = find_provider("cloudflare")
+            .unwrap()
+            .default_variant()
+            .unwrap()
+            .fill(&settings, true);
+
+        assert!(filled.ipv4.enabled);
+        assert!(filled.ipv6.enabled);
+        assert_eq!(filled.ipv4.servers.len(), 2);
+        assert_eq!(filled.ipv4.servers[1].address, "1.0.0.1");
+        assert_eq!(filled.ipv6.servers[0].doh_mode, DohMode::On);
+        assert_eq!(
+            filled.ipv6.servers[0].doh_template,
+            "https://cloudflare-dns.com/dns-query"
+        );
+        assert_eq!(filled.connection_suffix, settings.connection_suffix);
+        assert_eq!(filled.nrpt, settings.nrpt);
+    }
+
+    #[test]
+    fn test_provider_fill_without_doh() {
+        let filled = find_provider("mullvad")
+            .unwrap()
+            .default_variant()
+            .unwrap()
+            .fill(&DnsSettings::new(), false);
+
+        assert_eq!(filled.ipv4.servers.len(), 1);
+        assert!(
+            filled
+                .ipv4
+                .servers
+                .iter()
+                .chain(&filled.ipv6.servers)
+                .all(|s| s.doh_mode == DohMode::Off && !s.doh_template.is_empty())
+        );
+    }
+}
@@ -0,0 +1,189 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::future::{Future, poll_fn};
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::Poll;
+use tokio::sync::{mpsc, oneshot};
+
+type Job = Pin<Box<dyn Future<Output = ()>>>;
+type JobReceiver = mpsc::UnboundedReceiver<(String, Job)>;
+
+/// Runs DNS operations one after another per interface, so that an apply,
+/// a refresh and an automatic switch on the same interface never
+/// interleave their commands. Operations on different interfaces run side
+/// by side.
+#[derive(Clone)]
+pub struct CommandQueue {
+    jobs: mpsc::UnboundedSender<(String, Job)>,
+    receiver: Rc<RefCell<Option<JobReceiver>>>,
+}
+
+impl fmt::Debug for CommandQueue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CommandQueue").finish_non_exhaustive()
+    }
+}
+
+impl Default for CommandQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CommandQueue {
+    pub fn new() -> Self {
+        let (jobs, receiver) = mpsc::unbounded_channel();
+        Self {
+            jobs,
+            receiver: Rc::new(RefCell::new(Some(receiver))),
+        }
+    }
+
+    /// The task that runs the queued operations, for the caller to spawn.
+    /// Only the first call gets it.
+    pub fn worker(&self) -> Option<impl Future<Output = ()> + 'static> {
+        let receiver = self.receiver.borrow_mut().take()?;
+        Some(run_jobs(receiver))
+    }
+
+    /// Queues `operation` behind the others on the interface `lane` and
+    /// waits for its result. Dropping the returned future, as cancelling
+    /// an apply does, drops the operation too, which kills its running
+    /// command. `None` when the worker has stopped.
+    pub async fn run<T: 'static>(
+        &self,
+        lane: &str,
+        operation: impl Future<Output = T> + 'static,
+    ) -> Option<T> {
+        let (sender, receiver) = oneshot::channel();
+        let mut sender = Some(sender);
+        let mut operation = Box::pin(operation);
+        let job = poll_fn(move |cx| {
+            let Some(result) = sender.as_mut() else {
+                return Poll::Ready(());
+            };
+            if result.poll_closed(cx).is_ready() {
+                return Poll::Ready(());
+            }
+            let value = std::task::ready!(operation.as_mut().poll(cx));
+            if let Some(result) = sender.take() {
+                let _ = result.send(value);
+            }
+            Poll::Ready(())
+        });
+        self.jobs.send((lane.to_string(), Box::pin(job))).ok()?;
+        receiver.await.ok()
+    }
+}
+
+/// Starts the first waiting job of each lane that has none running, and
+/// polls the running ones, until every `CommandQueue` is gone.
+async fn run_jobs(mut receiver: JobReceiver) {
+    let mut waiting: HashMap<String, VecDeque<Job>> = HashMap::new();
+    let mut running: Vec<(String, Job)> = Vec::new();
+    let mut closed = false;
+    poll_fn(|cx| {
+        while !closed {
+            match receiver.poll_recv(cx) {
+                Poll::Ready(Some((lane, job))) => waiting.entry(lane).or_default().push_back(job),
+                Poll::Ready(None) => closed = true,
+                Poll::Pending => break,
+            }
+        }
+        loop {
+            for (lane, jobs) in waiting.iter_mut() {
+                if !running.iter().any(|(busy, _)| busy == lane)
+                    && let Some(job) = jobs.pop_front()
+                {
+                    running.push((lane.clone(), job));
+                }
+            }
+            waiting.retain(|_, jobs| !jobs.is_empty());
+            let count = running.len();
+            running.retain_mut(|(_, job)| job.as_mut().poll(cx).is_pending());
+            // A finished job may have let the next one on its lane start.
+            if running.len() == count {
+                break;
+            }
+        }
+        if closed && running.is_empty() && waiting.is_empty() {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_runs_one_job_per_lane_at_a_time() {
+        let queue = CommandQueue::new();
+        let worker = queue.worker().unwrap();
+        assert!(queue.worker().is_none());
+
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let step = |name: &'static str, millis: u64| {
+            let log = log.clone();
+            async move {
+                log.borrow_mut().push(format!("{} start", name));
+                tokio::time::sleep(Duration::from_millis(millis)).await;
+                log.borrow_mut().push(format!("{} end", name));
+                name
+            }
+        };
+        let operations = async move {
+            let results = tokio::join!(
+                queue.run("a", step("first", 30)),
+                queue.run("a", step("second", 0)),
+                queue.run("b", step("other", 0)),
+            );
+            assert_eq!(results, (Some("first"), Some("second"), Some("other")));
+        };
+        tokio::join!(worker, operations);
+
+        let log = log.borrow();
+        let position = |entry: &str| log.iter().position(|e| e == entry).unwrap();
+        assert!(position("first end") < position("second start"));
+        assert!(position("other end") < position("first end"));
+    }
+
+    #[tokio::test]
+    async fn test_dropping_the_caller_drops_the_operation() {
+        struct SetOnDrop(Rc<Cell<bool>>);
+        impl Drop for SetOnDrop {
+            fn drop(&mut self) {
+                self.0.set(true);
+            }
+        }
+
+        let queue = CommandQueue::new();
+        let worker = queue.worker().unwrap();
+        let dropped = Rc::new(Cell::new(false));
+        let operation = {
+            let guard = SetOnDrop(dropped.clone());
+            async move {
+                let _guard = guard;
+                std::future::pending::<()>().await;
+            }
+        };
+        let operations = async move {
+            let stalled = queue.run("a", operation);
+            assert!(
+                tokio::time::timeout(Duration::from_millis(20), stalled)
+                    .await
+                    .is_err()
+            );
+            assert_eq!(queue.run("a", async { 1 }).await, Some(1));
+        };
+        tokio::join!(worker, operations);
+        assert!(dropped.get());
+    }
+}
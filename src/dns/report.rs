@@ -0,0 +1,416 @@
+use crate::dns::backend::DnsBackend;
+use crate::dns::commands::{command_error_message, run_powershell};
+use crate::dns::types::{CurrentDnsState, DnsOrigin, NetworkInterface};
+use crate::dns::updater::current_version;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::path::Path;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ReportError {
+    #[error("Failed to read DNS suffixes: {0}")]
+    Command(String),
+
+    #[error("Failed to parse DNS suffixes")]
+    InvalidOutput,
+
+    #[error("Failed to write the report: {0}")]
+    Write(String),
+}
+
+pub type Result<T> = std::result::Result<T, ReportError>;
+
+/// DNS suffixes of every adapter and the global search list.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DnsSuffixes {
+    /// Connection-specific suffix by interface index.
+    pub connection: HashMap<u32, String>,
+    pub search_list: Vec<String>,
+}
+
+const DNS_SUFFIXES_SCRIPT: &str = r#"
+$adapters = @(Get-DnsClient | ForEach-Object { @{ Index = $_.InterfaceIndex; Suffix = $_.ConnectionSpecificSuffix } })
+$searchList = @((Get-DnsClientGlobalSetting).SuffixSearchList)
+ConvertTo-Json -Compress -Depth 3 -InputObject @{ Adapters = $adapters; SearchList = $searchList }
+"#;
+
+#[derive(Deserialize)]
+struct SuffixOutput {
+    #[serde(rename = "Adapters", default)]
+    adapters: Vec<AdapterSuffix>,
+    #[serde(rename = "SearchList", default)]
+    search_list: Vec<Option<String>>,
+}
+
+#[derive(Deserialize)]
+struct AdapterSuffix {
+    #[serde(rename = "Index")]
+    index: u32,
+    #[serde(rename = "Suffix", default)]
+    suffix: Option<String>,
+}
+
+/// Parses the JSON object printed by `DNS_SUFFIXES_SCRIPT`.
+fn parse_dns_suffixes(output: &str) -> Result<DnsSuffixes> {
+    let output: SuffixOutput =
+        serde_json::from_str(output.trim()).map_err(|_| ReportError::InvalidOutput)?;
+    Ok(DnsSuffixes {
+        connection: output
+            .adapters
+            .into_iter()
+            .filter_map(|a| Some((a.index, a.suffix.filter(|s| !s.is_empty())?)))
+            .collect(),
+        search_list: output.search_list.into_iter().flatten().collect(),
+    })
+}
+
+pub async fn get_dns_suffixes() -> Result<DnsSuffixes> {
+    let output = run_powershell(DNS_SUFFIXES_SCRIPT)
+        .await
+        .map_err(|e| ReportError::Command(command_error_message(e)))?;
+    parse_dns_suffixes(&output)
+}
+
+/// Everything windns knows about the DNS setup of this computer, as saved
+/// for support tickets or for comparing machines.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct StateReport {
+    pub windns_version: String,
+    pub computer: Option<String>,
+    pub adapters: Vec<AdapterReport>,
+    pub doh_templates: Vec<DohTemplateReport>,
+    pub suffix_search_list: Vec<String>,
+    /// Parts of the state that could not be read.
+    pub errors: Vec<String>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct AdapterReport {
+    pub name: String,
+    pub interface_index: u32,
+    pub interface_guid: String,
+    /// Whether the adapter gets its DNS servers automatically.
+    pub automatic: bool,
+    /// Name of the profile whose servers are in use, if any.
+    pub profile: Option<String>,
+    pub ipv4: Vec<String>,
+    pub ipv4_origin: DnsOrigin,
+    pub ipv6: Vec<String>,
+    pub ipv6_origin: DnsOrigin,
+    /// Servers that are queried over DoH.
+    pub doh_servers: Vec<String>,
+    pub connection_suffix: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DohTemplateReport {
+    pub address: String,
+    pub template: String,
+    pub allow_fallback: bool,
+}
+
+/// Reads the DNS servers of every interface and the system-wide DoH
+/// templates. `profile_of` names the profile matching an interface's
+/// servers.
+pub async fn collect_state_report<B: DnsBackend>(
+    backend: &B,
+    interfaces: &[NetworkInterface],
+    suffixes: Result<DnsSuffixes>,
+    profile_of: impl Fn(&NetworkInterface, &CurrentDnsState) -> Option<String>,
+) -> StateReport {
+    let mut errors = Vec::new();
+    let suffixes = suffixes.unwrap_or_else(|e| {
+        errors.push(e.to_string());
+        DnsSuffixes::default()
+    });
+
+    let mut adapters = Vec::with_capacity(interfaces.len());
+    for interface in interfaces {
+        let (current, error) = match backend.get_current_dns(interface).await {
+            Ok(current) => (current, None),
+            Err(e) => (CurrentDnsState::new(), Some(e.to_string())),
+        };
+        let automatic = error.is_none() && current.is_automatic();
+        let profile = if error.is_none() && !automatic {
+            profile_of(interface, &current)
+        } else {
+            None
+        };
+        adapters.push(AdapterReport {
+            name: interface.name.clone(),
+            interface_index: interface.interface_index,
+            interface_guid: interface.interface_guid.clone(),
+            automatic,
+            profile,
+            ipv4: current.ipv4,
+            ipv4_origin: current.ipv4_origin,
+            ipv6: current.ipv6,
+            ipv6_origin: current.ipv6_origin,
+            doh_servers: current.doh_servers,
+            connection_suffix: suffixes.connection.get(&interface.interface_index).cloned(),
+            error,
+        });
+    }
+
+    let doh_templates = match backend.get_doh_templates().await {
+        Ok(entries) => entries
+            .into_iter()
+            .map(|entry| DohTemplateReport {
+                address: entry.address,
+                template: entry.doh_template,
+                allow_fallback: entry.allow_fallback,
+            })
+            .collect(),
+        Err(e) => {
+            errors.push(e.to_string());
+            Vec::new()
+        }
+    };
+
+    StateReport {
+        windns_version: current_version().to_string(),
+        computer: std::env::var("COMPUTERNAME").ok(),
+        adapters,
+        doh_templates,
+        suffix_search_list: suffixes.search_list,
+        errors,
+    }
+}
+
+impl StateReport {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::from("# windns DNS state\n\n");
+        let _ = writeln!(out, "- windns version: {}", self.windns_version);
+        if let Some(computer) = &self.computer {
+            let _ = writeln!(out, "- Computer: {}", computer);
+        }
+
+        out.push_str("\n## Adapters\n");
+        for adapter in &self.adapters {
+            let _ = write!(
+                out,
+                "\n### {} ({})\n\n- GUID: {}\n",
+                adapter.name, adapter.interface_index, adapter.interface_guid
+            );
+            if let Some(error) = &adapter.error {
+                let _ = writeln!(out, "- Error: {}", error);
+                continue;
+            }
+            let profile = match &adapter.profile {
+                _ if adapter.automatic => "Automatic",
+                Some(name) => name,
+                None => "Custom",
+            };
+            let _ = writeln!(out, "- Profile: {}", profile);
+            let _ = writeln!(
+                out,
+                "- IPv4{}: {}",
+                origin_suffix(adapter.ipv4_origin),
+                list_or_none(&adapter.ipv4)
+            );
+            let _ = writeln!(
+                out,
+                "- IPv6{}: {}",
+                origin_suffix(adapter.ipv6_origin),
+                list_or_none(&adapter.ipv6)
+            );
+            let _ = writeln!(out, "- DoH: {}", list_or_none(&adapter.doh_servers));
+            let _ = writeln!(
+                out,
+                "- Connection suffix: {}",
+                adapter.connection_suffix.as_deref().unwrap_or("none")
+            );
+        }
+
+        out.push_str("\n## DoH Templates\n\n");
+        if self.doh_templates.is_empty() {
+            out.push_str("None\n");
+        } else {
+            out.push_str("| Server | Template | Fallback to UDP |\n|---|---|---|\n");
+            for entry in &self.doh_templates {
+                let _ = writeln!(
+                    out,
+                    "| {} | {} | {} |",
+                    entry.address,
+                    entry.template,
+                    if entry.allow_fallback { "yes" } else { "no" }
+                );
+            }
+        }
+
+        out.push_str("\n## Suffix Search List\n\n");
+        if self.suffix_search_list.is_empty() {
+            out.push_str("None\n");
+        }
+        for suffix in &self.suffix_search_list {
+            let _ = writeln!(out, "- {}", suffix);
+        }
+
+        if !self.errors.is_empty() {
+            out.push_str("\n## Errors\n\n");
+            for error in &self.errors {
+                let _ = writeln!(out, "- {}", error);
+            }
+        }
+        out
+    }
+}
+
+fn origin_suffix(origin: DnsOrigin) -> &'static str {
+    match origin {
+        DnsOrigin::Unknown => "",
+        DnsOrigin::Dhcp => " (DHCP)",
+        DnsOrigin::Static => " (static)",
+    }
+}
+
+fn list_or_none(items: &[String]) -> String {
+    if items.is_empty() {
+        "none".to_string()
+    } else {
+        items.join(", ")
+    }
+}
+
+/// Writes the report as Markdown for a `.md` path and as JSON otherwise.
+pub fn save_state_report(report: &StateReport, path: &Path) -> Result<()> {
+    let is_markdown = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("md"));
+    let content = if is_markdown {
+        report.to_markdown()
+    } else {
+        report.to_json()
+    };
+    std::fs::write(path, content).map_err(|e| ReportError::Write(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dns::backend::mock::MockBackend;
+    use crate::dns::types::test_interface;
+
+    #[test]
+    fn test_parse_dns_suffixes() {
+        let output = r#"{"Adapters":[{"Index":12,"Suffix":"corp.example"},{"Index":3,"Suffix":""},{"Index":7,"Suffix":null}],"SearchList":["corp.example","example.org"]}"#;
+        let suffixes = parse_dns_suffixes(output).unwrap();
+        assert_eq!(suffixes.connection.len(), 1);
+        assert_eq!(
+            suffixes.connection.get(&12).map(String::as_str),
+            Some("corp.example")
+        );
+        assert_eq!(suffixes.search_list, vec!["corp.example", "example.org"]);
+
+        let suffixes = parse_dns_suffixes(r#"{"Adapters":[],"SearchList":[null]}"#).unwrap();
+        assert_eq!(suffixes, DnsSuffixes::default());
+        assert!(parse_dns_suffixes("").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_collect_state_report() {
+        let backend = MockBackend::new();
+        *backend.current.borrow_mut() = CurrentDnsState {
+            ipv4: vec!["1.1.1.1".to_string()],
+            ipv4_origin: DnsOrigin::Static,
+            doh_servers: vec!["1.1.1.1".to_string()],
+            ..Default::default()
+        };
+        *backend.doh_servers.borrow_mut() = vec!["1.1.1.1".to_string()];
+        let suffixes = DnsSuffixes {
+            connection: HashMap::from([(2, "corp.example".to_string())]),
+            search_list: vec!["example.org".to_string()],
+        };
+        let interfaces = [test_interface("Ethernet", 1), test_interface("Wi-Fi", 2)];
+
+        let report = collect_state_report(&backend, &interfaces, Ok(suffixes), |i, _| {
+            (i.interface_index == 1).then(|| "Cloudflare".to_string())
+        })
+        .await;
+        assert_eq!(report.adapters.len(), 2);
+        assert_eq!(report.adapters[0].profile.as_deref(), Some("Cloudflare"));
+        assert!(!report.adapters[0].automatic);
+        assert_eq!(report.adapters[0].connection_suffix, None);
+        assert_eq!(report.adapters[1].profile, None);
+        assert_eq!(
+            report.adapters[1].connection_suffix.as_deref(),
+            Some("corp.example")
+        );
+        assert_eq!(report.doh_templates.len(), 1);
+        assert_eq!(
+            report.doh_templates[0].template,
+            "https://1.1.1.1/dns-query"
+        );
+        assert_eq!(report.suffix_search_list, vec!["example.org"]);
+        assert!(report.errors.is_empty());
+
+        let report =
+            collect_state_report(&backend, &[], Err(ReportError::InvalidOutput), |_, _| None).await;
+        assert_eq!(report.errors, vec!["Failed to parse DNS suffixes"]);
+    }
+
+    #[test]
+    fn test_state_report_markdown() {
+        let report = StateReport {
+            windns_version: "1.2.3".to_string(),
+            computer: Some("PC".to_string()),
+            adapters: vec![
+                AdapterReport {
+                    name: "Ethernet".to_string(),
+                    interface_index: 1,
+                    interface_guid: "{GUID-1}".to_string(),
+                    automatic: false,
+                    profile: Some("Cloudflare".to_string()),
+                    ipv4: vec!["1.1.1.1".to_string(), "1.0.0.1".to_string()],
+                    ipv4_origin: DnsOrigin::Static,
+                    ipv6: Vec::new(),
+                    ipv6_origin: DnsOrigin::Unknown,
+                    doh_servers: vec!["1.1.1.1".to_string()],
+                    connection_suffix: None,
+                    error: None,
+                },
+                AdapterReport {
+                    name: "Wi-Fi".to_string(),
+                    interface_index: 2,
+                    interface_guid: "{GUID-2}".to_string(),
+                    automatic: true,
+                    profile: None,
+                    ipv4: Vec::new(),
+                    ipv4_origin: DnsOrigin::Unknown,
+                    ipv6: Vec::new(),
+                    ipv6_origin: DnsOrigin::Unknown,
+                    doh_servers: Vec::new(),
+                    connection_suffix: None,
+                    error: Some("Timed out".to_string()),
+                },
+            ],
+            doh_templates: vec![DohTemplateReport {
+                address: "1.1.1.1".to_string(),
+                template: "https://cloudflare-dns.com/dns-query".to_string(),
+                allow_fallback: false,
+            }],
+            suffix_search_list: Vec::new(),
+            errors: Vec::new(),
+        };
+
+        let markdown = report.to_markdown();
+        assert!(markdown.contains("- Computer: PC\n"));
+        assert!(markdown.contains("### Ethernet (1)\n\n- GUID: {GUID-1}\n- Profile: Cloudflare\n"));
+        assert!(markdown.contains("- IPv4 (static): 1.1.1.1, 1.0.0.1\n- IPv6: none\n"));
+        assert!(markdown.contains("### Wi-Fi (2)\n\n- GUID: {GUID-2}\n- Error: Timed out\n\n"));
+        assert!(markdown.contains("| 1.1.1.1 | https://cloudflare-dns.com/dns-query | no |"));
+        assert!(markdown.contains("## Suffix Search List\n\nNone\n"));
+        assert!(!markdown.contains("## Errors"));
+
+        let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+        assert_eq!(json["adapters"][0]["ipv4_origin"], "Static");
+        assert_eq!(json["doh_templates"][0]["allow_fallback"], false);
+    }
+}
@@ -0,0 +1,178 @@
+use crate::dns::backend::{DnsBackend, SystemBackend};
+use crate::dns::commands::{Result, set_dns_with_settings, snapshot_dns_settings};
+use crate::dns::config::save_config;
+use crate::dns::elevation::is_elevated;
+use crate::dns::history::{HistoryEntry, append_history};
+use crate::dns::network::get_network_interfaces;
+use crate::dns::types::{AppConfig, DnsSettings, NetworkInterface};
+use crate::i18n::t;
+
+/// Command line argument that resets every adapter and exits, as
+/// `windns reset-all`.
+const RESET_ALL_ARG: &str = "reset-all";
+
+pub fn reset_all_argument(args: impl IntoIterator<Item = String>) -> bool {
+    args.into_iter().any(|arg| arg == RESET_ALL_ARG)
+}
+
+/// The adapters a reset of all of them covers: every physical one, and the
+/// virtual ones windns has applied settings to. VPN and virtual machine
+/// adapters windns never touched keep the servers their software set.
+pub fn interfaces_to_reset(
+    interfaces: Vec<NetworkInterface>,
+    config: &AppConfig,
+) -> Vec<NetworkInterface> {
+    interfaces
+        .into_iter()
+        .filter(|interface| {
+            !interface.is_virtual
+                || config
+                    .interface_profiles
+                    .contains_key(&interface.interface_guid)
+                || config.original_dns.contains_key(&interface.interface_guid)
+        })
+        .collect()
+}
+
+/// Resets each of `interfaces` to the DNS servers DHCP provides, turning
+/// off the DoH settings windns made for it, and carries on past failures.
+/// Returns the settings each had before, if they could be read, and the
+/// result.
+pub async fn reset_interfaces<B: DnsBackend>(
+    backend: &B,
+    interfaces: &[NetworkInterface],
+) -> Vec<(Option<DnsSettings>, Result<Option<String>>)> {
+    let mut results = Vec::new();
+    for interface in interfaces {
+        let before = snapshot_dns_settings(backend, interface).await.ok();
+        let result = set_dns_with_settings(backend, interface, &DnsSettings::new()).await;
+        results.push((before, result));
+    }
+    results
+}
+
+/// Runs `windns reset-all` without opening the window, printing one line
+/// per adapter. Returns the exit code, 1 when any adapter failed.
+pub fn run_reset_all(mut config: AppConfig) -> i32 {
+    if !is_elevated() {
+        eprintln!("{}", t!("reset-all-not-elevated"));
+        return 1;
+    }
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 1;
+        }
+    };
+    let interfaces = match get_network_interfaces(false) {
+        Ok(interfaces) => interfaces_to_reset(interfaces, &config),
+        Err(e) => {
+            eprintln!("{}", t!("reset-all-list-failed", error = e));
+            return 1;
+        }
+    };
+
+    let results = runtime.block_on(async {
+        let backend = SystemBackend::detect()
+            .await
+            .with_cim(config.settings.uses_cim_backend());
+        reset_interfaces(&backend, &interfaces).await
+    });
+
+    let mut failed = false;
+    for (interface, (before, result)) in interfaces.iter().zip(results) {
+        let name = config.interface_name(interface);
+        let mut entry =
+            HistoryEntry::now(&interface.interface_guid, &name, before, DnsSettings::new());
+        match result {
+            Ok(warning) => {
+                config.mark_automatic(&interface.interface_guid);
+                match &warning {
+                    Some(warning) => println!("{}: {}", name, warning),
+                    None => println!("{}", t!("reset-all-interface-done", interface = name)),
+                }
+                entry.warning = warning;
+            }
+            Err(e) => {
+                failed = true;
+                eprintln!("{}: {}", name, e);
+                entry.error = Some(e.to_string());
+            }
+        }
+        if let Err(e) = append_history(&entry) {
+            eprintln!("Failed to write the history: {}", e);
+        }
+    }
+
+    if let Err(e) = save_config(&config) {
+        eprintln!("{}", t!("save-config-failed", error = e));
+        failed = true;
+    }
+    i32::from(failed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dns::backend::mock::MockBackend;
+    use crate::dns::types::test_interface;
+
+    fn interface(index: u32, is_virtual: bool) -> NetworkInterface {
+        NetworkInterface {
+            has_ipv6: false,
+            is_virtual,
+            ..test_interface(&format!("Adapter {}", index), index)
+        }
+    }
+
+    #[test]
+    fn test_reset_all_argument() {
+        assert!(reset_all_argument(["reset-all".to_string()]));
+        assert!(!reset_all_argument(["--minimized".to_string()]));
+    }
+
+    #[test]
+    fn test_interfaces_to_reset_skips_untouched_virtual_adapters() {
+        let mut config = AppConfig::default();
+        config
+            .interface_profiles
+            .insert("{GUID-3}".to_string(), Some("work".to_string()));
+        let interfaces = vec![interface(1, false), interface(2, true), interface(3, true)];
+
+        let indexes: Vec<u32> = interfaces_to_reset(interfaces, &config)
+            .iter()
+            .map(|i| i.interface_index)
+            .collect();
+        assert_eq!(indexes, vec![1, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_reset_interfaces() {
+        let backend = MockBackend::new();
+        let interfaces = [interface(1, false), interface(2, false)];
+
+        let results = reset_interfaces(&backend, &interfaces).await;
+        assert!(results.iter().all(|(before, _)| before.is_some()));
+        assert!(results.iter().all(|(_, result)| matches!(result, Ok(None))));
+        let calls = backend.calls();
+        assert!(calls.contains(&"disable_doh_registry {GUID-1}".to_string()));
+        assert!(calls.contains(&"disable_doh_registry {GUID-2}".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_reset_interfaces_carries_on_past_failures() {
+        let backend = MockBackend {
+            fail_set: true,
+            ..MockBackend::new()
+        };
+        let interfaces = [interface(1, false), interface(2, false)];
+
+        let results = reset_interfaces(&backend, &interfaces).await;
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|(_, result)| result.is_err()));
+        let calls = backend.calls();
+        assert!(calls.contains(&"set_automatic 1".to_string()));
+        assert!(calls.contains(&"set_automatic 2".to_string()));
+    }
+}
@@ -0,0 +1,198 @@
+use crate::dns::ddr::{encode_query, query_id};
+use crate::dns::validation::{parse_ipv6_with_zone, server_ip, validate_dns_suffix};
+use std::net::{IpAddr, SocketAddr, SocketAddrV6};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio::net::UdpSocket;
+
+#[derive(Error, Debug)]
+pub enum ResolutionCheckError {
+    #[error("Invalid DNS server address: {0}")]
+    InvalidAddress(String),
+
+    #[error("Invalid host name: {0}")]
+    InvalidHost(String),
+
+    #[error("Query failed: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("The DNS server did not respond in time")]
+    Timeout,
+
+    #[error("The DNS server sent an invalid response")]
+    InvalidResponse,
+
+    #[error("The DNS server did not find {0}")]
+    NotFound(String),
+
+    #[error("The DNS server returned error code {0}")]
+    ServerError(u16),
+}
+
+pub type Result<T> = std::result::Result<T, ResolutionCheckError>;
+
+pub const DEFAULT_CHECK_HOST: &str = "example.com";
+const QUERY_TIMEOUT: Duration = Duration::from_secs(3);
+const TYPE_A: u16 = 1;
+pub(crate) const RCODE_NOERROR: u16 = 0;
+pub(crate) const RCODE_SERVFAIL: u16 = 2;
+const RCODE_NXDOMAIN: u16 = 3;
+
+/// Asks the DNS server at `server` for the address of `host` and returns
+/// how long the answer took.
+pub async fn check_resolution(server: &str, host: &str) -> Result<Duration> {
+    let response = query_a(server, host).await?;
+    match response.rcode {
+        RCODE_NOERROR if response.answers > 0 => Ok(response.elapsed),
+        RCODE_NOERROR | RCODE_NXDOMAIN => Err(ResolutionCheckError::NotFound(host.to_string())),
+        rcode => Err(ResolutionCheckError::ServerError(rcode)),
+    }
+}
+
+/// How long the DNS server at `server` takes to answer a query for `host`,
+/// whatever the answer.
+pub async fn measure_latency(server: &str, host: &str) -> Result<Duration> {
+    query_a(server, host).await.map(|response| response.elapsed)
+}
+
+/// Whether anything answers DNS queries at `server`, whatever the answer.
+/// Tells a local resolver that is running from one that is not.
+pub async fn server_answers(server: &str) -> bool {
+    exchange(server, DEFAULT_CHECK_HOST, TYPE_A).await.is_ok()
+}
+
+/// What a DNS server answered to a single query.
+#[derive(Debug)]
+pub(crate) struct QueryResponse {
+    pub elapsed: Duration,
+    pub rcode: u16,
+    pub answers: u16,
+}
+
+/// Sends an A query for `host` straight to the DNS server at `server`.
+pub(crate) async fn query_a(server: &str, host: &str) -> Result<QueryResponse> {
+    let host = host.trim().trim_end_matches('.');
+    if host.is_empty() || !validate_dns_suffix(host) {
+        return Err(ResolutionCheckError::InvalidHost(host.to_string()));
+    }
+
+    let (id, message, elapsed) = exchange(server, host, TYPE_A).await?;
+    let (rcode, answers) = parse_response(id, &message)?;
+    Ok(QueryResponse {
+        elapsed,
+        rcode,
+        answers,
+    })
+}
+
+/// Sends a query for `host` over UDP to the DNS server at `server` and
+/// returns the query id, the raw response and how long it took. `host`
+/// must already be a valid name.
+pub(crate) async fn exchange(
+    server: &str,
+    host: &str,
+    record_type: u16,
+) -> Result<(u16, Vec<u8>, Duration)> {
+    let ip: IpAddr = server_ip(server)
+        .ok_or_else(|| ResolutionCheckError::InvalidAddress(server.to_string()))?;
+    // A link-local server is only reachable through the interface its zone
+    // ID names.
+    let scope_id = parse_ipv6_with_zone(server.trim())
+        .and_then(|(_, zone)| zone)
+        .unwrap_or(0);
+
+    let bind_address: SocketAddr = match ip {
+        IpAddr::V4(_) => "0.0.0.0:0".parse().unwrap(),
+        IpAddr::V6(_) => "[::]:0".parse().unwrap(),
+    };
+    let socket = UdpSocket::bind(bind_address).await?;
+    let server_address = match ip {
+        IpAddr::V4(ip) => SocketAddr::from((ip, 53)),
+        IpAddr::V6(ip) => SocketAddrV6::new(ip, 53, 0, scope_id).into(),
+    };
+    socket.connect(server_address).await?;
+
+    let id = query_id();
+    let started = Instant::now();
+    socket.send(&encode_query(id, host, record_type)).await?;
+
+    let mut buffer = [0u8; 4096];
+    let len = tokio::time::timeout(QUERY_TIMEOUT, socket.recv(&mut buffer))
+        .await
+        .map_err(|_| ResolutionCheckError::Timeout)??;
+    Ok((id, buffer[..len].to_vec(), started.elapsed()))
+}
+
+/// Returns the response code and answer count of a response to query `id`.
+pub(crate) fn parse_response(id: u16, message: &[u8]) -> Result<(u16, u16)> {
+    let header = |index: usize| {
+        message
+            .get(index * 2..index * 2 + 2)
+            .map(|b| u16::from_be_bytes([b[0], b[1]]))
+            .ok_or(ResolutionCheckError::InvalidResponse)
+    };
+
+    let flags = header(1)?;
+    if header(0)? != id || flags & 0x8000 == 0 || message.len() < 12 {
+        return Err(ResolutionCheckError::InvalidResponse);
+    }
+    Ok((flags & 0x000f, header(3)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(id: u16, rcode: u16, answers: u16) -> Vec<u8> {
+        let mut message = encode_query(id, "example.com", TYPE_A);
+        message[2..4].copy_from_slice(&(0x8180 | rcode).to_be_bytes());
+        message[6..8].copy_from_slice(&answers.to_be_bytes());
+        message
+    }
+
+    #[test]
+    fn test_parse_response() {
+        assert_eq!(parse_response(7, &response(7, 0, 1)).unwrap(), (0, 1));
+        assert_eq!(parse_response(7, &response(7, 3, 0)).unwrap(), (3, 0));
+        assert_eq!(parse_response(7, &response(7, 2, 0)).unwrap(), (2, 0));
+        assert!(matches!(
+            parse_response(7, &response(8, 0, 1)),
+            Err(ResolutionCheckError::InvalidResponse)
+        ));
+        assert!(matches!(
+            parse_response(7, &encode_query(7, "example.com", TYPE_A)),
+            Err(ResolutionCheckError::InvalidResponse)
+        ));
+        assert!(parse_response(7, &[0, 7, 0x81]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_check_resolution_invalid_input() {
+        assert!(matches!(
+            check_resolution("not an address", DEFAULT_CHECK_HOST).await,
+            Err(ResolutionCheckError::InvalidAddress(_))
+        ));
+        assert!(matches!(
+            check_resolution("127.0.0.1", "bad host!").await,
+            Err(ResolutionCheckError::InvalidHost(_))
+        ));
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_measure_latency() {
+        let elapsed = measure_latency("1.1.1.1", "windns-latency.invalid")
+            .await
+            .unwrap();
+        assert!(elapsed < QUERY_TIMEOUT);
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_check_resolution() {
+        let elapsed = check_resolution("1.1.1.1", DEFAULT_CHECK_HOST)
+            .await
+            .unwrap();
+        assert!(elapsed < QUERY_TIMEOUT);
+    }
+}
@@ -0,0 +1,302 @@
+use crate::dns::types::AppConfig;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub enum Weekday {
+    Mon,
+    Tue,
+    Wed,
+    Thu,
+    Fri,
+    Sat,
+    Sun,
+}
+
+impl Weekday {
+    pub const ALL: [Weekday; 7] = [
+        Weekday::Mon,
+        Weekday::Tue,
+        Weekday::Wed,
+        Weekday::Thu,
+        Weekday::Fri,
+        Weekday::Sat,
+        Weekday::Sun,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Weekday::Mon => "Mon",
+            Weekday::Tue => "Tue",
+            Weekday::Wed => "Wed",
+            Weekday::Thu => "Thu",
+            Weekday::Fri => "Fri",
+            Weekday::Sat => "Sat",
+            Weekday::Sun => "Sun",
+        }
+    }
+
+    fn previous(&self) -> Weekday {
+        let index = Self::ALL.iter().position(|d| d == self).unwrap_or(0);
+        Self::ALL[(index + 6) % 7]
+    }
+}
+
+/// Time of day in minutes after midnight, written as `HH:MM`.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Debug, Default)]
+#[serde(try_from = "String", into = "String")]
+pub struct TimeOfDay(u16);
+
+impl TimeOfDay {
+    pub fn new(hour: u16, minute: u16) -> Option<Self> {
+        (hour < 24 && minute < 60).then_some(Self(hour * 60 + minute))
+    }
+
+    pub fn parse(text: &str) -> Option<Self> {
+        let (hour, minute) = text.trim().split_once(':')?;
+        Self::new(hour.parse().ok()?, minute.parse().ok()?)
+    }
+
+    /// The time `minutes` later, past midnight if need be.
+    pub fn plus_minutes(self, minutes: u64) -> Self {
+        Self(((u64::from(self.0) + minutes) % (24 * 60)) as u16)
+    }
+}
+
+impl fmt::Display for TimeOfDay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}:{:02}", self.0 / 60, self.0 % 60)
+    }
+}
+
+impl TryFrom<String> for TimeOfDay {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        Self::parse(&text).ok_or_else(|| format!("invalid time of day: {}", text))
+    }
+}
+
+impl From<TimeOfDay> for String {
+    fn from(time: TimeOfDay) -> Self {
+        time.to_string()
+    }
+}
+
+/// Applies a profile from `start` until `end` on the given days. An entry
+/// whose end is earlier than its start runs past midnight into the next
+/// day.
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct ScheduleEntry {
+    pub id: String,
+    /// Profile to apply; `None` switches the interface to Automatic.
+    pub profile_id: Option<String>,
+    pub start: TimeOfDay,
+    pub end: TimeOfDay,
+    /// Days the entry starts on.
+    pub days: Vec<Weekday>,
+}
+
+impl ScheduleEntry {
+    pub fn new() -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            profile_id: None,
+            start: TimeOfDay::new(22, 0).unwrap_or_default(),
+            end: TimeOfDay::new(7, 0).unwrap_or_default(),
+            days: Weekday::ALL.to_vec(),
+        }
+    }
+
+    fn is_active(&self, day: Weekday, time: TimeOfDay) -> bool {
+        if self.start < self.end {
+            self.days.contains(&day) && self.start <= time && time < self.end
+        } else {
+            (self.days.contains(&day) && time >= self.start)
+                || (self.days.contains(&day.previous()) && time < self.end)
+        }
+    }
+}
+
+impl Default for ScheduleEntry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Profiles to apply to the selected interface by time of day. Outside of
+/// every entry, `default_profile_id` is applied, or Automatic if unset.
+#[derive(Clone, PartialEq, Serialize, Deserialize, Default, Debug)]
+pub struct Schedule {
+    pub enabled: bool,
+    #[serde(default)]
+    pub entries: Vec<ScheduleEntry>,
+    #[serde(default)]
+    pub default_profile_id: Option<String>,
+}
+
+impl Schedule {
+    pub fn is_empty(&self) -> bool {
+        !self.enabled && self.entries.is_empty() && self.default_profile_id.is_none()
+    }
+
+    /// Profile the schedule calls for at `time` on `day`, or `None` for
+    /// Automatic. The first active entry wins.
+    pub fn target_profile(&self, day: Weekday, time: TimeOfDay) -> Option<String> {
+        self.entries
+            .iter()
+            .find(|entry| entry.is_active(day, time))
+            .map_or(&self.default_profile_id, |entry| &entry.profile_id)
+            .clone()
+    }
+
+    pub fn validate(&self, config: &AppConfig) -> Result<(), String> {
+        for entry in &self.entries {
+            if entry.days.is_empty() {
+                return Err(format!(
+                    "The schedule entry starting at {} has no days",
+                    entry.start
+                ));
+            }
+            if entry.start == entry.end {
+                return Err(format!(
+                    "The schedule entry starting at {} ends at the same time",
+                    entry.start
+                ));
+            }
+        }
+
+        let profile_ids = self
+            .entries
+            .iter()
+            .map(|entry| &entry.profile_id)
+            .chain([&self.default_profile_id]);
+        for id in profile_ids.flatten() {
+            if config.find_profile(id).is_none() {
+                return Err("A scheduled profile no longer exists".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Current local day of the week and time of day.
+#[cfg(target_os = "windows")]
+pub fn local_time() -> (Weekday, TimeOfDay) {
+    use windows::Win32::System::SystemInformation::GetLocalTime;
+
+    let now = unsafe { GetLocalTime() };
+    // wDayOfWeek counts from Sunday.
+    let day = Weekday::ALL[(now.wDayOfWeek as usize + 6) % 7];
+    let time = TimeOfDay::new(now.wHour, now.wMinute).unwrap_or_default();
+    (day, time)
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn local_time() -> (Weekday, TimeOfDay) {
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let minutes = (secs / 60 % (24 * 60)) as u16;
+    // 1970-01-01 was a Thursday.
+    let day = Weekday::ALL[((secs / 86_400 + 3) % 7) as usize];
+    (day, TimeOfDay(minutes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dns::types::DnsProfile;
+
+    fn time(text: &str) -> TimeOfDay {
+        TimeOfDay::parse(text).unwrap()
+    }
+
+    fn entry(start: &str, end: &str, days: &[Weekday], profile_id: &str) -> ScheduleEntry {
+        ScheduleEntry {
+            profile_id: Some(profile_id.to_string()),
+            start: time(start),
+            end: time(end),
+            days: days.to_vec(),
+            ..ScheduleEntry::new()
+        }
+    }
+
+    #[test]
+    fn test_time_of_day_parse() {
+        assert_eq!(time("07:05").to_string(), "07:05");
+        assert_eq!(time(" 23:59 ").to_string(), "23:59");
+        assert!(TimeOfDay::parse("24:00").is_none());
+        assert!(TimeOfDay::parse("12:60").is_none());
+        assert!(TimeOfDay::parse("noon").is_none());
+        assert_eq!(time("07:05").plus_minutes(30), time("07:35"));
+        assert_eq!(time("23:30").plus_minutes(120), time("01:30"));
+    }
+
+    #[test]
+    fn test_schedule_entry_serialization() {
+        let entry = entry("22:00", "07:00", &[Weekday::Fri, Weekday::Sat], "kids");
+        let json = serde_json::to_string(&entry).unwrap();
+        assert!(json.contains(r#""start":"22:00","end":"07:00","days":["Fri","Sat"]"#));
+        assert_eq!(serde_json::from_str::<ScheduleEntry>(&json).unwrap(), entry);
+        assert!(serde_json::from_str::<ScheduleEntry>(&json.replace("22:00", "25:00")).is_err());
+    }
+
+    #[test]
+    fn test_target_profile_same_day_entry() {
+        let schedule = Schedule {
+            enabled: true,
+            entries: vec![entry("09:00", "17:00", &[Weekday::Mon], "work")],
+            default_profile_id: Some("home".to_string()),
+        };
+
+        let target = |day, at| schedule.target_profile(day, time(at));
+        assert_eq!(target(Weekday::Mon, "09:00").as_deref(), Some("work"));
+        assert_eq!(target(Weekday::Mon, "16:59").as_deref(), Some("work"));
+        assert_eq!(target(Weekday::Mon, "17:00").as_deref(), Some("home"));
+        assert_eq!(target(Weekday::Tue, "10:00").as_deref(), Some("home"));
+    }
+
+    #[test]
+    fn test_target_profile_overnight_entry() {
+        let schedule = Schedule {
+            enabled: true,
+            entries: vec![entry("22:00", "07:00", &[Weekday::Sun], "filter")],
+            default_profile_id: None,
+        };
+
+        let target = |day, at| schedule.target_profile(day, time(at));
+        assert_eq!(target(Weekday::Sun, "21:59"), None);
+        assert_eq!(target(Weekday::Sun, "22:00").as_deref(), Some("filter"));
+        assert_eq!(target(Weekday::Mon, "06:59").as_deref(), Some("filter"));
+        assert_eq!(target(Weekday::Mon, "07:00"), None);
+        assert_eq!(target(Weekday::Sun, "06:00"), None);
+    }
+
+    #[test]
+    fn test_schedule_validate() {
+        let mut config = AppConfig::new();
+        let profile = DnsProfile::new("Filter".to_string());
+        let id = profile.id.clone();
+        config.add_profile(profile);
+
+        let mut schedule = Schedule {
+            enabled: true,
+            entries: vec![entry("22:00", "07:00", &Weekday::ALL, &id)],
+            default_profile_id: None,
+        };
+        assert!(schedule.validate(&config).is_ok());
+
+        schedule.entries[0].days.clear();
+        assert!(schedule.validate(&config).is_err());
+
+        schedule.entries[0].days = vec![Weekday::Mon];
+        schedule.entries[0].end = schedule.entries[0].start;
+        assert!(schedule.validate(&config).is_err());
+
+        schedule.entries[0].end = time("23:00");
+        schedule.default_profile_id = Some("deleted".to_string());
+        assert!(schedule.validate(&config).is_err());
+    }
+}
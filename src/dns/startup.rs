@@ -0,0 +1,198 @@
+use std::path::Path;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum StartupError {
+    #[error("Failed to update the startup entry: {0}")]
+    Registry(String),
+}
+
+pub type Result<T> = std::result::Result<T, StartupError>;
+
+/// Key of the programs started at logon for the current user.
+#[cfg(target_os = "windows")]
+const RUN_KEY: &str = r"Software\Microsoft\Windows\CurrentVersion\Run";
+#[cfg(target_os = "windows")]
+const RUN_VALUE: &str = "windns";
+const MINIMIZED_ARG: &str = "--minimized";
+
+/// Whether windns starts at logon, and whether it starts minimized then.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StartupSetting {
+    pub enabled: bool,
+    pub minimized: bool,
+}
+
+impl StartupSetting {
+    /// Reads the setting from the command line registered to run at
+    /// logon, if there is one.
+    fn from_command(command: Option<&str>) -> Self {
+        match command {
+            Some(command) => Self {
+                enabled: true,
+                minimized: command.split_whitespace().any(|arg| arg == MINIMIZED_ARG),
+            },
+            None => Self::default(),
+        }
+    }
+
+    fn command(&self, exe: &Path) -> String {
+        let mut command = format!("\"{}\"", exe.display());
+        if self.minimized {
+            command.push(' ');
+            command.push_str(MINIMIZED_ARG);
+        }
+        command
+    }
+}
+
+/// Whether the app was started with the argument registered for starting
+/// minimized at logon.
+pub fn launched_minimized(args: impl IntoIterator<Item = String>) -> bool {
+    args.into_iter().any(|arg| arg == MINIMIZED_ARG)
+}
+
+#[cfg(target_os = "windows")]
+pub fn startup_setting() -> StartupSetting {
+    StartupSetting::from_command(read_run_value().as_deref())
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn startup_setting() -> StartupSetting {
+    StartupSetting::default()
+}
+
+#[cfg(target_os = "windows")]
+fn read_run_value() -> Option<String> {
+    use windows::Win32::Foundation::NO_ERROR;
+    use windows::Win32::System::Registry::{HKEY_CURRENT_USER, RRF_RT_REG_SZ, RegGetValueW};
+    use windows::core::HSTRING;
+
+    let subkey = HSTRING::from(RUN_KEY);
+    let value = HSTRING::from(RUN_VALUE);
+    let mut size = 0u32;
+    let result = unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            &subkey,
+            &value,
+            RRF_RT_REG_SZ,
+            None,
+            None,
+            Some(&mut size),
+        )
+    };
+    if result != NO_ERROR {
+        return None;
+    }
+
+    let mut buffer = vec![0u16; (size as usize).div_ceil(2)];
+    let result = unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            &subkey,
+            &value,
+            RRF_RT_REG_SZ,
+            None,
+            Some(buffer.as_mut_ptr().cast()),
+            Some(&mut size),
+        )
+    };
+    if result != NO_ERROR {
+        return None;
+    }
+    let len = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+    Some(String::from_utf16_lossy(&buffer[..len]))
+}
+
+/// Registers or unregisters windns to run at logon of the current user.
+#[cfg(target_os = "windows")]
+pub fn set_startup_setting(setting: StartupSetting) -> Result<()> {
+    use windows::Win32::Foundation::{ERROR_FILE_NOT_FOUND, NO_ERROR};
+    use windows::Win32::System::Registry::{
+        HKEY_CURRENT_USER, REG_SZ, RegDeleteKeyValueW, RegSetKeyValueW,
+    };
+    use windows::core::HSTRING;
+
+    let subkey = HSTRING::from(RUN_KEY);
+    let value = HSTRING::from(RUN_VALUE);
+
+    let result = if setting.enabled {
+        let exe = std::env::current_exe().map_err(|e| StartupError::Registry(e.to_string()))?;
+        let data: Vec<u16> = setting
+            .command(&exe)
+            .encode_utf16()
+            .chain(std::iter::once(0))
+            .collect();
+        unsafe {
+            RegSetKeyValueW(
+                HKEY_CURRENT_USER,
+                &subkey,
+                &value,
+                REG_SZ.0,
+                Some(data.as_ptr().cast()),
+                (data.len() * 2) as u32,
+            )
+        }
+    } else {
+        match unsafe { RegDeleteKeyValueW(HKEY_CURRENT_USER, &subkey, &value) } {
+            ERROR_FILE_NOT_FOUND => NO_ERROR,
+            result => result,
+        }
+    };
+
+    if result != NO_ERROR {
+        return Err(StartupError::Registry(format!(
+            "registry error {}",
+            result.0
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn set_startup_setting(_setting: StartupSetting) -> Result<()> {
+    Err(StartupError::Registry(
+        "Not supported on this platform".to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_startup_setting_command_round_trip() {
+        let exe = Path::new(r"C:\Program Files\windns\windns.exe");
+        for setting in [
+            StartupSetting {
+                enabled: true,
+                minimized: false,
+            },
+            StartupSetting {
+                enabled: true,
+                minimized: true,
+            },
+        ] {
+            let command = setting.command(exe);
+            assert!(command.starts_with(r#""C:\Program Files\windns\windns.exe""#));
+            assert_eq!(StartupSetting::from_command(Some(&command)), setting);
+        }
+        assert_eq!(
+            StartupSetting::from_command(None),
+            StartupSetting::default()
+        );
+    }
+
+    #[test]
+    fn test_launched_minimized() {
+        let args = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+        assert!(launched_minimized(args(&["--minimized"])));
+        assert!(launched_minimized(args(&[
+            "--interface",
+            "{GUID-1}",
+            "--minimized"
+        ])));
+        assert!(!launched_minimized(args(&["--interface", "{GUID-1}"])));
+    }
+}
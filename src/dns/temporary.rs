@@ -0,0 +1,75 @@
+use crate::dns::types::DnsSettings;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Durations offered for applying a profile temporarily, in minutes.
+pub const TEMPORARY_APPLY_MINUTES: [u64; 3] = [30, 60, 120];
+
+/// Settings applied to an interface for a limited time, and what to put
+/// back once the time is up. Kept in the config, so that the settings are
+/// still put back if windns was closed in the meantime.
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct TemporaryApply {
+    /// Settings the interface had before.
+    pub before: DnsSettings,
+    /// Profile last applied to the interface before, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_profile: Option<String>,
+    /// When to put `before` back, in seconds since the Unix epoch.
+    pub expires_at: u64,
+}
+
+impl TemporaryApply {
+    /// Time left at `now`, in seconds since the Unix epoch.
+    pub fn remaining(&self, now: u64) -> Duration {
+        Duration::from_secs(self.expires_at.saturating_sub(now))
+    }
+
+    pub fn has_expired(&self, now: u64) -> bool {
+        now >= self.expires_at
+    }
+}
+
+/// Seconds since the Unix epoch.
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// Time left written as `M:SS`, or `H:MM:SS` from an hour up.
+pub fn format_countdown(remaining: Duration) -> String {
+    let secs = remaining.as_secs();
+    let (hours, minutes, seconds) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_temporary_apply_remaining() {
+        let temporary = TemporaryApply {
+            before: DnsSettings::new(),
+            previous_profile: None,
+            expires_at: 1_000,
+        };
+        assert_eq!(temporary.remaining(400), Duration::from_secs(600));
+        assert!(!temporary.has_expired(999));
+        assert_eq!(temporary.remaining(1_200), Duration::ZERO);
+        assert!(temporary.has_expired(1_000));
+    }
+
+    #[test]
+    fn test_format_countdown() {
+        assert_eq!(format_countdown(Duration::ZERO), "0:00");
+        assert_eq!(format_countdown(Duration::from_secs(29 * 60 + 5)), "29:05");
+        assert_eq!(format_countdown(Duration::from_secs(7200)), "2:00:00");
+        assert_eq!(format_countdown(Duration::from_secs(3661)), "1:01:01");
+    }
+}
@@ -0,0 +1,91 @@
+use serde::{Deserialize, Serialize};
+
+/// Color theme chosen in the settings.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug, Default)]
+pub enum ThemePreference {
+    /// Follows the Windows app mode setting.
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+impl ThemePreference {
+    pub const ALL: [ThemePreference; 3] = [
+        ThemePreference::System,
+        ThemePreference::Light,
+        ThemePreference::Dark,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ThemePreference::System => "System",
+            ThemePreference::Light => "Light",
+            ThemePreference::Dark => "Dark",
+        }
+    }
+
+    pub fn parse(text: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|theme| theme.as_str() == text)
+    }
+
+    /// Whether the dark theme is used, given whether Windows is in dark
+    /// mode.
+    pub fn is_dark(&self, system_dark: bool) -> bool {
+        match self {
+            ThemePreference::System => system_dark,
+            ThemePreference::Light => false,
+            ThemePreference::Dark => true,
+        }
+    }
+}
+
+/// Whether Windows is set to dark mode for apps. Defaults to dark, the
+/// original look of windns, when the setting cannot be read.
+#[cfg(target_os = "windows")]
+pub fn system_prefers_dark() -> bool {
+    use windows::Win32::Foundation::NO_ERROR;
+    use windows::Win32::System::Registry::{HKEY_CURRENT_USER, RRF_RT_REG_DWORD, RegGetValueW};
+    use windows::core::w;
+
+    let mut value = 0u32;
+    let mut size = std::mem::size_of::<u32>() as u32;
+    let result = unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            w!(r"Software\Microsoft\Windows\CurrentVersion\Themes\Personalize"),
+            w!("AppsUseLightTheme"),
+            RRF_RT_REG_DWORD,
+            None,
+            Some((&mut value as *mut u32).cast()),
+            Some(&mut size),
+        )
+    };
+    result != NO_ERROR || value == 0
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn system_prefers_dark() -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_theme_preference_is_dark() {
+        assert!(ThemePreference::System.is_dark(true));
+        assert!(!ThemePreference::System.is_dark(false));
+        assert!(!ThemePreference::Light.is_dark(true));
+        assert!(ThemePreference::Dark.is_dark(false));
+    }
+
+    #[test]
+    fn test_theme_preference_parse() {
+        for theme in ThemePreference::ALL {
+            assert_eq!(ThemePreference::parse(theme.as_str()), Some(theme));
+        }
+        assert_eq!(ThemePreference::parse("Blue"), None);
+    }
+}
@@ -1,4 +1,14 @@
+use crate::dns::elevation::RelaunchSelection;
+use crate::dns::network_rules::NetworkRules;
+use crate::dns::providers::DnsProvider;
+use crate::dns::resolution_check::DEFAULT_CHECK_HOST;
+use crate::dns::scheduler::Schedule;
+use crate::dns::temporary::TemporaryApply;
+use crate::dns::theme::ThemePreference;
+use crate::dns::validation::same_address;
+use crate::i18n::{Language, t};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 #[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default, Debug)]
 pub enum DnsMode {
@@ -46,6 +56,14 @@ pub struct DnsServerEntry {
     pub doh_mode: DohMode,
     pub doh_template: String,
     pub allow_fallback: bool,
+    /// Whether Windows upgrades plain queries to this server to DoH on
+    /// every interface, rather than only where DoH is turned on.
+    #[serde(default = "default_auto_upgrade")]
+    pub auto_upgrade: bool,
+}
+
+fn default_auto_upgrade() -> bool {
+    true
 }
 
 impl Default for DnsServerEntry {
@@ -55,6 +73,7 @@ impl Default for DnsServerEntry {
             doh_mode: DohMode::Off,
             doh_template: String::new(),
             allow_fallback: true,
+            auto_upgrade: true,
         }
     }
 }
@@ -73,19 +92,109 @@ pub struct NetworkInterface {
     pub interface_guid: String,
     pub has_ipv4: bool,
     pub has_ipv6: bool,
+    /// Loopback, tunnel, VPN, or virtual machine adapter, hidden unless all
+    /// adapters are shown.
+    #[serde(default)]
+    pub is_virtual: bool,
+    /// VPN adapter, which takes over name resolution while it is up.
+    #[serde(default)]
+    pub is_vpn: bool,
+    /// Network a Wi-Fi adapter is connected to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssid: Option<String>,
+    /// Adapter without a link, such as an unplugged Ethernet port, listed
+    /// only when disconnected adapters are shown.
+    #[serde(default)]
+    pub is_disconnected: bool,
 }
 
 impl NetworkInterface {
     pub fn display_name(&self) -> String {
-        format!("{} ({})", self.name, self.interface_index)
+        self.display_name_as(None)
+    }
+
+    /// `display_name` with a name the user gave the adapter in place of the
+    /// Windows name and index.
+    pub fn display_name_as(&self, alias: Option<&str>) -> String {
+        let name = match alias {
+            Some(alias) => alias.to_string(),
+            None => format!("{} ({})", self.name, self.interface_index),
+        };
+        match &self.ssid {
+            Some(ssid) => format!("{} — {}", name, ssid),
+            None => name,
+        }
     }
 }
 
-#[derive(Clone, PartialEq, Serialize, Deserialize, Default, Debug)]
+/// Connected physical adapter with both address families and the GUID
+/// `{GUID-<index>}`, for tests.
+#[cfg(test)]
+pub(crate) fn test_interface(name: &str, index: u32) -> NetworkInterface {
+    NetworkInterface {
+        name: name.to_string(),
+        interface_index: index,
+        interface_guid: format!("{{GUID-{}}}", index),
+        has_ipv4: true,
+        has_ipv6: true,
+        is_virtual: false,
+        is_vpn: false,
+        ssid: None,
+        is_disconnected: false,
+    }
+}
+
+/// DNS servers of one address family, in the order Windows should use them.
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+#[serde(from = "DnsEntryConfig")]
 pub struct DnsEntry {
     pub enabled: bool,
-    pub primary: DnsServerEntry,
-    pub secondary: DnsServerEntry,
+    /// When not enabled, leaves the servers of the family as they are
+    /// instead of resetting them to DHCP.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub unchanged: bool,
+    pub servers: Vec<DnsServerEntry>,
+}
+
+impl Default for DnsEntry {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            unchanged: false,
+            servers: vec![DnsServerEntry::default(), DnsServerEntry::default()],
+        }
+    }
+}
+
+/// On-disk form of `DnsEntry`. Also accepts the `primary`/`secondary`
+/// layout written by older versions.
+#[derive(Deserialize)]
+struct DnsEntryConfig {
+    enabled: bool,
+    #[serde(default)]
+    unchanged: bool,
+    #[serde(default)]
+    servers: Option<Vec<DnsServerEntry>>,
+    #[serde(default)]
+    primary: Option<DnsServerEntry>,
+    #[serde(default)]
+    secondary: Option<DnsServerEntry>,
+}
+
+impl From<DnsEntryConfig> for DnsEntry {
+    fn from(config: DnsEntryConfig) -> Self {
+        let servers = config.servers.unwrap_or_else(|| {
+            [config.primary, config.secondary]
+                .into_iter()
+                .flatten()
+                .collect()
+        });
+        Self {
+            enabled: config.enabled,
+            unchanged: config.unchanged,
+            servers,
+        }
+    }
 }
 
 impl DnsEntry {
@@ -94,36 +203,248 @@ impl DnsEntry {
         Self::default()
     }
 
+    /// Human-readable name of the server at `index`, e.g. "Primary".
+    pub fn server_label(index: usize) -> String {
+        match index {
+            0 => t!("server-primary"),
+            1 => t!("server-secondary"),
+            n => t!("server-numbered", number = n + 1),
+        }
+    }
+
     #[allow(dead_code)]
     pub fn is_valid(&self) -> bool {
         if !self.enabled {
             return true;
         }
-        !self.primary.address.is_empty()
+        self.servers.first().is_some_and(|s| !s.address.is_empty())
+    }
+
+    /// Whether applying leaves the servers of the family as they are.
+    pub fn is_left_alone(&self) -> bool {
+        !self.enabled && self.unchanged
     }
 
     pub fn get_addresses(&self) -> Vec<String> {
-        let mut addresses = Vec::new();
-        if !self.primary.address.is_empty() {
-            addresses.push(self.primary.address.clone());
+        self.servers
+            .iter()
+            .filter(|s| !s.address.is_empty())
+            .map(|s| s.address.clone())
+            .collect()
+    }
+
+    /// The servers one per line, each followed by its DoH template when
+    /// DoH is on, for pasting elsewhere.
+    pub fn clipboard_text(&self) -> String {
+        self.servers
+            .iter()
+            .filter(|s| !s.address.is_empty())
+            .map(|s| {
+                if s.doh_mode == DohMode::On && !s.doh_template.is_empty() {
+                    format!("{} {}", s.address, s.doh_template)
+                } else {
+                    s.address.clone()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// DNS suffix search list switched together with the servers of a profile.
+/// When disabled, the system-wide search list is left as it is.
+#[derive(Clone, PartialEq, Serialize, Deserialize, Default, Debug)]
+pub struct DnsSuffixSettings {
+    pub enabled: bool,
+    /// Suffixes appended to unqualified names, in order.
+    #[serde(default)]
+    pub search_list: Vec<String>,
+}
+
+impl DnsSuffixSettings {
+    /// Search list entries without the blank rows left in the editor.
+    pub fn search_suffixes(&self) -> Vec<String> {
+        self.search_list
+            .iter()
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_string())
+            .collect()
+    }
+}
+
+/// A Name Resolution Policy Table rule that sends queries for a namespace
+/// to specific servers.
+#[derive(Clone, PartialEq, Serialize, Deserialize, Default, Debug)]
+pub struct NrptRule {
+    /// Domain suffix such as `*.corp.example`, or a fully qualified name.
+    pub namespace: String,
+    /// Comma-separated server addresses, as typed.
+    pub name_servers: String,
+}
+
+impl NrptRule {
+    /// The namespace as NRPT expects it: suffixes start with a dot.
+    pub fn normalized_namespace(&self) -> String {
+        let namespace = self.namespace.trim();
+        match namespace.strip_prefix('*') {
+            Some(suffix) => suffix.to_string(),
+            None => namespace.to_string(),
         }
-        if !self.secondary.address.is_empty() {
-            addresses.push(self.secondary.address.clone());
+    }
+
+    pub fn name_server_addresses(&self) -> Vec<String> {
+        self.name_servers
+            .split(',')
+            .map(|a| a.trim())
+            .filter(|a| !a.is_empty())
+            .map(|a| a.to_string())
+            .collect()
+    }
+}
+
+/// NRPT rules of a profile. When enabled, applying the profile replaces the
+/// rules windns created before; otherwise NRPT is left as it is.
+#[derive(Clone, PartialEq, Serialize, Deserialize, Default, Debug)]
+pub struct NrptSettings {
+    pub enabled: bool,
+    #[serde(default)]
+    pub rules: Vec<NrptRule>,
+}
+
+/// A resolver running on this machine, such as Pi-hole, AdGuard Home or
+/// dnscrypt-proxy, that a profile hands DNS to over the loopback addresses.
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+#[serde(default)]
+pub struct LocalResolver {
+    /// Whether to make sure the resolver answers before applying, so that
+    /// applying while it is stopped does not leave the machine without
+    /// name resolution.
+    pub check_listening: bool,
+}
+
+impl Default for LocalResolver {
+    fn default() -> Self {
+        Self {
+            check_listening: true,
         }
-        addresses
     }
 }
 
+impl LocalResolver {
+    pub const IPV4_ADDRESS: &str = "127.0.0.1";
+    pub const IPV6_ADDRESS: &str = "::1";
+}
+
 #[derive(Clone, PartialEq, Serialize, Deserialize, Default, Debug)]
 pub struct DnsSettings {
     pub ipv4: DnsEntry,
     pub ipv6: DnsEntry,
+    #[serde(default)]
+    pub suffixes: DnsSuffixSettings,
+    /// Connection-specific suffix of the interface; `None` leaves it as it is.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connection_suffix: Option<String>,
+    #[serde(default)]
+    pub nrpt: NrptSettings,
+    /// Host that has to resolve once the settings are applied, e.g. an
+    /// intranet host for a work profile.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub check_host: Option<String>,
+    /// PowerShell command run before the settings are applied, e.g. to
+    /// start a local proxy. The apply stops if it fails.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub before_apply: Option<String>,
+    /// PowerShell command run once the settings are applied, e.g.
+    /// `ipconfig /registerdns`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after_apply: Option<String>,
+    /// The hook commands encrypted with DPAPI once the user allowed them to
+    /// run in the editor; see `hooks_approved`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hooks_approval: Option<String>,
+    /// Set when the servers are those of a local resolver.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub local_resolver: Option<LocalResolver>,
 }
 
 impl DnsSettings {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Whether a command is set to run before or after applying.
+    pub fn has_hooks(&self) -> bool {
+        [&self.before_apply, &self.after_apply]
+            .into_iter()
+            .flatten()
+            .any(|command| !command.trim().is_empty())
+    }
+
+    /// Drops the hook commands, as for settings that come from elsewhere.
+    pub fn remove_hooks(&mut self) {
+        self.before_apply = None;
+        self.after_apply = None;
+        self.hooks_approval = None;
+    }
+
+    /// The hook commands as they are approved.
+    pub(crate) fn hook_text(&self) -> Vec<u8> {
+        serde_json::to_vec(&(&self.before_apply, &self.after_apply)).unwrap_or_default()
+    }
+
+    /// Hands both families to a local resolver on the loopback addresses,
+    /// without DoH, which the resolver does itself if at all.
+    pub fn use_local_resolver(&mut self) {
+        let entry = |address: &str| DnsEntry {
+            enabled: true,
+            unchanged: false,
+            servers: vec![DnsServerEntry {
+                address: address.to_string(),
+                ..DnsServerEntry::default()
+            }],
+        };
+        self.ipv4 = entry(LocalResolver::IPV4_ADDRESS);
+        self.ipv6 = entry(LocalResolver::IPV6_ADDRESS);
+        self.local_resolver
+            .get_or_insert_with(LocalResolver::default);
+    }
+
+    /// Whether a family is to be left as it is, so that applying needs
+    /// the current servers of the interface.
+    pub fn leaves_family_unchanged(&self) -> bool {
+        [&self.ipv4, &self.ipv6]
+            .iter()
+            .any(|entry| entry.is_left_alone())
+    }
+
+    /// These settings with each family left unchanged set to the static
+    /// servers it has in `current`, DoH settings included, with their
+    /// templates among the system-wide `templates`. A family on DHCP, or
+    /// whose origin is unknown, stays left alone, and the backends do not
+    /// touch it.
+    pub fn keeping_unchanged(
+        &self,
+        current: &CurrentDnsState,
+        templates: &[DnsServerEntry],
+    ) -> DnsSettings {
+        let mut settings = self.clone();
+        for (entry, family) in [
+            (&mut settings.ipv4, AddressFamily::IPv4),
+            (&mut settings.ipv6, AddressFamily::IPv6),
+        ] {
+            if !entry.is_left_alone()
+                || current.origin(family) != DnsOrigin::Static
+                || current.addresses(family).is_empty()
+            {
+                continue;
+            }
+            entry.enabled = true;
+            entry.unchanged = false;
+            entry.servers = current.server_entries(family, templates);
+        }
+        settings
+    }
 }
 
 #[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
@@ -131,6 +452,12 @@ pub struct DnsProfile {
     pub id: String,
     pub name: String,
     pub settings: DnsSettings,
+    /// Labels for narrowing the profile list, e.g. "home" or "testing".
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Accent color as `#rrggbb`, shown next to the profile name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
 }
 
 impl DnsProfile {
@@ -139,8 +466,35 @@ impl DnsProfile {
             id: uuid::Uuid::new_v4().to_string(),
             name,
             settings: DnsSettings::new(),
+            tags: Vec::new(),
+            color: None,
+        }
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    /// Whether the name or a tag contains `query`, ignoring case. An empty
+    /// query matches every profile.
+    pub fn matches_search(&self, query: &str) -> bool {
+        let query = query.trim().to_lowercase();
+        std::iter::once(&self.name)
+            .chain(&self.tags)
+            .any(|text| text.to_lowercase().contains(&query))
+    }
+}
+
+/// Splits comma-separated tags, dropping empty ones and repeats that only
+/// differ in case.
+pub fn parse_tags(text: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in text.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+        if !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            tags.push(tag.to_string());
         }
     }
+    tags
 }
 
 /// Window state with position in physical pixels and size in logical pixels.
@@ -156,6 +510,8 @@ pub struct WindowState {
     pub width: u32,
     /// Height in logical pixels
     pub height: u32,
+    /// Whether the window is maximized. The position and size are then
+    /// those it returns to when it is restored.
     pub maximized: bool,
 }
 
@@ -176,12 +532,200 @@ impl WindowState {
     pub const MIN_HEIGHT: u32 = 300;
 }
 
+/// Preferences edited in the Settings dialog.
+#[derive(Clone, PartialEq, Serialize, Deserialize, Default, Debug)]
+pub struct AppSettings {
+    /// Whether to clear the DNS cache after every apply; unset means yes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flush_cache_after_apply: Option<bool>,
+    /// Whether to warn when DNS servers are changed outside windns; unset
+    /// means yes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warn_on_external_dns_change: Option<bool>,
+    /// Whether to send a test query to every DoH template before applying
+    /// it; unset means yes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub probe_doh_before_apply: Option<bool>,
+    /// Whether to resolve a test host through the new servers after every
+    /// apply; unset means yes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub check_resolution_after_apply: Option<bool>,
+    /// Host resolved by that check; unset means `example.com`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolution_check_host: Option<String>,
+    /// Color theme; unset follows Windows.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub theme: Option<ThemePreference>,
+    /// Language of the user interface; unset follows Windows.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<Language>,
+    /// Whether to ask before deleting a profile; unset means yes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confirm_profile_delete: Option<bool>,
+    /// Seconds between checks for DNS changes made outside windns; unset
+    /// means 5.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dns_refresh_secs: Option<u64>,
+    /// Whether virtual and hidden adapters are listed; unset means no.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub show_all_interfaces: Option<bool>,
+    /// Whether adapters without a link are listed too; unset means no.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub show_disconnected_interfaces: Option<bool>,
+    /// Whether other programs may control windns through its named pipe;
+    /// unset means no.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enable_control_server: Option<bool>,
+    /// Whether profiles are encrypted for the current Windows user when
+    /// saved; unset means no.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encrypt_profiles: Option<bool>,
+    /// Whether every external command is recorded in the trace log; unset
+    /// means no.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace_commands: Option<bool>,
+    /// Whether DNS servers are set through WMI instead of the IP Helper
+    /// API; unset means no.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub use_cim_backend: Option<bool>,
+    /// Whether a Windows notification is shown when a network rule applies
+    /// a profile; unset means yes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notify_network_rules: Option<bool>,
+}
+
+impl AppSettings {
+    pub const DNS_REFRESH_SECS_RANGE: std::ops::RangeInclusive<u64> = 1..=3600;
+    const DEFAULT_DNS_REFRESH_SECS: u64 = 5;
+
+    pub fn probes_doh_before_apply(&self) -> bool {
+        self.probe_doh_before_apply.unwrap_or(true)
+    }
+
+    pub fn checks_resolution_after_apply(&self) -> bool {
+        self.check_resolution_after_apply.unwrap_or(true)
+    }
+
+    pub fn resolution_check_host(&self) -> &str {
+        self.resolution_check_host
+            .as_deref()
+            .unwrap_or(DEFAULT_CHECK_HOST)
+    }
+
+    pub fn flushes_cache_after_apply(&self) -> bool {
+        self.flush_cache_after_apply.unwrap_or(true)
+    }
+
+    pub fn notifies_network_rules(&self) -> bool {
+        self.notify_network_rules.unwrap_or(true)
+    }
+
+    pub fn theme(&self) -> ThemePreference {
+        self.theme.unwrap_or_default()
+    }
+
+    pub fn language(&self) -> Language {
+        self.language.unwrap_or_else(Language::detect)
+    }
+
+    pub fn warns_on_external_dns_change(&self) -> bool {
+        self.warn_on_external_dns_change.unwrap_or(true)
+    }
+
+    pub fn confirms_profile_delete(&self) -> bool {
+        self.confirm_profile_delete.unwrap_or(true)
+    }
+
+    pub fn dns_refresh_secs(&self) -> u64 {
+        let range = Self::DNS_REFRESH_SECS_RANGE;
+        self.dns_refresh_secs
+            .unwrap_or(Self::DEFAULT_DNS_REFRESH_SECS)
+            .clamp(*range.start(), *range.end())
+    }
+
+    pub fn dns_refresh_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.dns_refresh_secs())
+    }
+
+    pub fn shows_all_interfaces(&self) -> bool {
+        self.show_all_interfaces.unwrap_or(false)
+    }
+
+    pub fn shows_disconnected_interfaces(&self) -> bool {
+        self.show_disconnected_interfaces.unwrap_or(false)
+    }
+
+    pub fn enables_control_server(&self) -> bool {
+        self.enable_control_server.unwrap_or(false)
+    }
+
+    pub fn encrypts_profiles(&self) -> bool {
+        self.encrypt_profiles.unwrap_or(false)
+    }
+
+    pub fn traces_commands(&self) -> bool {
+        self.trace_commands.unwrap_or(false)
+    }
+
+    pub fn uses_cim_backend(&self) -> bool {
+        self.use_cim_backend.unwrap_or(false)
+    }
+}
+
 #[derive(Clone, PartialEq, Serialize, Deserialize, Default, Debug)]
 pub struct AppConfig {
     #[serde(default)]
     pub profiles: Vec<DnsProfile>,
     #[serde(default)]
     pub window: Option<WindowState>,
+    /// Seconds an external command may run before it is killed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command_timeout_secs: Option<u64>,
+    /// DoH server mappings registered by windns, so they can be removed later.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub managed_doh_servers: Vec<String>,
+    /// User preferences. They are stored next to the other fields, where
+    /// earlier versions kept them.
+    #[serde(flatten)]
+    pub settings: AppSettings,
+    /// DNS settings of each interface before windns first changed them,
+    /// keyed by interface GUID.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub original_dns: BTreeMap<String, DnsSettings>,
+    /// Profile last applied to each interface, keyed by interface GUID.
+    /// `None` means the interface was last set to Automatic.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub interface_profiles: BTreeMap<String, Option<String>>,
+    /// GUIDs of the adapters the user has hidden from the list.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hidden_interfaces: Vec<String>,
+    /// Names the user gave adapters, by GUID.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub interface_aliases: BTreeMap<String, String>,
+    /// Rules that switch profiles when an interface joins a known network.
+    #[serde(default, skip_serializing_if = "NetworkRules::is_empty")]
+    pub network_rules: NetworkRules,
+    /// Profiles applied to the selected interface by time of day.
+    #[serde(default, skip_serializing_if = "Schedule::is_empty")]
+    pub schedule: Schedule,
+    /// Profile applied by each Ctrl+Alt+<digit> hotkey, keyed by the digit.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub hotkeys: BTreeMap<u8, String>,
+    /// Providers added by the user, listed after the built-in ones.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub custom_providers: Vec<DnsProvider>,
+    /// Interface, mode and profile selected when windns was last closed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_selection: Option<RelaunchSelection>,
+    /// Settings applied for a limited time, to be reverted when it is up,
+    /// keyed by interface GUID.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub temporary_applies: BTreeMap<String, TemporaryApply>,
+    /// Profiles in the config file that could not be read, as they are in
+    /// the file. They are written back after the other profiles on save,
+    /// so that they can still be fixed by hand.
+    #[serde(skip)]
+    pub unreadable_profiles: Vec<serde_json::Value>,
 }
 
 impl AppConfig {
@@ -189,10 +733,27 @@ impl AppConfig {
         Self::default()
     }
 
+    /// How `interface` is named in the app: by its alias if it has one.
+    pub fn interface_name(&self, interface: &NetworkInterface) -> String {
+        interface.display_name_as(
+            self.interface_aliases
+                .get(&interface.interface_guid)
+                .map(String::as_str),
+        )
+    }
+
     pub fn find_profile(&self, id: &str) -> Option<&DnsProfile> {
         self.profiles.iter().find(|p| p.id == id)
     }
 
+    /// Finds a profile by ID, or else by name ignoring case, for profiles
+    /// named from outside windns such as in a `windns://` link.
+    pub fn find_profile_by_name_or_id(&self, name_or_id: &str) -> Option<&DnsProfile> {
+        let name = name_or_id.to_lowercase();
+        self.find_profile(name_or_id)
+            .or_else(|| self.profiles.iter().find(|p| p.name.to_lowercase() == name))
+    }
+
     pub fn find_profile_mut(&mut self, id: &str) -> Option<&mut DnsProfile> {
         self.profiles.iter_mut().find(|p| p.id == id)
     }
@@ -204,6 +765,9 @@ impl AppConfig {
     pub fn remove_profile(&mut self, id: &str) -> bool {
         if let Some(pos) = self.profiles.iter().position(|p| p.id == id) {
             self.profiles.remove(pos);
+            self.interface_profiles
+                .retain(|_, profile_id| profile_id.as_deref() != Some(id));
+            self.hotkeys.retain(|_, profile_id| profile_id != id);
             true
         } else {
             false
@@ -215,12 +779,104 @@ impl AppConfig {
         profiles.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
         profiles
     }
+
+    /// Every tag used by a profile, sorted without regard to case.
+    pub fn profile_tags(&self) -> Vec<String> {
+        let mut tags: Vec<String> = Vec::new();
+        for tag in self.profiles.iter().flat_map(|p| &p.tags) {
+            if !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                tags.push(tag.clone());
+            }
+        }
+        tags.sort_by_key(|t| t.to_lowercase());
+        tags
+    }
+
+    pub fn find_custom_provider(&self, id: &str) -> Option<&DnsProvider> {
+        self.custom_providers.iter().find(|p| p.id == id)
+    }
+
+    /// Records that an interface was reset to Automatic, dropping any
+    /// pending revert of a temporary apply to it.
+    pub fn mark_automatic(&mut self, interface_guid: &str) {
+        self.interface_profiles
+            .insert(interface_guid.to_string(), None);
+        self.temporary_applies.remove(interface_guid);
+    }
+
+    pub fn record_doh_servers(&mut self, addresses: &[String]) {
+        for address in addresses {
+            if !self.managed_doh_servers.contains(address) {
+                self.managed_doh_servers.push(address.clone());
+            }
+        }
+    }
+
+    /// Managed DoH server addresses that no profile uses with DoH enabled.
+    pub fn unused_doh_servers(&self) -> Vec<String> {
+        let referenced: Vec<&str> = self
+            .profiles
+            .iter()
+            .flat_map(|p| {
+                p.settings
+                    .ipv4
+                    .servers
+                    .iter()
+                    .chain(&p.settings.ipv6.servers)
+            })
+            .filter(|server| server.doh_mode == DohMode::On)
+            .map(|server| server.address.as_str())
+            .collect();
+
+        self.managed_doh_servers
+            .iter()
+            .filter(|address| !referenced.contains(&address.as_str()))
+            .cloned()
+            .collect()
+    }
+}
+
+/// An NRPT rule currently configured on the system.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ActiveNrptRule {
+    /// Identifier Windows assigned to the rule.
+    pub name: String,
+    pub namespaces: Vec<String>,
+    pub name_servers: Vec<String>,
+    /// Whether the rule was created by windns.
+    pub managed: bool,
+}
+
+/// Where the current DNS servers of an address family come from.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum DnsOrigin {
+    #[default]
+    Unknown,
+    Dhcp,
+    Static,
+}
+
+impl DnsOrigin {
+    pub fn label(&self) -> Option<String> {
+        match self {
+            DnsOrigin::Unknown => None,
+            DnsOrigin::Dhcp => Some("DHCP".to_string()),
+            DnsOrigin::Static => Some(t!("origin-static")),
+        }
+    }
 }
 
-#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
 pub struct CurrentDnsState {
     pub ipv4: Vec<String>,
     pub ipv6: Vec<String>,
+    /// Current servers that are queried over DoH.
+    #[serde(default)]
+    pub doh_servers: Vec<String>,
+    #[serde(default)]
+    pub ipv4_origin: DnsOrigin,
+    #[serde(default)]
+    pub ipv6_origin: DnsOrigin,
 }
 
 impl CurrentDnsState {
@@ -228,16 +884,76 @@ impl CurrentDnsState {
         Self::default()
     }
 
-    pub fn get_display(&self, family: AddressFamily) -> String {
-        let addresses = match family {
+    /// Whether every family with servers gets them from DHCP.
+    pub fn is_automatic(&self) -> bool {
+        [
+            (&self.ipv4, self.ipv4_origin),
+            (&self.ipv6, self.ipv6_origin),
+        ]
+        .iter()
+        .all(|(addresses, origin)| addresses.is_empty() || *origin == DnsOrigin::Dhcp)
+    }
+
+    pub fn addresses(&self, family: AddressFamily) -> &[String] {
+        match family {
             AddressFamily::IPv4 => &self.ipv4,
             AddressFamily::IPv6 => &self.ipv6,
-        };
+        }
+    }
 
-        if addresses.is_empty() {
-            "Automatic".to_string()
+    pub fn origin(&self, family: AddressFamily) -> DnsOrigin {
+        match family {
+            AddressFamily::IPv4 => self.ipv4_origin,
+            AddressFamily::IPv6 => self.ipv6_origin,
+        }
+    }
+
+    /// The servers of `family` as entries. Those queried over DoH get their
+    /// template and flags from the system-wide `templates`.
+    pub fn server_entries(
+        &self,
+        family: AddressFamily,
+        templates: &[DnsServerEntry],
+    ) -> Vec<DnsServerEntry> {
+        self.addresses(family)
+            .iter()
+            .map(|address| {
+                let template = templates.iter().find(|t| {
+                    same_address(&t.address, address)
+                        && self.doh_servers.iter().any(|d| same_address(d, address))
+                });
+                DnsServerEntry {
+                    address: address.clone(),
+                    ..template.cloned().unwrap_or_default()
+                }
+            })
+            .collect()
+    }
+
+    /// The server address, marked when it is queried over DoH.
+    pub fn server_label(&self, address: &str) -> String {
+        if self.doh_servers.iter().any(|a| a == address) {
+            format!("{} (DoH)", address)
         } else {
-            addresses.join(", ")
+            address.to_string()
+        }
+    }
+
+    pub fn get_display(&self, family: AddressFamily) -> String {
+        let addresses = self.addresses(family);
+        if addresses.is_empty() {
+            return t!("automatic");
+        }
+
+        let servers = addresses
+            .iter()
+            .map(|address| self.server_label(address))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        match self.origin(family).label() {
+            Some(label) => format!("{} ({})", servers, label),
+            None => servers,
         }
     }
 }
@@ -273,34 +989,135 @@ mod tests {
         assert_eq!(entry.doh_mode, DohMode::Off);
         assert_eq!(entry.doh_template, "");
         assert!(entry.allow_fallback);
+        assert!(entry.auto_upgrade);
     }
 
     #[test]
     fn test_network_interface_display_name() {
+        let interface = test_interface("Ethernet", 12);
+        assert_eq!(interface.display_name(), "Ethernet (12)");
+
         let interface = NetworkInterface {
-            name: "Ethernet".to_string(),
-            interface_index: 12,
-            interface_guid: "{GUID}".to_string(),
-            has_ipv4: true,
-            has_ipv6: false,
+            name: "Wi-Fi".to_string(),
+            ssid: Some("HomeNet".to_string()),
+            ..interface
         };
-        assert_eq!(interface.display_name(), "Ethernet (12)");
+        assert_eq!(interface.display_name(), "Wi-Fi (12) — HomeNet");
+        assert_eq!(
+            interface.display_name_as(Some("Gaming Wi-Fi")),
+            "Gaming Wi-Fi — HomeNet"
+        );
     }
 
     #[test]
     fn test_dns_entry_new() {
         let entry = DnsEntry::new();
         assert!(!entry.enabled);
-        assert_eq!(entry.primary.address, "");
-        assert_eq!(entry.secondary.address, "");
+        assert_eq!(entry.servers.len(), 2);
+        assert_eq!(entry.servers[0].address, "");
+        assert_eq!(entry.servers[1].address, "");
+    }
+
+    #[test]
+    fn test_dns_entry_server_label() {
+        assert_eq!(DnsEntry::server_label(0), "Primary");
+        assert_eq!(DnsEntry::server_label(1), "Secondary");
+        assert_eq!(DnsEntry::server_label(2), "Server 3");
+    }
+
+    #[test]
+    fn test_dns_entry_is_valid_when_enabled_without_servers() {
+        let entry = DnsEntry {
+            enabled: true,
+            unchanged: false,
+            servers: Vec::new(),
+        };
+        assert!(!entry.is_valid());
+    }
+
+    #[test]
+    fn test_dns_entry_get_addresses_more_than_two() {
+        let server = |address: &str| DnsServerEntry {
+            address: address.to_string(),
+            ..Default::default()
+        };
+        let entry = DnsEntry {
+            enabled: true,
+            unchanged: false,
+            servers: vec![
+                server("9.9.9.9"),
+                server(""),
+                server("1.1.1.1"),
+                server("8.8.8.8"),
+            ],
+        };
+        assert_eq!(entry.get_addresses(), vec!["9.9.9.9", "1.1.1.1", "8.8.8.8"]);
+    }
+
+    #[test]
+    fn test_dns_entry_clipboard_text() {
+        let entry = DnsEntry {
+            enabled: true,
+            unchanged: false,
+            servers: vec![
+                DnsServerEntry {
+                    address: "1.1.1.1".to_string(),
+                    doh_mode: DohMode::On,
+                    doh_template: "https://cloudflare-dns.com/dns-query".to_string(),
+                    ..Default::default()
+                },
+                DnsServerEntry::default(),
+                DnsServerEntry {
+                    address: "1.0.0.1".to_string(),
+                    doh_template: "https://cloudflare-dns.com/dns-query".to_string(),
+                    ..Default::default()
+                },
+            ],
+        };
+        assert_eq!(
+            entry.clipboard_text(),
+            "1.1.1.1 https://cloudflare-dns.com/dns-query\n1.0.0.1"
+        );
+        assert_eq!(DnsEntry::default().clipboard_text(), "");
+    }
+
+    #[test]
+    fn test_dns_entry_deserialize_legacy_layout() {
+        let json = r#"{
+            "enabled": true,
+            "primary": {"address": "8.8.8.8", "doh_mode": "Off", "doh_template": "", "allow_fallback": true},
+            "secondary": {"address": "", "doh_mode": "Off", "doh_template": "", "allow_fallback": true}
+        }"#;
+        let entry: DnsEntry = serde_json::from_str(json).unwrap();
+        assert!(entry.enabled);
+        assert_eq!(entry.servers.len(), 2);
+        assert_eq!(entry.servers[0].address, "8.8.8.8");
+        assert!(entry.servers[0].auto_upgrade);
+    }
+
+    #[test]
+    fn test_dns_entry_serialize_roundtrip() {
+        let entry = DnsEntry {
+            enabled: true,
+            unchanged: false,
+            servers: vec![DnsServerEntry {
+                address: "1.1.1.1".to_string(),
+                ..Default::default()
+            }],
+        };
+        let json = serde_json::to_string(&entry).unwrap();
+        assert!(json.contains("\"servers\""));
+        assert!(!json.contains("\"primary\""));
+        let loaded: DnsEntry = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, entry);
     }
 
     #[test]
     fn test_dns_entry_is_valid_when_disabled() {
         let entry = DnsEntry {
             enabled: false,
-            primary: DnsServerEntry::default(),
-            secondary: DnsServerEntry::default(),
+            unchanged: false,
+            servers: vec![DnsServerEntry::default(), DnsServerEntry::default()],
         };
         assert!(entry.is_valid());
     }
@@ -309,8 +1126,8 @@ mod tests {
     fn test_dns_entry_is_valid_when_enabled_with_empty_primary() {
         let entry = DnsEntry {
             enabled: true,
-            primary: DnsServerEntry::default(),
-            secondary: DnsServerEntry::default(),
+            unchanged: false,
+            servers: vec![DnsServerEntry::default(), DnsServerEntry::default()],
         };
         assert!(!entry.is_valid());
     }
@@ -319,11 +1136,14 @@ mod tests {
     fn test_dns_entry_is_valid_when_enabled_with_primary() {
         let entry = DnsEntry {
             enabled: true,
-            primary: DnsServerEntry {
-                address: "8.8.8.8".to_string(),
-                ..Default::default()
-            },
-            secondary: DnsServerEntry::default(),
+            unchanged: false,
+            servers: vec![
+                DnsServerEntry {
+                    address: "8.8.8.8".to_string(),
+                    ..Default::default()
+                },
+                DnsServerEntry::default(),
+            ],
         };
         assert!(entry.is_valid());
     }
@@ -338,11 +1158,14 @@ mod tests {
     fn test_dns_entry_get_addresses_when_primary_only() {
         let entry = DnsEntry {
             enabled: true,
-            primary: DnsServerEntry {
-                address: "8.8.8.8".to_string(),
-                ..Default::default()
-            },
-            secondary: DnsServerEntry::default(),
+            unchanged: false,
+            servers: vec![
+                DnsServerEntry {
+                    address: "8.8.8.8".to_string(),
+                    ..Default::default()
+                },
+                DnsServerEntry::default(),
+            ],
         };
         assert_eq!(entry.get_addresses(), vec!["8.8.8.8"]);
     }
@@ -351,14 +1174,17 @@ mod tests {
     fn test_dns_entry_get_addresses_when_both_set() {
         let entry = DnsEntry {
             enabled: true,
-            primary: DnsServerEntry {
-                address: "8.8.8.8".to_string(),
-                ..Default::default()
-            },
-            secondary: DnsServerEntry {
-                address: "8.8.4.4".to_string(),
-                ..Default::default()
-            },
+            unchanged: false,
+            servers: vec![
+                DnsServerEntry {
+                    address: "8.8.8.8".to_string(),
+                    ..Default::default()
+                },
+                DnsServerEntry {
+                    address: "8.8.4.4".to_string(),
+                    ..Default::default()
+                },
+            ],
         };
         assert_eq!(entry.get_addresses(), vec!["8.8.8.8", "8.8.4.4"]);
     }
@@ -370,6 +1196,211 @@ mod tests {
         assert!(!settings.ipv6.enabled);
     }
 
+    #[test]
+    fn test_dns_settings_deserialize_without_suffixes() {
+        let json =
+            r#"{"ipv4":{"enabled":false,"servers":[]},"ipv6":{"enabled":false,"servers":[]}}"#;
+        let settings: DnsSettings = serde_json::from_str(json).unwrap();
+        assert_eq!(settings.suffixes, DnsSuffixSettings::default());
+        assert_eq!(settings.connection_suffix, None);
+    }
+
+    #[test]
+    fn test_use_local_resolver() {
+        let mut settings = DnsSettings::new();
+        settings.ipv4.servers[0].address = "9.9.9.9".to_string();
+        settings.ipv4.servers[0].doh_mode = DohMode::On;
+        settings.use_local_resolver();
+
+        assert_eq!(settings.local_resolver, Some(LocalResolver::default()));
+        assert!(settings.ipv4.enabled && settings.ipv6.enabled);
+        assert_eq!(settings.ipv4.get_addresses(), vec!["127.0.0.1"]);
+        assert_eq!(settings.ipv6.get_addresses(), vec!["::1"]);
+        assert_eq!(settings.ipv4.servers[0].doh_mode, DohMode::Off);
+
+        let json = serde_json::to_string(&settings).unwrap();
+        assert!(json.contains(r#""local_resolver":{"check_listening":true}"#));
+        let parsed: DnsSettings =
+            serde_json::from_str(&json.replace(r#"{"check_listening":true}"#, "{}")).unwrap();
+        assert_eq!(parsed, settings);
+        assert!(
+            !serde_json::to_string(&DnsSettings::new())
+                .unwrap()
+                .contains("local_resolver")
+        );
+    }
+
+    #[test]
+    fn test_dns_settings_connection_suffix_round_trip() {
+        let settings = DnsSettings {
+            connection_suffix: Some("corp.example.com".to_string()),
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&settings).unwrap();
+        assert!(json.contains(r#""connection_suffix":"corp.example.com""#));
+        assert_eq!(
+            serde_json::from_str::<DnsSettings>(&json).unwrap(),
+            settings
+        );
+
+        let json = serde_json::to_string(&DnsSettings::new()).unwrap();
+        assert!(!json.contains("connection_suffix"));
+    }
+
+    #[test]
+    fn test_dns_settings_apply_hooks_round_trip() {
+        let settings = DnsSettings {
+            before_apply: Some("Start-Service dnscrypt-proxy".to_string()),
+            after_apply: Some("ipconfig /registerdns".to_string()),
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&settings).unwrap();
+        assert_eq!(
+            serde_json::from_str::<DnsSettings>(&json).unwrap(),
+            settings
+        );
+
+        let json = serde_json::to_string(&DnsSettings::new()).unwrap();
+        assert!(!json.contains("before_apply"));
+        assert!(!json.contains("after_apply"));
+    }
+
+    #[test]
+    fn test_dns_settings_keeping_unchanged() {
+        let mut settings = DnsSettings::new();
+        settings.ipv4.enabled = true;
+        settings.ipv4.servers[0].address = "1.1.1.1".to_string();
+        settings.ipv6.unchanged = true;
+        assert!(settings.leaves_family_unchanged());
+        let json = serde_json::to_string(&settings).unwrap();
+        assert!(json.contains(r#""unchanged":true"#));
+        assert_eq!(
+            serde_json::from_str::<DnsSettings>(&json).unwrap(),
+            settings
+        );
+
+        let vpn_pushed = CurrentDnsState {
+            ipv4: vec!["192.168.1.1".to_string()],
+            ipv6: vec!["fd00::53".to_string()],
+            ipv4_origin: DnsOrigin::Dhcp,
+            ipv6_origin: DnsOrigin::Static,
+            ..Default::default()
+        };
+        let kept = settings.keeping_unchanged(&vpn_pushed, &[]);
+        assert_eq!(kept.ipv4, settings.ipv4);
+        assert!(kept.ipv6.enabled);
+        assert_eq!(kept.ipv6.get_addresses(), vec!["fd00::53"]);
+
+        let dhcp = CurrentDnsState {
+            ipv6_origin: DnsOrigin::Dhcp,
+            ..vpn_pushed
+        };
+        assert!(settings.keeping_unchanged(&dhcp, &[]).ipv6.is_left_alone());
+
+        settings.ipv6.unchanged = false;
+        assert!(!settings.leaves_family_unchanged());
+        let json = serde_json::to_string(&settings).unwrap();
+        assert!(!json.contains("unchanged"));
+    }
+
+    #[test]
+    fn test_keeping_unchanged_keeps_doh_of_static_family() {
+        let mut settings = DnsSettings::new();
+        settings.ipv4.unchanged = true;
+        let current = CurrentDnsState {
+            ipv4: vec!["1.1.1.1".to_string(), "8.8.8.8".to_string()],
+            doh_servers: vec!["1.1.1.1".to_string()],
+            ipv4_origin: DnsOrigin::Static,
+            ..Default::default()
+        };
+        let templates = vec![
+            DnsServerEntry {
+                address: "1.1.1.1".to_string(),
+                doh_mode: DohMode::On,
+                doh_template: "https://cloudflare-dns.com/dns-query".to_string(),
+                allow_fallback: false,
+                ..Default::default()
+            },
+            DnsServerEntry {
+                address: "8.8.8.8".to_string(),
+                doh_mode: DohMode::On,
+                doh_template: "https://dns.google/dns-query".to_string(),
+                ..Default::default()
+            },
+        ];
+
+        let kept = settings.keeping_unchanged(&current, &templates);
+        assert!(kept.ipv4.enabled && !kept.ipv4.unchanged);
+        assert_eq!(kept.ipv4.servers[0], templates[0]);
+        // 8.8.8.8 has a template but is not queried over DoH here.
+        assert_eq!(kept.ipv4.servers[1].address, "8.8.8.8");
+        assert_eq!(kept.ipv4.servers[1].doh_mode, DohMode::Off);
+        assert!(kept.ipv4.servers[1].doh_template.is_empty());
+    }
+
+    #[test]
+    fn test_keeping_unchanged_leaves_unknown_family_alone() {
+        let mut settings = DnsSettings::new();
+        settings.ipv4.enabled = true;
+        settings.ipv4.servers[0].address = "1.1.1.1".to_string();
+        settings.ipv6.unchanged = true;
+        let current = CurrentDnsState {
+            ipv6: vec!["fd00::53".to_string()],
+            ipv6_origin: DnsOrigin::Unknown,
+            ..Default::default()
+        };
+
+        let kept = settings.keeping_unchanged(&current, &[]);
+        assert_eq!(kept.ipv4, settings.ipv4);
+        assert_eq!(kept.ipv6, settings.ipv6);
+        assert!(kept.leaves_family_unchanged());
+    }
+
+    #[test]
+    fn test_dns_suffix_settings_search_suffixes_skips_blank() {
+        let suffixes = DnsSuffixSettings {
+            enabled: true,
+            search_list: vec![
+                " corp.example.com ".to_string(),
+                String::new(),
+                "example.com".to_string(),
+            ],
+        };
+        assert_eq!(
+            suffixes.search_suffixes(),
+            vec!["corp.example.com", "example.com"]
+        );
+    }
+
+    #[test]
+    fn test_nrpt_rule_normalized_namespace() {
+        let rule = |namespace: &str| NrptRule {
+            namespace: namespace.to_string(),
+            name_servers: String::new(),
+        };
+        assert_eq!(
+            rule("*.corp.example").normalized_namespace(),
+            ".corp.example"
+        );
+        assert_eq!(
+            rule(" .corp.example ").normalized_namespace(),
+            ".corp.example"
+        );
+        assert_eq!(
+            rule("host.corp.example").normalized_namespace(),
+            "host.corp.example"
+        );
+    }
+
+    #[test]
+    fn test_nrpt_rule_name_server_addresses() {
+        let rule = NrptRule {
+            namespace: ".corp.example".to_string(),
+            name_servers: "10.0.0.53, ,10.0.1.53,".to_string(),
+        };
+        assert_eq!(rule.name_server_addresses(), vec!["10.0.0.53", "10.0.1.53"]);
+    }
+
     #[test]
     fn test_dns_profile_new() {
         let profile = DnsProfile::new("Test Profile".to_string());
@@ -377,6 +1408,41 @@ mod tests {
         assert!(!profile.id.is_empty());
         assert!(!profile.settings.ipv4.enabled);
         assert!(!profile.settings.ipv6.enabled);
+        assert!(profile.tags.is_empty());
+        assert!(profile.color.is_none());
+    }
+
+    #[test]
+    fn test_parse_tags() {
+        assert_eq!(
+            parse_tags(" home, Work ,,work, testing"),
+            vec!["home", "Work", "testing"]
+        );
+        assert!(parse_tags(" , ").is_empty());
+    }
+
+    #[test]
+    fn test_dns_profile_matches_search() {
+        let mut profile = DnsProfile::new("Office VPN".to_string());
+        profile.tags = vec!["Work".to_string()];
+        assert!(profile.matches_search(""));
+        assert!(profile.matches_search("vpn"));
+        assert!(profile.matches_search(" WORK "));
+        assert!(!profile.matches_search("home"));
+    }
+
+    #[test]
+    fn test_dns_profile_tags_serialization() {
+        let mut profile = DnsProfile::new("Home".to_string());
+        let json = serde_json::to_string(&profile).unwrap();
+        assert!(!json.contains("tags"));
+
+        profile.tags = vec!["home".to_string()];
+        let json = serde_json::to_string(&profile).unwrap();
+        assert!(json.contains(r#""tags":["home"]"#));
+        let restored: DnsProfile = serde_json::from_str(&json).unwrap();
+        assert!(restored.has_tag("HOME"));
+        assert!(!restored.has_tag("work"));
     }
 
     #[test]
@@ -385,6 +1451,138 @@ mod tests {
         assert_eq!(config.profiles.len(), 0);
     }
 
+    #[test]
+    fn test_app_config_flushes_cache_after_apply() {
+        let mut config = AppConfig::new();
+        assert!(config.settings.flushes_cache_after_apply());
+
+        config.settings.flush_cache_after_apply = Some(false);
+        assert!(!config.settings.flushes_cache_after_apply());
+
+        let json = serde_json::to_string(&AppConfig::new()).unwrap();
+        assert!(!json.contains("flush_cache_after_apply"));
+    }
+
+    #[test]
+    fn test_app_config_notifies_network_rules() {
+        let mut config = AppConfig::new();
+        assert!(config.settings.notifies_network_rules());
+
+        config.settings.notify_network_rules = Some(false);
+        assert!(!config.settings.notifies_network_rules());
+    }
+
+    #[test]
+    fn test_app_config_theme() {
+        let mut config = AppConfig::new();
+        assert_eq!(config.settings.theme(), ThemePreference::System);
+        let json = serde_json::to_string(&config).unwrap();
+        assert!(!json.contains("theme"));
+
+        config.settings.theme = Some(ThemePreference::Light);
+        let json = serde_json::to_string(&config).unwrap();
+        assert!(json.contains(r#""theme":"Light""#));
+        let restored: AppConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.settings.theme(), ThemePreference::Light);
+    }
+
+    #[test]
+    fn test_app_config_language() {
+        let mut config = AppConfig::new();
+        let json = serde_json::to_string(&config).unwrap();
+        assert!(!json.contains("language"));
+
+        config.settings.language = Some(Language::Japanese);
+        let json = serde_json::to_string(&config).unwrap();
+        assert!(json.contains(r#""language":"Japanese""#));
+        let restored: AppConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.settings.language(), Language::Japanese);
+    }
+
+    #[test]
+    fn test_app_config_original_dns_round_trip() {
+        let json = serde_json::to_string(&AppConfig::new()).unwrap();
+        assert!(!json.contains("original_dns"));
+
+        let mut config = AppConfig::new();
+        config
+            .original_dns
+            .insert("{GUID-1}".to_string(), DnsSettings::new());
+        let json = serde_json::to_string(&config).unwrap();
+        let restored: AppConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            restored.original_dns.get("{GUID-1}"),
+            Some(&DnsSettings::new())
+        );
+    }
+
+    #[test]
+    fn test_app_config_probes_doh_before_apply() {
+        let mut config = AppConfig::new();
+        assert!(config.settings.probes_doh_before_apply());
+
+        config.settings.probe_doh_before_apply = Some(false);
+        assert!(!config.settings.probes_doh_before_apply());
+
+        let json = serde_json::to_string(&AppConfig::new()).unwrap();
+        assert!(!json.contains("probe_doh_before_apply"));
+    }
+
+    #[test]
+    fn test_app_config_resolution_check() {
+        let mut config = AppConfig::new();
+        assert!(config.settings.checks_resolution_after_apply());
+        assert_eq!(config.settings.resolution_check_host(), "example.com");
+
+        config.settings.check_resolution_after_apply = Some(false);
+        config.settings.resolution_check_host = Some("intranet.corp.example".to_string());
+        assert!(!config.settings.checks_resolution_after_apply());
+        assert_eq!(
+            config.settings.resolution_check_host(),
+            "intranet.corp.example"
+        );
+
+        let json = serde_json::to_string(&AppConfig::new()).unwrap();
+        assert!(!json.contains("resolution_check"));
+    }
+
+    #[test]
+    fn test_app_config_warns_on_external_dns_change() {
+        let mut config = AppConfig::new();
+        assert!(config.settings.warns_on_external_dns_change());
+
+        config.settings.warn_on_external_dns_change = Some(false);
+        assert!(!config.settings.warns_on_external_dns_change());
+    }
+
+    #[test]
+    fn test_app_settings_stay_at_the_top_level() {
+        let json = r#"{"profiles":[],"flush_cache_after_apply":false,"theme":"Dark"}"#;
+        let config: AppConfig = serde_json::from_str(json).unwrap();
+        assert!(!config.settings.flushes_cache_after_apply());
+        assert_eq!(config.settings.theme(), ThemePreference::Dark);
+
+        let json = serde_json::to_string(&config).unwrap();
+        assert!(json.contains(r#""flush_cache_after_apply":false"#));
+        assert!(!json.contains("settings"));
+    }
+
+    #[test]
+    fn test_app_settings_confirm_and_refresh() {
+        let mut settings = AppSettings::default();
+        assert!(settings.confirms_profile_delete());
+        assert_eq!(settings.dns_refresh_secs(), 5);
+        assert!(!settings.enables_control_server());
+
+        settings.confirm_profile_delete = Some(false);
+        settings.dns_refresh_secs = Some(0);
+        assert!(!settings.confirms_profile_delete());
+        assert_eq!(settings.dns_refresh_secs(), 1);
+
+        settings.dns_refresh_secs = Some(86400);
+        assert_eq!(settings.dns_refresh_interval().as_secs(), 3600);
+    }
+
     #[test]
     fn test_app_config_find_profile_found() {
         let mut config = AppConfig::new();
@@ -404,6 +1602,26 @@ mod tests {
         assert!(found.is_none());
     }
 
+    #[test]
+    fn test_app_config_find_profile_by_name_or_id() {
+        let mut config = AppConfig::new();
+        let profile = DnsProfile::new("Cloudflare".to_string());
+        let id = profile.id.clone();
+        config.add_profile(profile);
+
+        assert_eq!(
+            config.find_profile_by_name_or_id(&id).map(|p| &p.id),
+            Some(&id)
+        );
+        assert_eq!(
+            config
+                .find_profile_by_name_or_id("cloudflare")
+                .map(|p| &p.id),
+            Some(&id)
+        );
+        assert!(config.find_profile_by_name_or_id("Google").is_none());
+    }
+
     #[test]
     fn test_app_config_find_profile_mut_found() {
         let mut config = AppConfig::new();
@@ -437,10 +1655,29 @@ mod tests {
         let profile = DnsProfile::new("Test".to_string());
         let id = profile.id.clone();
         config.add_profile(profile);
+        config
+            .interface_profiles
+            .insert("{GUID-1}".to_string(), Some(id.clone()));
+        config
+            .interface_profiles
+            .insert("{GUID-2}".to_string(), None);
+        config.hotkeys.insert(1, id.clone());
 
         let result = config.remove_profile(&id);
         assert!(result);
         assert_eq!(config.profiles.len(), 0);
+        assert_eq!(config.interface_profiles.len(), 1);
+        assert!(config.hotkeys.is_empty());
+    }
+
+    #[test]
+    fn test_app_config_hotkeys_serialization() {
+        let mut config = AppConfig::new();
+        config.hotkeys.insert(3, "profile-id".to_string());
+
+        let json = serde_json::to_string(&config).unwrap();
+        assert!(json.contains(r#""hotkeys":{"3":"profile-id"}"#));
+        assert_eq!(serde_json::from_str::<AppConfig>(&json).unwrap(), config);
     }
 
     #[test]
@@ -471,6 +1708,45 @@ mod tests {
         assert_eq!(sorted[2].name, "Zebra");
     }
 
+    #[test]
+    fn test_app_config_profile_tags() {
+        let mut config = AppConfig::new();
+        let mut home = DnsProfile::new("Home".to_string());
+        home.tags = vec!["home".to_string(), "Testing".to_string()];
+        let mut lab = DnsProfile::new("Lab".to_string());
+        lab.tags = vec!["testing".to_string(), "Work".to_string()];
+        config.add_profile(home);
+        config.add_profile(lab);
+        config.add_profile(DnsProfile::new("Plain".to_string()));
+
+        assert_eq!(config.profile_tags(), vec!["home", "Testing", "Work"]);
+    }
+
+    #[test]
+    fn test_app_config_record_doh_servers_deduplicates() {
+        let mut config = AppConfig::new();
+        config.record_doh_servers(&["1.1.1.1".to_string(), "9.9.9.9".to_string()]);
+        config.record_doh_servers(&["1.1.1.1".to_string()]);
+        assert_eq!(config.managed_doh_servers, vec!["1.1.1.1", "9.9.9.9"]);
+    }
+
+    #[test]
+    fn test_app_config_unused_doh_servers() {
+        let mut config = AppConfig::new();
+        let mut profile = DnsProfile::new("Cloudflare".to_string());
+        profile.settings.ipv4.servers[0].address = "1.1.1.1".to_string();
+        profile.settings.ipv4.servers[0].doh_mode = DohMode::On;
+        profile.settings.ipv4.servers[1].address = "1.0.0.1".to_string();
+        config.add_profile(profile);
+        config.record_doh_servers(&[
+            "1.1.1.1".to_string(),
+            "1.0.0.1".to_string(),
+            "9.9.9.9".to_string(),
+        ]);
+
+        assert_eq!(config.unused_doh_servers(), vec!["1.0.0.1", "9.9.9.9"]);
+    }
+
     #[test]
     fn test_current_dns_state_new() {
         let state = CurrentDnsState::new();
@@ -489,10 +1765,49 @@ mod tests {
         let state = CurrentDnsState {
             ipv4: vec!["8.8.8.8".to_string(), "8.8.4.4".to_string()],
             ipv6: vec![],
+            doh_servers: vec![],
+            ..Default::default()
         };
         assert_eq!(state.get_display(AddressFamily::IPv4), "8.8.8.8, 8.8.4.4");
     }
 
+    #[test]
+    fn test_current_dns_state_get_display_marks_doh_servers() {
+        let state = CurrentDnsState {
+            ipv4: vec!["1.1.1.1".to_string(), "8.8.8.8".to_string()],
+            ipv6: vec![],
+            doh_servers: vec!["1.1.1.1".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            state.get_display(AddressFamily::IPv4),
+            "1.1.1.1 (DoH), 8.8.8.8"
+        );
+    }
+
+    #[test]
+    fn test_current_dns_state_get_display_shows_origin() {
+        let state = CurrentDnsState {
+            ipv4: vec!["192.168.1.1".to_string()],
+            ipv6: vec!["2606:4700:4700::1111".to_string()],
+            doh_servers: vec!["2606:4700:4700::1111".to_string()],
+            ipv4_origin: DnsOrigin::Dhcp,
+            ipv6_origin: DnsOrigin::Static,
+        };
+        assert_eq!(state.get_display(AddressFamily::IPv4), "192.168.1.1 (DHCP)");
+        assert_eq!(
+            state.get_display(AddressFamily::IPv6),
+            "2606:4700:4700::1111 (DoH) (static)"
+        );
+    }
+
+    #[test]
+    fn test_current_dns_state_deserialize_without_origin() {
+        let state: CurrentDnsState =
+            serde_json::from_str(r#"{"ipv4":["1.1.1.1"],"ipv6":[]}"#).unwrap();
+        assert_eq!(state.ipv4_origin, DnsOrigin::Unknown);
+    }
+
     #[test]
     fn test_current_dns_state_get_display_ipv6_empty() {
         let state = CurrentDnsState::new();
@@ -504,6 +1819,8 @@ mod tests {
         let state = CurrentDnsState {
             ipv4: vec![],
             ipv6: vec!["2001:4860:4860::8888".to_string()],
+            doh_servers: vec![],
+            ..Default::default()
         };
         assert_eq!(
             state.get_display(AddressFamily::IPv6),
@@ -0,0 +1,543 @@
+use crate::dns::commands::{
+    command_error_message, escape_powershell_string, run_powershell, run_powershell_with_timeout,
+};
+use crate::dns::elevation::is_elevated;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum UpdateError {
+    #[error("{0}")]
+    RequestFailed(String),
+
+    #[error("GitHub sent an invalid release description")]
+    InvalidRelease,
+
+    #[error("The release has no Windows download")]
+    NoDownload,
+
+    #[error("The download does not match its published checksum")]
+    ChecksumMismatch,
+
+    #[error("The download contains no windns executable or installer")]
+    NoExecutable,
+
+    #[error("Failed to install the update: {0}")]
+    Install(String),
+}
+
+pub type Result<T> = std::result::Result<T, UpdateError>;
+
+const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/arenahito/windns/releases/latest";
+/// Suffix of the archive the release workflow uploads, next to which it
+/// puts `<archive>.sha256`.
+const ARCHIVE_SUFFIX: &str = "-windows-x64.zip";
+const CHECKSUM_SUFFIX: &str = ".sha256";
+const EXECUTABLE_NAME: &str = "windns.exe";
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(300);
+/// Lets other handles read a file, but not write, rename or delete it.
+#[cfg(target_os = "windows")]
+const FILE_SHARE_READ: u32 = 0x1;
+
+/// A published release of windns.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Release {
+    /// Version without the leading `v`, e.g. `0.4.0`.
+    pub version: String,
+    /// Release notes page.
+    pub page_url: String,
+    archive_url: String,
+    checksum_url: String,
+}
+
+/// A downloaded and verified update, ready to be installed, with the
+/// SHA-256 hash the file had when it was unpacked.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum UpdatePackage {
+    /// New `windns.exe`, swapped in for the running one on restart.
+    Executable { path: PathBuf, sha256: String },
+    /// Setup program that replaces the installed copy.
+    Installer { path: PathBuf, sha256: String },
+}
+
+#[derive(Deserialize)]
+struct GitHubRelease {
+    tag_name: String,
+    html_url: String,
+    #[serde(default)]
+    assets: Vec<GitHubAsset>,
+}
+
+#[derive(Deserialize)]
+struct GitHubAsset {
+    name: String,
+    browser_download_url: String,
+}
+
+pub fn current_version() -> &'static str {
+    env!("CARGO_PKG_VERSION")
+}
+
+/// Reads `1.2.3` or `v1.2.3`. Pre-release versions are not offered as
+/// updates, so anything else is rejected.
+fn parse_version(text: &str) -> Option<(u64, u64, u64)> {
+    let mut parts = text.trim().trim_start_matches('v').split('.');
+    let mut next = || parts.next()?.parse::<u64>().ok();
+    let version = (next()?, next()?, next()?);
+    parts.next().is_none().then_some(version)
+}
+
+/// Whether `latest` is a newer version than `current`.
+fn is_newer(latest: &str, current: &str) -> bool {
+    match (parse_version(latest), parse_version(current)) {
+        (Some(latest), Some(current)) => latest > current,
+        _ => false,
+    }
+}
+
+fn parse_release(json: &str) -> Result<Release> {
+    let release: GitHubRelease =
+        serde_json::from_str(json.trim()).map_err(|_| UpdateError::InvalidRelease)?;
+    let archive = release
+        .assets
+        .iter()
+        .find(|asset| asset.name.ends_with(ARCHIVE_SUFFIX))
+        .ok_or(UpdateError::NoDownload)?;
+    let checksum_name = format!("{}{}", archive.name, CHECKSUM_SUFFIX);
+    let checksum = release
+        .assets
+        .iter()
+        .find(|asset| asset.name == checksum_name)
+        .ok_or(UpdateError::NoDownload)?;
+
+    Ok(Release {
+        version: release.tag_name.trim_start_matches('v').to_string(),
+        page_url: release.html_url,
+        archive_url: archive.browser_download_url.clone(),
+        checksum_url: checksum.browser_download_url.clone(),
+    })
+}
+
+/// Asks GitHub for the latest release, returning it if it is newer than
+/// the running version.
+pub async fn check_for_update() -> Result<Option<Release>> {
+    let script = format!(
+        "(Invoke-WebRequest -UseBasicParsing -Uri '{}' -Headers @{{ Accept = 'application/vnd.github+json' }} -TimeoutSec 10).Content",
+        LATEST_RELEASE_URL
+    );
+    let output = run_powershell(&script)
+        .await
+        .map_err(|e| UpdateError::RequestFailed(command_error_message(e)))?;
+    let release = parse_release(&output)?;
+    Ok(is_newer(&release.version, current_version()).then_some(release))
+}
+
+/// Compares a file hash with a checksum file, which holds the hash alone or
+/// in the `sha256sum` format of hash and file name.
+fn checksum_matches(checksum_file: &str, hash: &str) -> bool {
+    checksum_file
+        .split_whitespace()
+        .next()
+        .is_some_and(|expected| !hash.is_empty() && expected.eq_ignore_ascii_case(hash.trim()))
+}
+
+/// Uppercase hex SHA-256 hash, as `Get-FileHash` prints it.
+fn sha256_hex(contents: &[u8]) -> String {
+    format!("{:X}", Sha256::digest(contents))
+}
+
+/// Folder updates are downloaded under. An elevated windns uses one in
+/// %ProgramData% that it restricts to administrators, so that programs
+/// running without elevation cannot swap the files it goes on to start.
+fn update_root(elevated: bool) -> Option<PathBuf> {
+    let base = if elevated {
+        std::env::var_os("ProgramData").map(PathBuf::from)
+    } else {
+        dirs::data_local_dir()
+    };
+    base.map(|base| base.join("windns"))
+}
+
+/// Gives `root` and everything in it to the Administrators group and
+/// SYSTEM alone, replacing whatever owner and access it had before.
+const RESTRICT_ROOT_SCRIPT: &str = r#"
+        icacls $root /setowner '*S-1-5-32-544' /T /C /Q | Out-Null
+        if ($LASTEXITCODE -ne 0) { throw 'Failed to take ownership of the update folder' }
+        icacls $root /inheritance:r /grant:r '*S-1-5-32-544:(OI)(CI)F' '*S-1-5-18:(OI)(CI)F' /T /C /Q | Out-Null
+        if ($LASTEXITCODE -ne 0) { throw 'Failed to restrict access to the update folder' }
+"#;
+
+fn download_script(release: &Release, root: &Path, dir: &Path, restrict: bool) -> String {
+    format!(
+        r#"
+        $ProgressPreference = 'SilentlyContinue'
+        $root = '{root}'
+        $dir = '{dir}'
+        New-Item -ItemType Directory -Path $root -Force | Out-Null
+        {restrict}
+        if (Test-Path -LiteralPath $dir) {{ Remove-Item -LiteralPath $dir -Recurse -Force }}
+        New-Item -ItemType Directory -Path $dir | Out-Null
+        $archive = Join-Path $dir 'update.zip'
+        Invoke-WebRequest -UseBasicParsing -Uri '{archive}' -OutFile $archive -TimeoutSec 60
+        Invoke-WebRequest -UseBasicParsing -Uri '{checksum}' -OutFile (Join-Path $dir 'update.zip.sha256') -TimeoutSec 10
+        (Get-FileHash -LiteralPath $archive -Algorithm SHA256).Hash
+        "#,
+        root = escape_powershell_string(&root.to_string_lossy()),
+        dir = escape_powershell_string(&dir.to_string_lossy()),
+        restrict = if restrict { RESTRICT_ROOT_SCRIPT } else { "" },
+        archive = escape_powershell_string(&release.archive_url),
+        checksum = escape_powershell_string(&release.checksum_url),
+    )
+}
+
+/// Unpacks the archive, refusing to if it no longer has the verified
+/// `hash`, and lists each executable in it as its hash and path.
+fn extract_script(dir: &Path, hash: &str) -> String {
+    format!(
+        r#"
+        $dir = '{dir}'
+        $archive = Join-Path $dir 'update.zip'
+        if ((Get-FileHash -LiteralPath $archive -Algorithm SHA256).Hash -ne '{hash}') {{
+            throw 'The download changed after it was verified'
+        }}
+        $files = Join-Path $dir 'files'
+        Expand-Archive -LiteralPath $archive -DestinationPath $files -Force
+        Get-ChildItem -LiteralPath $files -Recurse -Filter '*.exe' | ForEach-Object {{
+            "$((Get-FileHash -LiteralPath $_.FullName -Algorithm SHA256).Hash)`t$($_.FullName)"
+        }}
+        "#,
+        dir = escape_powershell_string(&dir.to_string_lossy()),
+        hash = escape_powershell_string(hash.trim()),
+    )
+}
+
+/// Picks what to install from the executables in the archive, listed as
+/// `hash<TAB>path` lines: windns itself if it is there, otherwise its
+/// setup program.
+fn find_package(files: &str) -> Option<UpdatePackage> {
+    let files: Vec<(&str, &str)> = files
+        .lines()
+        .filter_map(|line| line.trim().split_once('\t'))
+        .map(|(hash, path)| (hash.trim(), path.trim()))
+        .filter(|(hash, path)| !hash.is_empty() && !path.is_empty())
+        .collect();
+    let file_name = |path: &str| {
+        path.rsplit(['\\', '/'])
+            .next()
+            .unwrap_or(path)
+            .to_lowercase()
+    };
+
+    if let Some((hash, path)) = files.iter().find(|(_, p)| file_name(p) == EXECUTABLE_NAME) {
+        return Some(UpdatePackage::Executable {
+            path: PathBuf::from(path),
+            sha256: hash.to_string(),
+        });
+    }
+    files
+        .iter()
+        .find(|(_, p)| file_name(p).contains("setup"))
+        .map(|(hash, path)| UpdatePackage::Installer {
+            path: PathBuf::from(path),
+            sha256: hash.to_string(),
+        })
+}
+
+/// Downloads the release and unpacks it once its SHA-256 hash matches the
+/// one published with it.
+pub async fn download_update(release: &Release) -> Result<UpdatePackage> {
+    let elevated = is_elevated();
+    let root = update_root(elevated).ok_or_else(|| {
+        UpdateError::RequestFailed("No folder to download the update to".to_string())
+    })?;
+    let dir = root.join("updates").join(&release.version);
+    let request_failed = |e| UpdateError::RequestFailed(command_error_message(e));
+
+    let hash = run_powershell_with_timeout(
+        &download_script(release, &root, &dir, elevated),
+        DOWNLOAD_TIMEOUT,
+    )
+    .await
+    .map_err(request_failed)?;
+    let checksum = std::fs::read_to_string(dir.join("update.zip.sha256"))
+        .map_err(|e| UpdateError::RequestFailed(e.to_string()))?;
+    if !checksum_matches(&checksum, &hash) {
+        return Err(UpdateError::ChecksumMismatch);
+    }
+
+    let files = run_powershell(&extract_script(&dir, &hash))
+        .await
+        .map_err(request_failed)?;
+    find_package(&files).ok_or(UpdateError::NoExecutable)
+}
+
+/// Reads `path`, checks it still has the hash `sha256` and hands its
+/// contents to `use_file`. On Windows the file stays open without sharing
+/// write or delete access until `use_file` returns, so that it cannot be
+/// swapped between the check and its use.
+fn with_verified_file<T>(
+    path: &Path,
+    sha256: &str,
+    use_file: impl FnOnce(&[u8]) -> std::io::Result<T>,
+) -> Result<T> {
+    let install_error = |e: std::io::Error| UpdateError::Install(e.to_string());
+    let mut options = std::fs::OpenOptions::new();
+    options.read(true);
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::fs::OpenOptionsExt;
+        options.share_mode(FILE_SHARE_READ);
+    }
+    let mut file = options.open(path).map_err(install_error)?;
+    let mut contents = Vec::new();
+    file.read_to_end(&mut contents).map_err(install_error)?;
+    if !checksum_matches(sha256, &sha256_hex(&contents)) {
+        return Err(UpdateError::ChecksumMismatch);
+    }
+    let result = use_file(&contents).map_err(install_error);
+    drop(file);
+    result
+}
+
+/// Where the replaced executable is kept until the next start, as Windows
+/// does not allow deleting a running program but does allow renaming it.
+fn old_executable_path(exe: &Path) -> PathBuf {
+    let mut name = exe.as_os_str().to_owned();
+    name.push(".old");
+    PathBuf::from(name)
+}
+
+/// Removes the executable left behind by the last update, if any.
+pub fn remove_old_executable() {
+    if let Ok(exe) = std::env::current_exe() {
+        let _ = std::fs::remove_file(old_executable_path(&exe));
+    }
+}
+
+fn replace_executable(contents: &[u8], exe: &Path) -> std::io::Result<()> {
+    let old = old_executable_path(exe);
+    let _ = std::fs::remove_file(&old);
+    std::fs::rename(exe, &old)?;
+    if let Err(e) = std::fs::write(exe, contents) {
+        let _ = std::fs::rename(&old, exe);
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Installs a downloaded update once the file still has the hash it was
+/// unpacked with. A new executable is swapped in for the running one and
+/// started with `args`; an installer is simply started. The caller is
+/// expected to close the current window once this succeeds.
+pub fn install_update(package: &UpdatePackage, args: &[String]) -> Result<()> {
+    let install_error = |e: std::io::Error| UpdateError::Install(e.to_string());
+    match package {
+        UpdatePackage::Executable { path, sha256 } => {
+            let exe = std::env::current_exe().map_err(install_error)?;
+            with_verified_file(path, sha256, |contents| replace_executable(contents, &exe))?;
+            Command::new(&exe)
+                .args(args)
+                .spawn()
+                .map_err(install_error)?;
+        }
+        UpdatePackage::Installer { path, sha256 } => {
+            with_verified_file(path, sha256, |_| Command::new(path).spawn())?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn release_json(assets: &str) -> String {
+        format!(
+            r#"{{"tag_name": "v0.4.0", "html_url": "https://github.com/arenahito/windns/releases/tag/v0.4.0", "assets": [{}]}}"#,
+            assets
+        )
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("0.3.2"), Some((0, 3, 2)));
+        assert_eq!(parse_version("v1.10.0\n"), Some((1, 10, 0)));
+        assert_eq!(parse_version("1.0"), None);
+        assert_eq!(parse_version("1.0.0.1"), None);
+        assert_eq!(parse_version("1.0.0-beta"), None);
+    }
+
+    #[test]
+    fn test_is_newer() {
+        assert!(is_newer("0.4.0", "0.3.2"));
+        assert!(is_newer("0.3.10", "0.3.9"));
+        assert!(!is_newer("0.3.2", "0.3.2"));
+        assert!(!is_newer("0.3.1", "0.3.2"));
+        assert!(!is_newer("latest", "0.3.2"));
+    }
+
+    #[test]
+    fn test_parse_release() {
+        let json = release_json(
+            r#"
+            {"name": "windns-v0.4.0-windows-x64.zip", "browser_download_url": "https://example.com/a.zip"},
+            {"name": "windns-v0.4.0-windows-x64.zip.sha256", "browser_download_url": "https://example.com/a.zip.sha256"}
+            "#,
+        );
+        let release = parse_release(&json).unwrap();
+        assert_eq!(release.version, "0.4.0");
+        assert_eq!(
+            release.page_url,
+            "https://github.com/arenahito/windns/releases/tag/v0.4.0"
+        );
+        assert_eq!(release.archive_url, "https://example.com/a.zip");
+        assert_eq!(release.checksum_url, "https://example.com/a.zip.sha256");
+    }
+
+    #[test]
+    fn test_parse_release_requires_checksum() {
+        let json = release_json(
+            r#"{"name": "windns-v0.4.0-windows-x64.zip", "browser_download_url": "https://example.com/a.zip"}"#,
+        );
+        assert!(matches!(parse_release(&json), Err(UpdateError::NoDownload)));
+        assert!(matches!(
+            parse_release(&release_json("")),
+            Err(UpdateError::NoDownload)
+        ));
+        assert!(matches!(
+            parse_release("Not Found"),
+            Err(UpdateError::InvalidRelease)
+        ));
+    }
+
+    #[test]
+    fn test_checksum_matches() {
+        let hash = "9F86D081884C7D659A2FEAA0C55AD015A3BF4F1B2B0B822CD15D6C15B0F00A08\r\n";
+        let sum = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+        assert!(checksum_matches(sum, hash));
+        assert!(checksum_matches(
+            &format!("{}  windns-v0.4.0-windows-x64.zip\n", sum),
+            hash
+        ));
+        assert!(!checksum_matches(&sum.replace('9', "8"), hash));
+        assert!(!checksum_matches("", hash));
+        assert!(!checksum_matches(sum, ""));
+    }
+
+    #[test]
+    fn test_find_package() {
+        assert_eq!(
+            find_package(
+                "AAAA\tC:\\tmp\\files\\windns_0.4.0_x64-setup.exe\r\nBBBB\tC:\\tmp\\files\\WinDNS.exe\r\n"
+            ),
+            Some(UpdatePackage::Executable {
+                path: PathBuf::from("C:\\tmp\\files\\WinDNS.exe"),
+                sha256: "BBBB".to_string(),
+            })
+        );
+        assert_eq!(
+            find_package("AAAA\tC:\\tmp\\files\\windns_0.4.0_x64-setup.exe\r\n"),
+            Some(UpdatePackage::Installer {
+                path: PathBuf::from("C:\\tmp\\files\\windns_0.4.0_x64-setup.exe"),
+                sha256: "AAAA".to_string(),
+            })
+        );
+        assert_eq!(find_package("AAAA\tC:\\tmp\\files\\other.exe\r\n"), None);
+        assert_eq!(find_package("C:\\tmp\\files\\windns.exe\r\n"), None);
+        assert_eq!(find_package(""), None);
+    }
+
+    #[test]
+    fn test_extract_script_checks_the_archive_again() {
+        let script = extract_script(Path::new("/tmp/windns/updates/0.4.0"), "ABCD\r\n");
+        assert!(script.contains("-Algorithm SHA256).Hash -ne 'ABCD'"));
+        assert!(script.contains("throw 'The download changed after it was verified'"));
+    }
+
+    #[test]
+    fn test_download_script_escapes_paths() {
+        let release = parse_release(&release_json(
+            r#"
+            {"name": "windns-v0.4.0-windows-x64.zip", "browser_download_url": "https://example.com/a.zip"},
+            {"name": "windns-v0.4.0-windows-x64.zip.sha256", "browser_download_url": "https://example.com/a.zip.sha256"}
+            "#,
+        ))
+        .unwrap();
+        let script = download_script(
+            &release,
+            Path::new("/tmp/O'Brien"),
+            Path::new("/tmp/O'Brien/updates/0.4.0"),
+            false,
+        );
+        assert!(script.contains("$root = '/tmp/O''Brien'"));
+        assert!(script.contains("$dir = '/tmp/O''Brien/updates/0.4.0'"));
+        assert!(script.contains("-Uri 'https://example.com/a.zip' -OutFile $archive"));
+        assert!(!script.contains("icacls"));
+
+        let script = download_script(
+            &release,
+            Path::new("C:\\ProgramData\\windns"),
+            Path::new("C:\\ProgramData\\windns\\updates\\0.4.0"),
+            true,
+        );
+        assert!(script.contains("icacls $root /inheritance:r /grant:r '*S-1-5-32-544:(OI)(CI)F'"));
+    }
+
+    #[test]
+    fn test_sha256_hex() {
+        assert_eq!(
+            sha256_hex(b"test"),
+            "9F86D081884C7D659A2FEAA0C55AD015A3BF4F1B2B0B822CD15D6C15B0F00A08"
+        );
+    }
+
+    #[test]
+    fn test_with_verified_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("windns.exe");
+        std::fs::write(&path, "test").unwrap();
+        let hash = sha256_hex(b"test");
+
+        let contents = with_verified_file(&path, &hash, |contents| Ok(contents.to_vec())).unwrap();
+        assert_eq!(contents, b"test");
+
+        std::fs::write(&path, "swapped").unwrap();
+        let mut used = false;
+        let result = with_verified_file(&path, &hash, |_| {
+            used = true;
+            Ok(())
+        });
+        assert!(matches!(result, Err(UpdateError::ChecksumMismatch)));
+        assert!(!used);
+    }
+
+    #[test]
+    fn test_old_executable_path() {
+        assert_eq!(
+            old_executable_path(Path::new("/opt/windns/windns.exe")),
+            PathBuf::from("/opt/windns/windns.exe.old")
+        );
+    }
+
+    #[test]
+    fn test_replace_executable() {
+        let dir = std::env::temp_dir().join(format!("windns-updater-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let exe = dir.join("windns.exe");
+        std::fs::write(&exe, "old").unwrap();
+
+        replace_executable(b"new", &exe).unwrap();
+        assert_eq!(std::fs::read_to_string(&exe).unwrap(), "new");
+        assert_eq!(
+            std::fs::read_to_string(old_executable_path(&exe)).unwrap(),
+            "old"
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,4 +1,4 @@
-use std::net::{Ipv4Addr, Ipv6Addr};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
 pub fn validate_ipv4(addr: &str) -> bool {
     if addr.trim().is_empty() {
@@ -7,22 +7,255 @@ pub fn validate_ipv4(addr: &str) -> bool {
     addr.parse::<Ipv4Addr>().is_ok()
 }
 
+/// Accepts a zone ID on link-local addresses, as in `fe80::1%12`, which
+/// some routers hand out as their resolver.
 pub fn validate_ipv6(addr: &str) -> bool {
     if addr.trim().is_empty() {
         return true;
     }
-    addr.parse::<Ipv6Addr>().is_ok()
+    parse_ipv6_with_zone(addr).is_some()
 }
 
+/// Parses an IPv6 address with an optional zone ID. Windows zone IDs are
+/// interface indexes, and only link-local addresses need one.
+pub fn parse_ipv6_with_zone(addr: &str) -> Option<(Ipv6Addr, Option<u32>)> {
+    match addr.split_once('%') {
+        Some((address, zone)) => {
+            let address: Ipv6Addr = address.parse().ok()?;
+            if zone.is_empty() || !zone.chars().all(|c| c.is_ascii_digit()) {
+                return None;
+            }
+            let zone: u32 = zone.parse().ok()?;
+            address
+                .is_unicast_link_local()
+                .then_some((address, Some(zone)))
+        }
+        None => Some((addr.parse().ok()?, None)),
+    }
+}
+
+/// The IP of a DNS server address, leaving out the zone ID of a link-local
+/// IPv6 one. Windows reports servers without it, as they are set per
+/// interface anyway.
+pub fn server_ip(addr: &str) -> Option<IpAddr> {
+    let addr = addr.trim();
+    addr.parse()
+        .ok()
+        .or_else(|| parse_ipv6_with_zone(addr).map(|(address, _)| IpAddr::V6(address)))
+}
+
+/// Whether two server addresses are the same IP, however they are written.
+pub(crate) fn same_address(a: &str, b: &str) -> bool {
+    match (server_ip(a), server_ip(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// A valid address that cannot be a DNS server elsewhere on the network,
+/// usually entered by mistake.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SuspiciousAddress {
+    /// Only answers if a DNS server runs on this machine.
+    Loopback,
+    Multicast,
+    Broadcast,
+    /// `0.0.0.0` or `::`.
+    Unspecified,
+}
+
+/// What makes `address` an unlikely DNS server, if anything.
+pub fn suspicious_address(address: &str) -> Option<SuspiciousAddress> {
+    let address: IpAddr = address.trim().parse().ok()?;
+    if address.is_unspecified() {
+        Some(SuspiciousAddress::Unspecified)
+    } else if address.is_loopback() {
+        Some(SuspiciousAddress::Loopback)
+    } else if address.is_multicast() {
+        Some(SuspiciousAddress::Multicast)
+    } else if address == IpAddr::V4(Ipv4Addr::BROADCAST) {
+        Some(SuspiciousAddress::Broadcast)
+    } else {
+        None
+    }
+}
+
+/// Something wrong or unusual in a DoH template.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DohTemplateIssue {
+    /// Not an `https://` URL.
+    NotHttps,
+    /// Nothing, or only a port, after the scheme.
+    MissingHost,
+    /// Whitespace, unbalanced braces, or an expression that is not an
+    /// RFC 6570 one.
+    Malformed,
+    /// Has expressions, but none of them is the `dns` variable the query
+    /// goes into.
+    NoDnsVariable,
+    /// A host name without a dot, which only resolves on a local network.
+    SingleLabelHost,
+}
+
+impl DohTemplateIssue {
+    /// Whether the template cannot work, rather than merely looking
+    /// unusual.
+    pub fn is_error(self) -> bool {
+        matches!(self, Self::NotHttps | Self::MissingHost | Self::Malformed)
+    }
+}
+
+/// Checks a DoH template, an RFC 6570 URI template with a `dns` variable
+/// (RFC 8484). A template without expressions, such as
+/// `https://cloudflare-dns.com/dns-query`, is fine: Windows adds the
+/// query itself.
+pub fn check_doh_template(template: &str) -> Vec<DohTemplateIssue> {
+    let template = template.trim();
+    if template.is_empty() {
+        return Vec::new();
+    }
+    let Some(rest) = template
+        .get(.."https://".len())
+        .filter(|scheme| scheme.eq_ignore_ascii_case("https://"))
+        .map(|scheme| &template[scheme.len()..])
+    else {
+        return vec![DohTemplateIssue::NotHttps];
+    };
+
+    let mut issues = Vec::new();
+    let authority = &rest[..rest.find(['/', '?', '#', '{']).unwrap_or(rest.len())];
+    match template_host(authority) {
+        None => issues.push(DohTemplateIssue::MissingHost),
+        Some(host) if !host.starts_with('[') && !host.contains('.') => {
+            issues.push(DohTemplateIssue::SingleLabelHost)
+        }
+        Some(_) => {}
+    }
+
+    match template_variables(rest) {
+        None => issues.push(DohTemplateIssue::Malformed),
+        Some(variables) if !variables.is_empty() && !variables.contains(&"dns") => {
+            issues.push(DohTemplateIssue::NoDnsVariable)
+        }
+        Some(_) => {}
+    }
+    issues.sort_by_key(|issue| !issue.is_error());
+    issues
+}
+
+/// Whether a DoH template can work, though it may still look unusual.
 pub fn validate_doh_template(template: &str) -> bool {
-    if template.trim().is_empty() {
+    !check_doh_template(template)
+        .iter()
+        .any(|issue| issue.is_error())
+}
+
+/// The host of a DoH template, e.g. `dns.example` for
+/// `https://dns.example/dns-query{?dns}`.
+pub fn doh_template_host(template: &str) -> Option<&str> {
+    let template = template.trim();
+    let rest = template
+        .get(.."https://".len())
+        .filter(|scheme| scheme.eq_ignore_ascii_case("https://"))
+        .map(|scheme| &template[scheme.len()..])?;
+    template_host(&rest[..rest.find(['/', '?', '#', '{']).unwrap_or(rest.len())])
+}
+
+/// The host of a URL authority such as `user@dns.example:8443`, or `None`
+/// if there is none or the port is not a number.
+fn template_host(authority: &str) -> Option<&str> {
+    let host_port = authority.rsplit_once('@').map_or(authority, |(_, h)| h);
+    let (host, port) = if host_port.starts_with('[') {
+        let end = host_port.find(']')?;
+        (&host_port[..=end], &host_port[end + 1..])
+    } else {
+        host_port.split_at(host_port.find(':').unwrap_or(host_port.len()))
+    };
+    let port_ok = port.is_empty()
+        || port
+            .strip_prefix(':')
+            .is_some_and(|p| !p.is_empty() && p.chars().all(|c| c.is_ascii_digit()));
+    (!host.is_empty() && port_ok).then_some(host)
+}
+
+/// Names of the variables in the expressions of a URI template, or `None`
+/// if the template is not well formed.
+fn template_variables(template: &str) -> Option<Vec<&str>> {
+    if template.chars().any(char::is_whitespace) {
+        return None;
+    }
+    let mut variables = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find(['{', '}']) {
+        if rest[start..].starts_with('}') {
+            return None;
+        }
+        let end = start + rest[start..].find('}')?;
+        let expression = &rest[start + 1..end];
+        let expression = expression
+            .strip_prefix(['+', '#', '.', '/', ';', '?', '&'])
+            .unwrap_or(expression);
+        for spec in expression.split(',') {
+            let name = match spec.split_once(':') {
+                Some((name, length)) => {
+                    let valid_length = !length.is_empty()
+                        && length.len() <= 4
+                        && length.chars().all(|c| c.is_ascii_digit());
+                    if !valid_length {
+                        return None;
+                    }
+                    name
+                }
+                None => spec.strip_suffix('*').unwrap_or(spec),
+            };
+            let valid_name = !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '%'));
+            if !valid_name {
+                return None;
+            }
+            variables.push(name);
+        }
+        rest = &rest[end + 1..];
+    }
+    Some(variables)
+}
+
+/// Checks a DNS suffix such as `corp.example.com`: dot-separated labels of
+/// letters, digits and hyphens that do not start or end with a hyphen.
+pub fn validate_dns_suffix(suffix: &str) -> bool {
+    if suffix.trim().is_empty() {
         return true;
     }
-    if !template.starts_with("https://") {
+    if suffix.len() > 253 {
         return false;
     }
-    let after_scheme = &template["https://".len()..];
-    !after_scheme.is_empty() && after_scheme.contains('.')
+    suffix.split('.').all(|label| {
+        !label.is_empty()
+            && label.len() <= 63
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    })
+}
+
+/// Whether `value` is a host name such as `dns.quad9.net` rather than an
+/// address: at least two labels, the last of which is not a number.
+pub fn looks_like_host_name(value: &str) -> bool {
+    let value = value.trim().trim_end_matches('.');
+    value.contains('.')
+        && value.parse::<std::net::IpAddr>().is_err()
+        && validate_dns_suffix(value)
+        && value
+            .rsplit('.')
+            .next()
+            .is_some_and(|tld| !tld.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Checks a color written as `#rrggbb`.
+pub fn validate_color(color: &str) -> bool {
+    color.len() == 7 && color.starts_with('#') && color[1..].chars().all(|c| c.is_ascii_hexdigit())
 }
 
 #[cfg(test)]
@@ -48,6 +281,68 @@ mod tests {
         assert!(validate_ipv6("::1"));
         assert!(!validate_ipv6("8.8.8.8"));
         assert!(!validate_ipv6("invalid"));
+        assert!(validate_ipv6("fe80::1%12"));
+        assert!(!validate_ipv6("fe80::1%"));
+        assert!(!validate_ipv6("fe80::1%eth0"));
+        assert!(!validate_ipv6("fe80::1%+12"));
+        assert!(!validate_ipv6("2001:4860:4860::8888%12"));
+    }
+
+    #[test]
+    fn test_parse_ipv6_with_zone() {
+        let link_local: Ipv6Addr = "fe80::1".parse().unwrap();
+        assert_eq!(
+            parse_ipv6_with_zone("fe80::1%12"),
+            Some((link_local, Some(12)))
+        );
+        assert_eq!(parse_ipv6_with_zone("fe80::1"), Some((link_local, None)));
+        assert_eq!(parse_ipv6_with_zone("fe80::1%99999999999"), None);
+
+        assert_eq!(server_ip(" fe80::1%12 "), Some(IpAddr::V6(link_local)));
+        assert_eq!(
+            server_ip("9.9.9.9"),
+            Some(IpAddr::V4(Ipv4Addr::new(9, 9, 9, 9)))
+        );
+        assert_eq!(server_ip("9.9.9.9%12"), None);
+    }
+
+    #[test]
+    fn test_same_address_compares_parsed_ips() {
+        assert!(same_address("2001:db8::1", "2001:0db8:0:0::1"));
+        assert!(!same_address("1.1.1.1", "1.0.0.1"));
+        assert!(same_address("fe80::1%12", "fe80::1"));
+    }
+
+    #[test]
+    fn test_suspicious_address() {
+        assert_eq!(suspicious_address("8.8.8.8"), None);
+        assert_eq!(suspicious_address("2001:4860:4860::8888"), None);
+        assert_eq!(suspicious_address("invalid"), None);
+        assert_eq!(
+            suspicious_address("127.0.0.1"),
+            Some(SuspiciousAddress::Loopback)
+        );
+        assert_eq!(suspicious_address("::1"), Some(SuspiciousAddress::Loopback));
+        assert_eq!(
+            suspicious_address("224.0.0.251"),
+            Some(SuspiciousAddress::Multicast)
+        );
+        assert_eq!(
+            suspicious_address("ff02::fb"),
+            Some(SuspiciousAddress::Multicast)
+        );
+        assert_eq!(
+            suspicious_address("255.255.255.255"),
+            Some(SuspiciousAddress::Broadcast)
+        );
+        assert_eq!(
+            suspicious_address(" 0.0.0.0 "),
+            Some(SuspiciousAddress::Unspecified)
+        );
+        assert_eq!(
+            suspicious_address("::"),
+            Some(SuspiciousAddress::Unspecified)
+        );
     }
 
     #[test]
@@ -61,6 +356,124 @@ mod tests {
         assert!(!validate_doh_template("http://dns.google/dns-query"));
         assert!(!validate_doh_template("invalid"));
         assert!(!validate_doh_template("https://"));
-        assert!(!validate_doh_template("https://nodot"));
+        // Unusual, but an intranet resolver may well be reached like this.
+        assert!(validate_doh_template("https://nodot"));
+    }
+
+    #[test]
+    fn test_check_doh_template_accepts_provider_templates() {
+        for template in [
+            "https://cloudflare-dns.com/dns-query",
+            "https://security.cloudflare-dns.com/dns-query",
+            "https://dns.google/dns-query",
+            "https://dns.google/dns-query{?dns}",
+            "https://dns.quad9.net/dns-query",
+            "https://doh.opendns.com/dns-query",
+            "https://dns.adguard-dns.com/dns-query",
+            "https://dns.mullvad.net/dns-query",
+            "https://dns.nextdns.io/abc123",
+            "https://doh.example:8443/dns-query{?dns}",
+            "https://[2606:4700:4700::1111]/dns-query",
+            "HTTPS://dns.google/dns-query",
+        ] {
+            assert_eq!(check_doh_template(template), vec![], "{}", template);
+        }
+    }
+
+    #[test]
+    fn test_check_doh_template_issues() {
+        use DohTemplateIssue::*;
+
+        assert_eq!(
+            check_doh_template("http://dns.google/dns-query"),
+            vec![NotHttps]
+        );
+        assert_eq!(check_doh_template("https://"), vec![MissingHost]);
+        assert_eq!(
+            check_doh_template("https://:443/dns-query"),
+            vec![MissingHost]
+        );
+        assert_eq!(
+            check_doh_template("https://dns.example:abc/"),
+            vec![MissingHost]
+        );
+        assert_eq!(
+            check_doh_template("https://dns.example/dns query"),
+            vec![Malformed]
+        );
+        assert_eq!(
+            check_doh_template("https://dns.example/dns-query{?dns"),
+            vec![Malformed]
+        );
+        assert_eq!(
+            check_doh_template("https://dns.example/dns-query}"),
+            vec![Malformed]
+        );
+        assert_eq!(
+            check_doh_template("https://dns.example/q{?dns-name}"),
+            vec![Malformed]
+        );
+        assert_eq!(
+            check_doh_template("https://dns.example/q{?query}"),
+            vec![NoDnsVariable]
+        );
+        assert_eq!(check_doh_template("https://dns.example/q{?ct,dns}"), vec![]);
+        assert_eq!(
+            check_doh_template("https://doh-server/dns-query"),
+            vec![SingleLabelHost]
+        );
+        assert_eq!(
+            check_doh_template("https://doh-server/q{?dns"),
+            vec![Malformed, SingleLabelHost]
+        );
+        assert!(NotHttps.is_error() && !SingleLabelHost.is_error());
+    }
+
+    #[test]
+    fn test_validate_dns_suffix() {
+        assert!(validate_dns_suffix(""));
+        assert!(validate_dns_suffix("corp.example.com"));
+        assert!(validate_dns_suffix("local"));
+        assert!(validate_dns_suffix("my-office.example"));
+        assert!(!validate_dns_suffix("corp..example.com"));
+        assert!(!validate_dns_suffix(".example.com"));
+        assert!(!validate_dns_suffix("-corp.example.com"));
+        assert!(!validate_dns_suffix("corp example.com"));
+        assert!(!validate_dns_suffix(&"a".repeat(64)));
+    }
+
+    #[test]
+    fn test_doh_template_host() {
+        assert_eq!(
+            doh_template_host(" https://dns.example/dns-query{?dns}"),
+            Some("dns.example")
+        );
+        assert_eq!(
+            doh_template_host("https://user@dns.example:8443"),
+            Some("dns.example")
+        );
+        assert_eq!(doh_template_host("http://dns.example/dns-query"), None);
+    }
+
+    #[test]
+    fn test_looks_like_host_name() {
+        assert!(looks_like_host_name("dns.quad9.net"));
+        assert!(looks_like_host_name(" one.one.one.one. "));
+        assert!(!looks_like_host_name("localhost"));
+        assert!(!looks_like_host_name("9.9.9.9"));
+        assert!(!looks_like_host_name("9.9.9"));
+        assert!(!looks_like_host_name("2620:fe::fe"));
+        assert!(!looks_like_host_name("dns quad9.net"));
+        assert!(!looks_like_host_name(""));
+    }
+
+    #[test]
+    fn test_validate_color() {
+        assert!(validate_color("#4fc3f7"));
+        assert!(validate_color("#C62828"));
+        assert!(!validate_color(""));
+        assert!(!validate_color("4fc3f7"));
+        assert!(!validate_color("#fff"));
+        assert!(!validate_color("#4fc3g7"));
     }
 }
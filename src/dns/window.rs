@@ -4,12 +4,29 @@ use dioxus::desktop::tao::window::Window;
 
 /// Capture current window state.
 /// Position is stored in physical pixels, size in logical pixels.
-/// Returns None if position cannot be determined (e.g., minimized).
-pub fn capture_window_state(window: &Window) -> Option<WindowState> {
+/// While the window is maximized, the bounds are taken from `restored`,
+/// the last state captured while it was neither maximized nor minimized,
+/// so that un-maximizing after a restart returns to them. While it is
+/// minimized, `restored` is returned as is.
+/// Returns None if position cannot be determined.
+pub fn capture_window_state(
+    window: &Window,
+    restored: Option<&WindowState>,
+) -> Option<WindowState> {
+    if window.is_minimized() {
+        return restored.cloned();
+    }
+    let maximized = window.is_maximized();
+    if maximized && let Some(restored) = restored {
+        return Some(WindowState {
+            maximized: true,
+            ..restored.clone()
+        });
+    }
+
     let scale = window.scale_factor();
     let position = window.outer_position().ok()?;
     let size = window.inner_size().to_logical::<u32>(scale);
-    let maximized = window.is_maximized();
 
     Some(WindowState {
         x: position.x,
@@ -76,7 +93,7 @@ pub fn validate_window_state(
                 y,
                 width,
                 height,
-                maximized: false,
+                maximized: state.maximized,
             }
         } else {
             WindowState::default()
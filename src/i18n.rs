@@ -0,0 +1,222 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// Language of the user interface.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Debug, Default)]
+pub enum Language {
+    #[default]
+    English,
+    Japanese,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::Japanese];
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::Japanese => "ja",
+        }
+    }
+
+    /// Name of the language in the language itself, for the picker.
+    pub fn native_name(&self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::Japanese => "日本語",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|language| language.code() == code)
+    }
+
+    /// Language of the Windows display, or English if windns has no
+    /// translation for it.
+    #[cfg(target_os = "windows")]
+    pub fn detect() -> Self {
+        use windows::Win32::Globalization::GetUserDefaultUILanguage;
+
+        // The low 10 bits of a LANGID are the primary language.
+        const LANG_JAPANESE: u16 = 0x11;
+        match unsafe { GetUserDefaultUILanguage() } & 0x3ff {
+            LANG_JAPANESE => Language::Japanese,
+            _ => Language::English,
+        }
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    pub fn detect() -> Self {
+        Language::English
+    }
+
+    fn source(&self) -> &'static str {
+        match self {
+            Language::English => include_str!("../locales/en.ftl"),
+            Language::Japanese => include_str!("../locales/ja.ftl"),
+        }
+    }
+
+    fn messages(&self) -> &'static HashMap<&'static str, &'static str> {
+        static ENGLISH: OnceLock<HashMap<&str, &str>> = OnceLock::new();
+        static JAPANESE: OnceLock<HashMap<&str, &str>> = OnceLock::new();
+        let cell = match self {
+            Language::English => &ENGLISH,
+            Language::Japanese => &JAPANESE,
+        };
+        cell.get_or_init(|| parse_messages(self.source()))
+    }
+}
+
+static LANGUAGE: AtomicU8 = AtomicU8::new(0);
+
+/// Sets the language that `t!` translates into.
+pub fn set_language(language: Language) {
+    LANGUAGE.store(language as u8, Ordering::Relaxed);
+}
+
+pub fn current_language() -> Language {
+    match LANGUAGE.load(Ordering::Relaxed) {
+        1 => Language::Japanese,
+        _ => Language::English,
+    }
+}
+
+/// Reads `key = value` lines, skipping blank lines and `#` comments.
+fn parse_messages(source: &str) -> HashMap<&str, &str> {
+    source
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim(), value.trim()))
+        .collect()
+}
+
+/// Replaces each `{ $name }` in `pattern` with its argument. Placeables
+/// without an argument are left as they are.
+fn format_message(pattern: &str, args: &[(&str, String)]) -> String {
+    let mut result = String::with_capacity(pattern.len());
+    let mut rest = pattern;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        result.push_str(&rest[..start]);
+        let placeable = &rest[start..start + len + 1];
+        let name = placeable[1..len].trim().trim_start_matches('$');
+        match args.iter().find(|(arg, _)| *arg == name) {
+            Some((_, value)) => result.push_str(value),
+            None => result.push_str(placeable),
+        }
+        rest = &rest[start + len + 1..];
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Translates `key` into `language`, falling back to English and then to
+/// the key itself.
+pub fn translate(language: Language, key: &str, args: &[(&str, String)]) -> String {
+    let pattern = language
+        .messages()
+        .get(key)
+        .or_else(|| Language::English.messages().get(key))
+        .copied()
+        .unwrap_or(key);
+    format_message(pattern, args)
+}
+
+/// Translates a message into the current language, e.g.
+/// `t!("profile-saved", name = profile.name)`.
+macro_rules! t {
+    ($key:expr) => {
+        $crate::i18n::translate($crate::i18n::current_language(), $key, &[])
+    };
+    ($key:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::translate(
+            $crate::i18n::current_language(),
+            $key,
+            &[$((stringify!($name), ($value).to_string())),+],
+        )
+    };
+}
+pub(crate) use t;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn placeables(pattern: &str) -> Vec<&str> {
+        let mut names: Vec<&str> = pattern
+            .split('{')
+            .skip(1)
+            .filter_map(|part| part.split_once('}'))
+            .map(|(name, _)| name.trim())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_format_message() {
+        let args = [("name", "Home".to_string()), ("count", 3.to_string())];
+        assert_eq!(
+            format_message("Applied { $name } to {$count} interfaces", &args),
+            "Applied Home to 3 interfaces"
+        );
+        assert_eq!(
+            format_message("Missing { $other }", &args),
+            "Missing { $other }"
+        );
+        assert_eq!(
+            format_message("Unclosed { $name", &args),
+            "Unclosed { $name"
+        );
+    }
+
+    #[test]
+    fn test_translate_falls_back() {
+        assert_eq!(
+            translate(Language::English, "no-such-key", &[]),
+            "no-such-key"
+        );
+        assert_eq!(
+            translate(Language::Japanese, "language", &[]),
+            Language::Japanese.messages()["language"]
+        );
+    }
+
+    #[test]
+    fn test_catalogs_have_the_same_messages() {
+        let english = Language::English.messages();
+        for language in Language::ALL {
+            let messages = language.messages();
+            for (key, pattern) in english {
+                let translated = messages
+                    .get(key)
+                    .unwrap_or_else(|| panic!("{} is missing {}", language.code(), key));
+                assert_eq!(
+                    placeables(translated),
+                    placeables(pattern),
+                    "{} has other placeables in {}",
+                    language.code(),
+                    key
+                );
+            }
+            assert_eq!(messages.len(), english.len(), "{}", language.code());
+        }
+    }
+
+    #[test]
+    fn test_language_codes() {
+        for language in Language::ALL {
+            assert_eq!(Language::from_code(language.code()), Some(language));
+        }
+        assert_eq!(Language::from_code("fr"), None);
+    }
+}
@@ -1,12 +1,19 @@
 mod app;
+mod cli;
 mod components;
 mod dns;
+mod i18n;
 mod state;
+mod tray;
 
 use dioxus::desktop::tao::dpi::{LogicalSize, PhysicalPosition};
 use dioxus::desktop::tao::window::Icon;
 use dioxus::desktop::{Config, WindowBuilder};
-use dns::{WindowState, load_config, validate_window_state};
+use dns::{
+    WindowState, cli_command, config_dir_argument, enable_verbose_trace, forward_link,
+    launched_minimized, link_argument, load_config, remove_old_executable, reset_all_argument,
+    run_reset_all, set_config_dir_argument, validate_window_state, verbose_argument,
+};
 
 fn load_icon() -> Option<Icon> {
     let icon_bytes = include_bytes!("../icons/icon.png");
@@ -16,6 +23,19 @@ fn load_icon() -> Option<Icon> {
 }
 
 fn main() {
+    // A link opened while windns is running is applied by that instance.
+    if let Some(link) = link_argument(std::env::args().skip(1))
+        && forward_link(&link)
+    {
+        return;
+    }
+    remove_old_executable();
+    if let Some(dir) = config_dir_argument(std::env::args().skip(1)) {
+        set_config_dir_argument(dir);
+    }
+    if verbose_argument(std::env::args().skip(1)) {
+        enable_verbose_trace();
+    }
     let config = match load_config() {
         Ok(c) => c,
         Err(e) => {
@@ -23,7 +43,15 @@ fn main() {
             Default::default()
         }
     };
+    i18n::set_language(config.settings.language());
+    if reset_all_argument(std::env::args().skip(1)) {
+        std::process::exit(run_reset_all(config));
+    }
+    if let Some(command) = cli_command(std::env::args().skip(1)) {
+        std::process::exit(cli::run_cli(command, config));
+    }
     let saved_state = config.window.clone().unwrap_or_default();
+    let start_minimized = launched_minimized(std::env::args().skip(1));
 
     let initial_width = saved_state.width.max(WindowState::MIN_WIDTH);
     let initial_height = saved_state.height.max(WindowState::MIN_HEIGHT);
@@ -69,6 +97,10 @@ fn main() {
                         if validated.maximized {
                             window.set_maximized(true);
                         }
+
+                        if start_minimized {
+                            window.set_minimized(true);
+                        }
                     }
                 }),
         )
@@ -1,20 +1,162 @@
 use crate::dns::{
-    AppConfig, CurrentDnsState, DnsMode, DnsProfile, DnsSettings, DohMode, NetworkInterface,
+    ActiveNrptRule, AddressFamily, AppConfig, Capabilities, CommandQueue, ConfigBackup,
+    ConfigFormat, ConfigLocation, ControlProfile, ControlStatus, CurrentDnsState, DnsBackend,
+    DnsEntry, DnsMode, DnsPolicy, DnsProfile, DnsProvider, DnsServerEntry, DnsSettings, DohMode,
+    DohTemplateIssue, HistoryEntry, InterfaceDetails, LeakTestResult, LookupQuery, LookupResponse,
+    MAX_HISTORY_ENTRIES, NetworkInfo, NetworkInterface, NetworkRules, NrptSettings, PreviewStep,
+    ProviderDraft, RelaunchSelection, Release, Schedule, StartupSetting, SuspiciousAddress,
+    SystemBackend, TemporaryApply, TimeOfDay, UpdatePackage, find_provider, local_time,
+    overriding_policies, parse_tags, providers, providers_from_doh_servers, suspicious_address,
 };
+use crate::i18n::t;
+use std::collections::{BTreeMap, VecDeque};
+use std::net::IpAddr;
+use std::time::Duration;
+
+/// What the current DNS servers of an interface correspond to.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DnsMatch<'a> {
+    /// The servers are those of this profile.
+    Profile(&'a DnsProfile),
+    /// The servers come from DHCP, or none are set.
+    Automatic,
+    /// Static servers that no profile has.
+    Custom,
+}
+
+/// Progress of updating windns from the settings.
+#[derive(Clone, PartialEq, Debug)]
+pub enum UpdateStatus {
+    Checking,
+    UpToDate,
+    Available(Release),
+    Downloading(Release),
+    /// Downloaded and verified, installed when windns restarts.
+    Ready(Release, UpdatePackage),
+    /// Checking or downloading failed, for this reason.
+    Failed(String),
+}
+
+/// Addresses a host name typed in as a DNS server resolved to, offered to
+/// take its place.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ResolvedHost {
+    pub family: AddressFamily,
+    pub index: usize,
+    pub host: String,
+    pub addresses: Vec<IpAddr>,
+}
 
 #[derive(Clone, Debug)]
 pub struct AppState {
     pub interfaces: Vec<NetworkInterface>,
-    pub selected_interface_index: usize,
+    /// GUID of the selected adapter, so the selection survives adapters
+    /// being added or removed.
+    pub selected_interface_guid: Option<String>,
+    /// Bumped whenever another interface is selected, so that a task
+    /// started for the previous one can tell that its result is stale.
+    selection_generation: u64,
     pub dns_mode: DnsMode,
     pub selected_profile_id: Option<String>,
     pub current_settings: DnsSettings,
     pub current_profile_name: String,
+    /// Tags of the selected profile as typed, separated by commas.
+    pub current_profile_tags: String,
+    /// Tag the profile list is narrowed to, if any.
+    pub profile_tag_filter: Option<String>,
+    /// Text typed to narrow the profile list by name or tag.
+    pub profile_search: String,
+    /// Accent color of the selected profile, as `#rrggbb`.
+    pub current_profile_color: Option<String>,
     pub current_dns_state: CurrentDnsState,
+    /// Addresses and hardware of the selected interface.
+    pub interface_details: Option<InterfaceDetails>,
     pub config: AppConfig,
-    pub message: Option<Message>,
+    /// Messages shown so far, oldest first, for the activity log.
+    pub message_log: VecDeque<LogEntry>,
+    /// DNS changes windns made, oldest first, for the History view.
+    pub history: VecDeque<HistoryEntry>,
+    /// The change a network rule made last, which its notification and the
+    /// tray menu can undo.
+    pub rule_undo: Option<HistoryEntry>,
+    /// Whether the latest message is still shown in the status bar.
+    pub showing_message: bool,
     pub is_loading: bool,
     pub show_delete_confirm: bool,
+    /// Asks before switching to Automatic would drop unsaved profile edits.
+    pub show_discard_confirm: bool,
+    pub show_settings: bool,
+    pub backend: SystemBackend,
+    pub capabilities: Capabilities,
+    /// DoH templates Windows already knows, by server address.
+    pub known_doh_templates: BTreeMap<IpAddr, String>,
+    /// Addresses of the host name last resolved from a server field.
+    pub resolved_host: Option<ResolvedHost>,
+    pub is_elevated: bool,
+    /// Group Policy settings that manage DNS on this machine.
+    pub dns_policies: Vec<DnsPolicy>,
+    /// NRPT rules currently configured on the system.
+    pub nrpt_rules: Vec<ActiveNrptRule>,
+    pub preview_steps: Option<Vec<PreviewStep>>,
+    /// Catalog provider and variant the current settings were last filled
+    /// from, as `(provider id, variant id)`.
+    pub selected_provider: Option<(String, String)>,
+    /// User-defined provider being added or edited, if the form is open.
+    pub provider_draft: Option<ProviderDraft>,
+    /// Result of the last DNS leak test, cleared when settings are applied.
+    pub leak_test: Option<LeakTestResult>,
+    pub is_leak_testing: bool,
+    /// Where checking for and installing an update has got to, if started.
+    pub update_status: Option<UpdateStatus>,
+    /// Outcome of the name resolution check run after the last apply.
+    pub resolution_check: Option<Message>,
+    /// Outcome of the last background check that names resolve and HTTPS
+    /// hosts can be reached.
+    pub health: Option<Message>,
+    /// Round-trip time of each DNS server in use, `None` if it did not
+    /// answer, from the last measurement.
+    pub server_latency: BTreeMap<String, Option<Duration>>,
+    /// DNSSEC check results, keyed by the DNS server that was checked.
+    pub dnssec_results: BTreeMap<String, Message>,
+    pub is_checking_dnssec: bool,
+    pub lookup_query: LookupQuery,
+    /// Answer to the last lookup, or why it failed.
+    pub lookup_result: Option<Result<LookupResponse, String>>,
+    pub is_looking_up: bool,
+    /// Network each interface was last seen on, keyed by interface GUID.
+    pub networks: BTreeMap<String, NetworkInfo>,
+    /// Network rules being edited, until they are saved.
+    pub network_rules_draft: Option<NetworkRules>,
+    /// Schedule being edited, until it is saved.
+    pub schedule_draft: Option<Schedule>,
+    /// Whether windns is registered to run at logon.
+    pub startup: StartupSetting,
+    /// Whether `windns://` links are registered to open windns.
+    pub link_handler: bool,
+    /// Profile the logon task applies, `Some(None)` when there is no task
+    /// and `None` until the task has been looked up.
+    pub logon_task: Option<Option<String>>,
+    /// Whether Windows is in dark mode, for the System theme.
+    pub system_dark_mode: bool,
+    /// Config backups listed in the restore dialog, while it is open.
+    pub config_backups: Option<Vec<ConfigBackup>>,
+    /// Format of the config file on disk.
+    pub config_format: ConfigFormat,
+    /// Directory of the config file, once it has been looked up.
+    pub config_location: Option<ConfigLocation>,
+    /// Serializes the DNS operations on each interface.
+    pub command_queue: CommandQueue,
+}
+
+/// How many messages the activity log keeps.
+const MESSAGE_LOG_LIMIT: usize = 100;
+
+/// A message in the activity log.
+#[derive(Clone, Debug)]
+pub struct LogEntry {
+    /// Local time the message was shown.
+    pub time: TimeOfDay,
+    pub message: Message,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -57,53 +199,560 @@ impl AppState {
     pub fn new() -> Self {
         Self {
             interfaces: Vec::new(),
-            selected_interface_index: 0,
+            selected_interface_guid: None,
+            selection_generation: 0,
             dns_mode: DnsMode::Automatic,
             selected_profile_id: None,
             current_settings: DnsSettings::new(),
             current_profile_name: String::new(),
+            current_profile_tags: String::new(),
+            profile_tag_filter: None,
+            profile_search: String::new(),
+            current_profile_color: None,
             current_dns_state: CurrentDnsState::new(),
+            interface_details: None,
             config: AppConfig::new(),
-            message: None,
+            message_log: VecDeque::new(),
+            history: VecDeque::new(),
+            rule_undo: None,
+            showing_message: false,
             is_loading: false,
             show_delete_confirm: false,
+            show_discard_confirm: false,
+            show_settings: false,
+            backend: SystemBackend::default(),
+            capabilities: Capabilities::default(),
+            is_elevated: true,
+            dns_policies: Vec::new(),
+            nrpt_rules: Vec::new(),
+            preview_steps: None,
+            selected_provider: None,
+            provider_draft: None,
+            leak_test: None,
+            is_leak_testing: false,
+            update_status: None,
+            resolution_check: None,
+            health: None,
+            server_latency: BTreeMap::new(),
+            known_doh_templates: BTreeMap::new(),
+            resolved_host: None,
+            dnssec_results: BTreeMap::new(),
+            is_checking_dnssec: false,
+            lookup_query: LookupQuery::default(),
+            lookup_result: None,
+            is_looking_up: false,
+            networks: BTreeMap::new(),
+            network_rules_draft: None,
+            schedule_draft: None,
+            startup: StartupSetting::default(),
+            link_handler: false,
+            logon_task: None,
+            system_dark_mode: true,
+            config_backups: None,
+            config_format: ConfigFormat::default(),
+            config_location: None,
+            command_queue: CommandQueue::new(),
         }
     }
 
+    pub fn is_dark_theme(&self) -> bool {
+        self.config.settings.theme().is_dark(self.system_dark_mode)
+    }
+
     pub fn selected_interface(&self) -> Option<&NetworkInterface> {
-        self.interfaces.get(self.selected_interface_index)
+        let guid = self.selected_interface_guid.as_ref()?;
+        self.interfaces.iter().find(|i| &i.interface_guid == guid)
+    }
+
+    /// Selects the adapter with `guid`. Returns false if it is not in the
+    /// interface list.
+    pub fn select_interface(&mut self, guid: &str) -> bool {
+        if self.interfaces.iter().any(|i| i.interface_guid == guid) {
+            self.set_selected_interface(Some(guid.to_string()));
+            true
+        } else {
+            false
+        }
+    }
+
+    fn set_selected_interface(&mut self, guid: Option<String>) {
+        if self.selected_interface_guid != guid {
+            self.selected_interface_guid = guid;
+            self.selection_generation += 1;
+        }
+    }
+
+    /// Tag for the current interface selection, to hand to
+    /// `is_current_selection` once a task is done.
+    pub fn selection_generation(&self) -> u64 {
+        self.selection_generation
+    }
+
+    /// Whether the interface selected when `selection_generation` returned
+    /// `generation` has stayed selected since.
+    pub fn is_current_selection(&self, generation: u64) -> bool {
+        self.selection_generation == generation
+    }
+
+    /// Replaces the interface list, keeping the selected adapter if it is
+    /// still present and falling back to the first connected one otherwise.
+    /// Returns false when a previously selected adapter has disappeared.
+    pub fn set_interfaces(&mut self, interfaces: Vec<NetworkInterface>) -> bool {
+        self.interfaces = interfaces;
+        if self.selected_interface().is_some() {
+            return true;
+        }
+
+        let had_selection = self.selected_interface_guid.is_some();
+        let fallback = self
+            .interfaces
+            .iter()
+            .find(|i| !self.is_interface_hidden(i) && !i.is_disconnected)
+            .or_else(|| {
+                self.interfaces
+                    .iter()
+                    .find(|i| !self.is_interface_hidden(i))
+            })
+            .or_else(|| self.interfaces.first())
+            .map(|i| i.interface_guid.clone());
+        self.set_selected_interface(fallback);
+        !had_selection
+    }
+
+    /// Whether `interface` is left out of the list unless all adapters are
+    /// shown: a virtual adapter, or one the user has hidden.
+    pub fn is_interface_hidden(&self, interface: &NetworkInterface) -> bool {
+        interface.is_virtual
+            || self
+                .config
+                .hidden_interfaces
+                .contains(&interface.interface_guid)
+    }
+
+    /// Adapters to list in the interface picker. The selected one is always
+    /// included.
+    pub fn visible_interfaces(&self) -> Vec<&NetworkInterface> {
+        let show_all = self.config.settings.shows_all_interfaces();
+        self.interfaces
+            .iter()
+            .filter(|i| {
+                show_all
+                    || !self.is_interface_hidden(i)
+                    || self.selected_interface_guid.as_ref() == Some(&i.interface_guid)
+            })
+            .collect()
+    }
+
+    /// A connected VPN adapter other than the selected one, whose DNS
+    /// servers are likely to be used instead of those of the selected
+    /// adapter.
+    pub fn active_vpn(&self) -> Option<&NetworkInterface> {
+        if self.selected_interface().is_none_or(|i| i.is_vpn) {
+            return None;
+        }
+        self.interfaces
+            .iter()
+            .find(|i| i.is_vpn && !i.is_disconnected)
     }
 
+    /// Names the adapter with `guid`, or goes back to its Windows name when
+    /// `alias` is blank.
+    pub fn set_interface_alias(&mut self, guid: &str, alias: &str) {
+        let alias = alias.trim();
+        if alias.is_empty() {
+            self.config.interface_aliases.remove(guid);
+        } else {
+            self.config
+                .interface_aliases
+                .insert(guid.to_string(), alias.to_string());
+        }
+    }
+
+    /// Adds the adapter with `guid` to, or removes it from, the adapters
+    /// the user has hidden.
+    pub fn set_interface_hidden(&mut self, guid: &str, hidden: bool) {
+        let list = &mut self.config.hidden_interfaces;
+        list.retain(|g| g != guid);
+        if hidden {
+            list.push(guid.to_string());
+        }
+    }
+
+    /// Records DNS servers read in the background. Returns true when they
+    /// differ from the last known state, i.e. something outside windns has
+    /// changed them.
+    pub fn observe_current_dns(&mut self, dns_state: CurrentDnsState) -> bool {
+        if dns_state == self.current_dns_state {
+            return false;
+        }
+        self.current_dns_state = dns_state;
+        true
+    }
+
+    /// The message shown in the status bar, if any.
+    pub fn message(&self) -> Option<&Message> {
+        self.message_log
+            .back()
+            .filter(|_| self.showing_message)
+            .map(|entry| &entry.message)
+    }
+
+    /// Shows `message` in the status bar and adds it to the activity log.
     pub fn set_message(&mut self, message: Message) {
-        self.message = Some(message);
+        if self.message_log.len() == MESSAGE_LOG_LIMIT {
+            self.message_log.pop_front();
+        }
+        self.message_log.push_back(LogEntry {
+            time: local_time().1,
+            message,
+        });
+        self.showing_message = true;
     }
 
+    /// Hides the message from the status bar. It stays in the log.
     pub fn clear_message(&mut self) {
-        self.message = None;
+        self.showing_message = false;
+    }
+
+    /// Adds a change to the History view, dropping the oldest one past
+    /// `MAX_HISTORY_ENTRIES`. A later change to the interface a network
+    /// rule changed last can no longer be undone from its notification.
+    pub fn add_history(&mut self, entry: HistoryEntry) {
+        if self
+            .rule_undo
+            .as_ref()
+            .is_some_and(|undo| undo.interface_guid == entry.interface_guid)
+        {
+            self.rule_undo = None;
+        }
+        if self.history.len() == MAX_HISTORY_ENTRIES {
+            self.history.pop_front();
+        }
+        self.history.push_back(entry);
+    }
+
+    pub fn clear_message_log(&mut self) {
+        self.message_log.clear();
+        self.showing_message = false;
     }
 
     pub fn set_loading(&mut self, loading: bool) {
         self.is_loading = loading;
     }
 
+    /// DNS settings the selected interface had before windns first changed
+    /// them.
+    pub fn original_dns(&self) -> Option<&DnsSettings> {
+        let interface = self.selected_interface()?;
+        self.config.original_dns.get(&interface.interface_guid)
+    }
+
+    /// DNS server the DNSSEC check looks at: the primary server of the
+    /// selected profile in Manual mode, or the server in use otherwise.
+    pub fn dnssec_server(&self) -> Option<String> {
+        if self.dns_mode == DnsMode::Manual {
+            [&self.current_settings.ipv4, &self.current_settings.ipv6]
+                .into_iter()
+                .filter(|entry| entry.enabled)
+                .flat_map(|entry| entry.servers.first())
+                .map(|server| server.address.trim())
+                .find(|address| !address.is_empty())
+                .map(str::to_string)
+        } else {
+            let current = &self.current_dns_state;
+            current.ipv4.iter().chain(&current.ipv6).next().cloned()
+        }
+    }
+
+    /// DNS server the lookup tool asks: the one typed in, or the first server
+    /// in use when left blank.
+    pub fn lookup_server(&self) -> Option<String> {
+        let server = self.lookup_query.server.trim();
+        if !server.is_empty() {
+            return Some(server.to_string());
+        }
+        let current = &self.current_dns_state;
+        current.ipv4.iter().chain(&current.ipv6).next().cloned()
+    }
+
+    /// Why DNS settings cannot be changed, if windns is running without
+    /// administrator rights. Everything else keeps working.
+    pub fn read_only_reason(&self) -> Option<String> {
+        (!self.is_elevated).then(|| t!("read-only-reason"))
+    }
+
+    /// Warning naming the Group Policy settings that manage DNS here, if
+    /// any do.
+    pub fn policy_warning(&self) -> Option<String> {
+        (!self.dns_policies.is_empty()).then(|| {
+            t!(
+                "dns-policy-managed",
+                policies = describe_policies(self.dns_policies.iter())
+            )
+        })
+    }
+
+    /// Warning naming the Group Policy settings that will keep `settings`
+    /// from taking effect, if any will.
+    pub fn policy_override_warning(&self, settings: &DnsSettings) -> Option<String> {
+        let policies = overriding_policies(&self.dns_policies, settings);
+        (!policies.is_empty()).then(|| {
+            t!(
+                "dns-policy-overrides",
+                policies = describe_policies(policies.into_iter())
+            )
+        })
+    }
+
+    /// Why DoH cannot be configured on this machine, if it cannot.
+    pub fn doh_unavailable_reason(&self) -> Option<String> {
+        if !self.capabilities.doh {
+            Some(t!("doh-needs-windows-11"))
+        } else if !self.backend.supports_doh() {
+            Some(t!("doh-needs-powershell"))
+        } else {
+            None
+        }
+    }
+
+    /// The selection to restore after restarting as administrator.
+    pub fn relaunch_selection(&self) -> RelaunchSelection {
+        RelaunchSelection {
+            interface_guid: self.selected_interface().map(|i| i.interface_guid.clone()),
+            profile_id: self.selected_profile_id.clone(),
+            dns_mode: Some(self.dns_mode),
+        }
+    }
+
+    /// Restores a selection made before restarting. Interfaces and profiles
+    /// that no longer exist are skipped.
+    pub fn restore_selection(&mut self, selection: &RelaunchSelection) {
+        if let Some(guid) = &selection.interface_guid {
+            self.select_interface(guid);
+        }
+        if let Some(id) = &selection.profile_id {
+            self.select_profile(id);
+        }
+        if let Some(mode) = selection.dns_mode
+            && (mode == DnsMode::Automatic || self.selected_profile_id.is_some())
+        {
+            self.dns_mode = mode;
+        }
+    }
+
+    /// Records the mode and profile just applied to the selected interface.
+    pub fn remember_interface_profile(&mut self) {
+        let Some(guid) = self.selected_interface().map(|i| i.interface_guid.clone()) else {
+            return;
+        };
+        let profile_id = match self.dns_mode {
+            DnsMode::Automatic => None,
+            DnsMode::Manual => self.selected_profile_id.clone(),
+        };
+        self.config.interface_profiles.insert(guid, profile_id);
+    }
+
+    /// Switches to the mode and profile last applied to the selected
+    /// interface. Nothing changes if it was never applied or its profile
+    /// has since been deleted.
+    pub fn restore_interface_profile(&mut self) {
+        let Some(last) = self
+            .selected_interface()
+            .and_then(|i| self.config.interface_profiles.get(&i.interface_guid))
+            .cloned()
+        else {
+            return;
+        };
+
+        match last {
+            None => self.dns_mode = DnsMode::Automatic,
+            Some(id) if self.config.find_profile(&id).is_some() => {
+                self.select_profile(&id);
+                self.dns_mode = DnsMode::Manual;
+            }
+            Some(_) => {}
+        }
+    }
+
+    /// Profile last applied to the selected interface, if it is set to a
+    /// profile rather than Automatic.
+    pub fn applied_profile(&self) -> Option<&DnsProfile> {
+        self.profile_applied_to(&self.selected_interface()?.interface_guid)
+    }
+
+    fn profile_applied_to(&self, interface_guid: &str) -> Option<&DnsProfile> {
+        let id = self
+            .config
+            .interface_profiles
+            .get(interface_guid)?
+            .as_ref()?;
+        self.config.find_profile(id)
+    }
+
+    /// Whether the DNS servers of the selected interface no longer are what
+    /// windns last applied to it, e.g. because DHCP or a VPN client has
+    /// replaced them. False when nothing was ever applied, or its profile
+    /// has since been deleted.
+    pub fn applied_dns_overridden(&self) -> bool {
+        let Some(last) = self
+            .selected_interface()
+            .and_then(|i| self.config.interface_profiles.get(&i.interface_guid))
+        else {
+            return false;
+        };
+        match last {
+            None => !self.current_dns_state.is_automatic(),
+            Some(id) => self
+                .config
+                .find_profile(id)
+                .is_some_and(|p| !profile_matches_dns(&p.settings, &self.current_dns_state)),
+        }
+    }
+
+    /// What the DNS servers now in use on the selected interface correspond
+    /// to. A profile matches when its enabled families have exactly the
+    /// current servers, in order, with DoH on the same ones; the profile
+    /// last applied wins when several match.
+    pub fn dns_match(&self) -> DnsMatch<'_> {
+        if self.current_dns_state.is_automatic() {
+            return DnsMatch::Automatic;
+        }
+        let matches =
+            |profile: &&DnsProfile| profile_matches_dns(&profile.settings, &self.current_dns_state);
+        self.applied_profile()
+            .filter(matches)
+            .or_else(|| self.config.sorted_profiles().into_iter().find(matches))
+            .map_or(DnsMatch::Custom, DnsMatch::Profile)
+    }
+
+    /// Profile with exactly the `current` servers of an interface, preferring
+    /// the one last applied to it.
+    pub fn matching_profile(
+        &self,
+        interface_guid: &str,
+        current: &CurrentDnsState,
+    ) -> Option<&DnsProfile> {
+        let matches = |profile: &&DnsProfile| profile_matches_dns(&profile.settings, current);
+        self.profile_applied_to(interface_guid)
+            .filter(matches)
+            .or_else(|| self.config.sorted_profiles().into_iter().find(matches))
+    }
+
+    /// Profiles as listed by the control server.
+    pub fn control_profiles(&self) -> Vec<ControlProfile> {
+        let active_id = match self.dns_match() {
+            DnsMatch::Profile(profile) => Some(profile.id.as_str()),
+            _ => None,
+        };
+        self.sorted_profiles()
+            .into_iter()
+            .map(|p| ControlProfile {
+                id: p.id.clone(),
+                name: p.name.clone(),
+                active: active_id == Some(p.id.as_str()),
+            })
+            .collect()
+    }
+
+    /// Selected interface and its DNS servers, as reported by the control
+    /// server.
+    pub fn control_status(&self) -> ControlStatus {
+        let interface = self.selected_interface();
+        let dns_match = self.dns_match();
+        ControlStatus {
+            interface: interface.map(|i| self.config.interface_name(i)),
+            interface_guid: interface.map(|i| i.interface_guid.clone()),
+            profile: match dns_match {
+                DnsMatch::Profile(profile) => Some(profile.name.clone()),
+                _ => None,
+            },
+            automatic: dns_match == DnsMatch::Automatic,
+            ipv4: self.current_dns_state.ipv4.clone(),
+            ipv6: self.current_dns_state.ipv6.clone(),
+            elevated: self.is_elevated,
+        }
+    }
+
+    /// Network rules as shown in the editor: the draft if there is one.
+    pub fn network_rules(&self) -> &NetworkRules {
+        self.network_rules_draft
+            .as_ref()
+            .unwrap_or(&self.config.network_rules)
+    }
+
+    /// Moves the edited network rules into the config after checking them.
+    pub fn save_network_rules(&mut self) -> Result<(), String> {
+        let Some(rules) = self.network_rules_draft.take() else {
+            return Ok(());
+        };
+        if let Err(e) = rules.validate(&self.config) {
+            self.network_rules_draft = Some(rules);
+            return Err(e);
+        }
+        self.config.network_rules = rules;
+        Ok(())
+    }
+
+    /// Schedule as shown in the editor: the draft if there is one.
+    pub fn schedule(&self) -> &Schedule {
+        self.schedule_draft
+            .as_ref()
+            .unwrap_or(&self.config.schedule)
+    }
+
+    /// Moves the edited schedule into the config after checking it.
+    pub fn save_schedule(&mut self) -> Result<(), String> {
+        let Some(schedule) = self.schedule_draft.take() else {
+            return Ok(());
+        };
+        if let Err(e) = schedule.validate(&self.config) {
+            self.schedule_draft = Some(schedule);
+            return Err(e);
+        }
+        self.config.schedule = schedule;
+        Ok(())
+    }
+
     pub fn sorted_profiles(&self) -> Vec<&DnsProfile> {
         self.config.sorted_profiles()
     }
 
+    /// Sorted profiles carrying the tag filter and matching the search
+    /// text, plus the selected profile so the list never hides the current
+    /// choice. A filter on a tag no profile has any more is ignored.
+    pub fn filtered_profiles(&self) -> Vec<&DnsProfile> {
+        let profiles = self.sorted_profiles();
+        let tag = self
+            .profile_tag_filter
+            .as_deref()
+            .filter(|tag| profiles.iter().any(|p| p.has_tag(tag)));
+        profiles
+            .into_iter()
+            .filter(|p| {
+                self.selected_profile_id.as_ref() == Some(&p.id)
+                    || (tag.is_none_or(|tag| p.has_tag(tag))
+                        && p.matches_search(&self.profile_search))
+            })
+            .collect()
+    }
+
     pub fn select_profile(&mut self, id: &str) {
         if let Some(profile) = self.config.find_profile(id) {
             self.selected_profile_id = Some(id.to_string());
             self.current_settings = profile.settings.clone();
             self.current_profile_name = profile.name.clone();
+            self.current_profile_tags = profile.tags.join(", ");
+            self.current_profile_color = profile.color.clone();
+            self.selected_provider = None;
         }
     }
 
     pub fn create_new_profile(&mut self) -> String {
-        let mut name = "New Profile".to_string();
+        let mut name = t!("new-profile");
         let mut counter = 1;
         while self.config.profiles.iter().any(|p| p.name == name) {
             counter += 1;
-            name = format!("New Profile {}", counter);
+            name = t!("new-profile-numbered", number = counter);
         }
 
         let profile = DnsProfile::new(name);
@@ -113,6 +762,62 @@ impl AppState {
         id
     }
 
+    /// The selected profile, with its current edits, as compact JSON for
+    /// the clipboard.
+    pub fn profile_share_text(&self) -> Result<String, String> {
+        let id = self
+            .selected_profile_id
+            .clone()
+            .ok_or_else(|| t!("no-profile-selected"))?;
+        let profile = DnsProfile {
+            id,
+            name: self.current_profile_name.trim().to_string(),
+            settings: self.current_settings.clone(),
+            tags: parse_tags(&self.current_profile_tags),
+            color: self.current_profile_color.clone(),
+        };
+        serde_json::to_string(&profile).map_err(|e| e.to_string())
+    }
+
+    /// Adds the profile shared as `text` under a new ID and selects it. A
+    /// name that is already taken gets a number appended, and hook commands
+    /// are dropped. Returns the name the profile was added under.
+    pub fn paste_profile(&mut self, text: &str) -> Result<String, String> {
+        let mut profile: DnsProfile =
+            serde_json::from_str(text.trim()).map_err(|_| t!("clipboard-not-profile"))?;
+
+        let base_name = profile.name.trim().to_string();
+        if base_name.is_empty() {
+            return Err(t!("pasted-profile-no-name"));
+        }
+        validate_dns_entry(&profile.settings.ipv4, AddressFamily::IPv4)?;
+        validate_dns_entry(&profile.settings.ipv6, AddressFamily::IPv6)?;
+        validate_dns_suffixes(&profile.settings)?;
+        validate_nrpt_rules(&profile.settings.nrpt)?;
+        if profile
+            .color
+            .as_deref()
+            .is_some_and(|color| !crate::dns::validate_color(color))
+        {
+            return Err(t!("pasted-profile-invalid-color"));
+        }
+
+        let mut name = base_name.clone();
+        let mut counter = 1;
+        while self.is_profile_name_duplicate(&name, None) {
+            counter += 1;
+            name = format!("{} ({})", base_name, counter);
+        }
+
+        profile.id = DnsProfile::new(String::new()).id;
+        profile.name = name.clone();
+        profile.settings.remove_hooks();
+        let id = profile.id.clone();
+        self.config.add_profile(profile);
+        self.select_profile(&id);
+        Ok(name)
+    }
+
     pub fn update_current_profile(&mut self) {
         let id = match &self.selected_profile_id {
             Some(id) => id.clone(),
@@ -121,21 +826,290 @@ impl AppState {
         if let Some(profile) = self.config.find_profile_mut(&id) {
             profile.name = self.current_profile_name.clone();
             profile.settings = self.current_settings.clone();
+            profile.tags = parse_tags(&self.current_profile_tags);
+            profile.color = self.current_profile_color.clone();
+        }
+    }
+
+    /// Whether the selected profile has been edited since it was last
+    /// saved.
+    pub fn has_unsaved_changes(&self) -> bool {
+        let Some(profile) = self
+            .selected_profile_id
+            .as_deref()
+            .and_then(|id| self.config.find_profile(id))
+        else {
+            return false;
+        };
+        profile.name != self.current_profile_name
+            || profile.settings != self.current_settings
+            || profile.tags != parse_tags(&self.current_profile_tags)
+            || profile.color != self.current_profile_color
+    }
+
+    /// Throws away the edits of the selected profile.
+    pub fn discard_profile_changes(&mut self) {
+        if let Some(id) = self.selected_profile_id.clone() {
+            self.select_profile(&id);
+        }
+    }
+
+    /// Built-in providers followed by the ones added by the user.
+    pub fn provider_catalog(&self) -> Vec<DnsProvider> {
+        providers()
+            .iter()
+            .chain(&self.config.custom_providers)
+            .cloned()
+            .collect()
+    }
+
+    fn find_any_provider(&self, id: &str) -> Option<&DnsProvider> {
+        self.config
+            .find_custom_provider(id)
+            .or_else(|| find_provider(id))
+    }
+
+    /// Fills the current settings from a catalog provider, using the given
+    /// variant or the provider's first one. DoH is turned on when it is
+    /// available. Returns false for an unknown provider or variant.
+    pub fn fill_from_provider(&mut self, provider_id: &str, variant_id: Option<&str>) -> bool {
+        let Some(provider) = self.find_any_provider(provider_id) else {
+            return false;
+        };
+        let variant = match variant_id {
+            Some(id) => provider.variant(id),
+            None => provider.default_variant(),
+        };
+        let Some(variant) = variant else {
+            return false;
+        };
+
+        let use_doh = self.doh_unavailable_reason().is_none();
+        let settings = variant.fill(&self.current_settings, use_doh);
+        self.selected_provider = Some((provider.id.clone(), variant.id.clone()));
+        self.current_settings = settings;
+        true
+    }
+
+    /// Adds the provider in the open form, or replaces the one it edits,
+    /// and closes the form. Returns the name of the saved provider.
+    pub fn save_provider_draft(&mut self) -> Result<String, String> {
+        let Some(draft) = &self.provider_draft else {
+            return Err(t!("no-provider-edited"));
+        };
+        let provider = draft.to_provider()?;
+
+        let duplicate = self
+            .provider_catalog()
+            .iter()
+            .any(|p| p.id != provider.id && p.name.eq_ignore_ascii_case(&provider.name));
+        if duplicate {
+            return Err(t!("duplicate-provider-name"));
+        }
+
+        let name = provider.name.clone();
+        let providers = &mut self.config.custom_providers;
+        match providers.iter_mut().find(|p| p.id == provider.id) {
+            Some(existing) => *existing = provider,
+            None => providers.push(provider),
+        }
+        self.provider_draft = None;
+        Ok(name)
+    }
+
+    /// Adds a provider for each DoH template among the servers Windows
+    /// lists that no provider has yet. Returns the names of the added
+    /// providers.
+    pub fn import_doh_providers(&mut self, servers: &[DnsServerEntry]) -> Vec<String> {
+        let imported = providers_from_doh_servers(servers, &self.provider_catalog());
+        let names = imported.iter().map(|p| p.name.clone()).collect();
+        self.config.custom_providers.extend(imported);
+        names
+    }
+
+    /// Removes a user-defined provider. Returns false if there is none with
+    /// `id`.
+    pub fn delete_custom_provider(&mut self, id: &str) -> bool {
+        let before = self.config.custom_providers.len();
+        self.config.custom_providers.retain(|p| p.id != id);
+        if self
+            .selected_provider
+            .as_ref()
+            .is_some_and(|(p, _)| p == id)
+        {
+            self.selected_provider = None;
+        }
+        self.config.custom_providers.len() != before
+    }
+
+    /// Remembers the DoH servers Windows lists, so that typing one of their
+    /// addresses fills in its template.
+    pub fn set_known_doh_templates(&mut self, servers: Vec<DnsServerEntry>) {
+        self.known_doh_templates = servers
+            .into_iter()
+            .filter(|server| !server.doh_template.is_empty())
+            .filter_map(|server| Some((server.address.parse().ok()?, server.doh_template)))
+            .collect();
+    }
+
+    /// Replaces the settings being edited. A server whose address was just
+    /// changed to one Windows knows a DoH template for, and that has no
+    /// template yet, gets that template with DoH on.
+    pub fn set_current_settings(&mut self, mut settings: DnsSettings) {
+        if self.doh_unavailable_reason().is_none() {
+            for (entry, previous) in [
+                (&mut settings.ipv4, &self.current_settings.ipv4),
+                (&mut settings.ipv6, &self.current_settings.ipv6),
+            ] {
+                for (index, server) in entry.servers.iter_mut().enumerate() {
+                    let changed = previous
+                        .servers
+                        .get(index)
+                        .is_none_or(|old| old.address != server.address);
+                    let known = server
+                        .address
+                        .trim()
+                        .parse::<IpAddr>()
+                        .ok()
+                        .and_then(|address| self.known_doh_templates.get(&address));
+                    if let Some(template) =
+                        known.filter(|_| changed && server.doh_template.is_empty())
+                    {
+                        server.doh_template = template.clone();
+                        server.doh_mode = DohMode::On;
+                    }
+                }
+            }
+        }
+        self.current_settings = settings;
+    }
+
+    /// The temporary apply running on the selected interface, if any.
+    pub fn selected_temporary_apply(&self) -> Option<&TemporaryApply> {
+        let guid = self.selected_interface_guid.as_ref()?;
+        self.config.temporary_applies.get(guid)
+    }
+
+    /// Ends the temporary apply of an interface once its settings have been
+    /// put back, recording the profile it had before as applied again.
+    pub fn finish_temporary_apply(&mut self, interface_guid: &str) {
+        let Some(temporary) = self.config.temporary_applies.remove(interface_guid) else {
+            return;
+        };
+        match temporary
+            .previous_profile
+            .filter(|id| self.config.find_profile(id).is_some())
+        {
+            Some(id) => {
+                self.config
+                    .interface_profiles
+                    .insert(interface_guid.to_string(), Some(id));
+            }
+            None => {
+                self.config.interface_profiles.remove(interface_guid);
+            }
+        }
+    }
+
+    /// Stores a discovered DoH template for a server of the current settings
+    /// and turns DoH on for it.
+    pub fn set_doh_template(&mut self, family: AddressFamily, index: usize, template: String) {
+        let entry = match family {
+            AddressFamily::IPv4 => &mut self.current_settings.ipv4,
+            AddressFamily::IPv6 => &mut self.current_settings.ipv6,
+        };
+        if let Some(server) = entry.servers.get_mut(index) {
+            server.doh_mode = DohMode::On;
+            server.doh_template = template;
+        }
+    }
+
+    /// The resolved addresses offered for a server of the current settings,
+    /// while it still holds the host name they were resolved from.
+    pub fn resolved_host_for(&self, family: AddressFamily, index: usize) -> Option<&ResolvedHost> {
+        let entry = match family {
+            AddressFamily::IPv4 => &self.current_settings.ipv4,
+            AddressFamily::IPv6 => &self.current_settings.ipv6,
+        };
+        let address = entry.servers.get(index)?.address.trim();
+        self.resolved_host.as_ref().filter(|resolved| {
+            resolved.family == family && resolved.index == index && resolved.host == address
+        })
+    }
+
+    /// Puts the offered addresses in place of the host name they were
+    /// resolved from. Addresses of the server's family replace it and fill
+    /// the empty servers after it, adding servers as needed; those of the
+    /// other family fill it if it has no servers yet. Each new server keeps
+    /// the DoH settings of the host name's.
+    pub fn use_resolved_host(&mut self) -> bool {
+        let Some(resolved) = self.resolved_host.clone() else {
+            return false;
+        };
+        if self
+            .resolved_host_for(resolved.family, resolved.index)
+            .is_none()
+        {
+            return false;
+        }
+        self.resolved_host = None;
+
+        let mut settings = self.current_settings.clone();
+        let (entry, other) = match resolved.family {
+            AddressFamily::IPv4 => (&mut settings.ipv4, &mut settings.ipv6),
+            AddressFamily::IPv6 => (&mut settings.ipv6, &mut settings.ipv4),
+        };
+        let source = entry.servers[resolved.index].clone();
+        let (same, different): (Vec<IpAddr>, Vec<IpAddr>) = resolved
+            .addresses
+            .iter()
+            .partition(|address| address.is_ipv4() == (resolved.family == AddressFamily::IPv4));
+
+        if same.is_empty() {
+            entry.servers[resolved.index].address.clear();
         }
+        fill_servers(entry, resolved.index, &same, &source);
+        if !different.is_empty() && other.servers.iter().all(|s| s.address.trim().is_empty()) {
+            other.enabled = true;
+            fill_servers(other, 0, &different, &source);
+        }
+        self.set_current_settings(settings);
+        true
     }
 
     pub fn delete_current_profile(&mut self) {
         if let Some(id) = self.selected_profile_id.take() {
             self.config.remove_profile(&id);
-            self.current_settings = DnsSettings::new();
-            self.current_profile_name = String::new();
-
-            if let Some(first) = self.config.sorted_profiles().first() {
-                let first_id = first.id.clone();
-                self.select_profile(&first_id);
-            } else {
-                self.dns_mode = DnsMode::Automatic;
-            }
+            self.select_first_profile();
+        }
+    }
+
+    /// Selects the first profile, or switches to Automatic if there is none.
+    fn select_first_profile(&mut self) {
+        self.selected_profile_id = None;
+        self.current_settings = DnsSettings::new();
+        self.current_profile_name = String::new();
+        self.current_profile_tags = String::new();
+        self.current_profile_color = None;
+
+        if let Some(first) = self.config.sorted_profiles().first() {
+            let first_id = first.id.clone();
+            self.select_profile(&first_id);
+        } else {
+            self.dns_mode = DnsMode::Automatic;
+        }
+    }
+
+    /// Replaces the config with a restored backup. The selected profile
+    /// stays selected, with its settings from the backup, if the backup has
+    /// it; unsaved rule and schedule edits are dropped.
+    pub fn restore_config(&mut self, config: AppConfig) {
+        self.config = config;
+        self.network_rules_draft = None;
+        self.schedule_draft = None;
+        match self.selected_profile_id.clone() {
+            Some(id) if self.config.find_profile(&id).is_some() => self.select_profile(&id),
+            _ => self.select_first_profile(),
         }
     }
 
@@ -145,159 +1119,990 @@ impl AppState {
         })
     }
 
-    pub fn validate_current_settings(&self) -> Result<(), String> {
+    /// Checks the current settings. Problems that block saving or applying
+    /// are errors; settings that would only partly take effect come back as
+    /// a warning.
+    pub fn validate_current_settings(&self) -> Result<Option<String>, String> {
         if self.dns_mode == DnsMode::Automatic {
-            return Ok(());
+            return Ok(None);
         }
 
         if self.selected_profile_id.is_none() {
-            return Err("No profile selected".to_string());
+            return Err(t!("no-profile-selected"));
         }
 
         if self.dns_mode == DnsMode::Manual {
             if self.current_profile_name.trim().is_empty() {
-                return Err("Profile name cannot be empty".to_string());
+                return Err(t!("empty-profile-name"));
             }
 
             if let Some(ref id) = self.selected_profile_id
                 && self.is_profile_name_duplicate(&self.current_profile_name, Some(id))
             {
-                return Err("A profile with this name already exists".to_string());
-            }
-        }
-
-        let ipv4_entry = &self.current_settings.ipv4;
-        let ipv6_entry = &self.current_settings.ipv6;
-
-        if ipv4_entry.enabled {
-            if ipv4_entry.primary.address.is_empty() {
-                return Err("IPv4 primary DNS is required when enabled".to_string());
-            }
-            if !crate::dns::validate_ipv4(&ipv4_entry.primary.address) {
-                return Err("Invalid IPv4 primary DNS address".to_string());
-            }
-            if !ipv4_entry.secondary.address.is_empty()
-                && !crate::dns::validate_ipv4(&ipv4_entry.secondary.address)
-            {
-                return Err("Invalid IPv4 secondary DNS address".to_string());
-            }
-            if ipv4_entry.primary.doh_mode == DohMode::On {
-                if ipv4_entry.primary.doh_template.is_empty() {
-                    return Err(
-                        "IPv4 primary DoH template URL is required when DoH is enabled".to_string(),
-                    );
-                }
-                if !crate::dns::validate_doh_template(&ipv4_entry.primary.doh_template) {
-                    return Err("Invalid IPv4 primary DoH template URL".to_string());
-                }
-            }
-            if ipv4_entry.secondary.doh_mode == DohMode::On {
-                if ipv4_entry.secondary.address.is_empty() {
-                    return Err(
-                        "IPv4 secondary DNS address is required when DoH is enabled".to_string()
-                    );
-                }
-                if ipv4_entry.secondary.doh_template.is_empty() {
-                    return Err(
-                        "IPv4 secondary DoH template URL is required when DoH is enabled"
-                            .to_string(),
-                    );
-                }
-                if !crate::dns::validate_doh_template(&ipv4_entry.secondary.doh_template) {
-                    return Err("Invalid IPv4 secondary DoH template URL".to_string());
-                }
+                return Err(t!("duplicate-profile-name"));
             }
         }
 
-        if ipv6_entry.enabled {
-            if ipv6_entry.primary.address.is_empty() {
-                return Err("IPv6 primary DNS is required when enabled".to_string());
-            }
-            if !crate::dns::validate_ipv6(&ipv6_entry.primary.address) {
-                return Err("Invalid IPv6 primary DNS address".to_string());
-            }
-            if !ipv6_entry.secondary.address.is_empty()
-                && !crate::dns::validate_ipv6(&ipv6_entry.secondary.address)
-            {
-                return Err("Invalid IPv6 secondary DNS address".to_string());
-            }
-            if ipv6_entry.primary.doh_mode == DohMode::On {
-                if ipv6_entry.primary.doh_template.is_empty() {
-                    return Err(
-                        "IPv6 primary DoH template URL is required when DoH is enabled".to_string(),
-                    );
-                }
-                if !crate::dns::validate_doh_template(&ipv6_entry.primary.doh_template) {
-                    return Err("Invalid IPv6 primary DoH template URL".to_string());
-                }
-            }
-            if ipv6_entry.secondary.doh_mode == DohMode::On {
-                if ipv6_entry.secondary.address.is_empty() {
-                    return Err(
-                        "IPv6 secondary DNS address is required when DoH is enabled".to_string()
-                    );
-                }
-                if ipv6_entry.secondary.doh_template.is_empty() {
-                    return Err(
-                        "IPv6 secondary DoH template URL is required when DoH is enabled"
-                            .to_string(),
-                    );
-                }
-                if !crate::dns::validate_doh_template(&ipv6_entry.secondary.doh_template) {
-                    return Err("Invalid IPv6 secondary DoH template URL".to_string());
-                }
-            }
+        validate_dns_entry(&self.current_settings.ipv4, AddressFamily::IPv4)?;
+        validate_dns_entry(&self.current_settings.ipv6, AddressFamily::IPv6)?;
+        validate_dns_suffixes(&self.current_settings)?;
+        validate_nrpt_rules(&self.current_settings.nrpt)?;
+        if let Some(host) = &self.current_settings.check_host
+            && !crate::dns::validate_dns_suffix(host.trim())
+        {
+            return Err(t!("invalid-check-host", host = host.trim()));
         }
 
-        Ok(())
+        let warnings: Vec<String> = [
+            self.family_mismatch_warning(),
+            self.suspicious_address_warning(),
+            self.doh_template_conflict_warning(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        Ok((!warnings.is_empty()).then(|| warnings.join(" ")))
     }
-}
 
-impl Default for AppState {
-    fn default() -> Self {
-        Self::new()
+    /// Warns about servers at addresses that are valid but almost certainly
+    /// not meant, such as a loopback or broadcast address. Loopback is what
+    /// a local resolver listens on, so it is not flagged for one.
+    fn suspicious_address_warning(&self) -> Option<String> {
+        let local_resolver = self.current_settings.local_resolver.is_some();
+        let warnings: Vec<String> = [&self.current_settings.ipv4, &self.current_settings.ipv6]
+            .into_iter()
+            .filter(|entry| entry.enabled)
+            .flat_map(|entry| &entry.servers)
+            .filter_map(|server| {
+                let issue = suspicious_address(&server.address)
+                    .filter(|issue| !(local_resolver && *issue == SuspiciousAddress::Loopback))?;
+                Some(t!(
+                    "suspicious-dns-address",
+                    address = server.address.trim(),
+                    reason = suspicious_address_message(issue)
+                ))
+            })
+            .collect();
+        (!warnings.is_empty()).then(|| warnings.join(" "))
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::dns::{DnsEntry, DnsServerEntry, DohMode, NetworkInterface};
-
-    fn create_test_interface(name: &str, index: u32) -> NetworkInterface {
-        NetworkInterface {
-            name: name.to_string(),
-            interface_index: index,
-            interface_guid: format!("{{GUID-{}}}", index),
-            has_ipv4: true,
-            has_ipv6: true,
+    /// Servers of the local resolver the current settings hand DNS to,
+    /// when they ask for it to be checked before applying.
+    pub fn local_resolver_servers(&self) -> Vec<String> {
+        let settings = &self.current_settings;
+        if self.dns_mode == DnsMode::Automatic
+            || !settings
+                .local_resolver
+                .as_ref()
+                .is_some_and(|resolver| resolver.check_listening)
+        {
+            return Vec::new();
         }
+        [&settings.ipv4, &settings.ipv6]
+            .into_iter()
+            .filter(|entry| entry.enabled)
+            .flat_map(|entry| entry.get_addresses())
+            .collect()
     }
 
-    fn create_valid_ipv4_settings() -> DnsEntry {
-        DnsEntry {
-            enabled: true,
-            primary: DnsServerEntry {
-                address: "8.8.8.8".to_string(),
-                doh_mode: DohMode::Off,
-                doh_template: String::new(),
-                allow_fallback: true,
-            },
-            secondary: DnsServerEntry::default(),
+    /// Warns about other profiles that use one of the DoH servers of the
+    /// current settings with another template. Windows keeps one template
+    /// per server address for the whole system, so applying replaces
+    /// theirs.
+    fn doh_template_conflict_warning(&self) -> Option<String> {
+        let current = doh_templates(&self.current_settings);
+        let mut conflicts = Vec::new();
+        for profile in self.sorted_profiles() {
+            if self.selected_profile_id.as_deref() == Some(profile.id.as_str()) {
+                continue;
+            }
+            let mut addresses: Vec<&str> = doh_templates(&profile.settings)
+                .into_iter()
+                .filter(|(address, template)| {
+                    current.iter().any(|(current_address, current_template)| {
+                        normalize_address(current_address) == normalize_address(address)
+                            && current_template != template
+                    })
+                })
+                .map(|(address, _)| address)
+                .collect();
+            addresses.sort_unstable();
+            addresses.dedup();
+            conflicts.extend(addresses.into_iter().map(|address| {
+                t!(
+                    "doh-template-conflict-entry",
+                    address = address,
+                    profile = &profile.name
+                )
+            }));
         }
-    }
 
-    fn create_valid_ipv6_settings() -> DnsEntry {
-        DnsEntry {
-            enabled: true,
-            primary: DnsServerEntry {
-                address: "2001:4860:4860::8888".to_string(),
-                doh_mode: DohMode::Off,
-                doh_template: String::new(),
-                allow_fallback: true,
-            },
-            secondary: DnsServerEntry::default(),
+        if conflicts.is_empty() {
+            return None;
         }
+        Some(t!(
+            "doh-template-conflict",
+            conflicts = conflicts.join(", ")
+        ))
+    }
+
+    /// Warns about servers configured for an address family the selected
+    /// interface does not have, as Windows would not use them.
+    fn family_mismatch_warning(&self) -> Option<String> {
+        let interface = self.selected_interface()?;
+        let missing: Vec<&str> = [
+            (
+                &self.current_settings.ipv4,
+                interface.has_ipv4,
+                AddressFamily::IPv4,
+            ),
+            (
+                &self.current_settings.ipv6,
+                interface.has_ipv6,
+                AddressFamily::IPv6,
+            ),
+        ]
+        .into_iter()
+        .filter(|(entry, available, _)| {
+            !available && entry.enabled && !entry.get_addresses().is_empty()
+        })
+        .map(|(_, _, family)| family.as_str())
+        .collect();
+
+        if missing.is_empty() {
+            return None;
+        }
+        Some(t!(
+            "family-mismatch",
+            families = missing.join("/"),
+            interface = self.config.interface_name(interface)
+        ))
+    }
+
+    /// Builds the list of operations an apply would perform with the current
+    /// mode and settings, without executing anything.
+    pub fn preview_apply(&self) -> Result<Vec<PreviewStep>, String> {
+        self.validate_current_settings()?;
+
+        let interface = self
+            .selected_interface()
+            .ok_or_else(|| t!("no-interface-selected"))?;
+
+        let settings = match self.dns_mode {
+            DnsMode::Automatic => DnsSettings::new(),
+            DnsMode::Manual => {
+                let templates: Vec<DnsServerEntry> = self
+                    .known_doh_templates
+                    .iter()
+                    .map(|(address, template)| DnsServerEntry {
+                        address: address.to_string(),
+                        doh_mode: DohMode::On,
+                        doh_template: template.clone(),
+                        ..Default::default()
+                    })
+                    .collect();
+                self.current_settings
+                    .keeping_unchanged(&self.current_dns_state, &templates)
+            }
+        };
+
+        let mut steps = self.backend.preview_settings(interface, &settings);
+        if self.config.settings.flushes_cache_after_apply() {
+            steps.push(PreviewStep::new("Clear DNS cache", "Clear-DnsClientCache"));
+        }
+        Ok(steps)
+    }
+}
+
+fn describe_policies<'a>(policies: impl Iterator<Item = &'a DnsPolicy>) -> String {
+    policies
+        .map(DnsPolicy::description)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Address and template of each server of `settings` that is to use DoH.
+fn doh_templates(settings: &DnsSettings) -> Vec<(&str, &str)> {
+    [&settings.ipv4, &settings.ipv6]
+        .into_iter()
+        .filter(|entry| entry.enabled)
+        .flat_map(|entry| &entry.servers)
+        .filter(|server| server.doh_mode == DohMode::On)
+        .map(|server| (server.address.trim(), server.doh_template.trim()))
+        .filter(|(address, template)| !address.is_empty() && !template.is_empty())
+        .collect()
+}
+
+/// Canonical form of a server address, so that e.g. `2606:4700::1111` and
+/// `2606:4700:0:0::1111` compare equal.
+fn normalize_address(address: &str) -> String {
+    let address = address.trim();
+    crate::dns::server_ip(address)
+        .map(|ip| ip.to_string())
+        .unwrap_or_else(|| address.to_lowercase())
+}
+
+fn profile_matches_dns(settings: &DnsSettings, current: &CurrentDnsState) -> bool {
+    let current_doh: Vec<String> = current
+        .doh_servers
+        .iter()
+        .map(|a| normalize_address(a))
+        .collect();
+    let mut has_servers = false;
+
+    for (entry, addresses) in [
+        (&settings.ipv4, &current.ipv4),
+        (&settings.ipv6, &current.ipv6),
+    ] {
+        if !entry.enabled {
+            continue;
+        }
+        let servers: Vec<&DnsServerEntry> = entry
+            .servers
+            .iter()
+            .filter(|s| !s.address.trim().is_empty())
+            .collect();
+        let expected: Vec<String> = servers
+            .iter()
+            .map(|s| normalize_address(&s.address))
+            .collect();
+        let actual: Vec<String> = addresses.iter().map(|a| normalize_address(a)).collect();
+        if expected != actual {
+            return false;
+        }
+        let doh_matches = servers.iter().zip(&expected).all(|(server, address)| {
+            (server.doh_mode == DohMode::On) == current_doh.contains(address)
+        });
+        if !doh_matches {
+            return false;
+        }
+        has_servers |= !expected.is_empty();
+    }
+
+    has_servers
+}
+
+/// Puts `addresses` in the server at `index` and the empty servers after
+/// it, adding servers when they run out. New servers copy the DoH settings
+/// of `source`.
+fn fill_servers(entry: &mut DnsEntry, index: usize, addresses: &[IpAddr], source: &DnsServerEntry) {
+    let mut slot = index;
+    for address in addresses {
+        while slot != index
+            && entry
+                .servers
+                .get(slot)
+                .is_some_and(|server| !server.address.trim().is_empty())
+        {
+            slot += 1;
+        }
+        let server = DnsServerEntry {
+            address: address.to_string(),
+            ..source.clone()
+        };
+        match entry.servers.get_mut(slot) {
+            Some(existing) => *existing = server,
+            None => entry.servers.push(server),
+        }
+        slot += 1;
+    }
+}
+
+/// Problems with the fields of one DNS server, shown next to them while
+/// typing.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct DnsServerErrors {
+    pub address: Option<String>,
+    /// A valid address that is unlikely to be a DNS server.
+    pub address_warning: Option<String>,
+    pub doh_template: Option<String>,
+    /// Something unusual about a template that may still work.
+    pub doh_template_warning: Option<String>,
+}
+
+fn suspicious_address_message(issue: SuspiciousAddress) -> String {
+    match issue {
+        SuspiciousAddress::Loopback => t!("field-loopback-address"),
+        SuspiciousAddress::Multicast => t!("field-multicast-address"),
+        SuspiciousAddress::Broadcast => t!("field-broadcast-address"),
+        SuspiciousAddress::Unspecified => t!("field-unspecified-address"),
+    }
+}
+
+/// Checks one server of an enabled address family the way
+/// `validate_current_settings` does, but reports every field.
+pub fn dns_server_errors(
+    server: &DnsServerEntry,
+    family: AddressFamily,
+    is_primary: bool,
+) -> DnsServerErrors {
+    let doh_enabled = server.doh_mode == DohMode::On;
+    let valid_address = match family {
+        AddressFamily::IPv4 => crate::dns::validate_ipv4(&server.address),
+        AddressFamily::IPv6 => crate::dns::validate_ipv6(&server.address),
+    };
+
+    let address_error = if server.address.is_empty() {
+        (is_primary || doh_enabled).then(|| t!("field-required"))
+    } else if crate::dns::looks_like_host_name(&server.address) {
+        Some(t!("field-host-name"))
+    } else if !valid_address {
+        Some(t!("field-invalid-address", family = family.as_str()))
+    } else {
+        None
+    };
+
+    let issues = if doh_enabled {
+        crate::dns::check_doh_template(&server.doh_template)
+    } else {
+        Vec::new()
+    };
+    let message = |issue: DohTemplateIssue| match issue {
+        DohTemplateIssue::NotHttps | DohTemplateIssue::MissingHost => {
+            t!("field-invalid-doh-template")
+        }
+        DohTemplateIssue::Malformed => t!("field-malformed-doh-template"),
+        DohTemplateIssue::NoDnsVariable => t!("field-doh-template-no-dns-variable"),
+        DohTemplateIssue::SingleLabelHost => t!("field-doh-template-single-label"),
+    };
+    let template_error = if doh_enabled && server.doh_template.is_empty() {
+        Some(t!("field-required"))
+    } else {
+        issues.iter().copied().find(|i| i.is_error()).map(message)
+    };
+    let template_warning = issues.iter().copied().find(|i| !i.is_error()).map(message);
+
+    DnsServerErrors {
+        address_warning: address_error
+            .is_none()
+            .then(|| suspicious_address(&server.address))
+            .flatten()
+            .map(suspicious_address_message),
+        address: address_error,
+        doh_template: template_error,
+        doh_template_warning: template_warning,
+    }
+}
+
+fn validate_dns_suffixes(settings: &DnsSettings) -> Result<(), String> {
+    if let Some(suffix) = &settings.connection_suffix
+        && !crate::dns::validate_dns_suffix(suffix.trim())
+    {
+        return Err(t!("invalid-connection-suffix"));
+    }
+
+    if !settings.suffixes.enabled {
+        return Ok(());
+    }
+
+    for suffix in settings.suffixes.search_suffixes() {
+        if !crate::dns::validate_dns_suffix(&suffix) {
+            return Err(t!("invalid-search-suffix", suffix = suffix));
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_nrpt_rules(nrpt: &NrptSettings) -> Result<(), String> {
+    if !nrpt.enabled {
+        return Ok(());
+    }
+
+    for rule in &nrpt.rules {
+        let namespace = rule.normalized_namespace();
+        let domain = namespace.strip_prefix('.').unwrap_or(&namespace);
+        if domain.is_empty() || !crate::dns::validate_dns_suffix(domain) {
+            return Err(t!(
+                "invalid-nrpt-namespace",
+                namespace = rule.namespace.trim()
+            ));
+        }
+
+        let addresses = rule.name_server_addresses();
+        if addresses.is_empty() {
+            return Err(t!("nrpt-rule-needs-server", namespace = namespace));
+        }
+        if let Some(address) = addresses
+            .iter()
+            .find(|a| a.parse::<std::net::IpAddr>().is_err())
+        {
+            return Err(t!("invalid-nrpt-server", address = address));
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_dns_entry(entry: &DnsEntry, family: AddressFamily) -> Result<(), String> {
+    if !entry.enabled {
+        return Ok(());
+    }
+
+    let family_label = family.as_str();
+    let validate_address = match family {
+        AddressFamily::IPv4 => crate::dns::validate_ipv4,
+        AddressFamily::IPv6 => crate::dns::validate_ipv6,
+    };
+
+    if entry.servers.first().is_none_or(|s| s.address.is_empty()) {
+        return Err(t!("primary-dns-required", family = family_label));
+    }
+
+    for (index, server) in entry.servers.iter().enumerate() {
+        let name = DnsEntry::server_label(index).to_lowercase();
+        if !server.address.is_empty() && !validate_address(&server.address) {
+            return Err(t!(
+                "invalid-dns-address",
+                family = family_label,
+                server = name
+            ));
+        }
+        if !server.address.trim().is_empty()
+            && entry.servers[..index].iter().any(|earlier| {
+                normalize_address(&earlier.address) == normalize_address(&server.address)
+            })
+        {
+            return Err(t!(
+                "duplicate-dns-address",
+                family = family_label,
+                server = name,
+                address = server.address.trim()
+            ));
+        }
+        if server.doh_mode == DohMode::On {
+            if server.address.is_empty() {
+                return Err(t!(
+                    "doh-address-required",
+                    family = family_label,
+                    server = name
+                ));
+            }
+            if server.doh_template.is_empty() {
+                return Err(t!(
+                    "doh-template-required",
+                    family = family_label,
+                    server = name
+                ));
+            }
+            if !crate::dns::validate_doh_template(&server.doh_template) {
+                return Err(t!(
+                    "invalid-doh-template",
+                    family = family_label,
+                    server = name
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+impl Default for AppState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dns::types::test_interface;
+    use crate::dns::{
+        DnsEntry, DnsOrigin, DnsServerEntry, DnsSuffixSettings, DohMode, LocalResolver,
+        NetworkCondition, NetworkRule, NrptRule, ScheduleEntry, ThemePreference, Weekday,
+    };
+
+    fn create_valid_ipv4_settings() -> DnsEntry {
+        DnsEntry {
+            enabled: true,
+            unchanged: false,
+            servers: vec![
+                DnsServerEntry {
+                    address: "8.8.8.8".to_string(),
+                    doh_mode: DohMode::Off,
+                    doh_template: String::new(),
+                    allow_fallback: true,
+                    auto_upgrade: true,
+                },
+                DnsServerEntry::default(),
+            ],
+        }
+    }
+
+    fn create_valid_ipv6_settings() -> DnsEntry {
+        DnsEntry {
+            enabled: true,
+            unchanged: false,
+            servers: vec![
+                DnsServerEntry {
+                    address: "2001:4860:4860::8888".to_string(),
+                    doh_mode: DohMode::Off,
+                    doh_template: String::new(),
+                    allow_fallback: true,
+                    auto_upgrade: true,
+                },
+                DnsServerEntry::default(),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_validate_current_settings_suffixes() {
+        let mut state = AppState::new();
+        state.create_new_profile();
+        state.dns_mode = DnsMode::Manual;
+        state.current_settings.suffixes = DnsSuffixSettings {
+            enabled: true,
+            search_list: vec!["corp.example.com".to_string(), String::new()],
+        };
+        assert!(state.validate_current_settings().is_ok());
+
+        state.current_settings.suffixes.search_list = vec!["bad suffix".to_string()];
+        assert_eq!(
+            state.validate_current_settings(),
+            Err("Invalid DNS suffix in search list: bad suffix".to_string())
+        );
+
+        state.current_settings.suffixes.enabled = false;
+        assert!(state.validate_current_settings().is_ok());
+    }
+
+    #[test]
+    fn test_validate_current_settings_doh_template_conflict() {
+        let mut state = AppState::new();
+        let mut doh = create_valid_ipv4_settings();
+        doh.servers[0].doh_mode = DohMode::On;
+        doh.servers[0].doh_template = "https://dns.google/dns-query".to_string();
+
+        state.create_new_profile();
+        state.current_profile_name = "Work".to_string();
+        state.current_settings.ipv4 = doh.clone();
+        state.update_current_profile();
+
+        state.create_new_profile();
+        state.dns_mode = DnsMode::Manual;
+        state.current_profile_name = "Home".to_string();
+        state.current_settings.ipv4 = doh;
+        assert_eq!(state.validate_current_settings(), Ok(None));
+
+        state.current_settings.ipv4.servers[0].doh_template =
+            "https://dns.google/dns-query{?dns}".to_string();
+        let warning = state.validate_current_settings().unwrap().unwrap();
+        assert!(warning.contains("8.8.8.8"));
+        assert!(warning.contains("Work"));
+
+        state.current_settings.ipv4.servers[0].doh_mode = DohMode::Off;
+        assert_eq!(state.validate_current_settings(), Ok(None));
+    }
+
+    #[test]
+    fn test_validate_current_settings_check_host() {
+        let mut state = AppState::new();
+        state.create_new_profile();
+        state.dns_mode = DnsMode::Manual;
+
+        state.current_settings.check_host = Some(" intranet.corp.example ".to_string());
+        assert!(state.validate_current_settings().is_ok());
+
+        state.current_settings.check_host = Some("intranet corp".to_string());
+        assert_eq!(
+            state.validate_current_settings(),
+            Err("Invalid host to check after apply: intranet corp".to_string())
+        );
+    }
+
+    #[test]
+    fn test_validate_current_settings_connection_suffix() {
+        let mut state = AppState::new();
+        state.create_new_profile();
+        state.dns_mode = DnsMode::Manual;
+
+        state.current_settings.connection_suffix = Some("corp.example.com".to_string());
+        assert!(state.validate_current_settings().is_ok());
+
+        // An empty suffix clears the one set on the interface.
+        state.current_settings.connection_suffix = Some(String::new());
+        assert!(state.validate_current_settings().is_ok());
+
+        state.current_settings.connection_suffix = Some("corp..example.com".to_string());
+        assert_eq!(
+            state.validate_current_settings(),
+            Err("Invalid connection-specific DNS suffix".to_string())
+        );
+    }
+
+    #[test]
+    fn test_validate_current_settings_nrpt_rules() {
+        let mut state = AppState::new();
+        state.create_new_profile();
+        state.dns_mode = DnsMode::Manual;
+        let rule = |namespace: &str, name_servers: &str| NrptRule {
+            namespace: namespace.to_string(),
+            name_servers: name_servers.to_string(),
+        };
+
+        state.current_settings.nrpt = NrptSettings {
+            enabled: true,
+            rules: vec![rule("*.corp.example", "10.0.0.53, fd00::53")],
+        };
+        assert!(state.validate_current_settings().is_ok());
+
+        state.current_settings.nrpt.rules = vec![rule("*.", "10.0.0.53")];
+        assert_eq!(
+            state.validate_current_settings(),
+            Err("Invalid NRPT namespace: *.".to_string())
+        );
+
+        state.current_settings.nrpt.rules = vec![rule(".corp.example", " ")];
+        assert_eq!(
+            state.validate_current_settings(),
+            Err("NRPT rule for .corp.example needs a name server".to_string())
+        );
+
+        state.current_settings.nrpt.rules = vec![rule(".corp.example", "10.0.0.999")];
+        assert_eq!(
+            state.validate_current_settings(),
+            Err("Invalid NRPT name server address: 10.0.0.999".to_string())
+        );
+
+        state.current_settings.nrpt.enabled = false;
+        assert!(state.validate_current_settings().is_ok());
+    }
+
+    #[test]
+    fn test_validate_current_settings_warns_on_missing_family() {
+        let mut state = AppState::new();
+        let mut interface = test_interface("Ethernet", 1);
+        interface.has_ipv6 = false;
+        state.set_interfaces(vec![interface]);
+        state.create_new_profile();
+        state.dns_mode = DnsMode::Manual;
+        state.current_settings.ipv4 = create_valid_ipv4_settings();
+        assert_eq!(state.validate_current_settings(), Ok(None));
+
+        state.current_settings.ipv6 = DnsEntry {
+            enabled: true,
+            unchanged: false,
+            servers: vec![DnsServerEntry {
+                address: "2001:4860:4860::8888".to_string(),
+                ..Default::default()
+            }],
+        };
+        assert_eq!(
+            state.validate_current_settings(),
+            Ok(Some(
+                "IPv6 servers are enabled, but Ethernet (1) has no IPv6 connectivity, so they will not be used."
+                    .to_string()
+            ))
+        );
+
+        state.current_settings.ipv6.enabled = false;
+        assert_eq!(state.validate_current_settings(), Ok(None));
+    }
+
+    #[test]
+    fn test_dns_server_errors() {
+        let server = |address: &str, doh_mode: DohMode, doh_template: &str| DnsServerEntry {
+            address: address.to_string(),
+            doh_mode,
+            doh_template: doh_template.to_string(),
+            ..Default::default()
+        };
+
+        let valid = server("8.8.8.8", DohMode::Off, "");
+        assert_eq!(
+            dns_server_errors(&valid, AddressFamily::IPv4, true),
+            DnsServerErrors::default()
+        );
+
+        let empty = server("", DohMode::Off, "");
+        assert!(
+            dns_server_errors(&empty, AddressFamily::IPv4, true)
+                .address
+                .is_some()
+        );
+        assert_eq!(
+            dns_server_errors(&empty, AddressFamily::IPv4, false),
+            DnsServerErrors::default()
+        );
+
+        let wrong_family = server("8.8.8.8", DohMode::Off, "");
+        let errors = dns_server_errors(&wrong_family, AddressFamily::IPv6, true);
+        assert_eq!(errors.address.as_deref(), Some("Not a valid IPv6 address"));
+        assert_eq!(errors.doh_template, None);
+
+        let doh = server("", DohMode::On, "http://dns.google/dns-query");
+        let errors = dns_server_errors(&doh, AddressFamily::IPv4, false);
+        assert!(errors.address.is_some());
+        assert!(errors.doh_template.is_some());
+
+        let intranet = server("10.0.0.53", DohMode::On, "https://resolver/dns-query");
+        let errors = dns_server_errors(&intranet, AddressFamily::IPv4, false);
+        assert_eq!(errors.doh_template, None);
+        assert!(errors.doh_template_warning.is_some());
+
+        let doh = server("8.8.8.8", DohMode::On, "https://dns.google/dns-query");
+        assert_eq!(
+            dns_server_errors(&doh, AddressFamily::IPv4, false),
+            DnsServerErrors::default()
+        );
+
+        let loopback = server("127.0.0.1", DohMode::Off, "");
+        let errors = dns_server_errors(&loopback, AddressFamily::IPv4, true);
+        assert_eq!(errors.address, None);
+        assert!(errors.address_warning.unwrap().contains("Loopback"));
+
+        let host = server("dns.quad9.net", DohMode::Off, "");
+        let errors = dns_server_errors(&host, AddressFamily::IPv4, true);
+        assert!(errors.address.unwrap().contains("Resolve"));
+    }
+
+    #[test]
+    fn test_finish_temporary_apply() {
+        let mut state = AppState::new();
+        state.interfaces = vec![test_interface("Ethernet", 1)];
+        state.selected_interface_guid = Some(state.interfaces[0].interface_guid.clone());
+        let guid = state.interfaces[0].interface_guid.clone();
+        state.create_new_profile();
+        let profile_id = state.selected_profile_id.clone().unwrap();
+        let temporary = |previous_profile: Option<String>| TemporaryApply {
+            before: DnsSettings::new(),
+            previous_profile,
+            expires_at: 1_000,
+        };
+
+        assert!(state.selected_temporary_apply().is_none());
+        state
+            .config
+            .temporary_applies
+            .insert(guid.clone(), temporary(Some(profile_id.clone())));
+        state
+            .config
+            .interface_profiles
+            .insert(guid.clone(), Some("bypass".to_string()));
+        assert!(state.selected_temporary_apply().is_some());
+
+        state.finish_temporary_apply(&guid);
+        assert!(state.config.temporary_applies.is_empty());
+        assert_eq!(state.config.interface_profiles[&guid], Some(profile_id));
+
+        state
+            .config
+            .temporary_applies
+            .insert(guid.clone(), temporary(Some("deleted".to_string())));
+        state.finish_temporary_apply(&guid);
+        assert!(!state.config.interface_profiles.contains_key(&guid));
+    }
+
+    #[test]
+    fn test_local_resolver() {
+        let mut state = AppState::new();
+        state.create_new_profile();
+        state.dns_mode = DnsMode::Manual;
+        state.current_settings.ipv4 = create_valid_ipv4_settings();
+        state.current_settings.ipv4.servers[0].address = "127.0.0.1".to_string();
+        assert!(state.validate_current_settings().unwrap().is_some());
+        assert!(state.local_resolver_servers().is_empty());
+
+        state.current_settings.use_local_resolver();
+        assert_eq!(state.validate_current_settings(), Ok(None));
+        assert_eq!(state.local_resolver_servers(), vec!["127.0.0.1", "::1"]);
+
+        state.current_settings.local_resolver = Some(LocalResolver {
+            check_listening: false,
+        });
+        assert!(state.local_resolver_servers().is_empty());
+        state.current_settings.local_resolver = Some(LocalResolver::default());
+        state.dns_mode = DnsMode::Automatic;
+        assert!(state.local_resolver_servers().is_empty());
+    }
+
+    #[test]
+    fn test_use_resolved_host() {
+        let mut state = AppState::new();
+        state.create_new_profile();
+        state.current_settings.ipv4.enabled = true;
+        state.current_settings.ipv4.servers[0].address = "dns.quad9.net".to_string();
+        state.current_settings.ipv4.servers[0].doh_mode = DohMode::On;
+        let addresses = ["9.9.9.9", "149.112.112.112", "2620:fe::fe"]
+            .map(|address| address.parse().unwrap())
+            .to_vec();
+        state.resolved_host = Some(ResolvedHost {
+            family: AddressFamily::IPv4,
+            index: 0,
+            host: "dns.quad9.net".to_string(),
+            addresses,
+        });
+        assert!(state.resolved_host_for(AddressFamily::IPv4, 0).is_some());
+        assert!(state.resolved_host_for(AddressFamily::IPv4, 1).is_none());
+
+        assert!(state.use_resolved_host());
+        assert_eq!(state.resolved_host, None);
+        let ipv4 = &state.current_settings.ipv4;
+        assert_eq!(ipv4.servers.len(), 2);
+        assert_eq!(ipv4.servers[0].address, "9.9.9.9");
+        assert_eq!(ipv4.servers[1].address, "149.112.112.112");
+        assert_eq!(ipv4.servers[1].doh_mode, DohMode::On);
+        let ipv6 = &state.current_settings.ipv6;
+        assert!(ipv6.enabled);
+        assert_eq!(ipv6.servers[0].address, "2620:fe::fe");
+        assert_eq!(ipv6.servers[1].address, "");
+        assert!(!state.use_resolved_host());
+    }
+
+    #[test]
+    fn test_use_resolved_host_keeps_other_servers() {
+        let mut state = AppState::new();
+        state.create_new_profile();
+        state.current_settings.ipv4.servers[0].address = "1.1.1.1".to_string();
+        state.current_settings.ipv4.servers[1].address = "dns.google".to_string();
+        state.current_settings.ipv6.servers[0].address = "2606:4700:4700::1111".to_string();
+        state.resolved_host = Some(ResolvedHost {
+            family: AddressFamily::IPv4,
+            index: 1,
+            host: "dns.google".to_string(),
+            addresses: ["8.8.8.8", "8.8.4.4", "2001:4860:4860::8888"]
+                .map(|address| address.parse().unwrap())
+                .to_vec(),
+        });
+
+        // The host name was edited after it was resolved.
+        state.current_settings.ipv4.servers[1].address = "dns.google.com".to_string();
+        assert!(!state.use_resolved_host());
+        state.current_settings.ipv4.servers[1].address = "dns.google".to_string();
+        assert!(state.use_resolved_host());
+
+        let addresses = |entry: &DnsEntry| {
+            entry
+                .servers
+                .iter()
+                .map(|server| server.address.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            addresses(&state.current_settings.ipv4),
+            ["1.1.1.1", "8.8.8.8", "8.8.4.4"]
+        );
+        assert_eq!(
+            addresses(&state.current_settings.ipv6),
+            ["2606:4700:4700::1111", ""]
+        );
+    }
+
+    #[test]
+    fn test_validate_current_settings_duplicate_and_suspicious_addresses() {
+        let mut state = AppState::new();
+        state.create_new_profile();
+        state.dns_mode = DnsMode::Manual;
+        state.current_settings.ipv4 = create_valid_ipv4_settings();
+        state.current_settings.ipv4.servers[1].address = "8.8.8.8".to_string();
+        assert_eq!(
+            state.validate_current_settings(),
+            Err("IPv4 secondary DNS address 8.8.8.8 is already in the list".to_string())
+        );
+
+        state.current_settings.ipv6 = create_valid_ipv6_settings();
+        state.current_settings.ipv6.servers[0].address = "2001:4860:4860:0::8888".to_string();
+        state.current_settings.ipv6.servers[1].address = "2001:4860:4860::8888".to_string();
+        state.current_settings.ipv4.servers[1].address = "0.0.0.0".to_string();
+        assert!(
+            state
+                .validate_current_settings()
+                .unwrap_err()
+                .starts_with("IPv6 secondary")
+        );
+
+        state.current_settings.ipv6.enabled = false;
+        let warning = state.validate_current_settings().unwrap().unwrap();
+        assert!(warning.contains("0.0.0.0 is probably not meant"));
+    }
+
+    #[test]
+    fn test_app_state_read_only_reason() {
+        let mut state = AppState::new();
+        assert_eq!(state.read_only_reason(), None);
+
+        state.is_elevated = false;
+        assert!(state.read_only_reason().unwrap().contains("administrator"));
+    }
+
+    #[test]
+    fn test_app_state_policy_warnings() {
+        let mut state = AppState::new();
+        assert_eq!(state.policy_warning(), None);
+        assert_eq!(state.policy_override_warning(&DnsSettings::new()), None);
+
+        state.dns_policies = vec![DnsPolicy::DohProhibited, DnsPolicy::SuffixSearchList];
+        let warning = state.policy_warning().unwrap();
+        assert!(warning.contains("DNS over HTTPS"));
+        assert!(warning.contains("DNS Suffix Search List"));
+        assert_eq!(state.policy_override_warning(&DnsSettings::new()), None);
+
+        let mut settings = DnsSettings::new();
+        settings.suffixes.enabled = true;
+        let warning = state.policy_override_warning(&settings).unwrap();
+        assert!(warning.contains("DNS Suffix Search List"));
+        assert!(!warning.contains("DNS over HTTPS"));
+    }
+
+    #[test]
+    fn test_app_state_doh_unavailable_reason() {
+        let mut state = AppState::new();
+        assert_eq!(state.doh_unavailable_reason(), None);
+
+        state.backend = SystemBackend::Netsh;
+        assert!(
+            state
+                .doh_unavailable_reason()
+                .unwrap()
+                .contains("PowerShell")
+        );
+
+        state.capabilities = Capabilities { doh: false };
+        assert!(
+            state
+                .doh_unavailable_reason()
+                .unwrap()
+                .contains("Windows 11")
+        );
+    }
+
+    #[test]
+    fn test_app_state_relaunch_selection_round_trip() {
+        let mut state = AppState::new();
+        state.set_interfaces(vec![
+            test_interface("Ethernet", 1),
+            test_interface("Wi-Fi", 2),
+        ]);
+        state.select_interface("{GUID-2}");
+        let id = state.create_new_profile();
+        state.dns_mode = DnsMode::Manual;
+
+        let selection = state.relaunch_selection();
+        let mut restored = AppState::new();
+        restored.set_interfaces(state.interfaces.clone());
+        restored.config = state.config.clone();
+        restored.restore_selection(&selection);
+
+        assert_eq!(restored.selected_interface().unwrap().name, "Wi-Fi");
+        assert_eq!(restored.selected_profile_id, Some(id));
+        assert_eq!(restored.dns_mode, DnsMode::Manual);
+    }
+
+    #[test]
+    fn test_app_state_restore_selection_skips_missing() {
+        let mut state = AppState::new();
+        state.set_interfaces(vec![test_interface("Ethernet", 1)]);
+        state.restore_selection(&RelaunchSelection {
+            interface_guid: Some("{GUID-9}".to_string()),
+            profile_id: Some("missing".to_string()),
+            dns_mode: Some(DnsMode::Manual),
+        });
+
+        assert_eq!(state.selected_interface().unwrap().name, "Ethernet");
+        assert_eq!(state.selected_profile_id, None);
+        assert_eq!(state.dns_mode, DnsMode::Automatic);
     }
 
     #[test]
@@ -325,16 +2130,20 @@ mod tests {
     fn test_app_state_new() {
         let state = AppState::new();
         assert_eq!(state.interfaces.len(), 0);
-        assert_eq!(state.selected_interface_index, 0);
+        assert!(state.selected_interface_guid.is_none());
         assert_eq!(state.dns_mode, DnsMode::Automatic);
         assert!(state.selected_profile_id.is_none());
         assert!(!state.current_settings.ipv4.enabled);
         assert!(!state.current_settings.ipv6.enabled);
         assert_eq!(state.current_profile_name, "");
         assert_eq!(state.config.profiles.len(), 0);
-        assert!(state.message.is_none());
+        assert!(state.message().is_none());
+        assert!(state.message_log.is_empty());
         assert!(!state.is_loading);
         assert!(!state.show_delete_confirm);
+        assert!(!state.show_discard_confirm);
+        assert_eq!(state.backend, SystemBackend::Native);
+        assert!(state.preview_steps.is_none());
     }
 
     #[test]
@@ -346,80 +2155,571 @@ mod tests {
     #[test]
     fn test_app_state_selected_interface_when_in_range() {
         let mut state = AppState::new();
-        state.interfaces.push(create_test_interface("Ethernet", 1));
-        state.interfaces.push(create_test_interface("WiFi", 2));
-        state.selected_interface_index = 1;
+        state.interfaces.push(test_interface("Ethernet", 1));
+        state.interfaces.push(test_interface("WiFi", 2));
+        assert!(state.select_interface("{GUID-2}"));
 
         let selected = state.selected_interface();
         assert!(selected.is_some());
         assert_eq!(selected.unwrap().name, "WiFi");
     }
 
+    #[test]
+    fn test_app_state_select_interface_unknown_guid() {
+        let mut state = AppState::new();
+        state.set_interfaces(vec![test_interface("Ethernet", 1)]);
+
+        assert!(!state.select_interface("{GUID-9}"));
+        assert_eq!(state.selected_interface_guid.as_deref(), Some("{GUID-1}"));
+    }
+
+    #[test]
+    fn test_app_state_set_interfaces_keeps_selection_when_list_changes() {
+        let mut state = AppState::new();
+        state.set_interfaces(vec![
+            test_interface("Ethernet", 1),
+            test_interface("WiFi", 2),
+        ]);
+        state.select_interface("{GUID-2}");
+
+        let kept = state.set_interfaces(vec![test_interface("VPN", 3), test_interface("WiFi", 2)]);
+
+        assert!(kept);
+        assert_eq!(state.selected_interface().unwrap().name, "WiFi");
+    }
+
+    #[test]
+    fn test_app_state_set_interfaces_falls_back_when_selected_disappears() {
+        let mut state = AppState::new();
+        assert!(state.set_interfaces(vec![
+            test_interface("Ethernet", 1),
+            test_interface("WiFi", 2),
+        ]));
+        state.select_interface("{GUID-2}");
+
+        let kept = state.set_interfaces(vec![test_interface("Ethernet", 1)]);
+
+        assert!(!kept);
+        assert_eq!(state.selected_interface().unwrap().name, "Ethernet");
+
+        assert!(!state.set_interfaces(Vec::new()));
+        assert!(state.selected_interface_guid.is_none());
+    }
+
+    #[test]
+    fn test_app_state_set_interfaces_prefers_connected_adapter() {
+        let mut state = AppState::new();
+        let mut docked = test_interface("Ethernet", 1);
+        docked.is_disconnected = true;
+        state.set_interfaces(vec![docked, test_interface("WiFi", 2)]);
+        assert_eq!(state.selected_interface().unwrap().name, "WiFi");
+
+        // A disconnected adapter can still be picked and configured.
+        assert!(state.select_interface("{GUID-1}"));
+    }
+
+    #[test]
+    fn test_app_state_selection_generation() {
+        let mut state = AppState::new();
+        state.set_interfaces(vec![
+            test_interface("Ethernet", 1),
+            test_interface("WiFi", 2),
+        ]);
+        let generation = state.selection_generation();
+
+        state.select_interface("{GUID-1}");
+        state.set_interfaces(vec![
+            test_interface("Ethernet", 1),
+            test_interface("WiFi", 2),
+        ]);
+        assert!(state.is_current_selection(generation));
+
+        state.select_interface("{GUID-2}");
+        assert!(!state.is_current_selection(generation));
+
+        // Going back does not make a result for the first selection current.
+        state.select_interface("{GUID-1}");
+        assert!(!state.is_current_selection(generation));
+
+        let generation = state.selection_generation();
+        state.set_interfaces(vec![test_interface("WiFi", 2)]);
+        assert!(!state.is_current_selection(generation));
+    }
+
+    #[test]
+    fn test_app_state_active_vpn() {
+        let mut state = AppState::new();
+        let mut vpn = test_interface("WireGuard", 1);
+        vpn.is_virtual = true;
+        vpn.is_vpn = true;
+        state.set_interfaces(vec![test_interface("Ethernet", 2)]);
+        assert!(state.active_vpn().is_none());
+
+        state.set_interfaces(vec![vpn.clone(), test_interface("Ethernet", 2)]);
+        assert_eq!(state.active_vpn().unwrap().name, "WireGuard");
+
+        vpn.is_disconnected = true;
+        state.set_interfaces(vec![vpn, test_interface("Ethernet", 2)]);
+        assert!(state.active_vpn().is_none());
+
+        state.select_interface("{GUID-1}");
+        assert!(state.active_vpn().is_none());
+    }
+
+    #[test]
+    fn test_app_state_visible_interfaces() {
+        let mut state = AppState::new();
+        let mut hyper_v = test_interface("vEthernet (WSL)", 1);
+        hyper_v.is_virtual = true;
+        state.set_interfaces(vec![
+            hyper_v,
+            test_interface("Ethernet", 2),
+            test_interface("WiFi", 3),
+        ]);
+        assert_eq!(state.selected_interface().unwrap().name, "Ethernet");
+
+        let names = |state: &AppState| {
+            state
+                .visible_interfaces()
+                .iter()
+                .map(|i| i.name.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&state), vec!["Ethernet", "WiFi"]);
+
+        state.set_interface_hidden("{GUID-3}", true);
+        assert_eq!(names(&state), vec!["Ethernet"]);
+
+        state.select_interface("{GUID-1}");
+        assert_eq!(names(&state), vec!["vEthernet (WSL)", "Ethernet"]);
+
+        state.config.settings.show_all_interfaces = Some(true);
+        assert_eq!(names(&state), vec!["vEthernet (WSL)", "Ethernet", "WiFi"]);
+
+        state.set_interface_hidden("{GUID-3}", false);
+        assert!(state.config.hidden_interfaces.is_empty());
+    }
+
+    #[test]
+    fn test_app_state_set_interface_alias() {
+        let mut state = AppState::new();
+        state.set_interfaces(vec![test_interface("Ethernet 2", 2)]);
+        let interface = state.selected_interface().unwrap().clone();
+        assert_eq!(state.config.interface_name(&interface), "Ethernet 2 (2)");
+
+        state.set_interface_alias("{GUID-2}", "  Docked Ethernet ");
+        assert_eq!(state.config.interface_name(&interface), "Docked Ethernet");
+        assert_eq!(
+            state.control_status().interface.as_deref(),
+            Some("Docked Ethernet")
+        );
+
+        state.set_interface_alias("{GUID-2}", " ");
+        assert!(state.config.interface_aliases.is_empty());
+    }
+
+    #[test]
+    fn test_app_state_observe_current_dns() {
+        let mut state = AppState::new();
+        let dns_state = CurrentDnsState {
+            ipv4: vec!["10.0.0.1".to_string()],
+            ..Default::default()
+        };
+
+        assert!(state.observe_current_dns(dns_state.clone()));
+        assert_eq!(state.current_dns_state, dns_state);
+        assert!(!state.observe_current_dns(dns_state));
+    }
+
     #[test]
     fn test_app_state_set_message() {
         let mut state = AppState::new();
         state.set_message(Message::success("Test"));
-        assert!(state.message.is_some());
-        assert_eq!(state.message.as_ref().unwrap().text, "Test");
-        assert_eq!(state.message.as_ref().unwrap().level, MessageLevel::Success);
+        assert!(state.message().is_some());
+        assert_eq!(state.message().unwrap().text, "Test");
+        assert_eq!(state.message().unwrap().level, MessageLevel::Success);
     }
 
     #[test]
     fn test_app_state_clear_message() {
         let mut state = AppState::new();
-        state.set_message(Message::success("Test"));
-        state.clear_message();
-        assert!(state.message.is_none());
+        state.set_message(Message::success("Test"));
+        state.clear_message();
+        assert!(state.message().is_none());
+        assert_eq!(state.message_log.len(), 1);
+    }
+
+    #[test]
+    fn test_app_state_message_log() {
+        let mut state = AppState::new();
+        for i in 0..MESSAGE_LOG_LIMIT + 5 {
+            state.set_message(Message::warning(format!("Warning {}", i)));
+        }
+        state.set_message(Message::success("Done"));
+
+        assert_eq!(state.message_log.len(), MESSAGE_LOG_LIMIT);
+        assert_eq!(state.message_log.front().unwrap().message.text, "Warning 6");
+        assert_eq!(state.message().unwrap().text, "Done");
+
+        state.clear_message_log();
+        assert!(state.message_log.is_empty());
+        assert!(state.message().is_none());
+    }
+
+    #[test]
+    fn test_app_state_add_history_drops_rule_undo() {
+        let mut state = AppState::new();
+        let entry = |guid: &str| HistoryEntry::now(guid, "Wi-Fi", None, DnsSettings::new());
+        state.rule_undo = Some(entry("{WIFI}"));
+
+        state.add_history(entry("{ETHERNET}"));
+        assert!(state.rule_undo.is_some());
+        state.add_history(entry("{WIFI}"));
+        assert!(state.rule_undo.is_none());
+    }
+
+    #[test]
+    fn test_app_state_add_history() {
+        let mut state = AppState::new();
+        for i in 0..MAX_HISTORY_ENTRIES + 1 {
+            state.add_history(HistoryEntry::now(
+                "{GUID}",
+                &format!("Ethernet {}", i),
+                None,
+                DnsSettings::new(),
+            ));
+        }
+
+        assert_eq!(state.history.len(), MAX_HISTORY_ENTRIES);
+        assert_eq!(state.history.front().unwrap().interface_name, "Ethernet 1");
+    }
+
+    #[test]
+    fn test_app_state_set_loading_true() {
+        let mut state = AppState::new();
+        state.set_loading(true);
+        assert!(state.is_loading);
+    }
+
+    #[test]
+    fn test_app_state_set_loading_false() {
+        let mut state = AppState::new();
+        state.set_loading(true);
+        state.set_loading(false);
+        assert!(!state.is_loading);
+    }
+
+    #[test]
+    fn test_app_state_sorted_profiles() {
+        let mut state = AppState::new();
+        state
+            .config
+            .add_profile(DnsProfile::new("Zebra".to_string()));
+        state
+            .config
+            .add_profile(DnsProfile::new("Apple".to_string()));
+
+        let sorted = state.sorted_profiles();
+        assert_eq!(sorted.len(), 2);
+        assert_eq!(sorted[0].name, "Apple");
+        assert_eq!(sorted[1].name, "Zebra");
+    }
+
+    #[test]
+    fn test_app_state_select_profile_when_exists() {
+        let mut state = AppState::new();
+        let profile = DnsProfile::new("Test Profile".to_string());
+        let id = profile.id.clone();
+        state.config.add_profile(profile);
+
+        state.select_profile(&id);
+        assert_eq!(state.selected_profile_id, Some(id));
+        assert_eq!(state.current_profile_name, "Test Profile");
+    }
+
+    #[test]
+    fn test_app_state_select_profile_when_not_exists() {
+        let mut state = AppState::new();
+        state.select_profile("non-existent-id");
+        assert!(state.selected_profile_id.is_none());
+    }
+
+    #[test]
+    fn test_app_state_interface_profile_roundtrip() {
+        let mut state = AppState::new();
+        state.set_interfaces(vec![
+            test_interface("Ethernet", 1),
+            test_interface("WiFi", 2),
+        ]);
+        let work = state.create_new_profile();
+        let home = state.create_new_profile();
+
+        state.select_interface("{GUID-1}");
+        state.select_profile(&work);
+        state.dns_mode = DnsMode::Manual;
+        state.remember_interface_profile();
+
+        state.select_interface("{GUID-2}");
+        state.dns_mode = DnsMode::Automatic;
+        state.remember_interface_profile();
+
+        state.select_profile(&home);
+        state.select_interface("{GUID-1}");
+        state.restore_interface_profile();
+        assert_eq!(state.dns_mode, DnsMode::Manual);
+        assert_eq!(state.selected_profile_id.as_deref(), Some(work.as_str()));
+
+        state.select_interface("{GUID-2}");
+        state.restore_interface_profile();
+        assert_eq!(state.dns_mode, DnsMode::Automatic);
+        assert_eq!(state.selected_profile_id.as_deref(), Some(work.as_str()));
+    }
+
+    #[test]
+    fn test_app_state_is_dark_theme() {
+        let mut state = AppState::new();
+        assert!(state.is_dark_theme());
+
+        state.system_dark_mode = false;
+        assert!(!state.is_dark_theme());
+
+        state.config.settings.theme = Some(ThemePreference::Dark);
+        assert!(state.is_dark_theme());
+    }
+
+    #[test]
+    fn test_app_state_applied_profile() {
+        let mut state = AppState::new();
+        state.set_interfaces(vec![
+            test_interface("Ethernet", 1),
+            test_interface("WiFi", 2),
+        ]);
+        let id = state.create_new_profile();
+        assert!(state.applied_profile().is_none());
+
+        state.select_interface("{GUID-1}");
+        state.dns_mode = DnsMode::Manual;
+        state.remember_interface_profile();
+        assert_eq!(
+            state.applied_profile().map(|p| p.id.as_str()),
+            Some(id.as_str())
+        );
+
+        state.select_interface("{GUID-2}");
+        state.dns_mode = DnsMode::Automatic;
+        state.remember_interface_profile();
+        assert!(state.applied_profile().is_none());
+    }
+
+    fn add_matching_test_profile(state: &mut AppState, name: &str, ipv6: &str) -> String {
+        let mut profile = DnsProfile::new(name.to_string());
+        profile.settings.ipv4 = create_valid_ipv4_settings();
+        profile.settings.ipv6.enabled = true;
+        profile.settings.ipv6.servers[0] = DnsServerEntry {
+            address: ipv6.to_string(),
+            doh_mode: DohMode::On,
+            doh_template: "https://dns.google/dns-query".to_string(),
+            allow_fallback: true,
+            auto_upgrade: true,
+        };
+        let id = profile.id.clone();
+        state.config.add_profile(profile);
+        id
+    }
+
+    #[test]
+    fn test_app_state_dns_match() {
+        let mut state = AppState::new();
+        assert_eq!(state.dns_match(), DnsMatch::Automatic);
+
+        state.current_dns_state = CurrentDnsState {
+            ipv4: vec!["192.168.1.1".to_string()],
+            ipv4_origin: DnsOrigin::Dhcp,
+            ..Default::default()
+        };
+        assert_eq!(state.dns_match(), DnsMatch::Automatic);
+
+        let id = add_matching_test_profile(&mut state, "Google DoH", "2001:4860:4860::8888");
+        state.current_dns_state = CurrentDnsState {
+            ipv4: vec!["8.8.8.8".to_string()],
+            ipv6: vec!["2001:4860:4860:0:0:0:0:8888".to_string()],
+            doh_servers: vec!["2001:4860:4860::8888".to_string()],
+            ipv4_origin: DnsOrigin::Static,
+            ipv6_origin: DnsOrigin::Static,
+        };
+        assert!(matches!(state.dns_match(), DnsMatch::Profile(p) if p.id == id));
+
+        // DoH off on a server the profile encrypts is not the same setup.
+        state.current_dns_state.doh_servers.clear();
+        assert_eq!(state.dns_match(), DnsMatch::Custom);
+
+        state.current_dns_state.doh_servers = vec!["2001:4860:4860::8888".to_string()];
+        state.current_dns_state.ipv4 = vec!["8.8.4.4".to_string()];
+        assert_eq!(state.dns_match(), DnsMatch::Custom);
+    }
+
+    #[test]
+    fn test_app_state_applied_dns_overridden() {
+        let mut state = AppState::new();
+        state.set_interfaces(vec![test_interface("Ethernet", 1)]);
+        let id = add_matching_test_profile(&mut state, "Google DoH", "2001:4860:4860::8888");
+        let applied = CurrentDnsState {
+            ipv4: vec!["8.8.8.8".to_string()],
+            ipv6: vec!["2001:4860:4860::8888".to_string()],
+            doh_servers: vec!["2001:4860:4860::8888".to_string()],
+            ipv4_origin: DnsOrigin::Static,
+            ipv6_origin: DnsOrigin::Static,
+        };
+        let dhcp = CurrentDnsState {
+            ipv4: vec!["192.168.1.1".to_string()],
+            ipv4_origin: DnsOrigin::Dhcp,
+            ..Default::default()
+        };
+        state.current_dns_state = dhcp.clone();
+        assert!(!state.applied_dns_overridden());
+
+        state.select_profile(&id);
+        state.dns_mode = DnsMode::Manual;
+        state.remember_interface_profile();
+        state.current_dns_state = applied;
+        assert!(!state.applied_dns_overridden());
+        state.current_dns_state = dhcp.clone();
+        assert!(state.applied_dns_overridden());
+
+        state.dns_mode = DnsMode::Automatic;
+        state.remember_interface_profile();
+        assert!(!state.applied_dns_overridden());
+        state.current_dns_state.ipv4_origin = DnsOrigin::Static;
+        assert!(state.applied_dns_overridden());
     }
 
     #[test]
-    fn test_app_state_set_loading_true() {
+    fn test_app_state_dns_match_prefers_applied_profile() {
         let mut state = AppState::new();
-        state.set_loading(true);
-        assert!(state.is_loading);
+        state.set_interfaces(vec![test_interface("Ethernet", 1)]);
+        add_matching_test_profile(&mut state, "A", "2001:4860:4860::8888");
+        let id = add_matching_test_profile(&mut state, "B", "2001:4860:4860::8888");
+        state.current_dns_state = CurrentDnsState {
+            ipv4: vec!["8.8.8.8".to_string()],
+            ipv6: vec!["2001:4860:4860::8888".to_string()],
+            doh_servers: vec!["2001:4860:4860::8888".to_string()],
+            ipv4_origin: DnsOrigin::Static,
+            ipv6_origin: DnsOrigin::Static,
+        };
+        assert!(matches!(state.dns_match(), DnsMatch::Profile(p) if p.name == "A"));
+
+        state.select_profile(&id);
+        state.dns_mode = DnsMode::Manual;
+        state.remember_interface_profile();
+        assert!(matches!(state.dns_match(), DnsMatch::Profile(p) if p.id == id));
     }
 
     #[test]
-    fn test_app_state_set_loading_false() {
+    fn test_app_state_control_status() {
         let mut state = AppState::new();
-        state.set_loading(true);
-        state.set_loading(false);
-        assert!(!state.is_loading);
+        state.set_interfaces(vec![test_interface("Ethernet", 1)]);
+        let id = add_matching_test_profile(&mut state, "Google DoH", "2001:4860:4860::8888");
+        state.create_new_profile();
+
+        let status = state.control_status();
+        assert_eq!(status.interface_guid.as_deref(), Some("{GUID-1}"));
+        assert!(status.automatic);
+        assert_eq!(status.profile, None);
+        assert!(state.control_profiles().iter().all(|p| !p.active));
+
+        state.current_dns_state = CurrentDnsState {
+            ipv4: vec!["8.8.8.8".to_string()],
+            ipv6: vec!["2001:4860:4860::8888".to_string()],
+            doh_servers: vec!["2001:4860:4860::8888".to_string()],
+            ipv4_origin: DnsOrigin::Static,
+            ipv6_origin: DnsOrigin::Static,
+        };
+        let status = state.control_status();
+        assert!(!status.automatic);
+        assert_eq!(status.profile.as_deref(), Some("Google DoH"));
+        assert_eq!(status.ipv4, vec!["8.8.8.8".to_string()]);
+
+        let profiles = state.control_profiles();
+        assert_eq!(profiles.len(), 2);
+        let active: Vec<&str> = profiles
+            .iter()
+            .filter(|p| p.active)
+            .map(|p| p.id.as_str())
+            .collect();
+        assert_eq!(active, vec![id.as_str()]);
     }
 
     #[test]
-    fn test_app_state_sorted_profiles() {
+    fn test_app_state_restore_interface_profile_without_record() {
         let mut state = AppState::new();
+        state.set_interfaces(vec![test_interface("Ethernet", 1)]);
+        let id = state.create_new_profile();
+        state.dns_mode = DnsMode::Manual;
+
+        state.restore_interface_profile();
+        assert_eq!(state.dns_mode, DnsMode::Manual);
+        assert_eq!(state.selected_profile_id, Some(id));
+
         state
             .config
-            .add_profile(DnsProfile::new("Zebra".to_string()));
-        state
-            .config
-            .add_profile(DnsProfile::new("Apple".to_string()));
-
-        let sorted = state.sorted_profiles();
-        assert_eq!(sorted.len(), 2);
-        assert_eq!(sorted[0].name, "Apple");
-        assert_eq!(sorted[1].name, "Zebra");
+            .interface_profiles
+            .insert("{GUID-1}".to_string(), Some("deleted".to_string()));
+        state.restore_interface_profile();
+        assert_eq!(state.dns_mode, DnsMode::Manual);
+        assert_ne!(state.selected_profile_id.as_deref(), Some("deleted"));
     }
 
     #[test]
-    fn test_app_state_select_profile_when_exists() {
+    fn test_app_state_save_network_rules() {
         let mut state = AppState::new();
-        let profile = DnsProfile::new("Test Profile".to_string());
-        let id = profile.id.clone();
-        state.config.add_profile(profile);
+        assert!(state.save_network_rules().is_ok());
 
-        state.select_profile(&id);
-        assert_eq!(state.selected_profile_id, Some(id));
-        assert_eq!(state.current_profile_name, "Test Profile");
+        let id = state.create_new_profile();
+        let mut rules = NetworkRules {
+            enabled: true,
+            rules: vec![NetworkRule {
+                condition: NetworkCondition::Ssid(String::new()),
+                profile_id: Some(id),
+                ..NetworkRule::new()
+            }],
+        };
+        state.network_rules_draft = Some(rules.clone());
+        assert!(state.save_network_rules().is_err());
+        assert_eq!(state.network_rules(), &rules);
+        assert!(state.config.network_rules.is_empty());
+
+        rules.rules[0].condition = NetworkCondition::Ssid("HomeNet".to_string());
+        state.network_rules_draft = Some(rules.clone());
+        assert!(state.save_network_rules().is_ok());
+        assert!(state.network_rules_draft.is_none());
+        assert_eq!(state.config.network_rules, rules);
+        assert_eq!(state.network_rules(), &rules);
     }
 
     #[test]
-    fn test_app_state_select_profile_when_not_exists() {
+    fn test_app_state_save_schedule() {
         let mut state = AppState::new();
-        state.select_profile("non-existent-id");
-        assert!(state.selected_profile_id.is_none());
+        let id = state.create_new_profile();
+        let mut schedule = Schedule {
+            enabled: true,
+            entries: vec![ScheduleEntry {
+                profile_id: Some(id),
+                days: Vec::new(),
+                ..ScheduleEntry::new()
+            }],
+            default_profile_id: None,
+        };
+
+        state.schedule_draft = Some(schedule.clone());
+        assert!(state.save_schedule().is_err());
+        assert_eq!(state.schedule(), &schedule);
+        assert!(state.config.schedule.is_empty());
+
+        schedule.entries[0].days = vec![Weekday::Sat, Weekday::Sun];
+        state.schedule_draft = Some(schedule.clone());
+        assert!(state.save_schedule().is_ok());
+        assert!(state.schedule_draft.is_none());
+        assert_eq!(state.config.schedule, schedule);
     }
 
     #[test]
@@ -448,6 +2748,79 @@ mod tests {
         assert_eq!(state.selected_profile_id, Some(id));
     }
 
+    #[test]
+    fn test_app_state_profile_share_text_roundtrip() {
+        let mut state = AppState::new();
+        assert!(state.profile_share_text().is_err());
+
+        state.create_new_profile();
+        state.current_profile_name = " Work ".to_string();
+        state.current_settings.ipv4 = create_valid_ipv4_settings();
+        let text = state.profile_share_text().unwrap();
+        assert!(!text.contains('\n'));
+
+        let mut other = AppState::new();
+        other
+            .config
+            .add_profile(DnsProfile::new("work".to_string()));
+        other
+            .config
+            .add_profile(DnsProfile::new("Work (2)".to_string()));
+
+        assert_eq!(other.paste_profile(&text).unwrap(), "Work (3)");
+        assert_eq!(other.config.profiles.len(), 3);
+        assert_eq!(other.current_profile_name, "Work (3)");
+        assert_eq!(other.current_settings, state.current_settings);
+        assert_ne!(other.selected_profile_id, state.selected_profile_id);
+
+        // Pasting into the same state must not reuse the ID.
+        let id = state.selected_profile_id.clone();
+        state.paste_profile(&text).unwrap();
+        assert_ne!(state.selected_profile_id, id);
+        assert_eq!(state.config.profiles.len(), 2);
+    }
+
+    #[test]
+    fn test_app_state_paste_profile_drops_hooks() {
+        let mut state = AppState::new();
+        state.create_new_profile();
+        state.current_settings.ipv4 = create_valid_ipv4_settings();
+        state.current_settings.before_apply = Some("Start-Process calc".to_string());
+        state.current_settings.hooks_approval = Some("AAAA".to_string());
+        let text = state.profile_share_text().unwrap();
+        assert!(text.contains("Start-Process calc"));
+
+        let mut other = AppState::new();
+        other.paste_profile(&text).unwrap();
+        assert!(!other.current_settings.has_hooks());
+        assert_eq!(other.current_settings.hooks_approval, None);
+    }
+
+    #[test]
+    fn test_app_state_paste_profile_rejects_invalid_text() {
+        let mut state = AppState::new();
+        assert!(state.paste_profile("hello").is_err());
+
+        let mut profile = DnsProfile::new("  ".to_string());
+        let text = serde_json::to_string(&profile).unwrap();
+        assert_eq!(
+            state.paste_profile(&text).unwrap_err(),
+            "The pasted profile has no name"
+        );
+
+        profile.name = "Broken".to_string();
+        profile.settings.ipv4.enabled = true;
+        profile.settings.ipv4.servers[0].address = "999.1.1.1".to_string();
+        let text = serde_json::to_string(&profile).unwrap();
+        assert!(state.paste_profile(&text).is_err());
+
+        profile.settings.ipv4.enabled = false;
+        profile.color = Some("red".to_string());
+        let text = serde_json::to_string(&profile).unwrap();
+        assert!(state.paste_profile(&text).is_err());
+        assert!(state.config.profiles.is_empty());
+    }
+
     #[test]
     fn test_app_state_update_current_profile_when_selected() {
         let mut state = AppState::new();
@@ -457,12 +2830,284 @@ mod tests {
         state.select_profile(&id);
 
         state.current_profile_name = "Updated Name".to_string();
+        state.current_profile_tags = "home, ,Home, testing".to_string();
+        state.current_profile_color = Some("#c62828".to_string());
         state.current_settings.ipv4 = create_valid_ipv4_settings();
         state.update_current_profile();
 
         let updated = state.config.find_profile(&id).unwrap();
         assert_eq!(updated.name, "Updated Name");
+        assert_eq!(updated.tags, vec!["home", "testing"]);
+        assert_eq!(updated.color.as_deref(), Some("#c62828"));
         assert!(updated.settings.ipv4.enabled);
+
+        state.select_profile(&id);
+        assert_eq!(state.current_profile_tags, "home, testing");
+    }
+
+    #[test]
+    fn test_app_state_has_unsaved_changes() {
+        let mut state = AppState::new();
+        assert!(!state.has_unsaved_changes());
+
+        let mut profile = DnsProfile::new("Home".to_string());
+        profile.tags = vec!["home".to_string()];
+        let id = profile.id.clone();
+        state.config.add_profile(profile);
+        state.select_profile(&id);
+        assert!(!state.has_unsaved_changes());
+
+        state.current_profile_tags = "home, ".to_string();
+        assert!(!state.has_unsaved_changes());
+
+        state.current_settings.ipv4 = create_valid_ipv4_settings();
+        assert!(state.has_unsaved_changes());
+
+        state.discard_profile_changes();
+        assert!(!state.has_unsaved_changes());
+        assert!(!state.current_settings.ipv4.enabled);
+
+        state.current_profile_name = "Office".to_string();
+        assert!(state.has_unsaved_changes());
+        state.update_current_profile();
+        assert!(!state.has_unsaved_changes());
+    }
+
+    #[test]
+    fn test_app_state_filtered_profiles() {
+        let mut state = AppState::new();
+        let mut home = DnsProfile::new("Home".to_string());
+        home.tags = vec!["home".to_string()];
+        let mut work = DnsProfile::new("Work".to_string());
+        work.tags = vec!["work".to_string()];
+        let work_id = work.id.clone();
+        state.config.add_profile(home);
+        state.config.add_profile(work);
+        state.config.add_profile(DnsProfile::new("Lab".to_string()));
+
+        let names = |state: &AppState| {
+            state
+                .filtered_profiles()
+                .iter()
+                .map(|p| p.name.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&state), vec!["Home", "Lab", "Work"]);
+
+        state.profile_tag_filter = Some("HOME".to_string());
+        assert_eq!(names(&state), vec!["Home"]);
+
+        // The selected profile stays listed.
+        state.select_profile(&work_id);
+        assert_eq!(names(&state), vec!["Home", "Work"]);
+
+        state.profile_tag_filter = Some("gone".to_string());
+        assert_eq!(names(&state), vec!["Home", "Lab", "Work"]);
+
+        state.profile_tag_filter = None;
+        state.selected_profile_id = None;
+        state.profile_search = " LA ".to_string();
+        assert_eq!(names(&state), vec!["Lab"]);
+
+        state.profile_search = "hom".to_string();
+        assert_eq!(names(&state), vec!["Home"]);
+
+        state.profile_search = "nothing".to_string();
+        assert!(names(&state).is_empty());
+    }
+
+    #[test]
+    fn test_app_state_fill_from_provider() {
+        let mut state = AppState::new();
+        let id = state.create_new_profile();
+
+        assert!(state.fill_from_provider("cloudflare", None));
+        assert_eq!(state.current_settings.ipv4.servers[0].address, "1.1.1.1");
+        assert_eq!(
+            state.selected_provider,
+            Some(("cloudflare".to_string(), "standard".to_string()))
+        );
+
+        assert!(state.fill_from_provider("cloudflare", Some("family")));
+        assert_eq!(state.current_settings.ipv4.servers[0].address, "1.1.1.3");
+        assert_eq!(state.current_settings.ipv4.servers[0].doh_mode, DohMode::On);
+
+        assert!(!state.fill_from_provider("cloudflare", Some("unknown")));
+        assert!(!state.fill_from_provider("unknown", None));
+        assert_eq!(state.current_settings.ipv4.servers[0].address, "1.1.1.3");
+
+        state.select_profile(&id);
+        assert_eq!(state.selected_provider, None);
+    }
+
+    #[test]
+    fn test_app_state_custom_providers() {
+        let mut state = AppState::new();
+        state.provider_draft = Some(ProviderDraft {
+            name: "Pi-hole".to_string(),
+            ipv4: "192.168.1.53".to_string(),
+            ..Default::default()
+        });
+
+        assert_eq!(state.save_provider_draft().unwrap(), "Pi-hole");
+        assert!(state.provider_draft.is_none());
+        let id = state.config.custom_providers[0].id.clone();
+        let catalog = state.provider_catalog();
+        assert_eq!(catalog.last().unwrap().id, id);
+        assert_eq!(catalog.len(), providers().len() + 1);
+
+        assert!(state.fill_from_provider(&id, None));
+        let server = &state.current_settings.ipv4.servers[0];
+        assert_eq!(server.address, "192.168.1.53");
+        assert_eq!(server.doh_mode, DohMode::Off);
+
+        let mut draft = ProviderDraft::from_provider(&state.config.custom_providers[0]);
+        draft.ipv4 = "192.168.1.54".to_string();
+        state.provider_draft = Some(draft);
+        state.save_provider_draft().unwrap();
+        assert_eq!(state.config.custom_providers.len(), 1);
+        assert_eq!(
+            state.config.custom_providers[0].variants[0].ipv4,
+            vec!["192.168.1.54"]
+        );
+
+        assert!(state.delete_custom_provider(&id));
+        assert!(state.config.custom_providers.is_empty());
+        assert_eq!(state.selected_provider, None);
+        assert!(!state.delete_custom_provider(&id));
+    }
+
+    #[test]
+    fn test_app_state_save_provider_draft_rejects_duplicate_name() {
+        let mut state = AppState::new();
+        state.provider_draft = Some(ProviderDraft {
+            name: "cloudflare".to_string(),
+            ipv4: "192.168.1.53".to_string(),
+            ..Default::default()
+        });
+
+        assert!(state.save_provider_draft().is_err());
+        assert!(state.provider_draft.is_some());
+        assert!(state.config.custom_providers.is_empty());
+    }
+
+    #[test]
+    fn test_app_state_import_doh_providers() {
+        let mut state = AppState::new();
+        let servers = vec![
+            DnsServerEntry {
+                address: "8.8.8.8".to_string(),
+                doh_mode: DohMode::On,
+                doh_template: "https://dns.google/dns-query".to_string(),
+                ..Default::default()
+            },
+            DnsServerEntry {
+                address: "45.90.28.0".to_string(),
+                doh_mode: DohMode::On,
+                doh_template: "https://dns.nextdns.io/abc123".to_string(),
+                ..Default::default()
+            },
+        ];
+
+        assert_eq!(state.import_doh_providers(&servers), vec!["dns.nextdns.io"]);
+        assert_eq!(state.config.custom_providers.len(), 1);
+        assert!(state.import_doh_providers(&servers).is_empty());
+        assert_eq!(state.config.custom_providers.len(), 1);
+    }
+
+    #[test]
+    fn test_app_state_fill_from_provider_without_doh() {
+        let mut state = AppState::new();
+        state.capabilities.doh = false;
+
+        assert!(state.fill_from_provider("quad9", Some("ecs")));
+        let server = &state.current_settings.ipv4.servers[0];
+        assert_eq!(server.address, "9.9.9.11");
+        assert_eq!(server.doh_mode, DohMode::Off);
+    }
+
+    #[test]
+    fn test_app_state_set_current_settings_fills_known_templates() {
+        let mut state = AppState::new();
+        state.set_known_doh_templates(vec![
+            DnsServerEntry {
+                address: "8.8.8.8".to_string(),
+                doh_template: "https://dns.google/dns-query".to_string(),
+                ..Default::default()
+            },
+            DnsServerEntry {
+                address: "2001:4860:4860::8888".to_string(),
+                doh_template: "https://dns.google/dns-query".to_string(),
+                ..Default::default()
+            },
+        ]);
+        let typed = |ipv4: &str, ipv6: &str| {
+            let mut settings = DnsSettings::default();
+            settings.ipv4.servers = vec![DnsServerEntry {
+                address: ipv4.to_string(),
+                ..Default::default()
+            }];
+            settings.ipv6.servers = vec![DnsServerEntry {
+                address: ipv6.to_string(),
+                ..Default::default()
+            }];
+            settings
+        };
+
+        state.set_current_settings(typed("8.8.8.", "2001:4860:4860:0::8888"));
+        assert_eq!(
+            state.current_settings.ipv4.servers[0].doh_mode,
+            DohMode::Off
+        );
+        let server = &state.current_settings.ipv6.servers[0];
+        assert_eq!(server.doh_mode, DohMode::On);
+        assert_eq!(server.doh_template, "https://dns.google/dns-query");
+
+        state.set_current_settings(typed("8.8.8.8", "2001:4860:4860:0::8888"));
+        assert_eq!(state.current_settings.ipv4.servers[0].doh_mode, DohMode::On);
+
+        // Turning DoH off again for the same address sticks.
+        let mut settings = state.current_settings.clone();
+        settings.ipv4.servers[0].doh_mode = DohMode::Off;
+        settings.ipv4.servers[0].doh_template.clear();
+        state.set_current_settings(settings);
+        assert_eq!(
+            state.current_settings.ipv4.servers[0].doh_mode,
+            DohMode::Off
+        );
+
+        state.capabilities = Capabilities { doh: false };
+        state.set_current_settings(typed("1.1.1.1", ""));
+        state.set_current_settings(typed("8.8.8.8", ""));
+        assert_eq!(
+            state.current_settings.ipv4.servers[0].doh_mode,
+            DohMode::Off
+        );
+    }
+
+    #[test]
+    fn test_app_state_set_doh_template() {
+        let mut state = AppState::new();
+        state.current_settings.ipv6 = DnsEntry {
+            enabled: true,
+            unchanged: false,
+            servers: vec![DnsServerEntry {
+                address: "2001:db8::1".to_string(),
+                ..Default::default()
+            }],
+        };
+
+        state.set_doh_template(
+            AddressFamily::IPv6,
+            0,
+            "https://dns.example.net/dns-query".to_string(),
+        );
+        state.set_doh_template(AddressFamily::IPv6, 5, "ignored".to_string());
+
+        let server = &state.current_settings.ipv6.servers[0];
+        assert_eq!(server.doh_mode, DohMode::On);
+        assert_eq!(server.doh_template, "https://dns.example.net/dns-query");
+        assert_eq!(state.current_settings.ipv6.servers.len(), 1);
     }
 
     #[test]
@@ -520,6 +3165,36 @@ mod tests {
         assert_eq!(state.config.profiles.len(), initial_count);
     }
 
+    #[test]
+    fn test_app_state_restore_config() {
+        let mut state = AppState::new();
+        let mut kept = DnsProfile::new("Kept".to_string());
+        kept.settings.ipv4.servers[0].address = "9.9.9.9".to_string();
+        let kept_id = kept.id.clone();
+        let mut backup = AppConfig::new();
+        backup.add_profile(kept.clone());
+
+        state.config.add_profile(kept);
+        state.select_profile(&kept_id);
+        state.current_settings.ipv4.servers[0].address = "1.1.1.1".to_string();
+        state.schedule_draft = Some(Schedule::default());
+        state.restore_config(backup.clone());
+        assert_eq!(state.selected_profile_id.as_deref(), Some(kept_id.as_str()));
+        assert_eq!(state.current_settings.ipv4.servers[0].address, "9.9.9.9");
+        assert!(state.schedule_draft.is_none());
+
+        let other = state.create_new_profile();
+        state.restore_config(backup);
+        assert_ne!(state.selected_profile_id.as_deref(), Some(other.as_str()));
+        assert_eq!(state.selected_profile_id.as_deref(), Some(kept_id.as_str()));
+
+        state.dns_mode = DnsMode::Manual;
+        state.restore_config(AppConfig::new());
+        assert!(state.selected_profile_id.is_none());
+        assert_eq!(state.current_profile_name, "");
+        assert_eq!(state.dns_mode, DnsMode::Automatic);
+    }
+
     #[test]
     fn test_app_state_is_profile_name_duplicate_when_duplicate() {
         let mut state = AppState::new();
@@ -567,6 +3242,117 @@ mod tests {
         assert!(state.validate_current_settings().is_ok());
     }
 
+    #[test]
+    fn test_app_state_preview_apply_automatic() {
+        let mut state = AppState::new();
+        state.set_interfaces(vec![test_interface("Ethernet", 1)]);
+
+        let steps = state.preview_apply().unwrap();
+        assert_eq!(steps.len(), 3);
+        assert!(steps[0].command.contains("NameServer = \"\""));
+        assert_eq!(steps[1].title, "Turn off DoH on the interface");
+        assert_eq!(steps[2].title, "Clear DNS cache");
+    }
+
+    #[test]
+    fn test_app_state_dnssec_server() {
+        let mut state = AppState::new();
+        state.current_dns_state.ipv6 = vec!["fe80::1".to_string()];
+        assert_eq!(state.dnssec_server().as_deref(), Some("fe80::1"));
+
+        state.dns_mode = DnsMode::Manual;
+        assert_eq!(state.dnssec_server(), None);
+
+        state.current_settings.ipv6 = DnsEntry {
+            enabled: true,
+            unchanged: false,
+            servers: vec![DnsServerEntry {
+                address: "2606:4700:4700::1111".to_string(),
+                ..Default::default()
+            }],
+        };
+        assert_eq!(
+            state.dnssec_server().as_deref(),
+            Some("2606:4700:4700::1111")
+        );
+
+        state.current_settings.ipv4 = DnsEntry {
+            enabled: true,
+            unchanged: false,
+            servers: vec![DnsServerEntry {
+                address: " 9.9.9.9 ".to_string(),
+                ..Default::default()
+            }],
+        };
+        assert_eq!(state.dnssec_server().as_deref(), Some("9.9.9.9"));
+    }
+
+    #[test]
+    fn test_app_state_lookup_server() {
+        let mut state = AppState::new();
+        assert_eq!(state.lookup_server(), None);
+
+        state.current_dns_state.ipv6 = vec!["fe80::1".to_string()];
+        assert_eq!(state.lookup_server().as_deref(), Some("fe80::1"));
+
+        state.lookup_query.server = " 1.1.1.1 ".to_string();
+        assert_eq!(state.lookup_server().as_deref(), Some("1.1.1.1"));
+    }
+
+    #[test]
+    fn test_app_state_original_dns_follows_selected_interface() {
+        let mut state = AppState::new();
+        state.set_interfaces(vec![
+            test_interface("Ethernet", 1),
+            test_interface("Wi-Fi", 2),
+        ]);
+        state
+            .config
+            .original_dns
+            .insert("{GUID-2}".to_string(), DnsSettings::new());
+
+        assert!(state.original_dns().is_none());
+        state.select_interface("{GUID-2}");
+        assert_eq!(state.original_dns(), Some(&DnsSettings::new()));
+    }
+
+    #[test]
+    fn test_app_state_preview_apply_without_cache_flush() {
+        let mut state = AppState::new();
+        state.set_interfaces(vec![test_interface("Ethernet", 1)]);
+        state.config.settings.flush_cache_after_apply = Some(false);
+
+        let steps = state.preview_apply().unwrap();
+        assert_eq!(steps.len(), 2);
+        assert!(steps.iter().all(|step| step.title != "Clear DNS cache"));
+    }
+
+    #[test]
+    fn test_app_state_preview_apply_manual() {
+        let mut state = AppState::new();
+        state.set_interfaces(vec![test_interface("Ethernet", 1)]);
+        state.create_new_profile();
+        state.dns_mode = DnsMode::Manual;
+        state.current_settings.ipv4 = create_valid_ipv4_settings();
+
+        let steps = state.preview_apply().unwrap();
+        assert!(steps[0].command.contains("IPv4 NameServer = \"8.8.8.8\""));
+    }
+
+    #[test]
+    fn test_app_state_preview_apply_no_interface() {
+        let state = AppState::new();
+        assert_eq!(state.preview_apply().unwrap_err(), "No interface selected");
+    }
+
+    #[test]
+    fn test_app_state_preview_apply_invalid_settings() {
+        let mut state = AppState::new();
+        state.set_interfaces(vec![test_interface("Ethernet", 1)]);
+        state.dns_mode = DnsMode::Manual;
+        assert_eq!(state.preview_apply().unwrap_err(), "No profile selected");
+    }
+
     #[test]
     fn test_app_state_validate_current_settings_no_profile_selected() {
         let mut state = AppState::new();
@@ -636,7 +3422,7 @@ mod tests {
         state.select_profile(&id);
         state.dns_mode = DnsMode::Manual;
         state.current_settings.ipv4.enabled = true;
-        state.current_settings.ipv4.primary.address = "invalid".to_string();
+        state.current_settings.ipv4.servers[0].address = "invalid".to_string();
 
         let result = state.validate_current_settings();
         assert!(result.is_err());
@@ -652,13 +3438,49 @@ mod tests {
         state.select_profile(&id);
         state.dns_mode = DnsMode::Manual;
         state.current_settings.ipv4 = create_valid_ipv4_settings();
-        state.current_settings.ipv4.secondary.address = "invalid".to_string();
+        state.current_settings.ipv4.servers[1].address = "invalid".to_string();
 
         let result = state.validate_current_settings();
         assert!(result.is_err());
         assert_eq!(result.unwrap_err(), "Invalid IPv4 secondary DNS address");
     }
 
+    #[test]
+    fn test_app_state_validate_current_settings_ipv4_invalid_additional_server() {
+        let mut state = AppState::new();
+        let profile = DnsProfile::new("Test".to_string());
+        let id = profile.id.clone();
+        state.config.add_profile(profile);
+        state.select_profile(&id);
+        state.dns_mode = DnsMode::Manual;
+        state.current_settings.ipv4 = create_valid_ipv4_settings();
+        state.current_settings.ipv4.servers.push(DnsServerEntry {
+            address: "invalid".to_string(),
+            ..Default::default()
+        });
+
+        let result = state.validate_current_settings();
+        assert_eq!(result.unwrap_err(), "Invalid IPv4 server 3 DNS address");
+    }
+
+    #[test]
+    fn test_app_state_validate_current_settings_ipv4_no_servers() {
+        let mut state = AppState::new();
+        let profile = DnsProfile::new("Test".to_string());
+        let id = profile.id.clone();
+        state.config.add_profile(profile);
+        state.select_profile(&id);
+        state.dns_mode = DnsMode::Manual;
+        state.current_settings.ipv4 = create_valid_ipv4_settings();
+        state.current_settings.ipv4.servers.clear();
+
+        let result = state.validate_current_settings();
+        assert_eq!(
+            result.unwrap_err(),
+            "IPv4 primary DNS is required when enabled"
+        );
+    }
+
     #[test]
     fn test_app_state_validate_current_settings_ipv4_doh_on_empty_template() {
         let mut state = AppState::new();
@@ -668,7 +3490,7 @@ mod tests {
         state.select_profile(&id);
         state.dns_mode = DnsMode::Manual;
         state.current_settings.ipv4 = create_valid_ipv4_settings();
-        state.current_settings.ipv4.primary.doh_mode = DohMode::On;
+        state.current_settings.ipv4.servers[0].doh_mode = DohMode::On;
 
         let result = state.validate_current_settings();
         assert!(result.is_err());
@@ -687,8 +3509,8 @@ mod tests {
         state.select_profile(&id);
         state.dns_mode = DnsMode::Manual;
         state.current_settings.ipv4 = create_valid_ipv4_settings();
-        state.current_settings.ipv4.primary.doh_mode = DohMode::On;
-        state.current_settings.ipv4.primary.doh_template = "invalid".to_string();
+        state.current_settings.ipv4.servers[0].doh_mode = DohMode::On;
+        state.current_settings.ipv4.servers[0].doh_template = "invalid".to_string();
 
         let result = state.validate_current_settings();
         assert!(result.is_err());
@@ -704,8 +3526,8 @@ mod tests {
         state.select_profile(&id);
         state.dns_mode = DnsMode::Manual;
         state.current_settings.ipv4 = create_valid_ipv4_settings();
-        state.current_settings.ipv4.secondary.address = "8.8.4.4".to_string();
-        state.current_settings.ipv4.secondary.doh_mode = DohMode::On;
+        state.current_settings.ipv4.servers[1].address = "8.8.4.4".to_string();
+        state.current_settings.ipv4.servers[1].doh_mode = DohMode::On;
 
         let result = state.validate_current_settings();
         assert!(result.is_err());
@@ -724,9 +3546,9 @@ mod tests {
         state.select_profile(&id);
         state.dns_mode = DnsMode::Manual;
         state.current_settings.ipv4 = create_valid_ipv4_settings();
-        state.current_settings.ipv4.secondary.address = "8.8.4.4".to_string();
-        state.current_settings.ipv4.secondary.doh_mode = DohMode::On;
-        state.current_settings.ipv4.secondary.doh_template = "invalid".to_string();
+        state.current_settings.ipv4.servers[1].address = "8.8.4.4".to_string();
+        state.current_settings.ipv4.servers[1].doh_mode = DohMode::On;
+        state.current_settings.ipv4.servers[1].doh_template = "invalid".to_string();
 
         let result = state.validate_current_settings();
         assert!(result.is_err());
@@ -763,7 +3585,7 @@ mod tests {
         state.select_profile(&id);
         state.dns_mode = DnsMode::Manual;
         state.current_settings.ipv6.enabled = true;
-        state.current_settings.ipv6.primary.address = "invalid".to_string();
+        state.current_settings.ipv6.servers[0].address = "invalid".to_string();
 
         let result = state.validate_current_settings();
         assert!(result.is_err());
@@ -779,7 +3601,7 @@ mod tests {
         state.select_profile(&id);
         state.dns_mode = DnsMode::Manual;
         state.current_settings.ipv6 = create_valid_ipv6_settings();
-        state.current_settings.ipv6.secondary.address = "invalid".to_string();
+        state.current_settings.ipv6.servers[1].address = "invalid".to_string();
 
         let result = state.validate_current_settings();
         assert!(result.is_err());
@@ -795,7 +3617,7 @@ mod tests {
         state.select_profile(&id);
         state.dns_mode = DnsMode::Manual;
         state.current_settings.ipv6 = create_valid_ipv6_settings();
-        state.current_settings.ipv6.primary.doh_mode = DohMode::On;
+        state.current_settings.ipv6.servers[0].doh_mode = DohMode::On;
 
         let result = state.validate_current_settings();
         assert!(result.is_err());
@@ -814,8 +3636,8 @@ mod tests {
         state.select_profile(&id);
         state.dns_mode = DnsMode::Manual;
         state.current_settings.ipv6 = create_valid_ipv6_settings();
-        state.current_settings.ipv6.primary.doh_mode = DohMode::On;
-        state.current_settings.ipv6.primary.doh_template = "invalid".to_string();
+        state.current_settings.ipv6.servers[0].doh_mode = DohMode::On;
+        state.current_settings.ipv6.servers[0].doh_template = "invalid".to_string();
 
         let result = state.validate_current_settings();
         assert!(result.is_err());
@@ -831,8 +3653,8 @@ mod tests {
         state.select_profile(&id);
         state.dns_mode = DnsMode::Manual;
         state.current_settings.ipv6 = create_valid_ipv6_settings();
-        state.current_settings.ipv6.secondary.address = "2001:4860:4860::8844".to_string();
-        state.current_settings.ipv6.secondary.doh_mode = DohMode::On;
+        state.current_settings.ipv6.servers[1].address = "2001:4860:4860::8844".to_string();
+        state.current_settings.ipv6.servers[1].doh_mode = DohMode::On;
 
         let result = state.validate_current_settings();
         assert!(result.is_err());
@@ -851,9 +3673,9 @@ mod tests {
         state.select_profile(&id);
         state.dns_mode = DnsMode::Manual;
         state.current_settings.ipv6 = create_valid_ipv6_settings();
-        state.current_settings.ipv6.secondary.address = "2001:4860:4860::8844".to_string();
-        state.current_settings.ipv6.secondary.doh_mode = DohMode::On;
-        state.current_settings.ipv6.secondary.doh_template = "invalid".to_string();
+        state.current_settings.ipv6.servers[1].address = "2001:4860:4860::8844".to_string();
+        state.current_settings.ipv6.servers[1].doh_mode = DohMode::On;
+        state.current_settings.ipv6.servers[1].doh_template = "invalid".to_string();
 
         let result = state.validate_current_settings();
         assert!(result.is_err());
@@ -885,8 +3707,8 @@ mod tests {
 
         assert_eq!(default_state.interfaces.len(), new_state.interfaces.len());
         assert_eq!(
-            default_state.selected_interface_index,
-            new_state.selected_interface_index
+            default_state.selected_interface_guid,
+            new_state.selected_interface_guid
         );
         assert_eq!(default_state.dns_mode, new_state.dns_mode);
         assert_eq!(
@@ -901,7 +3723,10 @@ mod tests {
             default_state.config.profiles.len(),
             new_state.config.profiles.len()
         );
-        assert_eq!(default_state.message.is_none(), new_state.message.is_none());
+        assert_eq!(
+            default_state.message().is_none(),
+            new_state.message().is_none()
+        );
         assert_eq!(default_state.is_loading, new_state.is_loading);
         assert_eq!(
             default_state.show_delete_confirm,
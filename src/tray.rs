@@ -0,0 +1,137 @@
+use crate::dns::{TimeOfDay, local_time, unix_now};
+use crate::i18n::{Language, t};
+use crate::state::AppState;
+use dioxus::desktop::trayicon::menu::{CheckMenuItem, Menu, MenuId, MenuItem, PredefinedMenuItem};
+use dioxus::desktop::trayicon::{DioxusTrayIcon, DioxusTrayMenu};
+
+const AUTOMATIC_ITEM_ID: &str = "automatic";
+const PROFILE_ITEM_PREFIX: &str = "profile:";
+const RESET_ALL_ITEM_ID: &str = "reset-all";
+const UNDO_RULE_ITEM_ID: &str = "undo-rule";
+
+/// What the tray menu shows: the selected interface, every profile and the
+/// one last applied to that interface.
+#[derive(Clone, PartialEq, Default)]
+pub struct TrayMenuState {
+    /// Name of the interface the profiles apply to.
+    pub interface: Option<String>,
+    /// Profile IDs and names, sorted by name.
+    pub profiles: Vec<(String, String)>,
+    /// `Some(None)` when the interface is set to Automatic, `None` when
+    /// nothing has been applied to it yet.
+    pub active: Option<Option<String>>,
+    /// When the previous settings of the interface are put back, if they
+    /// were changed temporarily.
+    pub reverts_at: Option<TimeOfDay>,
+    /// Name of the interface a network rule last switched, while that
+    /// change can be undone.
+    pub undo_rule: Option<String>,
+    /// Rebuilds the menu in the new language when it changes.
+    pub language: Language,
+}
+
+impl TrayMenuState {
+    pub fn from_state(state: &AppState) -> Self {
+        let profiles = state
+            .sorted_profiles()
+            .into_iter()
+            .map(|p| (p.id.clone(), p.name.clone()))
+            .collect();
+        let selected = state.selected_interface();
+        let active = selected
+            .and_then(|i| state.config.interface_profiles.get(&i.interface_guid))
+            .cloned();
+        let reverts_at = state.selected_temporary_apply().map(|temporary| {
+            let minutes = temporary.remaining(unix_now()).as_secs().div_ceil(60);
+            local_time().1.plus_minutes(minutes)
+        });
+        let undo_rule = state
+            .rule_undo
+            .as_ref()
+            .map(|entry| entry.interface_name.clone());
+        Self {
+            interface: selected.map(|i| state.config.interface_name(i)),
+            profiles,
+            active,
+            reverts_at,
+            undo_rule,
+            language: state.config.settings.language(),
+        }
+    }
+
+    pub fn build_menu(&self) -> DioxusTrayMenu {
+        let menu = Menu::new();
+        if let Some(interface) = &self.interface {
+            let header = MenuItem::new(interface.replace('&', "&&"), false, None);
+            let _ = menu.append(&header);
+            if let Some(time) = self.reverts_at {
+                let reverts =
+                    MenuItem::new(t!("tray-reverts-at", time = time.to_string()), false, None);
+                let _ = menu.append(&reverts);
+            }
+            let _ = menu.append(&PredefinedMenuItem::separator());
+        }
+        let automatic = CheckMenuItem::with_id(
+            AUTOMATIC_ITEM_ID,
+            t!("automatic"),
+            true,
+            self.active == Some(None),
+            None,
+        );
+        let _ = menu.append(&automatic);
+
+        if !self.profiles.is_empty() {
+            let _ = menu.append(&PredefinedMenuItem::separator());
+        }
+        for (id, name) in &self.profiles {
+            let checked = self.active == Some(Some(id.clone()));
+            // A single & marks a mnemonic in Windows menus.
+            let item = CheckMenuItem::with_id(
+                format!("{}{}", PROFILE_ITEM_PREFIX, id),
+                name.replace('&', "&&"),
+                true,
+                checked,
+                None,
+            );
+            let _ = menu.append(&item);
+        }
+
+        let _ = menu.append(&PredefinedMenuItem::separator());
+        if let Some(interface) = &self.undo_rule {
+            let label = t!("tray-undo-rule", interface = interface).replace('&', "&&");
+            let undo = MenuItem::with_id(UNDO_RULE_ITEM_ID, label, true, None);
+            let _ = menu.append(&undo);
+        }
+        let reset_all = MenuItem::with_id(RESET_ALL_ITEM_ID, t!("reset-all"), true, None);
+        let _ = menu.append(&reset_all);
+        menu
+    }
+}
+
+/// Profile to apply for a clicked menu item, `Some(None)` meaning
+/// Automatic.
+pub fn menu_target(id: &MenuId) -> Option<Option<String>> {
+    let id: &str = id.as_ref();
+    if id == AUTOMATIC_ITEM_ID {
+        return Some(None);
+    }
+    id.strip_prefix(PROFILE_ITEM_PREFIX)
+        .map(|profile_id| Some(profile_id.to_string()))
+}
+
+/// Whether the clicked menu item resets every adapter to Automatic.
+pub fn is_reset_all_item(id: &MenuId) -> bool {
+    id.as_ref() == RESET_ALL_ITEM_ID
+}
+
+/// Whether the clicked menu item undoes the change a network rule made.
+pub fn is_undo_rule_item(id: &MenuId) -> bool {
+    id.as_ref() == UNDO_RULE_ITEM_ID
+}
+
+pub fn load_tray_icon() -> Option<DioxusTrayIcon> {
+    let icon_bytes = include_bytes!("../icons/icon.png");
+    let image = image::load_from_memory(icon_bytes).ok()?.into_rgba8();
+    let (width, height) = image.dimensions();
+    DioxusTrayIcon::from_rgba(image.into_raw(), width, height).ok()
+}